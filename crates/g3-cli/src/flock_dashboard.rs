@@ -0,0 +1,710 @@
+//! Live dashboard for flock mode
+//!
+//! Shows every segment's status while a flock runs, styled with the retro
+//! TUI's color theme. The selected segment's output can be opened and
+//! segments can be cancelled. Closing the dashboard leaves the flock running
+//! and its progress is printed to the terminal again.
+
+use anyhow::Result;
+use crossterm::{
+    event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers},
+    execute,
+    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
+};
+use g3_ensembles::status::SegmentState;
+use g3_ensembles::{FlockEvent, SegmentCanceller, SegmentStatus};
+use ratatui::{
+    backend::CrosstermBackend,
+    layout::{Alignment, Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{
+        Block, Borders, Cell, Paragraph, Row, Scrollbar, ScrollbarOrientation, ScrollbarState,
+        Table, TableState,
+    },
+    Frame, Terminal,
+};
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::io;
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc::{self, error::TryRecvError};
+
+use crate::theme::ColorTheme;
+
+/// Output lines kept per segment
+const MAX_OUTPUT_LINES: usize = 1000;
+
+/// Orchestrator messages kept for the log pane
+const MAX_MESSAGES: usize = 100;
+
+/// Lines moved by PageUp / PageDown in the output view
+const PAGE_LINES: usize = 10;
+
+/// How long to wait for a key press before redrawing
+const TICK: Duration = Duration::from_millis(100);
+
+/// What a key press asks the dashboard to do
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum KeyAction {
+    None,
+    Cancel(usize),
+    Close,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum View {
+    /// Table of all segments
+    Segments,
+    /// Output of one segment, scrolled up `from_bottom` lines (0 follows
+    /// new output)
+    Output {
+        segment_id: usize,
+        from_bottom: usize,
+    },
+}
+
+struct DashboardState {
+    /// Module names in segment order
+    modules: Vec<String>,
+    segments: BTreeMap<usize, SegmentStatus>,
+    output: HashMap<usize, VecDeque<String>>,
+    messages: VecDeque<String>,
+    /// Index of the selected segment (segment id - 1)
+    selected: usize,
+    view: View,
+    finished: bool,
+    started: Instant,
+}
+
+impl DashboardState {
+    fn new() -> Self {
+        Self {
+            modules: Vec::new(),
+            segments: BTreeMap::new(),
+            output: HashMap::new(),
+            messages: VecDeque::new(),
+            selected: 0,
+            view: View::Segments,
+            finished: false,
+            started: Instant::now(),
+        }
+    }
+
+    fn apply(&mut self, event: FlockEvent) {
+        match event {
+            FlockEvent::SegmentsStarted { modules } => self.modules = modules,
+            FlockEvent::SegmentUpdated(status) => {
+                self.segments.insert(status.segment_id, status);
+            }
+            FlockEvent::SegmentOutput {
+                segment_id,
+                line,
+                is_error,
+            } => {
+                let lines = self.output.entry(segment_id).or_default();
+                lines.push_back(if is_error {
+                    format!("[ERROR] {}", line)
+                } else {
+                    line
+                });
+                if lines.len() > MAX_OUTPUT_LINES {
+                    lines.pop_front();
+                }
+            }
+            FlockEvent::Message(message) => self.push_message(message.trim().to_string()),
+            FlockEvent::SegmentsFinished => self.finished = true,
+        }
+    }
+
+    fn push_message(&mut self, message: String) {
+        if message.is_empty() {
+            return;
+        }
+        self.messages.push_back(message);
+        if self.messages.len() > MAX_MESSAGES {
+            self.messages.pop_front();
+        }
+    }
+
+    fn segment_count(&self) -> usize {
+        let highest = self.segments.keys().next_back().copied().unwrap_or(0);
+        self.modules.len().max(highest)
+    }
+
+    fn module_name(&self, segment_id: usize) -> &str {
+        self.modules
+            .get(segment_id - 1)
+            .map(String::as_str)
+            .unwrap_or("?")
+    }
+
+    fn count_by_state(&self, state: SegmentState) -> usize {
+        self.segments.values().filter(|s| s.state == state).count()
+    }
+
+    fn handle_key(&mut self, key: KeyEvent) -> KeyAction {
+        if key.kind != KeyEventKind::Press {
+            return KeyAction::None;
+        }
+        if key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('c') {
+            return KeyAction::Close;
+        }
+
+        match self.view {
+            View::Segments => match key.code {
+                KeyCode::Up | KeyCode::Char('k') => {
+                    self.selected = self.selected.saturating_sub(1);
+                }
+                KeyCode::Down | KeyCode::Char('j') if self.selected + 1 < self.segment_count() => {
+                    self.selected += 1;
+                }
+                KeyCode::Enter if self.segment_count() > 0 => {
+                    self.view = View::Output {
+                        segment_id: self.selected + 1,
+                        from_bottom: 0,
+                    };
+                }
+                KeyCode::Char('c') => return self.cancel(self.selected + 1),
+                KeyCode::Char('q') | KeyCode::Esc => return KeyAction::Close,
+                _ => {}
+            },
+            View::Output {
+                segment_id,
+                ref mut from_bottom,
+            } => {
+                let max_scroll = self
+                    .output
+                    .get(&segment_id)
+                    .map_or(0, |lines| lines.len().saturating_sub(1));
+                match key.code {
+                    KeyCode::Up | KeyCode::Char('k') => {
+                        *from_bottom = (*from_bottom + 1).min(max_scroll);
+                    }
+                    KeyCode::Down | KeyCode::Char('j') => {
+                        *from_bottom = from_bottom.saturating_sub(1);
+                    }
+                    KeyCode::PageUp => *from_bottom = (*from_bottom + PAGE_LINES).min(max_scroll),
+                    KeyCode::PageDown => *from_bottom = from_bottom.saturating_sub(PAGE_LINES),
+                    KeyCode::End => *from_bottom = 0,
+                    KeyCode::Esc | KeyCode::Enter | KeyCode::Backspace => {
+                        self.view = View::Segments;
+                    }
+                    KeyCode::Char('c') => return self.cancel(segment_id),
+                    KeyCode::Char('q') => return KeyAction::Close,
+                    _ => {}
+                }
+            }
+        }
+        KeyAction::None
+    }
+
+    /// Cancel a segment that has not finished yet
+    fn cancel(&mut self, segment_id: usize) -> KeyAction {
+        let cancellable = self.segments.get(&segment_id).is_some_and(|s| {
+            matches!(
+                s.state,
+                SegmentState::Pending | SegmentState::Blocked | SegmentState::Running
+            )
+        });
+        if !cancellable {
+            return KeyAction::None;
+        }
+        self.push_message(format!(
+            "Cancelling segment {} ({})",
+            segment_id,
+            self.module_name(segment_id)
+        ));
+        KeyAction::Cancel(segment_id)
+    }
+}
+
+/// Show the dashboard from the moment the segments start until they have all
+/// finished or it is closed. Blocks the calling thread.
+pub fn run(
+    theme: ColorTheme,
+    mut events: mpsc::UnboundedReceiver<FlockEvent>,
+    canceller: SegmentCanceller,
+) -> Result<()> {
+    let mut state = DashboardState::new();
+
+    // Partitioning and workspace setup print to the terminal as usual
+    loop {
+        match events.blocking_recv() {
+            Some(event @ FlockEvent::SegmentsStarted { .. }) => {
+                state.apply(event);
+                break;
+            }
+            Some(_) => {}
+            None => return Ok(()),
+        }
+    }
+
+    let mut screen = DashboardScreen::enter()?;
+    loop {
+        loop {
+            match events.try_recv() {
+                Ok(event) => state.apply(event),
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => {
+                    state.finished = true;
+                    break;
+                }
+            }
+        }
+        if state.finished {
+            break;
+        }
+
+        screen.terminal.draw(|f| draw(f, &state, &theme))?;
+
+        if event::poll(TICK)? {
+            if let Event::Key(key) = event::read()? {
+                match state.handle_key(key) {
+                    KeyAction::Cancel(segment_id) => canceller.cancel(segment_id),
+                    KeyAction::Close => break,
+                    KeyAction::None => {}
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Alternate screen the dashboard draws on; the terminal is restored on drop
+struct DashboardScreen {
+    terminal: Terminal<CrosstermBackend<io::Stdout>>,
+}
+
+impl DashboardScreen {
+    fn enter() -> Result<Self> {
+        enable_raw_mode()?;
+        let mut stdout = io::stdout();
+        execute!(stdout, EnterAlternateScreen)?;
+        let terminal = Terminal::new(CrosstermBackend::new(stdout))?;
+        Ok(Self { terminal })
+    }
+}
+
+impl Drop for DashboardScreen {
+    fn drop(&mut self) {
+        let _ = disable_raw_mode();
+        let _ = execute!(self.terminal.backend_mut(), LeaveAlternateScreen);
+        let _ = self.terminal.show_cursor();
+    }
+}
+
+fn draw(f: &mut Frame, state: &DashboardState, theme: &ColorTheme) {
+    let area = f.area();
+    f.render_widget(
+        Block::default().style(Style::default().bg(theme.terminal_bg.to_color())),
+        area,
+    );
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(3), // Summary
+            Constraint::Min(5),    // Segments or output
+            Constraint::Length(7), // Flock log
+            Constraint::Length(1), // Key bar
+        ])
+        .split(area);
+
+    draw_summary(f, chunks[0], state, theme);
+    match state.view {
+        View::Segments => draw_segments(f, chunks[1], state, theme),
+        View::Output {
+            segment_id,
+            from_bottom,
+        } => draw_output(f, chunks[1], state, segment_id, from_bottom, theme),
+    }
+    draw_messages(f, chunks[2], state, theme);
+    draw_key_bar(f, chunks[3], &state.view, theme);
+}
+
+/// Bordered block in the retro TUI style
+fn panel<'a>(title: String, theme: &ColorTheme) -> Block<'a> {
+    Block::default()
+        .title(title)
+        .title_alignment(Alignment::Center)
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(theme.terminal_dim_green.to_color()))
+        .style(Style::default().bg(theme.terminal_bg.to_color()))
+}
+
+fn state_label(state: &SegmentState, theme: &ColorTheme) -> (&'static str, Color) {
+    match state {
+        SegmentState::Pending => ("PENDING", theme.terminal_pale_blue.to_color()),
+        SegmentState::Blocked => ("BLOCKED", theme.terminal_pale_blue.to_color()),
+        SegmentState::Running => ("RUNNING", theme.terminal_dark_amber.to_color()),
+        SegmentState::Completed => ("COMPLETED", theme.terminal_success.to_color()),
+        SegmentState::Failed => ("FAILED", theme.terminal_red.to_color()),
+        SegmentState::Cancelled => ("CANCELLED", theme.terminal_dim_green.to_color()),
+    }
+}
+
+fn draw_summary(f: &mut Frame, area: Rect, state: &DashboardState, theme: &ColorTheme) {
+    let elapsed = state.started.elapsed().as_secs();
+    let label = Style::default()
+        .fg(theme.terminal_amber.to_color())
+        .add_modifier(Modifier::BOLD);
+    let value = Style::default().fg(theme.terminal_white.to_color());
+
+    let mut spans = vec![
+        Span::styled(" SEGMENTS: ", label),
+        Span::styled(state.segment_count().to_string(), value),
+    ];
+    for segment_state in [
+        SegmentState::Running,
+        SegmentState::Completed,
+        SegmentState::Failed,
+        SegmentState::Cancelled,
+    ] {
+        let (name, color) = state_label(&segment_state, theme);
+        spans.push(Span::styled(" | ", label));
+        spans.push(Span::styled(format!("{}: ", name), label));
+        spans.push(Span::styled(
+            state.count_by_state(segment_state).to_string(),
+            Style::default().fg(color).add_modifier(Modifier::BOLD),
+        ));
+    }
+    spans.push(Span::styled(" | ELAPSED: ", label));
+    spans.push(Span::styled(
+        format!(
+            "{:02}:{:02}:{:02}",
+            elapsed / 3600,
+            elapsed / 60 % 60,
+            elapsed % 60
+        ),
+        value,
+    ));
+
+    let summary = Paragraph::new(Line::from(spans)).block(panel(" G3 FLOCK ".to_string(), theme));
+    f.render_widget(summary, area);
+}
+
+fn draw_segments(f: &mut Frame, area: Rect, state: &DashboardState, theme: &ColorTheme) {
+    let header_style = Style::default()
+        .fg(theme.terminal_amber.to_color())
+        .add_modifier(Modifier::BOLD);
+    let header = Row::new(
+        [
+            "#",
+            "MODULE",
+            "STATE",
+            "TURN",
+            "TOKENS",
+            "TOOLS",
+            "ERRORS",
+            "LAST MESSAGE",
+        ]
+        .map(|title| Cell::from(title).style(header_style)),
+    );
+
+    let text = Style::default().fg(theme.terminal_green.to_color());
+    let rows: Vec<Row> = (1..=state.segment_count())
+        .map(|segment_id| {
+            let module = state.module_name(segment_id).to_string();
+            let Some(status) = state.segments.get(&segment_id) else {
+                return Row::new(vec![Cell::from(segment_id.to_string()), Cell::from(module)])
+                    .style(text);
+            };
+            let (label, color) = state_label(&status.state, theme);
+            let message = match status.state {
+                SegmentState::Failed | SegmentState::Cancelled => status
+                    .error_message
+                    .as_ref()
+                    .or(status.last_message.as_ref()),
+                _ => status.last_message.as_ref(),
+            };
+            let errors_style = if status.errors > 0 {
+                Style::default().fg(theme.terminal_red.to_color())
+            } else {
+                text
+            };
+            Row::new(vec![
+                Cell::from(segment_id.to_string()),
+                Cell::from(module),
+                Cell::from(label).style(Style::default().fg(color).add_modifier(Modifier::BOLD)),
+                Cell::from(format!("{}/{}", status.current_turn, status.max_turns)),
+                Cell::from(status.tokens_used.to_string()),
+                Cell::from(status.tool_calls.to_string()),
+                Cell::from(status.errors.to_string()).style(errors_style),
+                Cell::from(message.cloned().unwrap_or_default()),
+            ])
+            .style(text)
+        })
+        .collect();
+
+    let widths = [
+        Constraint::Length(3),
+        Constraint::Length(18),
+        Constraint::Length(10),
+        Constraint::Length(6),
+        Constraint::Length(8),
+        Constraint::Length(6),
+        Constraint::Length(7),
+        Constraint::Min(10),
+    ];
+    let table = Table::new(rows, widths)
+        .header(header)
+        .block(panel(" SEGMENTS ".to_string(), theme))
+        .row_highlight_style(
+            Style::default()
+                .bg(theme.terminal_dim_green.to_color())
+                .fg(theme.terminal_bg.to_color()),
+        );
+
+    let mut table_state = TableState::default().with_selected(Some(state.selected));
+    f.render_stateful_widget(table, area, &mut table_state);
+}
+
+fn draw_output(
+    f: &mut Frame,
+    area: Rect,
+    state: &DashboardState,
+    segment_id: usize,
+    from_bottom: usize,
+    theme: &ColorTheme,
+) {
+    let empty = VecDeque::new();
+    let lines = state.output.get(&segment_id).unwrap_or(&empty);
+    let visible_height = area.height.saturating_sub(2) as usize;
+    let end = lines.len().saturating_sub(from_bottom);
+    let start = end.saturating_sub(visible_height);
+
+    let visible: Vec<Line> = lines
+        .range(start..end)
+        .map(|line| {
+            let color = if line.starts_with("[ERROR]") {
+                theme.terminal_red.to_color()
+            } else {
+                theme.terminal_green.to_color()
+            };
+            Line::from(Span::styled(line.as_str(), Style::default().fg(color)))
+        })
+        .collect();
+
+    let title = format!(
+        " SEGMENT {} - {} - OUTPUT ",
+        segment_id,
+        state.module_name(segment_id).to_uppercase()
+    );
+    f.render_widget(Paragraph::new(visible).block(panel(title, theme)), area);
+
+    if lines.len() > visible_height {
+        let scrollbar = Scrollbar::new(ScrollbarOrientation::VerticalRight)
+            .begin_symbol(Some("▲"))
+            .end_symbol(Some("▼"))
+            .track_symbol(Some("│"))
+            .thumb_symbol("█")
+            .style(Style::default().fg(theme.terminal_dim_green.to_color()));
+        let mut scrollbar_state = ScrollbarState::new(lines.len().saturating_sub(visible_height))
+            .position(start)
+            .viewport_content_length(visible_height);
+        f.render_stateful_widget(scrollbar, area, &mut scrollbar_state);
+    }
+}
+
+fn draw_messages(f: &mut Frame, area: Rect, state: &DashboardState, theme: &ColorTheme) {
+    let visible_height = area.height.saturating_sub(2) as usize;
+    let skip = state.messages.len().saturating_sub(visible_height);
+    let lines: Vec<Line> = state
+        .messages
+        .iter()
+        .skip(skip)
+        .map(|message| {
+            Line::from(Span::styled(
+                message.as_str(),
+                Style::default().fg(theme.terminal_amber.to_color()),
+            ))
+        })
+        .collect();
+    f.render_widget(
+        Paragraph::new(lines).block(panel(" FLOCK LOG ".to_string(), theme)),
+        area,
+    );
+}
+
+fn draw_key_bar(f: &mut Frame, area: Rect, view: &View, theme: &ColorTheme) {
+    let keys: &[(&str, &str)] = match view {
+        View::Segments => &[
+            ("↑↓", "SELECT"),
+            ("ENTER", "OUTPUT"),
+            ("C", "CANCEL SEGMENT"),
+            ("Q", "CLOSE DASHBOARD"),
+        ],
+        View::Output { .. } => &[
+            ("↑↓ PGUP PGDN", "SCROLL"),
+            ("END", "FOLLOW"),
+            ("ESC", "BACK"),
+            ("C", "CANCEL SEGMENT"),
+            ("Q", "CLOSE DASHBOARD"),
+        ],
+    };
+
+    let mut spans = Vec::new();
+    for (key, action) in keys {
+        spans.push(Span::styled(
+            format!(" {} ", key),
+            Style::default()
+                .fg(theme.terminal_amber.to_color())
+                .add_modifier(Modifier::BOLD),
+        ));
+        spans.push(Span::styled(
+            format!("{}  ", action),
+            Style::default().fg(theme.terminal_dim_green.to_color()),
+        ));
+    }
+
+    let bar = Paragraph::new(Line::from(spans))
+        .style(Style::default().bg(theme.terminal_bg.to_color()))
+        .alignment(Alignment::Left);
+    f.render_widget(bar, area);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use std::path::PathBuf;
+
+    fn segment(segment_id: usize, state: SegmentState) -> SegmentStatus {
+        SegmentStatus {
+            segment_id,
+            workspace: PathBuf::from(format!("segment-{}", segment_id)),
+            state,
+            started_at: Utc::now(),
+            completed_at: None,
+            tokens_used: 0,
+            tool_calls: 0,
+            errors: 0,
+            current_turn: 0,
+            max_turns: 5,
+            last_message: None,
+            error_message: None,
+        }
+    }
+
+    fn key(code: KeyCode) -> KeyEvent {
+        KeyEvent::new(code, KeyModifiers::NONE)
+    }
+
+    fn started_state() -> DashboardState {
+        let mut state = DashboardState::new();
+        state.apply(FlockEvent::SegmentsStarted {
+            modules: vec!["core".to_string(), "api".to_string()],
+        });
+        state.apply(FlockEvent::SegmentUpdated(segment(
+            1,
+            SegmentState::Running,
+        )));
+        state.apply(FlockEvent::SegmentUpdated(segment(
+            2,
+            SegmentState::Blocked,
+        )));
+        state
+    }
+
+    #[test]
+    fn test_events_update_segments_and_output() {
+        let mut state = started_state();
+        let mut running = segment(1, SegmentState::Running);
+        running.current_turn = 2;
+        state.apply(FlockEvent::SegmentUpdated(running));
+        state.apply(FlockEvent::SegmentOutput {
+            segment_id: 1,
+            line: "TURN 2/5".to_string(),
+            is_error: false,
+        });
+        state.apply(FlockEvent::SegmentOutput {
+            segment_id: 1,
+            line: "oops".to_string(),
+            is_error: true,
+        });
+        state.apply(FlockEvent::Message("\n✅ Segment 3 completed".to_string()));
+
+        assert_eq!(state.segment_count(), 2);
+        assert_eq!(state.segments[&1].current_turn, 2);
+        assert_eq!(state.count_by_state(SegmentState::Blocked), 1);
+        assert_eq!(
+            state.output[&1].iter().collect::<Vec<_>>(),
+            vec!["TURN 2/5", "[ERROR] oops"]
+        );
+        assert_eq!(state.messages.back().unwrap(), "✅ Segment 3 completed");
+        assert!(!state.finished);
+        state.apply(FlockEvent::SegmentsFinished);
+        assert!(state.finished);
+    }
+
+    #[test]
+    fn test_keys_select_drill_in_and_cancel() {
+        let mut state = started_state();
+
+        assert_eq!(state.handle_key(key(KeyCode::Down)), KeyAction::None);
+        assert_eq!(state.selected, 1);
+        // There is no third segment to move to
+        state.handle_key(key(KeyCode::Down));
+        assert_eq!(state.selected, 1);
+        assert_eq!(
+            state.handle_key(key(KeyCode::Char('c'))),
+            KeyAction::Cancel(2)
+        );
+        assert_eq!(state.messages.back().unwrap(), "Cancelling segment 2 (api)");
+
+        state.handle_key(key(KeyCode::Up));
+        state.handle_key(key(KeyCode::Enter));
+        assert_eq!(
+            state.view,
+            View::Output {
+                segment_id: 1,
+                from_bottom: 0
+            }
+        );
+        assert_eq!(
+            state.handle_key(key(KeyCode::Char('c'))),
+            KeyAction::Cancel(1)
+        );
+        state.handle_key(key(KeyCode::Esc));
+        assert_eq!(state.view, View::Segments);
+
+        // Finished segments cannot be cancelled
+        state.apply(FlockEvent::SegmentUpdated(segment(
+            1,
+            SegmentState::Completed,
+        )));
+        assert_eq!(state.handle_key(key(KeyCode::Char('c'))), KeyAction::None);
+        assert_eq!(state.handle_key(key(KeyCode::Char('q'))), KeyAction::Close);
+    }
+
+    #[test]
+    fn test_output_scrolls_within_the_buffer() {
+        let mut state = started_state();
+        for i in 0..3 {
+            state.apply(FlockEvent::SegmentOutput {
+                segment_id: 1,
+                line: format!("line {}", i),
+                is_error: false,
+            });
+        }
+        state.handle_key(key(KeyCode::Enter));
+        state.handle_key(key(KeyCode::PageUp));
+        assert_eq!(
+            state.view,
+            View::Output {
+                segment_id: 1,
+                from_bottom: 2
+            }
+        );
+        state.handle_key(key(KeyCode::Down));
+        state.handle_key(key(KeyCode::End));
+        assert_eq!(
+            state.view,
+            View::Output {
+                segment_id: 1,
+                from_bottom: 0
+            }
+        );
+    }
+}
@@ -118,6 +118,14 @@ fn generate_turn_histogram(turn_metrics: &[TurnMetrics]) -> String {
     histogram
 }
 
+/// The tighter of two optional spending limits
+fn tighter_cost_limit(a: Option<f64>, b: Option<f64>) -> Option<f64> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(a.min(b)),
+        (a, b) => a.or(b),
+    }
+}
+
 /// Format a Duration as human-readable elapsed time (e.g., "1h 23m 45s", "5m 30s", "45s")
 fn format_elapsed_time(duration: Duration) -> String {
     let total_secs = duration.as_secs();
@@ -256,15 +264,21 @@ use sha2::{Digest, Sha256};
 use std::path::Path;
 use std::path::PathBuf;
 use std::process::exit;
+use std::sync::Arc;
 use tokio_util::sync::CancellationToken;
 use tracing::{error, info};
 
+use g3_core::cost::{format_usd, BudgetExceeded};
 use g3_core::error_handling::{classify_error, ErrorType, RecoverableError};
+use g3_core::machine_events::{MachineEvent, MachineEventWriter};
+use g3_core::machine_ui_writer::MachineUiWriter;
+mod flock_dashboard;
 mod simple_output;
+mod theme;
 mod ui_writer_impl;
 use simple_output::SimpleOutput;
-mod machine_ui_writer;
-use machine_ui_writer::MachineUiWriter;
+mod stdio_server;
+use stdio_server::{serve_stdio, RpcPeer};
 use ui_writer_impl::ConsoleUiWriter;
 
 #[derive(Parser, Clone)]
@@ -319,11 +333,15 @@ pub struct Cli {
     #[arg(long)]
     pub chat: bool,
 
-    /// Enable machine-friendly output mode with JSON markers and stats
+    /// Emit a versioned JSON Lines event stream instead of human-readable output
     #[arg(long)]
     pub machine: bool,
 
-    /// Override the configured provider (anthropic, databricks, embedded, openai, openrouter)
+    /// Serve a long-lived agent over JSON-RPC on stdin/stdout (for editor integrations)
+    #[arg(long, conflicts_with_all = ["machine", "autonomous", "auto", "planning", "task"])]
+    pub serve_stdio: bool,
+
+    /// Override the configured provider (anthropic, databricks, embedded, openai, openrouter, ollama, gemini, bedrock)
     #[arg(long, value_name = "PROVIDER")]
     pub provider: Option<String>,
 
@@ -367,6 +385,34 @@ pub struct Cli {
     #[arg(long, default_value = "5")]
     pub flock_max_turns: usize,
 
+    /// Maximum number of flock segments running at once (default: unlimited)
+    #[arg(long, value_name = "N")]
+    pub flock_concurrency: Option<usize>,
+
+    /// Test command run on the integration branch after each segment merge (for flock mode)
+    #[arg(long, value_name = "COMMAND")]
+    pub flock_test_command: Option<String>,
+
+    /// Times a failed flock segment is rerun in its workspace (default: 0)
+    #[arg(long, default_value = "0", value_name = "N")]
+    pub flock_retries: usize,
+
+    /// Create flock segments as git worktrees on per-segment branches instead of full clones
+    #[arg(long, conflicts_with = "flock_resume")]
+    pub flock_worktrees: bool,
+
+    /// Resume an interrupted flock run from its workspace, rerunning segments that did not complete
+    #[arg(long, value_name = "FLOCK_WORKSPACE", conflicts_with_all = ["project", "flock_workspace", "segments"])]
+    pub flock_resume: Option<PathBuf>,
+
+    /// Show a live dashboard of flock segments, where segment output can be viewed and segments cancelled
+    #[arg(long)]
+    pub flock_dashboard: bool,
+
+    /// Color theme for the flock dashboard: retro, dracula or a theme file (default: retro)
+    #[arg(long, value_name = "THEME", requires = "flock_dashboard")]
+    pub theme: Option<String>,
+
     /// Enable planning mode for requirements-driven development
     #[arg(long, conflicts_with_all = ["autonomous", "auto", "chat"])]
     pub planning: bool,
@@ -382,6 +428,14 @@ pub struct Cli {
     /// Enable fast codebase discovery before first LLM turn
     #[arg(long, value_name = "PATH")]
     pub codebase_fast_start: Option<PathBuf>,
+
+    /// Resume a saved session by ID (from logs/g3_session_<id>.json)
+    #[arg(long, value_name = "SESSION_ID", conflicts_with_all = ["autonomous", "auto", "planning"])]
+    pub resume: Option<String>,
+
+    /// Resume the most recent session in the workspace
+    #[arg(long = "continue", conflicts_with_all = ["resume", "autonomous", "auto", "planning"])]
+    pub continue_session: bool,
 }
 
 pub async fn run() -> Result<()> {
@@ -396,10 +450,13 @@ pub async fn run() -> Result<()> {
             project_dir.clone(),
             flock_workspace.clone(),
             num_segments,
-            cli.flock_max_turns,
+            &cli,
         )
         .await;
     }
+    if let Some(flock_workspace) = &cli.flock_resume {
+        return resume_flock_mode(flock_workspace.clone(), &cli).await;
+    }
     if cli.codebase_fast_start.is_some() {
         print!("codebase_fast_start is temporarily disabled.");
         exit(1);
@@ -421,7 +478,7 @@ pub async fn run() -> Result<()> {
     }
 
     // Only initialize logging if not in retro mode
-    if !cli.machine {
+    if !cli.machine && !cli.serve_stdio {
         // Initialize logging with filtering
         use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, EnvFilter};
 
@@ -533,7 +590,16 @@ pub async fn run() -> Result<()> {
 
     // Validate provider if specified
     if let Some(ref provider) = cli.provider {
-        let valid_providers = ["anthropic", "databricks", "embedded", "openai", "openrouter"];
+        let valid_providers = [
+            "anthropic",
+            "databricks",
+            "embedded",
+            "openai",
+            "openrouter",
+            "ollama",
+            "gemini",
+            "bedrock",
+        ];
         let provider_type = provider.split('.').next().unwrap_or(provider);
         if !valid_providers.contains(&provider_type) {
             return Err(anyhow::anyhow!(
@@ -556,12 +622,27 @@ pub async fn run() -> Result<()> {
     };
 
     // Execute task, autonomous mode, or start interactive mode based on machine mode
-    if cli.machine {
-        // Machine mode - use MachineUiWriter
+    if cli.serve_stdio {
+        // JSON-RPC server - events become notifications, prompts go to the client
+        let peer = Arc::new(RpcPeer::stdout());
+        let ui_writer = MachineUiWriter::new(peer.clone());
+        let mut agent = Agent::new_with_readme_and_quiet(
+            config.clone(),
+            ui_writer,
+            combined_content.clone(),
+            cli.quiet,
+        )
+        .await?;
+        resume_requested_session(&mut agent, &cli).await?;
 
-        let ui_writer = MachineUiWriter::new();
+        serve_stdio(agent, peer).await?;
+    } else if cli.machine {
+        // Machine mode - use MachineUiWriter, which emits JSON Lines events
 
-        let agent = if cli.autonomous {
+        let events = Arc::new(MachineEventWriter::stdout());
+        let ui_writer = MachineUiWriter::new(events.clone());
+
+        let mut agent = if cli.autonomous {
             Agent::new_autonomous_with_readme_and_quiet(
                 config.clone(),
                 ui_writer,
@@ -579,7 +660,36 @@ pub async fn run() -> Result<()> {
             .await?
         };
 
-        run_with_machine_mode(agent, cli, project).await?;
+        let resumed = resume_requested_session(&mut agent, &cli).await?;
+        let (provider, model) = agent.get_provider_info().ok().unzip();
+        let mode = if cli.autonomous {
+            "autonomous"
+        } else if cli.task.is_some() {
+            "task"
+        } else {
+            "interactive"
+        };
+        events.emit(MachineEvent::SessionStart {
+            mode: mode.to_string(),
+            provider,
+            model,
+            workspace: Some(project.workspace().display().to_string()),
+            session_id: agent.get_session_id().map(str::to_string),
+        });
+        if let Some((session_id, restored)) = resumed {
+            events.emit(MachineEvent::Message {
+                text: format!("Resumed session {} ({} messages)", session_id, restored),
+            });
+        }
+
+        let result = run_with_machine_mode(agent, cli, project, &events).await;
+        if let Err(e) = &result {
+            events.emit(MachineEvent::Error {
+                message: e.to_string(),
+            });
+        }
+        events.emit(MachineEvent::SessionEnd);
+        result?;
     } else {
         // Normal mode - use ConsoleUiWriter
 
@@ -599,7 +709,7 @@ pub async fn run() -> Result<()> {
 
         let ui_writer = ConsoleUiWriter::new();
 
-        let agent = if cli.autonomous {
+        let mut agent = if cli.autonomous {
             Agent::new_autonomous_with_readme_and_quiet(
                 config.clone(),
                 ui_writer,
@@ -617,18 +727,43 @@ pub async fn run() -> Result<()> {
             .await?
         };
 
+        if let Some((session_id, restored)) = resume_requested_session(&mut agent, &cli).await? {
+            println!(
+                "{}🔄 Resumed session {} ({} messages){}",
+                SetForegroundColor(Color::DarkGrey),
+                session_id,
+                restored,
+                ResetColor
+            );
+        }
+
         run_with_console_mode(agent, cli, project, combined_content).await?;
     }
 
     Ok(())
 }
 
+/// Load the session named by `--resume`, or the latest one for `--continue`.
+/// Returns the session id and the number of restored messages.
+async fn resume_requested_session<W: UiWriter>(
+    agent: &mut Agent<W>,
+    cli: &Cli,
+) -> Result<Option<(String, usize)>> {
+    let session_id = match (&cli.resume, cli.continue_session) {
+        (Some(id), _) => id.clone(),
+        (None, true) => g3_core::session::SessionLog::latest_session_id()?,
+        (None, false) => return Ok(None),
+    };
+    let restored = agent.resume_session(&session_id).await?;
+    Ok(Some((session_id, restored)))
+}
+
 /// Run flock mode - parallel multi-agent development
 async fn run_flock_mode(
     project_dir: PathBuf,
     flock_workspace: PathBuf,
     num_segments: usize,
-    max_turns: usize,
+    cli: &Cli,
 ) -> Result<()> {
     let output = SimpleOutput::new();
 
@@ -638,17 +773,16 @@ async fn run_flock_mode(
     output.print(&format!("📁 Project: {}", project_dir.display()));
     output.print(&format!("🗂️  Workspace: {}", flock_workspace.display()));
     output.print(&format!("🔢 Segments: {}", num_segments));
-    output.print(&format!("🔄 Max Turns per Segment: {}", max_turns));
-    output.print("");
+    print_flock_options(&output, cli);
 
     // Create flock configuration
-    let config = g3_ensembles::FlockConfig::new(project_dir, flock_workspace, num_segments)?
-        .with_max_turns(max_turns);
+    let config = g3_ensembles::FlockConfig::new(project_dir, flock_workspace, num_segments)?;
+    let config = apply_flock_options(config, cli);
 
     // Create and run flock mode
-    let mut flock = g3_ensembles::FlockMode::new(config)?;
+    let flock = g3_ensembles::FlockMode::new(config)?;
 
-    match flock.run().await {
+    match run_flock(flock, &output, cli).await {
         Ok(_) => output.print("\n✅ Flock mode completed successfully"),
         Err(e) => output.print(&format!("\n❌ Flock mode failed: {}", e)),
     }
@@ -656,6 +790,92 @@ async fn run_flock_mode(
     Ok(())
 }
 
+/// Resume an interrupted flock run from the status saved in its workspace
+async fn resume_flock_mode(flock_workspace: PathBuf, cli: &Cli) -> Result<()> {
+    let output = SimpleOutput::new();
+
+    output.print("");
+    output.print("🦅 G3 FLOCK MODE - Resuming");
+    output.print("");
+    output.print(&format!("🗂️  Workspace: {}", flock_workspace.display()));
+    print_flock_options(&output, cli);
+
+    let config = g3_ensembles::FlockConfig::resume(flock_workspace)?;
+    let config = apply_flock_options(config, cli);
+    let flock = g3_ensembles::FlockMode::resume(config)?;
+
+    match run_flock(flock, &output, cli).await {
+        Ok(_) => output.print("\n✅ Flock mode completed successfully"),
+        Err(e) => output.print(&format!("\n❌ Flock mode failed: {}", e)),
+    }
+
+    Ok(())
+}
+
+/// Run the flock, showing the live dashboard while its segments run if asked to
+async fn run_flock(
+    mut flock: g3_ensembles::FlockMode,
+    output: &SimpleOutput,
+    cli: &Cli,
+) -> Result<()> {
+    if !cli.flock_dashboard {
+        return flock.run().await;
+    }
+
+    let theme = theme::ColorTheme::load(cli.theme.as_deref())?;
+    let (events_tx, events_rx) = tokio::sync::mpsc::unbounded_channel();
+    let canceller = flock.canceller();
+    let mut flock = flock.with_events(events_tx);
+    let dashboard =
+        tokio::task::spawn_blocking(move || flock_dashboard::run(theme, events_rx, canceller));
+
+    let result = flock.run().await;
+    // Closes the event channel if the segments never started
+    drop(flock);
+    match dashboard.await {
+        Ok(Ok(())) => {}
+        Ok(Err(e)) => output.print(&format!("⚠️  Flock dashboard failed: {}", e)),
+        Err(e) => output.print(&format!("⚠️  Flock dashboard crashed: {}", e)),
+    }
+    result
+}
+
+fn print_flock_options(output: &SimpleOutput, cli: &Cli) {
+    output.print(&format!(
+        "🔄 Max Turns per Segment: {}",
+        cli.flock_max_turns
+    ));
+    if let Some(concurrency) = cli.flock_concurrency {
+        output.print(&format!("🚦 Max Concurrent Segments: {}", concurrency));
+    }
+    if cli.flock_retries > 0 {
+        output.print(&format!("🔁 Retries per Segment: {}", cli.flock_retries));
+    }
+    if let Some(ref command) = cli.flock_test_command {
+        output.print(&format!("🧪 Integration Tests: {}", command));
+    }
+    if cli.flock_worktrees {
+        output.print("🌳 Segment Worktrees: enabled");
+    }
+    output.print("");
+}
+
+fn apply_flock_options(config: g3_ensembles::FlockConfig, cli: &Cli) -> g3_ensembles::FlockConfig {
+    let mut config = config
+        .with_max_turns(cli.flock_max_turns)
+        .with_max_retries(cli.flock_retries);
+    if let Some(concurrency) = cli.flock_concurrency {
+        config = config.with_max_concurrent_segments(concurrency);
+    }
+    if let Some(ref command) = cli.flock_test_command {
+        config = config.with_test_command(command.clone());
+    }
+    if cli.flock_worktrees {
+        config = config.with_segment_worktrees();
+    }
+    config
+}
+
 /// Accumulative autonomous mode: accumulates requirements from user input
 /// and runs autonomous mode after each input
 async fn run_accumulative_mode(
@@ -985,19 +1205,15 @@ async fn run_accumulative_mode(
 async fn run_autonomous_machine(
     mut agent: Agent<MachineUiWriter>,
     project: Project,
+    events: &MachineEventWriter,
     show_prompt: bool,
     show_code: bool,
-    max_turns: usize,
-    _quiet: bool,
-    _codebase_fast_start: Option<PathBuf>,
 ) -> Result<()> {
-    println!("AUTONOMOUS_MODE_STARTED");
-    println!("WORKSPACE: {}", project.workspace().display());
-    println!("MAX_TURNS: {}", max_turns);
-
     // Check if requirements exist
     if !project.has_requirements() {
-        println!("ERROR: requirements.md not found in workspace directory");
+        events.emit(MachineEvent::Error {
+            message: "requirements.md not found in workspace directory".to_string(),
+        });
         return Ok(());
     }
 
@@ -1005,13 +1221,13 @@ async fn run_autonomous_machine(
     let requirements = match project.read_requirements()? {
         Some(content) => content,
         None => {
-            println!("ERROR: Could not read requirements");
+            events.emit(MachineEvent::Error {
+                message: "Could not read requirements".to_string(),
+            });
             return Ok(());
         }
     };
 
-    println!("REQUIREMENTS_LOADED");
-
     // For now, just execute a simple autonomous loop
     // This is a simplified version - full implementation would need coach-player loop
     let task = format!(
@@ -1019,16 +1235,31 @@ async fn run_autonomous_machine(
         requirements
     );
 
-    println!("TASK_START");
-    let result = agent
+    // There are no coaches here, so the run budget is the player's alone
+    let budget = agent.get_config().budget.clone();
+    agent.set_cost_limit(tighter_cost_limit(
+        budget.max_cost_usd,
+        budget.max_autonomous_cost_usd,
+    ));
+
+    events.emit(MachineEvent::TaskStart { task: task.clone() });
+    let result = match agent
         .execute_task_with_timing(&task, None, false, show_prompt, show_code, true, None)
-        .await?;
-    println!("AGENT_RESPONSE:");
-    println!("{}", result.response);
-    println!("END_AGENT_RESPONSE");
-    println!("TASK_END");
+        .await
+    {
+        Ok(result) => result,
+        Err(e) if e.downcast_ref::<BudgetExceeded>().is_some() => {
+            events.emit(MachineEvent::Error {
+                message: e.to_string(),
+            });
+            return Ok(());
+        }
+        Err(e) => return Err(e),
+    };
+    events.emit(MachineEvent::TaskEnd {
+        response: result.response,
+    });
 
-    println!("AUTONOMOUS_MODE_ENDED");
     Ok(())
 }
 
@@ -1085,21 +1316,21 @@ async fn run_with_machine_mode(
     mut agent: Agent<MachineUiWriter>,
     cli: Cli,
     project: Project,
+    events: &MachineEventWriter,
 ) -> Result<()> {
     if cli.autonomous {
         // Autonomous mode with coach-player feedback loop
         run_autonomous_machine(
             agent,
             project,
+            events,
             cli.show_prompt,
             cli.show_code,
-            cli.max_turns,
-            cli.quiet,
-            cli.codebase_fast_start.clone(),
         )
         .await?;
     } else if let Some(task) = cli.task {
         // Single-shot mode
+        events.emit(MachineEvent::TaskStart { task: task.clone() });
         let result = agent
             .execute_task_with_timing(
                 &task,
@@ -1111,12 +1342,12 @@ async fn run_with_machine_mode(
                 None,
             )
             .await?;
-        println!("AGENT_RESPONSE:");
-        println!("{}", result.response);
-        println!("END_AGENT_RESPONSE");
+        events.emit(MachineEvent::TaskEnd {
+            response: result.response,
+        });
     } else {
         // Interactive mode
-        run_interactive_machine(agent, cli.show_prompt, cli.show_code).await?;
+        run_interactive_machine(agent, events, cli.show_prompt, cli.show_code).await?;
     }
 
     Ok(())
@@ -1413,6 +1644,9 @@ async fn run_interactive<W: UiWriter>(
                                     "  /readme    - Reload README.md and AGENTS.md from disk",
                                 );
                                 output.print("  /stats     - Show detailed context and performance statistics");
+                                output.print("  /rewind [n|id] [--revert] - List turns, or cut the conversation back n turns or to a message id (--revert also undoes file edits)");
+                                output.print("  /fork [n|id] [--revert]   - Continue in a new session, optionally rewound; the original session is kept");
+                                output.print("  /undo      - Restore the files changed by the last write_file/str_replace");
                                 output.print("  /help      - Show this help message");
                                 output.print("  exit/quit  - Exit the interactive session");
                                 output.print("");
@@ -1465,6 +1699,62 @@ async fn run_interactive<W: UiWriter>(
                                 output.print(&stats);
                                 continue;
                             }
+                            "/undo" => {
+                                match agent.undo_last_edit() {
+                                    Ok(Some(checkpoint)) => {
+                                        output.print(&format!("↩️ Undid {}", checkpoint.tool));
+                                        for file in &checkpoint.files {
+                                            output.print(&format!("  {}", file.path.display()));
+                                        }
+                                    }
+                                    Ok(None) => output.print("ℹ️ No edits to undo"),
+                                    Err(e) => output.print(&format!("❌ Error during undo: {}", e)),
+                                }
+                                continue;
+                            }
+                            cmd if cmd.split_whitespace().next() == Some("/rewind") => {
+                                let (target, revert) = parse_rewind_args(&cmd["/rewind".len()..]);
+                                match target {
+                                    None => {
+                                        let turns = agent.user_turns();
+                                        if turns.is_empty() {
+                                            output.print("📜 No turns to rewind yet");
+                                        } else {
+                                            output.print("📜 Turns (use /rewind <n> or /rewind <id>):");
+                                            for (i, (id, task)) in turns.iter().enumerate() {
+                                                output.print(&format!(
+                                                    "  {:>3}. [{}] {}",
+                                                    turns.len() - i,
+                                                    id,
+                                                    turn_preview(task)
+                                                ));
+                                            }
+                                        }
+                                    }
+                                    Some(target) => match agent.rewind(&target, revert) {
+                                        Ok(summary) => print_rewind_summary(&output, &summary),
+                                        Err(e) => output.print(&format!("❌ {}", e)),
+                                    },
+                                }
+                                continue;
+                            }
+                            cmd if cmd.split_whitespace().next() == Some("/fork") => {
+                                let (target, revert) = parse_rewind_args(&cmd["/fork".len()..]);
+                                let original = agent.get_session_id().map(str::to_string);
+                                let fork_id = agent.fork_session();
+                                output.print(&format!(
+                                    "🍴 Forked session {} → {}",
+                                    original.as_deref().unwrap_or("(unsaved)"),
+                                    fork_id
+                                ));
+                                if let Some(target) = target {
+                                    match agent.rewind(&target, revert) {
+                                        Ok(summary) => print_rewind_summary(&output, &summary),
+                                        Err(e) => output.print(&format!("❌ {}", e)),
+                                    }
+                                }
+                                continue;
+                            }
                             _ => {
                                 output.print(&format!(
                                     "❌ Unknown command: {}. Type /help for available commands.",
@@ -1591,22 +1881,10 @@ async fn execute_task<W: UiWriter>(
 
 async fn run_interactive_machine(
     mut agent: Agent<MachineUiWriter>,
+    events: &MachineEventWriter,
     show_prompt: bool,
     show_code: bool,
 ) -> Result<()> {
-    println!("INTERACTIVE_MODE_STARTED");
-
-    // Display provider and model information
-    match agent.get_provider_info() {
-        Ok((provider, model)) => {
-            println!("PROVIDER: {}", provider);
-            println!("MODEL: {}", model);
-        }
-        Err(e) => {
-            println!("ERROR: Failed to get provider info: {}", e);
-        }
-    }
-
     // Initialize rustyline editor with history
     let mut rl = DefaultEditor::new()?;
 
@@ -1639,69 +1917,103 @@ async fn run_interactive_machine(
 
                 // Check for control commands
                 if input.starts_with('/') {
-                    match input.as_str() {
-                        "/compact" => {
-                            println!("COMMAND: compact");
-                            match agent.force_summarize().await {
-                                Ok(true) => println!("RESULT: Summarization completed"),
-                                Ok(false) => println!("RESULT: Summarization failed"),
-                                Err(e) => println!("ERROR: {}", e),
+                    let command = input
+                        .trim_start_matches('/')
+                        .split_whitespace()
+                        .next()
+                        .unwrap_or_default()
+                        .to_string();
+                    let result: Result<String> = match input.as_str() {
+                        "/compact" => match agent.force_summarize().await {
+                            Ok(true) => Ok("Summarization completed".to_string()),
+                            Ok(false) => Err(anyhow::anyhow!("Summarization failed")),
+                            Err(e) => Err(e),
+                        },
+                        "/thinnify" => Ok(agent.force_thin()),
+                        "/skinnify" => Ok(agent.force_thin_all()),
+                        "/readme" => match agent.reload_readme() {
+                            Ok(true) => Ok("README content reloaded successfully".to_string()),
+                            Ok(false) => Err(anyhow::anyhow!(
+                                "No README was loaded at startup, cannot reload"
+                            )),
+                            Err(e) => Err(e),
+                        },
+                        "/stats" => Ok(agent.get_stats()),
+                        "/undo" => agent.undo_last_edit().map(|checkpoint| match checkpoint {
+                            Some(checkpoint) => {
+                                let mut lines = vec![format!("Undid {}", checkpoint.tool)];
+                                for file in &checkpoint.files {
+                                    lines.push(format!("Reverted {}", file.path.display()));
+                                }
+                                lines.join("\n")
                             }
-                            continue;
-                        }
-                        "/thinnify" => {
-                            println!("COMMAND: thinnify");
-                            let summary = agent.force_thin();
-                            println!("{}", summary);
-                            continue;
-                        }
-                        "/skinnify" => {
-                            println!("COMMAND: skinnify");
-                            let summary = agent.force_thin_all();
-                            println!("{}", summary);
-                            continue;
-                        }
-                        "/readme" => {
-                            println!("COMMAND: readme");
-                            match agent.reload_readme() {
-                                Ok(true) => {
-                                    println!("RESULT: README content reloaded successfully")
+                            None => "No edits to undo".to_string(),
+                        }),
+                        cmd if cmd.split_whitespace().next() == Some("/rewind") => {
+                            let (target, revert) = parse_rewind_args(&cmd["/rewind".len()..]);
+                            match target {
+                                None => {
+                                    let turns = agent.user_turns();
+                                    Ok(turns
+                                        .iter()
+                                        .enumerate()
+                                        .map(|(i, (id, task))| {
+                                            format!(
+                                                "{} {} {}",
+                                                turns.len() - i,
+                                                id,
+                                                turn_preview(task)
+                                            )
+                                        })
+                                        .collect::<Vec<_>>()
+                                        .join("\n"))
                                 }
-                                Ok(false) => println!(
-                                    "RESULT: No README was loaded at startup, cannot reload"
-                                ),
-                                Err(e) => println!("ERROR: {}", e),
+                                Some(target) => agent
+                                    .rewind(&target, revert)
+                                    .map(|summary| rewind_summary_text(&summary)),
                             }
-                            continue;
                         }
-                        "/stats" => {
-                            println!("COMMAND: stats");
-                            let stats = agent.get_stats();
-                            // Emit stats as structured data (name: value pairs)
-                            println!("{}", stats);
-                            continue;
-                        }
-                        "/help" => {
-                            println!("COMMAND: help");
-                            println!("AVAILABLE_COMMANDS: /compact /thinnify /skinnify /readme /stats /help");
-                            continue;
-                        }
-                        _ => {
-                            println!("ERROR: Unknown command: {}", input);
-                            continue;
+                        cmd if cmd.split_whitespace().next() == Some("/fork") => {
+                            let (target, revert) = parse_rewind_args(&cmd["/fork".len()..]);
+                            let fork_id = agent.fork_session();
+                            let forked = format!("Forked session {}", fork_id);
+                            match target {
+                                None => Ok(forked),
+                                Some(target) => agent.rewind(&target, revert).map(|summary| {
+                                    format!("{}\n{}", forked, rewind_summary_text(&summary))
+                                }),
+                            }
                         }
-                    }
+                        "/help" => Ok("/compact /thinnify /skinnify /readme /stats /rewind /fork /undo /help".to_string()),
+                        _ => Err(anyhow::anyhow!("Unknown command: {}", input)),
+                    };
+                    events.emit(match result {
+                        Ok(output) => MachineEvent::CommandResult {
+                            command,
+                            success: true,
+                            output,
+                        },
+                        Err(e) => MachineEvent::CommandResult {
+                            command,
+                            success: false,
+                            output: e.to_string(),
+                        },
+                    });
+                    continue;
                 }
 
                 // Execute task
-                println!("TASK_START");
-                execute_task_machine(&mut agent, &input, show_prompt, show_code).await;
-                println!("TASK_END");
+                events.emit(MachineEvent::TaskStart {
+                    task: input.clone(),
+                });
+                execute_task_machine(&mut agent, events, &input, show_prompt, show_code).await;
             }
             Err(ReadlineError::Interrupted) => continue,
             Err(ReadlineError::Eof) => break,
             Err(err) => {
-                println!("ERROR: {:?}", err);
+                events.emit(MachineEvent::Error {
+                    message: err.to_string(),
+                });
                 break;
             }
         }
@@ -1712,12 +2024,12 @@ async fn run_interactive_machine(
         let _ = rl.save_history(history_path);
     }
 
-    println!("INTERACTIVE_MODE_ENDED");
     Ok(())
 }
 
 async fn execute_task_machine(
     agent: &mut Agent<MachineUiWriter>,
+    events: &MachineEventWriter,
     input: &str,
     show_prompt: bool,
     show_code: bool,
@@ -1741,24 +2053,21 @@ async fn execute_task_machine(
             }
             _ = tokio::signal::ctrl_c() => {
                 cancel_token_clone.cancel();
-                println!("CANCELLED");
+                events.emit(MachineEvent::Cancelled);
                 return;
             }
         };
 
         match execution_result {
             Ok(result) => {
-                if attempt > 1 {
-                    println!("RETRY_SUCCESS: attempt {}", attempt);
-                }
-                println!("AGENT_RESPONSE:");
-                println!("{}", result.response);
-                println!("END_AGENT_RESPONSE");
+                events.emit(MachineEvent::TaskEnd {
+                    response: result.response,
+                });
                 return;
             }
             Err(e) => {
                 if e.to_string().contains("cancelled") {
-                    println!("CANCELLED");
+                    events.emit(MachineEvent::Cancelled);
                     return;
                 }
 
@@ -1774,10 +2083,12 @@ async fn execute_task_machine(
                     let delay_ms = 1000 * (2_u64.pow(attempt - 1));
                     let delay = std::time::Duration::from_millis(delay_ms);
 
-                    println!(
-                        "TIMEOUT: attempt {} of {}, retrying in {:?}",
-                        attempt, MAX_TIMEOUT_RETRIES, delay
-                    );
+                    events.emit(MachineEvent::Retry {
+                        attempt: attempt + 1,
+                        max_attempts: MAX_TIMEOUT_RETRIES,
+                        delay_ms,
+                        reason: e.to_string(),
+                    });
 
                     // Wait before retrying
                     tokio::time::sleep(delay).await;
@@ -1785,16 +2096,62 @@ async fn execute_task_machine(
                 }
 
                 // For non-timeout errors or after max retries
-                println!("ERROR: {}", e);
-                if attempt > 1 {
-                    println!("FAILED_AFTER_RETRIES: {}", attempt);
-                }
+                let message = if attempt > 1 {
+                    format!("{} (failed after {} attempts)", e, attempt)
+                } else {
+                    e.to_string()
+                };
+                events.emit(MachineEvent::Error { message });
                 return;
             }
         }
     }
 }
 
+/// Plain-text description of a rewind for machine-mode command results
+fn rewind_summary_text(summary: &g3_core::session::RewindSummary) -> String {
+    let mut lines = vec![format!("Removed {} messages", summary.removed_messages)];
+    for path in &summary.reverted_files {
+        lines.push(format!("Reverted {}", path.display()));
+    }
+    lines.join("\n")
+}
+
+/// Parse `/rewind` and `/fork` arguments: an optional turn count or message
+/// id, and a `--revert` flag
+fn parse_rewind_args(args: &str) -> (Option<g3_core::session::RewindTarget>, bool) {
+    let mut target = None;
+    let mut revert = false;
+    for arg in args.split_whitespace() {
+        if arg == "--revert" {
+            revert = true;
+        } else {
+            target = Some(g3_core::session::RewindTarget::parse(arg));
+        }
+    }
+    (target, revert)
+}
+
+/// First line of a task, shortened for turn listings
+fn turn_preview(task: &str) -> String {
+    let line = task.lines().next().unwrap_or_default();
+    if line.chars().count() > 60 {
+        format!("{}...", line.chars().take(57).collect::<String>())
+    } else {
+        line.to_string()
+    }
+}
+
+fn print_rewind_summary(output: &SimpleOutput, summary: &g3_core::session::RewindSummary) {
+    output.print(&format!(
+        "⏪ Removed {} messages from the conversation",
+        summary.removed_messages
+    ));
+    for path in &summary.reverted_files {
+        output.print(&format!("  ↩️ Restored {}", path.display()));
+    }
+}
+
 fn handle_execution_error(e: &anyhow::Error, input: &str, output: &SimpleOutput, attempt: u32) {
     // Enhanced error logging with detailed information
     error!("=== TASK EXECUTION ERROR ===");
@@ -2078,10 +2435,35 @@ async fn run_autonomous(
     let mut coach_feedback = String::new();
     let mut implementation_approved = false;
 
+    // Spend across the player and every coach; each agent enforces its share
+    let session_cost_limit = agent.get_config().budget.max_cost_usd;
+    let run_cost_limit = agent.get_config().budget.max_autonomous_cost_usd;
+    let mut coach_spent_usd = 0.0;
+    let mut budget_exceeded = false;
+
     loop {
         let turn_start_time = Instant::now();
         let turn_start_tokens = agent.get_context_window().used_tokens;
 
+        let run_spent_usd = coach_spent_usd + agent.cost().total_usd;
+        if let Some(limit) = run_cost_limit {
+            if run_spent_usd >= limit {
+                output.print("\n=== SESSION STOPPED - BUDGET EXCEEDED ===");
+                output.print(&format!(
+                    "💸 Autonomous run spent {} of its {} budget",
+                    format_usd(run_spent_usd),
+                    format_usd(limit)
+                ));
+                budget_exceeded = true;
+                break;
+            }
+        }
+        // The player may spend whatever the coaches have not
+        agent.set_cost_limit(tighter_cost_limit(
+            session_cost_limit,
+            run_cost_limit.map(|limit| limit - coach_spent_usd),
+        ));
+
         output.print(&format!(
             "\n=== TURN {}/{} - PLAYER MODE ===",
             turn, max_turns
@@ -2159,6 +2541,12 @@ async fn run_autonomous(
                     break;
                 }
                 Err(e) => {
+                    if e.downcast_ref::<BudgetExceeded>().is_some() {
+                        output.print(&format!("💸 Player stopped: {}", e));
+                        budget_exceeded = true;
+                        break;
+                    }
+
                     // Check if this is a context length exceeded error
                     use g3_core::error_handling::{classify_error, ErrorType, RecoverableError};
                     let error_type = classify_error(&e);
@@ -2249,6 +2637,11 @@ async fn run_autonomous(
             }
         }
 
+        if budget_exceeded {
+            output.print("\n=== SESSION STOPPED - BUDGET EXCEEDED ===");
+            break;
+        }
+
         // If player failed after max retries, increment turn and continue
         if player_failed {
             output.print(&format!(
@@ -2298,6 +2691,10 @@ async fn run_autonomous(
 
         // Surface provider info for coach agent
         coach_agent.print_provider_banner("Coach");
+        coach_agent.set_cost_limit(tighter_cost_limit(
+            session_cost_limit,
+            run_cost_limit.map(|limit| limit - coach_spent_usd - agent.cost().total_usd),
+        ));
 
         // Ensure coach agent is also in the workspace directory
         project.enter_workspace()?;
@@ -2374,6 +2771,13 @@ Remember: Be clear in your review and concise in your feedback. APPROVE iff the
                     break;
                 }
                 Err(e) => {
+                    if e.downcast_ref::<BudgetExceeded>().is_some() {
+                        output.print(&format!("💸 Coach stopped: {}", e));
+                        coach_result_opt = None;
+                        budget_exceeded = true;
+                        break;
+                    }
+
                     // Check if this is a context length exceeded error
                     use g3_core::error_handling::{classify_error, ErrorType, RecoverableError};
                     let error_type = classify_error(&e);
@@ -2466,6 +2870,12 @@ Remember: Be clear in your review and concise in your feedback. APPROVE iff the
             }
         }
 
+        coach_spent_usd += coach_agent.cost().total_usd;
+        if budget_exceeded {
+            output.print("\n=== SESSION STOPPED - BUDGET EXCEEDED ===");
+            break;
+        }
+
         output.print("🎓 Coach review completed");
 
         // If coach failed after max retries, increment turn and continue with default feedback
@@ -2581,6 +2991,8 @@ Remember: Be clear in your review and concise in your feedback. APPROVE iff the
         "📝 Final Status: {}",
         if implementation_approved {
             "✅ APPROVED"
+        } else if budget_exceeded {
+            "💸 BUDGET EXCEEDED"
         } else if turn >= max_turns {
             "⏰ MAX TURNS REACHED"
         } else {
@@ -2603,6 +3015,19 @@ Remember: Be clear in your review and concise in your feedback. APPROVE iff the
         context_window.percentage_used()
     ));
 
+    let run_spent_usd = coach_spent_usd + agent.cost().total_usd;
+    output.print("\n💰 Cost:");
+    output.print(&format!("   • Player: {}", format_usd(agent.cost().total_usd)));
+    output.print(&format!("   • Coaches: {}", format_usd(coach_spent_usd)));
+    match run_cost_limit {
+        Some(limit) => output.print(&format!(
+            "   • Total: {} of {} budget",
+            format_usd(run_spent_usd),
+            format_usd(limit)
+        )),
+        None => output.print(&format!("   • Total: {}", format_usd(run_spent_usd))),
+    }
+
     // Add per-turn histogram
     output.print(&generate_turn_histogram(&turn_metrics));
     output.print(&"=".repeat(60));
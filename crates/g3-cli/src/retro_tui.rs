@@ -50,6 +50,8 @@ pub enum TuiMessage {
         total: u32,
         percentage: f32,
     },
+    /// Session spend in US dollars
+    CostUpdate(f64),
     SSEReceived, // New message type for SSE events (including pings)
     Error(String),
     Exit,
@@ -89,6 +91,8 @@ struct TerminalState {
     status_line: String,
     /// Context window info
     context_info: (u32, u32, f32),
+    /// Session spend in US dollars
+    cost_usd: f64,
     /// Provider and model info
     provider_info: (String, String),
     /// Status blink state (for PROCESSING)
@@ -139,6 +143,7 @@ impl TerminalState {
             last_blink: Instant::now(),
             status_line: "READY".to_string(),
             context_info: (0, 0, 0.0),
+            cost_usd: 0.0,
             provider_info: ("UNKNOWN".to_string(), "UNKNOWN".to_string()),
             status_blink: true,
             last_status_blink: Instant::now(),
@@ -571,6 +576,9 @@ impl RetroTui {
                             
                             state.last_token_count = used;
                         }
+                        TuiMessage::CostUpdate(cost_usd) => {
+                            state.cost_usd = cost_usd;
+                        }
                         TuiMessage::SSEReceived => {
                             state.sse_count += 1;
                             
@@ -735,6 +743,7 @@ impl RetroTui {
                 status_bar_chunk,
                 &state.status_line,
                 state.context_info,
+                state.cost_usd,
                 &state.provider_info,
                 state.status_blink,
                 &state.theme,
@@ -1205,6 +1214,7 @@ impl RetroTui {
         area: Rect,
         status_line: &str,
         context_info: (u32, u32, f32),
+        cost_usd: f64,
         provider_info: &(String, String),
         status_blink: bool,
         theme: &ColorTheme,
@@ -1259,6 +1269,18 @@ impl RetroTui {
                     .fg(theme.terminal_amber.to_color())
                     .add_modifier(Modifier::BOLD),
             ),
+            Span::styled(
+                " | COST: ",
+                Style::default()
+                    .fg(theme.terminal_amber.to_color())
+                    .add_modifier(Modifier::BOLD),
+            ),
+            Span::styled(
+                g3_core::cost::format_usd(cost_usd),
+                Style::default()
+                    .fg(theme.terminal_amber.to_color())
+                    .add_modifier(Modifier::BOLD),
+            ),
             Span::styled(
                 " | ",
                 Style::default()
@@ -1328,6 +1350,11 @@ impl RetroTui {
         });
     }
 
+    /// Update the session's running cost
+    pub fn update_cost(&self, cost_usd: f64) {
+        let _ = self.tx.send(TuiMessage::CostUpdate(cost_usd));
+    }
+
     /// Update provider and model info
     pub fn update_provider_info(&self, provider: &str, model: &str) {
         if let Ok(mut state) = self.state.lock() {
@@ -0,0 +1,466 @@
+//! JSON-RPC 2.0 over stdin/stdout (`--serve-stdio`)
+//!
+//! Keeps one `Agent` alive for an editor plugin. Each message is a single
+//! line of JSON. The client calls methods such as `prompt`, `cancel`,
+//! `get_todo` and `get_stats`; the server streams the machine-mode events
+//! of a running prompt as `event` notifications. When the agent needs an
+//! answer (`prompt_user_yes_no`, `prompt_user_choice`) the server sends a
+//! `prompt_user` request and waits for the client's response.
+
+use anyhow::Result;
+use chrono::Utc;
+use g3_core::machine_events::{MachineEvent, MachineEventLine, MachineEventSink, PROTOCOL_VERSION};
+use g3_core::machine_ui_writer::MachineUiWriter;
+use g3_core::Agent;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::io::{self, BufRead, Write};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{mpsc, Arc, Mutex};
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender};
+use tokio_util::sync::CancellationToken;
+
+const PARSE_ERROR: i64 = -32700;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+/// The agent failed, or is busy with another prompt
+const SERVER_ERROR: i64 = -32000;
+/// The prompt was cancelled by the client
+const REQUEST_CANCELLED: i64 = -32800;
+
+/// A request or notification from the client
+#[derive(Debug, Deserialize)]
+pub struct RpcRequest {
+    /// Absent for notifications, which get no response
+    #[serde(default)]
+    pub id: Option<Value>,
+    pub method: String,
+    #[serde(default)]
+    pub params: Value,
+}
+
+#[derive(Debug)]
+struct RpcError {
+    code: i64,
+    message: String,
+}
+
+impl RpcError {
+    fn new(code: i64, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+        }
+    }
+}
+
+impl From<anyhow::Error> for RpcError {
+    fn from(e: anyhow::Error) -> Self {
+        Self::new(SERVER_ERROR, e.to_string())
+    }
+}
+
+/// The connection to the client: serializes outgoing messages and matches
+/// responses to the `prompt_user` requests the server sent
+pub struct RpcPeer {
+    out: Mutex<Box<dyn Write + Send>>,
+    event_seq: AtomicU64,
+    next_prompt: AtomicU64,
+    pending_prompts: Mutex<HashMap<String, mpsc::Sender<usize>>>,
+    /// Set once stdin is closed; no answer can arrive after that
+    closed: AtomicBool,
+}
+
+impl RpcPeer {
+    pub fn new(out: Box<dyn Write + Send>) -> Self {
+        Self {
+            out: Mutex::new(out),
+            event_seq: AtomicU64::new(0),
+            next_prompt: AtomicU64::new(0),
+            pending_prompts: Mutex::new(HashMap::new()),
+            closed: AtomicBool::new(false),
+        }
+    }
+
+    pub fn stdout() -> Self {
+        Self::new(Box::new(io::stdout()))
+    }
+
+    fn send(&self, message: Value) {
+        let mut out = self.out.lock().unwrap();
+        let _ = writeln!(out, "{}", message);
+        let _ = out.flush();
+    }
+
+    fn respond(&self, id: Option<Value>, result: std::result::Result<Value, RpcError>) {
+        // Notifications get no response
+        let Some(id) = id else { return };
+        let message = match result {
+            Ok(result) => json!({"jsonrpc": "2.0", "id": id, "result": result}),
+            Err(e) => json!({
+                "jsonrpc": "2.0",
+                "id": id,
+                "error": {"code": e.code, "message": e.message},
+            }),
+        };
+        self.send(message);
+    }
+
+    /// Handle one line from the client. Requests go to `requests`; responses
+    /// to `prompt_user` are delivered to the waiting agent directly, because
+    /// the agent blocks the request loop while it waits.
+    fn route_line(&self, line: &str, requests: &UnboundedSender<RpcRequest>) {
+        let message: Value = match serde_json::from_str(line) {
+            Ok(message) => message,
+            Err(e) => {
+                let error = RpcError::new(PARSE_ERROR, format!("Parse error: {}", e));
+                self.respond(Some(Value::Null), Err(error));
+                return;
+            }
+        };
+
+        if message.get("method").is_none() {
+            let id = message.get("id").map(|id| match id {
+                Value::String(s) => s.clone(),
+                other => other.to_string(),
+            });
+            let answer = message
+                .get("result")
+                .and_then(|result| result.get("answer"))
+                .and_then(Value::as_u64);
+            if let Some(sender) = id.and_then(|id| self.pending_prompts.lock().unwrap().remove(&id))
+            {
+                // An error response or missing answer drops the sender, which
+                // declines the prompt
+                if let Some(answer) = answer {
+                    let _ = sender.send(answer as usize);
+                }
+            }
+            return;
+        }
+
+        match serde_json::from_value::<RpcRequest>(message) {
+            Ok(request) => {
+                // The request loop cannot see a cancel while the agent is
+                // blocked in a prompt, so release the agent here
+                if matches!(request.method.as_str(), "cancel" | "shutdown") {
+                    self.decline_pending_prompts();
+                }
+                let _ = requests.send(request);
+            }
+            Err(e) => {
+                let error = RpcError::new(INVALID_PARAMS, format!("Invalid request: {}", e));
+                self.respond(Some(Value::Null), Err(error));
+            }
+        }
+    }
+
+    /// Read stdin on its own thread so prompt answers arrive while the agent
+    /// is blocked waiting for them
+    fn spawn_stdin_reader(self: &Arc<Self>) -> UnboundedReceiver<RpcRequest> {
+        let (tx, rx) = unbounded_channel();
+        let peer = self.clone();
+        std::thread::spawn(move || {
+            for line in io::stdin().lock().lines() {
+                let Ok(line) = line else { break };
+                if !line.trim().is_empty() {
+                    peer.route_line(&line, &tx);
+                }
+            }
+            peer.close();
+        });
+        rx
+    }
+
+    /// The client is gone; release any agent waiting for an answer and
+    /// decline prompts from now on
+    fn close(&self) {
+        let mut pending = self.pending_prompts.lock().unwrap();
+        self.closed.store(true, Ordering::SeqCst);
+        pending.clear();
+    }
+
+    /// Decline the prompts the agent is waiting on, without closing
+    fn decline_pending_prompts(&self) {
+        self.pending_prompts.lock().unwrap().clear();
+    }
+}
+
+impl MachineEventSink for RpcPeer {
+    fn emit(&self, event: MachineEvent) {
+        let line = MachineEventLine {
+            v: PROTOCOL_VERSION,
+            seq: self.event_seq.fetch_add(1, Ordering::SeqCst),
+            ts: Utc::now(),
+            event,
+        };
+        self.send(json!({"jsonrpc": "2.0", "method": "event", "params": line}));
+    }
+
+    /// Ask the client and wait for its answer. If the client declines or
+    /// disconnects, the last option is chosen ("no" for yes/no prompts).
+    fn prompt(&self, message: &str, options: &[&str]) -> usize {
+        let id = format!("prompt-{}", self.next_prompt.fetch_add(1, Ordering::SeqCst));
+        let decline = options.len().saturating_sub(1);
+        let (tx, rx) = mpsc::channel();
+        {
+            let mut pending = self.pending_prompts.lock().unwrap();
+            if self.closed.load(Ordering::SeqCst) {
+                return decline;
+            }
+            pending.insert(id.clone(), tx);
+        }
+        self.send(json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "method": "prompt_user",
+            "params": {"message": message, "options": options},
+        }));
+
+        match tokio::task::block_in_place(|| rx.recv()) {
+            Ok(answer) if answer < options.len() => answer,
+            _ => decline,
+        }
+    }
+
+    fn answers_prompts(&self) -> bool {
+        true
+    }
+}
+
+/// Serve JSON-RPC requests until the client sends `shutdown` or closes stdin
+pub async fn serve_stdio(mut agent: Agent<MachineUiWriter>, peer: Arc<RpcPeer>) -> Result<()> {
+    let mut requests = peer.spawn_stdin_reader();
+
+    while let Some(request) = requests.recv().await {
+        match request.method.as_str() {
+            "prompt" => {
+                if !run_prompt(&mut agent, &peer, request, &mut requests).await {
+                    break;
+                }
+            }
+            "shutdown" => {
+                peer.respond(request.id, Ok(Value::Null));
+                break;
+            }
+            _ => {
+                let result = handle_request(&mut agent, &request).await;
+                peer.respond(request.id, result);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Requests that can be answered while no prompt is running
+async fn handle_request(
+    agent: &mut Agent<MachineUiWriter>,
+    request: &RpcRequest,
+) -> std::result::Result<Value, RpcError> {
+    match request.method.as_str() {
+        "initialize" => {
+            let (provider, model) = agent.get_provider_info()?;
+            Ok(json!({
+                "protocol_version": PROTOCOL_VERSION,
+                "provider": provider,
+                "model": model,
+                "session_id": agent.get_session_id(),
+            }))
+        }
+        "cancel" => Ok(json!({"cancelled": false})),
+        "get_todo" => Ok(json!({"todo": agent.get_todo().await})),
+        "get_stats" => Ok(json!({"stats": agent.get_stats()})),
+        "compact" => Ok(json!({"success": agent.force_summarize().await?})),
+        "thinnify" => Ok(json!({"summary": agent.force_thin()})),
+        "skinnify" => Ok(json!({"summary": agent.force_thin_all()})),
+        method => Err(RpcError::new(
+            METHOD_NOT_FOUND,
+            format!("Method not found: {}", method),
+        )),
+    }
+}
+
+/// Run a `prompt` request to completion, answering `cancel` in the meantime.
+/// Returns false if the server should stop.
+async fn run_prompt(
+    agent: &mut Agent<MachineUiWriter>,
+    peer: &RpcPeer,
+    request: RpcRequest,
+    requests: &mut UnboundedReceiver<RpcRequest>,
+) -> bool {
+    let Some(text) = request.params.get("text").and_then(Value::as_str) else {
+        let error = RpcError::new(INVALID_PARAMS, "prompt requires a string `text` parameter");
+        peer.respond(request.id, Err(error));
+        return true;
+    };
+
+    let cancellation_token = CancellationToken::new();
+    let mut keep_serving = true;
+    let mut client_gone = false;
+
+    let task = agent.execute_task_with_timing_cancellable(
+        text,
+        None,
+        false,
+        false,
+        false,
+        true,
+        cancellation_token.clone(),
+        None,
+    );
+    tokio::pin!(task);
+
+    let result = loop {
+        tokio::select! {
+            result = &mut task => break result,
+            next = requests.recv(), if !client_gone => match next {
+                Some(other) if other.method == "cancel" => {
+                    cancellation_token.cancel();
+                    peer.decline_pending_prompts();
+                    peer.respond(other.id, Ok(json!({"cancelled": true})));
+                }
+                Some(other) if other.method == "shutdown" => {
+                    cancellation_token.cancel();
+                    peer.decline_pending_prompts();
+                    keep_serving = false;
+                    peer.respond(other.id, Ok(Value::Null));
+                }
+                Some(other) => {
+                    let error = RpcError::new(SERVER_ERROR, "Agent is busy with a prompt");
+                    peer.respond(other.id, Err(error));
+                }
+                None => {
+                    cancellation_token.cancel();
+                    client_gone = true;
+                    keep_serving = false;
+                }
+            },
+        }
+    };
+
+    let result = match result {
+        Ok(result) => Ok(json!({"response": result.response})),
+        Err(_) if cancellation_token.is_cancelled() => {
+            Err(RpcError::new(REQUEST_CANCELLED, "Prompt cancelled"))
+        }
+        Err(e) => Err(e.into()),
+    };
+    peer.respond(request.id, result);
+    keep_serving
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Clone, Default)]
+    struct SharedBuffer(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for SharedBuffer {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl SharedBuffer {
+        fn messages(&self) -> Vec<Value> {
+            let bytes = self.0.lock().unwrap().clone();
+            String::from_utf8(bytes)
+                .unwrap()
+                .lines()
+                .map(|line| serde_json::from_str(line).unwrap())
+                .collect()
+        }
+    }
+
+    #[test]
+    fn test_requests_and_prompt_answers_are_routed() {
+        let buffer = SharedBuffer::default();
+        let peer = Arc::new(RpcPeer::new(Box::new(buffer.clone())));
+        let (tx, mut rx) = unbounded_channel();
+
+        peer.route_line(r#"{"jsonrpc":"2.0","id":1,"method":"get_stats"}"#, &tx);
+        let request = rx.try_recv().unwrap();
+        assert_eq!(request.method, "get_stats");
+        assert_eq!(request.id, Some(json!(1)));
+
+        // The agent blocks in prompt() until the client answers
+        let asker = peer.clone();
+        let answer = std::thread::spawn(move || asker.prompt("Run rm?", &["yes", "no"]));
+        let prompt_request = loop {
+            if let Some(message) = buffer.messages().pop() {
+                break message;
+            }
+            std::thread::yield_now();
+        };
+        assert_eq!(prompt_request["method"], "prompt_user");
+        assert_eq!(prompt_request["params"]["options"], json!(["yes", "no"]));
+
+        let response =
+            json!({"jsonrpc": "2.0", "id": prompt_request["id"], "result": {"answer": 0}});
+        peer.route_line(&response.to_string(), &tx);
+        assert_eq!(answer.join().unwrap(), 0);
+        assert!(rx.try_recv().is_err());
+    }
+
+    #[test]
+    fn test_declined_prompt_picks_last_option() {
+        let buffer = SharedBuffer::default();
+        let peer = Arc::new(RpcPeer::new(Box::new(buffer.clone())));
+        let (tx, _rx) = unbounded_channel();
+
+        let asker = peer.clone();
+        let answer = std::thread::spawn(move || asker.prompt("Allow?", &["yes", "no"]));
+        while buffer.messages().is_empty() {
+            std::thread::yield_now();
+        }
+        let error =
+            json!({"jsonrpc": "2.0", "id": "prompt-0", "error": {"code": 1, "message": "no"}});
+        peer.route_line(&error.to_string(), &tx);
+        assert_eq!(answer.join().unwrap(), 1);
+
+        peer.route_line("not json", &tx);
+        assert_eq!(
+            buffer.messages().last().unwrap()["error"]["code"],
+            PARSE_ERROR
+        );
+
+        // Once stdin is closed, prompts are declined without asking
+        let sent = buffer.messages().len();
+        peer.close();
+        assert_eq!(peer.prompt("Allow?", &["yes", "no"]), 1);
+        assert_eq!(buffer.messages().len(), sent);
+    }
+
+    #[test]
+    fn test_cancel_declines_pending_prompt() {
+        let buffer = SharedBuffer::default();
+        let peer = Arc::new(RpcPeer::new(Box::new(buffer.clone())));
+        let (tx, mut rx) = unbounded_channel();
+
+        let asker = peer.clone();
+        let answer = std::thread::spawn(move || asker.prompt("Allow?", &["yes", "no"]));
+        while buffer.messages().is_empty() {
+            std::thread::yield_now();
+        }
+        peer.route_line(r#"{"jsonrpc":"2.0","id":2,"method":"cancel"}"#, &tx);
+        assert_eq!(answer.join().unwrap(), 1);
+        assert_eq!(rx.try_recv().unwrap().method, "cancel");
+
+        // Unlike a closed connection, later prompts are still asked
+        let asker = peer.clone();
+        let answer = std::thread::spawn(move || asker.prompt("Allow?", &["yes", "no"]));
+        while buffer.messages().len() < 2 {
+            std::thread::yield_now();
+        }
+        let response = json!({"jsonrpc": "2.0", "id": "prompt-1", "result": {"answer": 0}});
+        peer.route_line(&response.to_string(), &tx);
+        assert_eq!(answer.join().unwrap(), 0);
+    }
+}
@@ -276,6 +276,10 @@ impl UiWriter for ConsoleUiWriter {
         let _ = io::stdout().flush();
     }
 
+    fn is_interactive(&self) -> bool {
+        true
+    }
+
     fn prompt_user_yes_no(&self, message: &str) -> bool {
         print!("{} [y/N] ", message);
         let _ = io::stdout().flush();
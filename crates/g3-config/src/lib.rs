@@ -11,6 +11,19 @@ pub struct Config {
     pub computer_control: ComputerControlConfig,
     pub webdriver: WebDriverConfig,
     pub macax: MacAxConfig,
+    #[serde(default)]
+    pub mcp: McpConfig,
+    #[serde(default)]
+    pub permissions: PermissionsConfig,
+    #[serde(default)]
+    pub execution: ExecutionConfig,
+    /// Prices per million tokens, keyed by model name or provider (`anthropic.default`)
+    #[serde(default)]
+    pub pricing: HashMap<String, ModelPricing>,
+    #[serde(default)]
+    pub budget: BudgetConfig,
+    #[serde(default)]
+    pub semantic_index: SemanticIndexConfig,
 }
 
 /// Provider configuration with named configs per provider type
@@ -19,14 +32,18 @@ pub struct ProvidersConfig {
     /// Default provider in format "<provider_type>.<config_name>"
     pub default_provider: String,
     
+    /// Providers to fail over to, in order, when default_provider keeps failing
+    #[serde(default)]
+    pub fallbacks: Vec<String>,
+
     /// Provider for planner mode (optional, falls back to default_provider)
-    pub planner: Option<String>,
+    pub planner: Option<ProviderChain>,
     
     /// Provider for coach in autonomous mode (optional, falls back to default_provider)
-    pub coach: Option<String>,
+    pub coach: Option<ProviderChain>,
     
     /// Provider for player in autonomous mode (optional, falls back to default_provider)
-    pub player: Option<String>,
+    pub player: Option<ProviderChain>,
     
     /// Named Anthropic provider configs
     #[serde(default)]
@@ -51,6 +68,18 @@ pub struct ProvidersConfig {
     /// Named OpenRouter provider configs
     #[serde(default)]
     pub openrouter: HashMap<String, OpenRouterConfig>,
+
+    /// Named Ollama / llama-server provider configs
+    #[serde(default)]
+    pub ollama: HashMap<String, OllamaConfig>,
+
+    /// Named Google Gemini provider configs
+    #[serde(default)]
+    pub gemini: HashMap<String, GeminiConfig>,
+
+    /// Named AWS Bedrock provider configs
+    #[serde(default)]
+    pub bedrock: HashMap<String, BedrockConfig>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -72,6 +101,58 @@ pub struct OpenRouterConfig {
     pub provider_preferences: Option<ProviderPreferencesConfig>,
     pub http_referer: Option<String>,
     pub x_title: Option<String>,
+    /// Prompt caching for Anthropic and Gemini models: ephemeral, 5minute or 1hour
+    pub cache_config: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BedrockConfig {
+    /// Bedrock model or inference profile id
+    pub model: String,
+    /// Defaults to AWS_REGION, then the profile's region
+    pub region: Option<String>,
+    /// Profile in ~/.aws/credentials; environment credentials are used when unset
+    pub profile: Option<String>,
+    /// Overrides https://bedrock-runtime.<region>.amazonaws.com
+    pub endpoint_url: Option<String>,
+    pub max_tokens: Option<u32>,
+    pub temperature: Option<f32>,
+    pub cache_config: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GeminiConfig {
+    pub api_key: String,
+    pub model: String,
+    pub base_url: Option<String>,
+    pub max_tokens: Option<u32>,
+    pub temperature: Option<f32>,
+    /// Defaults to the 1M-token window of the Gemini 2.5 models
+    pub context_length: Option<u32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OllamaConfig {
+    pub model: String,
+    /// Defaults to http://localhost:11434
+    pub base_url: Option<String>,
+    #[serde(default)]
+    pub server: OllamaServerKind,
+    pub max_tokens: Option<u32>,
+    pub temperature: Option<f32>,
+    /// Asked from the server when unset
+    pub context_length: Option<u32>,
+    /// Asked from the server when unset
+    pub native_tool_calling: Option<bool>,
+}
+
+/// Which local model server an Ollama provider talks to
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "kebab-case")]
+pub enum OllamaServerKind {
+    #[default]
+    Ollama,
+    LlamaServer,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -81,10 +162,47 @@ pub struct ProviderPreferencesConfig {
     pub require_parameters: Option<bool>,
 }
 
+/// A provider reference for a role, optionally followed by fallbacks
+///
+/// Accepts either `player = "anthropic.default"` or
+/// `player = ["anthropic.default", "openrouter.backup"]`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum ProviderChain {
+    Single(String),
+    Chain(Vec<String>),
+}
+
+impl ProviderChain {
+    /// The provider tried first
+    pub fn primary(&self) -> &str {
+        match self {
+            ProviderChain::Single(provider) => provider,
+            ProviderChain::Chain(providers) => providers.first().map(String::as_str).unwrap_or(""),
+        }
+    }
+
+    /// The providers to fail over to, in order
+    pub fn fallbacks(&self) -> &[String] {
+        match self {
+            ProviderChain::Single(_) => &[],
+            ProviderChain::Chain(providers) => providers.get(1..).unwrap_or(&[]),
+        }
+    }
+}
+
+impl From<&str> for ProviderChain {
+    fn from(provider: &str) -> Self {
+        ProviderChain::Single(provider.to_string())
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AnthropicConfig {
     pub api_key: String,
     pub model: String,
+    /// Defaults to https://api.anthropic.com/v1
+    pub base_url: Option<String>,
     pub max_tokens: Option<u32>,
     pub temperature: Option<f32>,
     pub cache_config: Option<String>,
@@ -125,12 +243,51 @@ pub struct AgentConfig {
     pub autonomous_max_retry_attempts: u32,
     #[serde(default = "default_check_todo_staleness")]
     pub check_todo_staleness: bool,
+    /// Recoverable errors in a row before switching to the next fallback provider
+    #[serde(default = "default_provider_failover_after")]
+    pub provider_failover_after: u32,
+    /// Download tiktoken rank files for OpenAI models that are not cached yet
+    #[serde(default)]
+    pub download_tokenizers: bool,
 }
 
 fn default_check_todo_staleness() -> bool {
     true
 }
 
+fn default_provider_failover_after() -> u32 {
+    2
+}
+
+/// Prices in US dollars per million tokens
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ModelPricing {
+    pub input_per_mtok: f64,
+    pub output_per_mtok: f64,
+    /// Price of input tokens read from the prompt cache (defaults to `input_per_mtok`)
+    pub cached_input_per_mtok: Option<f64>,
+    /// Price of input tokens written to the prompt cache (defaults to `input_per_mtok`)
+    pub cache_write_per_mtok: Option<f64>,
+}
+
+/// Spending limits in US dollars
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BudgetConfig {
+    /// Stop a session once it has spent this much
+    pub max_cost_usd: Option<f64>,
+    /// Stop an autonomous coach/player run once the player and coaches together have spent this much
+    pub max_autonomous_cost_usd: Option<f64>,
+}
+
+/// Local embedding index behind the `semantic_search` tool
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SemanticIndexConfig {
+    /// GGUF embedding model; the tool is disabled while this is unset
+    pub embedding_model: Option<String>,
+    pub gpu_layers: Option<u32>,
+    pub threads: Option<u32>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ComputerControlConfig {
     pub enabled: bool,
@@ -167,6 +324,198 @@ pub struct MacAxConfig {
     pub enabled: bool,
 }
 
+/// Model Context Protocol (MCP) configuration
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct McpConfig {
+    /// Named MCP servers whose tools are exposed to the agent
+    #[serde(default)]
+    pub servers: HashMap<String, McpServerConfig>,
+}
+
+/// A single MCP server, reached either over stdio (`command`) or streamable HTTP (`url`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct McpServerConfig {
+    /// Executable to spawn for the stdio transport
+    pub command: Option<String>,
+    #[serde(default)]
+    pub args: Vec<String>,
+    /// Extra environment variables for the spawned server
+    #[serde(default)]
+    pub env: HashMap<String, String>,
+    /// Endpoint for the streamable HTTP transport
+    pub url: Option<String>,
+    /// Extra HTTP headers (e.g. Authorization) for the HTTP transport
+    #[serde(default)]
+    pub headers: HashMap<String, String>,
+    #[serde(default = "default_mcp_enabled")]
+    pub enabled: bool,
+    /// Per-request timeout in seconds
+    pub timeout_seconds: Option<u64>,
+}
+
+fn default_mcp_enabled() -> bool {
+    true
+}
+
+/// Outcome of a permission check for a tool call
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum PermissionAction {
+    #[default]
+    Allow,
+    Deny,
+    /// Ask the user (interactive mode only)
+    Prompt,
+}
+
+/// Tool permission policy
+///
+/// Deny rules win over prompt rules, which win over allow rules. Calls that
+/// match no rule fall back to `default`. When nobody can answer a prompt
+/// (`--machine` or autonomous mode), `unattended` decides instead.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PermissionsConfig {
+    /// Action for calls that match no rule
+    #[serde(default)]
+    pub default: PermissionAction,
+    /// Replaces `prompt` when running without a user (allow or deny)
+    #[serde(default)]
+    pub unattended: PermissionAction,
+    /// Skip the default for tools that cannot modify anything (read_file, code_search, ...)
+    #[serde(default = "default_auto_allow_read_only")]
+    pub auto_allow_read_only: bool,
+    #[serde(default)]
+    pub rules: Vec<PermissionRule>,
+}
+
+/// A single allow/deny/prompt rule
+///
+/// The rule applies when `tool` matches the tool name and the call's subject
+/// (the shell command, the file path, or the JSON arguments for other tools)
+/// matches `pattern` or `regex`. A rule with neither matches every call.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PermissionRule {
+    /// Tool name, may contain `*` (e.g. "webdriver_*")
+    pub tool: String,
+    pub action: PermissionAction,
+    /// Glob where `*` matches any characters (including `/`) and `?` matches one.
+    /// `${workspace}` expands to the workspace directory and `~` to the home directory.
+    pub pattern: Option<String>,
+    pub regex: Option<String>,
+}
+
+fn default_auto_allow_read_only() -> bool {
+    true
+}
+
+impl Default for PermissionsConfig {
+    fn default() -> Self {
+        Self {
+            default: PermissionAction::Allow,
+            unattended: PermissionAction::Allow,
+            auto_allow_read_only: true,
+            rules: Vec::new(),
+        }
+    }
+}
+
+/// Where shell, Python and JavaScript commands run
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum ExecutionBackendKind {
+    /// On the host, with the agent's privileges
+    #[default]
+    Direct,
+    /// bubblewrap sandbox: only the workspace is writable, no network
+    Sandbox,
+    /// Rootless container with the workspace mounted
+    Container,
+}
+
+/// Execution backend selection
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct ExecutionConfig {
+    /// Backend for interactive and single-task runs
+    #[serde(default)]
+    pub backend: ExecutionBackendKind,
+    /// Backend for coach/player loops (falls back to `backend`)
+    pub autonomous_backend: Option<ExecutionBackendKind>,
+    #[serde(default)]
+    pub sandbox: SandboxConfig,
+    #[serde(default)]
+    pub container: ContainerConfig,
+}
+
+impl ExecutionConfig {
+    /// Backend to use for the given mode
+    pub fn backend_for(&self, is_autonomous: bool) -> ExecutionBackendKind {
+        if is_autonomous {
+            self.autonomous_backend.unwrap_or(self.backend)
+        } else {
+            self.backend
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SandboxConfig {
+    /// bubblewrap executable
+    #[serde(default = "default_sandbox_program")]
+    pub program: String,
+    #[serde(default)]
+    pub allow_network: bool,
+    /// Extra host paths that stay writable (e.g. "~/.cargo")
+    #[serde(default)]
+    pub writable_paths: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ContainerConfig {
+    /// "podman" or "docker"
+    #[serde(default = "default_container_runtime")]
+    pub runtime: String,
+    #[serde(default = "default_container_image")]
+    pub image: String,
+    #[serde(default)]
+    pub allow_network: bool,
+    /// Extra arguments for `<runtime> run`
+    #[serde(default)]
+    pub extra_args: Vec<String>,
+}
+
+fn default_sandbox_program() -> String {
+    "bwrap".to_string()
+}
+
+fn default_container_runtime() -> String {
+    "podman".to_string()
+}
+
+fn default_container_image() -> String {
+    "docker.io/library/rust:latest".to_string()
+}
+
+impl Default for SandboxConfig {
+    fn default() -> Self {
+        Self {
+            program: default_sandbox_program(),
+            allow_network: false,
+            writable_paths: Vec::new(),
+        }
+    }
+}
+
+impl Default for ContainerConfig {
+    fn default() -> Self {
+        Self {
+            runtime: default_container_runtime(),
+            image: default_container_image(),
+            allow_network: false,
+            extra_args: Vec::new(),
+        }
+    }
+}
+
 impl Default for MacAxConfig {
     fn default() -> Self {
         Self { enabled: false }
@@ -213,6 +562,7 @@ impl Default for Config {
         Self {
             providers: ProvidersConfig {
                 default_provider: "databricks.default".to_string(),
+                fallbacks: Vec::new(),
                 planner: None,
                 coach: None,
                 player: None,
@@ -222,6 +572,9 @@ impl Default for Config {
                 embedded: HashMap::new(),
                 openai_compatible: HashMap::new(),
                 openrouter: HashMap::new(),
+                ollama: HashMap::new(),
+                gemini: HashMap::new(),
+                bedrock: HashMap::new(),
             },
             agent: AgentConfig {
                 max_context_length: None,
@@ -233,10 +586,18 @@ impl Default for Config {
                 max_retry_attempts: 3,
                 autonomous_max_retry_attempts: 6,
                 check_todo_staleness: true,
+                provider_failover_after: 2,
+                download_tokenizers: false,
             },
             computer_control: ComputerControlConfig::default(),
             webdriver: WebDriverConfig::default(),
             macax: MacAxConfig::default(),
+            mcp: McpConfig::default(),
+            permissions: PermissionsConfig::default(),
+            execution: ExecutionConfig::default(),
+            pricing: HashMap::new(),
+            budget: BudgetConfig::default(),
+            semantic_index: SemanticIndexConfig::default(),
         }
     }
 }
@@ -340,6 +701,8 @@ impl Config {
             
             // Validate the default_provider format
             config.validate_provider_reference(&config.providers.default_provider)?;
+            config.validate_fallbacks()?;
+            config.validate_mcp_servers()?;
             
             return Ok(config);
         }
@@ -447,11 +810,38 @@ impl Config {
                     );
                 }
             }
+            "ollama" => {
+                if !self.providers.ollama.contains_key(config_name) {
+                    anyhow::bail!(
+                        "Provider config 'ollama.{}' not found. Available: {:?}",
+                        config_name,
+                        self.providers.ollama.keys().collect::<Vec<_>>()
+                    );
+                }
+            }
+            "gemini" => {
+                if !self.providers.gemini.contains_key(config_name) {
+                    anyhow::bail!(
+                        "Provider config 'gemini.{}' not found. Available: {:?}",
+                        config_name,
+                        self.providers.gemini.keys().collect::<Vec<_>>()
+                    );
+                }
+            }
+            "bedrock" => {
+                if !self.providers.bedrock.contains_key(config_name) {
+                    anyhow::bail!(
+                        "Provider config 'bedrock.{}' not found. Available: {:?}",
+                        config_name,
+                        self.providers.bedrock.keys().collect::<Vec<_>>()
+                    );
+                }
+            }
             _ => {
                 // Check openai_compatible providers
                 if !self.providers.openai_compatible.contains_key(provider_type) {
                     anyhow::bail!(
-                        "Unknown provider type '{}'. Valid types: anthropic, openai, databricks, embedded, openrouter, or openai_compatible names",
+                        "Unknown provider type '{}'. Valid types: anthropic, openai, databricks, embedded, openrouter, ollama, gemini, bedrock, or openai_compatible names",
                         provider_type
                     );
                 }
@@ -461,6 +851,40 @@ impl Config {
         Ok(())
     }
 
+    /// Validate fallback providers, including those listed for roles.
+    /// Role providers themselves are checked when the role's config is built.
+    fn validate_fallbacks(&self) -> Result<()> {
+        let roles = [
+            &self.providers.planner,
+            &self.providers.coach,
+            &self.providers.player,
+        ];
+        let role_fallbacks = roles.into_iter().flatten().flat_map(|chain| chain.fallbacks());
+
+        for fallback in self.providers.fallbacks.iter().chain(role_fallbacks) {
+            self.validate_provider_reference(fallback)?;
+        }
+        Ok(())
+    }
+
+    /// Validate that each MCP server specifies exactly one transport
+    fn validate_mcp_servers(&self) -> Result<()> {
+        for (name, server) in &self.mcp.servers {
+            match (&server.command, &server.url) {
+                (Some(_), None) | (None, Some(_)) => {}
+                (Some(_), Some(_)) => anyhow::bail!(
+                    "MCP server '{}' sets both 'command' and 'url'; choose one transport",
+                    name
+                ),
+                (None, None) => anyhow::bail!(
+                    "MCP server '{}' must set either 'command' (stdio) or 'url' (HTTP)",
+                    name
+                ),
+            }
+        }
+        Ok(())
+    }
+
     /// Parse a provider reference into (provider_type, config_name)
     pub fn parse_provider_reference(reference: &str) -> Result<(String, String)> {
         let parts: Vec<&str> = reference.split('.').collect();
@@ -540,6 +964,36 @@ impl Config {
                         ));
                     }
                 }
+                "ollama" => {
+                    if let Some(ref mut ollama_config) = config.providers.ollama.get_mut(&config_name) {
+                        ollama_config.model = model;
+                    } else {
+                        return Err(anyhow::anyhow!(
+                            "Provider config 'ollama.{}' not found.",
+                            config_name
+                        ));
+                    }
+                }
+                "gemini" => {
+                    if let Some(ref mut gemini_config) = config.providers.gemini.get_mut(&config_name) {
+                        gemini_config.model = model;
+                    } else {
+                        return Err(anyhow::anyhow!(
+                            "Provider config 'gemini.{}' not found.",
+                            config_name
+                        ));
+                    }
+                }
+                "bedrock" => {
+                    if let Some(ref mut bedrock_config) = config.providers.bedrock.get_mut(&config_name) {
+                        bedrock_config.model = model;
+                    } else {
+                        return Err(anyhow::anyhow!(
+                            "Provider config 'bedrock.{}' not found.",
+                            config_name
+                        ));
+                    }
+                }
                 _ => {
                     // Check openai_compatible
                     if let Some(ref mut compat_config) = config.providers.openai_compatible.get_mut(&provider_type) {
@@ -561,7 +1015,8 @@ impl Config {
     pub fn get_planner_provider(&self) -> &str {
         self.providers
             .planner
-            .as_deref()
+            .as_ref()
+            .map(ProviderChain::primary)
             .unwrap_or(&self.providers.default_provider)
     }
 
@@ -569,7 +1024,8 @@ impl Config {
     pub fn get_coach_provider(&self) -> &str {
         self.providers
             .coach
-            .as_deref()
+            .as_ref()
+            .map(ProviderChain::primary)
             .unwrap_or(&self.providers.default_provider)
     }
 
@@ -577,10 +1033,41 @@ impl Config {
     pub fn get_player_provider(&self) -> &str {
         self.providers
             .player
-            .as_deref()
+            .as_ref()
+            .map(ProviderChain::primary)
             .unwrap_or(&self.providers.default_provider)
     }
 
+    /// The default provider followed by its fallbacks, without duplicates
+    pub fn provider_chain(&self) -> Vec<String> {
+        let mut chain = vec![self.providers.default_provider.clone()];
+        for fallback in &self.providers.fallbacks {
+            if !chain.contains(fallback) {
+                chain.push(fallback.clone());
+            }
+        }
+        chain
+    }
+
+    /// Pricing for `model` served by `provider` (e.g. `anthropic.default`).
+    ///
+    /// An entry for the provider wins, then one for the exact model name, then
+    /// the longest entry the model name contains, so `claude-sonnet-4-5` also
+    /// prices `claude-sonnet-4-5-20250929` and Bedrock's
+    /// `anthropic.claude-sonnet-4-5-20250929-v1:0`.
+    pub fn pricing_for(&self, provider: &str, model: &str) -> Option<&ModelPricing> {
+        self.pricing
+            .get(provider)
+            .or_else(|| self.pricing.get(model))
+            .or_else(|| {
+                self.pricing
+                    .iter()
+                    .filter(|(key, _)| model.contains(key.as_str()))
+                    .max_by_key(|(key, _)| key.len())
+                    .map(|(_, pricing)| pricing)
+            })
+    }
+
     /// Create a copy of the config with a different default provider
     pub fn with_provider_override(&self, provider_ref: &str) -> Result<Self> {
         // Validate that the provider is configured
@@ -591,19 +1078,33 @@ impl Config {
         Ok(config)
     }
 
+    /// Create a copy of the config using a role's provider and fallbacks.
+    /// Roles without their own provider list keep the default provider's fallbacks.
+    fn for_role(&self, chain: Option<&ProviderChain>) -> Result<Self> {
+        let Some(chain) = chain else {
+            return self.with_provider_override(&self.providers.default_provider);
+        };
+
+        let mut config = self.with_provider_override(chain.primary())?;
+        if let ProviderChain::Chain(_) = chain {
+            config.providers.fallbacks = chain.fallbacks().to_vec();
+        }
+        Ok(config)
+    }
+
     /// Create a copy of the config for planner mode
     pub fn for_planner(&self) -> Result<Self> {
-        self.with_provider_override(self.get_planner_provider())
+        self.for_role(self.providers.planner.as_ref())
     }
 
     /// Create a copy of the config for coach mode in autonomous execution
     pub fn for_coach(&self) -> Result<Self> {
-        self.with_provider_override(self.get_coach_provider())
+        self.for_role(self.providers.coach.as_ref())
     }
 
     /// Create a copy of the config for player mode in autonomous execution
     pub fn for_player(&self) -> Result<Self> {
-        self.with_provider_override(self.get_player_provider())
+        self.for_role(self.providers.player.as_ref())
     }
 
     /// Get Anthropic config by name
@@ -626,6 +1127,11 @@ impl Config {
         self.providers.embedded.get(name)
     }
 
+    /// Get Gemini config by name
+    pub fn get_gemini_config(&self, name: &str) -> Option<&GeminiConfig> {
+        self.providers.gemini.get(name)
+    }
+
     /// Get the current default provider's config
     pub fn get_default_provider_config(&self) -> Result<ProviderConfigRef<'_>> {
         let (provider_type, config_name) = Self::parse_provider_reference(
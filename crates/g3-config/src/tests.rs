@@ -1,6 +1,6 @@
 #[cfg(test)]
 mod tests {
-    use crate::Config;
+    use crate::{Config, ExecutionBackendKind, OllamaServerKind, PermissionAction, ProviderChain};
     use std::fs;
     use tempfile::TempDir;
 
@@ -273,4 +273,479 @@ autonomous_max_retry_attempts = 6
         // Test that planner falls back to default provider
         assert_eq!(config.get_planner_provider(), "databricks.default");
     }
+
+    #[test]
+    fn test_mcp_servers() {
+        let temp_dir = TempDir::new().unwrap();
+        let config_path = temp_dir.path().join("test_config.toml");
+
+        let config_content = format!(r#"
+[providers]
+default_provider = "databricks.default"
+
+[providers.databricks.default]
+host = "https://test.databricks.com"
+token = "test-token"
+model = "test-model"
+
+[agent]
+fallback_default_max_tokens = 8192
+enable_streaming = true
+timeout_seconds = 60
+auto_compact = true
+allow_multiple_tool_calls = false
+max_retry_attempts = 3
+autonomous_max_retry_attempts = 6
+
+[mcp.servers.build]
+command = "build-tools-mcp"
+args = ["--stdio"]
+
+[mcp.servers.deploy]
+url = "https://deploy.internal/mcp"
+headers = {{ Authorization = "Bearer test" }}
+enabled = false
+{}"#, test_config_footer());
+
+        fs::write(&config_path, config_content).unwrap();
+
+        let config = Config::load(Some(config_path.to_str().unwrap())).unwrap();
+        assert_eq!(config.mcp.servers.len(), 2);
+
+        let build = &config.mcp.servers["build"];
+        assert_eq!(build.command.as_deref(), Some("build-tools-mcp"));
+        assert_eq!(build.args, vec!["--stdio"]);
+        assert!(build.enabled);
+
+        let deploy = &config.mcp.servers["deploy"];
+        assert_eq!(deploy.url.as_deref(), Some("https://deploy.internal/mcp"));
+        assert_eq!(deploy.headers["Authorization"], "Bearer test");
+        assert!(!deploy.enabled);
+    }
+
+    #[test]
+    fn test_mcp_server_requires_single_transport() {
+        let temp_dir = TempDir::new().unwrap();
+        let config_path = temp_dir.path().join("test_config.toml");
+
+        let config_content = format!(r#"
+[providers]
+default_provider = "databricks.default"
+
+[providers.databricks.default]
+host = "https://test.databricks.com"
+model = "test-model"
+
+[agent]
+fallback_default_max_tokens = 8192
+enable_streaming = true
+timeout_seconds = 60
+auto_compact = true
+allow_multiple_tool_calls = false
+max_retry_attempts = 3
+autonomous_max_retry_attempts = 6
+
+[mcp.servers.broken]
+args = ["--stdio"]
+{}"#, test_config_footer());
+
+        fs::write(&config_path, config_content).unwrap();
+
+        let err = Config::load(Some(config_path.to_str().unwrap())).unwrap_err();
+        assert!(err.to_string().contains("MCP server 'broken'"));
+    }
+
+    #[test]
+    fn test_permissions_section() {
+        let temp_dir = TempDir::new().unwrap();
+        let config_path = temp_dir.path().join("test_config.toml");
+
+        let config_content = format!(r#"
+[providers]
+default_provider = "databricks.default"
+
+[providers.databricks.default]
+host = "https://test.databricks.com"
+model = "test-model"
+
+[agent]
+fallback_default_max_tokens = 8192
+enable_streaming = true
+timeout_seconds = 60
+auto_compact = true
+allow_multiple_tool_calls = false
+max_retry_attempts = 3
+autonomous_max_retry_attempts = 6
+
+[permissions]
+default = "prompt"
+unattended = "deny"
+
+[[permissions.rules]]
+tool = "shell"
+action = "deny"
+pattern = "rm -rf *"
+
+[[permissions.rules]]
+tool = "write_file"
+action = "allow"
+pattern = "${{workspace}}/*"
+{}"#, test_config_footer());
+
+        fs::write(&config_path, config_content).unwrap();
+
+        let config = Config::load(Some(config_path.to_str().unwrap())).unwrap();
+        assert_eq!(config.permissions.default, PermissionAction::Prompt);
+        assert_eq!(config.permissions.unattended, PermissionAction::Deny);
+        assert!(config.permissions.auto_allow_read_only);
+        assert_eq!(config.permissions.rules.len(), 2);
+        assert_eq!(config.permissions.rules[0].action, PermissionAction::Deny);
+        assert_eq!(config.permissions.rules[1].pattern.as_deref(), Some("${workspace}/*"));
+    }
+
+    #[test]
+    fn test_permissions_default_allows_everything() {
+        let config = Config::default();
+        assert_eq!(config.permissions.default, PermissionAction::Allow);
+        assert_eq!(config.permissions.unattended, PermissionAction::Allow);
+        assert!(config.permissions.rules.is_empty());
+    }
+
+    #[test]
+    fn test_execution_backend_per_mode() {
+        let temp_dir = TempDir::new().unwrap();
+        let config_path = temp_dir.path().join("test_config.toml");
+
+        let config_content = format!(r#"
+[providers]
+default_provider = "databricks.default"
+
+[providers.databricks.default]
+host = "https://test.databricks.com"
+model = "test-model"
+
+[agent]
+fallback_default_max_tokens = 8192
+enable_streaming = true
+timeout_seconds = 60
+auto_compact = true
+allow_multiple_tool_calls = false
+max_retry_attempts = 3
+autonomous_max_retry_attempts = 6
+
+[execution]
+backend = "direct"
+autonomous_backend = "sandbox"
+
+[execution.sandbox]
+writable_paths = ["~/.cargo"]
+{}"#, test_config_footer());
+
+        fs::write(&config_path, config_content).unwrap();
+
+        let config = Config::load(Some(config_path.to_str().unwrap())).unwrap();
+        assert_eq!(config.execution.backend_for(false), ExecutionBackendKind::Direct);
+        assert_eq!(config.execution.backend_for(true), ExecutionBackendKind::Sandbox);
+        assert_eq!(config.execution.sandbox.program, "bwrap");
+        assert!(!config.execution.sandbox.allow_network);
+        assert_eq!(config.execution.sandbox.writable_paths, vec!["~/.cargo"]);
+        assert_eq!(config.execution.container.runtime, "podman");
+    }
+
+    #[test]
+    fn test_ollama_provider() {
+        let temp_dir = TempDir::new().unwrap();
+        let config_path = temp_dir.path().join("test_config.toml");
+
+        let config_content = format!(r#"
+[providers]
+default_provider = "ollama.local"
+
+[providers.ollama.local]
+model = "qwen2.5-coder:14b"
+
+[providers.ollama.shared]
+model = "qwen3-coder"
+base_url = "http://gpu-box:8080"
+server = "llama-server"
+context_length = 32768
+
+[agent]
+fallback_default_max_tokens = 8192
+enable_streaming = true
+timeout_seconds = 60
+auto_compact = true
+allow_multiple_tool_calls = false
+max_retry_attempts = 3
+autonomous_max_retry_attempts = 6
+{}"#, test_config_footer());
+
+        fs::write(&config_path, config_content).unwrap();
+
+        let config = Config::load_with_overrides(
+            Some(config_path.to_str().unwrap()),
+            Some("ollama.shared".to_string()),
+            Some("qwen3-coder:30b".to_string()),
+        )
+        .unwrap();
+
+        let local = &config.providers.ollama["local"];
+        assert_eq!(local.server, OllamaServerKind::Ollama);
+        assert!(local.base_url.is_none());
+        assert!(local.context_length.is_none());
+
+        let shared = &config.providers.ollama["shared"];
+        assert_eq!(shared.server, OllamaServerKind::LlamaServer);
+        assert_eq!(shared.model, "qwen3-coder:30b");
+        assert_eq!(shared.context_length, Some(32768));
+        assert_eq!(config.providers.default_provider, "ollama.shared");
+
+        assert!(Config::load_with_overrides(
+            Some(config_path.to_str().unwrap()),
+            Some("ollama.missing".to_string()),
+            None,
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_gemini_planner_provider() {
+        let temp_dir = TempDir::new().unwrap();
+        let config_path = temp_dir.path().join("test_config.toml");
+
+        let config_content = format!(r#"
+[providers]
+default_provider = "anthropic.default"
+planner = "gemini.planner"
+
+[providers.anthropic.default]
+api_key = "test-key"
+model = "claude-sonnet-4-5"
+
+[providers.gemini.planner]
+api_key = "test-gemini-key"
+model = "gemini-2.5-pro"
+
+[agent]
+fallback_default_max_tokens = 8192
+enable_streaming = true
+timeout_seconds = 60
+auto_compact = true
+allow_multiple_tool_calls = false
+max_retry_attempts = 3
+autonomous_max_retry_attempts = 6
+{}"#, test_config_footer());
+
+        fs::write(&config_path, config_content).unwrap();
+
+        let config = Config::load(Some(config_path.to_str().unwrap())).unwrap();
+        assert_eq!(config.get_planner_provider(), "gemini.planner");
+
+        let gemini = config.get_gemini_config("planner").unwrap();
+        assert_eq!(gemini.model, "gemini-2.5-pro");
+        assert!(gemini.base_url.is_none());
+        assert!(gemini.context_length.is_none());
+
+        let planner_config = config.for_planner().unwrap();
+        assert_eq!(planner_config.providers.default_provider, "gemini.planner");
+    }
+
+    #[test]
+    fn test_bedrock_provider() {
+        let temp_dir = TempDir::new().unwrap();
+        let config_path = temp_dir.path().join("test_config.toml");
+
+        let config_content = format!(r#"
+[providers]
+default_provider = "bedrock.default"
+
+[providers.bedrock.default]
+model = "us.anthropic.claude-sonnet-4-5-20250929-v1:0"
+region = "eu-west-1"
+profile = "claude"
+cache_config = "ephemeral"
+
+[agent]
+fallback_default_max_tokens = 8192
+enable_streaming = true
+timeout_seconds = 60
+auto_compact = true
+allow_multiple_tool_calls = false
+max_retry_attempts = 3
+autonomous_max_retry_attempts = 6
+{}"#, test_config_footer());
+
+        fs::write(&config_path, config_content).unwrap();
+
+        let config = Config::load(Some(config_path.to_str().unwrap())).unwrap();
+        let bedrock = &config.providers.bedrock["default"];
+        assert_eq!(bedrock.model, "us.anthropic.claude-sonnet-4-5-20250929-v1:0");
+        assert_eq!(bedrock.region.as_deref(), Some("eu-west-1"));
+        assert_eq!(bedrock.profile.as_deref(), Some("claude"));
+        assert!(bedrock.endpoint_url.is_none());
+
+        let overridden = Config::load_with_overrides(
+            Some(config_path.to_str().unwrap()),
+            None,
+            Some("anthropic.claude-haiku-4-5-20251001-v1:0".to_string()),
+        )
+        .unwrap();
+        assert_eq!(
+            overridden.providers.bedrock["default"].model,
+            "anthropic.claude-haiku-4-5-20251001-v1:0"
+        );
+    }
+
+    #[test]
+    fn test_provider_fallback_chains() {
+        let temp_dir = TempDir::new().unwrap();
+        let config_path = temp_dir.path().join("test_config.toml");
+
+        let config_content = format!(r#"
+[providers]
+default_provider = "anthropic.default"
+fallbacks = ["openrouter.backup"]
+player = ["anthropic.player", "openrouter.backup", "anthropic.default"]
+coach = "anthropic.default"
+
+[providers.anthropic.default]
+api_key = "test-key"
+model = "claude-sonnet-4-5"
+
+[providers.anthropic.player]
+api_key = "test-key"
+model = "claude-opus-4-1"
+
+[providers.openrouter.backup]
+api_key = "test-openrouter-key"
+model = "anthropic/claude-sonnet-4.5"
+
+[agent]
+fallback_default_max_tokens = 8192
+enable_streaming = true
+timeout_seconds = 60
+auto_compact = true
+allow_multiple_tool_calls = false
+max_retry_attempts = 3
+autonomous_max_retry_attempts = 6
+provider_failover_after = 3
+{}"#, test_config_footer());
+
+        fs::write(&config_path, config_content).unwrap();
+
+        let config = Config::load(Some(config_path.to_str().unwrap())).unwrap();
+        assert_eq!(config.agent.provider_failover_after, 3);
+        assert_eq!(config.provider_chain(), vec!["anthropic.default", "openrouter.backup"]);
+        assert_eq!(config.get_player_provider(), "anthropic.player");
+        assert_eq!(
+            config.providers.coach,
+            Some(ProviderChain::Single("anthropic.default".to_string()))
+        );
+
+        let player_config = config.for_player().unwrap();
+        assert_eq!(
+            player_config.provider_chain(),
+            vec!["anthropic.player", "openrouter.backup", "anthropic.default"]
+        );
+
+        // Roles without their own list keep the default provider's fallbacks
+        let coach_config = config.for_coach().unwrap();
+        assert_eq!(coach_config.provider_chain(), vec!["anthropic.default", "openrouter.backup"]);
+    }
+
+    #[test]
+    fn test_unknown_fallback_provider_is_rejected() {
+        let temp_dir = TempDir::new().unwrap();
+        let config_path = temp_dir.path().join("test_config.toml");
+
+        let config_content = format!(r#"
+[providers]
+default_provider = "anthropic.default"
+player = ["anthropic.default", "openrouter.missing"]
+
+[providers.anthropic.default]
+api_key = "test-key"
+model = "claude-sonnet-4-5"
+
+[agent]
+fallback_default_max_tokens = 8192
+enable_streaming = true
+timeout_seconds = 60
+auto_compact = true
+allow_multiple_tool_calls = false
+max_retry_attempts = 3
+autonomous_max_retry_attempts = 6
+{}"#, test_config_footer());
+
+        fs::write(&config_path, config_content).unwrap();
+
+        let err = Config::load(Some(config_path.to_str().unwrap())).unwrap_err();
+        assert!(err.to_string().contains("openrouter.missing"), "unexpected error: {}", err);
+    }
+
+    #[test]
+    fn test_pricing_and_budget() {
+        let temp_dir = TempDir::new().unwrap();
+        let config_path = temp_dir.path().join("test_config.toml");
+
+        let config_content = format!(r#"
+[providers]
+default_provider = "anthropic.default"
+
+[providers.anthropic.default]
+api_key = "test-key"
+model = "claude-sonnet-4-5"
+
+[agent]
+fallback_default_max_tokens = 8192
+enable_streaming = true
+timeout_seconds = 60
+auto_compact = true
+allow_multiple_tool_calls = false
+max_retry_attempts = 3
+autonomous_max_retry_attempts = 6
+
+[budget]
+max_cost_usd = 5.0
+max_autonomous_cost_usd = 20.0
+
+[pricing.claude]
+input_per_mtok = 1.0
+output_per_mtok = 1.0
+
+[pricing."claude-sonnet-4-5"]
+input_per_mtok = 3.0
+output_per_mtok = 15.0
+cached_input_per_mtok = 0.3
+cache_write_per_mtok = 3.75
+
+[pricing."openai.local"]
+input_per_mtok = 0.0
+output_per_mtok = 0.0
+{}"#, test_config_footer());
+
+        fs::write(&config_path, config_content).unwrap();
+        let config = Config::load(Some(config_path.to_str().unwrap())).unwrap();
+
+        assert_eq!(config.budget.max_cost_usd, Some(5.0));
+        assert_eq!(config.budget.max_autonomous_cost_usd, Some(20.0));
+
+        // The longest matching model name wins, including inside Bedrock model ids
+        let sonnet = config
+            .pricing_for("bedrock.default", "anthropic.claude-sonnet-4-5-20250929-v1:0")
+            .unwrap();
+        assert_eq!(sonnet.input_per_mtok, 3.0);
+        assert_eq!(sonnet.cached_input_per_mtok, Some(0.3));
+        assert_eq!(
+            config.pricing_for("anthropic.default", "claude-haiku-4-5").unwrap().input_per_mtok,
+            1.0
+        );
+
+        // A provider entry overrides the model's price
+        assert_eq!(
+            config.pricing_for("openai.local", "claude-sonnet-4-5").unwrap().output_per_mtok,
+            0.0
+        );
+        assert!(config.pricing_for("openai.default", "gpt-4o").is_none());
+    }
 }
@@ -27,6 +27,8 @@ mod test_multiple_tool_calls {
             max_retry_attempts: 3,
             autonomous_max_retry_attempts: 6,
             check_todo_staleness: true,
+            provider_failover_after: 2,
+            download_tokenizers: false,
         };
 
         // Test serialization
@@ -0,0 +1,131 @@
+//! File-edit checkpoints
+//!
+//! Before a tool that modifies files runs, the agent snapshots the previous
+//! contents of every file the call declares through
+//! `ToolHandler::modified_files`. Each checkpoint is anchored to the last
+//! message in the conversation at that moment, so rewinding the conversation
+//! can also revert the edits made after the rewind point. `/undo` and the
+//! `undo_last_edit` tool restore the most recent checkpoint. None of this
+//! depends on git.
+
+use anyhow::{Context, Result};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+/// Contents of a file before an edit
+#[derive(Debug, Clone)]
+pub struct FileSnapshot {
+    pub path: PathBuf,
+    /// `None` if the file did not exist
+    pub previous: Option<Vec<u8>>,
+}
+
+/// The files touched by one tool call
+#[derive(Debug, Clone)]
+pub struct Checkpoint {
+    pub tool: String,
+    /// Id of the last message in the conversation when the call ran
+    pub anchor_message_id: String,
+    pub files: Vec<FileSnapshot>,
+}
+
+#[derive(Debug, Default)]
+pub struct CheckpointStore {
+    checkpoints: Vec<Checkpoint>,
+}
+
+impl CheckpointStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Snapshot `paths` before `tool` modifies them
+    pub fn record(&mut self, tool: &str, anchor_message_id: &str, paths: &[PathBuf]) {
+        let files = paths
+            .iter()
+            .map(|path| FileSnapshot {
+                path: path.clone(),
+                previous: std::fs::read(path).ok(),
+            })
+            .collect();
+        self.checkpoints.push(Checkpoint {
+            tool: tool.to_string(),
+            anchor_message_id: anchor_message_id.to_string(),
+            files,
+        });
+    }
+
+    pub fn checkpoints(&self) -> &[Checkpoint] {
+        &self.checkpoints
+    }
+
+    pub fn len(&self) -> usize {
+        self.checkpoints.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.checkpoints.is_empty()
+    }
+
+    /// Drop the newest checkpoint if the call it was taken for left every
+    /// file untouched (e.g. a `str_replace` whose diff did not apply)
+    pub fn discard_if_unchanged(&mut self) {
+        let unchanged = self.checkpoints.last().is_some_and(|checkpoint| {
+            checkpoint
+                .files
+                .iter()
+                .all(|file| std::fs::read(&file.path).ok() == file.previous)
+        });
+        if unchanged {
+            self.checkpoints.pop();
+        }
+    }
+
+    /// Restore the files of the newest checkpoint and drop it. Returns the
+    /// restored checkpoint, or `None` if there is nothing to undo.
+    pub fn undo_last(&mut self) -> Result<Option<Checkpoint>> {
+        let Some(checkpoint) = self.checkpoints.pop() else {
+            return Ok(None);
+        };
+        for file in checkpoint.files.iter().rev() {
+            restore_snapshot(file)?;
+        }
+        Ok(Some(checkpoint))
+    }
+
+    /// Restore (newest first) and drop every checkpoint anchored to one of
+    /// `message_ids`. Returns the restored paths.
+    pub fn revert_anchored_to(&mut self, message_ids: &HashSet<&str>) -> Result<Vec<PathBuf>> {
+        let mut restored = Vec::new();
+        while let Some(index) = self
+            .checkpoints
+            .iter()
+            .rposition(|c| message_ids.contains(c.anchor_message_id.as_str()))
+        {
+            let checkpoint = self.checkpoints.remove(index);
+            for file in checkpoint.files.iter().rev() {
+                restore_snapshot(file)?;
+                if !restored.contains(&file.path) {
+                    restored.push(file.path.clone());
+                }
+            }
+        }
+        Ok(restored)
+    }
+}
+
+fn restore_snapshot(snapshot: &FileSnapshot) -> Result<()> {
+    let path: &Path = &snapshot.path;
+    match &snapshot.previous {
+        Some(content) => {
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            std::fs::write(path, content)
+                .with_context(|| format!("Failed to restore {}", path.display()))
+        }
+        None if path.exists() => std::fs::remove_file(path)
+            .with_context(|| format!("Failed to remove {}", path.display())),
+        None => Ok(()),
+    }
+}
@@ -5,7 +5,25 @@ use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
 mod searcher;
-pub use searcher::TreeSitterSearcher;
+pub use searcher::{Capture, TreeSitterSearcher};
+
+/// Directories that never contain source worth indexing
+const SKIPPED_DIRS: &[&str] = &[
+    "target",
+    "node_modules",
+    "dist",
+    "build",
+    "vendor",
+    "logs",
+    "__pycache__",
+];
+
+/// True for hidden, build-output and dependency directories, which the
+/// semantic index and repo map do not walk into
+pub(crate) fn is_skipped_dir(entry: &walkdir::DirEntry) -> bool {
+    let name = entry.file_name().to_string_lossy();
+    entry.file_type().is_dir() && (name.starts_with('.') || SKIPPED_DIRS.contains(&name.as_ref()))
+}
 
 /// Request for batch code searches
 #[derive(Debug, Clone, Serialize, Deserialize)]
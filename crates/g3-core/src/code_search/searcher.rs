@@ -7,6 +7,16 @@ use streaming_iterator::StreamingIterator;
 use tree_sitter::{Language, Parser, Query, QueryCursor};
 use walkdir::WalkDir;
 
+/// One capture from [`TreeSitterSearcher::captures`]
+#[derive(Debug, Clone)]
+pub struct Capture {
+    /// Capture name in the query, without the `@`
+    pub name: String,
+    pub text: String,
+    /// 1-based
+    pub line: usize,
+}
+
 pub struct TreeSitterSearcher {
     parsers: HashMap<String, Parser>,
     languages: HashMap<String, Language>,
@@ -324,6 +334,65 @@ impl TreeSitterSearcher {
         })
     }
 
+    /// Compile `query` for one of the searcher's languages
+    pub fn compile_query(&self, language: &str, query: &str) -> Result<Query> {
+        let language = self
+            .languages
+            .get(language)
+            .ok_or_else(|| anyhow!("Unsupported language: {}", language))?;
+        Query::new(language, query).map_err(|e| anyhow!("Invalid query: {}", e))
+    }
+
+    /// Parse `source` and return every capture of `query`, in match order
+    pub fn captures(
+        &mut self,
+        language: &str,
+        query: &Query,
+        source: &str,
+    ) -> Result<Vec<Capture>> {
+        let parser = self
+            .parsers
+            .get_mut(language)
+            .ok_or_else(|| anyhow!("Unsupported language: {}", language))?;
+        let tree = parser
+            .parse(source, None)
+            .ok_or_else(|| anyhow!("Failed to parse {} source", language))?;
+
+        let mut captures = Vec::new();
+        let mut cursor = QueryCursor::new();
+        let mut query_matches = cursor.matches(query, tree.root_node(), source.as_bytes());
+        query_matches.advance();
+        while let Some(query_match) = query_matches.get() {
+            for capture in query_match.captures {
+                captures.push(Capture {
+                    name: query.capture_names()[capture.index as usize].to_string(),
+                    text: source[capture.node.byte_range()].to_string(),
+                    line: capture.node.start_position().row + 1,
+                });
+            }
+            query_matches.advance();
+        }
+        Ok(captures)
+    }
+
+    /// The searcher language a file is written in, by extension
+    pub fn language_for_path(path: &Path) -> Option<&'static str> {
+        [
+            "rust",
+            "python",
+            "javascript",
+            "typescript",
+            "go",
+            "java",
+            "c",
+            "cpp",
+            "haskell",
+            "scheme",
+        ]
+        .into_iter()
+        .find(|language| Self::is_language_file(path, language))
+    }
+
     fn is_language_file(path: &Path, language: &str) -> bool {
         let ext = path.extension().and_then(|e| e.to_str());
         match (language, ext) {
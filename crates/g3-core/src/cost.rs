@@ -0,0 +1,169 @@
+//! Spend tracking
+//!
+//! Every response's `Usage` is priced with the `[pricing]` table from the
+//! config and added to a per-session `CostTracker`. Cache reads and writes
+//! are priced separately, so prompt caching shows up as a discount. Requests
+//! for models without a price are counted but cost nothing.
+//!
+//! `BudgetExceeded` is returned (inside an `anyhow::Error`) when a session
+//! would send a request after reaching its spending limit.
+
+use g3_config::ModelPricing;
+use g3_providers::Usage;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// Dollar cost of one response
+pub fn usage_cost(usage: &Usage, pricing: &ModelPricing) -> f64 {
+    let cached_input = pricing
+        .cached_input_per_mtok
+        .unwrap_or(pricing.input_per_mtok);
+    let cache_write = pricing
+        .cache_write_per_mtok
+        .unwrap_or(pricing.input_per_mtok);
+    let uncached_input = usage
+        .prompt_tokens
+        .saturating_sub(usage.cache_read_tokens)
+        .saturating_sub(usage.cache_creation_tokens);
+
+    (uncached_input as f64 * pricing.input_per_mtok
+        + usage.cache_read_tokens as f64 * cached_input
+        + usage.cache_creation_tokens as f64 * cache_write
+        + usage.completion_tokens as f64 * pricing.output_per_mtok)
+        / 1_000_000.0
+}
+
+/// Format a dollar amount with enough precision for small sessions
+pub fn format_usd(amount: f64) -> String {
+    if amount < 1.0 {
+        format!("${:.4}", amount)
+    } else {
+        format!("${:.2}", amount)
+    }
+}
+
+/// Running spend for a session
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct CostTracker {
+    pub total_usd: f64,
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+    pub cache_read_tokens: u64,
+    pub cache_creation_tokens: u64,
+    /// Requests priced at zero because their model has no `[pricing]` entry
+    pub unpriced_requests: u32,
+    /// Spend per model
+    pub by_model: BTreeMap<String, f64>,
+}
+
+impl CostTracker {
+    /// Add one response's usage and return its cost
+    pub fn record(&mut self, model: &str, usage: &Usage, pricing: Option<&ModelPricing>) -> f64 {
+        self.prompt_tokens += usage.prompt_tokens as u64;
+        self.completion_tokens += usage.completion_tokens as u64;
+        self.cache_read_tokens += usage.cache_read_tokens as u64;
+        self.cache_creation_tokens += usage.cache_creation_tokens as u64;
+
+        let Some(pricing) = pricing else {
+            self.unpriced_requests += 1;
+            return 0.0;
+        };
+        let cost = usage_cost(usage, pricing);
+        self.total_usd += cost;
+        *self.by_model.entry(model.to_string()).or_default() += cost;
+        cost
+    }
+
+    /// Share of prompt tokens served from the provider's cache, if any
+    /// prompt tokens were reported
+    pub fn cache_hit_rate(&self) -> Option<f64> {
+        (self.prompt_tokens > 0)
+            .then(|| self.cache_read_tokens as f64 / self.prompt_tokens as f64)
+    }
+}
+
+/// A spending limit was reached
+#[derive(Debug, Clone, PartialEq)]
+pub struct BudgetExceeded {
+    pub spent_usd: f64,
+    pub limit_usd: f64,
+}
+
+impl fmt::Display for BudgetExceeded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Spending budget exceeded: {} spent of {} allowed",
+            format_usd(self.spent_usd),
+            format_usd(self.limit_usd)
+        )
+    }
+}
+
+impl std::error::Error for BudgetExceeded {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sonnet() -> ModelPricing {
+        ModelPricing {
+            input_per_mtok: 3.0,
+            output_per_mtok: 15.0,
+            cached_input_per_mtok: Some(0.3),
+            cache_write_per_mtok: Some(3.75),
+        }
+    }
+
+    #[test]
+    fn test_cache_reads_are_discounted() {
+        let usage = Usage {
+            prompt_tokens: 1_000_000,
+            completion_tokens: 100_000,
+            total_tokens: 1_100_000,
+            cache_read_tokens: 800_000,
+            cache_creation_tokens: 100_000,
+        };
+        // 100k uncached at $3, 800k cached at $0.30, 100k written at $3.75, 100k out at $15
+        let cost = usage_cost(&usage, &sonnet());
+        assert!((cost - (0.3 + 0.24 + 0.375 + 1.5)).abs() < 1e-9, "cost was {}", cost);
+    }
+
+    #[test]
+    fn test_cache_hit_rate() {
+        let mut tracker = CostTracker::default();
+        assert_eq!(tracker.cache_hit_rate(), None);
+
+        let usage = Usage {
+            prompt_tokens: 4000,
+            completion_tokens: 100,
+            total_tokens: 4100,
+            cache_read_tokens: 3000,
+            cache_creation_tokens: 500,
+        };
+        tracker.record("claude-sonnet-4-5", &usage, Some(&sonnet()));
+        assert_eq!(tracker.cache_hit_rate(), Some(0.75));
+    }
+
+    #[test]
+    fn test_unpriced_models_are_counted_but_free() {
+        let mut tracker = CostTracker::default();
+        let usage = Usage {
+            prompt_tokens: 2000,
+            completion_tokens: 500,
+            total_tokens: 2500,
+            ..Default::default()
+        };
+
+        tracker.record("local-model", &usage, None);
+        assert_eq!(tracker.total_usd, 0.0);
+        assert_eq!(tracker.unpriced_requests, 1);
+
+        let cost = tracker.record("claude-sonnet-4-5", &usage, Some(&sonnet()));
+        assert!((cost - 0.0135).abs() < 1e-9);
+        assert_eq!(tracker.total_usd, cost);
+        assert_eq!(tracker.prompt_tokens, 4000);
+        assert_eq!(tracker.by_model["claude-sonnet-4-5"], cost);
+    }
+}
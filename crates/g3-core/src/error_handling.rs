@@ -187,6 +187,17 @@ pub enum RecoverableError {
     ContextLengthExceeded,
 }
 
+impl RecoverableError {
+    /// Whether a different provider might succeed where this one failed.
+    /// Token and context limits depend on the conversation, not the provider's health.
+    pub fn warrants_failover(&self) -> bool {
+        !matches!(
+            self,
+            RecoverableError::TokenLimit | RecoverableError::ContextLengthExceeded
+        )
+    }
+}
+
 /// Classify an error as recoverable or non-recoverable
 pub fn classify_error(error: &anyhow::Error) -> ErrorType {
     let error_str = error.to_string().to_lowercase();
@@ -495,6 +506,15 @@ mod tests {
         assert_eq!(classify_error(&error), ErrorType::NonRecoverable);
     }
 
+    #[test]
+    fn test_failover_only_for_provider_failures() {
+        assert!(RecoverableError::RateLimit.warrants_failover());
+        assert!(RecoverableError::ModelBusy.warrants_failover());
+        assert!(RecoverableError::ServerError.warrants_failover());
+        assert!(!RecoverableError::TokenLimit.warrants_failover());
+        assert!(!RecoverableError::ContextLengthExceeded.warrants_failover());
+    }
+
     #[test]
     fn test_retry_delay_calculation() {
         // Test that delays increase exponentially
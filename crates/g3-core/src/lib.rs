@@ -1,9 +1,19 @@
+pub mod checkpoints;
 pub mod code_search;
+pub mod cost;
 pub mod error_handling;
 pub mod feedback_extraction;
+pub mod machine_events;
+pub mod machine_ui_writer;
+pub mod mcp;
+pub mod permissions;
 pub mod project;
+pub mod repo_map;
 pub mod retry;
+pub mod semantic_index;
+pub mod session;
 pub mod task_result;
+pub mod tools;
 pub mod ui_writer;
 
 pub use task_result::TaskResult;
@@ -28,18 +38,18 @@ mod prompts;
 
 use anyhow::Result;
 use chrono::Local;
-use g3_computer_control::WebDriverController;
 use g3_config::Config;
-use g3_execution::CodeExecutor;
-use g3_providers::{CacheControl, CompletionRequest, Message, MessageRole, ProviderRegistry, Tool};
+use g3_providers::{
+    BpeTokenizer, CacheControl, CompletionRequest, EstimatingTokenizer, Message, MessageRole, ProviderRegistry,
+    Tokenizer, Tool,
+};
 use prompts::{get_system_prompt_for_native, SYSTEM_PROMPT_FOR_NON_NATIVE_TOOL_USE};
 #[allow(unused_imports)]
 use regex::Regex;
 use serde::{Deserialize, Serialize};
-use serde_json::json;
 use std::fs::OpenOptions;
 use std::io::Write;
-use std::sync::{Mutex, OnceLock};
+use std::sync::{Arc, Mutex, OnceLock};
 use std::time::{Duration, Instant};
 use tokio_util::sync::CancellationToken;
 use tracing::{debug, error, info, warn};
@@ -47,25 +57,25 @@ use tracing::{debug, error, info, warn};
 /// Get the path to the todo.g3.md file.
 /// 
 /// Checks for G3_TODO_PATH environment variable first (used by planning mode),
-/// then falls back to todo.g3.md in the current directory.
+/// then falls back to todo.g3.md in the current workspace.
 fn get_todo_path() -> std::path::PathBuf {
     if let Ok(custom_path) = std::env::var("G3_TODO_PATH") {
         std::path::PathBuf::from(custom_path)
     } else {
-        std::env::current_dir().unwrap_or_default().join("todo.g3.md")
+        project::current_workspace().join("todo.g3.md")
     }
 }
 
 /// Get the path to the logs directory.
 ///
 /// Checks for G3_WORKSPACE_PATH environment variable first (used by planning mode),
-/// then falls back to "logs" in the current directory.
+/// then falls back to "logs" in the current workspace.
 fn get_logs_dir() -> std::path::PathBuf {
     if let Ok(workspace_path) = std::env::var("G3_WORKSPACE_PATH") {
         let logs_path = std::path::PathBuf::from(workspace_path).join("logs");
         logs_path
     } else {
-        let logs_path = std::env::current_dir().unwrap_or_default().join("logs");
+        let logs_path = project::current_workspace().join("logs");
         logs_path
     }
 }
@@ -444,8 +454,19 @@ pub struct ContextWindow {
     pub cumulative_tokens: u32, // Track cumulative tokens across all interactions
     pub conversation_history: Vec<Message>,
     pub last_thinning_percentage: u32, // Track the last percentage at which we thinned
+    tokenizer: Arc<dyn Tokenizer>,
+    /// Last message of the region rewritten by the previous thinning pass.
+    /// It keeps a cache breakpoint, and later passes leave the prefix up to
+    /// it alone when they can.
+    cache_anchor: Option<usize>,
 }
 
+/// Most cache breakpoints a request may carry (Anthropic's limit)
+const MAX_CACHE_BREAKPOINTS: usize = 4;
+
+/// Content prefix of the system message holding a compaction summary
+const SUMMARY_PREFIX: &str = "Previous conversation summary:";
+
 impl ContextWindow {
     pub fn new(total_tokens: u32) -> Self {
         Self {
@@ -454,9 +475,27 @@ impl ContextWindow {
             cumulative_tokens: 0,
             conversation_history: Vec::new(),
             last_thinning_percentage: 0,
+            tokenizer: Arc::new(EstimatingTokenizer::default()),
+            cache_anchor: None,
         }
     }
 
+    /// Count tokens with `tokenizer` instead of the generic estimator
+    pub fn with_tokenizer(mut self, tokenizer: Arc<dyn Tokenizer>) -> Self {
+        self.tokenizer = tokenizer;
+        self
+    }
+
+    /// Switch tokenizers (e.g. after a provider switch) and recount the history
+    pub fn set_tokenizer(&mut self, tokenizer: Arc<dyn Tokenizer>) {
+        self.tokenizer = tokenizer;
+        self.recalculate_tokens();
+    }
+
+    pub fn tokenizer(&self) -> &Arc<dyn Tokenizer> {
+        &self.tokenizer
+    }
+
     pub fn add_message(&mut self, message: Message) {
         self.add_message_with_tokens(message, None);
     }
@@ -469,8 +508,8 @@ impl ContextWindow {
             return;
         }
 
-        // Use provided token count if available, otherwise estimate
-        let token_count = tokens.unwrap_or_else(|| Self::estimate_tokens(&message.content));
+        // Use provided token count if available, otherwise count it ourselves
+        let token_count = tokens.unwrap_or_else(|| self.tokenizer.count_message_tokens(&message));
         self.used_tokens += token_count;
         self.cumulative_tokens += token_count;
         self.conversation_history.push(message);
@@ -498,18 +537,41 @@ impl ContextWindow {
         );
     }
 
-    /// More accurate token estimation
-    fn estimate_tokens(text: &str) -> u32 {
-        // Better heuristic:
-        // - Average English text: ~4 characters per token
-        // - Code/JSON: ~3 characters per token (more symbols)
-        // - Add 10% buffer for safety
-        let base_estimate = if text.contains("{") || text.contains("```") || text.contains("fn ") {
-            (text.len() as f32 / 3.0).ceil() as u32 // Code/JSON
-        } else {
-            (text.len() as f32 / 4.0).ceil() as u32 // Regular text
-        };
-        (base_estimate as f32 * 1.1).ceil() as u32 // Add 10% buffer
+    /// Count tokens in `text` with the active provider's tokenizer
+    pub fn count_tokens(&self, text: &str) -> u32 {
+        self.tokenizer.count_tokens(text)
+    }
+
+    /// Count the tokens a request will take: every message plus the tool definitions
+    pub fn count_request_tokens(&self, request: &CompletionRequest) -> u32 {
+        let messages: u32 = request
+            .messages
+            .iter()
+            .map(|message| self.tokenizer.count_message_tokens(message))
+            .sum();
+        let tools: u32 = request
+            .tools
+            .iter()
+            .flatten()
+            .map(|tool| self.count_tokens(&serde_json::to_string(tool).unwrap_or_default()))
+            .sum();
+        messages + tools
+    }
+
+    /// Pre-flight check before sending `request`: recount what will actually be sent
+    /// so thinning and summarization see the true size, not the running total
+    pub fn preflight(&mut self, request: &CompletionRequest) -> u32 {
+        let request_tokens = self.count_request_tokens(request);
+        if request_tokens != self.used_tokens {
+            debug!(
+                "Pre-flight count with {}: {} tokens (tracked: {})",
+                self.tokenizer.name(),
+                request_tokens,
+                self.used_tokens
+            );
+        }
+        self.used_tokens = request_tokens;
+        request_tokens
     }
 
     pub fn update_usage(&mut self, usage: &g3_providers::Usage) {
@@ -596,6 +658,7 @@ Format this as a detailed but concise summary that can be used to resume the con
         // Clear the conversation history
         self.conversation_history.clear();
         self.used_tokens = 0;
+        self.cache_anchor = None;
 
         // Re-add the original system prompt first (critical invariant)
         if let Some(system_prompt) = original_system_prompt {
@@ -610,7 +673,7 @@ Format this as a detailed but concise summary that can be used to resume the con
         // Add the summary as a system message
         let summary_message = Message::new(
             MessageRole::System,
-            format!("Previous conversation summary:\n\n{}", summary),
+            format!("{}\n\n{}", SUMMARY_PREFIX, summary),
         );
         self.add_message(summary_message);
 
@@ -627,6 +690,100 @@ Format this as a detailed but concise summary that can be used to resume the con
         old_chars.saturating_sub(new_chars)
     }
 
+    /// Remove the message with `message_id` and everything after it.
+    /// Returns the removed messages, or `None` if the id is unknown or
+    /// belongs to a system message (which must stay in place).
+    pub fn rewind_to(&mut self, message_id: &str) -> Option<Vec<Message>> {
+        let index = self
+            .conversation_history
+            .iter()
+            .position(|m| m.id == message_id)?;
+        if matches!(self.conversation_history[index].role, MessageRole::System) {
+            return None;
+        }
+        let removed = self.conversation_history.split_off(index);
+        if self.cache_anchor.is_some_and(|anchor| anchor >= index) {
+            self.cache_anchor = None;
+        }
+        self.recalculate_tokens();
+        Some(removed)
+    }
+
+    /// Messages that should carry a prompt-cache breakpoint, in order. In
+    /// priority order they are: the newest message, the end of the system
+    /// prompt and README, the last summary, the thinning anchor and the
+    /// previous user turn.
+    ///
+    /// The first three only change on compaction. The newest message caches
+    /// the whole conversation for the next request. That request then reads
+    /// it back through the previous-turn breakpoint.
+    pub fn cache_breakpoints(&self) -> Vec<usize> {
+        let history = &self.conversation_history;
+        let Some(newest) = history.len().checked_sub(1) else {
+            return Vec::new();
+        };
+        let is_summary = |message: &Message| {
+            matches!(message.role, MessageRole::System) && message.content.starts_with(SUMMARY_PREFIX)
+        };
+
+        let system_prefix = history
+            .iter()
+            .take_while(|message| matches!(message.role, MessageRole::System) && !is_summary(message))
+            .count()
+            .checked_sub(1);
+        let summary = history.iter().rposition(is_summary);
+        let anchor = self.cache_anchor.filter(|&anchor| anchor < newest);
+        let previous_turn = history[..newest]
+            .iter()
+            .rposition(|message| matches!(message.role, MessageRole::User));
+
+        let mut breakpoints = Vec::new();
+        for index in [Some(newest), system_prefix, summary, anchor, previous_turn]
+            .into_iter()
+            .flatten()
+        {
+            if breakpoints.len() < MAX_CACHE_BREAKPOINTS && !breakpoints.contains(&index) {
+                breakpoints.push(index);
+            }
+        }
+        breakpoints.sort_unstable();
+        breakpoints
+    }
+
+    /// Mark `cache_breakpoints` in `messages` (the history or a request built
+    /// from it) with `cache_control`, clearing any other marks. With `None`,
+    /// all marks are cleared.
+    pub fn apply_cache_breakpoints(
+        &self,
+        messages: &mut [Message],
+        cache_control: Option<&CacheControl>,
+    ) {
+        for message in messages.iter_mut() {
+            message.cache_control = None;
+        }
+        let Some(cache_control) = cache_control else {
+            return;
+        };
+        for index in self.cache_breakpoints() {
+            if let Some(message) = messages.get_mut(index) {
+                message.cache_control = Some(cache_control.clone());
+            }
+        }
+    }
+
+    /// Rough check for what thinning can shrink, used to decide whether it
+    /// can stay clear of the cached prefix
+    fn is_thinnable(&self, index: usize) -> bool {
+        self.conversation_history.get(index).is_some_and(|message| {
+            message.content.len() > 500
+                && match message.role {
+                    MessageRole::User => message.content.starts_with("Tool result:"),
+                    MessageRole::Assistant => message.content.contains("\"tool\""),
+                    MessageRole::System => false,
+                }
+        })
+    }
+
     /// Check if we should trigger context thinning
     /// Triggers at 50%, 60%, 70%, and 80% thresholds
     pub fn should_thin(&self) -> bool {
@@ -656,6 +813,15 @@ Format this as a detailed but concise summary that can be used to resume the con
         let total_messages = self.conversation_history.len();
         let first_third_end = (total_messages / 3).max(1);
 
+        // Rewriting a message invalidates the provider's cache from there on.
+        // Start after the previous pass's anchor if anything is left to thin there.
+        let scan_start = match self.cache_anchor {
+            Some(anchor) if (anchor + 1..first_third_end).any(|i| self.is_thinnable(i)) => {
+                anchor + 1
+            }
+            _ => 0,
+        };
+
         let mut leaned_count = 0;
         let mut tool_call_leaned_count = 0;
         let mut chars_saved = 0;
@@ -671,7 +837,7 @@ Format this as a detailed but concise summary that can be used to resume the con
         }
 
         // Scan the first third of messages
-        for i in 0..first_third_end {
+        for i in scan_start..first_third_end {
             // Check if the previous message was a TODO tool call (before getting mutable reference)
             let is_todo_result = if i > 0 {
                 if let Some(prev_message) = self.conversation_history.get(i - 1) {
@@ -851,6 +1017,9 @@ Format this as a detailed but concise summary that can be used to resume the con
 
         // Recalculate token usage after thinning
         self.recalculate_tokens();
+        if leaned_count > 0 || tool_call_leaned_count > 0 {
+            self.cache_anchor = Some(first_third_end - 1);
+        }
 
         if leaned_count > 0 {
             if tool_call_leaned_count > 0 {
@@ -1078,6 +1247,9 @@ Format this as a detailed but concise summary that can be used to resume the con
 
         // Recalculate token usage after thinning
         self.recalculate_tokens();
+        if leaned_count > 0 || tool_call_leaned_count > 0 {
+            self.cache_anchor = total_messages.checked_sub(1);
+        }
 
         if leaned_count > 0 {
             if tool_call_leaned_count > 0 {
@@ -1110,7 +1282,7 @@ Format this as a detailed but concise summary that can be used to resume the con
     fn recalculate_tokens(&mut self) {
         let mut total = 0;
         for message in &self.conversation_history {
-            total += Self::estimate_tokens(&message.content);
+            total += self.tokenizer.count_message_tokens(message);
         }
         self.used_tokens = total;
 
@@ -1175,6 +1347,22 @@ pub struct Agent<W: UiWriter> {
     requirements_sha: Option<String>,
     /// Working directory for tool execution (set by --codebase-fast-start)
     working_dir: Option<String>,
+    /// Built-in, custom and MCP tools available to the agent
+    tools: tools::ToolRegistry,
+    /// Allow/deny/prompt rules checked before every tool call
+    permissions: permissions::PermissionPolicy,
+    /// Runs shell commands on the configured execution backend
+    executor: g3_execution::CodeExecutor,
+    /// Snapshots of files taken before each editing tool call
+    checkpoints: Mutex<checkpoints::CheckpointStore>,
+    /// The default provider and its fallbacks, in failover order
+    provider_chain: Vec<String>,
+    /// Failovers so far, saved in the session log
+    provider_switches: Vec<session::ProviderSwitch>,
+    /// Spend so far, saved in the session log
+    cost: cost::CostTracker,
+    /// Refuse to send requests once `cost` reaches this many dollars
+    cost_limit_usd: Option<f64>,
 }
 
 impl<W: UiWriter> Agent<W> {
@@ -1244,21 +1432,22 @@ impl<W: UiWriter> Agent<W> {
 
         // In autonomous mode, we need to register both coach and player providers
         // Otherwise, only register the default provider
+        // Fallback providers are registered up front so a failover needs no setup
         let providers_to_register: Vec<String> = if is_autonomous {
-            let mut providers = vec![config.providers.default_provider.clone()];
-            if let Some(coach) = &config.providers.coach {
-                if !providers.contains(coach) {
-                    providers.push(coach.clone());
-                }
-            }
-            if let Some(player) = &config.providers.player {
-                if !providers.contains(player) {
-                    providers.push(player.clone());
+            let mut providers = config.provider_chain();
+            let roles = [&config.providers.coach, &config.providers.player];
+            for chain in roles.into_iter().flatten() {
+                let role_providers =
+                    std::iter::once(chain.primary()).chain(chain.fallbacks().iter().map(String::as_str));
+                for provider in role_providers {
+                    if !providers.iter().any(|p| p == provider) {
+                        providers.push(provider.to_string());
+                    }
                 }
             }
             providers
         } else {
-            vec![config.providers.default_provider.clone()]
+            config.provider_chain()
         };
 
         // Only register providers that are configured AND selected
@@ -1349,10 +1538,67 @@ impl<W: UiWriter> Agent<W> {
             }
         }
 
+        // Register Ollama / llama-server providers from HashMap
+        for (name, ollama_config) in &config.providers.ollama {
+            if should_register("ollama", name) {
+                let mut ollama_provider = g3_providers::OllamaProvider::new_with_name(
+                    format!("ollama.{}", name),
+                    ollama_config.model.clone(),
+                    ollama_config.base_url.clone(),
+                    ollama_config.max_tokens,
+                    ollama_config.temperature,
+                )?
+                .with_server(match ollama_config.server {
+                    g3_config::OllamaServerKind::Ollama => g3_providers::OllamaServer::Ollama,
+                    g3_config::OllamaServerKind::LlamaServer => {
+                        g3_providers::OllamaServer::LlamaServer
+                    }
+                });
+
+                if let Some(context_length) = ollama_config.context_length {
+                    ollama_provider = ollama_provider.with_context_length(context_length);
+                }
+
+                if let Some(native_tool_calling) = ollama_config.native_tool_calling {
+                    ollama_provider = ollama_provider.with_native_tool_calling(native_tool_calling);
+                }
+
+                // Ask the server about anything the config left unset
+                if let Err(e) = ollama_provider.autodetect().await {
+                    let available = ollama_provider.list_models().await.unwrap_or_default();
+                    warn!(
+                        "Could not query model info for ollama.{}: {}. Models on server: {:?}",
+                        name, e, available
+                    );
+                }
+
+                providers.register(ollama_provider);
+            }
+        }
+
+        // Register Gemini providers from HashMap
+        for (name, gemini_config) in &config.providers.gemini {
+            if should_register("gemini", name) {
+                let mut gemini_provider = g3_providers::GeminiProvider::new_with_name(
+                    format!("gemini.{}", name),
+                    gemini_config.api_key.clone(),
+                    Some(gemini_config.model.clone()),
+                    gemini_config.max_tokens,
+                    gemini_config.temperature,
+                )?;
+
+                if let Some(base_url) = &gemini_config.base_url {
+                    gemini_provider = gemini_provider.with_base_url(base_url.clone());
+                }
+
+                providers.register(gemini_provider);
+            }
+        }
+
         // Register Anthropic providers from HashMap
         for (name, anthropic_config) in &config.providers.anthropic {
             if should_register("anthropic", name) {
-                let anthropic_provider = g3_providers::AnthropicProvider::new_with_name(
+                let mut anthropic_provider = g3_providers::AnthropicProvider::new_with_name(
                     format!("anthropic.{}", name),
                     anthropic_config.api_key.clone(),
                     Some(anthropic_config.model.clone()),
@@ -1362,10 +1608,34 @@ impl<W: UiWriter> Agent<W> {
                     anthropic_config.enable_1m_context,
                     anthropic_config.thinking_budget_tokens,
                 )?;
+                if let Some(base_url) = &anthropic_config.base_url {
+                    anthropic_provider = anthropic_provider.with_base_url(base_url.clone());
+                }
                 providers.register(anthropic_provider);
             }
         }
 
+        // Register Bedrock providers from HashMap
+        for (name, bedrock_config) in &config.providers.bedrock {
+            if should_register("bedrock", name) {
+                let mut bedrock_provider = g3_providers::BedrockProvider::new_with_name(
+                    format!("bedrock.{}", name),
+                    bedrock_config.model.clone(),
+                    bedrock_config.region.clone(),
+                    bedrock_config.profile.clone(),
+                    bedrock_config.max_tokens,
+                    bedrock_config.temperature,
+                    bedrock_config.cache_config.clone(),
+                )?;
+
+                if let Some(endpoint_url) = &bedrock_config.endpoint_url {
+                    bedrock_provider = bedrock_provider.with_endpoint_url(endpoint_url.clone());
+                }
+
+                providers.register(bedrock_provider);
+            }
+        }
+
         // Register Databricks providers from HashMap
         for (name, databricks_config) in &config.providers.databricks {
             if should_register("databricks", name) {
@@ -1407,7 +1677,11 @@ impl<W: UiWriter> Agent<W> {
         let mut context_warnings = Vec::new();
         let context_length =
             Self::get_configured_context_length(&config, &providers, &mut context_warnings)?;
-        let mut context_window = ContextWindow::new(context_length);
+        let tokenizer = {
+            let provider = providers.get(None)?;
+            Self::tokenizer_for_provider(provider, config.agent.download_tokenizers)
+        };
+        let mut context_window = ContextWindow::new(context_length).with_tokenizer(tokenizer);
 
         // Surface any context warnings to the user via UI
         for warning in context_warnings {
@@ -1424,7 +1698,7 @@ impl<W: UiWriter> Agent<W> {
             // For native tool calling providers, use a more explicit system prompt
             get_system_prompt_for_native(config.agent.allow_multiple_tool_calls)
         } else {
-            // For non-native providers (embedded models), use JSON format instructions
+            // For non-native providers, use JSON format instructions
             SYSTEM_PROMPT_FOR_NON_NATIVE_TOOL_USE.to_string()
         };
 
@@ -1468,8 +1742,25 @@ impl<W: UiWriter> Agent<W> {
             None
         };
 
+        // Register built-in tools, then any tools from configured MCP servers
+        // (MCP connection failures are logged, not fatal)
+        let mut tools = tools::ToolRegistry::builtin();
+        let mcp = std::sync::Arc::new(mcp::McpManager::connect(&config.mcp).await);
+        for handler in mcp::McpManager::handlers(&mcp) {
+            tools.register(handler);
+        }
+
+        let permissions = permissions::PermissionPolicy::from_config(
+            &config.permissions,
+            &project::current_workspace(),
+        )?;
+
+        let executor = create_code_executor(&config.execution, is_autonomous)?;
+
         // Capture macax_enabled before moving config
         let macax_enabled = config.macax.enabled;
+        let provider_chain = config.provider_chain();
+        let cost_limit_usd = config.budget.max_cost_usd;
 
         Ok(Self {
             providers,
@@ -1503,6 +1794,14 @@ impl<W: UiWriter> Agent<W> {
             tool_call_count: 0,
             requirements_sha: None,
             working_dir: None,
+            tools,
+            permissions,
+            executor,
+            checkpoints: Mutex::new(checkpoints::CheckpointStore::new()),
+            provider_chain,
+            provider_switches: Vec::new(),
+            cost: cost::CostTracker::default(),
+            cost_limit_usd,
         })
     }
 
@@ -1551,13 +1850,24 @@ impl<W: UiWriter> Agent<W> {
         }
     }
 
-    /// Count how many cache_control annotations exist in the conversation history
-    fn count_cache_controls_in_history(&self) -> usize {
-        self.context_window
-            .conversation_history
-            .iter()
-            .filter(|msg| msg.cache_control.is_some())
-            .count()
+    /// Prompt-cache breakpoint type for the current provider, from its
+    /// `cache_config`. `None` if caching is off or unsupported.
+    fn cache_control(&self) -> Option<CacheControl> {
+        let provider = self.providers.get(None).ok()?;
+        if !provider.supports_cache_control() {
+            return None;
+        }
+        let provider_name = provider.name();
+        let provider_type = provider_name.split('.').next().unwrap_or("");
+        let config_name = provider_name.split('.').nth(1).unwrap_or("default");
+        let providers = &self.config.providers;
+        let cache_config = match provider_type {
+            "anthropic" => providers.anthropic.get(config_name)?.cache_config.as_ref(),
+            "bedrock" => providers.bedrock.get(config_name)?.cache_config.as_ref(),
+            "openrouter" => providers.openrouter.get(config_name)?.cache_config.as_ref(),
+            _ => None,
+        }?;
+        Self::parse_cache_control(cache_config)
     }
 
     /// Get the configured max_tokens for a provider from top-level config
@@ -1577,6 +1887,9 @@ impl<W: UiWriter> Agent<W> {
             "databricks" => config.providers.databricks.get(config_name)?.max_tokens,
             "embedded" => config.providers.embedded.get(config_name)?.max_tokens,
             "openrouter" => config.providers.openrouter.get(config_name)?.max_tokens,
+            "ollama" => config.providers.ollama.get(config_name)?.max_tokens,
+            "gemini" => config.providers.gemini.get(config_name)?.max_tokens,
+            "bedrock" => config.providers.bedrock.get(config_name)?.max_tokens,
             _ => {
                 // Check openai_compatible
                 config.providers.openai_compatible.get(provider_type)?.max_tokens
@@ -1601,6 +1914,9 @@ impl<W: UiWriter> Agent<W> {
             "databricks" => config.providers.databricks.get(config_name)?.temperature,
             "embedded" => config.providers.embedded.get(config_name)?.temperature,
             "openrouter" => config.providers.openrouter.get(config_name)?.temperature,
+            "ollama" => config.providers.ollama.get(config_name)?.temperature,
+            "gemini" => config.providers.gemini.get(config_name)?.temperature,
+            "bedrock" => config.providers.bedrock.get(config_name)?.temperature,
             _ => {
                 // Check openai_compatible
                 config.providers.openai_compatible.get(provider_type)?.temperature
@@ -1959,7 +2275,7 @@ impl<W: UiWriter> Agent<W> {
                     400000
                 }
             }
-            "anthropic" => {
+            "anthropic" | "bedrock" => {
                 // Claude models have large context windows
                 if let Some(max_tokens) = Self::provider_max_tokens(config, provider_name) {
                     warnings.push(format!(
@@ -1998,6 +2314,18 @@ impl<W: UiWriter> Agent<W> {
                     128000 // Default for OpenRouter
                 }
             }
+            "ollama" => {
+                // Configured, or reported by the model server at registration
+                provider
+                    .context_length()
+                    .unwrap_or(config.agent.fallback_default_max_tokens as u32)
+            }
+            "gemini" => config
+                .providers
+                .gemini
+                .get(config_name)
+                .and_then(|gemini_config| gemini_config.context_length)
+                .unwrap_or(1_048_576), // Gemini 2.5 models accept 1M input tokens
             _ => config.agent.fallback_default_max_tokens as u32,
         };
 
@@ -2078,6 +2406,53 @@ impl<W: UiWriter> Agent<W> {
         self.session_id.as_deref()
     }
 
+    /// Current contents of the TODO list
+    pub async fn get_todo(&self) -> String {
+        self.todo_content.read().await.clone()
+    }
+
+    /// Restore a session saved by `save_context_window` so the conversation
+    /// continues where it left off. Returns the number of restored messages.
+    pub async fn resume_session(&mut self, session_id: &str) -> Result<usize> {
+        let log = session::SessionLog::load(session_id)?;
+
+        let starts_with_system = matches!(
+            log.conversation_history.first().map(|m| &m.role),
+            Some(MessageRole::System)
+        );
+        if !starts_with_system {
+            return Err(anyhow::anyhow!(
+                "Session '{}' does not start with a system prompt and cannot be resumed",
+                session_id
+            ));
+        }
+
+        // Keep the current provider's context size; the session may have used another
+        let mut context_window = ContextWindow::new(self.context_window.total_tokens)
+            .with_tokenizer(self.context_window.tokenizer().clone());
+        context_window.used_tokens = log.used_tokens;
+        context_window.cumulative_tokens = log.cumulative_tokens;
+        context_window.conversation_history = log.conversation_history;
+        let restored = context_window.conversation_history.len();
+        self.context_window = context_window;
+
+        if let Some(todo) = log.todo {
+            let todo_path = get_todo_path();
+            if todo.trim().is_empty() {
+                let _ = std::fs::remove_file(&todo_path);
+            } else if let Err(e) = std::fs::write(&todo_path, &todo) {
+                warn!("Failed to restore TODO list to {:?}: {}", todo_path, e);
+            }
+            *self.todo_content.write().await = todo;
+        }
+
+        self.provider_switches = log.provider_switches;
+        self.cost = log.cost;
+        self.session_id = Some(log.session_id);
+        info!("Resumed session {} with {} messages", session_id, restored);
+        Ok(restored)
+    }
+
     pub async fn execute_task(
         &mut self,
         description: &str,
@@ -2180,34 +2555,7 @@ impl<W: UiWriter> Agent<W> {
         }
 
         // Add user message to context window
-        let user_message = {
-            // Check if we should use cache control (every 10 tool calls)
-            // But only if we haven't already added 4 cache_control annotations
-            let provider = self.providers.get(None)?;
-            let provider_name = provider.name();
-            let provider_type = provider_name.split('.').next().unwrap_or("");
-            let config_name = provider_name.split('.').nth(1).unwrap_or("default");
-            if let Some(cache_config) = match provider_type {
-                "anthropic" => {
-                    self.config
-                        .providers
-                        .anthropic
-                        .get(config_name)
-                        .and_then(|c| c.cache_config.as_ref())
-                        .and_then(|config| Self::parse_cache_control(config))
-                }
-                _ => None,
-            } {
-                Message::with_cache_control_validated(
-                    MessageRole::User,
-                    format!("Task: {}", description),
-                    cache_config,
-                    provider,
-                )
-            } else {
-                Message::new(MessageRole::User, format!("Task: {}", description))
-            }
-        };
+        let user_message = Message::new(MessageRole::User, format!("Task: {}", description));
         self.context_window.add_message(user_message);
 
         // Execute fast-discovery tool calls if provided (immediately after user message)
@@ -2218,11 +2566,7 @@ impl<W: UiWriter> Agent<W> {
             if let Some(path) = options.fast_start_path {
                 self.working_dir = Some(path.to_string());
             }
-            let provider = self.providers.get(None)?;
-            let supports_cache = provider.supports_cache_control();
-            let message_count = options.messages.len();
-
-            for (idx, discovery_msg) in options.messages.iter().enumerate() {
+            for discovery_msg in options.messages.iter() {
                 if let Ok(tool_call) = serde_json::from_str::<ToolCall>(&discovery_msg.content) {
                     self.add_message_to_context(discovery_msg.clone());
                     let result = self
@@ -2230,20 +2574,8 @@ impl<W: UiWriter> Agent<W> {
                         .await
                         .unwrap_or_else(|e| format!("Error: {}", e));
 
-                    // Add cache_control to the last user message if provider supports it (anthropic)
-                    let is_last = idx == message_count - 1;
-                    let result_message = if supports_cache
-                        && is_last
-                        && self.count_cache_controls_in_history() < 4
-                    {
-                        Message::with_cache_control(
-                            MessageRole::User,
-                            format!("Tool result: {}", result),
-                            CacheControl::ephemeral(),
-                        )
-                    } else {
-                        Message::new(MessageRole::User, format!("Tool result: {}", result))
-                    };
+                    let result_message =
+                        Message::new(MessageRole::User, format!("Tool result: {}", result));
                     self.add_message_to_context(result_message);
                 }
             }
@@ -2258,11 +2590,7 @@ impl<W: UiWriter> Agent<W> {
         let _has_native_tool_calling = provider.has_native_tool_calling();
         let _supports_cache_control = provider.supports_cache_control();
         let tools = if provider.has_native_tool_calling() {
-            Some(Self::create_tool_definitions(
-                self.config.webdriver.enabled,
-                self.config.macax.enabled,
-                self.config.computer_control.enabled,
-            ))
+            Some(self.tool_definitions())
         } else {
             None
         };
@@ -2314,6 +2642,7 @@ impl<W: UiWriter> Agent<W> {
             prompt_tokens: 100,                                   // Estimate
             completion_tokens: response_content.len() as u32 / 4, // Rough estimate
             total_tokens: 100 + (response_content.len() as u32 / 4),
+            ..Default::default()
         };
 
         // Update context window with estimated token usage
@@ -2347,6 +2676,110 @@ impl<W: UiWriter> Agent<W> {
         Ok(task_result)
     }
 
+    /// Task messages in the conversation, oldest first, as (message id, task text)
+    pub fn user_turns(&self) -> Vec<(&str, &str)> {
+        self.context_window
+            .conversation_history
+            .iter()
+            .filter(|m| matches!(m.role, MessageRole::User))
+            .filter_map(|m| {
+                m.content
+                    .strip_prefix("Task: ")
+                    .map(|task| (m.id.as_str(), task))
+            })
+            .collect()
+    }
+
+    /// Cut the conversation back to just before the target message, optionally
+    /// reverting the file edits made from that point on
+    pub fn rewind(
+        &mut self,
+        target: &session::RewindTarget,
+        revert_files: bool,
+    ) -> Result<session::RewindSummary> {
+        let message_id = match target {
+            session::RewindTarget::Turns(turns) => {
+                let user_turns = self.user_turns();
+                if *turns == 0 || *turns > user_turns.len() {
+                    return Err(anyhow::anyhow!(
+                        "Cannot rewind {} turn(s); the conversation has {}",
+                        turns,
+                        user_turns.len()
+                    ));
+                }
+                user_turns[user_turns.len() - turns].0.to_string()
+            }
+            session::RewindTarget::Message(id) => id.clone(),
+        };
+
+        // A tool call's checkpoint is anchored to the message before the
+        // assistant message that issued it, because that message is only
+        // added once the call has run
+        let history = &self.context_window.conversation_history;
+        let previous_id = history
+            .iter()
+            .position(|m| m.id == message_id)
+            .and_then(|index| index.checked_sub(1))
+            .map(|index| history[index].id.clone());
+
+        let removed = self.context_window.rewind_to(&message_id).ok_or_else(|| {
+            anyhow::anyhow!("No user or assistant message with id '{}'", message_id)
+        })?;
+
+        let reverted_files = if revert_files {
+            let mut removed_ids: std::collections::HashSet<&str> =
+                removed.iter().map(|m| m.id.as_str()).collect();
+            removed_ids.extend(previous_id.as_deref());
+            self.checkpoints
+                .get_mut()
+                .unwrap()
+                .revert_anchored_to(&removed_ids)?
+        } else {
+            Vec::new()
+        };
+
+        self.save_context_window("rewound");
+        info!(
+            "Rewound {} messages to before {}, reverted {} files",
+            removed.len(),
+            message_id,
+            reverted_files.len()
+        );
+
+        Ok(session::RewindSummary {
+            removed_messages: removed.len(),
+            reverted_files,
+        })
+    }
+
+    /// Restore the files changed by the most recent editing tool call.
+    /// Returns the undone checkpoint, or `None` if there is nothing to undo.
+    pub fn undo_last_edit(&mut self) -> Result<Option<checkpoints::Checkpoint>> {
+        let undone = self.checkpoints.get_mut().unwrap().undo_last()?;
+        if let Some(checkpoint) = &undone {
+            info!(
+                "Undid {} edit of {} file(s)",
+                checkpoint.tool,
+                checkpoint.files.len()
+            );
+        }
+        Ok(undone)
+    }
+
+    /// Continue in a new session that starts from the current conversation.
+    /// The original session log is left as it was, so it can still be resumed.
+    pub fn fork_session(&mut self) -> String {
+        let base = self
+            .session_id
+            .as_deref()
+            .map(|id| id.split("_fork_").next().unwrap_or(id).to_string())
+            .unwrap_or_else(|| "session".to_string());
+        let fork_id = format!("{}_fork_{}", base, Local::now().format("%H%M%S"));
+        self.session_id = Some(fork_id.clone());
+        self.save_context_window("forked");
+        fork_id
+    }
+
     /// Generate a session ID based on the initial prompt
     fn generate_session_id(&self, description: &str) -> String {
         use std::collections::hash_map::DefaultHasher;
@@ -2407,9 +2840,17 @@ impl<W: UiWriter> Agent<W> {
             "context_window": {
                 "used_tokens": self.context_window.used_tokens,
                 "total_tokens": self.context_window.total_tokens,
+                "cumulative_tokens": self.context_window.cumulative_tokens,
                 "percentage_used": self.context_window.percentage_used(),
-                "conversation_history": self.context_window.conversation_history
-            }
+                "conversation_history": self.context_window.conversation_history,
+                "message_ids": self.context_window.conversation_history
+                    .iter()
+                    .map(|m| m.id.as_str())
+                    .collect::<Vec<_>>()
+            },
+            "todo": self.todo_content.try_read().ok().map(|todo| todo.clone()),
+            "provider_switches": self.provider_switches,
+            "cost": self.cost
         });
 
         match serde_json::to_string_pretty(&context_data) {
@@ -2491,8 +2932,8 @@ impl<W: UiWriter> Agent<W> {
         for message in &self.context_window.conversation_history {
             let _timestamp = chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
 
-            // Estimate tokens for this message
-            let message_tokens = ContextWindow::estimate_tokens(&message.content);
+            // Count tokens for this message
+            let message_tokens = self.context_window.count_tokens(&message.content);
 
             // Format token count
             let token_str = Self::format_token_count(message_tokens);
@@ -2761,6 +3202,7 @@ impl<W: UiWriter> Agent<W> {
         // Get the summary
         match provider.complete(summary_request).await {
             Ok(summary_response) => {
+                self.record_usage(&summary_response.usage);
                 self.ui_writer
                     .print_context_status("✅ Context compacted successfully.\n");
 
@@ -2944,6 +3386,50 @@ impl<W: UiWriter> Agent<W> {
         }
         stats.push('\n');
 
+        // Spend
+        stats.push_str("💰 Cost:\n");
+        stats.push_str(&format!(
+            "   • Session Total:     {:>10}\n",
+            cost::format_usd(self.cost.total_usd)
+        ));
+        if let Some(limit) = self.cost_limit_usd {
+            stats.push_str(&format!(
+                "   • Budget:            {:>10}\n",
+                cost::format_usd(limit)
+            ));
+        }
+        stats.push_str(&format!(
+            "   • Cached Input:      {:>10} tokens read, {} written\n",
+            self.cost.cache_read_tokens, self.cost.cache_creation_tokens
+        ));
+        if let Some(hit_rate) = self.cost.cache_hit_rate() {
+            stats.push_str(&format!(
+                "   • Cache Hit Rate:    {:>9.1}% of prompt tokens\n",
+                hit_rate * 100.0
+            ));
+        }
+        if self.cache_control().is_some() {
+            stats.push_str(&format!(
+                "   • Cache Breakpoints: {:>10}\n",
+                self.context_window
+                    .cache_breakpoints()
+                    .iter()
+                    .map(|index| index.to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ));
+        }
+        for (model, spent) in &self.cost.by_model {
+            stats.push_str(&format!("   • {}: {}\n", model, cost::format_usd(*spent)));
+        }
+        if self.cost.unpriced_requests > 0 {
+            stats.push_str(&format!(
+                "   • Unpriced Requests: {:>10} (no [pricing] entry)\n",
+                self.cost.unpriced_requests
+            ));
+        }
+        stats.push('\n');
+
         // Conversation history
         stats.push_str("💬 Conversation History:\n");
         stats.push_str(&format!(
@@ -3028,10 +3514,89 @@ impl<W: UiWriter> Agent<W> {
         &self.tool_call_metrics
     }
 
+    /// Spend so far in this session
+    pub fn cost(&self) -> &cost::CostTracker {
+        &self.cost
+    }
+
+    /// Limit this session's spend (`None` removes the limit).
+    /// Defaults to `budget.max_cost_usd`.
+    pub fn set_cost_limit(&mut self, limit_usd: Option<f64>) {
+        self.cost_limit_usd = limit_usd;
+    }
+
+    pub fn cost_limit(&self) -> Option<f64> {
+        self.cost_limit_usd
+    }
+
+    /// Price `usage` for the current provider and add it to the session's spend
+    fn record_usage(&mut self, usage: &g3_providers::Usage) {
+        let Ok(provider) = self.providers.get(None) else {
+            return;
+        };
+        let (provider_name, model) = (provider.name().to_string(), provider.model().to_string());
+        let pricing = self.config.pricing_for(&provider_name, &model);
+        let cost = self.cost.record(&model, usage, pricing);
+        debug!(
+            "Request cost {} (session total {})",
+            cost::format_usd(cost),
+            cost::format_usd(self.cost.total_usd)
+        );
+    }
+
+    /// Account for a finished stream, estimating its usage if the provider sent none
+    fn record_stream_usage(
+        &mut self,
+        usage: Option<g3_providers::Usage>,
+        response: &str,
+        request: &CompletionRequest,
+    ) {
+        if let Some(usage) = usage {
+            debug!("Updating context window with actual usage from stream");
+            self.context_window.update_usage_from_response(&usage);
+            self.record_usage(&usage);
+        } else {
+            // Fall back to estimation if no usage data was provided
+            debug!("No usage data from stream, using estimation");
+            let estimated_tokens = self.context_window.count_tokens(response);
+            self.context_window.add_streaming_tokens(estimated_tokens);
+            let prompt_tokens = self.context_window.count_request_tokens(request);
+            self.record_usage(&g3_providers::Usage {
+                prompt_tokens,
+                completion_tokens: estimated_tokens,
+                total_tokens: prompt_tokens + estimated_tokens,
+                ..Default::default()
+            });
+        }
+    }
+
+    /// Fail with `BudgetExceeded` once the session has spent its limit
+    fn check_budget(&self) -> Result<()> {
+        match self.cost_limit_usd {
+            Some(limit_usd) if self.cost.total_usd >= limit_usd => {
+                Err(anyhow::Error::new(cost::BudgetExceeded {
+                    spent_usd: self.cost.total_usd,
+                    limit_usd,
+                }))
+            }
+            _ => Ok(()),
+        }
+    }
+
     pub fn get_config(&self) -> &Config {
         &self.config
     }
 
+    /// Tools the agent can call. Use `tool_registry_mut` to register custom
+    /// tools or disable built-ins for a particular mode.
+    pub fn tool_registry(&self) -> &tools::ToolRegistry {
+        &self.tools
+    }
+
+    pub fn tool_registry_mut(&mut self) -> &mut tools::ToolRegistry {
+        &mut self.tools
+    }
+
     pub fn set_requirements_sha(&mut self, sha: String) {
         self.requirements_sha = Some(sha);
     }
@@ -3045,590 +3610,24 @@ impl<W: UiWriter> Agent<W> {
             .await
     }
 
-    /// Create tool definitions for native tool calling providers
-    fn create_tool_definitions(
-        enable_webdriver: bool,
-        enable_macax: bool,
-        enable_computer_control: bool,
-    ) -> Vec<Tool> {
-        let mut tools = vec![
-            Tool {
-                name: "shell".to_string(),
-                description: "Execute shell commands".to_string(),
-                input_schema: json!({
-                    "type": "object",
-                    "properties": {
-                        "command": {
-                            "type": "string",
-                            "description": "The shell command to execute"
-                        }
-                    },
-                    "required": ["command"]
-                }),
-            },
-            Tool {
-                name: "read_file".to_string(),
-                description: "Read the contents of a file. For image files (png, jpg, jpeg, gif, bmp, tiff, webp), automatically extracts text using OCR. For text files, optionally read a specific character range.".to_string(),
-                input_schema: json!({
-                    "type": "object",
-                    "properties": {
-                        "file_path": {
-                            "type": "string",
-                            "description": "The path to the file to read"
-                        },
-                        "start": {
-                            "type": "integer",
-                            "description": "Starting character position (0-indexed, inclusive). If omitted, reads from beginning."
-                        },
-                        "end": {
-                            "type": "integer",
-                            "description": "Ending character position (0-indexed, EXCLUSIVE). If omitted, reads to end of file."
-                        }
-                    },
-                    "required": ["file_path"]
-                }),
-            },
-            Tool {
-                name: "write_file".to_string(),
-                description: "Write content to a file (creates or overwrites). You MUST provide all arguments".to_string(),
-                input_schema: json!({
-                    "type": "object",
-                    "properties": {
-                        "file_path": {
-                            "type": "string",
-                            "description": "The path to the file to write"
-                        },
-                        "content": {
-                            "type": "string",
-                            "description": "The content to write to the file"
-                        }
-                    },
-                    "required": ["file_path", "content"]
-                }),
-            },
-            Tool {
-                name: "str_replace".to_string(),
-                description: "Apply a unified diff to a file. Supports multiple hunks and context lines. Optionally constrain the search to a [start, end) character range (0-indexed; end is EXCLUSIVE). Useful to disambiguate matches or limit scope in large files.".to_string(),
-                input_schema: json!({
-                    "type": "object",
-                    "properties": {
-                        "file_path": {
-                            "type": "string",
-                            "description": "The path to the file to edit"
-                        },
-                        "diff": {
-                            "type": "string",
-                            "description": "A unified diff showing what to replace. Supports @@ hunk headers, context lines, and multiple hunks (---/+++ headers optional for minimal diffs)."
-                        },
-                        "start": {
-                            "type": "integer",
-                            "description": "Starting character position in the file (0-indexed, inclusive). If omitted, searches from beginning."
-                        },
-                        "end": {
-                            "type": "integer",
-                            "description": "Ending character position in the file (0-indexed, EXCLUSIVE - character at this position is NOT included). If omitted, searches to end of file."
-                        }
-                    },
-                    "required": ["file_path", "diff"]
-                }),
-            },
-            Tool {
-                name: "final_output".to_string(),
-                description: "Signal task completion with a detailed summary".to_string(),
-                input_schema: json!({
-                    "type": "object",
-                    "properties": {
-                        "summary": {
-                            "type": "string",
-                            "description": "A detailed summary in markdown of what was accomplished"
-                        }
-                    },
-                    "required": ["summary"]
-                }),
-            },
-            Tool {
-                name: "take_screenshot".to_string(),
-                description: "Capture a screenshot of a specific application window. You MUST specify the window_id parameter with the application name (e.g., 'Safari', 'Terminal', 'Google Chrome'). The tool will automatically use the native screencapture command with the application's window ID for a clean capture. Use list_windows first to identify available windows.".to_string(),
-                input_schema: json!({
-                    "type": "object",
-                    "properties": {
-                        "path": {
-                            "type": "string",
-                            "description": "Filename for the screenshot (e.g., 'safari.png'). If a relative path is provided, the screenshot will be saved to ~/tmp or $TMPDIR. Use an absolute path to save elsewhere."
-                        },
-                        "window_id": {
-                            "type": "string",
-                            "description": "REQUIRED: Application name to capture (e.g., 'Safari', 'Terminal', 'Google Chrome'). The tool will capture the frontmost window of that application using its native window ID."
-                        },
-                        "region": {
-                            "type": "object",
-                            "properties": {
-                                "x": {"type": "integer"},
-                                "y": {"type": "integer"},
-                                "width": {"type": "integer"},
-                                "height": {"type": "integer"}
-                            }
-                        }
-                    },
-                    "required": ["path", "window_id"]
-                }),
-            },
-            Tool {
-                name: "extract_text".to_string(),
-                description: "Extract text from an image file using OCR. For extracting text from a specific window, use vision_find_text instead which automatically handles window capture.".to_string(),
-                input_schema: json!({
-                    "type": "object",
-                    "properties": {
-                        "path": {
-                            "type": "string",
-                            "description": "Path to image file (optional if region is provided)"
-                        },
-                    }
-                }),
-            },
-            Tool {
-                name: "todo_read".to_string(),
-                description: "Read your current TODO list from todo.g3.md file in the workspace directory. Shows what tasks are planned and their status. Call this at the start of multi-step tasks to check for existing plans, and during execution to review progress before updating. TODO lists persist across g3 sessions.".to_string(),
-                input_schema: json!({
-                    "type": "object",
-                    "properties": {},
-                    "required": []
-                }),
-            },
-            Tool {
-                name: "todo_write".to_string(),
-                description: "Create or update your TODO list in todo.g3.md file with a complete task plan. Use markdown checkboxes: - [ ] for pending, - [x] for complete. This tool replaces the entire file content, so always call todo_read first to preserve existing content. Essential for multi-step tasks. Changes persist across g3 sessions.".to_string(),
-                input_schema: json!({
-                    "type": "object",
-                    "properties": {
-                        "content": {
-                            "type": "string",
-                            "description": "The TODO list content to save. Use markdown checkbox format: - [ ] for incomplete tasks, - [x] for completed tasks. Support nested tasks with indentation."
-                        }
-                    },
-                    "required": ["content"]
-                }),
-            },
-            Tool {
-                name: "code_coverage".to_string(),
-                description: "Generate a code coverage report for the entire workspace using cargo llvm-cov. This runs all tests with coverage instrumentation and returns a summary of coverage statistics. Requires llvm-tools-preview and cargo-llvm-cov to be installed (they will be auto-installed if missing).".to_string(),
-                input_schema: json!({
-                    "type": "object",
-                    "properties": {},
-                    "required": []
-                }),
-            },
-        ];
-
-        // Add code_search tool
-        tools.push(Tool {
-            name: "code_search".to_string(),
-            description: "Syntax-aware code search that understands code structure, not just text. Finds actual functions, classes, methods, and other code constructs - ignores matches in comments and strings. Much more accurate than grep for code searches. Supports batch searches (up to 20 parallel) with structured results and context lines. Languages: Rust, Python, JavaScript, TypeScript, Go, Java, C, C++, Kotlin. Uses tree-sitter query syntax.".to_string(),
-            input_schema: json!({
-                "type": "object",
-                "properties": {
-                    "searches": {
-                        "type": "array",
-                        "maxItems": 20,
-                        "items": {
-                            "type": "object",
-                            "properties": {
-                                "name": { "type": "string", "description": "Label for this search." },
-                                "query": { "type": "string", "description": "tree-sitter query in S-expression format (e.g., \"(function_item name: (identifier) @name)\")"},
-                                "language": { "type": "string", "enum": ["rust", "python", "javascript", "typescript", "go", "java", "c", "cpp", "kotlin"], "description": "Programming language to search." },
-                                "paths": { "type": "array", "items": { "type": "string" }, "description": "Paths/dirs to search. Defaults to current dir if empty." },
-                                "context_lines": { "type": "integer", "minimum": 0, "maximum": 20, "default": 0, "description": "Lines of context to include around each match." }
-                            },
-                            "required": ["name", "query", "language"]
-                        }
-                    },
-                    "max_concurrency": { "type": "integer", "minimum": 1, "default": 4 },
-                    "max_matches_per_search": { "type": "integer", "minimum": 1, "default": 500 }
-                },
-                "required": ["searches"]
-            }),
-        });
+    /// Tool definitions sent to providers with native tool calling
+    fn tool_definitions(&self) -> Vec<Tool> {
+        self.tools.definitions(&self.config)
+    }
 
-        // Add WebDriver tools if enabled
-        if enable_webdriver {
-            tools.extend(vec![
-                Tool {
-                    name: "webdriver_start".to_string(),
-                    description: "Start a Safari WebDriver session for browser automation. Must be called before any other webdriver tools. Requires Safari's 'Allow Remote Automation' to be enabled in Develop menu.".to_string(),
-                    input_schema: json!({
-                        "type": "object",
-                        "properties": {},
-                        "required": []
-                    }),
-                },
-                Tool {
-                    name: "webdriver_navigate".to_string(),
-                    description: "Navigate to a URL in the browser".to_string(),
-                    input_schema: json!({
-                        "type": "object",
-                        "properties": {
-                            "url": {
-                                "type": "string",
-                                "description": "The URL to navigate to (must include protocol, e.g., https://)"
-                            }
-                        },
-                        "required": ["url"]
-                    }),
-                },
-                Tool {
-                    name: "webdriver_get_url".to_string(),
-                    description: "Get the current URL of the browser".to_string(),
-                    input_schema: json!({
-                        "type": "object",
-                        "properties": {},
-                        "required": []
-                    }),
-                },
-                Tool {
-                    name: "webdriver_get_title".to_string(),
-                    description: "Get the title of the current page".to_string(),
-                    input_schema: json!({
-                        "type": "object",
-                        "properties": {},
-                        "required": []
-                    }),
-                },
-                Tool {
-                    name: "webdriver_find_element".to_string(),
-                    description: "Find an element on the page by CSS selector and return its text content".to_string(),
-                    input_schema: json!({
-                        "type": "object",
-                        "properties": {
-                            "selector": {
-                                "type": "string",
-                                "description": "CSS selector to find the element (e.g., 'h1', '.class-name', '#id')"
-                            }
-                        },
-                        "required": ["selector"]
-                    }),
-                },
-                Tool {
-                    name: "webdriver_find_elements".to_string(),
-                    description: "Find all elements matching a CSS selector and return their text content".to_string(),
-                    input_schema: json!({
-                        "type": "object",
-                        "properties": {
-                            "selector": {
-                                "type": "string",
-                                "description": "CSS selector to find elements"
-                            }
-                        },
-                        "required": ["selector"]
-                    }),
-                },
-                Tool {
-                    name: "webdriver_click".to_string(),
-                    description: "Click an element on the page".to_string(),
-                    input_schema: json!({
-                        "type": "object",
-                        "properties": {
-                            "selector": {
-                                "type": "string",
-                                "description": "CSS selector for the element to click"
-                            }
-                        },
-                        "required": ["selector"]
-                    }),
-                },
-                Tool {
-                    name: "webdriver_send_keys".to_string(),
-                    description: "Type text into an input element".to_string(),
-                    input_schema: json!({
-                        "type": "object",
-                        "properties": {
-                            "selector": {
-                                "type": "string",
-                                "description": "CSS selector for the input element"
-                            },
-                            "text": {
-                                "type": "string",
-                                "description": "Text to type into the element"
-                            },
-                            "clear_first": {
-                                "type": "boolean",
-                                "description": "Whether to clear the element before typing (default: true)"
-                            }
-                        },
-                        "required": ["selector", "text"]
-                    }),
-                },
-                Tool {
-                    name: "webdriver_execute_script".to_string(),
-                    description: "Execute JavaScript code in the browser and return the result".to_string(),
-                    input_schema: json!({
-                        "type": "object",
-                        "properties": {
-                            "script": {
-                                "type": "string",
-                                "description": "JavaScript code to execute (use 'return' to return a value)"
-                            }
-                        },
-                        "required": ["script"]
-                    }),
-                },
-                Tool {
-                    name: "webdriver_get_page_source".to_string(),
-                    description: "Get the rendered HTML source of the current page. Returns the current DOM state after JavaScript execution.".to_string(),
-                    input_schema: json!({
-                        "type": "object",
-                        "properties": {
-                            "max_length": {
-                                "type": "integer",
-                                "description": "Maximum length of HTML to return (default: 10000, use 0 for no truncation)"
-                            },
-                            "save_to_file": {
-                                "type": "string",
-                                "description": "Optional file path to save the HTML instead of returning it inline"
-                            }
-                        },
-                        "required": []
-                    }),
-                },
-                Tool {
-                    name: "webdriver_screenshot".to_string(),
-                    description: "Take a screenshot of the browser window".to_string(),
-                    input_schema: json!({
-                        "type": "object",
-                        "properties": {
-                            "path": {
-                                "type": "string",
-                                "description": "Path where to save the screenshot (e.g., '/tmp/screenshot.png')"
-                            }
-                        },
-                        "required": ["path"]
-                    }),
-                },
-                Tool {
-                    name: "webdriver_back".to_string(),
-                    description: "Navigate back in browser history".to_string(),
-                    input_schema: json!({
-                        "type": "object",
-                        "properties": {},
-                        "required": []
-                    }),
-                },
-                Tool {
-                    name: "webdriver_forward".to_string(),
-                    description: "Navigate forward in browser history".to_string(),
-                    input_schema: json!({
-                        "type": "object",
-                        "properties": {},
-                        "required": []
-                    }),
-                },
-                Tool {
-                    name: "webdriver_refresh".to_string(),
-                    description: "Refresh the current page".to_string(),
-                    input_schema: json!({
-                        "type": "object",
-                        "properties": {},
-                        "required": []
-                    }),
-                },
-                Tool {
-                    name: "webdriver_quit".to_string(),
-                    description: "Close the browser and end the WebDriver session".to_string(),
-                    input_schema: json!({
-                        "type": "object",
-                        "properties": {},
-                        "required": []
-                    }),
-                },
-            ]);
-        }
-
-        // Add macOS Accessibility tools if enabled
-        if enable_macax {
-            tools.extend(vec![
-                Tool {
-                    name: "macax_list_apps".to_string(),
-                    description: "List all running applications that can be controlled via macOS Accessibility API".to_string(),
-                    input_schema: json!({
-                        "type": "object",
-                        "properties": {},
-                        "required": []
-                    }),
-                },
-                Tool {
-                    name: "macax_get_frontmost_app".to_string(),
-                    description: "Get the name of the currently active (frontmost) application".to_string(),
-                    input_schema: json!({
-                        "type": "object",
-                        "properties": {},
-                        "required": []
-                    }),
-                },
-                Tool {
-                    name: "macax_activate_app".to_string(),
-                    description: "Bring an application to the front (activate it)".to_string(),
-                    input_schema: json!({
-                        "type": "object",
-                        "properties": {
-                            "app_name": {
-                                "type": "string",
-                                "description": "Name of the application to activate (e.g., 'Safari', 'TextEdit')"
-                            }
-                        },
-                        "required": ["app_name"]
-                    }),
-                },
-                Tool {
-                    name: "macax_press_key".to_string(),
-                    description: "Press a keyboard key or shortcut in an application (e.g., Cmd+S to save)".to_string(),
-                    input_schema: json!({
-                        "type": "object",
-                        "properties": {
-                            "app_name": {
-                                "type": "string",
-                                "description": "Name of the application"
-                            },
-                            "key": {
-                                "type": "string",
-                                "description": "Key to press (e.g., 's', 'return', 'tab')"
-                            },
-                            "modifiers": {
-                                "type": "array",
-                                "items": {
-                                    "type": "string"
-                                },
-                                "description": "Modifier keys (e.g., ['command', 'shift'])"
-                            }
-                        },
-                        "required": ["app_name", "key"]
-                    }),
-                },
-            ]);
-
-            // Add type_text tool for typing arbitrary text
-            tools.push(Tool {
-                name: "macax_type_text".to_string(),
-                description: "Type arbitrary text into the currently focused element in an application (supports unicode, emojis, etc.)".to_string(),
-                input_schema: json!({
-                    "type": "object",
-                    "properties": {
-                        "app_name": {
-                            "type": "string",
-                            "description": "Name of the application"
-                        },
-                        "text": {
-                            "type": "string",
-                            "description": "Text to type (can include unicode, emojis, special characters)"
-                        }
-                    },
-                    "required": ["app_name", "text"]
-                }),
-            });
-        }
-
-        // Add extract_text_with_boxes tool (requires macax flag)
-        if enable_macax {
-            tools.push(Tool {
-                name: "extract_text_with_boxes".to_string(),
-                description: "Extract all text from an image file with bounding box coordinates for each text element. Returns JSON array with text, position (x, y), size (width, height), and confidence for each detected text. Uses Apple Vision Framework for precise sub-pixel accuracy.".to_string(),
-                input_schema: json!({
-                    "type": "object",
-                    "properties": {
-                        "path": {
-                            "type": "string",
-                            "description": "Path to image file to extract text from"
-                        },
-                        "app_name": {
-                            "type": "string",
-                            "description": "Optional: Name of application to screenshot first (e.g., 'Safari', 'Things3'). If provided, takes screenshot of app before extracting text."
-                        }
-                    },
-                    "required": ["path"]
-                }),
-            });
-        }
-
-        // Add vision-guided tools (requires computer control)
-        if enable_computer_control {
-            // Add vision-guided tools
-            tools.push(Tool {
-                name: "vision_find_text".to_string(),
-                description: "Find text in a specific application window and return its location with bounding box coordinates (x, y, width, height) and confidence score. Useful for locating UI elements. Uses Apple Vision Framework for precise sub-pixel accuracy.".to_string(),
-                input_schema: json!({
-                    "type": "object",
-                    "properties": {
-                        "app_name": {
-                            "type": "string",
-                            "description": "Name of the application to search in (e.g., 'Things3', 'Safari', 'TextEdit')"
-                        },
-                        "text": {
-                            "type": "string",
-                            "description": "The text to search for on screen"
-                        }
-                    },
-                    "required": ["app_name", "text"]
-                }),
-            });
-
-            tools.push(Tool {
-                name: "vision_click_text".to_string(),
-                description: "Find text in a specific application window and click on it (useful for clicking buttons, links, menu items)".to_string(),
-                input_schema: json!({
-                    "type": "object",
-                    "properties": {
-                        "app_name": {
-                            "type": "string",
-                            "description": "Name of the application (e.g., 'Things3', 'Safari', 'TextEdit')"
-                        },
-                        "text": {
-                            "type": "string",
-                            "description": "The text to click on (e.g., 'Submit', 'OK', 'Cancel', '+')"
-                        }
-                    },
-                    "required": ["app_name", "text"]
-                }),
-            });
-
-            tools.push(Tool {
-                name: "vision_click_near_text".to_string(),
-                description: "Find text in a specific application window and click near it (useful for clicking text fields next to labels)".to_string(),
-                input_schema: json!({
-                    "type": "object",
-                    "properties": {
-                        "app_name": {
-                            "type": "string",
-                            "description": "Name of the application (e.g., 'Things3', 'Safari', 'TextEdit')"
-                        },
-                        "text": {
-                            "type": "string",
-                            "description": "The label text to find (e.g., 'Name:', 'Email:', 'Task:')"
-                        },
-                        "direction": {
-                            "type": "string",
-                            "enum": ["right", "below", "left", "above"],
-                            "description": "Direction to click relative to the text (default: right)"
-                        },
-                        "distance": {
-                            "type": "integer",
-                            "description": "Distance in pixels from the text (default: 50)"
-                        }
-                    },
-                    "required": ["app_name", "text"]
-                }),
-            });
-        }
-
-        tools
-    }
-
-    /// Helper method to stream with retry logic
-    async fn stream_with_retry(
-        &self,
-        request: &CompletionRequest,
-        error_context: &error_handling::ErrorContext,
-    ) -> Result<g3_providers::CompletionStream> {
-        use crate::error_handling::{calculate_retry_delay, classify_error, ErrorType};
+    /// Helper method to stream with retry logic
+    ///
+    /// After `agent.provider_failover_after` recoverable errors in a row, the
+    /// agent fails over to the next provider in its chain for the rest of the session.
+    async fn stream_with_retry(
+        &mut self,
+        request: &mut CompletionRequest,
+        error_context: &error_handling::ErrorContext,
+    ) -> Result<g3_providers::CompletionStream> {
+        use crate::error_handling::{calculate_retry_delay, classify_error, ErrorType};
 
         let mut attempt = 0;
+        let mut consecutive_failures = 0;
         let max_attempts = if self.is_autonomous {
             self.config.agent.autonomous_max_retry_attempts
         } else {
@@ -3637,9 +3636,14 @@ impl<W: UiWriter> Agent<W> {
 
         loop {
             attempt += 1;
+            // Placed per attempt, since a failover may change the provider
+            let cache_control = self.cache_control();
+            self.context_window
+                .apply_cache_breakpoints(&mut request.messages, cache_control.as_ref());
             let provider = self.providers.get(None)?;
+            let result = provider.stream(request.clone()).await;
 
-            match provider.stream(request.clone()).await {
+            match result {
                 Ok(stream) => {
                     if attempt > 1 {
                         info!("Stream started successfully after {} attempts", attempt);
@@ -3653,25 +3657,154 @@ impl<W: UiWriter> Agent<W> {
                     );
                     return Ok(stream);
                 }
-                Err(e) if attempt < max_attempts => {
-                    if matches!(classify_error(&e), ErrorType::Recoverable(_)) {
-                        let delay = calculate_retry_delay(attempt, self.is_autonomous);
-                        warn!(
-                            "Recoverable error on attempt {}/{}: {}. Retrying in {:?}...",
-                            attempt, max_attempts, e, delay
-                        );
-                        tokio::time::sleep(delay).await;
-                    } else {
+                Err(e) => {
+                    let ErrorType::Recoverable(recoverable_type) = classify_error(&e) else {
+                        error_context.clone().log_error(&e);
+                        return Err(e);
+                    };
+
+                    consecutive_failures += 1;
+                    if recoverable_type.warrants_failover()
+                        && consecutive_failures >= self.config.agent.provider_failover_after
+                    {
+                        if let Some(next) = self.next_fallback_provider() {
+                            self.switch_provider(&next, &e.to_string(), request)?;
+                            attempt = 0;
+                            consecutive_failures = 0;
+                            continue;
+                        }
+                    }
+
+                    if attempt >= max_attempts {
                         error_context.clone().log_error(&e);
                         return Err(e);
                     }
+
+                    let delay = calculate_retry_delay(attempt, self.is_autonomous);
+                    warn!(
+                        "Recoverable error on attempt {}/{}: {}. Retrying in {:?}...",
+                        attempt, max_attempts, e, delay
+                    );
+                    tokio::time::sleep(delay).await;
                 }
-                Err(e) => {
-                    error_context.clone().log_error(&e);
-                    return Err(e);
+            }
+        }
+    }
+
+    /// The provider after the active one in the failover chain, if any
+    fn next_fallback_provider(&self) -> Option<String> {
+        let active = self.providers.get(None).ok()?.name().to_string();
+        let position = self.provider_chain.iter().position(|p| *p == active)?;
+        self.provider_chain.get(position + 1).cloned()
+    }
+
+    /// The best tokenizer available for `provider`. With `download`, known OpenAI
+    /// encodings that are not cached yet are fetched in the background for future
+    /// sessions; otherwise uncached encodings fall back to an estimate.
+    fn tokenizer_for_provider(
+        provider: &dyn g3_providers::LLMProvider,
+        download: bool,
+    ) -> Arc<dyn Tokenizer> {
+        let encoding = g3_providers::tokenizer::bpe_encoding_for_model(provider.model())
+            .filter(|_| download);
+        if let Some(encoding) = encoding.filter(|e| !BpeTokenizer::cache_path(e).exists()) {
+            if let Ok(handle) = tokio::runtime::Handle::try_current() {
+                handle.spawn(async move {
+                    if let Err(e) = BpeTokenizer::download(encoding).await {
+                        debug!("Could not download {} tokenizer: {}", encoding, e);
+                    }
+                });
+            }
+        }
+
+        let tokenizer = g3_providers::tokenizer_for(provider);
+        debug!("Counting tokens with {}", tokenizer.name());
+        tokenizer
+    }
+
+    /// Fail over to another registered provider mid-session.
+    ///
+    /// Tool-call instructions in the system prompt, cache-control markers and
+    /// the context window size are adapted to the new provider, along with the
+    /// request that was about to be sent. The switch is recorded in the session log.
+    fn switch_provider(
+        &mut self,
+        to: &str,
+        reason: &str,
+        request: &mut CompletionRequest,
+    ) -> Result<()> {
+        let (from, from_native) = {
+            let provider = self.providers.get(None)?;
+            (provider.name().to_string(), provider.has_native_tool_calling())
+        };
+        self.providers.set_default(to)?;
+        self.config.providers.default_provider = to.to_string();
+
+        let (native, supports_cache_control) = {
+            let provider = self.providers.get(None)?;
+            (provider.has_native_tool_calling(), provider.supports_cache_control())
+        };
+        warn!("Provider {} keeps failing ({}). Switching to {}", from, reason, to);
+        self.ui_writer.print_context_status(&format!(
+            "\n🔀 {} is unavailable. Continuing with {}...",
+            from, to
+        ));
+
+        let system_prompt = (native != from_native).then(|| {
+            if native {
+                get_system_prompt_for_native(self.config.agent.allow_multiple_tool_calls)
+            } else {
+                SYSTEM_PROMPT_FOR_NON_NATIVE_TOOL_USE.to_string()
+            }
+        });
+        for messages in [
+            &mut self.context_window.conversation_history,
+            &mut request.messages,
+        ] {
+            if let (Some(prompt), Some(first)) = (&system_prompt, messages.first_mut()) {
+                if matches!(first.role, MessageRole::System) && first.content.contains("You are G3") {
+                    first.content = prompt.clone();
+                }
+            }
+            if !supports_cache_control {
+                for message in messages.iter_mut() {
+                    message.cache_control = None;
                 }
             }
         }
+
+        let mut context_warnings = Vec::new();
+        self.context_window.total_tokens =
+            Self::get_configured_context_length(&self.config, &self.providers, &mut context_warnings)?;
+        let tokenizer = Self::tokenizer_for_provider(
+            self.providers.get(None)?,
+            self.config.agent.download_tokenizers,
+        );
+        self.context_window.set_tokenizer(tokenizer);
+        for warning in context_warnings {
+            self.ui_writer.print_context_status(&format!("⚠️ {}", warning));
+        }
+
+        request.tools = native.then(|| self.tool_definitions());
+        if request.max_tokens.is_some() {
+            request.max_tokens = Some(self.resolve_max_tokens(to));
+        }
+        if request.temperature.is_some() {
+            request.temperature = Some(self.resolve_temperature(to));
+        }
+
+        self.provider_switches.push(session::ProviderSwitch {
+            from,
+            to: to.to_string(),
+            reason: reason.to_string(),
+            timestamp: std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs(),
+            message_count: self.context_window.conversation_history.len(),
+        });
+        self.save_context_window("provider_switch");
+        Ok(())
     }
 
     async fn stream_completion_with_tools(
@@ -3691,6 +3824,9 @@ impl<W: UiWriter> Agent<W> {
         const MAX_ITERATIONS: usize = 400; // Prevent infinite loops
         let mut response_started = false;
 
+        // Count the request with the provider's tokenizer before deciding whether it fits
+        self.context_window.preflight(&request);
+
         // Check if we need to summarize before starting
         if self.context_window.should_summarize() {
             // First try thinning if we are at capacity, don't call the LLM for a summary (might fail)
@@ -3804,6 +3940,7 @@ impl<W: UiWriter> Agent<W> {
                 // Get the summary
                 match provider.complete(summary_request).await {
                     Ok(summary_response) => {
+                        self.record_usage(&summary_response.usage);
                         self.ui_writer.print_context_status(
                             "✅ Context compacted successfully. Continuing...\n",
                         );
@@ -3888,8 +4025,11 @@ impl<W: UiWriter> Agent<W> {
                 request.max_tokens
             );
 
+            // Stop before the request that would overspend
+            self.check_budget()?;
+
             // Try to get stream with retry logic
-            let mut stream = match self.stream_with_retry(&request, &error_context).await {
+            let mut stream = match self.stream_with_retry(&mut request, &error_context).await {
                 Ok(s) => s,
                 Err(e) => {
                     error!("Failed to start stream: {}", e);
@@ -3901,7 +4041,7 @@ impl<W: UiWriter> Agent<W> {
                         );
                         tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
 
-                        match self.stream_with_retry(&request, &error_context).await {
+                        match self.stream_with_retry(&mut request, &error_context).await {
                             Ok(s) => s,
                             Err(e2) => {
                                 error!("Failed to start stream after retry: {}", e2);
@@ -4297,6 +4437,14 @@ impl<W: UiWriter> Agent<W> {
                                 }
                             }
 
+                            if tool_call.tool != "final_output" {
+                                self.ui_writer.print_tool_result(
+                                    &tool_call.tool,
+                                    exec_duration,
+                                    tool_success,
+                                );
+                            }
+
                             // Add the tool call and result to the context window using RAW unfiltered content
                             // This ensures the log file contains the true raw content including JSON tool calls
                             let tool_message = if !raw_content_for_log.trim().is_empty() {
@@ -4319,47 +4467,10 @@ impl<W: UiWriter> Agent<W> {
                                     ),
                                 )
                             };
-                            let result_message = {
-                                // Check if we should use cache control (every 10 tool calls)
-                                // But only if we haven't already added 4 cache_control annotations
-                                if self.tool_call_count > 0
-                                    && self.tool_call_count % 10 == 0
-                                    && self.count_cache_controls_in_history() < 4
-                                {
-                                    let provider = self.providers.get(None)?;
-                                    let provider_name = provider.name();
-                                    let provider_type = provider_name.split('.').next().unwrap_or("");
-                                    let config_name = provider_name.split('.').nth(1).unwrap_or("default");
-                                    if let Some(cache_config) = match provider_type {
-                                        "anthropic" => {
-                                            self.config
-                                                .providers
-                                                .anthropic
-                                                .get(config_name)
-                                                .and_then(|c| c.cache_config.as_ref())
-                                                .and_then(|config| Self::parse_cache_control(config))
-                                        }
-                                        _ => None,
-                                    } {
-                                        Message::with_cache_control_validated(
-                                            MessageRole::User,
-                                            format!("Tool result: {}", tool_result),
-                                            cache_config,
-                                            provider,
-                                        )
-                                    } else {
-                                        Message::new(
-                                            MessageRole::User,
-                                            format!("Tool result: {}", tool_result),
-                                        )
-                                    }
-                                } else {
-                                    Message::new(
-                                        MessageRole::User,
-                                        format!("Tool result: {}", tool_result),
-                                    )
-                                }
-                            };
+                            let result_message = Message::new(
+                                MessageRole::User,
+                                format!("Tool result: {}", tool_result),
+                            );
 
                             self.context_window.add_message(tool_message);
                             self.context_window.add_message(result_message);
@@ -4406,11 +4517,7 @@ impl<W: UiWriter> Agent<W> {
                             // Ensure tools are included for native providers in subsequent iterations
                             let provider_for_tools = self.providers.get(None)?;
                             if provider_for_tools.has_native_tool_calling() {
-                                request.tools = Some(Self::create_tool_definitions(
-                                    self.config.webdriver.enabled,
-                                    self.config.macax.enabled,
-                                    self.config.computer_control.enabled,
-                                ));
+                                request.tools = Some(self.tool_definitions());
                             }
 
                             // DO NOT add final_display_content to full_response here!
@@ -4620,6 +4727,12 @@ impl<W: UiWriter> Agent<W> {
                                 // Return empty string to avoid duplication
                                 full_response = String::new();
 
+                                self.record_stream_usage(
+                                    accumulated_usage.take(),
+                                    &current_response,
+                                    &request,
+                                );
+
                                 // Save context window BEFORE returning
                                 self.save_context_window("completed");
                                 let _ttft =
@@ -4699,15 +4812,7 @@ impl<W: UiWriter> Agent<W> {
             }
 
             // Update context window with actual usage if available
-            if let Some(usage) = accumulated_usage {
-                debug!("Updating context window with actual usage from stream");
-                self.context_window.update_usage_from_response(&usage);
-            } else {
-                // Fall back to estimation if no usage data was provided
-                debug!("No usage data from stream, using estimation");
-                let estimated_tokens = ContextWindow::estimate_tokens(&current_response);
-                self.context_window.add_streaming_tokens(estimated_tokens);
-            }
+            self.record_stream_usage(accumulated_usage, &current_response, &request);
 
             // If we get here and no tool was executed, we're done
             if !tool_executed {
@@ -4843,1785 +4948,154 @@ impl<W: UiWriter> Agent<W> {
         );
         debug!("======================");
 
-        match tool_call.tool.as_str() {
-            "shell" => {
-                debug!("Processing shell tool call");
-                if let Some(command) = tool_call.args.get("command") {
-                    debug!("Found command parameter: {:?}", command);
-                    if let Some(command_str) = command.as_str() {
-                        debug!("Command string: {}", command_str);
-                        // Use shell escaping to handle filenames with spaces and special characters
-                        let escaped_command = shell_escape_command(command_str);
-
-                        let executor = CodeExecutor::new();
-
-                        // Create a receiver for streaming output
-                        struct ToolOutputReceiver<'a, W: UiWriter> {
-                            ui_writer: &'a W,
-                        }
-
-                        impl<'a, W: UiWriter> g3_execution::OutputReceiver for ToolOutputReceiver<'a, W> {
-                            fn on_output_line(&self, line: &str) {
-                                self.ui_writer.update_tool_output_line(line);
-                            }
-                        }
-
-                        let receiver = ToolOutputReceiver {
-                            ui_writer: &self.ui_writer,
-                        };
-
-                        debug!("ABOUT TO CALL execute_bash_streaming_in_dir: escaped_command='{}', working_dir={:?}", escaped_command, working_dir);
-
-                        match executor
-                            .execute_bash_streaming_in_dir(&escaped_command, &receiver, working_dir)
-                            .await
-                        {
-                            Ok(result) => {
-                                if result.success {
-                                    Ok(if result.stdout.is_empty() {
-                                        "✅ Command executed successfully".to_string()
-                                    } else {
-                                        result.stdout.trim().to_string()
-                                    })
-                                } else {
-                                    Ok(format!("❌ Command failed: {}", result.stderr.trim()))
-                                }
-                            }
-                            Err(e) => Ok(format!("❌ Execution error: {}", e)),
-                        }
-                    } else {
-                        debug!("Command parameter is not a string: {:?}", command);
-                        Ok("❌ Invalid command argument".to_string())
-                    }
-                } else {
-                    debug!("No command parameter found in args: {:?}", tool_call.args);
-                    debug!(
-                        "Available keys: {:?}",
-                        tool_call
-                            .args
-                            .as_object()
-                            .map(|obj| obj.keys().collect::<Vec<_>>())
-                    );
-                    Ok("❌ Missing command argument".to_string())
-                }
-            }
-            "read_file" => {
-                debug!("Processing read_file tool call");
-                if let Some(file_path) = tool_call.args.get("file_path") {
-                    if let Some(path_str) = file_path.as_str() {
-                        // Expand tilde (~) to home directory
-                        let expanded_path = shellexpand::tilde(path_str);
-                        let path_str = expanded_path.as_ref();
-
-                        // Check if this is an image file
-                        let is_image = path_str.to_lowercase().ends_with(".png")
-                            || path_str.to_lowercase().ends_with(".jpg")
-                            || path_str.to_lowercase().ends_with(".jpeg")
-                            || path_str.to_lowercase().ends_with(".gif")
-                            || path_str.to_lowercase().ends_with(".bmp")
-                            || path_str.to_lowercase().ends_with(".tiff")
-                            || path_str.to_lowercase().ends_with(".tif")
-                            || path_str.to_lowercase().ends_with(".webp");
-
-                        // If it's an image file, use OCR via extract_text
-                        if is_image {
-                            if let Some(controller) = &self.computer_controller {
-                                match controller.extract_text_from_image(path_str).await {
-                                    Ok(text) => {
-                                        return Ok(format!(
-                                            "📄 Image file (OCR extracted):\n{}",
-                                            text
-                                        ));
-                                    }
-                                    Err(e) => {
-                                        return Ok(format!(
-                                            "❌ Failed to extract text from image '{}': {}",
-                                            path_str, e
-                                        ))
-                                    }
-                                }
-                            } else {
-                                return Ok("❌ Computer control not enabled. Cannot perform OCR on image files. Set computer_control.enabled = true in config.".to_string());
-                            }
-                        }
-
-                        // Extract optional start and end positions
-                        let start_char = tool_call
-                            .args
-                            .get("start")
-                            .and_then(|v| v.as_u64())
-                            .map(|n| n as usize);
-                        let end_char = tool_call
-                            .args
-                            .get("end")
-                            .and_then(|v| v.as_u64())
-                            .map(|n| n as usize);
-
-                        debug!(
-                            "Reading file: {}, start={:?}, end={:?}",
-                            path_str, start_char, end_char
-                        );
-
-                        match std::fs::read_to_string(path_str) {
-                            Ok(content) => {
-                                // Validate and apply range if specified
-                                let start = start_char.unwrap_or(0);
-                                let end = end_char.unwrap_or(content.len());
-
-                                // Validation
-                                if start > content.len() {
-                                    return Ok(format!(
-                                        "❌ Start position {} exceeds file length {}",
-                                        start,
-                                        content.len()
-                                    ));
-                                }
-                                if end > content.len() {
-                                    return Ok(format!(
-                                        "❌ End position {} exceeds file length {}",
-                                        end,
-                                        content.len()
-                                    ));
-                                }
-                                if start > end {
-                                    return Ok(format!(
-                                        "❌ Start position {} is greater than end position {}",
-                                        start, end
-                                    ));
-                                }
-
-                                // Extract the requested portion, ensuring we're at char boundaries
-                                // Find the nearest valid char boundaries
-                                let start_boundary = if start == 0 {
-                                    0
-                                } else {
-                                    content
-                                        .char_indices()
-                                        .find(|(i, _)| *i >= start)
-                                        .map(|(i, _)| i)
-                                        .unwrap_or(start)
-                                };
-                                let end_boundary = content
-                                    .char_indices()
-                                    .find(|(i, _)| *i >= end)
-                                    .map(|(i, _)| i)
-                                    .unwrap_or(content.len());
-
-                                let partial_content = &content[start_boundary..end_boundary];
-                                let line_count = partial_content.lines().count();
-                                let total_lines = content.lines().count();
-
-                                // Format output with range info if partial
-                                if start_char.is_some() || end_char.is_some() {
-                                    Ok(format!(
-                                        "📄 File content (chars {}-{}, {} lines of {} total):\n{}",
-                                        start_boundary,
-                                        end_boundary,
-                                        line_count,
-                                        total_lines,
-                                        partial_content
-                                    ))
-                                } else {
-                                    Ok(format!(
-                                        "📄 File content ({} lines):\n{}",
-                                        line_count, content
-                                    ))
-                                }
-                            }
-                            Err(e) => Ok(format!("❌ Failed to read file '{}': {}", path_str, e)),
-                        }
-                    } else {
-                        Ok("❌ Invalid file_path argument".to_string())
-                    }
-                } else {
-                    Ok("❌ Missing file_path argument".to_string())
-                }
+        let handler = match self.tools.get(&tool_call.tool) {
+            Some(handler) => handler.clone(),
+            None => {
+                warn!("Unknown tool: {}", tool_call.tool);
+                return Ok(format!("❓ Unknown tool: {}", tool_call.tool));
             }
-            "write_file" => {
-                debug!("Processing write_file tool call");
-                debug!("Raw tool_call.args: {:?}", tool_call.args);
-                debug!(
-                    "Args as JSON: {}",
-                    serde_json::to_string(&tool_call.args)
-                        .unwrap_or_else(|_| "failed to serialize".to_string())
-                );
-                debug!(
-                    "Args type: {:?}",
-                    std::any::type_name_of_val(&tool_call.args)
-                );
-                debug!("Args is_object: {}", tool_call.args.is_object());
-                debug!("Args is_array: {}", tool_call.args.is_array());
-                debug!("Args is_null: {}", tool_call.args.is_null());
-
-                // Try multiple argument formats that different providers might use
-                let (path_str, content_str) = if let Some(args_obj) = tool_call.args.as_object() {
-                    debug!(
-                        "Args object keys: {:?}",
-                        args_obj.keys().collect::<Vec<_>>()
-                    );
-
-                    // Format 1: Standard format with file_path and content
-                    if let (Some(path_val), Some(content_val)) =
-                        (args_obj.get("file_path"), args_obj.get("content"))
-                    {
-                        debug!("Found file_path and content keys");
-                        if let (Some(path), Some(content)) =
-                            (path_val.as_str(), content_val.as_str())
-                        {
-                            debug!(
-                                "Successfully extracted file_path='{}', content_len={}",
-                                path,
-                                content.len()
-                            );
-                            (Some(path), Some(content))
-                        } else {
-                            debug!("file_path or content values are not strings: path_val={:?}, content_val={:?}", path_val, content_val);
-                            (None, None)
-                        }
-                    }
-                    // Format 2: Anthropic-style with path and content
-                    else if let (Some(path_val), Some(content_val)) =
-                        (args_obj.get("path"), args_obj.get("content"))
-                    {
-                        debug!("Found path and content keys (Anthropic style)");
-                        if let (Some(path), Some(content)) =
-                            (path_val.as_str(), content_val.as_str())
-                        {
-                            debug!(
-                                "Successfully extracted path='{}', content_len={}",
-                                path,
-                                content.len()
-                            );
-                            (Some(path), Some(content))
-                        } else {
-                            debug!("path or content values are not strings: path_val={:?}, content_val={:?}", path_val, content_val);
-                            (None, None)
-                        }
-                    }
-                    // Format 3: Alternative naming with filename and text
-                    else if let (Some(path_val), Some(content_val)) =
-                        (args_obj.get("filename"), args_obj.get("text"))
-                    {
-                        debug!("Found filename and text keys");
-                        if let (Some(path), Some(content)) =
-                            (path_val.as_str(), content_val.as_str())
-                        {
-                            debug!(
-                                "Successfully extracted filename='{}', text_len={}",
-                                path,
-                                content.len()
-                            );
-                            (Some(path), Some(content))
-                        } else {
-                            debug!("filename or text values are not strings: path_val={:?}, content_val={:?}", path_val, content_val);
-                            (None, None)
-                        }
-                    }
-                    // Format 4: Alternative naming with file and data
-                    else if let (Some(path_val), Some(content_val)) =
-                        (args_obj.get("file"), args_obj.get("data"))
-                    {
-                        debug!("Found file and data keys");
-                        if let (Some(path), Some(content)) =
-                            (path_val.as_str(), content_val.as_str())
-                        {
-                            debug!(
-                                "Successfully extracted file='{}', data_len={}",
-                                path,
-                                content.len()
-                            );
-                            (Some(path), Some(content))
-                        } else {
-                            debug!("file or data values are not strings: path_val={:?}, content_val={:?}", path_val, content_val);
-                            (None, None)
-                        }
-                    } else {
-                        debug!(
-                            "No matching key patterns found. Available argument keys: {:?}",
-                            args_obj.keys().collect::<Vec<_>>()
-                        );
-                        (None, None)
-                    }
-                } else {
-                    debug!("Args is not an object, checking if it's an array");
-                    // Format 5: Args might be an array [path, content]
-                    if let Some(args_array) = tool_call.args.as_array() {
-                        debug!("Args is an array with {} elements", args_array.len());
-                        if args_array.len() >= 2 {
-                            if let (Some(path), Some(content)) =
-                                (args_array[0].as_str(), args_array[1].as_str())
-                            {
-                                debug!(
-                                    "Successfully extracted from array: path='{}', content_len={}",
-                                    path,
-                                    content.len()
-                                );
-                                (Some(path), Some(content))
-                            } else {
-                                debug!(
-                                    "Array elements are not strings: [0]={:?}, [1]={:?}",
-                                    args_array[0], args_array[1]
-                                );
-                                (None, None)
-                            }
-                        } else {
-                            debug!("Array has insufficient elements: {}", args_array.len());
-                            (None, None)
-                        }
-                    } else {
-                        debug!("Args is neither object nor array");
-                        (None, None)
-                    }
-                };
-
-                debug!(
-                    "Final extracted values: path_str={:?}, content_str_len={:?}",
-                    path_str,
-                    content_str.map(|c| c.len())
-                );
-
-                if let (Some(path), Some(content)) = (path_str, content_str) {
-                    // Expand tilde (~) to home directory
-                    let expanded_path = shellexpand::tilde(path);
-                    let path = expanded_path.as_ref();
-
-                    debug!("Writing to file: {}", path);
+        };
 
-                    // Create parent directories if they don't exist
-                    if let Some(parent) = std::path::Path::new(path).parent() {
-                        if let Err(e) = std::fs::create_dir_all(parent) {
-                            return Ok(format!(
-                                "❌ Failed to create parent directories for '{}': {}",
-                                path, e
-                            ));
-                        }
-                    }
+        if let Some(denied) = self.check_tool_permission(handler.as_ref(), tool_call) {
+            return Ok(denied);
+        }
 
-                    match std::fs::write(path, content) {
-                        Ok(()) => {
-                            let line_count = content.lines().count();
-                            let char_count = content.len();
-                            Ok(format!(
-                                "✅ Successfully wrote {} lines ({} characters)",
-                                line_count, char_count
-                            ))
-                        }
-                        Err(e) => Ok(format!("❌ Failed to write to file '{}': {}", path, e)),
-                    }
-                } else {
-                    // Provide more detailed error information
-                    let available_keys = if let Some(obj) = tool_call.args.as_object() {
-                        obj.keys().collect::<Vec<_>>()
-                    } else {
-                        vec![]
-                    };
+        let modified_files = handler.modified_files(tool_call);
+        if !modified_files.is_empty() {
+            let anchor = self
+                .context_window
+                .conversation_history
+                .last()
+                .map(|m| m.id.clone())
+                .unwrap_or_default();
+            self.checkpoints
+                .get_mut()
+                .unwrap()
+                .record(&tool_call.tool, &anchor, &modified_files);
+        }
 
-                    Ok(format!(
-                        "❌ Missing file_path or content argument. Available keys: {:?}. Expected formats: {{\"file_path\": \"...\", \"content\": \"...\"}}, {{\"path\": \"...\", \"content\": \"...\"}}, {{\"filename\": \"...\", \"text\": \"...\"}}, or {{\"file\": \"...\", \"data\": \"...\"}}",
-                        available_keys
-                    ))
-                }
-            }
-            "str_replace" => {
-                debug!("Processing str_replace tool call");
+        let ctx = tools::ToolContext {
+            config: &self.config,
+            ui_writer: &self.ui_writer,
+            working_dir,
+            executor: &self.executor,
+            requirements_sha: &self.requirements_sha,
+            todo_content: &self.todo_content,
+            computer_controller: &self.computer_controller,
+            webdriver_session: &self.webdriver_session,
+            webdriver_process: &self.webdriver_process,
+            macax_controller: &self.macax_controller,
+            checkpoints: &self.checkpoints,
+        };
 
-                // Extract arguments
-                let args_obj = match tool_call.args.as_object() {
-                    Some(obj) => obj,
-                    None => return Ok("❌ Invalid arguments: expected object".to_string()),
-                };
+        let result = handler.execute(tool_call, &ctx).await;
+        if !modified_files.is_empty() {
+            self.checkpoints.get_mut().unwrap().discard_if_unchanged();
+        }
+        result
+    }
 
-                let file_path = match args_obj.get("file_path").and_then(|v| v.as_str()) {
-                    Some(path) => {
-                        // Expand tilde (~) to home directory
-                        let expanded_path = shellexpand::tilde(path);
-                        expanded_path.into_owned()
-                    }
-                    None => return Ok("❌ Missing or invalid file_path argument".to_string()),
-                };
+    /// Apply the permission policy to a call. Returns the message to give the
+    /// model instead of a result if the call is not allowed.
+    fn check_tool_permission(
+        &self,
+        handler: &dyn tools::ToolHandler,
+        tool_call: &ToolCall,
+    ) -> Option<String> {
+        use g3_config::PermissionAction;
+
+        let subject = handler
+            .permission_subject(tool_call)
+            .unwrap_or_else(|| tool_call.args.to_string());
+        let decision = self.permissions.evaluate(
+            &tool_call.tool,
+            &subject,
+            handler.permissions().is_read_only(),
+        );
 
-                let diff = match args_obj.get("diff").and_then(|v| v.as_str()) {
-                    Some(d) => d,
-                    None => return Ok("❌ Missing or invalid diff argument".to_string()),
-                };
+        let unattended = self.is_autonomous || !self.ui_writer.is_interactive();
+        let (allowed, reason) = match decision.action {
+            PermissionAction::Allow => (true, decision.reason),
+            PermissionAction::Deny => (false, decision.reason),
+            PermissionAction::Prompt if unattended => (
+                self.permissions.unattended_action() == PermissionAction::Allow,
+                format!("{}, unattended", decision.reason),
+            ),
+            PermissionAction::Prompt => (
+                self.ui_writer.prompt_user_yes_no(&format!(
+                    "🔐 Allow {}: {}?",
+                    tool_call.tool, subject
+                )),
+                "declined by user".to_string(),
+            ),
+        };
 
-                // Optional start and end character positions (0-indexed, end is EXCLUSIVE)
-                let start_char = args_obj
-                    .get("start")
-                    .and_then(|v| v.as_u64())
-                    .map(|n| n as usize);
-                let end_char = args_obj
-                    .get("end")
-                    .and_then(|v| v.as_u64())
-                    .map(|n| n as usize);
+        if allowed {
+            None
+        } else {
+            warn!("Tool call {} denied ({}): {}", tool_call.tool, reason, subject);
+            Some(format!(
+                "❌ Permission denied for {} ({}). Do not retry this call; choose another approach or ask the user.",
+                tool_call.tool, reason
+            ))
+        }
+    }
 
-                debug!(
-                    "str_replace: path={}, start={:?}, end={:?}",
-                    file_path, start_char, end_char
-                );
+    fn format_duration(duration: Duration) -> String {
+        let total_ms = duration.as_millis();
 
-                // Read the existing file
-                let file_content = match std::fs::read_to_string(&file_path) {
-                    Ok(content) => content,
-                    Err(e) => return Ok(format!("❌ Failed to read file '{}': {}", file_path, e)),
-                };
+        if total_ms < 1000 {
+            format!("{}ms", total_ms)
+        } else if total_ms < 60_000 {
+            let seconds = duration.as_secs_f64();
+            format!("{:.1}s", seconds)
+        } else {
+            let minutes = total_ms / 60_000;
+            let remaining_seconds = (total_ms % 60_000) as f64 / 1000.0;
+            format!("{}m {:.1}s", minutes, remaining_seconds)
+        }
+    }
+}
 
-                // Apply unified diff to content
-                let result =
-                    match apply_unified_diff_to_string(&file_content, diff, start_char, end_char) {
-                        Ok(r) => r,
-                        Err(e) => return Ok(format!("❌ {}", e)),
-                    };
+// Note: JSON tool call filtering is now handled by fixed_filter_json::fixed_filter_json_tool_calls
 
-                // Write the result back to the file
-                match std::fs::write(&file_path, &result) {
-                    Ok(()) => Ok("✅ applied unified diff".to_string()),
-                    Err(e) => Ok(format!("❌ Failed to write to file '{}': {}", file_path, e)),
-                }
-            }
-            "final_output" => {
-                if let Some(summary) = tool_call.args.get("summary") {
-                    if let Some(summary_str) = summary.as_str() {
-                        Ok(summary_str.to_string())
-                    } else {
-                        Ok("✅ Turn completed".to_string())
-                    }
-                } else {
-                    Ok("✅ Turn completed".to_string())
-                }
-            }
-            "take_screenshot" => {
-                if let Some(controller) = &self.computer_controller {
-                    let path = tool_call
-                        .args
-                        .get("path")
-                        .and_then(|v| v.as_str())
-                        .ok_or_else(|| anyhow::anyhow!("Missing path argument"))?;
-
-                    // Extract window_id (app name) - REQUIRED
-                    let window_id = tool_call.args.get("window_id").and_then(|v| v.as_str())
-                        .ok_or_else(|| anyhow::anyhow!("Missing window_id argument. You must specify which window to capture (e.g., 'Safari', 'Terminal', 'Google Chrome')."))?;
-
-                    // Extract region if provided
-                    let region = tool_call
-                        .args
-                        .get("region")
-                        .and_then(|v| v.as_object())
-                        .map(|region_obj| g3_computer_control::types::Rect {
-                            x: region_obj.get("x").and_then(|v| v.as_i64()).unwrap_or(0) as i32,
-                            y: region_obj.get("y").and_then(|v| v.as_i64()).unwrap_or(0) as i32,
-                            width: region_obj
-                                .get("width")
-                                .and_then(|v| v.as_i64())
-                                .unwrap_or(0) as i32,
-                            height: region_obj
-                                .get("height")
-                                .and_then(|v| v.as_i64())
-                                .unwrap_or(0) as i32,
-                        });
-
-                    match controller
-                        .take_screenshot(path, region, Some(window_id))
-                        .await
-                    {
-                        Ok(_) => {
-                            // Get the actual path where the screenshot was saved
-                            let actual_path = if path.starts_with('/') {
-                                path.to_string()
-                            } else {
-                                let temp_dir = std::env::var("TMPDIR")
-                                    .or_else(|_| {
-                                        std::env::var("HOME").map(|h| format!("{}/tmp", h))
-                                    })
-                                    .unwrap_or_else(|_| "/tmp".to_string());
-                                format!("{}/{}", temp_dir.trim_end_matches('/'), path)
-                            };
-
-                            Ok(format!(
-                                "✅ Screenshot of {} saved to: {}",
-                                window_id, actual_path
-                            ))
-                        }
-                        Err(e) => Ok(format!("❌ Failed to take screenshot: {}", e)),
-                    }
-                } else {
-                    Ok("❌ Computer control not enabled. Set computer_control.enabled = true in config.".to_string())
-                }
-            }
-            "extract_text" => {
-                if let Some(controller) = &self.computer_controller {
-                    let path = tool_call
-                        .args
-                        .get("path")
-                        .and_then(|v| v.as_str())
-                        .ok_or_else(|| anyhow::anyhow!("Missing path argument"))?;
-
-                    // Extract text from image file only
-                    match controller.extract_text_from_image(path).await {
-                        Ok(text) => Ok(format!("✅ Extracted text:\n{}", text)),
-                        Err(e) => Ok(format!("❌ Failed to extract text: {}", e)),
-                    }
-                } else {
-                    Ok("❌ Computer control not enabled. Set computer_control.enabled = true in config.".to_string())
-                }
-            }
-            "todo_read" => {
-                debug!("Processing todo_read tool call");
-                // Read from todo.g3.md file (uses G3_TODO_PATH env var if set, else current dir)
-                let todo_path = get_todo_path();
-
-                if !todo_path.exists() {
-                    // Also update in-memory content to stay in sync
-                    let mut todo = self.todo_content.write().await;
-                    *todo = String::new();
-                    Ok("📝 TODO list is empty (no todo.g3.md file found)".to_string())
-                } else {
-                    match std::fs::read_to_string(&todo_path) {
-                        Ok(content) => {
-                            // Update in-memory content to stay in sync
-                            let mut todo = self.todo_content.write().await;
-                            *todo = content.clone();
-
-                            // Check for staleness if enabled and we have a requirements SHA
-                            if self.config.agent.check_todo_staleness {
-                                if let Some(req_sha) = &self.requirements_sha {
-                                    // Parse the first line for the SHA header
-                                    if let Some(first_line) = content.lines().next() {
-                                        if first_line.starts_with(
-                                            "{{Based on the requirements file with SHA256:",
-                                        ) {
-                                            let parts: Vec<&str> =
-                                                first_line.split("SHA256:").collect();
-                                            if parts.len() > 1 {
-                                                let todo_sha =
-                                                    parts[1].trim().trim_end_matches("}}").trim();
-                                                if todo_sha != req_sha {
-                                                    let warning = format!(
-                                                        "⚠️ TODO list is stale! It was generated from a different requirements file.\nExpected SHA: {}\nFound SHA:    {}",
-                                                        req_sha, todo_sha
-                                                    );
-                                                    self.ui_writer.print_context_status(&warning);
-
-                                                    // Beep 6 times
-                                                    print!("\x07\x07\x07\x07\x07\x07");
-                                                    let _ = std::io::stdout().flush();
-
-                                                    let options = [
-                                                        "Ignore and Continue",
-                                                        "Mark as Stale",
-                                                        "Quit Application",
-                                                    ];
-                                                    let choice = self.ui_writer.prompt_user_choice("Requirements have changed! What would you like to do?", &options);
-
-                                                    match choice {
-                                                        0 => {
-                                                            // Ignore and Continue
-                                                            self.ui_writer.print_context_status(
-                                                                "⚠️ Ignoring staleness warning.",
-                                                            );
-                                                        }
-                                                        1 => {
-                                                            // Mark as Stale
-                                                            // We return a message to the agent so it knows to regenerate/fix it.
-                                                            return Ok("⚠️ TODO list is stale (requirements changed). Please regenerate the TODO list to match the new requirements.".to_string());
-                                                        }
-                                                        2 => {
-                                                            // Quit Application
-                                                            self.ui_writer.print_context_status("❌ Quitting application as requested.");
-                                                            std::process::exit(0);
-                                                        }
-                                                        _ => unreachable!(),
-                                                    }
-                                                }
-                                            }
-                                        } else {
-                                            // Header missing, but we have a SHA. Warn the user?
-                                            // For now, maybe just proceed... assuming it's an old TODO.
-                                        }
-                                    }
-                                }
-                            }
-
-                            if content.trim().is_empty() {
-                                Ok("📝 TODO list is empty".to_string())
-                            } else {
-                                // Print the TODO content to the console
-                                self.ui_writer.print_context_status("📝 TODO list:");
-                                for line in content.lines() {
-                                    self.ui_writer.print_tool_output_line(line);
-                                }
-                                Ok(format!("📝 TODO list:\n{}", content))
-                            }
-                        }
-                        Err(e) => Ok(format!("❌ Failed to read TODO.md: {}", e)),
-                    }
-                }
-            }
-            "todo_write" => {
-                debug!("Processing todo_write tool call");
-                if let Some(content) = tool_call.args.get("content") {
-                    if let Some(content_str) = content.as_str() {
-                        let char_count = content_str.chars().count();
-                        let max_chars = std::env::var("G3_TODO_MAX_CHARS")
-                            .ok()
-                            .and_then(|s| s.parse().ok())
-                            .unwrap_or(50_000);
-
-                        if max_chars > 0 && char_count > max_chars {
-                            return Ok(format!(
-                                "❌ TODO list too large: {} chars (max: {})",
-                                char_count, max_chars
-                            ));
-                        }
-
-                        // Check if all todos are completed (all checkboxes are checked)
-                        let has_incomplete = content_str.lines().any(|line| {
-                            let trimmed = line.trim();
-                            trimmed.starts_with("- [ ]")
-                        });
-
-                        // If all todos are complete, delete the file instead of writing
-                        // EXCEPT in planner mode (G3_TODO_PATH is set) - preserve for rename to completed_todo_*.md
-                        let in_planner_mode = std::env::var("G3_TODO_PATH").is_ok();
-                        let todo_path = get_todo_path();
-                        
-                        if !in_planner_mode && !has_incomplete && (content_str.contains("- [x]") || content_str.contains("- [X]")) {
-                            if todo_path.exists() {
-                                match std::fs::remove_file(&todo_path) {
-                                    Ok(_) => {
-                                        let mut todo = self.todo_content.write().await;
-                                        *todo = String::new();
-                                        // Show the final completed TODOs before deletion
-                                        let mut result = String::from("✅ All TODOs completed! Removed todo.g3.md\n\nFinal status:\n");
-                                        for line in content_str.lines() {
-                                            self.ui_writer.print_tool_output_line(line);
-                                            result.push_str(line);
-                                            result.push('\n');
-                                        }
-                                        return Ok(result);
-                                    }
-                                    Err(e) => return Ok(format!("❌ Failed to remove todo.g3.md: {}", e)),
-                                }
-                            }
-                        }
-
-                        // Write to todo.g3.md file (uses G3_TODO_PATH env var if set, else current dir)
-
-                        match std::fs::write(&todo_path, content_str) {
-                            Ok(_) => {
-                                // Also update in-memory content to stay in sync
-                                let mut todo = self.todo_content.write().await;
-                                *todo = content_str.to_string();
-                                // Print the TODO content to the console (inside the tool frame)
-                                for line in content_str.lines() {
-                                    self.ui_writer.print_tool_output_line(line);
-                                }
-                                Ok(format!("✅ TODO list updated ({} chars) and saved to todo.g3.md:\n{}", char_count, content_str))
-                            }
-                            Err(e) => Ok(format!("❌ Failed to write todo.g3.md: {}", e)),
-                        }
-                    } else {
-                        Ok("❌ Invalid content argument".to_string())
-                    }
-                } else {
-                    Ok("❌ Missing content argument".to_string())
-                }
-            }
-            "code_coverage" => {
-                debug!("Processing code_coverage tool call");
-                self.ui_writer
-                    .print_context_status("🔍 Generating code coverage report...");
-
-                // Ensure coverage tools are installed
-                match g3_execution::ensure_coverage_tools_installed() {
-                    Ok(already_installed) => {
-                        if !already_installed {
-                            self.ui_writer
-                                .print_context_status("✅ Coverage tools installed successfully");
-                        }
-                    }
-                    Err(e) => {
-                        return Ok(format!("❌ Failed to install coverage tools: {}", e));
-                    }
-                }
-
-                // Run cargo llvm-cov --workspace
-                let output = std::process::Command::new("cargo")
-                    .args(&["llvm-cov", "--workspace"])
-                    .current_dir(std::env::current_dir()?)
-                    .output()?;
-
-                if output.status.success() {
-                    let stdout = String::from_utf8_lossy(&output.stdout);
-                    let stderr = String::from_utf8_lossy(&output.stderr);
-
-                    // Combine output
-                    let mut result =
-                        String::from("✅ Code coverage report generated successfully\n\n");
-                    result.push_str("## Coverage Summary\n");
-                    result.push_str(&stdout);
-                    if !stderr.is_empty() {
-                        result.push_str("\n## Warnings\n");
-                        result.push_str(&stderr);
-                    }
-                    Ok(result)
-                } else {
-                    let stderr = String::from_utf8_lossy(&output.stderr);
-                    Ok(format!(
-                        "❌ Failed to generate coverage report:\n{}",
-                        stderr
-                    ))
-                }
-            }
-            "webdriver_start" => {
-                debug!("Processing webdriver_start tool call");
-
-                if !self.config.webdriver.enabled {
-                    return Ok(
-                        "❌ WebDriver is not enabled. Use --webdriver flag to enable.".to_string(),
-                    );
-                }
-
-                // Check if session already exists
-                let session_guard = self.webdriver_session.read().await;
-                if session_guard.is_some() {
-                    drop(session_guard);
-                    return Ok("✅ WebDriver session already active".to_string());
-                }
-                drop(session_guard);
-
-                // Determine which browser to use based on config
-                use g3_config::WebDriverBrowser;
-                match &self.config.webdriver.browser {
-                    WebDriverBrowser::Safari => {
-                        // Note: Safari Remote Automation must be enabled before using WebDriver.
-                        // Run this once: safaridriver --enable
-                        // Or enable manually: Safari → Develop → Allow Remote Automation
-
-                        let port = self.config.webdriver.safari_port;
-
-                        let driver_result = tokio::process::Command::new("safaridriver")
-                            .arg("--port")
-                            .arg(port.to_string())
-                            .stdout(std::process::Stdio::null())
-                            .stderr(std::process::Stdio::null())
-                            .spawn();
-
-                        let mut webdriver_process = match driver_result {
-                            Ok(process) => process,
-                            Err(e) => {
-                                return Ok(format!("❌ Failed to start safaridriver: {}\n\nMake sure safaridriver is installed.", e));
-                            }
-                        };
-
-                        // Wait for safaridriver to start up
-                        tokio::time::sleep(tokio::time::Duration::from_millis(1000)).await;
-
-                        // Connect to SafariDriver
-                        match g3_computer_control::SafariDriver::with_port(port).await {
-                            Ok(driver) => {
-                                let session = std::sync::Arc::new(tokio::sync::Mutex::new(WebDriverSession::Safari(driver)));
-                                *self.webdriver_session.write().await = Some(session);
-                                *self.webdriver_process.write().await = Some(webdriver_process);
-
-                                Ok("✅ WebDriver session started successfully! Safari should open automatically.".to_string())
-                            }
-                            Err(e) => {
-                                let _ = webdriver_process.kill().await;
-                                Ok(format!("❌ Failed to connect to SafariDriver: {}\n\nThis might be because:\n  - Safari Remote Automation is not enabled (run: safaridriver --enable)\n  - Port {} is already in use\n  - Safari failed to start\n  - Network connectivity issue\n\nTo enable Remote Automation:\n  1. Run: safaridriver --enable (requires password, one-time setup)\n  2. Or manually: Safari → Develop → Allow Remote Automation", e, port))
-                            }
-                        }
-                    }
-                    WebDriverBrowser::ChromeHeadless => {
-                        let port = self.config.webdriver.chrome_port;
-
-                        // Start chromedriver process
-                        let driver_result = tokio::process::Command::new("chromedriver")
-                            .arg(format!("--port={}", port))
-                            .stdout(std::process::Stdio::null())
-                            .stderr(std::process::Stdio::null())
-                            .spawn();
-
-                        let mut webdriver_process = match driver_result {
-                            Ok(process) => process,
-                            Err(e) => {
-                                return Ok(format!("❌ Failed to start chromedriver: {}\n\nMake sure chromedriver is installed and in your PATH.\n\nInstall with:\n  - macOS: brew install chromedriver\n  - Linux: apt install chromium-chromedriver\n  - Or download from: https://chromedriver.chromium.org/downloads", e));
-                            }
-                        };
-
-                        // Wait for chromedriver to be ready with retry loop
-                        let max_retries = 10;
-                        let mut last_error = None;
-                        
-                        for attempt in 0..max_retries {
-                            // Wait before each attempt (200ms between retries, total max ~2s)
-                            tokio::time::sleep(tokio::time::Duration::from_millis(200)).await;
-                            
-                            // Try to connect to ChromeDriver in headless mode (with optional custom binary)
-                            let driver_result = match &self.config.webdriver.chrome_binary {
-                                Some(binary) => g3_computer_control::ChromeDriver::with_port_headless_and_binary(port, Some(binary)).await,
-                                None => g3_computer_control::ChromeDriver::with_port_headless(port).await,
-                            };
-                            
-                            match driver_result {
-                                Ok(driver) => {
-                                    let session = std::sync::Arc::new(tokio::sync::Mutex::new(WebDriverSession::Chrome(driver)));
-                                    *self.webdriver_session.write().await = Some(session);
-                                    *self.webdriver_process.write().await = Some(webdriver_process);
-
-                                    return Ok("✅ WebDriver session started successfully! Chrome is running in headless mode (no visible window).".to_string());
-                                }
-                                Err(e) => {
-                                    last_error = Some(e);
-                                    if attempt < max_retries - 1 {
-                                        // Continue retrying
-                                        continue;
-                                    }
-                                }
-                            }
-                        }
-
-                        // All retries failed
-                        let _ = webdriver_process.kill().await;
-                        let error_msg = last_error.map(|e| e.to_string()).unwrap_or_else(|| "Unknown error".to_string());
-                        Ok(format!("❌ Failed to connect to ChromeDriver after {} attempts: {}\n\nThis might be because:\n  - Chrome is not installed\n  - ChromeDriver version doesn't match Chrome version\n  - Port {} is already in use\n\nMake sure Chrome and ChromeDriver are installed and compatible.", max_retries, error_msg, port))
-                    }
-                }
-            }
-            "webdriver_navigate" => {
-                debug!("Processing webdriver_navigate tool call");
-
-                if !self.config.webdriver.enabled {
-                    return Ok(
-                        "❌ WebDriver is not enabled. Use --webdriver flag to enable.".to_string(),
-                    );
-                }
-
-                let session_guard = self.webdriver_session.read().await;
-                let session = match session_guard.as_ref() {
-                    Some(s) => s.clone(),
-                    None => {
-                        return Ok(
-                            "❌ No active WebDriver session. Call webdriver_start first."
-                                .to_string(),
-                        )
-                    }
-                };
-                drop(session_guard);
-                let url = match tool_call.args.get("url").and_then(|v| v.as_str()) {
-                    Some(u) => u,
-                    None => return Ok("❌ Missing url argument".to_string()),
-                };
-
-                let mut driver = session.lock().await;
-                match driver.navigate(url).await {
-                    Ok(_) => Ok(format!("✅ Navigated to {}", url)),
-                    Err(e) => Ok(format!("❌ Failed to navigate: {}", e)),
-                }
-            }
-            "webdriver_get_url" => {
-                debug!("Processing webdriver_get_url tool call");
-
-                if !self.config.webdriver.enabled {
-                    return Ok(
-                        "❌ WebDriver is not enabled. Use --webdriver flag to enable.".to_string(),
-                    );
-                }
-
-                let session_guard = self.webdriver_session.read().await;
-                let session = match session_guard.as_ref() {
-                    Some(s) => s.clone(),
-                    None => {
-                        return Ok(
-                            "❌ No active WebDriver session. Call webdriver_start first."
-                                .to_string(),
-                        )
-                    }
-                };
-
-                let driver = session.lock().await;
-                match driver.current_url().await {
-                    Ok(url) => Ok(format!("Current URL: {}", url)),
-                    Err(e) => Ok(format!("❌ Failed to get URL: {}", e)),
-                }
-            }
-            "webdriver_get_title" => {
-                debug!("Processing webdriver_get_title tool call");
-
-                if !self.config.webdriver.enabled {
-                    return Ok(
-                        "❌ WebDriver is not enabled. Use --webdriver flag to enable.".to_string(),
-                    );
-                }
-
-                let session_guard = self.webdriver_session.read().await;
-                let session = match session_guard.as_ref() {
-                    Some(s) => s.clone(),
-                    None => {
-                        return Ok(
-                            "❌ No active WebDriver session. Call webdriver_start first."
-                                .to_string(),
-                        )
-                    }
-                };
-
-                let driver = session.lock().await;
-                match driver.title().await {
-                    Ok(title) => Ok(format!("Page title: {}", title)),
-                    Err(e) => Ok(format!("❌ Failed to get title: {}", e)),
-                }
-            }
-            "webdriver_find_element" => {
-                debug!("Processing webdriver_find_element tool call");
-
-                if !self.config.webdriver.enabled {
-                    return Ok(
-                        "❌ WebDriver is not enabled. Use --webdriver flag to enable.".to_string(),
-                    );
-                }
-
-                let session_guard = self.webdriver_session.read().await;
-                let session = match session_guard.as_ref() {
-                    Some(s) => s.clone(),
-                    None => {
-                        return Ok(
-                            "❌ No active WebDriver session. Call webdriver_start first."
-                                .to_string(),
-                        )
-                    }
-                };
-
-                let selector = match tool_call.args.get("selector").and_then(|v| v.as_str()) {
-                    Some(s) => s,
-                    None => return Ok("❌ Missing selector argument".to_string()),
-                };
-
-                let mut driver = session.lock().await;
-                match driver.find_element(selector).await {
-                    Ok(elem) => match elem.text().await {
-                        Ok(text) => Ok(format!("Element text: {}", text)),
-                        Err(e) => Ok(format!("❌ Failed to get element text: {}", e)),
-                    },
-                    Err(e) => Ok(format!("❌ Failed to find element '{}': {}", selector, e)),
-                }
-            }
-            "webdriver_find_elements" => {
-                debug!("Processing webdriver_find_elements tool call");
-
-                if !self.config.webdriver.enabled {
-                    return Ok(
-                        "❌ WebDriver is not enabled. Use --webdriver flag to enable.".to_string(),
-                    );
-                }
-
-                let session_guard = self.webdriver_session.read().await;
-                let session = match session_guard.as_ref() {
-                    Some(s) => s.clone(),
-                    None => {
-                        return Ok(
-                            "❌ No active WebDriver session. Call webdriver_start first."
-                                .to_string(),
-                        )
-                    }
-                };
-
-                let selector = match tool_call.args.get("selector").and_then(|v| v.as_str()) {
-                    Some(s) => s,
-                    None => return Ok("❌ Missing selector argument".to_string()),
-                };
-
-                let mut driver = session.lock().await;
-                match driver.find_elements(selector).await {
-                    Ok(elements) => {
-                        let mut results = Vec::new();
-                        for (i, elem) in elements.iter().enumerate() {
-                            match elem.text().await {
-                                Ok(text) => results.push(format!("[{}]: {}", i, text)),
-                                Err(_) => results.push(format!("[{}]: <error getting text>", i)),
-                            }
-                        }
-                        Ok(format!(
-                            "Found {} elements:\n{}",
-                            results.len(),
-                            results.join("\n")
-                        ))
-                    }
-                    Err(e) => Ok(format!("❌ Failed to find elements '{}': {}", selector, e)),
-                }
-            }
-            "webdriver_click" => {
-                debug!("Processing webdriver_click tool call");
-
-                if !self.config.webdriver.enabled {
-                    return Ok(
-                        "❌ WebDriver is not enabled. Use --webdriver flag to enable.".to_string(),
-                    );
-                }
-
-                let session_guard = self.webdriver_session.read().await;
-                let session = match session_guard.as_ref() {
-                    Some(s) => s.clone(),
-                    None => {
-                        return Ok(
-                            "❌ No active WebDriver session. Call webdriver_start first."
-                                .to_string(),
-                        )
-                    }
-                };
-
-                let selector = match tool_call.args.get("selector").and_then(|v| v.as_str()) {
-                    Some(s) => s,
-                    None => return Ok("❌ Missing selector argument".to_string()),
-                };
-
-                let mut driver = session.lock().await;
-                match driver.find_element(selector).await {
-                    Ok(mut elem) => match elem.click().await {
-                        Ok(_) => Ok(format!("✅ Clicked element '{}'", selector)),
-                        Err(e) => Ok(format!("❌ Failed to click element: {}", e)),
-                    },
-                    Err(e) => Ok(format!("❌ Failed to find element '{}': {}", selector, e)),
-                }
-            }
-            "webdriver_send_keys" => {
-                debug!("Processing webdriver_send_keys tool call");
-
-                if !self.config.webdriver.enabled {
-                    return Ok(
-                        "❌ WebDriver is not enabled. Use --webdriver flag to enable.".to_string(),
-                    );
-                }
-
-                let session_guard = self.webdriver_session.read().await;
-                let session = match session_guard.as_ref() {
-                    Some(s) => s.clone(),
-                    None => {
-                        return Ok(
-                            "❌ No active WebDriver session. Call webdriver_start first."
-                                .to_string(),
-                        )
-                    }
-                };
-
-                let selector = match tool_call.args.get("selector").and_then(|v| v.as_str()) {
-                    Some(s) => s,
-                    None => return Ok("❌ Missing selector argument".to_string()),
-                };
-
-                let text = match tool_call.args.get("text").and_then(|v| v.as_str()) {
-                    Some(t) => t,
-                    None => return Ok("❌ Missing text argument".to_string()),
-                };
-
-                let clear_first = tool_call
-                    .args
-                    .get("clear_first")
-                    .and_then(|v| v.as_bool())
-                    .unwrap_or(true);
-
-                let mut driver = session.lock().await;
-                match driver.find_element(selector).await {
-                    Ok(mut elem) => {
-                        if clear_first {
-                            if let Err(e) = elem.clear().await {
-                                return Ok(format!("❌ Failed to clear element: {}", e));
-                            }
-                        }
-                        match elem.send_keys(text).await {
-                            Ok(_) => Ok(format!("✅ Sent keys to element '{}'", selector)),
-                            Err(e) => Ok(format!("❌ Failed to send keys: {}", e)),
-                        }
-                    }
-                    Err(e) => Ok(format!("❌ Failed to find element '{}': {}", selector, e)),
-                }
-            }
-            "webdriver_execute_script" => {
-                debug!("Processing webdriver_execute_script tool call");
-
-                if !self.config.webdriver.enabled {
-                    return Ok(
-                        "❌ WebDriver is not enabled. Use --webdriver flag to enable.".to_string(),
-                    );
-                }
-
-                let session_guard = self.webdriver_session.read().await;
-                let session = match session_guard.as_ref() {
-                    Some(s) => s.clone(),
-                    None => {
-                        return Ok(
-                            "❌ No active WebDriver session. Call webdriver_start first."
-                                .to_string(),
-                        )
-                    }
-                };
-
-                let script = match tool_call.args.get("script").and_then(|v| v.as_str()) {
-                    Some(s) => s,
-                    None => return Ok("❌ Missing script argument".to_string()),
-                };
-
-                let mut driver = session.lock().await;
-                match driver.execute_script(script, vec![]).await {
-                    Ok(result) => Ok(format!("Script result: {:?}", result)),
-                    Err(e) => Ok(format!("❌ Failed to execute script: {}", e)),
-                }
-            }
-            "webdriver_get_page_source" => {
-                debug!("Processing webdriver_get_page_source tool call");
-
-                if !self.config.webdriver.enabled {
-                    return Ok(
-                        "❌ WebDriver is not enabled. Use --webdriver flag to enable.".to_string(),
-                    );
-                }
-
-                // Extract optional parameters
-                let max_length = tool_call
-                    .args
-                    .get("max_length")
-                    .and_then(|v| v.as_u64())
-                    .map(|n| n as usize)
-                    .unwrap_or(10000);
-
-                let save_to_file = tool_call
-                    .args
-                    .get("save_to_file")
-                    .and_then(|v| v.as_str());
-
-                let session_guard = self.webdriver_session.read().await;
-                let session = match session_guard.as_ref() {
-                    Some(s) => s.clone(),
-                    None => {
-                        return Ok(
-                            "❌ No active WebDriver session. Call webdriver_start first."
-                                .to_string(),
-                        )
-                    }
-                };
-
-                let driver = session.lock().await;
-                match driver.page_source().await {
-                    Ok(source) => {
-                        // If save_to_file is specified, write to file
-                        if let Some(file_path) = save_to_file {
-                            let expanded_path = shellexpand::tilde(file_path);
-                            let path_str = expanded_path.as_ref();
-
-                            // Create parent directories if needed
-                            if let Some(parent) = std::path::Path::new(path_str).parent() {
-                                if let Err(e) = std::fs::create_dir_all(parent) {
-                                    return Ok(format!("❌ Failed to create directories: {}", e));
-                                }
-                            }
-
-                            match std::fs::write(path_str, &source) {
-                                Ok(_) => Ok(format!(
-                                    "✅ Page source ({} chars) saved to: {}",
-                                    source.len(),
-                                    path_str
-                                )),
-                                Err(e) => Ok(format!("❌ Failed to write file: {}", e)),
-                            }
-                        } else if max_length > 0 && source.len() > max_length {
-                            // Truncate if max_length is set and source exceeds it
-                            Ok(format!(
-                                "Page source ({} chars, truncated to {}):\n{}...",
-                                source.len(),
-                                max_length,
-                                &source[..max_length]
-                            ))
-                        } else {
-                            // Return full source
-                            Ok(format!("Page source ({} chars):\n{}", source.len(), source))
-                        }
-                    }
-                    Err(e) => Ok(format!("❌ Failed to get page source: {}", e)),
-                }
-            }
-            "webdriver_screenshot" => {
-                debug!("Processing webdriver_screenshot tool call");
-
-                if !self.config.webdriver.enabled {
-                    return Ok(
-                        "❌ WebDriver is not enabled. Use --webdriver flag to enable.".to_string(),
-                    );
-                }
-
-                let session_guard = self.webdriver_session.read().await;
-                let session = match session_guard.as_ref() {
-                    Some(s) => s.clone(),
-                    None => {
-                        return Ok(
-                            "❌ No active WebDriver session. Call webdriver_start first."
-                                .to_string(),
-                        )
-                    }
-                };
-
-                let path = match tool_call.args.get("path").and_then(|v| v.as_str()) {
-                    Some(p) => p,
-                    None => return Ok("❌ Missing path argument".to_string()),
-                };
-
-                let mut driver = session.lock().await;
-                match driver.screenshot(path).await {
-                    Ok(_) => Ok(format!("✅ Screenshot saved to {}", path)),
-                    Err(e) => Ok(format!("❌ Failed to take screenshot: {}", e)),
-                }
-            }
-            "webdriver_back" => {
-                debug!("Processing webdriver_back tool call");
-
-                if !self.config.webdriver.enabled {
-                    return Ok(
-                        "❌ WebDriver is not enabled. Use --webdriver flag to enable.".to_string(),
-                    );
-                }
-
-                let session_guard = self.webdriver_session.read().await;
-                let session = match session_guard.as_ref() {
-                    Some(s) => s.clone(),
-                    None => {
-                        return Ok(
-                            "❌ No active WebDriver session. Call webdriver_start first."
-                                .to_string(),
-                        )
-                    }
-                };
-
-                let mut driver = session.lock().await;
-                match driver.back().await {
-                    Ok(_) => Ok("✅ Navigated back".to_string()),
-                    Err(e) => Ok(format!("❌ Failed to navigate back: {}", e)),
-                }
-            }
-            "webdriver_forward" => {
-                debug!("Processing webdriver_forward tool call");
-
-                if !self.config.webdriver.enabled {
-                    return Ok(
-                        "❌ WebDriver is not enabled. Use --webdriver flag to enable.".to_string(),
-                    );
-                }
-
-                let session_guard = self.webdriver_session.read().await;
-                let session = match session_guard.as_ref() {
-                    Some(s) => s.clone(),
-                    None => {
-                        return Ok(
-                            "❌ No active WebDriver session. Call webdriver_start first."
-                                .to_string(),
-                        )
-                    }
-                };
-
-                let mut driver = session.lock().await;
-                match driver.forward().await {
-                    Ok(_) => Ok("✅ Navigated forward".to_string()),
-                    Err(e) => Ok(format!("❌ Failed to navigate forward: {}", e)),
-                }
-            }
-            "webdriver_refresh" => {
-                debug!("Processing webdriver_refresh tool call");
-
-                if !self.config.webdriver.enabled {
-                    return Ok(
-                        "❌ WebDriver is not enabled. Use --webdriver flag to enable.".to_string(),
-                    );
-                }
-
-                let session_guard = self.webdriver_session.read().await;
-                let session = match session_guard.as_ref() {
-                    Some(s) => s.clone(),
-                    None => {
-                        return Ok(
-                            "❌ No active WebDriver session. Call webdriver_start first."
-                                .to_string(),
-                        )
-                    }
-                };
-
-                let mut driver = session.lock().await;
-                match driver.refresh().await {
-                    Ok(_) => Ok("✅ Page refreshed".to_string()),
-                    Err(e) => Ok(format!("❌ Failed to refresh page: {}", e)),
-                }
-            }
-            "webdriver_quit" => {
-                debug!("Processing webdriver_quit tool call");
-
-                if !self.config.webdriver.enabled {
-                    return Ok(
-                        "❌ WebDriver is not enabled. Use --webdriver flag to enable.".to_string(),
-                    );
-                }
-
-                // Take the session
-                let session = match self.webdriver_session.write().await.take() {
-                    Some(s) => s.clone(),
-                    None => return Ok("❌ No active WebDriver session.".to_string()),
-                };
-
-                // Quit the WebDriver session
-                match std::sync::Arc::try_unwrap(session) {
-                    Ok(mutex) => {
-                        let driver = mutex.into_inner();
-                        match driver.quit().await {
-                            Ok(_) => {
-                                info!("WebDriver session closed successfully");
-
-                                // Kill the safaridriver process
-                                if let Some(mut process) =
-                                    self.webdriver_process.write().await.take()
-                                {
-                                    if let Err(e) = process.kill().await {
-                                        warn!("Failed to kill safaridriver process: {}", e);
-                                    } else {
-                                        info!("Safaridriver process terminated");
-                                    }
-                                }
-
-                                Ok("✅ WebDriver session closed and safaridriver stopped"
-                                    .to_string())
-                            }
-                            Err(e) => Ok(format!("❌ Failed to quit WebDriver: {}", e)),
-                        }
-                    }
-                    Err(_) => Ok("❌ Cannot quit: WebDriver session is still in use".to_string()),
-                }
-            }
-            "macax_list_apps" => {
-                debug!("Processing macax_list_apps tool call");
-
-                if !self.config.macax.enabled {
-                    return Ok(
-                        "❌ macOS Accessibility is not enabled. Use --macax flag to enable."
-                            .to_string(),
-                    );
-                }
-
-                let controller_guard = self.macax_controller.read().await;
-                let controller = match controller_guard.as_ref() {
-                    Some(c) => c,
-                    None => {
-                        return Ok("❌ macOS Accessibility controller not initialized.".to_string())
-                    }
-                };
-
-                match controller.list_applications() {
-                    Ok(apps) => {
-                        let app_list: Vec<String> = apps.iter().map(|a| a.name.clone()).collect();
-                        Ok(format!("Running applications:\n{}", app_list.join("\n")))
-                    }
-                    Err(e) => Ok(format!("❌ Failed to list applications: {}", e)),
-                }
-            }
-            "macax_get_frontmost_app" => {
-                debug!("Processing macax_get_frontmost_app tool call");
-
-                if !self.config.macax.enabled {
-                    return Ok(
-                        "❌ macOS Accessibility is not enabled. Use --macax flag to enable."
-                            .to_string(),
-                    );
-                }
-
-                let controller_guard = self.macax_controller.read().await;
-                let controller = match controller_guard.as_ref() {
-                    Some(c) => c,
-                    None => {
-                        return Ok("❌ macOS Accessibility controller not initialized.".to_string())
-                    }
-                };
-
-                match controller.get_frontmost_app() {
-                    Ok(app) => Ok(format!("Frontmost application: {}", app.name)),
-                    Err(e) => Ok(format!("❌ Failed to get frontmost app: {}", e)),
-                }
-            }
-            "macax_activate_app" => {
-                debug!("Processing macax_activate_app tool call");
-
-                if !self.config.macax.enabled {
-                    return Ok(
-                        "❌ macOS Accessibility is not enabled. Use --macax flag to enable."
-                            .to_string(),
-                    );
-                }
-
-                let app_name = match tool_call.args.get("app_name").and_then(|v| v.as_str()) {
-                    Some(n) => n,
-                    None => return Ok("❌ Missing app_name argument".to_string()),
-                };
-
-                let controller_guard = self.macax_controller.read().await;
-                let controller = match controller_guard.as_ref() {
-                    Some(c) => c,
-                    None => {
-                        return Ok("❌ macOS Accessibility controller not initialized.".to_string())
-                    }
-                };
-
-                match controller.activate_app(app_name) {
-                    Ok(_) => Ok(format!("✅ Activated application: {}", app_name)),
-                    Err(e) => Ok(format!("❌ Failed to activate app: {}", e)),
-                }
-            }
-            "macax_press_key" => {
-                debug!("Processing macax_press_key tool call");
-
-                if !self.config.macax.enabled {
-                    return Ok(
-                        "❌ macOS Accessibility is not enabled. Use --macax flag to enable."
-                            .to_string(),
-                    );
-                }
-
-                let app_name = match tool_call.args.get("app_name").and_then(|v| v.as_str()) {
-                    Some(n) => n,
-                    None => return Ok("❌ Missing app_name argument".to_string()),
-                };
-
-                let key = match tool_call.args.get("key").and_then(|v| v.as_str()) {
-                    Some(k) => k,
-                    None => return Ok("❌ Missing key argument".to_string()),
-                };
-
-                let modifiers_vec: Vec<&str> = tool_call
-                    .args
-                    .get("modifiers")
-                    .and_then(|v| v.as_array())
-                    .map(|arr| arr.iter().filter_map(|v| v.as_str()).collect())
-                    .unwrap_or_default();
-
-                let controller_guard = self.macax_controller.read().await;
-                let controller = match controller_guard.as_ref() {
-                    Some(c) => c,
-                    None => {
-                        return Ok("❌ macOS Accessibility controller not initialized.".to_string())
-                    }
-                };
-
-                match controller.press_key(app_name, key, modifiers_vec.clone()) {
-                    Ok(_) => {
-                        let modifier_str = if modifiers_vec.is_empty() {
-                            String::new()
-                        } else {
-                            format!(" with modifiers: {}", modifiers_vec.join("+"))
-                        };
-                        Ok(format!("✅ Pressed key: {}{}", key, modifier_str))
-                    }
-                    Err(e) => Ok(format!("❌ Failed to press key: {}", e)),
-                }
-            }
-            "macax_type_text" => {
-                debug!("Processing macax_type_text tool call");
-
-                if !self.config.macax.enabled {
-                    return Ok(
-                        "❌ macOS Accessibility is not enabled. Use --macax flag to enable."
-                            .to_string(),
-                    );
-                }
-
-                let app_name = match tool_call.args.get("app_name").and_then(|v| v.as_str()) {
-                    Some(n) => n,
-                    None => return Ok("❌ Missing app_name argument".to_string()),
-                };
-
-                let text = match tool_call.args.get("text").and_then(|v| v.as_str()) {
-                    Some(t) => t,
-                    None => return Ok("❌ Missing text argument".to_string()),
-                };
-
-                let controller_guard = self.macax_controller.read().await;
-                let controller = match controller_guard.as_ref() {
-                    Some(c) => c,
-                    None => {
-                        return Ok("❌ macOS Accessibility controller not initialized.".to_string())
-                    }
-                };
-
-                match controller.type_text(app_name, text) {
-                    Ok(_) => Ok(format!("✅ Typed text into {}", app_name)),
-                    Err(e) => Ok(format!("❌ Failed to type text: {}", e)),
-                }
-            }
-            "vision_find_text" => {
-                debug!("Processing vision_find_text tool call");
-
-                if let Some(controller) = &self.computer_controller {
-                    let app_name = tool_call
-                        .args
-                        .get("app_name")
-                        .and_then(|v| v.as_str())
-                        .ok_or_else(|| anyhow::anyhow!("Missing app_name parameter"))?;
-
-                    let text = tool_call
-                        .args
-                        .get("text")
-                        .and_then(|v| v.as_str())
-                        .ok_or_else(|| anyhow::anyhow!("Missing text parameter"))?;
-
-                    match controller.find_text_in_app(app_name, text).await {
-                        Ok(Some(location)) => {
-                            Ok(format!(
-                                "✅ Found '{}' in {} at position ({}, {}) with size {}x{} (confidence: {:.0}%)",
-                                location.text, app_name, location.x, location.y, location.width, location.height,
-                                location.confidence * 100.0
-                            ))
-                        }
-                        Ok(None) => Ok(format!("❌ Could not find '{}' in {}", text, app_name)),
-                        Err(e) => Ok(format!("❌ Error finding text: {}", e)),
-                    }
-                } else {
-                    Ok("❌ Computer control not enabled. Set computer_control.enabled = true in config.".to_string())
-                }
-            }
-            "vision_click_text" => {
-                debug!("Processing vision_click_text tool call");
-
-                if let Some(controller) = &self.computer_controller {
-                    let app_name = tool_call
-                        .args
-                        .get("app_name")
-                        .and_then(|v| v.as_str())
-                        .ok_or_else(|| anyhow::anyhow!("Missing app_name parameter"))?;
-
-                    let text = tool_call
-                        .args
-                        .get("text")
-                        .and_then(|v| v.as_str())
-                        .ok_or_else(|| anyhow::anyhow!("Missing text parameter"))?;
-
-                    match controller.find_text_in_app(app_name, text).await {
-                        Ok(Some(location)) => {
-                            // Click on center of text
-                            // IMPORTANT: location coordinates are in NSScreen space (Y=0 at BOTTOM, increases UPWARD)
-                            // location.x is the LEFT edge of the bounding box
-                            // location.y is the TOP edge of the bounding box (highest Y value in NSScreen space)
-                            // location.width and location.height are already scaled to screen space
-                            // To get center: we need to add half the SCALED width and subtract half the SCALED height
-
-                            if location.width == 0 || location.height == 0 {
-                                return Ok(format!(
-                                    "❌ Invalid bounding box dimensions: width={}, height={}",
-                                    location.width, location.height
-                                ));
-                            }
-
-                            debug!("[vision_click_text] Location from find_text_in_app: x={}, y={}, width={}, height={}, text='{}'",
-                                location.x, location.y, location.width, location.height, location.text);
-
-                            // Calculate center using the SCALED dimensions
-                            // X: Use right edge instead of center (Vision OCR bounding box seems offset)
-                            // This gives us: left edge + full width = right edge
-                            // Y: top edge - half of scaled height (subtract because Y increases upward)
-                            let click_x = location.x + location.width; // Right edge
-                            let half_height = location.height / 2;
-                            let click_y = location.y - half_height;
-
-                            debug!("[vision_click_text] Click position calculation: x={} + {} = {} (right edge), y={} - {} = {}",
-                                location.x, location.width, click_x, location.y, half_height, click_y);
-                            debug!("[vision_click_text] This means: left_edge={}, center={}, right_edge={}",
-                                location.x, click_x, location.x + location.width);
-
-                            match controller.click_at(click_x, click_y, Some(app_name)) {
-                                Ok(_) => Ok(format!(
-                                    "✅ Clicked on '{}' in {} at ({}, {})",
-                                    text, app_name, click_x, click_y
-                                )),
-                                Err(e) => Ok(format!("❌ Failed to click: {}", e)),
-                            }
-                        }
-                        Ok(None) => Ok(format!("❌ Could not find '{}' in {}", text, app_name)),
-                        Err(e) => Ok(format!("❌ Error finding text: {}", e)),
-                    }
-                } else {
-                    Ok("❌ Computer control not enabled. Set computer_control.enabled = true in config.".to_string())
-                }
-            }
-            "extract_text_with_boxes" => {
-                debug!("Processing extract_text_with_boxes tool call");
-
-                if !self.config.macax.enabled {
-                    return Ok(
-                        "❌ extract_text_with_boxes requires --macax flag to be enabled"
-                            .to_string(),
-                    );
-                }
-
-                if let Some(controller) = &self.computer_controller {
-                    let path = tool_call
-                        .args
-                        .get("path")
-                        .and_then(|v| v.as_str())
-                        .ok_or_else(|| anyhow::anyhow!("Missing path parameter"))?;
-
-                    // Optional: take screenshot of app first
-                    let final_path = if let Some(app_name) =
-                        tool_call.args.get("app_name").and_then(|v| v.as_str())
-                    {
-                        let temp_path =
-                            format!("/tmp/g3_extract_boxes_{}.png", uuid::Uuid::new_v4());
-                        match controller
-                            .take_screenshot(&temp_path, None, Some(app_name))
-                            .await
-                        {
-                            Ok(_) => temp_path,
-                            Err(e) => return Ok(format!("❌ Failed to take screenshot: {}", e)),
-                        }
-                    } else {
-                        path.to_string()
-                    };
-
-                    // Extract text with locations
-                    match controller.extract_text_with_locations(&final_path).await {
-                        Ok(locations) => {
-                            // Clean up temp file if we created one
-                            if final_path != path {
-                                let _ = std::fs::remove_file(&final_path);
-                            }
-
-                            // Return as JSON
-                            match serde_json::to_string_pretty(&locations) {
-                                Ok(json) => Ok(format!(
-                                    "✅ Extracted {} text elements:\n{}",
-                                    locations.len(),
-                                    json
-                                )),
-                                Err(e) => Ok(format!("❌ Failed to serialize results: {}", e)),
-                            }
-                        }
-                        Err(e) => Ok(format!("❌ Failed to extract text: {}", e)),
-                    }
-                } else {
-                    Ok("❌ Computer control not enabled. Set computer_control.enabled = true in config.".to_string())
-                }
-            }
-            "vision_click_near_text" => {
-                debug!("Processing vision_click_near_text tool call");
-
-                if let Some(controller) = &self.computer_controller {
-                    let app_name = tool_call
-                        .args
-                        .get("app_name")
-                        .and_then(|v| v.as_str())
-                        .ok_or_else(|| anyhow::anyhow!("Missing app_name parameter"))?;
-
-                    let text = tool_call
-                        .args
-                        .get("text")
-                        .and_then(|v| v.as_str())
-                        .ok_or_else(|| anyhow::anyhow!("Missing text parameter"))?;
-
-                    let direction = tool_call
-                        .args
-                        .get("direction")
-                        .and_then(|v| v.as_str())
-                        .unwrap_or("right");
-
-                    let distance = tool_call
-                        .args
-                        .get("distance")
-                        .and_then(|v| v.as_i64())
-                        .unwrap_or(50) as i32;
-
-                    match controller.find_text_in_app(app_name, text).await {
-                        Ok(Some(location)) => {
-                            // Calculate click position based on direction
-                            // location.x is LEFT edge, location.y is TOP edge (in NSScreen space)
-                            let (click_x, click_y) = match direction {
-                                "right" => (
-                                    location.x + location.width + distance,
-                                    location.y - (location.height / 2),
-                                ),
-                                "below" => (
-                                    location.x + (location.width / 2),
-                                    location.y - location.height - distance,
-                                ),
-                                "left" => {
-                                    (location.x - distance, location.y - (location.height / 2))
-                                }
-                                "above" => {
-                                    (location.x + (location.width / 2), location.y + distance)
-                                }
-                                _ => (
-                                    location.x + location.width + distance,
-                                    location.y - (location.height / 2),
-                                ),
-                            };
-                            debug!(
-                                "[vision_click_near_text] Clicking {} of text at ({}, {})",
-                                direction, click_x, click_y
-                            );
-
-                            match controller.click_at(click_x, click_y, Some(app_name)) {
-                                Ok(_) => Ok(format!(
-                                    "✅ Clicked {} of '{}' in {} at ({}, {})",
-                                    direction, text, app_name, click_x, click_y
-                                )),
-                                Err(e) => Ok(format!("❌ Failed to click: {}", e)),
-                            }
-                        }
-                        Ok(None) => Ok(format!("❌ Could not find '{}' in {}", text, app_name)),
-                        Err(e) => Ok(format!("❌ Error finding text: {}", e)),
-                    }
-                } else {
-                    Ok("❌ Computer control not enabled. Set computer_control.enabled = true in config.".to_string())
-                }
-            }
-            "code_search" => {
-                debug!("Processing code_search tool call");
-
-                // Parse the request
-                let request: crate::code_search::CodeSearchRequest =
-                    match serde_json::from_value(tool_call.args.clone()) {
-                        Ok(req) => req,
-                        Err(e) => {
-                            return Ok(format!("❌ Invalid code_search arguments: {}", e));
-                        }
-                    };
-
-                // Execute the code search
-                match crate::code_search::execute_code_search(request).await {
-                    Ok(response) => {
-                        // Serialize the response to JSON
-                        match serde_json::to_string_pretty(&response) {
-                            Ok(json_output) => {
-                                Ok(format!("✅ Code search completed\n{}", json_output))
-                            }
-                            Err(e) => Ok(format!("❌ Failed to serialize response: {}", e)),
-                        }
-                    }
-                    Err(e) => Ok(format!("❌ Code search failed: {}", e)),
-                }
-            }
-            _ => {
-                warn!("Unknown tool: {}", tool_call.tool);
-                Ok(format!("❓ Unknown tool: {}", tool_call.tool))
-            }
-        }
-    }
-
-    fn format_duration(duration: Duration) -> String {
-        let total_ms = duration.as_millis();
-
-        if total_ms < 1000 {
-            format!("{}ms", total_ms)
-        } else if total_ms < 60_000 {
-            let seconds = duration.as_secs_f64();
-            format!("{:.1}s", seconds)
-        } else {
-            let minutes = total_ms / 60_000;
-            let remaining_seconds = (total_ms % 60_000) as f64 / 1000.0;
-            format!("{}m {:.1}s", minutes, remaining_seconds)
-        }
-    }
+/// Build the executor for the configured backend, with the current directory
+/// as the workspace. Fails if the sandbox or container tooling is missing so
+/// commands never silently fall back to running on the host.
+fn create_code_executor(
+    config: &g3_config::ExecutionConfig,
+    is_autonomous: bool,
+) -> Result<g3_execution::CodeExecutor> {
+    use g3_config::ExecutionBackendKind;
+    use g3_execution::{ContainerOptions, ExecutionBackend, SandboxOptions};
+
+    let workspace = project::current_workspace();
+    let backend = match config.backend_for(is_autonomous) {
+        ExecutionBackendKind::Direct => ExecutionBackend::Direct,
+        ExecutionBackendKind::Sandbox => ExecutionBackend::Sandbox(SandboxOptions {
+            program: config.sandbox.program.clone(),
+            workspace,
+            allow_network: config.sandbox.allow_network,
+            writable_paths: config
+                .sandbox
+                .writable_paths
+                .iter()
+                .map(|p| std::path::PathBuf::from(shellexpand::tilde(p).into_owned()))
+                .collect(),
+        }),
+        ExecutionBackendKind::Container => ExecutionBackend::Container(ContainerOptions {
+            runtime: config.container.runtime.clone(),
+            image: config.container.image.clone(),
+            workspace,
+            allow_network: config.container.allow_network,
+            extra_args: config.container.extra_args.clone(),
+        }),
+    };
+    backend.check_available()?;
+    debug!("Using '{}' execution backend", backend.name());
+    Ok(g3_execution::CodeExecutor::with_backend(backend))
 }
 
-// Note: JSON tool call filtering is now handled by fixed_filter_json::fixed_filter_json_tool_calls
-
 // Apply unified diff to an input string with optional [start, end) bounds
 pub fn apply_unified_diff_to_string(
     file_content: &str,
@@ -0,0 +1,360 @@
+//! Machine-mode event stream
+//!
+//! With `--machine`, g3 writes one JSON object per line to stdout. Every
+//! line carries the protocol version (`v`), a sequence number, a timestamp
+//! and a `type` tag naming the event; the remaining fields depend on the
+//! type. The JSON Schema for the stream is published as `SCHEMA`.
+//!
+//! `MachineEventWriter` produces the stream. Consumers can use
+//! `parse_event_line` or `MachineEventReader` to read it back. Event types
+//! added later in the same protocol version deserialize as
+//! `MachineEvent::Unknown`, so older clients keep working.
+
+use anyhow::{anyhow, Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::io::{self, BufRead, Write};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+/// Version of the event stream written by this build
+pub const PROTOCOL_VERSION: u32 = 1;
+
+/// JSON Schema describing every line of the event stream
+pub const SCHEMA: &str = include_str!("../schemas/machine-events.v1.json");
+
+/// A single event in the stream
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum MachineEvent {
+    /// The agent is ready; always the first event
+    SessionStart {
+        mode: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        provider: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        model: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        workspace: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        session_id: Option<String>,
+    },
+    /// The process is about to exit
+    SessionEnd,
+    /// A task was handed to the agent
+    TaskStart { task: String },
+    /// A task finished; `response` is the agent's closing text
+    TaskEnd { response: String },
+    /// Free-form output that has no dedicated event type
+    Message { text: String },
+    /// The system prompt, when `--show-prompt` is set
+    SystemPrompt { text: String },
+    /// Context window housekeeping (compaction, thinning, warnings)
+    ContextStatus {
+        message: String,
+        #[serde(default)]
+        thinning: bool,
+    },
+    /// A tool call is about to run
+    ToolStart {
+        tool: String,
+        #[serde(default)]
+        args: Value,
+    },
+    /// One line of tool output
+    ToolOutput { tool: String, line: String },
+    /// A tool call finished
+    ToolEnd {
+        tool: String,
+        duration_ms: u64,
+        success: bool,
+    },
+    /// A chunk of streamed assistant text
+    AssistantDelta { text: String },
+    /// The agent's `final_output` summary
+    FinalOutput { summary: String },
+    /// Result of a slash command
+    CommandResult {
+        command: String,
+        success: bool,
+        output: String,
+    },
+    /// A failed task is being retried
+    Retry {
+        attempt: u32,
+        max_attempts: u32,
+        delay_ms: u64,
+        reason: String,
+    },
+    /// The current task was cancelled
+    Cancelled,
+    /// A task or command failed
+    Error { message: String },
+    /// The agent asked a question; machine mode answers it automatically
+    /// with `answer`, an index into `options`
+    PromptRequest {
+        message: String,
+        options: Vec<String>,
+        answer: usize,
+    },
+    /// An event type this client does not know about
+    #[serde(other)]
+    Unknown,
+}
+
+/// One line of the stream: an event plus its envelope
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MachineEventLine {
+    pub v: u32,
+    pub seq: u64,
+    pub ts: DateTime<Utc>,
+    #[serde(flatten)]
+    pub event: MachineEvent,
+}
+
+/// Destination for the events of a machine-mode session
+pub trait MachineEventSink: Send + Sync {
+    fn emit(&self, event: MachineEvent);
+
+    /// Ask the client to choose one of `options` and return its index
+    /// Default: report the question as `prompt_request` and pick the first option
+    fn prompt(&self, message: &str, options: &[&str]) -> usize {
+        self.emit(MachineEvent::PromptRequest {
+            message: message.to_string(),
+            options: options.iter().map(|o| o.to_string()).collect(),
+            answer: 0,
+        });
+        0
+    }
+
+    /// Returns true if `prompt` reaches someone who can answer it
+    fn answers_prompts(&self) -> bool {
+        false
+    }
+}
+
+/// Writes events as JSON lines, numbering them in order
+pub struct MachineEventWriter<W: Write + Send = io::Stdout> {
+    out: Mutex<W>,
+    seq: AtomicU64,
+}
+
+impl MachineEventWriter<io::Stdout> {
+    pub fn stdout() -> Self {
+        Self::new(io::stdout())
+    }
+}
+
+impl<W: Write + Send> MachineEventWriter<W> {
+    pub fn new(out: W) -> Self {
+        Self {
+            out: Mutex::new(out),
+            seq: AtomicU64::new(0),
+        }
+    }
+
+    /// Write one event and flush it
+    pub fn emit(&self, event: MachineEvent) {
+        let mut out = self.out.lock().unwrap();
+        let line = MachineEventLine {
+            v: PROTOCOL_VERSION,
+            seq: self.seq.fetch_add(1, Ordering::SeqCst),
+            ts: Utc::now(),
+            event,
+        };
+        // Serializing these types cannot fail, and a closed stdout leaves
+        // nobody to report the error to
+        if let Ok(json) = serde_json::to_string(&line) {
+            let _ = writeln!(out, "{}", json);
+            let _ = out.flush();
+        }
+    }
+
+    pub fn into_inner(self) -> W {
+        self.out.into_inner().unwrap()
+    }
+}
+
+impl<W: Write + Send> MachineEventSink for MachineEventWriter<W> {
+    fn emit(&self, event: MachineEvent) {
+        MachineEventWriter::emit(self, event);
+    }
+}
+
+/// Parse one line of the stream, rejecting other protocol versions
+pub fn parse_event_line(line: &str) -> Result<MachineEventLine> {
+    let value: Value = serde_json::from_str(line).context("Machine event is not valid JSON")?;
+    let version = value
+        .get("v")
+        .and_then(Value::as_u64)
+        .ok_or_else(|| anyhow!("Machine event has no protocol version"))?;
+    if version != PROTOCOL_VERSION as u64 {
+        return Err(anyhow!(
+            "Unsupported machine event protocol version {} (expected {})",
+            version,
+            PROTOCOL_VERSION
+        ));
+    }
+    serde_json::from_value(value).context("Malformed machine event")
+}
+
+/// Reads events from a g3 `--machine` stream, skipping blank lines
+pub struct MachineEventReader<R: BufRead> {
+    lines: io::Lines<R>,
+}
+
+impl<R: BufRead> MachineEventReader<R> {
+    pub fn new(reader: R) -> Self {
+        Self {
+            lines: reader.lines(),
+        }
+    }
+}
+
+impl<R: BufRead> Iterator for MachineEventReader<R> {
+    type Item = Result<MachineEventLine>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let line = match self.lines.next()? {
+                Ok(line) => line,
+                Err(e) => return Some(Err(e.into())),
+            };
+            if !line.trim().is_empty() {
+                return Some(parse_event_line(&line));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_events_round_trip_through_reader() {
+        let writer = MachineEventWriter::new(Vec::new());
+        writer.emit(MachineEvent::ToolStart {
+            tool: "shell".to_string(),
+            args: serde_json::json!({"command": "ls"}),
+        });
+        writer.emit(MachineEvent::ToolEnd {
+            tool: "shell".to_string(),
+            duration_ms: 12,
+            success: true,
+        });
+        let output = writer.into_inner();
+
+        let first = String::from_utf8(output.clone()).unwrap();
+        assert!(first.starts_with(r#"{"v":1,"seq":0,"ts":"#));
+        assert!(first.contains(r#""type":"tool_start","tool":"shell""#));
+
+        let events: Vec<_> = MachineEventReader::new(output.as_slice())
+            .map(|line| line.unwrap())
+            .collect();
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[1].seq, 1);
+        assert_eq!(
+            events[1].event,
+            MachineEvent::ToolEnd {
+                tool: "shell".to_string(),
+                duration_ms: 12,
+                success: true,
+            }
+        );
+    }
+
+    #[test]
+    fn test_unknown_types_and_versions() {
+        let line = r#"{"v":1,"seq":4,"ts":"2025-01-01T00:00:00Z","type":"shiny_new_event","x":1}"#;
+        assert_eq!(parse_event_line(line).unwrap().event, MachineEvent::Unknown);
+
+        let line = r#"{"v":2,"seq":4,"ts":"2025-01-01T00:00:00Z","type":"session_end"}"#;
+        let err = parse_event_line(line).unwrap_err().to_string();
+        assert!(err.contains("version 2"), "{}", err);
+    }
+
+    #[test]
+    fn test_schema_lists_every_event_type() {
+        let schema: Value = serde_json::from_str(SCHEMA).unwrap();
+        let types: Vec<&str> = schema["$defs"]["event"]["oneOf"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|variant| variant["properties"]["type"]["const"].as_str().unwrap())
+            .collect();
+
+        let samples = [
+            MachineEvent::SessionStart {
+                mode: "task".to_string(),
+                provider: None,
+                model: None,
+                workspace: None,
+                session_id: None,
+            },
+            MachineEvent::SessionEnd,
+            MachineEvent::TaskStart {
+                task: String::new(),
+            },
+            MachineEvent::TaskEnd {
+                response: String::new(),
+            },
+            MachineEvent::Message {
+                text: String::new(),
+            },
+            MachineEvent::SystemPrompt {
+                text: String::new(),
+            },
+            MachineEvent::ContextStatus {
+                message: String::new(),
+                thinning: false,
+            },
+            MachineEvent::ToolStart {
+                tool: String::new(),
+                args: Value::Null,
+            },
+            MachineEvent::ToolOutput {
+                tool: String::new(),
+                line: String::new(),
+            },
+            MachineEvent::ToolEnd {
+                tool: String::new(),
+                duration_ms: 0,
+                success: true,
+            },
+            MachineEvent::AssistantDelta {
+                text: String::new(),
+            },
+            MachineEvent::FinalOutput {
+                summary: String::new(),
+            },
+            MachineEvent::CommandResult {
+                command: String::new(),
+                success: true,
+                output: String::new(),
+            },
+            MachineEvent::Retry {
+                attempt: 1,
+                max_attempts: 3,
+                delay_ms: 0,
+                reason: String::new(),
+            },
+            MachineEvent::Cancelled,
+            MachineEvent::Error {
+                message: String::new(),
+            },
+            MachineEvent::PromptRequest {
+                message: String::new(),
+                options: vec![],
+                answer: 0,
+            },
+        ];
+        assert_eq!(types.len(), samples.len());
+        for sample in samples {
+            let json = serde_json::to_value(&sample).unwrap();
+            let name = json["type"].as_str().unwrap();
+            assert!(types.contains(&name), "schema is missing {}", name);
+        }
+    }
+}
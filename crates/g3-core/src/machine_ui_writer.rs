@@ -0,0 +1,156 @@
+use crate::machine_events::{MachineEvent, MachineEventSink};
+use crate::ui_writer::UiWriter;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Machine-mode implementation of UiWriter that emits the typed events
+/// described in `machine_events`, either as JSON Lines (`--machine`)
+/// or as JSON-RPC notifications (`--serve-stdio`).
+///
+/// This is designed for programmatic consumption and outputs everything verbatim
+pub struct MachineUiWriter {
+    events: Arc<dyn MachineEventSink>,
+    /// Tool whose output is currently being printed
+    current_tool: Mutex<String>,
+}
+
+impl MachineUiWriter {
+    pub fn new(events: Arc<dyn MachineEventSink>) -> Self {
+        Self {
+            events,
+            current_tool: Mutex::new(String::new()),
+        }
+    }
+
+    fn emit(&self, event: MachineEvent) {
+        self.events.emit(event);
+    }
+
+    fn message(&self, text: &str) {
+        // Blank spacer lines carry no information for a machine reader
+        if !text.trim().is_empty() {
+            self.emit(MachineEvent::Message {
+                text: text.to_string(),
+            });
+        }
+    }
+
+    fn tool_output(&self, line: &str) {
+        self.emit(MachineEvent::ToolOutput {
+            tool: self.current_tool.lock().unwrap().clone(),
+            line: line.to_string(),
+        });
+    }
+}
+
+impl UiWriter for MachineUiWriter {
+    fn print(&self, message: &str) {
+        self.message(message);
+    }
+
+    fn println(&self, message: &str) {
+        self.message(message);
+    }
+
+    fn print_inline(&self, message: &str) {
+        self.message(message);
+    }
+
+    fn print_system_prompt(&self, prompt: &str) {
+        self.emit(MachineEvent::SystemPrompt {
+            text: prompt.to_string(),
+        });
+    }
+
+    fn print_context_status(&self, message: &str) {
+        self.emit(MachineEvent::ContextStatus {
+            message: message.trim().to_string(),
+            thinning: false,
+        });
+    }
+
+    fn print_context_thinning(&self, message: &str) {
+        self.emit(MachineEvent::ContextStatus {
+            message: message.trim().to_string(),
+            thinning: true,
+        });
+    }
+
+    fn print_tool_header(&self, tool_name: &str, tool_args: Option<&serde_json::Value>) {
+        *self.current_tool.lock().unwrap() = tool_name.to_string();
+        self.emit(MachineEvent::ToolStart {
+            tool: tool_name.to_string(),
+            args: tool_args.cloned().unwrap_or_default(),
+        });
+    }
+
+    fn print_tool_arg(&self, _key: &str, _value: &str) {
+        // Full arguments are already part of tool_start
+    }
+
+    fn print_tool_output_header(&self) {}
+
+    fn update_tool_output_line(&self, line: &str) {
+        self.tool_output(line);
+    }
+
+    fn print_tool_output_line(&self, line: &str) {
+        self.tool_output(line);
+    }
+
+    fn print_tool_output_summary(&self, _count: usize) {
+        // Machine mode never truncates tool output
+    }
+
+    fn print_tool_timing(&self, _duration_str: &str) {
+        // Reported as duration_ms in tool_end
+    }
+
+    fn print_tool_result(&self, tool_name: &str, duration: Duration, success: bool) {
+        self.emit(MachineEvent::ToolEnd {
+            tool: tool_name.to_string(),
+            duration_ms: duration.as_millis() as u64,
+            success,
+        });
+    }
+
+    fn print_agent_prompt(&self) {}
+
+    fn print_agent_response(&self, content: &str) {
+        self.emit(MachineEvent::AssistantDelta {
+            text: content.to_string(),
+        });
+    }
+
+    fn notify_sse_received(&self) {
+        // No-op for machine mode
+    }
+
+    fn flush(&self) {
+        // Every event is flushed as it is written
+    }
+
+    fn wants_full_output(&self) -> bool {
+        true // Machine mode wants complete, untruncated output
+    }
+
+    fn is_interactive(&self) -> bool {
+        self.events.answers_prompts()
+    }
+
+    fn prompt_user_yes_no(&self, message: &str) -> bool {
+        // With --machine nobody can answer, so the sink reports the request and
+        // picks "yes" to allow automation to proceed
+        self.events.prompt(message, &["yes", "no"]) == 0
+    }
+
+    fn prompt_user_choice(&self, message: &str, options: &[&str]) -> usize {
+        self.events.prompt(message, options)
+    }
+
+    fn print_final_output(&self, summary: &str) {
+        self.emit(MachineEvent::FinalOutput {
+            summary: summary.to_string(),
+        });
+    }
+}
@@ -0,0 +1,580 @@
+//! Model Context Protocol (MCP) client
+//!
+//! Connects to the servers listed under `[mcp.servers.*]`, discovers their tools
+//! and exposes them to the agent alongside the built-in tools. Two transports are
+//! supported: stdio (newline-delimited JSON-RPC over a child process) and
+//! streamable HTTP (JSON-RPC POSTs answered with JSON or an SSE stream).
+
+use crate::tools::{ToolContext, ToolHandler, ToolPermissions};
+use crate::ToolCall;
+use anyhow::{anyhow, Context, Result};
+use async_trait::async_trait;
+use g3_config::{McpConfig, McpServerConfig};
+use g3_providers::Tool;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::process::Stdio;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::process::{Child, ChildStdin, ChildStdout, Command};
+use tokio::sync::Mutex;
+use tracing::{debug, info, warn};
+
+/// MCP protocol revision we advertise during `initialize`
+pub const PROTOCOL_VERSION: &str = "2025-03-26";
+
+/// Prefix for tool names exposed to the model, e.g. `mcp__build__run_tests`
+pub const TOOL_PREFIX: &str = "mcp__";
+
+const DEFAULT_TIMEOUT_SECS: u64 = 60;
+
+/// A tool as advertised by an MCP server in `tools/list`
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct McpTool {
+    pub name: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default = "default_input_schema")]
+    pub input_schema: Value,
+}
+
+fn default_input_schema() -> Value {
+    json!({ "type": "object", "properties": {} })
+}
+
+enum Transport {
+    Stdio {
+        // Held so the server is killed when the client is dropped
+        _child: Child,
+        stdin: ChildStdin,
+        stdout: BufReader<ChildStdout>,
+    },
+    Http {
+        client: reqwest::Client,
+        url: String,
+        headers: HashMap<String, String>,
+        session_id: Option<String>,
+    },
+}
+
+/// Connection to a single MCP server
+pub struct McpClient {
+    name: String,
+    transport: Mutex<Transport>,
+    next_id: AtomicU64,
+    timeout: Duration,
+    tools: Vec<McpTool>,
+}
+
+impl McpClient {
+    /// Spawn or connect to the server, perform the handshake and list its tools
+    pub async fn connect(name: &str, config: &McpServerConfig) -> Result<Self> {
+        let transport = match (&config.command, &config.url) {
+            (Some(command), None) => {
+                let mut child = Command::new(shellexpand::tilde(command).as_ref())
+                    .args(&config.args)
+                    .envs(expand_env_values(name, &config.env)?)
+                    .stdin(Stdio::piped())
+                    .stdout(Stdio::piped())
+                    .stderr(Stdio::null())
+                    .kill_on_drop(true)
+                    .spawn()
+                    .with_context(|| format!("Failed to spawn MCP server '{}'", name))?;
+                let stdin = child.stdin.take().ok_or_else(|| anyhow!("No stdin"))?;
+                let stdout = child.stdout.take().ok_or_else(|| anyhow!("No stdout"))?;
+                Transport::Stdio {
+                    _child: child,
+                    stdin,
+                    stdout: BufReader::new(stdout),
+                }
+            }
+            (None, Some(url)) => Transport::Http {
+                client: reqwest::Client::new(),
+                url: url.clone(),
+                headers: expand_env_values(name, &config.headers)?,
+                session_id: None,
+            },
+            _ => {
+                return Err(anyhow!(
+                    "MCP server '{}' must set exactly one of 'command' or 'url'",
+                    name
+                ))
+            }
+        };
+
+        let mut client = Self {
+            name: name.to_string(),
+            transport: Mutex::new(transport),
+            next_id: AtomicU64::new(1),
+            timeout: Duration::from_secs(config.timeout_seconds.unwrap_or(DEFAULT_TIMEOUT_SECS)),
+            tools: Vec::new(),
+        };
+
+        client.initialize().await?;
+        client.tools = client.list_tools().await?;
+        Ok(client)
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn tools(&self) -> &[McpTool] {
+        &self.tools
+    }
+
+    async fn initialize(&self) -> Result<()> {
+        let result = self
+            .request(
+                "initialize",
+                json!({
+                    "protocolVersion": PROTOCOL_VERSION,
+                    "capabilities": {},
+                    "clientInfo": { "name": "g3", "version": env!("CARGO_PKG_VERSION") },
+                }),
+            )
+            .await?;
+        let server_info = result.get("serverInfo").cloned().unwrap_or_default();
+        debug!("MCP server '{}' initialized: {}", self.name, server_info);
+        self.notify("notifications/initialized", json!({})).await
+    }
+
+    async fn list_tools(&self) -> Result<Vec<McpTool>> {
+        let mut tools = Vec::new();
+        let mut cursor: Option<String> = None;
+        loop {
+            let params = match &cursor {
+                Some(c) => json!({ "cursor": c }),
+                None => json!({}),
+            };
+            let result = self.request("tools/list", params).await?;
+            let page: Vec<McpTool> = serde_json::from_value(
+                result.get("tools").cloned().unwrap_or_else(|| json!([])),
+            )?;
+            tools.extend(page);
+            cursor = result
+                .get("nextCursor")
+                .and_then(|c| c.as_str())
+                .map(str::to_string);
+            if cursor.is_none() {
+                break;
+            }
+        }
+        Ok(tools)
+    }
+
+    /// Invoke a tool and flatten its content blocks into text
+    pub async fn call_tool(&self, tool: &str, arguments: Value) -> Result<(String, bool)> {
+        let result = self
+            .request("tools/call", json!({ "name": tool, "arguments": arguments }))
+            .await?;
+        let is_error = result
+            .get("isError")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false);
+
+        let mut text = Vec::new();
+        if let Some(content) = result.get("content").and_then(|c| c.as_array()) {
+            for block in content {
+                match block.get("type").and_then(|t| t.as_str()) {
+                    Some("text") => {
+                        if let Some(t) = block.get("text").and_then(|t| t.as_str()) {
+                            text.push(t.to_string());
+                        }
+                    }
+                    Some("resource") => {
+                        if let Some(t) = block.pointer("/resource/text").and_then(|t| t.as_str()) {
+                            text.push(t.to_string());
+                        }
+                    }
+                    Some(other) => text.push(format!("[{} content omitted]", other)),
+                    None => {}
+                }
+            }
+        }
+        if text.is_empty() {
+            if let Some(structured) = result.get("structuredContent") {
+                text.push(structured.to_string());
+            }
+        }
+
+        Ok((text.join("\n"), is_error))
+    }
+
+    async fn request(&self, method: &str, params: Value) -> Result<Value> {
+        let id = self.next_id.fetch_add(1, Ordering::SeqCst);
+        let message = json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params });
+
+        let response = tokio::time::timeout(self.timeout, self.exchange(&message, Some(id)))
+            .await
+            .map_err(|_| anyhow!("MCP server '{}' timed out on {}", self.name, method))??;
+
+        if let Some(error) = response.get("error") {
+            return Err(anyhow!(
+                "MCP server '{}' returned error for {}: {}",
+                self.name,
+                method,
+                error
+                    .get("message")
+                    .and_then(|m| m.as_str())
+                    .unwrap_or(&error.to_string())
+            ));
+        }
+        Ok(response.get("result").cloned().unwrap_or(Value::Null))
+    }
+
+    async fn notify(&self, method: &str, params: Value) -> Result<()> {
+        let message = json!({ "jsonrpc": "2.0", "method": method, "params": params });
+        self.exchange(&message, None).await?;
+        Ok(())
+    }
+
+    /// Send a message and, if `id` is set, wait for the matching response
+    async fn exchange(&self, message: &Value, id: Option<u64>) -> Result<Value> {
+        let mut transport = self.transport.lock().await;
+        match &mut *transport {
+            Transport::Stdio { stdin, stdout, .. } => {
+                let mut line = serde_json::to_string(message)?;
+                line.push('\n');
+                stdin.write_all(line.as_bytes()).await?;
+                stdin.flush().await?;
+
+                let Some(id) = id else {
+                    return Ok(Value::Null);
+                };
+                loop {
+                    let mut buf = String::new();
+                    if stdout.read_line(&mut buf).await? == 0 {
+                        return Err(anyhow!("MCP server '{}' closed its stdout", self.name));
+                    }
+                    let Ok(incoming) = serde_json::from_str::<Value>(buf.trim()) else {
+                        debug!("Ignoring non-JSON line from MCP server '{}'", self.name);
+                        continue;
+                    };
+                    if is_response_to(&incoming, id) {
+                        return Ok(incoming);
+                    }
+                    // Server-initiated requests (sampling, roots, ...) are not supported
+                    if let (Some(req_id), Some(_)) = (incoming.get("id"), incoming.get("method")) {
+                        let reply = json!({
+                            "jsonrpc": "2.0",
+                            "id": req_id,
+                            "error": { "code": -32601, "message": "Method not supported by g3" },
+                        });
+                        let mut line = serde_json::to_string(&reply)?;
+                        line.push('\n');
+                        stdin.write_all(line.as_bytes()).await?;
+                        stdin.flush().await?;
+                    }
+                }
+            }
+            Transport::Http {
+                client,
+                url,
+                headers,
+                session_id,
+            } => {
+                let mut builder = client
+                    .post(url.as_str())
+                    .header("Accept", "application/json, text/event-stream")
+                    .header("MCP-Protocol-Version", PROTOCOL_VERSION)
+                    .json(message);
+                for (key, value) in headers.iter() {
+                    builder = builder.header(key.as_str(), value.as_str());
+                }
+                if let Some(sid) = session_id.as_deref() {
+                    builder = builder.header("Mcp-Session-Id", sid);
+                }
+
+                let response = builder.send().await?;
+                if let Some(sid) = response
+                    .headers()
+                    .get("mcp-session-id")
+                    .and_then(|v| v.to_str().ok())
+                {
+                    *session_id = Some(sid.to_string());
+                }
+
+                let status = response.status();
+                if !status.is_success() {
+                    let body = response.text().await.unwrap_or_default();
+                    return Err(anyhow!(
+                        "MCP server '{}' returned HTTP {}: {}",
+                        self.name,
+                        status,
+                        body
+                    ));
+                }
+
+                let Some(id) = id else {
+                    return Ok(Value::Null);
+                };
+                let is_sse = response
+                    .headers()
+                    .get("content-type")
+                    .and_then(|v| v.to_str().ok())
+                    .map(|ct| ct.starts_with("text/event-stream"))
+                    .unwrap_or(false);
+                let body = response.text().await?;
+
+                if is_sse {
+                    parse_sse_response(&body, id).ok_or_else(|| {
+                        anyhow!("MCP server '{}' sent no response for request {}", self.name, id)
+                    })
+                } else {
+                    Ok(serde_json::from_str(&body)?)
+                }
+            }
+        }
+    }
+}
+
+fn is_response_to(message: &Value, id: u64) -> bool {
+    message.get("method").is_none() && message.get("id").and_then(|v| v.as_u64()) == Some(id)
+}
+
+/// Find the JSON-RPC response with the given id in an SSE body
+fn parse_sse_response(body: &str, id: u64) -> Option<Value> {
+    let mut data = String::new();
+    for line in body.lines().chain(std::iter::once("")) {
+        if let Some(rest) = line.strip_prefix("data:") {
+            if !data.is_empty() {
+                data.push('\n');
+            }
+            data.push_str(rest.trim_start());
+        } else if line.is_empty() && !data.is_empty() {
+            if let Ok(message) = serde_json::from_str::<Value>(&data) {
+                if is_response_to(&message, id) {
+                    return Some(message);
+                }
+            }
+            data.clear();
+        }
+    }
+    None
+}
+
+/// Expand `$VAR` and `${VAR}` in header and environment values, so secrets
+/// can stay out of the config file
+fn expand_env_values(
+    server: &str,
+    values: &HashMap<String, String>,
+) -> Result<HashMap<String, String>> {
+    values
+        .iter()
+        .map(|(key, value)| {
+            let expanded = shellexpand::env(value)
+                .with_context(|| format!("MCP server '{}': cannot expand '{}'", server, key))?;
+            Ok((key.clone(), expanded.into_owned()))
+        })
+        .collect()
+}
+
+/// Build the model-facing name for an MCP tool: `mcp__<server>__<tool>`
+///
+/// Characters outside `[A-Za-z0-9_-]` are replaced so the name is accepted by
+/// all providers' tool-name validation.
+pub fn qualified_tool_name(server: &str, tool: &str) -> String {
+    let sanitize = |s: &str| -> String {
+        s.chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() || c == '_' || c == '-' {
+                    c
+                } else {
+                    '_'
+                }
+            })
+            .collect()
+    };
+    format!("{}{}__{}", TOOL_PREFIX, sanitize(server), sanitize(tool))
+}
+
+/// All connected MCP servers and the routing table for their tools
+#[derive(Default)]
+pub struct McpManager {
+    clients: Vec<McpClient>,
+    /// qualified tool name -> (client index, server-side tool name)
+    routes: HashMap<String, (usize, String)>,
+}
+
+impl McpManager {
+    /// Connect to every enabled server. Servers that fail to start are logged and skipped.
+    pub async fn connect(config: &McpConfig) -> Self {
+        let mut manager = Self::default();
+
+        let mut names: Vec<&String> = config.servers.keys().collect();
+        names.sort();
+
+        for name in names {
+            let server = &config.servers[name];
+            if !server.enabled {
+                continue;
+            }
+            match McpClient::connect(name, server).await {
+                Ok(client) => {
+                    info!(
+                        "Connected to MCP server '{}' ({} tools)",
+                        name,
+                        client.tools().len()
+                    );
+                    manager.add_client(client);
+                }
+                Err(e) => warn!("Failed to connect to MCP server '{}': {}", name, e),
+            }
+        }
+
+        manager
+    }
+
+    fn add_client(&mut self, client: McpClient) {
+        let index = self.clients.len();
+        for tool in client.tools() {
+            let qualified = qualified_tool_name(client.name(), &tool.name);
+            if self.routes.contains_key(&qualified) {
+                warn!("Duplicate MCP tool '{}' ignored", qualified);
+                continue;
+            }
+            self.routes.insert(qualified, (index, tool.name.clone()));
+        }
+        self.clients.push(client);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.routes.is_empty()
+    }
+
+    pub fn has_tool(&self, name: &str) -> bool {
+        self.routes.contains_key(name)
+    }
+
+    /// Tool definitions for every discovered MCP tool, in a stable order
+    pub fn tool_definitions(&self) -> Vec<Tool> {
+        let mut tools = Vec::new();
+        for client in &self.clients {
+            for tool in client.tools() {
+                let name = qualified_tool_name(client.name(), &tool.name);
+                if self.routes.get(&name).map(|(_, t)| t) != Some(&tool.name) {
+                    continue;
+                }
+                tools.push(Tool {
+                    name,
+                    description: format!(
+                        "[MCP: {}] {}",
+                        client.name(),
+                        tool.description.as_deref().unwrap_or("")
+                    ),
+                    input_schema: tool.input_schema.clone(),
+                });
+            }
+        }
+        tools
+    }
+
+    /// One registry handler per discovered tool, all sharing this manager
+    pub fn handlers(manager: &Arc<Self>) -> Vec<Arc<dyn ToolHandler>> {
+        manager
+            .tool_definitions()
+            .into_iter()
+            .map(|definition| {
+                Arc::new(McpToolHandler {
+                    manager: Arc::clone(manager),
+                    definition,
+                }) as Arc<dyn ToolHandler>
+            })
+            .collect()
+    }
+
+    /// Route a tool call to its server, formatting the result like built-in tools
+    pub async fn call_tool(&self, name: &str, args: Value) -> Result<String> {
+        let (index, tool) = self
+            .routes
+            .get(name)
+            .ok_or_else(|| anyhow!("Unknown MCP tool: {}", name))?;
+        let client = &self.clients[*index];
+
+        match client.call_tool(tool, args).await {
+            Ok((text, false)) => Ok(format!("✅ {}", text)),
+            Ok((text, true)) => Ok(format!("❌ {}", text)),
+            Err(e) => Ok(format!("❌ MCP tool '{}' failed: {}", name, e)),
+        }
+    }
+}
+
+/// Registry entry for a single MCP tool
+struct McpToolHandler {
+    manager: Arc<McpManager>,
+    definition: Tool,
+}
+
+#[async_trait]
+impl ToolHandler for McpToolHandler {
+    fn name(&self) -> &str {
+        &self.definition.name
+    }
+
+    fn definition(&self) -> Tool {
+        self.definition.clone()
+    }
+
+    fn permissions(&self) -> ToolPermissions {
+        // External servers can do anything; treat them as running commands
+        ToolPermissions {
+            executes_commands: true,
+            network: true,
+            ..Default::default()
+        }
+    }
+
+    async fn execute(&self, tool_call: &ToolCall, _ctx: &ToolContext<'_>) -> Result<String> {
+        debug!("Routing {} to MCP server", tool_call.tool);
+        self.manager
+            .call_tool(&tool_call.tool, tool_call.args.clone())
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_qualified_tool_name_sanitizes() {
+        assert_eq!(qualified_tool_name("build", "run_tests"), "mcp__build__run_tests");
+        assert_eq!(qualified_tool_name("my.server", "deploy/app"), "mcp__my_server__deploy_app");
+    }
+
+    #[test]
+    fn test_parse_sse_response_picks_matching_id() {
+        let body = "event: message\n\
+                    data: {\"jsonrpc\":\"2.0\",\"method\":\"notifications/progress\",\"params\":{}}\n\
+                    \n\
+                    event: message\n\
+                    data: {\"jsonrpc\":\"2.0\",\"id\":7,\"result\":{\"ok\":true}}\n";
+        let response = parse_sse_response(body, 7).unwrap();
+        assert_eq!(response["result"]["ok"], true);
+        assert!(parse_sse_response(body, 8).is_none());
+    }
+
+    #[test]
+    fn test_expand_env_values() {
+        std::env::set_var("G3_MCP_TEST_TOKEN", "secret");
+        let headers = HashMap::from([
+            (
+                "Authorization".to_string(),
+                "Bearer ${G3_MCP_TEST_TOKEN}".to_string(),
+            ),
+            ("X-Plain".to_string(), "value".to_string()),
+        ]);
+        let expanded = expand_env_values("deploy", &headers).unwrap();
+        assert_eq!(expanded["Authorization"], "Bearer secret");
+        assert_eq!(expanded["X-Plain"], "value");
+
+        let missing = HashMap::from([("X-Key".to_string(), "${G3_MCP_TEST_UNSET}".to_string())]);
+        let err = expand_env_values("deploy", &missing).unwrap_err();
+        assert!(err.to_string().contains("X-Key"));
+    }
+}
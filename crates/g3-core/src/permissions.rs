@@ -0,0 +1,185 @@
+//! Tool permission policy
+//!
+//! Compiles the `[permissions]` config section into matchers and decides
+//! whether a tool call may run. The agent consults the policy before
+//! dispatching each call; `Prompt` decisions are resolved through
+//! `UiWriter::prompt_user_yes_no` or, when running unattended, by the
+//! configured `unattended` action.
+
+use anyhow::{anyhow, Result};
+use g3_config::{PermissionAction, PermissionsConfig};
+use regex::Regex;
+use std::path::{Component, Path, PathBuf};
+
+struct CompiledRule {
+    tool: Regex,
+    subject: Option<Regex>,
+    action: PermissionAction,
+}
+
+/// A decision for a single tool call, with the rule that produced it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PermissionDecision {
+    pub action: PermissionAction,
+    /// Human-readable description of the deciding rule (or default)
+    pub reason: String,
+}
+
+pub struct PermissionPolicy {
+    rules: Vec<(CompiledRule, String)>,
+    default: PermissionAction,
+    unattended: PermissionAction,
+    auto_allow_read_only: bool,
+}
+
+impl PermissionPolicy {
+    /// Compile the policy, expanding `${workspace}` to `workspace`
+    pub fn from_config(config: &PermissionsConfig, workspace: &Path) -> Result<Self> {
+        let workspace_str = workspace.to_string_lossy();
+        let mut rules = Vec::new();
+
+        for (i, rule) in config.rules.iter().enumerate() {
+            let subject = match (&rule.pattern, &rule.regex) {
+                (Some(_), Some(_)) => {
+                    return Err(anyhow!(
+                        "Permission rule {} for '{}' sets both 'pattern' and 'regex'",
+                        i + 1,
+                        rule.tool
+                    ))
+                }
+                (Some(pattern), None) => {
+                    let expanded = pattern.replace("${workspace}", &workspace_str);
+                    let expanded = shellexpand::tilde(&expanded).into_owned();
+                    Some(glob_to_regex(&expanded)?)
+                }
+                (None, Some(regex)) => Some(Regex::new(regex).map_err(|e| {
+                    anyhow!("Invalid regex in permission rule {}: {}", i + 1, e)
+                })?),
+                (None, None) => None,
+            };
+
+            let description = format!(
+                "{} rule for '{}'{}",
+                action_name(rule.action),
+                rule.tool,
+                rule.pattern
+                    .as_ref()
+                    .or(rule.regex.as_ref())
+                    .map(|p| format!(" matching '{}'", p))
+                    .unwrap_or_default()
+            );
+
+            rules.push((
+                CompiledRule {
+                    tool: glob_to_regex(&rule.tool)?,
+                    subject,
+                    action: rule.action,
+                },
+                description,
+            ));
+        }
+
+        Ok(Self {
+            rules,
+            default: config.default,
+            unattended: config.unattended,
+            auto_allow_read_only: config.auto_allow_read_only,
+        })
+    }
+
+    /// Evaluate the rules for a call. Deny beats prompt, prompt beats allow.
+    pub fn evaluate(&self, tool: &str, subject: &str, read_only: bool) -> PermissionDecision {
+        let mut matched: Option<(PermissionAction, &str)> = None;
+
+        for (rule, description) in &self.rules {
+            if !rule.tool.is_match(tool) {
+                continue;
+            }
+            if let Some(subject_re) = &rule.subject {
+                if !subject_re.is_match(subject) {
+                    continue;
+                }
+            }
+            let stronger = match (matched.map(|(a, _)| a), rule.action) {
+                (None, _) => true,
+                (Some(PermissionAction::Deny), _) => false,
+                (Some(_), PermissionAction::Deny) => true,
+                (Some(PermissionAction::Allow), PermissionAction::Prompt) => true,
+                _ => false,
+            };
+            if stronger {
+                matched = Some((rule.action, description));
+            }
+        }
+
+        match matched {
+            Some((action, description)) => PermissionDecision {
+                action,
+                reason: description.to_string(),
+            },
+            None if read_only && self.auto_allow_read_only => PermissionDecision {
+                action: PermissionAction::Allow,
+                reason: "read-only tool".to_string(),
+            },
+            None => PermissionDecision {
+                action: self.default,
+                reason: "default policy".to_string(),
+            },
+        }
+    }
+
+    /// What a `Prompt` decision becomes when nobody can answer it
+    pub fn unattended_action(&self) -> PermissionAction {
+        match self.unattended {
+            PermissionAction::Prompt => PermissionAction::Deny,
+            action => action,
+        }
+    }
+}
+
+fn action_name(action: PermissionAction) -> &'static str {
+    match action {
+        PermissionAction::Allow => "allow",
+        PermissionAction::Deny => "deny",
+        PermissionAction::Prompt => "prompt",
+    }
+}
+
+/// Translate a glob into an anchored regex. `*` matches any run of characters
+/// (including `/`), `?` matches a single character; everything else is literal.
+fn glob_to_regex(glob: &str) -> Result<Regex> {
+    let mut re = String::from("^");
+    for c in glob.chars() {
+        match c {
+            '*' => re.push_str(".*"),
+            '?' => re.push('.'),
+            c => re.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    re.push('$');
+    Regex::new(&re).map_err(|e| anyhow!("Invalid pattern '{}': {}", glob, e))
+}
+
+/// Make a tool path argument absolute and lexically normalized so rules such
+/// as `${workspace}/*` cannot be sidestepped with `..` or `~`.
+pub fn resolve_path(path: &str) -> String {
+    let expanded = shellexpand::tilde(path);
+    let path = Path::new(expanded.as_ref());
+    let absolute = if path.is_absolute() {
+        path.to_path_buf()
+    } else {
+        crate::project::current_workspace().join(path)
+    };
+
+    let mut normalized = PathBuf::new();
+    for component in absolute.components() {
+        match component {
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            Component::CurDir => {}
+            other => normalized.push(other.as_os_str()),
+        }
+    }
+    normalized.to_string_lossy().into_owned()
+}
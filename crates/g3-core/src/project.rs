@@ -1,7 +1,33 @@
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
+use std::future::Future;
 use std::path::{Path, PathBuf};
 
+tokio::task_local! {
+    /// Workspace of the enclosing `Project::scope`
+    static SCOPED_WORKSPACE: PathBuf;
+}
+
+/// The workspace set by an enclosing `Project::scope`, if any
+pub fn scoped_workspace() -> Option<PathBuf> {
+    SCOPED_WORKSPACE.try_with(|dir| dir.clone()).ok()
+}
+
+/// The directory relative paths resolve against: the scoped workspace, or
+/// the process working directory outside a scope
+pub fn current_workspace() -> PathBuf {
+    scoped_workspace().unwrap_or_else(|| std::env::current_dir().unwrap_or_default())
+}
+
+/// Resolve a tool path (after tilde expansion) against the scoped workspace.
+/// Outside a scope the path is returned unchanged.
+pub fn workspace_path(path: &str) -> String {
+    match scoped_workspace() {
+        Some(dir) if Path::new(path).is_relative() => dir.join(path).to_string_lossy().into_owned(),
+        _ => path.to_string(),
+    }
+}
+
 /// Represents a G3 project with workspace configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Project {
@@ -128,6 +154,16 @@ impl Project {
         Ok(())
     }
 
+    /// Run `future` with this project as its workspace, without changing the
+    /// process working directory. Tool paths, shell commands, `todo.g3.md`
+    /// and session logs resolve against the workspace inside the scope, so
+    /// agents for different projects can share one process.
+    pub async fn scope<F: Future>(&self, future: F) -> F::Output {
+        SCOPED_WORKSPACE
+            .scope(self.workspace_dir.clone(), future)
+            .await
+    }
+
     /// Get the logs directory for the project
     pub fn logs_dir(&self) -> PathBuf {
         self.workspace_dir.join("logs")
@@ -0,0 +1,433 @@
+//! Repository map: the definitions in a codebase, ranked by how much the
+//! rest of the code depends on them
+//!
+//! Every supported file is run through [`TreeSitterSearcher`] with a
+//! definitions query and a references query for its language. Files form a
+//! graph with an edge from each file to the files defining the names it
+//! references, and PageRank over that graph orders the files. The map is
+//! rendered most central file first until a token budget runs out, and is
+//! used both by the `repo_map` tool and as discovery context for the planner.
+
+use crate::code_search::{is_skipped_dir, TreeSitterSearcher};
+use crate::semantic_index::MAX_FILE_BYTES;
+use anyhow::{bail, Result};
+use g3_providers::Tokenizer;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tracing::debug;
+use tree_sitter::Query;
+use walkdir::WalkDir;
+
+/// Definitions listed per file, most referenced first
+const MAX_SYMBOLS_PER_FILE: usize = 12;
+
+const DAMPING: f64 = 0.85;
+const PAGERANK_ITERATIONS: usize = 30;
+
+/// Definition and reference queries for one language. Definition patterns
+/// capture the name node under the kind of definition (`@function`,
+/// `@struct`, ...); reference patterns capture names as `@ref`.
+struct TagQueries {
+    language: &'static str,
+    definitions: &'static str,
+    references: &'static str,
+}
+
+const TAG_QUERIES: &[TagQueries] = &[
+    TagQueries {
+        language: "rust",
+        definitions: r#"
+            (function_item name: (identifier) @function)
+            (function_signature_item name: (identifier) @function)
+            (struct_item name: (type_identifier) @struct)
+            (enum_item name: (type_identifier) @enum)
+            (union_item name: (type_identifier) @union)
+            (trait_item name: (type_identifier) @trait)
+            (type_item name: (type_identifier) @type)
+            (macro_definition name: (identifier) @macro)
+            (const_item name: (identifier) @const)
+            (static_item name: (identifier) @static)
+        "#,
+        references: r#"
+            (call_expression function: (identifier) @ref)
+            (call_expression function: (field_expression field: (field_identifier) @ref))
+            (call_expression function: (scoped_identifier name: (identifier) @ref))
+            (macro_invocation macro: (identifier) @ref)
+            (type_identifier) @ref
+        "#,
+    },
+    TagQueries {
+        language: "python",
+        definitions: r#"
+            (function_definition name: (identifier) @function)
+            (class_definition name: (identifier) @class)
+        "#,
+        references: r#"
+            (call function: (identifier) @ref)
+            (call function: (attribute attribute: (identifier) @ref))
+            (class_definition superclasses: (argument_list (identifier) @ref))
+        "#,
+    },
+    TagQueries {
+        language: "javascript",
+        definitions: r#"
+            (function_declaration name: (identifier) @function)
+            (class_declaration name: (identifier) @class)
+            (method_definition name: (property_identifier) @method)
+            (variable_declarator name: (identifier) @function value: [(arrow_function) (function_expression)])
+        "#,
+        references: r#"
+            (call_expression function: (identifier) @ref)
+            (call_expression function: (member_expression property: (property_identifier) @ref))
+            (new_expression constructor: (identifier) @ref)
+        "#,
+    },
+    TagQueries {
+        language: "typescript",
+        definitions: r#"
+            (function_declaration name: (identifier) @function)
+            (class_declaration name: (type_identifier) @class)
+            (abstract_class_declaration name: (type_identifier) @class)
+            (interface_declaration name: (type_identifier) @interface)
+            (type_alias_declaration name: (type_identifier) @type)
+            (enum_declaration name: (identifier) @enum)
+            (method_definition name: (property_identifier) @method)
+            (variable_declarator name: (identifier) @function value: [(arrow_function) (function_expression)])
+        "#,
+        references: r#"
+            (call_expression function: (identifier) @ref)
+            (call_expression function: (member_expression property: (property_identifier) @ref))
+            (new_expression constructor: (identifier) @ref)
+            (type_identifier) @ref
+        "#,
+    },
+    TagQueries {
+        language: "go",
+        definitions: r#"
+            (function_declaration name: (identifier) @function)
+            (method_declaration name: (field_identifier) @method)
+            (type_spec name: (type_identifier) @type)
+        "#,
+        references: r#"
+            (call_expression function: (identifier) @ref)
+            (call_expression function: (selector_expression field: (field_identifier) @ref))
+            (type_identifier) @ref
+        "#,
+    },
+    TagQueries {
+        language: "java",
+        definitions: r#"
+            (class_declaration name: (identifier) @class)
+            (interface_declaration name: (identifier) @interface)
+            (enum_declaration name: (identifier) @enum)
+            (record_declaration name: (identifier) @record)
+            (method_declaration name: (identifier) @method)
+        "#,
+        references: r#"
+            (method_invocation name: (identifier) @ref)
+            (object_creation_expression type: (type_identifier) @ref)
+            (type_identifier) @ref
+        "#,
+    },
+    TagQueries {
+        language: "c",
+        definitions: r#"
+            (function_definition declarator: (function_declarator declarator: (identifier) @function))
+            (struct_specifier name: (type_identifier) @struct body: (field_declaration_list))
+            (enum_specifier name: (type_identifier) @enum body: (enumerator_list))
+            (type_definition declarator: (type_identifier) @type)
+        "#,
+        references: r#"
+            (call_expression function: (identifier) @ref)
+            (type_identifier) @ref
+        "#,
+    },
+    TagQueries {
+        language: "cpp",
+        definitions: r#"
+            (function_definition declarator: (function_declarator declarator: (identifier) @function))
+            (function_definition declarator: (function_declarator declarator: (field_identifier) @method))
+            (function_definition declarator: (function_declarator declarator: (qualified_identifier name: (identifier) @method)))
+            (class_specifier name: (type_identifier) @class body: (field_declaration_list))
+            (struct_specifier name: (type_identifier) @struct body: (field_declaration_list))
+            (enum_specifier name: (type_identifier) @enum body: (enumerator_list))
+            (namespace_definition name: (namespace_identifier) @namespace)
+        "#,
+        references: r#"
+            (call_expression function: (identifier) @ref)
+            (call_expression function: (field_expression field: (field_identifier) @ref))
+            (call_expression function: (qualified_identifier name: (identifier) @ref))
+            (type_identifier) @ref
+        "#,
+    },
+    TagQueries {
+        language: "haskell",
+        definitions: r#"
+            (function name: (variable) @function)
+            (data_type name: (name) @type)
+            (newtype name: (name) @type)
+            (class name: (name) @class)
+        "#,
+        references: r#"
+            (apply function: (variable) @ref)
+            (name) @ref
+        "#,
+    },
+    TagQueries {
+        language: "scheme",
+        definitions: r#"
+            (list . (symbol) @_define . (list . (symbol) @function) (#eq? @_define "define"))
+            (list . (symbol) @_define . (symbol) @variable (#eq? @_define "define"))
+        "#,
+        references: r#"
+            (list . (symbol) @ref)
+        "#,
+    },
+];
+
+/// A named definition in a file
+#[derive(Debug, Clone, PartialEq)]
+pub struct Definition {
+    pub name: String,
+    /// Capture name from the definitions query, e.g. `function` or `struct`
+    pub kind: String,
+    /// 1-based
+    pub line: usize,
+    /// References to the name from other files
+    pub references: usize,
+}
+
+/// A file in the map with its definitions
+#[derive(Debug, Clone)]
+pub struct RankedFile {
+    /// Relative to the mapped root, with `/` separators
+    pub path: String,
+    pub rank: f64,
+    pub definitions: Vec<Definition>,
+}
+
+/// Ranked definitions of a directory tree
+#[derive(Debug, Clone, Default)]
+pub struct RepoMap {
+    /// Most central first
+    pub files: Vec<RankedFile>,
+}
+
+struct ParsedFile {
+    path: String,
+    definitions: Vec<Definition>,
+    references: HashMap<String, usize>,
+}
+
+impl RepoMap {
+    /// Resolve the directory `path` names relative to `workspace`, refusing
+    /// anything that leads outside the workspace
+    pub fn resolve_root(workspace: &Path, path: &str) -> Result<PathBuf> {
+        let workspace = workspace.canonicalize()?;
+        let root = match workspace.join(path).canonicalize() {
+            Ok(root) if root.is_dir() => root,
+            _ => bail!("Not a directory: {}", path),
+        };
+        if !root.starts_with(&workspace) {
+            bail!("{} is outside the workspace", path);
+        }
+        Ok(root)
+    }
+
+    /// Parse every supported file under `root` and rank them
+    pub fn build(root: &Path) -> Result<Self> {
+        let mut searcher = TreeSitterSearcher::new()?;
+        let mut queries: HashMap<&'static str, Option<(Query, Query)>> = HashMap::new();
+        let mut parsed = Vec::new();
+
+        for entry in WalkDir::new(root)
+            .into_iter()
+            .filter_entry(|e| e.depth() == 0 || !is_skipped_dir(e))
+            .filter_map(|e| e.ok())
+        {
+            let path = entry.path();
+            if !entry.file_type().is_file() {
+                continue;
+            }
+            let Some(language) = TreeSitterSearcher::language_for_path(path) else {
+                continue;
+            };
+            if !entry.metadata().is_ok_and(|m| m.len() <= MAX_FILE_BYTES) {
+                continue;
+            }
+            let Some((definitions_query, references_query)) = queries
+                .entry(language)
+                .or_insert_with(|| compile_queries(&searcher, language))
+            else {
+                continue;
+            };
+            let Ok(source) = fs::read_to_string(path) else {
+                continue;
+            };
+
+            let definitions = searcher
+                .captures(language, definitions_query, &source)?
+                .into_iter()
+                .filter(|c| !c.name.starts_with('_'))
+                .map(|c| Definition {
+                    name: c.text,
+                    kind: c.name,
+                    line: c.line,
+                    references: 0,
+                })
+                .collect();
+            let mut references = HashMap::new();
+            for capture in searcher.captures(language, references_query, &source)? {
+                *references.entry(capture.text).or_insert(0) += 1;
+            }
+
+            let relative = path.strip_prefix(root).unwrap_or(path);
+            parsed.push(ParsedFile {
+                path: relative
+                    .components()
+                    .map(|c| c.as_os_str().to_string_lossy())
+                    .collect::<Vec<_>>()
+                    .join("/"),
+                definitions,
+                references,
+            });
+        }
+
+        Ok(Self::rank(parsed))
+    }
+
+    fn rank(mut parsed: Vec<ParsedFile>) -> Self {
+        let n = parsed.len();
+        let mut defined_in: HashMap<String, Vec<usize>> = HashMap::new();
+        for (i, file) in parsed.iter().enumerate() {
+            let names: HashSet<&str> = file.definitions.iter().map(|d| d.name.as_str()).collect();
+            for name in names {
+                defined_in.entry(name.to_string()).or_default().push(i);
+            }
+        }
+
+        // Edge weights from each referencing file to the defining files. A
+        // name defined in several files splits its weight between them, and
+        // repeated references count sub-linearly.
+        let mut edges: Vec<HashMap<usize, f64>> = vec![HashMap::new(); n];
+        let mut reference_counts: HashMap<(usize, String), usize> = HashMap::new();
+        for (from, file) in parsed.iter().enumerate() {
+            for (name, &count) in &file.references {
+                let Some(targets) = defined_in.get(name) else {
+                    continue;
+                };
+                if targets.contains(&from) {
+                    // Most likely the file's own definition
+                    continue;
+                }
+                let weight = (count as f64).sqrt() / targets.len() as f64;
+                for &to in targets {
+                    *edges[from].entry(to).or_insert(0.0) += weight;
+                    *reference_counts.entry((to, name.clone())).or_insert(0) += count;
+                }
+            }
+        }
+
+        let ranks = pagerank(&edges);
+        for (i, file) in parsed.iter_mut().enumerate() {
+            for definition in &mut file.definitions {
+                definition.references = reference_counts
+                    .get(&(i, definition.name.clone()))
+                    .copied()
+                    .unwrap_or(0);
+            }
+        }
+
+        let mut files: Vec<RankedFile> = parsed
+            .into_iter()
+            .zip(ranks)
+            .filter(|(file, _)| !file.definitions.is_empty())
+            .map(|(file, rank)| RankedFile {
+                path: file.path,
+                rank,
+                definitions: file.definitions,
+            })
+            .collect();
+        files.sort_by(|a, b| b.rank.total_cmp(&a.rank).then(a.path.cmp(&b.path)));
+        debug!("Repo map ranked {} of {} files", files.len(), n);
+        Self { files }
+    }
+
+    /// Text listing of the most central files and their most referenced
+    /// definitions that fits in `max_tokens`
+    pub fn render(&self, max_tokens: u32, tokenizer: &dyn Tokenizer) -> String {
+        let mut output = String::new();
+        let mut used = 0;
+        let mut shown = 0;
+
+        for file in &self.files {
+            let mut definitions: Vec<&Definition> = file.definitions.iter().collect();
+            definitions.sort_by(|a, b| b.references.cmp(&a.references).then(a.line.cmp(&b.line)));
+            definitions.truncate(MAX_SYMBOLS_PER_FILE);
+            definitions.sort_by_key(|d| d.line);
+
+            let mut block = format!("{}:\n", file.path);
+            for definition in &definitions {
+                block.push_str(&format!(
+                    "  {}: {} {}\n",
+                    definition.line, definition.kind, definition.name
+                ));
+            }
+            let tokens = tokenizer.count_tokens(&block);
+            if used + tokens > max_tokens {
+                break;
+            }
+            used += tokens;
+            shown += 1;
+            output.push_str(&block);
+        }
+
+        let omitted = self.files.len() - shown;
+        if omitted > 0 {
+            output.push_str(&format!("... {} more files\n", omitted));
+        }
+        output
+    }
+}
+
+fn compile_queries(searcher: &TreeSitterSearcher, language: &str) -> Option<(Query, Query)> {
+    let tags = TAG_QUERIES.iter().find(|t| t.language == language)?;
+    let compiled = searcher
+        .compile_query(language, tags.definitions)
+        .and_then(|definitions| {
+            let references = searcher.compile_query(language, tags.references)?;
+            Ok((definitions, references))
+        });
+    match compiled {
+        Ok(queries) => Some(queries),
+        Err(e) => {
+            debug!("Repo map skips {} files: {}", language, e);
+            None
+        }
+    }
+}
+
+/// PageRank over weighted outgoing edges; files without outgoing edges
+/// spread their rank evenly
+fn pagerank(edges: &[HashMap<usize, f64>]) -> Vec<f64> {
+    let n = edges.len();
+    if n == 0 {
+        return Vec::new();
+    }
+    let base = (1.0 - DAMPING) / n as f64;
+    let totals: Vec<f64> = edges.iter().map(|e| e.values().sum()).collect();
+    let mut ranks = vec![1.0 / n as f64; n];
+
+    for _ in 0..PAGERANK_ITERATIONS {
+        let dangling: f64 = (0..n).filter(|&i| totals[i] == 0.0).map(|i| ranks[i]).sum();
+        let mut next = vec![base + DAMPING * dangling / n as f64; n];
+        for (from, targets) in edges.iter().enumerate() {
+            for (&to, &weight) in targets {
+                next[to] += DAMPING * ranks[from] * weight / totals[from];
+            }
+        }
+        ranks = next;
+    }
+    ranks
+}
@@ -0,0 +1,218 @@
+use std::path::Path;
+use tree_sitter::{Language, Node, Parser};
+
+/// Symbols longer than this are split into their members (for containers
+/// such as impl blocks and classes) or truncated before embedding
+const MAX_CHUNK_LINES: usize = 120;
+
+/// Window size for files without any recognised symbols
+const WINDOW_LINES: usize = 60;
+
+/// A piece of a source file that is embedded as one vector
+#[derive(Debug, Clone, PartialEq)]
+pub struct Chunk {
+    /// Symbol name, or the file name for windows of symbol-less files
+    pub symbol: String,
+    /// tree-sitter node kind, e.g. `function_item`, or `window`
+    pub kind: String,
+    /// 1-based, inclusive
+    pub start_line: usize,
+    pub end_line: usize,
+    pub text: String,
+}
+
+/// Node kinds that become chunks
+const SYMBOL_KINDS: &[&str] = &[
+    // Rust
+    "function_item",
+    "struct_item",
+    "enum_item",
+    "union_item",
+    "trait_item",
+    "impl_item",
+    "mod_item",
+    "macro_definition",
+    "const_item",
+    "static_item",
+    "type_item",
+    // Python
+    "function_definition",
+    "class_definition",
+    "decorated_definition",
+    // JavaScript / TypeScript
+    "function_declaration",
+    "generator_function_declaration",
+    "class_declaration",
+    "method_definition",
+    "interface_declaration",
+    "type_alias_declaration",
+    "enum_declaration",
+    "lexical_declaration",
+    // Go
+    "method_declaration",
+    "type_declaration",
+    // Java
+    "constructor_declaration",
+    "record_declaration",
+    // C / C++
+    "struct_specifier",
+    "class_specifier",
+    "namespace_definition",
+    "template_declaration",
+];
+
+/// Kinds whose members are chunked separately when the whole is too large
+const CONTAINER_KINDS: &[&str] = &[
+    "impl_item",
+    "trait_item",
+    "mod_item",
+    "class_definition",
+    "decorated_definition",
+    "class_declaration",
+    "interface_declaration",
+    "record_declaration",
+    "class_specifier",
+    "struct_specifier",
+    "namespace_definition",
+    "template_declaration",
+];
+
+/// Wrappers and bodies that are searched for symbols without becoming chunks
+const TRANSPARENT_KINDS: &[&str] = &[
+    "export_statement",
+    "declaration_list",
+    "block",
+    "class_body",
+    "interface_body",
+    "field_declaration_list",
+    "enum_body",
+    "body_statement",
+];
+
+/// tree-sitter language for a file, by extension
+pub fn language_for_path(path: &Path) -> Option<Language> {
+    let ext = path.extension().and_then(|e| e.to_str())?;
+    let language: Language = match ext {
+        "rs" => tree_sitter_rust::LANGUAGE.into(),
+        "py" => tree_sitter_python::LANGUAGE.into(),
+        "js" | "jsx" | "mjs" => tree_sitter_javascript::LANGUAGE.into(),
+        "ts" => tree_sitter_typescript::LANGUAGE_TYPESCRIPT.into(),
+        "tsx" => tree_sitter_typescript::LANGUAGE_TSX.into(),
+        "go" => tree_sitter_go::LANGUAGE.into(),
+        "java" => tree_sitter_java::LANGUAGE.into(),
+        "c" | "h" => tree_sitter_c::LANGUAGE.into(),
+        "cpp" | "cc" | "cxx" | "hpp" | "hxx" => tree_sitter_cpp::LANGUAGE.into(),
+        "hs" => tree_sitter_haskell::LANGUAGE.into(),
+        "scm" | "ss" | "sld" | "sls" => tree_sitter_scheme::LANGUAGE.into(),
+        _ => return None,
+    };
+    Some(language)
+}
+
+/// Split a source file into symbol chunks. Files in unsupported languages
+/// yield nothing; files without symbols are split into fixed windows.
+pub fn chunk_source(path: &Path, source: &str) -> Vec<Chunk> {
+    let Some(language) = language_for_path(path) else {
+        return Vec::new();
+    };
+    let mut parser = Parser::new();
+    if parser.set_language(&language).is_err() {
+        return Vec::new();
+    }
+    let Some(tree) = parser.parse(source, None) else {
+        return Vec::new();
+    };
+
+    let mut chunks = Vec::new();
+    collect_chunks(tree.root_node(), source, &mut chunks);
+    if chunks.is_empty() {
+        chunks = window_chunks(path, source);
+    }
+    chunks
+}
+
+fn collect_chunks(node: Node, source: &str, chunks: &mut Vec<Chunk>) {
+    let mut cursor = node.walk();
+    for child in node.named_children(&mut cursor) {
+        let kind = child.kind();
+        if SYMBOL_KINDS.contains(&kind) {
+            let start_line = child.start_position().row + 1;
+            let end_line = child.end_position().row + 1;
+            let lines = end_line - start_line + 1;
+            if lines > MAX_CHUNK_LINES && CONTAINER_KINDS.contains(&kind) {
+                // Keep the header so the container itself stays findable
+                let header = source[child.byte_range()]
+                    .lines()
+                    .next()
+                    .unwrap_or_default()
+                    .to_string();
+                chunks.push(Chunk {
+                    symbol: symbol_name(child, source),
+                    kind: kind.to_string(),
+                    start_line,
+                    end_line: start_line,
+                    text: header,
+                });
+                collect_chunks(child, source, chunks);
+            } else {
+                chunks.push(Chunk {
+                    symbol: symbol_name(child, source),
+                    kind: kind.to_string(),
+                    start_line,
+                    end_line,
+                    text: truncate_lines(&source[child.byte_range()], MAX_CHUNK_LINES),
+                });
+            }
+        } else if TRANSPARENT_KINDS.contains(&kind) {
+            collect_chunks(child, source, chunks);
+        }
+    }
+}
+
+/// The `name` field of a node, falling back to the type of an impl block or
+/// the first line of the node
+fn symbol_name(node: Node, source: &str) -> String {
+    let named = node
+        .child_by_field_name("name")
+        .or_else(|| node.child_by_field_name("type"))
+        .or_else(|| node.child_by_field_name("definition"))
+        .or_else(|| node.child_by_field_name("declarator"));
+    match named {
+        Some(name) if name.kind() == node.kind() || name.child_count() > 0 => {
+            symbol_name(name, source)
+        }
+        Some(name) => source[name.byte_range()].to_string(),
+        None => source[node.byte_range()]
+            .lines()
+            .next()
+            .unwrap_or_default()
+            .trim()
+            .chars()
+            .take(80)
+            .collect(),
+    }
+}
+
+fn window_chunks(path: &Path, source: &str) -> Vec<Chunk> {
+    let file_name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let lines: Vec<&str> = source.lines().collect();
+    lines
+        .chunks(WINDOW_LINES)
+        .enumerate()
+        .filter(|(_, window)| window.iter().any(|line| !line.trim().is_empty()))
+        .map(|(i, window)| Chunk {
+            symbol: file_name.clone(),
+            kind: "window".to_string(),
+            start_line: i * WINDOW_LINES + 1,
+            end_line: i * WINDOW_LINES + window.len(),
+            text: window.join("\n"),
+        })
+        .collect()
+}
+
+fn truncate_lines(text: &str, max_lines: usize) -> String {
+    text.lines().take(max_lines).collect::<Vec<_>>().join("\n")
+}
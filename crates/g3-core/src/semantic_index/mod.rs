@@ -0,0 +1,362 @@
+//! Local semantic code index for the `semantic_search` tool
+//!
+//! Source files are split into tree-sitter symbol chunks, embedded with a
+//! local embedding model and stored in `.g3/semantic_index.json` under the
+//! workspace. [`SemanticIndex::update`] re-embeds only files whose size or
+//! modification time changed since the last run and drops deleted files, so
+//! refreshing before every search is cheap.
+
+use crate::code_search::is_skipped_dir;
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::UNIX_EPOCH;
+use tracing::{debug, info};
+use walkdir::WalkDir;
+
+mod chunker;
+pub use chunker::{chunk_source, language_for_path, Chunk};
+
+/// Bump when the on-disk layout or chunking changes to force a rebuild
+const INDEX_VERSION: u32 = 1;
+
+/// Files larger than this are skipped (generated or vendored code)
+pub(crate) const MAX_FILE_BYTES: u64 = 512 * 1024;
+
+/// Texts sent to the embedding model per call
+const EMBED_BATCH_SIZE: usize = 32;
+
+/// Embedding inputs are cut to roughly 512 tokens
+const MAX_EMBED_CHARS: usize = 2000;
+
+/// Turns text into vectors; implemented by `g3_providers::EmbeddingModel`
+pub trait Embedder: Send + Sync {
+    /// Identifies the model; an index built with another model is discarded
+    fn model_name(&self) -> &str;
+
+    /// One vector per input text, in order
+    fn embed(&self, texts: &[String]) -> Result<Vec<Vec<f32>>>;
+}
+
+impl Embedder for g3_providers::EmbeddingModel {
+    fn model_name(&self) -> &str {
+        self.name()
+    }
+
+    fn embed(&self, texts: &[String]) -> Result<Vec<Vec<f32>>> {
+        g3_providers::EmbeddingModel::embed(self, texts)
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct IndexData {
+    version: u32,
+    model: String,
+    /// Keyed by path relative to the workspace, with `/` separators
+    files: BTreeMap<String, IndexedFile>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct IndexedFile {
+    /// Modification time in nanoseconds since the epoch
+    modified: u128,
+    len: u64,
+    chunks: Vec<IndexedChunk>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct IndexedChunk {
+    symbol: String,
+    kind: String,
+    start_line: usize,
+    end_line: usize,
+    /// Normalized to unit length, so dot product is cosine similarity
+    vector: Vec<f32>,
+}
+
+/// What an [`SemanticIndex::update`] changed
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct IndexUpdate {
+    pub added: usize,
+    pub updated: usize,
+    pub removed: usize,
+    pub chunks_embedded: usize,
+}
+
+impl IndexUpdate {
+    pub fn is_empty(&self) -> bool {
+        self.added == 0 && self.updated == 0 && self.removed == 0
+    }
+}
+
+/// A chunk that matched a query, best first
+#[derive(Debug, Clone, Serialize)]
+pub struct SemanticMatch {
+    pub file: String,
+    pub symbol: String,
+    pub kind: String,
+    pub start_line: usize,
+    pub end_line: usize,
+    pub score: f32,
+}
+
+pub struct SemanticIndex {
+    root: PathBuf,
+    embedder: Arc<dyn Embedder>,
+    data: IndexData,
+}
+
+impl SemanticIndex {
+    /// Where the index for `root` is stored
+    pub fn index_path(root: &Path) -> PathBuf {
+        root.join(".g3").join("semantic_index.json")
+    }
+
+    /// Load the stored index for `root`, or start an empty one if there is
+    /// none or it was built by a different model or index version
+    pub fn open(root: &Path, embedder: Arc<dyn Embedder>) -> Result<Self> {
+        let path = Self::index_path(root);
+        let stored = if path.exists() {
+            let json = fs::read_to_string(&path)
+                .with_context(|| format!("Cannot read {}", path.display()))?;
+            match serde_json::from_str::<IndexData>(&json) {
+                Ok(data)
+                    if data.version == INDEX_VERSION && data.model == embedder.model_name() =>
+                {
+                    Some(data)
+                }
+                Ok(_) => {
+                    info!("Rebuilding semantic index: model or index version changed");
+                    None
+                }
+                Err(e) => {
+                    info!("Rebuilding unreadable semantic index: {}", e);
+                    None
+                }
+            }
+        } else {
+            None
+        };
+
+        let data = stored.unwrap_or_else(|| IndexData {
+            version: INDEX_VERSION,
+            model: embedder.model_name().to_string(),
+            files: BTreeMap::new(),
+        });
+        Ok(Self {
+            root: root.to_path_buf(),
+            embedder,
+            data,
+        })
+    }
+
+    pub fn file_count(&self) -> usize {
+        self.data.files.len()
+    }
+
+    pub fn chunk_count(&self) -> usize {
+        self.data.files.values().map(|f| f.chunks.len()).sum()
+    }
+
+    /// Bring the index in line with the files under the root: embed new and
+    /// changed files, drop deleted ones. Nothing is written to disk.
+    pub fn update(&mut self) -> Result<IndexUpdate> {
+        let mut update = IndexUpdate::default();
+        let mut seen = Vec::new();
+
+        for entry in WalkDir::new(&self.root)
+            .into_iter()
+            .filter_entry(|e| e.depth() == 0 || !is_skipped_dir(e))
+            .filter_map(|e| e.ok())
+        {
+            let path = entry.path();
+            if !entry.file_type().is_file() || language_for_path(path).is_none() {
+                continue;
+            }
+            let Ok(metadata) = entry.metadata() else {
+                continue;
+            };
+            if metadata.len() > MAX_FILE_BYTES {
+                continue;
+            }
+            let Some(key) = self.relative_key(path) else {
+                continue;
+            };
+            let modified = metadata
+                .modified()
+                .ok()
+                .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+                .map(|d| d.as_nanos())
+                .unwrap_or_default();
+            seen.push(key.clone());
+
+            let existing = self.data.files.get(&key);
+            if existing.is_some_and(|f| f.modified == modified && f.len == metadata.len()) {
+                continue;
+            }
+            let is_new = existing.is_none();
+
+            let Ok(source) = fs::read_to_string(path) else {
+                continue;
+            };
+            let chunks = chunk_source(path, &source);
+            let vectors = self.embed_chunks(&key, &chunks)?;
+            update.chunks_embedded += chunks.len();
+            if is_new {
+                update.added += 1;
+            } else {
+                update.updated += 1;
+            }
+            debug!("Indexed {} ({} chunks)", key, chunks.len());
+
+            self.data.files.insert(
+                key,
+                IndexedFile {
+                    modified,
+                    len: metadata.len(),
+                    chunks: chunks
+                        .into_iter()
+                        .zip(vectors)
+                        .map(|(chunk, vector)| IndexedChunk {
+                            symbol: chunk.symbol,
+                            kind: chunk.kind,
+                            start_line: chunk.start_line,
+                            end_line: chunk.end_line,
+                            vector,
+                        })
+                        .collect(),
+                },
+            );
+        }
+
+        seen.sort();
+        let before = self.data.files.len();
+        self.data
+            .files
+            .retain(|key, _| seen.binary_search(key).is_ok());
+        update.removed = before - self.data.files.len();
+
+        Ok(update)
+    }
+
+    /// Write the index to `.g3/semantic_index.json`
+    pub fn save(&self) -> Result<()> {
+        let path = Self::index_path(&self.root);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let json = serde_json::to_string(&self.data)?;
+        fs::write(&path, json).with_context(|| format!("Cannot write {}", path.display()))
+    }
+
+    /// The `limit` chunks closest to `query`, optionally restricted to files
+    /// under `path_prefix` (relative to the root)
+    pub fn search(
+        &self,
+        query: &str,
+        limit: usize,
+        path_prefix: Option<&str>,
+    ) -> Result<Vec<SemanticMatch>> {
+        let query_vector = self
+            .embedder
+            .embed(&[query.to_string()])?
+            .into_iter()
+            .next()
+            .map(normalize)
+            .ok_or_else(|| anyhow!("Embedding model returned no vector for the query"))?;
+        let prefix = path_prefix
+            .map(|p| p.trim_start_matches("./").trim_end_matches('/'))
+            .filter(|p| !p.is_empty() && *p != ".");
+
+        let mut matches: Vec<SemanticMatch> = self
+            .data
+            .files
+            .iter()
+            .filter(|(file, _)| {
+                prefix.is_none_or(|p| {
+                    file.as_str() == p
+                        || file
+                            .strip_prefix(p)
+                            .is_some_and(|rest| rest.starts_with('/'))
+                })
+            })
+            .flat_map(|(file, indexed)| {
+                indexed.chunks.iter().map(|chunk| SemanticMatch {
+                    file: file.clone(),
+                    symbol: chunk.symbol.clone(),
+                    kind: chunk.kind.clone(),
+                    start_line: chunk.start_line,
+                    end_line: chunk.end_line,
+                    score: dot(&query_vector, &chunk.vector),
+                })
+            })
+            .collect();
+
+        matches.sort_by(|a, b| b.score.total_cmp(&a.score));
+        matches.truncate(limit);
+        Ok(matches)
+    }
+
+    fn relative_key(&self, path: &Path) -> Option<String> {
+        let relative = path.strip_prefix(&self.root).ok()?;
+        let parts: Vec<_> = relative
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy())
+            .collect();
+        Some(parts.join("/"))
+    }
+
+    fn embed_chunks(&self, file: &str, chunks: &[Chunk]) -> Result<Vec<Vec<f32>>> {
+        let texts: Vec<String> = chunks
+            .iter()
+            .map(|chunk| {
+                // The path and symbol name carry a lot of the meaning
+                let text = format!("{} {}\n{}", file, chunk.symbol, chunk.text);
+                match text.char_indices().nth(MAX_EMBED_CHARS) {
+                    Some((end, _)) => text[..end].to_string(),
+                    None => text,
+                }
+            })
+            .collect();
+
+        let mut vectors = Vec::with_capacity(texts.len());
+        for batch in texts.chunks(EMBED_BATCH_SIZE) {
+            let embedded = self.embedder.embed(batch)?;
+            if embedded.len() != batch.len() {
+                return Err(anyhow!(
+                    "Embedding model returned {} vectors for {} inputs",
+                    embedded.len(),
+                    batch.len()
+                ));
+            }
+            vectors.extend(embedded.into_iter().map(normalize));
+        }
+        Ok(vectors)
+    }
+}
+
+fn normalize(mut vector: Vec<f32>) -> Vec<f32> {
+    let norm = vector.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm > 0.0 {
+        vector.iter_mut().for_each(|x| *x /= norm);
+    }
+    vector
+}
+
+fn dot(a: &[f32], b: &[f32]) -> f32 {
+    a.iter().zip(b).map(|(x, y)| x * y).sum()
+}
+
+/// Snippet of a match's lines from the current file contents
+pub fn match_snippet(root: &Path, m: &SemanticMatch, max_lines: usize) -> Option<String> {
+    let source = fs::read_to_string(root.join(&m.file)).ok()?;
+    let lines: Vec<&str> = source
+        .lines()
+        .skip(m.start_line.saturating_sub(1))
+        .take((m.end_line + 1 - m.start_line).min(max_lines))
+        .collect();
+    Some(lines.join("\n"))
+}
@@ -0,0 +1,171 @@
+//! Session logs
+//!
+//! `Agent::save_context_window` writes each session to
+//! `logs/g3_session_<id>.json`. This module reads those files back so a
+//! session can be resumed with `g3 --resume <id>` or `g3 --continue`, and
+//! holds the types used by `/rewind` and `/fork`, the record of
+//! provider failovers and the session's spend.
+
+use crate::cost::CostTracker;
+use anyhow::{anyhow, Context, Result};
+use g3_providers::Message;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+/// A session as written by `Agent::save_context_window`
+#[derive(Debug, Clone)]
+pub struct SessionLog {
+    pub session_id: String,
+    pub used_tokens: u32,
+    pub total_tokens: u32,
+    pub cumulative_tokens: u32,
+    /// Conversation history with the message ids from the original session
+    pub conversation_history: Vec<Message>,
+    /// Contents of the TODO list when the session was saved
+    pub todo: Option<String>,
+    /// Failovers to fallback providers, oldest first
+    pub provider_switches: Vec<ProviderSwitch>,
+    /// Spend recorded in the session
+    pub cost: CostTracker,
+}
+
+/// A mid-session switch to a fallback provider
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProviderSwitch {
+    pub from: String,
+    pub to: String,
+    /// The last error reported by `from`
+    pub reason: String,
+    /// Seconds since the Unix epoch
+    pub timestamp: u64,
+    /// Length of the conversation when the switch happened
+    pub message_count: usize,
+}
+
+#[derive(Deserialize)]
+struct RawSessionLog {
+    session_id: Option<String>,
+    context_window: RawContextWindow,
+    #[serde(default)]
+    todo: Option<String>,
+    #[serde(default)]
+    provider_switches: Vec<ProviderSwitch>,
+    #[serde(default)]
+    cost: CostTracker,
+}
+
+#[derive(Deserialize)]
+struct RawContextWindow {
+    #[serde(default)]
+    used_tokens: u32,
+    #[serde(default)]
+    total_tokens: u32,
+    #[serde(default)]
+    cumulative_tokens: Option<u32>,
+    #[serde(default)]
+    conversation_history: Vec<serde_json::Value>,
+    /// `Message::id` is not serialized with the message, so ids are stored alongside
+    #[serde(default)]
+    message_ids: Vec<String>,
+}
+
+/// Where `Agent::rewind` cuts the conversation
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RewindTarget {
+    /// Drop the last `n` user turns
+    Turns(usize),
+    /// Drop this message (a `HHMMSS-XXX` id) and everything after it
+    Message(String),
+}
+
+impl RewindTarget {
+    /// A number is a turn count, anything else a message id
+    pub fn parse(arg: &str) -> Self {
+        match arg.parse::<usize>() {
+            Ok(turns) => RewindTarget::Turns(turns),
+            Err(_) => RewindTarget::Message(arg.to_string()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct RewindSummary {
+    pub removed_messages: usize,
+    /// Files restored from checkpoints (empty unless reverting was requested)
+    pub reverted_files: Vec<PathBuf>,
+}
+
+/// Path of the log file for `session_id`
+pub fn session_log_path(session_id: &str) -> PathBuf {
+    crate::logs_dir().join(format!("g3_session_{}.json", session_id))
+}
+
+impl SessionLog {
+    /// Load the log for `session_id` from the logs directory
+    pub fn load(session_id: &str) -> Result<Self> {
+        let path = session_log_path(session_id);
+        let content = std::fs::read_to_string(&path)
+            .with_context(|| format!("No session log found at {}", path.display()))?;
+        Self::parse(&content).with_context(|| format!("Invalid session log {}", path.display()))
+    }
+
+    /// Id of the most recently written session in the logs directory
+    pub fn latest_session_id() -> Result<String> {
+        let logs_dir = crate::logs_dir();
+        let entries = std::fs::read_dir(&logs_dir)
+            .with_context(|| format!("Cannot read logs directory {}", logs_dir.display()))?;
+
+        entries
+            .filter_map(|entry| entry.ok())
+            .filter_map(|entry| {
+                let name = entry.file_name().to_string_lossy().into_owned();
+                let id = name
+                    .strip_prefix("g3_session_")?
+                    .strip_suffix(".json")?
+                    .to_string();
+                let modified = entry.metadata().ok()?.modified().ok()?;
+                Some((modified, id))
+            })
+            .max()
+            .map(|(_, id)| id)
+            .ok_or_else(|| anyhow!("No sessions found in {}", logs_dir.display()))
+    }
+
+    pub fn parse(content: &str) -> Result<Self> {
+        let raw: RawSessionLog = serde_json::from_str(content)?;
+        let session_id = raw
+            .session_id
+            .ok_or_else(|| anyhow!("Session log has no session_id"))?;
+
+        let window = raw.context_window;
+        // Entries appended by `log_error_to_session` are diagnostics, not conversation
+        let entries: Vec<serde_json::Value> = window
+            .conversation_history
+            .into_iter()
+            .filter(|value| value.get("error_type").is_none())
+            .collect();
+        let ids_match = window.message_ids.len() == entries.len();
+
+        let mut conversation_history = Vec::with_capacity(entries.len());
+        for (i, value) in entries.into_iter().enumerate() {
+            let mut message: Message = serde_json::from_value(value)?;
+            message.id = if ids_match {
+                window.message_ids[i].clone()
+            } else {
+                Message::generate_id()
+            };
+            conversation_history.push(message);
+        }
+
+        Ok(Self {
+            session_id,
+            used_tokens: window.used_tokens,
+            total_tokens: window.total_tokens,
+            cumulative_tokens: window.cumulative_tokens.unwrap_or(window.used_tokens),
+            conversation_history,
+            todo: raw.todo,
+            provider_switches: raw.provider_switches,
+            cost: raw.cost,
+        })
+    }
+}
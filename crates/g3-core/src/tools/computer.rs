@@ -0,0 +1,566 @@
+//! Screenshots, OCR and vision-guided clicking
+
+use super::{ToolContext, ToolHandler, ToolPermissions};
+use crate::ToolCall;
+use anyhow::Result;
+use async_trait::async_trait;
+use g3_config::Config;
+use g3_providers::Tool;
+use serde_json::json;
+use tracing::debug;
+
+pub struct TakeScreenshotTool;
+
+#[async_trait]
+impl ToolHandler for TakeScreenshotTool {
+    fn name(&self) -> &str {
+        "take_screenshot"
+    }
+
+    fn definition(&self) -> Tool {
+        Tool {
+            name: "take_screenshot".to_string(),
+            description: "Capture a screenshot of a specific application window. You MUST specify the window_id parameter with the application name (e.g., 'Safari', 'Terminal', 'Google Chrome'). The tool will automatically use the native screencapture command with the application's window ID for a clean capture. Use list_windows first to identify available windows.".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "path": {
+                        "type": "string",
+                        "description": "Filename for the screenshot (e.g., 'safari.png'). If a relative path is provided, the screenshot will be saved to ~/tmp or $TMPDIR. Use an absolute path to save elsewhere."
+                    },
+                    "window_id": {
+                        "type": "string",
+                        "description": "REQUIRED: Application name to capture (e.g., 'Safari', 'Terminal', 'Google Chrome'). The tool will capture the frontmost window of that application using its native window ID."
+                    },
+                    "region": {
+                        "type": "object",
+                        "properties": {
+                            "x": {"type": "integer"},
+                            "y": {"type": "integer"},
+                            "width": {"type": "integer"},
+                            "height": {"type": "integer"}
+                        }
+                    }
+                },
+                "required": ["path", "window_id"]
+            }),
+        }
+    }
+
+    fn permissions(&self) -> ToolPermissions {
+        ToolPermissions {
+            controls_desktop: true,
+            ..Default::default()
+        }
+    }
+
+    async fn execute(&self, tool_call: &ToolCall, ctx: &ToolContext<'_>) -> Result<String> {
+        if let Some(controller) = &ctx.computer_controller {
+            let path = tool_call
+                .args
+                .get("path")
+                .and_then(|v| v.as_str())
+                .ok_or_else(|| anyhow::anyhow!("Missing path argument"))?;
+
+            // Extract window_id (app name) - REQUIRED
+            let window_id = tool_call.args.get("window_id").and_then(|v| v.as_str())
+                .ok_or_else(|| anyhow::anyhow!("Missing window_id argument. You must specify which window to capture (e.g., 'Safari', 'Terminal', 'Google Chrome')."))?;
+
+            // Extract region if provided
+            let region = tool_call
+                .args
+                .get("region")
+                .and_then(|v| v.as_object())
+                .map(|region_obj| g3_computer_control::types::Rect {
+                    x: region_obj.get("x").and_then(|v| v.as_i64()).unwrap_or(0) as i32,
+                    y: region_obj.get("y").and_then(|v| v.as_i64()).unwrap_or(0) as i32,
+                    width: region_obj
+                        .get("width")
+                        .and_then(|v| v.as_i64())
+                        .unwrap_or(0) as i32,
+                    height: region_obj
+                        .get("height")
+                        .and_then(|v| v.as_i64())
+                        .unwrap_or(0) as i32,
+                });
+
+            match controller
+                .take_screenshot(path, region, Some(window_id))
+                .await
+            {
+                Ok(_) => {
+                    // Get the actual path where the screenshot was saved
+                    let actual_path = if path.starts_with('/') {
+                        path.to_string()
+                    } else {
+                        let temp_dir = std::env::var("TMPDIR")
+                            .or_else(|_| {
+                                std::env::var("HOME").map(|h| format!("{}/tmp", h))
+                            })
+                            .unwrap_or_else(|_| "/tmp".to_string());
+                        format!("{}/{}", temp_dir.trim_end_matches('/'), path)
+                    };
+
+                    Ok(format!(
+                        "✅ Screenshot of {} saved to: {}",
+                        window_id, actual_path
+                    ))
+                }
+                Err(e) => Ok(format!("❌ Failed to take screenshot: {}", e)),
+            }
+        } else {
+            Ok("❌ Computer control not enabled. Set computer_control.enabled = true in config.".to_string())
+        }
+    }
+}
+
+pub struct ExtractTextTool;
+
+#[async_trait]
+impl ToolHandler for ExtractTextTool {
+    fn name(&self) -> &str {
+        "extract_text"
+    }
+
+    fn definition(&self) -> Tool {
+        Tool {
+            name: "extract_text".to_string(),
+            description: "Extract text from an image file using OCR. For extracting text from a specific window, use vision_find_text instead which automatically handles window capture.".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "path": {
+                        "type": "string",
+                        "description": "Path to image file (optional if region is provided)"
+                    },
+                }
+            }),
+        }
+    }
+
+    fn permissions(&self) -> ToolPermissions {
+        ToolPermissions {
+            reads_files: true,
+            ..Default::default()
+        }
+    }
+
+    async fn execute(&self, tool_call: &ToolCall, ctx: &ToolContext<'_>) -> Result<String> {
+        if let Some(controller) = &ctx.computer_controller {
+            let path = tool_call
+                .args
+                .get("path")
+                .and_then(|v| v.as_str())
+                .ok_or_else(|| anyhow::anyhow!("Missing path argument"))?;
+
+            // Extract text from image file only
+            match controller.extract_text_from_image(path).await {
+                Ok(text) => Ok(format!("✅ Extracted text:\n{}", text)),
+                Err(e) => Ok(format!("❌ Failed to extract text: {}", e)),
+            }
+        } else {
+            Ok("❌ Computer control not enabled. Set computer_control.enabled = true in config.".to_string())
+        }
+    }
+}
+
+pub struct ExtractTextWithBoxesTool;
+
+#[async_trait]
+impl ToolHandler for ExtractTextWithBoxesTool {
+    fn name(&self) -> &str {
+        "extract_text_with_boxes"
+    }
+
+    fn definition(&self) -> Tool {
+        Tool {
+            name: "extract_text_with_boxes".to_string(),
+            description: "Extract all text from an image file with bounding box coordinates for each text element. Returns JSON array with text, position (x, y), size (width, height), and confidence for each detected text. Uses Apple Vision Framework for precise sub-pixel accuracy.".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "path": {
+                        "type": "string",
+                        "description": "Path to image file to extract text from"
+                    },
+                    "app_name": {
+                        "type": "string",
+                        "description": "Optional: Name of application to screenshot first (e.g., 'Safari', 'Things3'). If provided, takes screenshot of app before extracting text."
+                    }
+                },
+                "required": ["path"]
+            }),
+        }
+    }
+
+    fn permissions(&self) -> ToolPermissions {
+        ToolPermissions {
+            controls_desktop: true,
+            ..Default::default()
+        }
+    }
+
+    fn is_available(&self, config: &Config) -> bool {
+        config.macax.enabled
+    }
+
+    async fn execute(&self, tool_call: &ToolCall, ctx: &ToolContext<'_>) -> Result<String> {
+        debug!("Processing extract_text_with_boxes tool call");
+
+        if !ctx.config.macax.enabled {
+            return Ok(
+                "❌ extract_text_with_boxes requires --macax flag to be enabled"
+                    .to_string(),
+            );
+        }
+
+        if let Some(controller) = &ctx.computer_controller {
+            let path = tool_call
+                .args
+                .get("path")
+                .and_then(|v| v.as_str())
+                .ok_or_else(|| anyhow::anyhow!("Missing path parameter"))?;
+
+            // Optional: take screenshot of app first
+            let final_path = if let Some(app_name) =
+                tool_call.args.get("app_name").and_then(|v| v.as_str())
+            {
+                let temp_path =
+                    format!("/tmp/g3_extract_boxes_{}.png", uuid::Uuid::new_v4());
+                match controller
+                    .take_screenshot(&temp_path, None, Some(app_name))
+                    .await
+                {
+                    Ok(_) => temp_path,
+                    Err(e) => return Ok(format!("❌ Failed to take screenshot: {}", e)),
+                }
+            } else {
+                path.to_string()
+            };
+
+            // Extract text with locations
+            match controller.extract_text_with_locations(&final_path).await {
+                Ok(locations) => {
+                    // Clean up temp file if we created one
+                    if final_path != path {
+                        let _ = std::fs::remove_file(&final_path);
+                    }
+
+                    // Return as JSON
+                    match serde_json::to_string_pretty(&locations) {
+                        Ok(json) => Ok(format!(
+                            "✅ Extracted {} text elements:\n{}",
+                            locations.len(),
+                            json
+                        )),
+                        Err(e) => Ok(format!("❌ Failed to serialize results: {}", e)),
+                    }
+                }
+                Err(e) => Ok(format!("❌ Failed to extract text: {}", e)),
+            }
+        } else {
+            Ok("❌ Computer control not enabled. Set computer_control.enabled = true in config.".to_string())
+        }
+    }
+}
+
+pub struct VisionFindTextTool;
+
+#[async_trait]
+impl ToolHandler for VisionFindTextTool {
+    fn name(&self) -> &str {
+        "vision_find_text"
+    }
+
+    fn definition(&self) -> Tool {
+        Tool {
+            name: "vision_find_text".to_string(),
+            description: "Find text in a specific application window and return its location with bounding box coordinates (x, y, width, height) and confidence score. Useful for locating UI elements. Uses Apple Vision Framework for precise sub-pixel accuracy.".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "app_name": {
+                        "type": "string",
+                        "description": "Name of the application to search in (e.g., 'Things3', 'Safari', 'TextEdit')"
+                    },
+                    "text": {
+                        "type": "string",
+                        "description": "The text to search for on screen"
+                    }
+                },
+                "required": ["app_name", "text"]
+            }),
+        }
+    }
+
+    fn permissions(&self) -> ToolPermissions {
+        ToolPermissions {
+            controls_desktop: true,
+            ..Default::default()
+        }
+    }
+
+    fn is_available(&self, config: &Config) -> bool {
+        config.computer_control.enabled
+    }
+
+    async fn execute(&self, tool_call: &ToolCall, ctx: &ToolContext<'_>) -> Result<String> {
+        debug!("Processing vision_find_text tool call");
+
+        if let Some(controller) = &ctx.computer_controller {
+            let app_name = tool_call
+                .args
+                .get("app_name")
+                .and_then(|v| v.as_str())
+                .ok_or_else(|| anyhow::anyhow!("Missing app_name parameter"))?;
+
+            let text = tool_call
+                .args
+                .get("text")
+                .and_then(|v| v.as_str())
+                .ok_or_else(|| anyhow::anyhow!("Missing text parameter"))?;
+
+            match controller.find_text_in_app(app_name, text).await {
+                Ok(Some(location)) => {
+                    Ok(format!(
+                        "✅ Found '{}' in {} at position ({}, {}) with size {}x{} (confidence: {:.0}%)",
+                        location.text, app_name, location.x, location.y, location.width, location.height,
+                        location.confidence * 100.0
+                    ))
+                }
+                Ok(None) => Ok(format!("❌ Could not find '{}' in {}", text, app_name)),
+                Err(e) => Ok(format!("❌ Error finding text: {}", e)),
+            }
+        } else {
+            Ok("❌ Computer control not enabled. Set computer_control.enabled = true in config.".to_string())
+        }
+    }
+}
+
+pub struct VisionClickTextTool;
+
+#[async_trait]
+impl ToolHandler for VisionClickTextTool {
+    fn name(&self) -> &str {
+        "vision_click_text"
+    }
+
+    fn definition(&self) -> Tool {
+        Tool {
+            name: "vision_click_text".to_string(),
+            description: "Find text in a specific application window and click on it (useful for clicking buttons, links, menu items)".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "app_name": {
+                        "type": "string",
+                        "description": "Name of the application (e.g., 'Things3', 'Safari', 'TextEdit')"
+                    },
+                    "text": {
+                        "type": "string",
+                        "description": "The text to click on (e.g., 'Submit', 'OK', 'Cancel', '+')"
+                    }
+                },
+                "required": ["app_name", "text"]
+            }),
+        }
+    }
+
+    fn permissions(&self) -> ToolPermissions {
+        ToolPermissions {
+            controls_desktop: true,
+            ..Default::default()
+        }
+    }
+
+    fn is_available(&self, config: &Config) -> bool {
+        config.computer_control.enabled
+    }
+
+    async fn execute(&self, tool_call: &ToolCall, ctx: &ToolContext<'_>) -> Result<String> {
+        debug!("Processing vision_click_text tool call");
+
+        if let Some(controller) = &ctx.computer_controller {
+            let app_name = tool_call
+                .args
+                .get("app_name")
+                .and_then(|v| v.as_str())
+                .ok_or_else(|| anyhow::anyhow!("Missing app_name parameter"))?;
+
+            let text = tool_call
+                .args
+                .get("text")
+                .and_then(|v| v.as_str())
+                .ok_or_else(|| anyhow::anyhow!("Missing text parameter"))?;
+
+            match controller.find_text_in_app(app_name, text).await {
+                Ok(Some(location)) => {
+                    // Click on center of text
+                    // IMPORTANT: location coordinates are in NSScreen space (Y=0 at BOTTOM, increases UPWARD)
+                    // location.x is the LEFT edge of the bounding box
+                    // location.y is the TOP edge of the bounding box (highest Y value in NSScreen space)
+                    // location.width and location.height are already scaled to screen space
+                    // To get center: we need to add half the SCALED width and subtract half the SCALED height
+
+                    if location.width == 0 || location.height == 0 {
+                        return Ok(format!(
+                            "❌ Invalid bounding box dimensions: width={}, height={}",
+                            location.width, location.height
+                        ));
+                    }
+
+                    debug!("[vision_click_text] Location from find_text_in_app: x={}, y={}, width={}, height={}, text='{}'",
+                        location.x, location.y, location.width, location.height, location.text);
+
+                    // Calculate center using the SCALED dimensions
+                    // X: Use right edge instead of center (Vision OCR bounding box seems offset)
+                    // This gives us: left edge + full width = right edge
+                    // Y: top edge - half of scaled height (subtract because Y increases upward)
+                    let click_x = location.x + location.width; // Right edge
+                    let half_height = location.height / 2;
+                    let click_y = location.y - half_height;
+
+                    debug!("[vision_click_text] Click position calculation: x={} + {} = {} (right edge), y={} - {} = {}",
+                        location.x, location.width, click_x, location.y, half_height, click_y);
+                    debug!("[vision_click_text] This means: left_edge={}, center={}, right_edge={}",
+                        location.x, click_x, location.x + location.width);
+
+                    match controller.click_at(click_x, click_y, Some(app_name)) {
+                        Ok(_) => Ok(format!(
+                            "✅ Clicked on '{}' in {} at ({}, {})",
+                            text, app_name, click_x, click_y
+                        )),
+                        Err(e) => Ok(format!("❌ Failed to click: {}", e)),
+                    }
+                }
+                Ok(None) => Ok(format!("❌ Could not find '{}' in {}", text, app_name)),
+                Err(e) => Ok(format!("❌ Error finding text: {}", e)),
+            }
+        } else {
+            Ok("❌ Computer control not enabled. Set computer_control.enabled = true in config.".to_string())
+        }
+    }
+}
+
+pub struct VisionClickNearTextTool;
+
+#[async_trait]
+impl ToolHandler for VisionClickNearTextTool {
+    fn name(&self) -> &str {
+        "vision_click_near_text"
+    }
+
+    fn definition(&self) -> Tool {
+        Tool {
+            name: "vision_click_near_text".to_string(),
+            description: "Find text in a specific application window and click near it (useful for clicking text fields next to labels)".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "app_name": {
+                        "type": "string",
+                        "description": "Name of the application (e.g., 'Things3', 'Safari', 'TextEdit')"
+                    },
+                    "text": {
+                        "type": "string",
+                        "description": "The label text to find (e.g., 'Name:', 'Email:', 'Task:')"
+                    },
+                    "direction": {
+                        "type": "string",
+                        "enum": ["right", "below", "left", "above"],
+                        "description": "Direction to click relative to the text (default: right)"
+                    },
+                    "distance": {
+                        "type": "integer",
+                        "description": "Distance in pixels from the text (default: 50)"
+                    }
+                },
+                "required": ["app_name", "text"]
+            }),
+        }
+    }
+
+    fn permissions(&self) -> ToolPermissions {
+        ToolPermissions {
+            controls_desktop: true,
+            ..Default::default()
+        }
+    }
+
+    fn is_available(&self, config: &Config) -> bool {
+        config.computer_control.enabled
+    }
+
+    async fn execute(&self, tool_call: &ToolCall, ctx: &ToolContext<'_>) -> Result<String> {
+        debug!("Processing vision_click_near_text tool call");
+
+        if let Some(controller) = &ctx.computer_controller {
+            let app_name = tool_call
+                .args
+                .get("app_name")
+                .and_then(|v| v.as_str())
+                .ok_or_else(|| anyhow::anyhow!("Missing app_name parameter"))?;
+
+            let text = tool_call
+                .args
+                .get("text")
+                .and_then(|v| v.as_str())
+                .ok_or_else(|| anyhow::anyhow!("Missing text parameter"))?;
+
+            let direction = tool_call
+                .args
+                .get("direction")
+                .and_then(|v| v.as_str())
+                .unwrap_or("right");
+
+            let distance = tool_call
+                .args
+                .get("distance")
+                .and_then(|v| v.as_i64())
+                .unwrap_or(50) as i32;
+
+            match controller.find_text_in_app(app_name, text).await {
+                Ok(Some(location)) => {
+                    // Calculate click position based on direction
+                    // location.x is LEFT edge, location.y is TOP edge (in NSScreen space)
+                    let (click_x, click_y) = match direction {
+                        "right" => (
+                            location.x + location.width + distance,
+                            location.y - (location.height / 2),
+                        ),
+                        "below" => (
+                            location.x + (location.width / 2),
+                            location.y - location.height - distance,
+                        ),
+                        "left" => {
+                            (location.x - distance, location.y - (location.height / 2))
+                        }
+                        "above" => {
+                            (location.x + (location.width / 2), location.y + distance)
+                        }
+                        _ => (
+                            location.x + location.width + distance,
+                            location.y - (location.height / 2),
+                        ),
+                    };
+                    debug!(
+                        "[vision_click_near_text] Clicking {} of text at ({}, {})",
+                        direction, click_x, click_y
+                    );
+
+                    match controller.click_at(click_x, click_y, Some(app_name)) {
+                        Ok(_) => Ok(format!(
+                            "✅ Clicked {} of '{}' in {} at ({}, {})",
+                            direction, text, app_name, click_x, click_y
+                        )),
+                        Err(e) => Ok(format!("❌ Failed to click: {}", e)),
+                    }
+                }
+                Ok(None) => Ok(format!("❌ Could not find '{}' in {}", text, app_name)),
+                Err(e) => Ok(format!("❌ Error finding text: {}", e)),
+            }
+        } else {
+            Ok("❌ Computer control not enabled. Set computer_control.enabled = true in config.".to_string())
+        }
+    }
+}
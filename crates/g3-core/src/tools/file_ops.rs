@@ -0,0 +1,603 @@
+//! File reading, writing and unified-diff editing
+
+use super::{ToolContext, ToolHandler, ToolPermissions};
+use crate::project::workspace_path;
+use crate::{apply_unified_diff_to_string, ToolCall};
+use anyhow::Result;
+use async_trait::async_trait;
+use g3_providers::Tool;
+use serde_json::json;
+use std::path::PathBuf;
+use tracing::debug;
+
+/// The target path of a file tool call, accepting the same argument
+/// spellings as `write_file`
+fn file_path_arg(args: &serde_json::Value) -> Option<&str> {
+    if let Some(obj) = args.as_object() {
+        ["file_path", "path", "filename", "file"]
+            .iter()
+            .find_map(|key| obj.get(*key).and_then(|v| v.as_str()))
+    } else {
+        args.as_array()
+            .and_then(|arr| arr.first())
+            .and_then(|v| v.as_str())
+    }
+}
+
+pub struct ReadFileTool;
+
+#[async_trait]
+impl ToolHandler for ReadFileTool {
+    fn name(&self) -> &str {
+        "read_file"
+    }
+
+    fn definition(&self) -> Tool {
+        Tool {
+            name: "read_file".to_string(),
+            description: "Read the contents of a file. For image files (png, jpg, jpeg, gif, bmp, tiff, webp), automatically extracts text using OCR. For text files, optionally read a specific character range.".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "file_path": {
+                        "type": "string",
+                        "description": "The path to the file to read"
+                    },
+                    "start": {
+                        "type": "integer",
+                        "description": "Starting character position (0-indexed, inclusive). If omitted, reads from beginning."
+                    },
+                    "end": {
+                        "type": "integer",
+                        "description": "Ending character position (0-indexed, EXCLUSIVE). If omitted, reads to end of file."
+                    }
+                },
+                "required": ["file_path"]
+            }),
+        }
+    }
+
+    fn permissions(&self) -> ToolPermissions {
+        ToolPermissions {
+            reads_files: true,
+            ..Default::default()
+        }
+    }
+
+    fn permission_subject(&self, tool_call: &ToolCall) -> Option<String> {
+        file_path_arg(&tool_call.args).map(crate::permissions::resolve_path)
+    }
+
+    async fn execute(&self, tool_call: &ToolCall, ctx: &ToolContext<'_>) -> Result<String> {
+        debug!("Processing read_file tool call");
+        if let Some(file_path) = tool_call.args.get("file_path") {
+            if let Some(path_str) = file_path.as_str() {
+                // Expand tilde (~) to home directory
+                let expanded_path = workspace_path(&shellexpand::tilde(path_str));
+                let path_str = expanded_path.as_str();
+
+                // Check if this is an image file
+                let is_image = path_str.to_lowercase().ends_with(".png")
+                    || path_str.to_lowercase().ends_with(".jpg")
+                    || path_str.to_lowercase().ends_with(".jpeg")
+                    || path_str.to_lowercase().ends_with(".gif")
+                    || path_str.to_lowercase().ends_with(".bmp")
+                    || path_str.to_lowercase().ends_with(".tiff")
+                    || path_str.to_lowercase().ends_with(".tif")
+                    || path_str.to_lowercase().ends_with(".webp");
+
+                // If it's an image file, use OCR via extract_text
+                if is_image {
+                    if let Some(controller) = &ctx.computer_controller {
+                        match controller.extract_text_from_image(path_str).await {
+                            Ok(text) => {
+                                return Ok(format!(
+                                    "📄 Image file (OCR extracted):\n{}",
+                                    text
+                                ));
+                            }
+                            Err(e) => {
+                                return Ok(format!(
+                                    "❌ Failed to extract text from image '{}': {}",
+                                    path_str, e
+                                ))
+                            }
+                        }
+                    } else {
+                        return Ok("❌ Computer control not enabled. Cannot perform OCR on image files. Set computer_control.enabled = true in config.".to_string());
+                    }
+                }
+
+                // Extract optional start and end positions
+                let start_char = tool_call
+                    .args
+                    .get("start")
+                    .and_then(|v| v.as_u64())
+                    .map(|n| n as usize);
+                let end_char = tool_call
+                    .args
+                    .get("end")
+                    .and_then(|v| v.as_u64())
+                    .map(|n| n as usize);
+
+                debug!(
+                    "Reading file: {}, start={:?}, end={:?}",
+                    path_str, start_char, end_char
+                );
+
+                match std::fs::read_to_string(path_str) {
+                    Ok(content) => {
+                        // Validate and apply range if specified
+                        let start = start_char.unwrap_or(0);
+                        let end = end_char.unwrap_or(content.len());
+
+                        // Validation
+                        if start > content.len() {
+                            return Ok(format!(
+                                "❌ Start position {} exceeds file length {}",
+                                start,
+                                content.len()
+                            ));
+                        }
+                        if end > content.len() {
+                            return Ok(format!(
+                                "❌ End position {} exceeds file length {}",
+                                end,
+                                content.len()
+                            ));
+                        }
+                        if start > end {
+                            return Ok(format!(
+                                "❌ Start position {} is greater than end position {}",
+                                start, end
+                            ));
+                        }
+
+                        // Extract the requested portion, ensuring we're at char boundaries
+                        // Find the nearest valid char boundaries
+                        let start_boundary = if start == 0 {
+                            0
+                        } else {
+                            content
+                                .char_indices()
+                                .find(|(i, _)| *i >= start)
+                                .map(|(i, _)| i)
+                                .unwrap_or(start)
+                        };
+                        let end_boundary = content
+                            .char_indices()
+                            .find(|(i, _)| *i >= end)
+                            .map(|(i, _)| i)
+                            .unwrap_or(content.len());
+
+                        let partial_content = &content[start_boundary..end_boundary];
+                        let line_count = partial_content.lines().count();
+                        let total_lines = content.lines().count();
+
+                        // Format output with range info if partial
+                        if start_char.is_some() || end_char.is_some() {
+                            Ok(format!(
+                                "📄 File content (chars {}-{}, {} lines of {} total):\n{}",
+                                start_boundary,
+                                end_boundary,
+                                line_count,
+                                total_lines,
+                                partial_content
+                            ))
+                        } else {
+                            Ok(format!(
+                                "📄 File content ({} lines):\n{}",
+                                line_count, content
+                            ))
+                        }
+                    }
+                    Err(e) => Ok(format!("❌ Failed to read file '{}': {}", path_str, e)),
+                }
+            } else {
+                Ok("❌ Invalid file_path argument".to_string())
+            }
+        } else {
+            Ok("❌ Missing file_path argument".to_string())
+        }
+    }
+}
+
+pub struct WriteFileTool;
+
+#[async_trait]
+impl ToolHandler for WriteFileTool {
+    fn name(&self) -> &str {
+        "write_file"
+    }
+
+    fn definition(&self) -> Tool {
+        Tool {
+            name: "write_file".to_string(),
+            description: "Write content to a file (creates or overwrites). You MUST provide all arguments".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "file_path": {
+                        "type": "string",
+                        "description": "The path to the file to write"
+                    },
+                    "content": {
+                        "type": "string",
+                        "description": "The content to write to the file"
+                    }
+                },
+                "required": ["file_path", "content"]
+            }),
+        }
+    }
+
+    fn permissions(&self) -> ToolPermissions {
+        ToolPermissions {
+            writes_files: true,
+            ..Default::default()
+        }
+    }
+
+    fn permission_subject(&self, tool_call: &ToolCall) -> Option<String> {
+        file_path_arg(&tool_call.args).map(crate::permissions::resolve_path)
+    }
+
+    fn modified_files(&self, tool_call: &ToolCall) -> Vec<PathBuf> {
+        file_path_arg(&tool_call.args)
+            .map(|path| PathBuf::from(crate::permissions::resolve_path(path)))
+            .into_iter()
+            .collect()
+    }
+
+    async fn execute(&self, tool_call: &ToolCall, _ctx: &ToolContext<'_>) -> Result<String> {
+        debug!("Processing write_file tool call");
+        debug!("Raw tool_call.args: {:?}", tool_call.args);
+        debug!(
+            "Args as JSON: {}",
+            serde_json::to_string(&tool_call.args)
+                .unwrap_or_else(|_| "failed to serialize".to_string())
+        );
+        debug!(
+            "Args type: {:?}",
+            std::any::type_name_of_val(&tool_call.args)
+        );
+        debug!("Args is_object: {}", tool_call.args.is_object());
+        debug!("Args is_array: {}", tool_call.args.is_array());
+        debug!("Args is_null: {}", tool_call.args.is_null());
+
+        // Try multiple argument formats that different providers might use
+        let (path_str, content_str) = if let Some(args_obj) = tool_call.args.as_object() {
+            debug!(
+                "Args object keys: {:?}",
+                args_obj.keys().collect::<Vec<_>>()
+            );
+
+            // Format 1: Standard format with file_path and content
+            if let (Some(path_val), Some(content_val)) =
+                (args_obj.get("file_path"), args_obj.get("content"))
+            {
+                debug!("Found file_path and content keys");
+                if let (Some(path), Some(content)) =
+                    (path_val.as_str(), content_val.as_str())
+                {
+                    debug!(
+                        "Successfully extracted file_path='{}', content_len={}",
+                        path,
+                        content.len()
+                    );
+                    (Some(path), Some(content))
+                } else {
+                    debug!("file_path or content values are not strings: path_val={:?}, content_val={:?}", path_val, content_val);
+                    (None, None)
+                }
+            }
+            // Format 2: Anthropic-style with path and content
+            else if let (Some(path_val), Some(content_val)) =
+                (args_obj.get("path"), args_obj.get("content"))
+            {
+                debug!("Found path and content keys (Anthropic style)");
+                if let (Some(path), Some(content)) =
+                    (path_val.as_str(), content_val.as_str())
+                {
+                    debug!(
+                        "Successfully extracted path='{}', content_len={}",
+                        path,
+                        content.len()
+                    );
+                    (Some(path), Some(content))
+                } else {
+                    debug!("path or content values are not strings: path_val={:?}, content_val={:?}", path_val, content_val);
+                    (None, None)
+                }
+            }
+            // Format 3: Alternative naming with filename and text
+            else if let (Some(path_val), Some(content_val)) =
+                (args_obj.get("filename"), args_obj.get("text"))
+            {
+                debug!("Found filename and text keys");
+                if let (Some(path), Some(content)) =
+                    (path_val.as_str(), content_val.as_str())
+                {
+                    debug!(
+                        "Successfully extracted filename='{}', text_len={}",
+                        path,
+                        content.len()
+                    );
+                    (Some(path), Some(content))
+                } else {
+                    debug!("filename or text values are not strings: path_val={:?}, content_val={:?}", path_val, content_val);
+                    (None, None)
+                }
+            }
+            // Format 4: Alternative naming with file and data
+            else if let (Some(path_val), Some(content_val)) =
+                (args_obj.get("file"), args_obj.get("data"))
+            {
+                debug!("Found file and data keys");
+                if let (Some(path), Some(content)) =
+                    (path_val.as_str(), content_val.as_str())
+                {
+                    debug!(
+                        "Successfully extracted file='{}', data_len={}",
+                        path,
+                        content.len()
+                    );
+                    (Some(path), Some(content))
+                } else {
+                    debug!("file or data values are not strings: path_val={:?}, content_val={:?}", path_val, content_val);
+                    (None, None)
+                }
+            } else {
+                debug!(
+                    "No matching key patterns found. Available argument keys: {:?}",
+                    args_obj.keys().collect::<Vec<_>>()
+                );
+                (None, None)
+            }
+        } else {
+            debug!("Args is not an object, checking if it's an array");
+            // Format 5: Args might be an array [path, content]
+            if let Some(args_array) = tool_call.args.as_array() {
+                debug!("Args is an array with {} elements", args_array.len());
+                if args_array.len() >= 2 {
+                    if let (Some(path), Some(content)) =
+                        (args_array[0].as_str(), args_array[1].as_str())
+                    {
+                        debug!(
+                            "Successfully extracted from array: path='{}', content_len={}",
+                            path,
+                            content.len()
+                        );
+                        (Some(path), Some(content))
+                    } else {
+                        debug!(
+                            "Array elements are not strings: [0]={:?}, [1]={:?}",
+                            args_array[0], args_array[1]
+                        );
+                        (None, None)
+                    }
+                } else {
+                    debug!("Array has insufficient elements: {}", args_array.len());
+                    (None, None)
+                }
+            } else {
+                debug!("Args is neither object nor array");
+                (None, None)
+            }
+        };
+
+        debug!(
+            "Final extracted values: path_str={:?}, content_str_len={:?}",
+            path_str,
+            content_str.map(|c| c.len())
+        );
+
+        if let (Some(path), Some(content)) = (path_str, content_str) {
+            // Expand tilde (~) to home directory
+            let expanded_path = workspace_path(&shellexpand::tilde(path));
+            let path = expanded_path.as_str();
+
+            debug!("Writing to file: {}", path);
+
+            // Create parent directories if they don't exist
+            if let Some(parent) = std::path::Path::new(path).parent() {
+                if let Err(e) = std::fs::create_dir_all(parent) {
+                    return Ok(format!(
+                        "❌ Failed to create parent directories for '{}': {}",
+                        path, e
+                    ));
+                }
+            }
+
+            match std::fs::write(path, content) {
+                Ok(()) => {
+                    let line_count = content.lines().count();
+                    let char_count = content.len();
+                    Ok(format!(
+                        "✅ Successfully wrote {} lines ({} characters)",
+                        line_count, char_count
+                    ))
+                }
+                Err(e) => Ok(format!("❌ Failed to write to file '{}': {}", path, e)),
+            }
+        } else {
+            // Provide more detailed error information
+            let available_keys = if let Some(obj) = tool_call.args.as_object() {
+                obj.keys().collect::<Vec<_>>()
+            } else {
+                vec![]
+            };
+
+            Ok(format!(
+                "❌ Missing file_path or content argument. Available keys: {:?}. Expected formats: {{\"file_path\": \"...\", \"content\": \"...\"}}, {{\"path\": \"...\", \"content\": \"...\"}}, {{\"filename\": \"...\", \"text\": \"...\"}}, or {{\"file\": \"...\", \"data\": \"...\"}}",
+                available_keys
+            ))
+        }
+    }
+}
+
+pub struct StrReplaceTool;
+
+#[async_trait]
+impl ToolHandler for StrReplaceTool {
+    fn name(&self) -> &str {
+        "str_replace"
+    }
+
+    fn definition(&self) -> Tool {
+        Tool {
+            name: "str_replace".to_string(),
+            description: "Apply a unified diff to a file. Supports multiple hunks and context lines. Optionally constrain the search to a [start, end) character range (0-indexed; end is EXCLUSIVE). Useful to disambiguate matches or limit scope in large files.".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "file_path": {
+                        "type": "string",
+                        "description": "The path to the file to edit"
+                    },
+                    "diff": {
+                        "type": "string",
+                        "description": "A unified diff showing what to replace. Supports @@ hunk headers, context lines, and multiple hunks (---/+++ headers optional for minimal diffs)."
+                    },
+                    "start": {
+                        "type": "integer",
+                        "description": "Starting character position in the file (0-indexed, inclusive). If omitted, searches from beginning."
+                    },
+                    "end": {
+                        "type": "integer",
+                        "description": "Ending character position in the file (0-indexed, EXCLUSIVE - character at this position is NOT included). If omitted, searches to end of file."
+                    }
+                },
+                "required": ["file_path", "diff"]
+            }),
+        }
+    }
+
+    fn permissions(&self) -> ToolPermissions {
+        ToolPermissions {
+            writes_files: true,
+            ..Default::default()
+        }
+    }
+
+    fn permission_subject(&self, tool_call: &ToolCall) -> Option<String> {
+        file_path_arg(&tool_call.args).map(crate::permissions::resolve_path)
+    }
+
+    fn modified_files(&self, tool_call: &ToolCall) -> Vec<PathBuf> {
+        file_path_arg(&tool_call.args)
+            .map(|path| PathBuf::from(crate::permissions::resolve_path(path)))
+            .into_iter()
+            .collect()
+    }
+
+    async fn execute(&self, tool_call: &ToolCall, _ctx: &ToolContext<'_>) -> Result<String> {
+        debug!("Processing str_replace tool call");
+
+        // Extract arguments
+        let args_obj = match tool_call.args.as_object() {
+            Some(obj) => obj,
+            None => return Ok("❌ Invalid arguments: expected object".to_string()),
+        };
+
+        let file_path = match args_obj.get("file_path").and_then(|v| v.as_str()) {
+            Some(path) => {
+                // Expand tilde (~) to home directory
+                workspace_path(&shellexpand::tilde(path))
+            }
+            None => return Ok("❌ Missing or invalid file_path argument".to_string()),
+        };
+
+        let diff = match args_obj.get("diff").and_then(|v| v.as_str()) {
+            Some(d) => d,
+            None => return Ok("❌ Missing or invalid diff argument".to_string()),
+        };
+
+        // Optional start and end character positions (0-indexed, end is EXCLUSIVE)
+        let start_char = args_obj
+            .get("start")
+            .and_then(|v| v.as_u64())
+            .map(|n| n as usize);
+        let end_char = args_obj
+            .get("end")
+            .and_then(|v| v.as_u64())
+            .map(|n| n as usize);
+
+        debug!(
+            "str_replace: path={}, start={:?}, end={:?}",
+            file_path, start_char, end_char
+        );
+
+        // Read the existing file
+        let file_content = match std::fs::read_to_string(&file_path) {
+            Ok(content) => content,
+            Err(e) => return Ok(format!("❌ Failed to read file '{}': {}", file_path, e)),
+        };
+
+        // Apply unified diff to content
+        let result =
+            match apply_unified_diff_to_string(&file_content, diff, start_char, end_char) {
+                Ok(r) => r,
+                Err(e) => return Ok(format!("❌ {}", e)),
+            };
+
+        // Write the result back to the file
+        match std::fs::write(&file_path, &result) {
+            Ok(()) => Ok("✅ applied unified diff".to_string()),
+            Err(e) => Ok(format!("❌ Failed to write to file '{}': {}", file_path, e)),
+        }
+    }
+}
+
+pub struct UndoLastEditTool;
+
+#[async_trait]
+impl ToolHandler for UndoLastEditTool {
+    fn name(&self) -> &str {
+        "undo_last_edit"
+    }
+
+    fn definition(&self) -> Tool {
+        Tool {
+            name: "undo_last_edit".to_string(),
+            description: "Restore the file(s) changed by the most recent write_file or str_replace call to their previous contents. Call repeatedly to step further back. Works without git.".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {},
+                "required": []
+            }),
+        }
+    }
+
+    fn permissions(&self) -> ToolPermissions {
+        ToolPermissions {
+            writes_files: true,
+            ..Default::default()
+        }
+    }
+
+    async fn execute(&self, _tool_call: &ToolCall, ctx: &ToolContext<'_>) -> Result<String> {
+        debug!("Processing undo_last_edit tool call");
+        let undone = match ctx.checkpoints.lock() {
+            Ok(mut checkpoints) => checkpoints.undo_last(),
+            Err(_) => return Ok("❌ Checkpoint store is unavailable".to_string()),
+        };
+
+        match undone {
+            Ok(Some(checkpoint)) => {
+                let files = checkpoint
+                    .files
+                    .iter()
+                    .map(|file| match file.previous {
+                        Some(_) => format!("restored {}", file.path.display()),
+                        None => format!("removed {}", file.path.display()),
+                    })
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                Ok(format!("✅ Undid {}: {}", checkpoint.tool, files))
+            }
+            Ok(None) => Ok("❌ No edits to undo".to_string()),
+            Err(e) => Ok(format!("❌ Failed to undo last edit: {}", e)),
+        }
+    }
+}
@@ -0,0 +1,362 @@
+//! macOS Accessibility API automation
+
+use super::{ToolContext, ToolHandler, ToolPermissions};
+use crate::ToolCall;
+use anyhow::Result;
+use async_trait::async_trait;
+use g3_config::Config;
+use g3_providers::Tool;
+use serde_json::json;
+use tracing::debug;
+
+pub struct MacaxListAppsTool;
+
+#[async_trait]
+impl ToolHandler for MacaxListAppsTool {
+    fn name(&self) -> &str {
+        "macax_list_apps"
+    }
+
+    fn definition(&self) -> Tool {
+        Tool {
+            name: "macax_list_apps".to_string(),
+            description: "List all running applications that can be controlled via macOS Accessibility API".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {},
+                "required": []
+            }),
+        }
+    }
+
+    fn permissions(&self) -> ToolPermissions {
+        ToolPermissions {
+            controls_desktop: true,
+            ..Default::default()
+        }
+    }
+
+    fn is_available(&self, config: &Config) -> bool {
+        config.macax.enabled
+    }
+
+    async fn execute(&self, _tool_call: &ToolCall, ctx: &ToolContext<'_>) -> Result<String> {
+        debug!("Processing macax_list_apps tool call");
+
+        if !ctx.config.macax.enabled {
+            return Ok(
+                "❌ macOS Accessibility is not enabled. Use --macax flag to enable."
+                    .to_string(),
+            );
+        }
+
+        let controller_guard = ctx.macax_controller.read().await;
+        let controller = match controller_guard.as_ref() {
+            Some(c) => c,
+            None => {
+                return Ok("❌ macOS Accessibility controller not initialized.".to_string())
+            }
+        };
+
+        match controller.list_applications() {
+            Ok(apps) => {
+                let app_list: Vec<String> = apps.iter().map(|a| a.name.clone()).collect();
+                Ok(format!("Running applications:\n{}", app_list.join("\n")))
+            }
+            Err(e) => Ok(format!("❌ Failed to list applications: {}", e)),
+        }
+    }
+}
+
+pub struct MacaxGetFrontmostAppTool;
+
+#[async_trait]
+impl ToolHandler for MacaxGetFrontmostAppTool {
+    fn name(&self) -> &str {
+        "macax_get_frontmost_app"
+    }
+
+    fn definition(&self) -> Tool {
+        Tool {
+            name: "macax_get_frontmost_app".to_string(),
+            description: "Get the name of the currently active (frontmost) application".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {},
+                "required": []
+            }),
+        }
+    }
+
+    fn permissions(&self) -> ToolPermissions {
+        ToolPermissions {
+            controls_desktop: true,
+            ..Default::default()
+        }
+    }
+
+    fn is_available(&self, config: &Config) -> bool {
+        config.macax.enabled
+    }
+
+    async fn execute(&self, _tool_call: &ToolCall, ctx: &ToolContext<'_>) -> Result<String> {
+        debug!("Processing macax_get_frontmost_app tool call");
+
+        if !ctx.config.macax.enabled {
+            return Ok(
+                "❌ macOS Accessibility is not enabled. Use --macax flag to enable."
+                    .to_string(),
+            );
+        }
+
+        let controller_guard = ctx.macax_controller.read().await;
+        let controller = match controller_guard.as_ref() {
+            Some(c) => c,
+            None => {
+                return Ok("❌ macOS Accessibility controller not initialized.".to_string())
+            }
+        };
+
+        match controller.get_frontmost_app() {
+            Ok(app) => Ok(format!("Frontmost application: {}", app.name)),
+            Err(e) => Ok(format!("❌ Failed to get frontmost app: {}", e)),
+        }
+    }
+}
+
+pub struct MacaxActivateAppTool;
+
+#[async_trait]
+impl ToolHandler for MacaxActivateAppTool {
+    fn name(&self) -> &str {
+        "macax_activate_app"
+    }
+
+    fn definition(&self) -> Tool {
+        Tool {
+            name: "macax_activate_app".to_string(),
+            description: "Bring an application to the front (activate it)".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "app_name": {
+                        "type": "string",
+                        "description": "Name of the application to activate (e.g., 'Safari', 'TextEdit')"
+                    }
+                },
+                "required": ["app_name"]
+            }),
+        }
+    }
+
+    fn permissions(&self) -> ToolPermissions {
+        ToolPermissions {
+            controls_desktop: true,
+            ..Default::default()
+        }
+    }
+
+    fn is_available(&self, config: &Config) -> bool {
+        config.macax.enabled
+    }
+
+    async fn execute(&self, tool_call: &ToolCall, ctx: &ToolContext<'_>) -> Result<String> {
+        debug!("Processing macax_activate_app tool call");
+
+        if !ctx.config.macax.enabled {
+            return Ok(
+                "❌ macOS Accessibility is not enabled. Use --macax flag to enable."
+                    .to_string(),
+            );
+        }
+
+        let app_name = match tool_call.args.get("app_name").and_then(|v| v.as_str()) {
+            Some(n) => n,
+            None => return Ok("❌ Missing app_name argument".to_string()),
+        };
+
+        let controller_guard = ctx.macax_controller.read().await;
+        let controller = match controller_guard.as_ref() {
+            Some(c) => c,
+            None => {
+                return Ok("❌ macOS Accessibility controller not initialized.".to_string())
+            }
+        };
+
+        match controller.activate_app(app_name) {
+            Ok(_) => Ok(format!("✅ Activated application: {}", app_name)),
+            Err(e) => Ok(format!("❌ Failed to activate app: {}", e)),
+        }
+    }
+}
+
+pub struct MacaxPressKeyTool;
+
+#[async_trait]
+impl ToolHandler for MacaxPressKeyTool {
+    fn name(&self) -> &str {
+        "macax_press_key"
+    }
+
+    fn definition(&self) -> Tool {
+        Tool {
+            name: "macax_press_key".to_string(),
+            description: "Press a keyboard key or shortcut in an application (e.g., Cmd+S to save)".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "app_name": {
+                        "type": "string",
+                        "description": "Name of the application"
+                    },
+                    "key": {
+                        "type": "string",
+                        "description": "Key to press (e.g., 's', 'return', 'tab')"
+                    },
+                    "modifiers": {
+                        "type": "array",
+                        "items": {
+                            "type": "string"
+                        },
+                        "description": "Modifier keys (e.g., ['command', 'shift'])"
+                    }
+                },
+                "required": ["app_name", "key"]
+            }),
+        }
+    }
+
+    fn permissions(&self) -> ToolPermissions {
+        ToolPermissions {
+            controls_desktop: true,
+            ..Default::default()
+        }
+    }
+
+    fn is_available(&self, config: &Config) -> bool {
+        config.macax.enabled
+    }
+
+    async fn execute(&self, tool_call: &ToolCall, ctx: &ToolContext<'_>) -> Result<String> {
+        debug!("Processing macax_press_key tool call");
+
+        if !ctx.config.macax.enabled {
+            return Ok(
+                "❌ macOS Accessibility is not enabled. Use --macax flag to enable."
+                    .to_string(),
+            );
+        }
+
+        let app_name = match tool_call.args.get("app_name").and_then(|v| v.as_str()) {
+            Some(n) => n,
+            None => return Ok("❌ Missing app_name argument".to_string()),
+        };
+
+        let key = match tool_call.args.get("key").and_then(|v| v.as_str()) {
+            Some(k) => k,
+            None => return Ok("❌ Missing key argument".to_string()),
+        };
+
+        let modifiers_vec: Vec<&str> = tool_call
+            .args
+            .get("modifiers")
+            .and_then(|v| v.as_array())
+            .map(|arr| arr.iter().filter_map(|v| v.as_str()).collect())
+            .unwrap_or_default();
+
+        let controller_guard = ctx.macax_controller.read().await;
+        let controller = match controller_guard.as_ref() {
+            Some(c) => c,
+            None => {
+                return Ok("❌ macOS Accessibility controller not initialized.".to_string())
+            }
+        };
+
+        match controller.press_key(app_name, key, modifiers_vec.clone()) {
+            Ok(_) => {
+                let modifier_str = if modifiers_vec.is_empty() {
+                    String::new()
+                } else {
+                    format!(" with modifiers: {}", modifiers_vec.join("+"))
+                };
+                Ok(format!("✅ Pressed key: {}{}", key, modifier_str))
+            }
+            Err(e) => Ok(format!("❌ Failed to press key: {}", e)),
+        }
+    }
+}
+
+pub struct MacaxTypeTextTool;
+
+#[async_trait]
+impl ToolHandler for MacaxTypeTextTool {
+    fn name(&self) -> &str {
+        "macax_type_text"
+    }
+
+    fn definition(&self) -> Tool {
+        Tool {
+            name: "macax_type_text".to_string(),
+            description: "Type arbitrary text into the currently focused element in an application (supports unicode, emojis, etc.)".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "app_name": {
+                        "type": "string",
+                        "description": "Name of the application"
+                    },
+                    "text": {
+                        "type": "string",
+                        "description": "Text to type (can include unicode, emojis, special characters)"
+                    }
+                },
+                "required": ["app_name", "text"]
+            }),
+        }
+    }
+
+    fn permissions(&self) -> ToolPermissions {
+        ToolPermissions {
+            controls_desktop: true,
+            ..Default::default()
+        }
+    }
+
+    fn is_available(&self, config: &Config) -> bool {
+        config.macax.enabled
+    }
+
+    async fn execute(&self, tool_call: &ToolCall, ctx: &ToolContext<'_>) -> Result<String> {
+        debug!("Processing macax_type_text tool call");
+
+        if !ctx.config.macax.enabled {
+            return Ok(
+                "❌ macOS Accessibility is not enabled. Use --macax flag to enable."
+                    .to_string(),
+            );
+        }
+
+        let app_name = match tool_call.args.get("app_name").and_then(|v| v.as_str()) {
+            Some(n) => n,
+            None => return Ok("❌ Missing app_name argument".to_string()),
+        };
+
+        let text = match tool_call.args.get("text").and_then(|v| v.as_str()) {
+            Some(t) => t,
+            None => return Ok("❌ Missing text argument".to_string()),
+        };
+
+        let controller_guard = ctx.macax_controller.read().await;
+        let controller = match controller_guard.as_ref() {
+            Some(c) => c,
+            None => {
+                return Ok("❌ macOS Accessibility controller not initialized.".to_string())
+            }
+        };
+
+        match controller.type_text(app_name, text) {
+            Ok(_) => Ok(format!("✅ Typed text into {}", app_name)),
+            Err(e) => Ok(format!("❌ Failed to type text: {}", e)),
+        }
+    }
+}
@@ -0,0 +1,438 @@
+//! Turn completion, code coverage, code search, semantic search and the repo map
+
+use super::{ToolContext, ToolHandler, ToolPermissions};
+use crate::semantic_index::{match_snippet, Embedder, SemanticIndex};
+use crate::ToolCall;
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use g3_config::Config;
+use g3_providers::Tool;
+use serde_json::json;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+use tracing::debug;
+
+pub struct FinalOutputTool;
+
+#[async_trait]
+impl ToolHandler for FinalOutputTool {
+    fn name(&self) -> &str {
+        "final_output"
+    }
+
+    fn definition(&self) -> Tool {
+        Tool {
+            name: "final_output".to_string(),
+            description: "Signal task completion with a detailed summary".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "summary": {
+                        "type": "string",
+                        "description": "A detailed summary in markdown of what was accomplished"
+                    }
+                },
+                "required": ["summary"]
+            }),
+        }
+    }
+
+    fn permissions(&self) -> ToolPermissions {
+        ToolPermissions::default()
+    }
+
+    async fn execute(&self, tool_call: &ToolCall, _ctx: &ToolContext<'_>) -> Result<String> {
+        if let Some(summary) = tool_call.args.get("summary") {
+            if let Some(summary_str) = summary.as_str() {
+                Ok(summary_str.to_string())
+            } else {
+                Ok("✅ Turn completed".to_string())
+            }
+        } else {
+            Ok("✅ Turn completed".to_string())
+        }
+    }
+}
+
+pub struct CodeCoverageTool;
+
+#[async_trait]
+impl ToolHandler for CodeCoverageTool {
+    fn name(&self) -> &str {
+        "code_coverage"
+    }
+
+    fn definition(&self) -> Tool {
+        Tool {
+            name: "code_coverage".to_string(),
+            description: "Generate a code coverage report for the entire workspace using cargo llvm-cov. This runs all tests with coverage instrumentation and returns a summary of coverage statistics. Requires llvm-tools-preview and cargo-llvm-cov to be installed (they will be auto-installed if missing).".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {},
+                "required": []
+            }),
+        }
+    }
+
+    fn permissions(&self) -> ToolPermissions {
+        ToolPermissions {
+            executes_commands: true,
+            ..Default::default()
+        }
+    }
+
+    async fn execute(&self, _tool_call: &ToolCall, ctx: &ToolContext<'_>) -> Result<String> {
+        debug!("Processing code_coverage tool call");
+        ctx.ui_writer
+            .print_context_status("🔍 Generating code coverage report...");
+
+        // Ensure coverage tools are installed
+        match g3_execution::ensure_coverage_tools_installed() {
+            Ok(already_installed) => {
+                if !already_installed {
+                    ctx.ui_writer
+                        .print_context_status("✅ Coverage tools installed successfully");
+                }
+            }
+            Err(e) => {
+                return Ok(format!("❌ Failed to install coverage tools: {}", e));
+            }
+        }
+
+        // Run cargo llvm-cov --workspace
+        let output = std::process::Command::new("cargo")
+            .args(&["llvm-cov", "--workspace"])
+            .current_dir(crate::project::current_workspace())
+            .output()?;
+
+        if output.status.success() {
+            let stdout = String::from_utf8_lossy(&output.stdout);
+            let stderr = String::from_utf8_lossy(&output.stderr);
+
+            // Combine output
+            let mut result =
+                String::from("✅ Code coverage report generated successfully\n\n");
+            result.push_str("## Coverage Summary\n");
+            result.push_str(&stdout);
+            if !stderr.is_empty() {
+                result.push_str("\n## Warnings\n");
+                result.push_str(&stderr);
+            }
+            Ok(result)
+        } else {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            Ok(format!(
+                "❌ Failed to generate coverage report:\n{}",
+                stderr
+            ))
+        }
+    }
+}
+
+pub struct CodeSearchTool;
+
+#[async_trait]
+impl ToolHandler for CodeSearchTool {
+    fn name(&self) -> &str {
+        "code_search"
+    }
+
+    fn definition(&self) -> Tool {
+        Tool {
+            name: "code_search".to_string(),
+            description: "Syntax-aware code search that understands code structure, not just text. Finds actual functions, classes, methods, and other code constructs - ignores matches in comments and strings. Much more accurate than grep for code searches. Supports batch searches (up to 20 parallel) with structured results and context lines. Languages: Rust, Python, JavaScript, TypeScript, Go, Java, C, C++, Kotlin. Uses tree-sitter query syntax.".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "searches": {
+                        "type": "array",
+                        "maxItems": 20,
+                        "items": {
+                            "type": "object",
+                            "properties": {
+                                "name": { "type": "string", "description": "Label for this search." },
+                                "query": { "type": "string", "description": "tree-sitter query in S-expression format (e.g., \"(function_item name: (identifier) @name)\")"},
+                                "language": { "type": "string", "enum": ["rust", "python", "javascript", "typescript", "go", "java", "c", "cpp", "kotlin"], "description": "Programming language to search." },
+                                "paths": { "type": "array", "items": { "type": "string" }, "description": "Paths/dirs to search. Defaults to current dir if empty." },
+                                "context_lines": { "type": "integer", "minimum": 0, "maximum": 20, "default": 0, "description": "Lines of context to include around each match." }
+                            },
+                            "required": ["name", "query", "language"]
+                        }
+                    },
+                    "max_concurrency": { "type": "integer", "minimum": 1, "default": 4 },
+                    "max_matches_per_search": { "type": "integer", "minimum": 1, "default": 500 }
+                },
+                "required": ["searches"]
+            }),
+        }
+    }
+
+    fn permissions(&self) -> ToolPermissions {
+        ToolPermissions {
+            reads_files: true,
+            ..Default::default()
+        }
+    }
+
+    async fn execute(&self, tool_call: &ToolCall, _ctx: &ToolContext<'_>) -> Result<String> {
+        debug!("Processing code_search tool call");
+
+        // Parse the request
+        let request: crate::code_search::CodeSearchRequest =
+            match serde_json::from_value(tool_call.args.clone()) {
+                Ok(req) => req,
+                Err(e) => {
+                    return Ok(format!("❌ Invalid code_search arguments: {}", e));
+                }
+            };
+
+        // Execute the code search
+        match crate::code_search::execute_code_search(request).await {
+            Ok(response) => {
+                // Serialize the response to JSON
+                match serde_json::to_string_pretty(&response) {
+                    Ok(json_output) => {
+                        Ok(format!("✅ Code search completed\n{}", json_output))
+                    }
+                    Err(e) => Ok(format!("❌ Failed to serialize response: {}", e)),
+                }
+            }
+            Err(e) => Ok(format!("❌ Code search failed: {}", e)),
+        }
+    }
+}
+
+pub struct RepoMapTool;
+
+#[async_trait]
+impl ToolHandler for RepoMapTool {
+    fn name(&self) -> &str {
+        "repo_map"
+    }
+
+    fn definition(&self) -> Tool {
+        Tool {
+            name: "repo_map".to_string(),
+            description: "Get a compact map of the codebase: the most important files with the line and name of the functions, types and classes they define. Files are ranked by how often the rest of the code references their definitions, and the map is cut to fit a token budget. Use it to orient yourself in an unfamiliar or large repository before reading files.".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "path": { "type": "string", "description": "Directory to map (relative to the workspace). Defaults to the workspace." },
+                    "max_tokens": { "type": "integer", "minimum": 256, "maximum": 8192, "default": 1024, "description": "Token budget for the map" }
+                },
+                "required": []
+            }),
+        }
+    }
+
+    fn permissions(&self) -> ToolPermissions {
+        ToolPermissions {
+            reads_files: true,
+            ..Default::default()
+        }
+    }
+
+    async fn execute(&self, tool_call: &ToolCall, _ctx: &ToolContext<'_>) -> Result<String> {
+        debug!("Processing repo_map tool call");
+
+        let path = tool_call
+            .args
+            .get("path")
+            .and_then(|p| p.as_str())
+            .unwrap_or(".");
+        let root = match crate::repo_map::RepoMap::resolve_root(
+            &crate::project::current_workspace(),
+            path,
+        ) {
+            Ok(root) => root,
+            Err(e) => return Ok(format!("❌ {}", e)),
+        };
+        let max_tokens = tool_call
+            .args
+            .get("max_tokens")
+            .and_then(|t| t.as_u64())
+            .unwrap_or(1024)
+            .clamp(256, 8192) as u32;
+
+        let map =
+            tokio::task::spawn_blocking(move || crate::repo_map::RepoMap::build(&root)).await?;
+        match map {
+            Ok(map) if map.files.is_empty() => {
+                Ok("❌ No definitions found in supported languages".to_string())
+            }
+            Ok(map) => Ok(format!(
+                "✅ Repo map ({} files with definitions, most referenced first)\n{}",
+                map.files.len(),
+                map.render(max_tokens, &g3_providers::EstimatingTokenizer::default())
+            )),
+            Err(e) => Ok(format!("❌ Failed to build repo map: {}", e)),
+        }
+    }
+}
+
+/// Lines of each match shown to the model
+const SEMANTIC_SNIPPET_LINES: usize = 12;
+
+/// Searches the local embedding index. The embedding model is loaded on the
+/// first call and each workspace's index is kept in memory between calls.
+#[derive(Default)]
+pub struct SemanticSearchTool {
+    embedder: Mutex<Option<Arc<dyn Embedder>>>,
+    indexes: Mutex<HashMap<PathBuf, SemanticIndex>>,
+}
+
+impl SemanticSearchTool {
+    /// Use `embedder` instead of loading `semantic_index.embedding_model`
+    pub fn with_embedder(embedder: Arc<dyn Embedder>) -> Self {
+        Self {
+            embedder: Mutex::new(Some(embedder)),
+            indexes: Mutex::new(HashMap::new()),
+        }
+    }
+
+    async fn embedder(&self, config: &Config) -> Result<Arc<dyn Embedder>> {
+        let mut embedder = self.embedder.lock().await;
+        if let Some(embedder) = embedder.as_ref() {
+            return Ok(embedder.clone());
+        }
+        let settings = config.semantic_index.clone();
+        let model_path = settings
+            .embedding_model
+            .ok_or_else(|| anyhow!("semantic_index.embedding_model is not configured"))?;
+        let model = tokio::task::spawn_blocking(move || {
+            g3_providers::EmbeddingModel::load(&model_path, settings.gpu_layers, settings.threads)
+        })
+        .await??;
+        let loaded: Arc<dyn Embedder> = Arc::new(model);
+        *embedder = Some(loaded.clone());
+        Ok(loaded)
+    }
+}
+
+#[async_trait]
+impl ToolHandler for SemanticSearchTool {
+    fn name(&self) -> &str {
+        "semantic_search"
+    }
+
+    fn definition(&self) -> Tool {
+        Tool {
+            name: "semantic_search".to_string(),
+            description: "Find code by meaning using a local embedding index of the workspace's functions, types and classes. Use it when you don't know where something is implemented (e.g. \"where are retries handled\") instead of grepping through many files, then read the returned line ranges. The index is refreshed for changed files before each search. Use code_search for exact structural queries.".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "query": { "type": "string", "description": "Natural language description of the code to find" },
+                    "limit": { "type": "integer", "minimum": 1, "maximum": 50, "default": 10, "description": "Maximum number of results" },
+                    "path": { "type": "string", "description": "Only search files under this directory (relative to the workspace)" }
+                },
+                "required": ["query"]
+            }),
+        }
+    }
+
+    fn permissions(&self) -> ToolPermissions {
+        ToolPermissions {
+            reads_files: true,
+            ..Default::default()
+        }
+    }
+
+    fn is_available(&self, config: &Config) -> bool {
+        config.semantic_index.embedding_model.is_some()
+    }
+
+    async fn execute(&self, tool_call: &ToolCall, ctx: &ToolContext<'_>) -> Result<String> {
+        debug!("Processing semantic_search tool call");
+
+        let Some(query) = tool_call.args.get("query").and_then(|q| q.as_str()) else {
+            return Ok("❌ Missing query argument".to_string());
+        };
+        let query = query.to_string();
+        let limit = tool_call
+            .args
+            .get("limit")
+            .and_then(|l| l.as_u64())
+            .unwrap_or(10)
+            .clamp(1, 50) as usize;
+        let path = tool_call
+            .args
+            .get("path")
+            .and_then(|p| p.as_str())
+            .map(str::to_string);
+
+        let embedder = match self.embedder(ctx.config).await {
+            Ok(embedder) => embedder,
+            Err(e) => return Ok(format!("❌ Semantic search unavailable: {}", e)),
+        };
+
+        let root = crate::project::current_workspace();
+        let mut indexes = self.indexes.lock().await;
+        let index = match indexes.remove(&root) {
+            Some(index) => index,
+            None => match SemanticIndex::open(&root, embedder) {
+                Ok(index) => index,
+                Err(e) => return Ok(format!("❌ Failed to open semantic index: {}", e)),
+            },
+        };
+        if index.file_count() == 0 {
+            ctx.ui_writer
+                .print_context_status("🔍 Building semantic index (first run may take a while)...");
+        }
+
+        // Embedding is CPU-bound; keep it off the async workers
+        let (index, result) = tokio::task::spawn_blocking(move || {
+            let mut index = index;
+            let result = index.update().and_then(|update| {
+                if !update.is_empty() {
+                    index.save()?;
+                }
+                let matches = index.search(&query, limit, path.as_deref())?;
+                Ok((update, matches))
+            });
+            (index, result)
+        })
+        .await?;
+
+        let (update, matches) = match result {
+            Ok(result) => result,
+            Err(e) => {
+                indexes.insert(root, index);
+                return Ok(format!("❌ Semantic search failed: {}", e));
+            }
+        };
+
+        let mut output = format!(
+            "✅ {} results (index: {} files, {} chunks",
+            matches.len(),
+            index.file_count(),
+            index.chunk_count()
+        );
+        if !update.is_empty() {
+            output.push_str(&format!(
+                "; re-indexed {} new, {} changed, {} removed files",
+                update.added, update.updated, update.removed
+            ));
+        }
+        output.push_str(")\n");
+        for (i, m) in matches.iter().enumerate() {
+            output.push_str(&format!(
+                "\n{}. {}:{}-{} {} `{}` (score {:.2})\n",
+                i + 1,
+                m.file,
+                m.start_line,
+                m.end_line,
+                m.kind,
+                m.symbol,
+                m.score
+            ));
+            if let Some(snippet) = match_snippet(&root, m, SEMANTIC_SNIPPET_LINES) {
+                output.push_str(&format!("```\n{}\n```\n", snippet));
+            }
+        }
+        indexes.insert(root, index);
+        Ok(output)
+    }
+}
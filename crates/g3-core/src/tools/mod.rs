@@ -0,0 +1,208 @@
+//! Tool handlers and the registry the agent dispatches tool calls through
+//!
+//! Every tool the model can call — built-in, custom or discovered from an MCP
+//! server — is a [`ToolHandler`]. The [`ToolRegistry`] owns the handlers,
+//! produces the `Tool` schemas sent to providers and resolves calls by name.
+//! Embedders can register their own handlers or disable built-ins per mode
+//! through `Agent::tool_registry_mut`.
+
+use crate::ui_writer::UiWriter;
+use crate::{ToolCall, WebDriverSession};
+use anyhow::Result;
+use async_trait::async_trait;
+use g3_config::Config;
+use g3_providers::Tool;
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use std::sync::Arc;
+use tokio::sync::{Mutex, RwLock};
+
+pub mod computer;
+pub mod file_ops;
+pub mod macax;
+pub mod misc;
+pub mod shell;
+pub mod todo;
+pub mod webdriver;
+
+/// What a tool may do to the machine it runs on.
+///
+/// Used to decide whether a call needs confirmation; it does not sandbox the
+/// handler itself.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ToolPermissions {
+    pub reads_files: bool,
+    pub writes_files: bool,
+    pub executes_commands: bool,
+    pub network: bool,
+    pub controls_desktop: bool,
+}
+
+impl ToolPermissions {
+    /// True if the tool cannot modify files, run commands or drive the desktop
+    pub fn is_read_only(&self) -> bool {
+        !self.writes_files && !self.executes_commands && !self.controls_desktop
+    }
+}
+
+/// Agent state a handler may use while executing a call
+pub struct ToolContext<'a> {
+    pub config: &'a Config,
+    pub ui_writer: &'a dyn UiWriter,
+    /// Working directory for commands (set by --codebase-fast-start)
+    pub working_dir: Option<&'a str>,
+    /// Runs commands on the configured execution backend
+    pub executor: &'a g3_execution::CodeExecutor,
+    pub requirements_sha: &'a Option<String>,
+    pub todo_content: &'a Arc<RwLock<String>>,
+    pub computer_controller: &'a Option<Box<dyn g3_computer_control::ComputerController>>,
+    pub webdriver_session: &'a Arc<RwLock<Option<Arc<Mutex<WebDriverSession>>>>>,
+    pub webdriver_process: &'a Arc<RwLock<Option<tokio::process::Child>>>,
+    pub macax_controller: &'a Arc<RwLock<Option<g3_computer_control::MacAxController>>>,
+    /// File snapshots taken before editing tool calls
+    pub checkpoints: &'a std::sync::Mutex<crate::checkpoints::CheckpointStore>,
+}
+
+/// A tool the model can call
+#[async_trait]
+pub trait ToolHandler: Send + Sync {
+    /// Name the model uses to call the tool; must match `definition().name`
+    fn name(&self) -> &str;
+
+    /// Schema sent to providers with native tool calling
+    fn definition(&self) -> Tool;
+
+    fn permissions(&self) -> ToolPermissions;
+
+    /// The part of a call that permission rules are matched against, e.g. the
+    /// command for `shell` or the absolute path for file tools. `None` means
+    /// rules see the JSON-encoded arguments.
+    fn permission_subject(&self, _tool_call: &ToolCall) -> Option<String> {
+        None
+    }
+
+    /// Files the call will modify. The agent snapshots them before the call
+    /// runs so the edit can be reverted later.
+    fn modified_files(&self, _tool_call: &ToolCall) -> Vec<PathBuf> {
+        Vec::new()
+    }
+
+    /// Whether the tool is advertised to the model under this config.
+    /// Unavailable tools can still be executed (and report why they are disabled).
+    fn is_available(&self, _config: &Config) -> bool {
+        true
+    }
+
+    /// Run the tool. Failures the model should see are returned as `Ok("❌ ...")`;
+    /// `Err` is reserved for problems that should abort the call.
+    async fn execute(&self, tool_call: &ToolCall, ctx: &ToolContext<'_>) -> Result<String>;
+}
+
+/// Tool handlers keyed by name, in registration order
+#[derive(Clone, Default)]
+pub struct ToolRegistry {
+    handlers: Vec<Arc<dyn ToolHandler>>,
+    index: HashMap<String, usize>,
+    disabled: HashSet<String>,
+}
+
+impl ToolRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registry containing all built-in g3 tools
+    pub fn builtin() -> Self {
+        let mut registry = Self::new();
+        registry.register(Arc::new(shell::ShellTool));
+        registry.register(Arc::new(file_ops::ReadFileTool));
+        registry.register(Arc::new(file_ops::WriteFileTool));
+        registry.register(Arc::new(file_ops::StrReplaceTool));
+        registry.register(Arc::new(file_ops::UndoLastEditTool));
+        registry.register(Arc::new(misc::FinalOutputTool));
+        registry.register(Arc::new(computer::TakeScreenshotTool));
+        registry.register(Arc::new(computer::ExtractTextTool));
+        registry.register(Arc::new(todo::TodoReadTool));
+        registry.register(Arc::new(todo::TodoWriteTool));
+        registry.register(Arc::new(misc::CodeCoverageTool));
+        registry.register(Arc::new(misc::CodeSearchTool));
+        registry.register(Arc::new(misc::SemanticSearchTool::default()));
+        registry.register(Arc::new(misc::RepoMapTool));
+        registry.register(Arc::new(webdriver::WebdriverStartTool));
+        registry.register(Arc::new(webdriver::WebdriverNavigateTool));
+        registry.register(Arc::new(webdriver::WebdriverGetUrlTool));
+        registry.register(Arc::new(webdriver::WebdriverGetTitleTool));
+        registry.register(Arc::new(webdriver::WebdriverFindElementTool));
+        registry.register(Arc::new(webdriver::WebdriverFindElementsTool));
+        registry.register(Arc::new(webdriver::WebdriverClickTool));
+        registry.register(Arc::new(webdriver::WebdriverSendKeysTool));
+        registry.register(Arc::new(webdriver::WebdriverExecuteScriptTool));
+        registry.register(Arc::new(webdriver::WebdriverGetPageSourceTool));
+        registry.register(Arc::new(webdriver::WebdriverScreenshotTool));
+        registry.register(Arc::new(webdriver::WebdriverBackTool));
+        registry.register(Arc::new(webdriver::WebdriverForwardTool));
+        registry.register(Arc::new(webdriver::WebdriverRefreshTool));
+        registry.register(Arc::new(webdriver::WebdriverQuitTool));
+        registry.register(Arc::new(macax::MacaxListAppsTool));
+        registry.register(Arc::new(macax::MacaxGetFrontmostAppTool));
+        registry.register(Arc::new(macax::MacaxActivateAppTool));
+        registry.register(Arc::new(macax::MacaxPressKeyTool));
+        registry.register(Arc::new(macax::MacaxTypeTextTool));
+        registry.register(Arc::new(computer::ExtractTextWithBoxesTool));
+        registry.register(Arc::new(computer::VisionFindTextTool));
+        registry.register(Arc::new(computer::VisionClickTextTool));
+        registry.register(Arc::new(computer::VisionClickNearTextTool));
+        registry
+    }
+
+    /// Register a handler, replacing any existing tool with the same name
+    pub fn register(&mut self, handler: Arc<dyn ToolHandler>) {
+        let name = handler.name().to_string();
+        match self.index.get(&name) {
+            Some(&i) => self.handlers[i] = handler,
+            None => {
+                self.index.insert(name, self.handlers.len());
+                self.handlers.push(handler);
+            }
+        }
+    }
+
+    /// Hide a tool from the model and refuse calls to it
+    pub fn disable(&mut self, name: &str) {
+        self.disabled.insert(name.to_string());
+    }
+
+    pub fn enable(&mut self, name: &str) {
+        self.disabled.remove(name);
+    }
+
+    pub fn is_enabled(&self, name: &str) -> bool {
+        self.index.contains_key(name) && !self.disabled.contains(name)
+    }
+
+    /// Look up an enabled tool by name
+    pub fn get(&self, name: &str) -> Option<&Arc<dyn ToolHandler>> {
+        if self.disabled.contains(name) {
+            return None;
+        }
+        self.index.get(name).map(|&i| &self.handlers[i])
+    }
+
+    /// Names of all enabled tools, in registration order
+    pub fn names(&self) -> Vec<&str> {
+        self.handlers
+            .iter()
+            .map(|h| h.name())
+            .filter(|name| !self.disabled.contains(*name))
+            .collect()
+    }
+
+    /// Schemas of the enabled tools that are available under `config`
+    pub fn definitions(&self, config: &Config) -> Vec<Tool> {
+        self.handlers
+            .iter()
+            .filter(|h| !self.disabled.contains(h.name()) && h.is_available(config))
+            .map(|h| h.definition())
+            .collect()
+    }
+}
@@ -0,0 +1,118 @@
+//! Shell command execution
+
+use super::{ToolContext, ToolHandler, ToolPermissions};
+use crate::ui_writer::UiWriter;
+use crate::{shell_escape_command, ToolCall};
+use anyhow::Result;
+use async_trait::async_trait;
+use g3_providers::Tool;
+use serde_json::json;
+use tracing::debug;
+
+pub struct ShellTool;
+
+#[async_trait]
+impl ToolHandler for ShellTool {
+    fn name(&self) -> &str {
+        "shell"
+    }
+
+    fn definition(&self) -> Tool {
+        Tool {
+            name: "shell".to_string(),
+            description: "Execute shell commands".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "command": {
+                        "type": "string",
+                        "description": "The shell command to execute"
+                    }
+                },
+                "required": ["command"]
+            }),
+        }
+    }
+
+    fn permissions(&self) -> ToolPermissions {
+        ToolPermissions {
+            executes_commands: true,
+            ..Default::default()
+        }
+    }
+
+    fn permission_subject(&self, tool_call: &ToolCall) -> Option<String> {
+        tool_call
+            .args
+            .get("command")
+            .and_then(|v| v.as_str())
+            .map(str::to_string)
+    }
+
+    async fn execute(&self, tool_call: &ToolCall, ctx: &ToolContext<'_>) -> Result<String> {
+        debug!("Processing shell tool call");
+        if let Some(command) = tool_call.args.get("command") {
+            debug!("Found command parameter: {:?}", command);
+            if let Some(command_str) = command.as_str() {
+                debug!("Command string: {}", command_str);
+                // Use shell escaping to handle filenames with spaces and special characters
+                let escaped_command = shell_escape_command(command_str);
+
+                // Create a receiver for streaming output
+                struct ToolOutputReceiver<'a> {
+                    ui_writer: &'a dyn UiWriter,
+                }
+
+                impl g3_execution::OutputReceiver for ToolOutputReceiver<'_> {
+                    fn on_output_line(&self, line: &str) {
+                        self.ui_writer.update_tool_output_line(line);
+                    }
+                }
+
+                let receiver = ToolOutputReceiver {
+                    ui_writer: ctx.ui_writer,
+                };
+
+                // Without an explicit working directory, commands run in the
+                // scoped workspace (see `Project::scope`)
+                let scoped_dir = crate::project::scoped_workspace()
+                    .map(|dir| dir.to_string_lossy().into_owned());
+                let working_dir = ctx.working_dir.or(scoped_dir.as_deref());
+
+                debug!("ABOUT TO CALL execute_bash_streaming_in_dir: escaped_command='{}', working_dir={:?}", escaped_command, working_dir);
+
+                match ctx
+                    .executor
+                    .execute_bash_streaming_in_dir(&escaped_command, &receiver, working_dir)
+                    .await
+                {
+                    Ok(result) => {
+                        if result.success {
+                            Ok(if result.stdout.is_empty() {
+                                "✅ Command executed successfully".to_string()
+                            } else {
+                                result.stdout.trim().to_string()
+                            })
+                        } else {
+                            Ok(format!("❌ Command failed: {}", result.stderr.trim()))
+                        }
+                    }
+                    Err(e) => Ok(format!("❌ Execution error: {}", e)),
+                }
+            } else {
+                debug!("Command parameter is not a string: {:?}", command);
+                Ok("❌ Invalid command argument".to_string())
+            }
+        } else {
+            debug!("No command parameter found in args: {:?}", tool_call.args);
+            debug!(
+                "Available keys: {:?}",
+                tool_call
+                    .args
+                    .as_object()
+                    .map(|obj| obj.keys().collect::<Vec<_>>())
+            );
+            Ok("❌ Missing command argument".to_string())
+        }
+    }
+}
@@ -0,0 +1,235 @@
+//! TODO list persistence (todo.g3.md)
+
+use super::{ToolContext, ToolHandler, ToolPermissions};
+use crate::{get_todo_path, ToolCall};
+use anyhow::Result;
+use async_trait::async_trait;
+use g3_providers::Tool;
+use serde_json::json;
+use std::io::Write;
+use tracing::debug;
+
+pub struct TodoReadTool;
+
+#[async_trait]
+impl ToolHandler for TodoReadTool {
+    fn name(&self) -> &str {
+        "todo_read"
+    }
+
+    fn definition(&self) -> Tool {
+        Tool {
+            name: "todo_read".to_string(),
+            description: "Read your current TODO list from todo.g3.md file in the workspace directory. Shows what tasks are planned and their status. Call this at the start of multi-step tasks to check for existing plans, and during execution to review progress before updating. TODO lists persist across g3 sessions.".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {},
+                "required": []
+            }),
+        }
+    }
+
+    fn permissions(&self) -> ToolPermissions {
+        ToolPermissions {
+            reads_files: true,
+            ..Default::default()
+        }
+    }
+
+    async fn execute(&self, _tool_call: &ToolCall, ctx: &ToolContext<'_>) -> Result<String> {
+        debug!("Processing todo_read tool call");
+        // Read from todo.g3.md file (uses G3_TODO_PATH env var if set, else current dir)
+        let todo_path = get_todo_path();
+
+        if !todo_path.exists() {
+            // Also update in-memory content to stay in sync
+            let mut todo = ctx.todo_content.write().await;
+            *todo = String::new();
+            Ok("📝 TODO list is empty (no todo.g3.md file found)".to_string())
+        } else {
+            match std::fs::read_to_string(&todo_path) {
+                Ok(content) => {
+                    // Update in-memory content to stay in sync
+                    let mut todo = ctx.todo_content.write().await;
+                    *todo = content.clone();
+
+                    // Check for staleness if enabled and we have a requirements SHA
+                    if ctx.config.agent.check_todo_staleness {
+                        if let Some(req_sha) = &ctx.requirements_sha {
+                            // Parse the first line for the SHA header
+                            if let Some(first_line) = content.lines().next() {
+                                if first_line.starts_with(
+                                    "{{Based on the requirements file with SHA256:",
+                                ) {
+                                    let parts: Vec<&str> =
+                                        first_line.split("SHA256:").collect();
+                                    if parts.len() > 1 {
+                                        let todo_sha =
+                                            parts[1].trim().trim_end_matches("}}").trim();
+                                        if todo_sha != req_sha {
+                                            let warning = format!(
+                                                "⚠️ TODO list is stale! It was generated from a different requirements file.\nExpected SHA: {}\nFound SHA:    {}",
+                                                req_sha, todo_sha
+                                            );
+                                            ctx.ui_writer.print_context_status(&warning);
+
+                                            // Beep 6 times
+                                            print!("\x07\x07\x07\x07\x07\x07");
+                                            let _ = std::io::stdout().flush();
+
+                                            let options = [
+                                                "Ignore and Continue",
+                                                "Mark as Stale",
+                                                "Quit Application",
+                                            ];
+                                            let choice = ctx.ui_writer.prompt_user_choice("Requirements have changed! What would you like to do?", &options);
+
+                                            match choice {
+                                                0 => {
+                                                    // Ignore and Continue
+                                                    ctx.ui_writer.print_context_status(
+                                                        "⚠️ Ignoring staleness warning.",
+                                                    );
+                                                }
+                                                1 => {
+                                                    // Mark as Stale
+                                                    // We return a message to the agent so it knows to regenerate/fix it.
+                                                    return Ok("⚠️ TODO list is stale (requirements changed). Please regenerate the TODO list to match the new requirements.".to_string());
+                                                }
+                                                2 => {
+                                                    // Quit Application
+                                                    ctx.ui_writer.print_context_status("❌ Quitting application as requested.");
+                                                    std::process::exit(0);
+                                                }
+                                                _ => unreachable!(),
+                                            }
+                                        }
+                                    }
+                                } else {
+                                    // Header missing, but we have a SHA. Warn the user?
+                                    // For now, maybe just proceed... assuming it's an old TODO.
+                                }
+                            }
+                        }
+                    }
+
+                    if content.trim().is_empty() {
+                        Ok("📝 TODO list is empty".to_string())
+                    } else {
+                        // Print the TODO content to the console
+                        ctx.ui_writer.print_context_status("📝 TODO list:");
+                        for line in content.lines() {
+                            ctx.ui_writer.print_tool_output_line(line);
+                        }
+                        Ok(format!("📝 TODO list:\n{}", content))
+                    }
+                }
+                Err(e) => Ok(format!("❌ Failed to read TODO.md: {}", e)),
+            }
+        }
+    }
+}
+
+pub struct TodoWriteTool;
+
+#[async_trait]
+impl ToolHandler for TodoWriteTool {
+    fn name(&self) -> &str {
+        "todo_write"
+    }
+
+    fn definition(&self) -> Tool {
+        Tool {
+            name: "todo_write".to_string(),
+            description: "Create or update your TODO list in todo.g3.md file with a complete task plan. Use markdown checkboxes: - [ ] for pending, - [x] for complete. This tool replaces the entire file content, so always call todo_read first to preserve existing content. Essential for multi-step tasks. Changes persist across g3 sessions.".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "content": {
+                        "type": "string",
+                        "description": "The TODO list content to save. Use markdown checkbox format: - [ ] for incomplete tasks, - [x] for completed tasks. Support nested tasks with indentation."
+                    }
+                },
+                "required": ["content"]
+            }),
+        }
+    }
+
+    fn permissions(&self) -> ToolPermissions {
+        ToolPermissions {
+            writes_files: true,
+            ..Default::default()
+        }
+    }
+
+    async fn execute(&self, tool_call: &ToolCall, ctx: &ToolContext<'_>) -> Result<String> {
+        debug!("Processing todo_write tool call");
+        if let Some(content) = tool_call.args.get("content") {
+            if let Some(content_str) = content.as_str() {
+                let char_count = content_str.chars().count();
+                let max_chars = std::env::var("G3_TODO_MAX_CHARS")
+                    .ok()
+                    .and_then(|s| s.parse().ok())
+                    .unwrap_or(50_000);
+
+                if max_chars > 0 && char_count > max_chars {
+                    return Ok(format!(
+                        "❌ TODO list too large: {} chars (max: {})",
+                        char_count, max_chars
+                    ));
+                }
+
+                // Check if all todos are completed (all checkboxes are checked)
+                let has_incomplete = content_str.lines().any(|line| {
+                    let trimmed = line.trim();
+                    trimmed.starts_with("- [ ]")
+                });
+
+                // If all todos are complete, delete the file instead of writing
+                // EXCEPT in planner mode (G3_TODO_PATH is set) - preserve for rename to completed_todo_*.md
+                let in_planner_mode = std::env::var("G3_TODO_PATH").is_ok();
+                let todo_path = get_todo_path();
+
+                if !in_planner_mode && !has_incomplete && (content_str.contains("- [x]") || content_str.contains("- [X]")) {
+                    if todo_path.exists() {
+                        match std::fs::remove_file(&todo_path) {
+                            Ok(_) => {
+                                let mut todo = ctx.todo_content.write().await;
+                                *todo = String::new();
+                                // Show the final completed TODOs before deletion
+                                let mut result = String::from("✅ All TODOs completed! Removed todo.g3.md\n\nFinal status:\n");
+                                for line in content_str.lines() {
+                                    ctx.ui_writer.print_tool_output_line(line);
+                                    result.push_str(line);
+                                    result.push('\n');
+                                }
+                                return Ok(result);
+                            }
+                            Err(e) => return Ok(format!("❌ Failed to remove todo.g3.md: {}", e)),
+                        }
+                    }
+                }
+
+                // Write to todo.g3.md file (uses G3_TODO_PATH env var if set, else current dir)
+
+                match std::fs::write(&todo_path, content_str) {
+                    Ok(_) => {
+                        // Also update in-memory content to stay in sync
+                        let mut todo = ctx.todo_content.write().await;
+                        *todo = content_str.to_string();
+                        // Print the TODO content to the console (inside the tool frame)
+                        for line in content_str.lines() {
+                            ctx.ui_writer.print_tool_output_line(line);
+                        }
+                        Ok(format!("✅ TODO list updated ({} chars) and saved to todo.g3.md:\n{}", char_count, content_str))
+                    }
+                    Err(e) => Ok(format!("❌ Failed to write todo.g3.md: {}", e)),
+                }
+            } else {
+                Ok("❌ Invalid content argument".to_string())
+            }
+        } else {
+            Ok("❌ Missing content argument".to_string())
+        }
+    }
+}
@@ -0,0 +1,1175 @@
+//! Browser automation via WebDriver (Safari or headless Chrome)
+
+use super::{ToolContext, ToolHandler, ToolPermissions};
+use crate::{WebDriverSession, ToolCall};
+use anyhow::Result;
+use async_trait::async_trait;
+use g3_config::Config;
+use g3_computer_control::WebDriverController;
+use g3_providers::Tool;
+use serde_json::json;
+use tracing::{debug, info, warn};
+
+pub struct WebdriverStartTool;
+
+#[async_trait]
+impl ToolHandler for WebdriverStartTool {
+    fn name(&self) -> &str {
+        "webdriver_start"
+    }
+
+    fn definition(&self) -> Tool {
+        Tool {
+            name: "webdriver_start".to_string(),
+            description: "Start a Safari WebDriver session for browser automation. Must be called before any other webdriver tools. Requires Safari's 'Allow Remote Automation' to be enabled in Develop menu.".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {},
+                "required": []
+            }),
+        }
+    }
+
+    fn permissions(&self) -> ToolPermissions {
+        ToolPermissions {
+            network: true,
+            ..Default::default()
+        }
+    }
+
+    fn is_available(&self, config: &Config) -> bool {
+        config.webdriver.enabled
+    }
+
+    async fn execute(&self, _tool_call: &ToolCall, ctx: &ToolContext<'_>) -> Result<String> {
+        debug!("Processing webdriver_start tool call");
+
+        if !ctx.config.webdriver.enabled {
+            return Ok(
+                "❌ WebDriver is not enabled. Use --webdriver flag to enable.".to_string(),
+            );
+        }
+
+        // Check if session already exists
+        let session_guard = ctx.webdriver_session.read().await;
+        if session_guard.is_some() {
+            drop(session_guard);
+            return Ok("✅ WebDriver session already active".to_string());
+        }
+        drop(session_guard);
+
+        // Determine which browser to use based on config
+        use g3_config::WebDriverBrowser;
+        match &ctx.config.webdriver.browser {
+            WebDriverBrowser::Safari => {
+                // Note: Safari Remote Automation must be enabled before using WebDriver.
+                // Run this once: safaridriver --enable
+                // Or enable manually: Safari → Develop → Allow Remote Automation
+
+                let port = ctx.config.webdriver.safari_port;
+
+                let driver_result = tokio::process::Command::new("safaridriver")
+                    .arg("--port")
+                    .arg(port.to_string())
+                    .stdout(std::process::Stdio::null())
+                    .stderr(std::process::Stdio::null())
+                    .spawn();
+
+                let mut webdriver_process = match driver_result {
+                    Ok(process) => process,
+                    Err(e) => {
+                        return Ok(format!("❌ Failed to start safaridriver: {}\n\nMake sure safaridriver is installed.", e));
+                    }
+                };
+
+                // Wait for safaridriver to start up
+                tokio::time::sleep(tokio::time::Duration::from_millis(1000)).await;
+
+                // Connect to SafariDriver
+                match g3_computer_control::SafariDriver::with_port(port).await {
+                    Ok(driver) => {
+                        let session = std::sync::Arc::new(tokio::sync::Mutex::new(WebDriverSession::Safari(driver)));
+                        *ctx.webdriver_session.write().await = Some(session);
+                        *ctx.webdriver_process.write().await = Some(webdriver_process);
+
+                        Ok("✅ WebDriver session started successfully! Safari should open automatically.".to_string())
+                    }
+                    Err(e) => {
+                        let _ = webdriver_process.kill().await;
+                        Ok(format!("❌ Failed to connect to SafariDriver: {}\n\nThis might be because:\n  - Safari Remote Automation is not enabled (run: safaridriver --enable)\n  - Port {} is already in use\n  - Safari failed to start\n  - Network connectivity issue\n\nTo enable Remote Automation:\n  1. Run: safaridriver --enable (requires password, one-time setup)\n  2. Or manually: Safari → Develop → Allow Remote Automation", e, port))
+                    }
+                }
+            }
+            WebDriverBrowser::ChromeHeadless => {
+                let port = ctx.config.webdriver.chrome_port;
+
+                // Start chromedriver process
+                let driver_result = tokio::process::Command::new("chromedriver")
+                    .arg(format!("--port={}", port))
+                    .stdout(std::process::Stdio::null())
+                    .stderr(std::process::Stdio::null())
+                    .spawn();
+
+                let mut webdriver_process = match driver_result {
+                    Ok(process) => process,
+                    Err(e) => {
+                        return Ok(format!("❌ Failed to start chromedriver: {}\n\nMake sure chromedriver is installed and in your PATH.\n\nInstall with:\n  - macOS: brew install chromedriver\n  - Linux: apt install chromium-chromedriver\n  - Or download from: https://chromedriver.chromium.org/downloads", e));
+                    }
+                };
+
+                // Wait for chromedriver to be ready with retry loop
+                let max_retries = 10;
+                let mut last_error = None;
+
+                for attempt in 0..max_retries {
+                    // Wait before each attempt (200ms between retries, total max ~2s)
+                    tokio::time::sleep(tokio::time::Duration::from_millis(200)).await;
+
+                    // Try to connect to ChromeDriver in headless mode (with optional custom binary)
+                    let driver_result = match &ctx.config.webdriver.chrome_binary {
+                        Some(binary) => g3_computer_control::ChromeDriver::with_port_headless_and_binary(port, Some(binary)).await,
+                        None => g3_computer_control::ChromeDriver::with_port_headless(port).await,
+                    };
+
+                    match driver_result {
+                        Ok(driver) => {
+                            let session = std::sync::Arc::new(tokio::sync::Mutex::new(WebDriverSession::Chrome(driver)));
+                            *ctx.webdriver_session.write().await = Some(session);
+                            *ctx.webdriver_process.write().await = Some(webdriver_process);
+
+                            return Ok("✅ WebDriver session started successfully! Chrome is running in headless mode (no visible window).".to_string());
+                        }
+                        Err(e) => {
+                            last_error = Some(e);
+                            if attempt < max_retries - 1 {
+                                // Continue retrying
+                                continue;
+                            }
+                        }
+                    }
+                }
+
+                // All retries failed
+                let _ = webdriver_process.kill().await;
+                let error_msg = last_error.map(|e| e.to_string()).unwrap_or_else(|| "Unknown error".to_string());
+                Ok(format!("❌ Failed to connect to ChromeDriver after {} attempts: {}\n\nThis might be because:\n  - Chrome is not installed\n  - ChromeDriver version doesn't match Chrome version\n  - Port {} is already in use\n\nMake sure Chrome and ChromeDriver are installed and compatible.", max_retries, error_msg, port))
+            }
+        }
+    }
+}
+
+pub struct WebdriverNavigateTool;
+
+#[async_trait]
+impl ToolHandler for WebdriverNavigateTool {
+    fn name(&self) -> &str {
+        "webdriver_navigate"
+    }
+
+    fn definition(&self) -> Tool {
+        Tool {
+            name: "webdriver_navigate".to_string(),
+            description: "Navigate to a URL in the browser".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "url": {
+                        "type": "string",
+                        "description": "The URL to navigate to (must include protocol, e.g., https://)"
+                    }
+                },
+                "required": ["url"]
+            }),
+        }
+    }
+
+    fn permissions(&self) -> ToolPermissions {
+        ToolPermissions {
+            network: true,
+            ..Default::default()
+        }
+    }
+
+    fn is_available(&self, config: &Config) -> bool {
+        config.webdriver.enabled
+    }
+
+    async fn execute(&self, tool_call: &ToolCall, ctx: &ToolContext<'_>) -> Result<String> {
+        debug!("Processing webdriver_navigate tool call");
+
+        if !ctx.config.webdriver.enabled {
+            return Ok(
+                "❌ WebDriver is not enabled. Use --webdriver flag to enable.".to_string(),
+            );
+        }
+
+        let session_guard = ctx.webdriver_session.read().await;
+        let session = match session_guard.as_ref() {
+            Some(s) => s.clone(),
+            None => {
+                return Ok(
+                    "❌ No active WebDriver session. Call webdriver_start first."
+                        .to_string(),
+                )
+            }
+        };
+        drop(session_guard);
+        let url = match tool_call.args.get("url").and_then(|v| v.as_str()) {
+            Some(u) => u,
+            None => return Ok("❌ Missing url argument".to_string()),
+        };
+
+        let mut driver = session.lock().await;
+        match driver.navigate(url).await {
+            Ok(_) => Ok(format!("✅ Navigated to {}", url)),
+            Err(e) => Ok(format!("❌ Failed to navigate: {}", e)),
+        }
+    }
+}
+
+pub struct WebdriverGetUrlTool;
+
+#[async_trait]
+impl ToolHandler for WebdriverGetUrlTool {
+    fn name(&self) -> &str {
+        "webdriver_get_url"
+    }
+
+    fn definition(&self) -> Tool {
+        Tool {
+            name: "webdriver_get_url".to_string(),
+            description: "Get the current URL of the browser".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {},
+                "required": []
+            }),
+        }
+    }
+
+    fn permissions(&self) -> ToolPermissions {
+        ToolPermissions {
+            network: true,
+            ..Default::default()
+        }
+    }
+
+    fn is_available(&self, config: &Config) -> bool {
+        config.webdriver.enabled
+    }
+
+    async fn execute(&self, _tool_call: &ToolCall, ctx: &ToolContext<'_>) -> Result<String> {
+        debug!("Processing webdriver_get_url tool call");
+
+        if !ctx.config.webdriver.enabled {
+            return Ok(
+                "❌ WebDriver is not enabled. Use --webdriver flag to enable.".to_string(),
+            );
+        }
+
+        let session_guard = ctx.webdriver_session.read().await;
+        let session = match session_guard.as_ref() {
+            Some(s) => s.clone(),
+            None => {
+                return Ok(
+                    "❌ No active WebDriver session. Call webdriver_start first."
+                        .to_string(),
+                )
+            }
+        };
+
+        let driver = session.lock().await;
+        match driver.current_url().await {
+            Ok(url) => Ok(format!("Current URL: {}", url)),
+            Err(e) => Ok(format!("❌ Failed to get URL: {}", e)),
+        }
+    }
+}
+
+pub struct WebdriverGetTitleTool;
+
+#[async_trait]
+impl ToolHandler for WebdriverGetTitleTool {
+    fn name(&self) -> &str {
+        "webdriver_get_title"
+    }
+
+    fn definition(&self) -> Tool {
+        Tool {
+            name: "webdriver_get_title".to_string(),
+            description: "Get the title of the current page".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {},
+                "required": []
+            }),
+        }
+    }
+
+    fn permissions(&self) -> ToolPermissions {
+        ToolPermissions {
+            network: true,
+            ..Default::default()
+        }
+    }
+
+    fn is_available(&self, config: &Config) -> bool {
+        config.webdriver.enabled
+    }
+
+    async fn execute(&self, _tool_call: &ToolCall, ctx: &ToolContext<'_>) -> Result<String> {
+        debug!("Processing webdriver_get_title tool call");
+
+        if !ctx.config.webdriver.enabled {
+            return Ok(
+                "❌ WebDriver is not enabled. Use --webdriver flag to enable.".to_string(),
+            );
+        }
+
+        let session_guard = ctx.webdriver_session.read().await;
+        let session = match session_guard.as_ref() {
+            Some(s) => s.clone(),
+            None => {
+                return Ok(
+                    "❌ No active WebDriver session. Call webdriver_start first."
+                        .to_string(),
+                )
+            }
+        };
+
+        let driver = session.lock().await;
+        match driver.title().await {
+            Ok(title) => Ok(format!("Page title: {}", title)),
+            Err(e) => Ok(format!("❌ Failed to get title: {}", e)),
+        }
+    }
+}
+
+pub struct WebdriverFindElementTool;
+
+#[async_trait]
+impl ToolHandler for WebdriverFindElementTool {
+    fn name(&self) -> &str {
+        "webdriver_find_element"
+    }
+
+    fn definition(&self) -> Tool {
+        Tool {
+            name: "webdriver_find_element".to_string(),
+            description: "Find an element on the page by CSS selector and return its text content".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "selector": {
+                        "type": "string",
+                        "description": "CSS selector to find the element (e.g., 'h1', '.class-name', '#id')"
+                    }
+                },
+                "required": ["selector"]
+            }),
+        }
+    }
+
+    fn permissions(&self) -> ToolPermissions {
+        ToolPermissions {
+            network: true,
+            ..Default::default()
+        }
+    }
+
+    fn is_available(&self, config: &Config) -> bool {
+        config.webdriver.enabled
+    }
+
+    async fn execute(&self, tool_call: &ToolCall, ctx: &ToolContext<'_>) -> Result<String> {
+        debug!("Processing webdriver_find_element tool call");
+
+        if !ctx.config.webdriver.enabled {
+            return Ok(
+                "❌ WebDriver is not enabled. Use --webdriver flag to enable.".to_string(),
+            );
+        }
+
+        let session_guard = ctx.webdriver_session.read().await;
+        let session = match session_guard.as_ref() {
+            Some(s) => s.clone(),
+            None => {
+                return Ok(
+                    "❌ No active WebDriver session. Call webdriver_start first."
+                        .to_string(),
+                )
+            }
+        };
+
+        let selector = match tool_call.args.get("selector").and_then(|v| v.as_str()) {
+            Some(s) => s,
+            None => return Ok("❌ Missing selector argument".to_string()),
+        };
+
+        let mut driver = session.lock().await;
+        match driver.find_element(selector).await {
+            Ok(elem) => match elem.text().await {
+                Ok(text) => Ok(format!("Element text: {}", text)),
+                Err(e) => Ok(format!("❌ Failed to get element text: {}", e)),
+            },
+            Err(e) => Ok(format!("❌ Failed to find element '{}': {}", selector, e)),
+        }
+    }
+}
+
+pub struct WebdriverFindElementsTool;
+
+#[async_trait]
+impl ToolHandler for WebdriverFindElementsTool {
+    fn name(&self) -> &str {
+        "webdriver_find_elements"
+    }
+
+    fn definition(&self) -> Tool {
+        Tool {
+            name: "webdriver_find_elements".to_string(),
+            description: "Find all elements matching a CSS selector and return their text content".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "selector": {
+                        "type": "string",
+                        "description": "CSS selector to find elements"
+                    }
+                },
+                "required": ["selector"]
+            }),
+        }
+    }
+
+    fn permissions(&self) -> ToolPermissions {
+        ToolPermissions {
+            network: true,
+            ..Default::default()
+        }
+    }
+
+    fn is_available(&self, config: &Config) -> bool {
+        config.webdriver.enabled
+    }
+
+    async fn execute(&self, tool_call: &ToolCall, ctx: &ToolContext<'_>) -> Result<String> {
+        debug!("Processing webdriver_find_elements tool call");
+
+        if !ctx.config.webdriver.enabled {
+            return Ok(
+                "❌ WebDriver is not enabled. Use --webdriver flag to enable.".to_string(),
+            );
+        }
+
+        let session_guard = ctx.webdriver_session.read().await;
+        let session = match session_guard.as_ref() {
+            Some(s) => s.clone(),
+            None => {
+                return Ok(
+                    "❌ No active WebDriver session. Call webdriver_start first."
+                        .to_string(),
+                )
+            }
+        };
+
+        let selector = match tool_call.args.get("selector").and_then(|v| v.as_str()) {
+            Some(s) => s,
+            None => return Ok("❌ Missing selector argument".to_string()),
+        };
+
+        let mut driver = session.lock().await;
+        match driver.find_elements(selector).await {
+            Ok(elements) => {
+                let mut results = Vec::new();
+                for (i, elem) in elements.iter().enumerate() {
+                    match elem.text().await {
+                        Ok(text) => results.push(format!("[{}]: {}", i, text)),
+                        Err(_) => results.push(format!("[{}]: <error getting text>", i)),
+                    }
+                }
+                Ok(format!(
+                    "Found {} elements:\n{}",
+                    results.len(),
+                    results.join("\n")
+                ))
+            }
+            Err(e) => Ok(format!("❌ Failed to find elements '{}': {}", selector, e)),
+        }
+    }
+}
+
+pub struct WebdriverClickTool;
+
+#[async_trait]
+impl ToolHandler for WebdriverClickTool {
+    fn name(&self) -> &str {
+        "webdriver_click"
+    }
+
+    fn definition(&self) -> Tool {
+        Tool {
+            name: "webdriver_click".to_string(),
+            description: "Click an element on the page".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "selector": {
+                        "type": "string",
+                        "description": "CSS selector for the element to click"
+                    }
+                },
+                "required": ["selector"]
+            }),
+        }
+    }
+
+    fn permissions(&self) -> ToolPermissions {
+        ToolPermissions {
+            network: true,
+            ..Default::default()
+        }
+    }
+
+    fn is_available(&self, config: &Config) -> bool {
+        config.webdriver.enabled
+    }
+
+    async fn execute(&self, tool_call: &ToolCall, ctx: &ToolContext<'_>) -> Result<String> {
+        debug!("Processing webdriver_click tool call");
+
+        if !ctx.config.webdriver.enabled {
+            return Ok(
+                "❌ WebDriver is not enabled. Use --webdriver flag to enable.".to_string(),
+            );
+        }
+
+        let session_guard = ctx.webdriver_session.read().await;
+        let session = match session_guard.as_ref() {
+            Some(s) => s.clone(),
+            None => {
+                return Ok(
+                    "❌ No active WebDriver session. Call webdriver_start first."
+                        .to_string(),
+                )
+            }
+        };
+
+        let selector = match tool_call.args.get("selector").and_then(|v| v.as_str()) {
+            Some(s) => s,
+            None => return Ok("❌ Missing selector argument".to_string()),
+        };
+
+        let mut driver = session.lock().await;
+        match driver.find_element(selector).await {
+            Ok(mut elem) => match elem.click().await {
+                Ok(_) => Ok(format!("✅ Clicked element '{}'", selector)),
+                Err(e) => Ok(format!("❌ Failed to click element: {}", e)),
+            },
+            Err(e) => Ok(format!("❌ Failed to find element '{}': {}", selector, e)),
+        }
+    }
+}
+
+pub struct WebdriverSendKeysTool;
+
+#[async_trait]
+impl ToolHandler for WebdriverSendKeysTool {
+    fn name(&self) -> &str {
+        "webdriver_send_keys"
+    }
+
+    fn definition(&self) -> Tool {
+        Tool {
+            name: "webdriver_send_keys".to_string(),
+            description: "Type text into an input element".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "selector": {
+                        "type": "string",
+                        "description": "CSS selector for the input element"
+                    },
+                    "text": {
+                        "type": "string",
+                        "description": "Text to type into the element"
+                    },
+                    "clear_first": {
+                        "type": "boolean",
+                        "description": "Whether to clear the element before typing (default: true)"
+                    }
+                },
+                "required": ["selector", "text"]
+            }),
+        }
+    }
+
+    fn permissions(&self) -> ToolPermissions {
+        ToolPermissions {
+            network: true,
+            ..Default::default()
+        }
+    }
+
+    fn is_available(&self, config: &Config) -> bool {
+        config.webdriver.enabled
+    }
+
+    async fn execute(&self, tool_call: &ToolCall, ctx: &ToolContext<'_>) -> Result<String> {
+        debug!("Processing webdriver_send_keys tool call");
+
+        if !ctx.config.webdriver.enabled {
+            return Ok(
+                "❌ WebDriver is not enabled. Use --webdriver flag to enable.".to_string(),
+            );
+        }
+
+        let session_guard = ctx.webdriver_session.read().await;
+        let session = match session_guard.as_ref() {
+            Some(s) => s.clone(),
+            None => {
+                return Ok(
+                    "❌ No active WebDriver session. Call webdriver_start first."
+                        .to_string(),
+                )
+            }
+        };
+
+        let selector = match tool_call.args.get("selector").and_then(|v| v.as_str()) {
+            Some(s) => s,
+            None => return Ok("❌ Missing selector argument".to_string()),
+        };
+
+        let text = match tool_call.args.get("text").and_then(|v| v.as_str()) {
+            Some(t) => t,
+            None => return Ok("❌ Missing text argument".to_string()),
+        };
+
+        let clear_first = tool_call
+            .args
+            .get("clear_first")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(true);
+
+        let mut driver = session.lock().await;
+        match driver.find_element(selector).await {
+            Ok(mut elem) => {
+                if clear_first {
+                    if let Err(e) = elem.clear().await {
+                        return Ok(format!("❌ Failed to clear element: {}", e));
+                    }
+                }
+                match elem.send_keys(text).await {
+                    Ok(_) => Ok(format!("✅ Sent keys to element '{}'", selector)),
+                    Err(e) => Ok(format!("❌ Failed to send keys: {}", e)),
+                }
+            }
+            Err(e) => Ok(format!("❌ Failed to find element '{}': {}", selector, e)),
+        }
+    }
+}
+
+pub struct WebdriverExecuteScriptTool;
+
+#[async_trait]
+impl ToolHandler for WebdriverExecuteScriptTool {
+    fn name(&self) -> &str {
+        "webdriver_execute_script"
+    }
+
+    fn definition(&self) -> Tool {
+        Tool {
+            name: "webdriver_execute_script".to_string(),
+            description: "Execute JavaScript code in the browser and return the result".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "script": {
+                        "type": "string",
+                        "description": "JavaScript code to execute (use 'return' to return a value)"
+                    }
+                },
+                "required": ["script"]
+            }),
+        }
+    }
+
+    fn permissions(&self) -> ToolPermissions {
+        ToolPermissions {
+            network: true,
+            ..Default::default()
+        }
+    }
+
+    fn is_available(&self, config: &Config) -> bool {
+        config.webdriver.enabled
+    }
+
+    async fn execute(&self, tool_call: &ToolCall, ctx: &ToolContext<'_>) -> Result<String> {
+        debug!("Processing webdriver_execute_script tool call");
+
+        if !ctx.config.webdriver.enabled {
+            return Ok(
+                "❌ WebDriver is not enabled. Use --webdriver flag to enable.".to_string(),
+            );
+        }
+
+        let session_guard = ctx.webdriver_session.read().await;
+        let session = match session_guard.as_ref() {
+            Some(s) => s.clone(),
+            None => {
+                return Ok(
+                    "❌ No active WebDriver session. Call webdriver_start first."
+                        .to_string(),
+                )
+            }
+        };
+
+        let script = match tool_call.args.get("script").and_then(|v| v.as_str()) {
+            Some(s) => s,
+            None => return Ok("❌ Missing script argument".to_string()),
+        };
+
+        let mut driver = session.lock().await;
+        match driver.execute_script(script, vec![]).await {
+            Ok(result) => Ok(format!("Script result: {:?}", result)),
+            Err(e) => Ok(format!("❌ Failed to execute script: {}", e)),
+        }
+    }
+}
+
+pub struct WebdriverGetPageSourceTool;
+
+#[async_trait]
+impl ToolHandler for WebdriverGetPageSourceTool {
+    fn name(&self) -> &str {
+        "webdriver_get_page_source"
+    }
+
+    fn definition(&self) -> Tool {
+        Tool {
+            name: "webdriver_get_page_source".to_string(),
+            description: "Get the rendered HTML source of the current page. Returns the current DOM state after JavaScript execution.".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "max_length": {
+                        "type": "integer",
+                        "description": "Maximum length of HTML to return (default: 10000, use 0 for no truncation)"
+                    },
+                    "save_to_file": {
+                        "type": "string",
+                        "description": "Optional file path to save the HTML instead of returning it inline"
+                    }
+                },
+                "required": []
+            }),
+        }
+    }
+
+    fn permissions(&self) -> ToolPermissions {
+        ToolPermissions {
+            network: true,
+            ..Default::default()
+        }
+    }
+
+    fn is_available(&self, config: &Config) -> bool {
+        config.webdriver.enabled
+    }
+
+    async fn execute(&self, tool_call: &ToolCall, ctx: &ToolContext<'_>) -> Result<String> {
+        debug!("Processing webdriver_get_page_source tool call");
+
+        if !ctx.config.webdriver.enabled {
+            return Ok(
+                "❌ WebDriver is not enabled. Use --webdriver flag to enable.".to_string(),
+            );
+        }
+
+        // Extract optional parameters
+        let max_length = tool_call
+            .args
+            .get("max_length")
+            .and_then(|v| v.as_u64())
+            .map(|n| n as usize)
+            .unwrap_or(10000);
+
+        let save_to_file = tool_call
+            .args
+            .get("save_to_file")
+            .and_then(|v| v.as_str());
+
+        let session_guard = ctx.webdriver_session.read().await;
+        let session = match session_guard.as_ref() {
+            Some(s) => s.clone(),
+            None => {
+                return Ok(
+                    "❌ No active WebDriver session. Call webdriver_start first."
+                        .to_string(),
+                )
+            }
+        };
+
+        let driver = session.lock().await;
+        match driver.page_source().await {
+            Ok(source) => {
+                // If save_to_file is specified, write to file
+                if let Some(file_path) = save_to_file {
+                    let expanded_path = shellexpand::tilde(file_path);
+                    let path_str = expanded_path.as_ref();
+
+                    // Create parent directories if needed
+                    if let Some(parent) = std::path::Path::new(path_str).parent() {
+                        if let Err(e) = std::fs::create_dir_all(parent) {
+                            return Ok(format!("❌ Failed to create directories: {}", e));
+                        }
+                    }
+
+                    match std::fs::write(path_str, &source) {
+                        Ok(_) => Ok(format!(
+                            "✅ Page source ({} chars) saved to: {}",
+                            source.len(),
+                            path_str
+                        )),
+                        Err(e) => Ok(format!("❌ Failed to write file: {}", e)),
+                    }
+                } else if max_length > 0 && source.len() > max_length {
+                    // Truncate if max_length is set and source exceeds it
+                    Ok(format!(
+                        "Page source ({} chars, truncated to {}):\n{}...",
+                        source.len(),
+                        max_length,
+                        &source[..max_length]
+                    ))
+                } else {
+                    // Return full source
+                    Ok(format!("Page source ({} chars):\n{}", source.len(), source))
+                }
+            }
+            Err(e) => Ok(format!("❌ Failed to get page source: {}", e)),
+        }
+    }
+}
+
+pub struct WebdriverScreenshotTool;
+
+#[async_trait]
+impl ToolHandler for WebdriverScreenshotTool {
+    fn name(&self) -> &str {
+        "webdriver_screenshot"
+    }
+
+    fn definition(&self) -> Tool {
+        Tool {
+            name: "webdriver_screenshot".to_string(),
+            description: "Take a screenshot of the browser window".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "path": {
+                        "type": "string",
+                        "description": "Path where to save the screenshot (e.g., '/tmp/screenshot.png')"
+                    }
+                },
+                "required": ["path"]
+            }),
+        }
+    }
+
+    fn permissions(&self) -> ToolPermissions {
+        ToolPermissions {
+            network: true,
+            ..Default::default()
+        }
+    }
+
+    fn is_available(&self, config: &Config) -> bool {
+        config.webdriver.enabled
+    }
+
+    async fn execute(&self, tool_call: &ToolCall, ctx: &ToolContext<'_>) -> Result<String> {
+        debug!("Processing webdriver_screenshot tool call");
+
+        if !ctx.config.webdriver.enabled {
+            return Ok(
+                "❌ WebDriver is not enabled. Use --webdriver flag to enable.".to_string(),
+            );
+        }
+
+        let session_guard = ctx.webdriver_session.read().await;
+        let session = match session_guard.as_ref() {
+            Some(s) => s.clone(),
+            None => {
+                return Ok(
+                    "❌ No active WebDriver session. Call webdriver_start first."
+                        .to_string(),
+                )
+            }
+        };
+
+        let path = match tool_call.args.get("path").and_then(|v| v.as_str()) {
+            Some(p) => p,
+            None => return Ok("❌ Missing path argument".to_string()),
+        };
+
+        let mut driver = session.lock().await;
+        match driver.screenshot(path).await {
+            Ok(_) => Ok(format!("✅ Screenshot saved to {}", path)),
+            Err(e) => Ok(format!("❌ Failed to take screenshot: {}", e)),
+        }
+    }
+}
+
+pub struct WebdriverBackTool;
+
+#[async_trait]
+impl ToolHandler for WebdriverBackTool {
+    fn name(&self) -> &str {
+        "webdriver_back"
+    }
+
+    fn definition(&self) -> Tool {
+        Tool {
+            name: "webdriver_back".to_string(),
+            description: "Navigate back in browser history".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {},
+                "required": []
+            }),
+        }
+    }
+
+    fn permissions(&self) -> ToolPermissions {
+        ToolPermissions {
+            network: true,
+            ..Default::default()
+        }
+    }
+
+    fn is_available(&self, config: &Config) -> bool {
+        config.webdriver.enabled
+    }
+
+    async fn execute(&self, _tool_call: &ToolCall, ctx: &ToolContext<'_>) -> Result<String> {
+        debug!("Processing webdriver_back tool call");
+
+        if !ctx.config.webdriver.enabled {
+            return Ok(
+                "❌ WebDriver is not enabled. Use --webdriver flag to enable.".to_string(),
+            );
+        }
+
+        let session_guard = ctx.webdriver_session.read().await;
+        let session = match session_guard.as_ref() {
+            Some(s) => s.clone(),
+            None => {
+                return Ok(
+                    "❌ No active WebDriver session. Call webdriver_start first."
+                        .to_string(),
+                )
+            }
+        };
+
+        let mut driver = session.lock().await;
+        match driver.back().await {
+            Ok(_) => Ok("✅ Navigated back".to_string()),
+            Err(e) => Ok(format!("❌ Failed to navigate back: {}", e)),
+        }
+    }
+}
+
+pub struct WebdriverForwardTool;
+
+#[async_trait]
+impl ToolHandler for WebdriverForwardTool {
+    fn name(&self) -> &str {
+        "webdriver_forward"
+    }
+
+    fn definition(&self) -> Tool {
+        Tool {
+            name: "webdriver_forward".to_string(),
+            description: "Navigate forward in browser history".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {},
+                "required": []
+            }),
+        }
+    }
+
+    fn permissions(&self) -> ToolPermissions {
+        ToolPermissions {
+            network: true,
+            ..Default::default()
+        }
+    }
+
+    fn is_available(&self, config: &Config) -> bool {
+        config.webdriver.enabled
+    }
+
+    async fn execute(&self, _tool_call: &ToolCall, ctx: &ToolContext<'_>) -> Result<String> {
+        debug!("Processing webdriver_forward tool call");
+
+        if !ctx.config.webdriver.enabled {
+            return Ok(
+                "❌ WebDriver is not enabled. Use --webdriver flag to enable.".to_string(),
+            );
+        }
+
+        let session_guard = ctx.webdriver_session.read().await;
+        let session = match session_guard.as_ref() {
+            Some(s) => s.clone(),
+            None => {
+                return Ok(
+                    "❌ No active WebDriver session. Call webdriver_start first."
+                        .to_string(),
+                )
+            }
+        };
+
+        let mut driver = session.lock().await;
+        match driver.forward().await {
+            Ok(_) => Ok("✅ Navigated forward".to_string()),
+            Err(e) => Ok(format!("❌ Failed to navigate forward: {}", e)),
+        }
+    }
+}
+
+pub struct WebdriverRefreshTool;
+
+#[async_trait]
+impl ToolHandler for WebdriverRefreshTool {
+    fn name(&self) -> &str {
+        "webdriver_refresh"
+    }
+
+    fn definition(&self) -> Tool {
+        Tool {
+            name: "webdriver_refresh".to_string(),
+            description: "Refresh the current page".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {},
+                "required": []
+            }),
+        }
+    }
+
+    fn permissions(&self) -> ToolPermissions {
+        ToolPermissions {
+            network: true,
+            ..Default::default()
+        }
+    }
+
+    fn is_available(&self, config: &Config) -> bool {
+        config.webdriver.enabled
+    }
+
+    async fn execute(&self, _tool_call: &ToolCall, ctx: &ToolContext<'_>) -> Result<String> {
+        debug!("Processing webdriver_refresh tool call");
+
+        if !ctx.config.webdriver.enabled {
+            return Ok(
+                "❌ WebDriver is not enabled. Use --webdriver flag to enable.".to_string(),
+            );
+        }
+
+        let session_guard = ctx.webdriver_session.read().await;
+        let session = match session_guard.as_ref() {
+            Some(s) => s.clone(),
+            None => {
+                return Ok(
+                    "❌ No active WebDriver session. Call webdriver_start first."
+                        .to_string(),
+                )
+            }
+        };
+
+        let mut driver = session.lock().await;
+        match driver.refresh().await {
+            Ok(_) => Ok("✅ Page refreshed".to_string()),
+            Err(e) => Ok(format!("❌ Failed to refresh page: {}", e)),
+        }
+    }
+}
+
+pub struct WebdriverQuitTool;
+
+#[async_trait]
+impl ToolHandler for WebdriverQuitTool {
+    fn name(&self) -> &str {
+        "webdriver_quit"
+    }
+
+    fn definition(&self) -> Tool {
+        Tool {
+            name: "webdriver_quit".to_string(),
+            description: "Close the browser and end the WebDriver session".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {},
+                "required": []
+            }),
+        }
+    }
+
+    fn permissions(&self) -> ToolPermissions {
+        ToolPermissions {
+            network: true,
+            ..Default::default()
+        }
+    }
+
+    fn is_available(&self, config: &Config) -> bool {
+        config.webdriver.enabled
+    }
+
+    async fn execute(&self, _tool_call: &ToolCall, ctx: &ToolContext<'_>) -> Result<String> {
+        debug!("Processing webdriver_quit tool call");
+
+        if !ctx.config.webdriver.enabled {
+            return Ok(
+                "❌ WebDriver is not enabled. Use --webdriver flag to enable.".to_string(),
+            );
+        }
+
+        // Take the session
+        let session = match ctx.webdriver_session.write().await.take() {
+            Some(s) => s.clone(),
+            None => return Ok("❌ No active WebDriver session.".to_string()),
+        };
+
+        // Quit the WebDriver session
+        match std::sync::Arc::try_unwrap(session) {
+            Ok(mutex) => {
+                let driver = mutex.into_inner();
+                match driver.quit().await {
+                    Ok(_) => {
+                        info!("WebDriver session closed successfully");
+
+                        // Kill the safaridriver process
+                        if let Some(mut process) =
+                            ctx.webdriver_process.write().await.take()
+                        {
+                            if let Err(e) = process.kill().await {
+                                warn!("Failed to kill safaridriver process: {}", e);
+                            } else {
+                                info!("Safaridriver process terminated");
+                            }
+                        }
+
+                        Ok("✅ WebDriver session closed and safaridriver stopped"
+                            .to_string())
+                    }
+                    Err(e) => Ok(format!("❌ Failed to quit WebDriver: {}", e)),
+                }
+            }
+            Err(_) => Ok("❌ Cannot quit: WebDriver session is still in use".to_string()),
+        }
+    }
+}
@@ -1,3 +1,5 @@
+use std::time::Duration;
+
 /// Interface for UI output operations
 /// This trait abstracts all UI operations to allow different implementations
 /// (console, TUI, web, etc.) without coupling the core logic to specific output methods.
@@ -41,6 +43,10 @@ pub trait UiWriter: Send + Sync {
     /// Print tool execution timing
     fn print_tool_timing(&self, duration_str: &str);
 
+    /// Report how a tool call ended, before its timing is printed
+    /// Default is a no-op; the console output already shows errors inline
+    fn print_tool_result(&self, _tool_name: &str, _duration: Duration, _success: bool) {}
+
     /// Print the agent prompt indicator
     fn print_agent_prompt(&self);
 
@@ -59,6 +65,12 @@ pub trait UiWriter: Send + Sync {
         false
     }
 
+    /// Returns true if a person is present to answer prompts
+    /// Default is false (prompts are answered automatically)
+    fn is_interactive(&self) -> bool {
+        false
+    }
+
     /// Prompt the user for a yes/no confirmation
     fn prompt_user_yes_no(&self, message: &str) -> bool;
 
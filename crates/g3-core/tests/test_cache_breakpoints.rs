@@ -0,0 +1,107 @@
+use g3_core::ContextWindow;
+use g3_providers::{CacheControl, Message, MessageRole};
+
+fn big_tool_result(i: usize) -> Message {
+    Message::new(
+        MessageRole::User,
+        format!("Tool result: {} {}", i, "x".repeat(1000)),
+    )
+}
+
+fn tool_call() -> Message {
+    Message::new(
+        MessageRole::Assistant,
+        r#"{"tool": "shell", "args": {"command": "ls"}}"#.to_string(),
+    )
+}
+
+/// System prompt plus `pairs` tool calls with large results
+fn conversation(pairs: usize) -> ContextWindow {
+    let mut context = ContextWindow::new(1_000_000);
+    context.add_message(Message::new(MessageRole::System, "You are G3".to_string()));
+    for i in 0..pairs {
+        context.add_message(tool_call());
+        context.add_message(big_tool_result(i));
+    }
+    context
+}
+
+#[test]
+fn test_breakpoints_follow_the_stable_prefix_and_the_newest_turns() {
+    let mut context = ContextWindow::new(10000);
+    context.add_message(Message::new(MessageRole::System, "You are G3".to_string()));
+    context.add_message(Message::new(
+        MessageRole::System,
+        "Project README".to_string(),
+    ));
+    context.add_message(Message::new(
+        MessageRole::User,
+        "Task: list files".to_string(),
+    ));
+    context.add_message(tool_call());
+    context.add_message(Message::new(
+        MessageRole::User,
+        "Tool result: a b".to_string(),
+    ));
+    context.add_message(tool_call());
+    context.add_message(Message::new(
+        MessageRole::User,
+        "Tool result: c".to_string(),
+    ));
+    assert_eq!(context.cache_breakpoints(), vec![1, 4, 6]);
+
+    let mut messages = context.conversation_history.clone();
+    context.apply_cache_breakpoints(&mut messages, Some(&CacheControl::one_hour()));
+    let marked: Vec<usize> = (0..messages.len())
+        .filter(|&i| messages[i].cache_control.is_some())
+        .collect();
+    assert_eq!(marked, vec![1, 4, 6]);
+    assert_eq!(
+        messages[6].cache_control.as_ref().unwrap().ttl.as_deref(),
+        Some("1h")
+    );
+
+    context.apply_cache_breakpoints(&mut messages, None);
+    assert!(messages.iter().all(|m| m.cache_control.is_none()));
+
+    // After compaction the summary gets its own breakpoint
+    context.reset_with_summary("Listed files".to_string(), Some("Continue".to_string()));
+    assert_eq!(context.conversation_history.len(), 4);
+    assert_eq!(context.cache_breakpoints(), vec![1, 2, 3]);
+}
+
+#[test]
+fn test_thinning_prefers_messages_after_the_cache_anchor() {
+    let mut context = conversation(12);
+    context.used_tokens = 500_000;
+    context.thin_context();
+
+    // The first pass covered messages 0..8 and anchors the cache there
+    assert!(context.conversation_history[6]
+        .content
+        .starts_with("Tool result saved to"));
+    let newest = context.conversation_history.len() - 1;
+    assert_eq!(context.cache_breakpoints(), vec![0, 7, newest - 2, newest]);
+
+    // A large result inside the cached prefix is left alone while there is
+    // something to thin after it
+    context.conversation_history[2] = big_tool_result(99);
+    for i in 0..12 {
+        context.add_message(tool_call());
+        context.add_message(big_tool_result(i));
+    }
+    context.used_tokens = 600_000;
+    context.thin_context();
+    assert!(context.conversation_history[2].content.len() > 1000);
+    assert!(context.conversation_history[14]
+        .content
+        .starts_with("Tool result saved to"));
+    assert!(context.cache_breakpoints().contains(&15));
+
+    // With nothing left after the anchor, thinning falls back to the prefix
+    context.used_tokens = 700_000;
+    context.thin_context();
+    assert!(context.conversation_history[2]
+        .content
+        .starts_with("Tool result saved to"));
+}
@@ -0,0 +1,166 @@
+#[path = "../../g3-providers/tests/common/mod.rs"]
+mod common;
+
+use common::{mock_server_with, Response};
+use g3_config::{AnthropicConfig, Config, ModelPricing, OpenAIConfig};
+use g3_core::cost::BudgetExceeded;
+use g3_core::session::SessionLog;
+use g3_core::ui_writer::NullUiWriter;
+use g3_core::Agent;
+use serial_test::serial;
+use tempfile::TempDir;
+
+/// Serve an OpenAI stream that reports 1M prompt tokens (200k cached) and 100k completion tokens
+async fn mock_openai() -> String {
+    let (base_url, _) = mock_server_with(|_| {
+        Response::sse(&[
+            r#"{"choices":[{"index":0,"delta":{"content":"Done"}}]}"#,
+            r#"{"choices":[{"index":0,"delta":{},"finish_reason":"stop"}]}"#,
+            r#"{"choices":[],"usage":{"prompt_tokens":1000000,"completion_tokens":100000,"total_tokens":1100000,"prompt_tokens_details":{"cached_tokens":200000}}}"#,
+        ])
+    })
+    .await;
+    base_url
+}
+
+/// Serve an Anthropic stream whose output count only arrives on `message_delta`:
+/// 1M input tokens and 100k output tokens
+async fn mock_anthropic() -> String {
+    let (base_url, _) = mock_server_with(|_| {
+        let events = [
+            r#"{"type":"message_start","message":{"usage":{"input_tokens":1000000,"output_tokens":1}}}"#,
+            r#"{"type":"content_block_start","index":0,"content_block":{"type":"text","text":""}}"#,
+            r#"{"type":"content_block_delta","index":0,"delta":{"type":"text_delta","text":"Done"}}"#,
+            r#"{"type":"content_block_stop","index":0}"#,
+            r#"{"type":"message_delta","delta":{"stop_reason":"end_turn"},"usage":{"output_tokens":100000}}"#,
+            r#"{"type":"message_stop"}"#,
+        ];
+        let body: String = events
+            .iter()
+            .map(|event| format!("data: {}\n\n", event))
+            .collect();
+        Response::raw("text/event-stream", body)
+    })
+    .await;
+    base_url
+}
+
+fn test_pricing() -> ModelPricing {
+    ModelPricing {
+        input_per_mtok: 2.0,
+        output_per_mtok: 10.0,
+        cached_input_per_mtok: Some(0.5),
+        cache_write_per_mtok: None,
+    }
+}
+
+async fn agent_with_pricing(max_cost_usd: Option<f64>) -> Agent<NullUiWriter> {
+    let mut config = Config::default();
+    config.providers.openai.insert(
+        "default".to_string(),
+        OpenAIConfig {
+            api_key: "test-key".to_string(),
+            model: "test-model".to_string(),
+            base_url: Some(mock_openai().await),
+            max_tokens: Some(1024),
+            temperature: None,
+        },
+    );
+    config.providers.default_provider = "openai.default".to_string();
+    config.agent.max_context_length = Some(128_000);
+    config
+        .pricing
+        .insert("test-model".to_string(), test_pricing());
+    config.budget.max_cost_usd = max_cost_usd;
+
+    Agent::new(config, NullUiWriter).await.unwrap()
+}
+
+#[tokio::test]
+#[serial]
+async fn test_cost_is_tracked_and_saved() {
+    let temp_dir = TempDir::new().unwrap();
+    std::env::set_current_dir(temp_dir.path()).unwrap();
+
+    let mut agent = agent_with_pricing(None).await;
+    agent.execute_task("Say hello", None, false).await.unwrap();
+
+    // 800k uncached at $2, 200k cached at $0.50, 100k out at $10
+    let expected = 1.6 + 0.1 + 1.0;
+    assert!(
+        (agent.cost().total_usd - expected).abs() < 1e-9,
+        "{:?}",
+        agent.cost()
+    );
+    assert_eq!(agent.cost().cache_read_tokens, 200_000);
+    assert!(agent.get_stats().contains("💰 Cost"));
+
+    let session_id = agent.get_session_id().unwrap().to_string();
+    let log = SessionLog::load(&session_id).unwrap();
+    assert_eq!(log.cost, *agent.cost());
+}
+
+#[tokio::test]
+#[serial]
+async fn test_streamed_anthropic_output_is_priced() {
+    let temp_dir = TempDir::new().unwrap();
+    std::env::set_current_dir(temp_dir.path()).unwrap();
+
+    let mut config = Config::default();
+    config.providers.anthropic.insert(
+        "default".to_string(),
+        AnthropicConfig {
+            api_key: "test-key".to_string(),
+            model: "test-model".to_string(),
+            base_url: Some(mock_anthropic().await),
+            max_tokens: Some(1024),
+            temperature: None,
+            cache_config: None,
+            enable_1m_context: None,
+            thinking_budget_tokens: None,
+        },
+    );
+    config.providers.default_provider = "anthropic.default".to_string();
+    config.agent.max_context_length = Some(128_000);
+    config
+        .pricing
+        .insert("test-model".to_string(), test_pricing());
+
+    let mut agent = Agent::new(config, NullUiWriter).await.unwrap();
+    agent.execute_task("Say hello", None, false).await.unwrap();
+
+    // 1M in at $2 and 100k out at $10; the output count comes from message_delta
+    assert_eq!(agent.cost().completion_tokens, 100_000);
+    assert!(
+        (agent.cost().total_usd - 3.0).abs() < 1e-9,
+        "{:?}",
+        agent.cost()
+    );
+}
+
+#[tokio::test]
+#[serial]
+async fn test_budget_stops_requests() {
+    let temp_dir = TempDir::new().unwrap();
+    std::env::set_current_dir(temp_dir.path()).unwrap();
+
+    let mut agent = agent_with_pricing(Some(2.0)).await;
+    // The first request fits the budget but overspends it
+    agent.execute_task("Say hello", None, false).await.unwrap();
+
+    let err = agent
+        .execute_task("Say hello again", None, false)
+        .await
+        .unwrap_err();
+    let exceeded = err
+        .downcast_ref::<BudgetExceeded>()
+        .unwrap_or_else(|| panic!("unexpected error: {}", err));
+    assert_eq!(exceeded.limit_usd, 2.0);
+
+    // Raising the limit lets the session continue
+    agent.set_cost_limit(Some(10.0));
+    agent
+        .execute_task("Say hello again", None, false)
+        .await
+        .unwrap();
+}
@@ -0,0 +1,82 @@
+use g3_core::ui_writer::NullUiWriter;
+use g3_core::{Agent, ToolCall};
+use serde_json::json;
+use serial_test::serial;
+use std::fs;
+use tempfile::TempDir;
+
+async fn create_test_agent(temp_dir: &TempDir) -> Agent<NullUiWriter> {
+    std::env::set_current_dir(temp_dir.path()).unwrap();
+    Agent::new(g3_config::Config::default(), NullUiWriter)
+        .await
+        .unwrap()
+}
+
+fn write_file(content: &str) -> ToolCall {
+    ToolCall {
+        tool: "write_file".to_string(),
+        args: json!({ "file_path": "main.rs", "content": content }),
+    }
+}
+
+fn undo() -> ToolCall {
+    ToolCall {
+        tool: "undo_last_edit".to_string(),
+        args: json!({}),
+    }
+}
+
+#[tokio::test]
+#[serial]
+async fn test_undo_tool_steps_back_through_edits() {
+    let temp_dir = TempDir::new().unwrap();
+    let mut agent = create_test_agent(&temp_dir).await;
+    let path = temp_dir.path().join("main.rs");
+
+    agent.execute_tool(&write_file("fn main() {}\n")).await.unwrap();
+    let str_replace = ToolCall {
+        tool: "str_replace".to_string(),
+        args: json!({
+            "file_path": "main.rs",
+            "diff": "--- old\n-fn main() {}\n+++ new\n+fn main() { run(); }\n"
+        }),
+    };
+    let result = agent.execute_tool(&str_replace).await.unwrap();
+    assert!(result.starts_with("✅"), "unexpected result: {}", result);
+    assert_eq!(fs::read_to_string(&path).unwrap(), "fn main() { run(); }\n");
+
+    let result = agent.execute_tool(&undo()).await.unwrap();
+    assert!(result.starts_with("✅ Undid str_replace"), "unexpected result: {}", result);
+    assert_eq!(fs::read_to_string(&path).unwrap(), "fn main() {}\n");
+
+    // Undoing the write that created the file removes it
+    let result = agent.execute_tool(&undo()).await.unwrap();
+    assert!(result.contains("removed"), "unexpected result: {}", result);
+    assert!(!path.exists());
+
+    let result = agent.execute_tool(&undo()).await.unwrap();
+    assert_eq!(result, "❌ No edits to undo");
+}
+
+#[tokio::test]
+#[serial]
+async fn test_failed_edit_leaves_no_checkpoint() {
+    let temp_dir = TempDir::new().unwrap();
+    let mut agent = create_test_agent(&temp_dir).await;
+    let path = temp_dir.path().join("main.rs");
+
+    agent.execute_tool(&write_file("one\n")).await.unwrap();
+    agent.execute_tool(&write_file("two\n")).await.unwrap();
+
+    let bad_replace = ToolCall {
+        tool: "str_replace".to_string(),
+        args: json!({ "file_path": "main.rs", "diff": "--- old\n-missing\n+++ new\n+text\n" }),
+    };
+    let result = agent.execute_tool(&bad_replace).await.unwrap();
+    assert!(result.starts_with("❌"), "unexpected result: {}", result);
+
+    // /undo skips the failed call and restores the previous write
+    let undone = agent.undo_last_edit().unwrap().unwrap();
+    assert_eq!(undone.tool, "write_file");
+    assert_eq!(fs::read_to_string(&path).unwrap(), "one\n");
+}
@@ -0,0 +1,142 @@
+use g3_config::{PermissionAction, PermissionRule, PermissionsConfig};
+use g3_core::permissions::{resolve_path, PermissionPolicy};
+use g3_core::ui_writer::NullUiWriter;
+use g3_core::{Agent, ToolCall};
+use serde_json::json;
+use serial_test::serial;
+use std::path::Path;
+use tempfile::TempDir;
+
+fn rule(tool: &str, action: PermissionAction, pattern: Option<&str>) -> PermissionRule {
+    PermissionRule {
+        tool: tool.to_string(),
+        action,
+        pattern: pattern.map(str::to_string),
+        regex: None,
+    }
+}
+
+fn policy(config: PermissionsConfig) -> PermissionPolicy {
+    PermissionPolicy::from_config(&config, Path::new("/work")).unwrap()
+}
+
+#[test]
+fn test_deny_beats_allow() {
+    let policy = policy(PermissionsConfig {
+        rules: vec![
+            rule("shell", PermissionAction::Allow, None),
+            rule("shell", PermissionAction::Deny, Some("rm -rf *")),
+        ],
+        ..Default::default()
+    });
+
+    let decision = policy.evaluate("shell", "rm -rf /tmp/build", false);
+    assert_eq!(decision.action, PermissionAction::Deny);
+    assert!(decision.reason.contains("rm -rf *"));
+
+    let decision = policy.evaluate("shell", "ls -la", false);
+    assert_eq!(decision.action, PermissionAction::Allow);
+}
+
+#[test]
+fn test_workspace_pattern_and_default() {
+    let policy = policy(PermissionsConfig {
+        default: PermissionAction::Prompt,
+        rules: vec![rule("write_file", PermissionAction::Allow, Some("${workspace}/*"))],
+        ..Default::default()
+    });
+
+    assert_eq!(
+        policy.evaluate("write_file", "/work/src/main.rs", false).action,
+        PermissionAction::Allow
+    );
+    assert_eq!(
+        policy.evaluate("write_file", "/etc/passwd", false).action,
+        PermissionAction::Prompt
+    );
+    // Read-only tools skip the default unless a rule matches
+    assert_eq!(
+        policy.evaluate("read_file", "/etc/passwd", true).action,
+        PermissionAction::Allow
+    );
+}
+
+#[test]
+fn test_tool_globs_and_regex() {
+    let policy = policy(PermissionsConfig {
+        rules: vec![
+            rule("webdriver_*", PermissionAction::Deny, None),
+            PermissionRule {
+                tool: "shell".to_string(),
+                action: PermissionAction::Prompt,
+                pattern: None,
+                regex: Some(r"^git\s+push".to_string()),
+            },
+        ],
+        ..Default::default()
+    });
+
+    assert_eq!(
+        policy.evaluate("webdriver_navigate", "{}", false).action,
+        PermissionAction::Deny
+    );
+    assert_eq!(
+        policy.evaluate("shell", "git push origin main", false).action,
+        PermissionAction::Prompt
+    );
+    assert_eq!(
+        policy.evaluate("shell", "git status", false).action,
+        PermissionAction::Allow
+    );
+}
+
+#[test]
+fn test_unattended_prompt_becomes_deny() {
+    let policy = policy(PermissionsConfig {
+        unattended: PermissionAction::Prompt,
+        ..Default::default()
+    });
+    assert_eq!(policy.unattended_action(), PermissionAction::Deny);
+}
+
+#[test]
+fn test_resolve_path_normalizes() {
+    assert_eq!(resolve_path("/work/src/../../etc/passwd"), "/etc/passwd");
+    assert_eq!(resolve_path("/work/./a.txt"), "/work/a.txt");
+}
+
+#[tokio::test]
+#[serial]
+async fn test_agent_denies_write_outside_workspace() {
+    let temp_dir = TempDir::new().unwrap();
+    let outside_dir = TempDir::new().unwrap();
+    std::env::set_current_dir(temp_dir.path()).unwrap();
+
+    let config = g3_config::Config {
+        permissions: PermissionsConfig {
+            default: PermissionAction::Prompt,
+            unattended: PermissionAction::Deny,
+            rules: vec![rule("write_file", PermissionAction::Allow, Some("${workspace}/*"))],
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    // NullUiWriter is not interactive, so prompts resolve to `unattended`
+    let mut agent = Agent::new(config, NullUiWriter).await.unwrap();
+
+    let inside = ToolCall {
+        tool: "write_file".to_string(),
+        args: json!({ "file_path": "notes.txt", "content": "hello" }),
+    };
+    let result = agent.execute_tool_call(&inside).await.unwrap();
+    assert!(result.starts_with("✅"), "unexpected result: {}", result);
+
+    let outside_path = outside_dir.path().join("notes.txt");
+    let outside = ToolCall {
+        tool: "write_file".to_string(),
+        args: json!({ "file_path": outside_path.to_str().unwrap(), "content": "hello" }),
+    };
+    let result = agent.execute_tool_call(&outside).await.unwrap();
+    assert!(result.starts_with("❌ Permission denied"), "unexpected result: {}", result);
+    assert!(!outside_path.exists());
+}
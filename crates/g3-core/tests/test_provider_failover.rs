@@ -0,0 +1,111 @@
+#[path = "../../g3-providers/tests/common/mod.rs"]
+mod common;
+
+use common::{mock_server_with, Requests, Response};
+use g3_config::{Config, OpenAIConfig};
+use g3_core::session::SessionLog;
+use g3_core::ui_writer::NullUiWriter;
+use g3_core::Agent;
+use serial_test::serial;
+use tempfile::TempDir;
+
+/// Serve an outage under `/primary` and a short OpenAI stream under `/backup`.
+/// Returns the base URL and the requests received so far.
+async fn mock_providers() -> (String, Requests) {
+    mock_server_with(|request| {
+        if request.path.starts_with("/primary") {
+            Response::raw("text/plain", "upstream overloaded")
+                .with_status("503 Service Unavailable")
+        } else {
+            Response::sse(&[
+                r#"{"choices":[{"index":0,"delta":{"content":"Hello from the backup"}}]}"#,
+                r#"{"choices":[{"index":0,"delta":{},"finish_reason":"stop"}]}"#,
+            ])
+        }
+    })
+    .await
+}
+
+/// Paths requested so far
+fn paths(requests: &Requests) -> Vec<String> {
+    requests
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|request| request.path.clone())
+        .collect()
+}
+
+fn openai_config(base_url: String) -> OpenAIConfig {
+    OpenAIConfig {
+        api_key: "test-key".to_string(),
+        model: "gpt-4o".to_string(),
+        base_url: Some(base_url),
+        max_tokens: Some(1024),
+        temperature: None,
+    }
+}
+
+#[tokio::test]
+#[serial]
+async fn test_agent_fails_over_to_fallback_provider() {
+    let temp_dir = TempDir::new().unwrap();
+    std::env::set_current_dir(temp_dir.path()).unwrap();
+    let (base_url, requests) = mock_providers().await;
+
+    let mut config = Config::default();
+    config.providers.openai.insert(
+        "primary".to_string(),
+        openai_config(format!("{}/primary", base_url)),
+    );
+    config.providers.openai.insert(
+        "backup".to_string(),
+        openai_config(format!("{}/backup", base_url)),
+    );
+    config.providers.default_provider = "openai.primary".to_string();
+    config.agent.max_context_length = Some(128_000);
+    config.providers.fallbacks = vec!["openai.backup".to_string()];
+    config.agent.provider_failover_after = 1;
+
+    let mut agent = Agent::new(config, NullUiWriter).await.unwrap();
+    agent.execute_task("Say hello", None, false).await.unwrap();
+
+    assert_eq!(agent.get_provider_info().unwrap().0, "openai.backup");
+    assert_eq!(
+        paths(&requests),
+        vec!["/primary/chat/completions", "/backup/chat/completions"]
+    );
+
+    let session_id = agent.get_session_id().unwrap().to_string();
+    let log = SessionLog::load(&session_id).unwrap();
+    assert_eq!(log.provider_switches.len(), 1);
+    assert_eq!(log.provider_switches[0].from, "openai.primary");
+    assert_eq!(log.provider_switches[0].to, "openai.backup");
+    assert!(log.provider_switches[0].reason.contains("503"));
+}
+
+#[tokio::test]
+#[serial]
+async fn test_no_failover_without_fallbacks() {
+    let temp_dir = TempDir::new().unwrap();
+    std::env::set_current_dir(temp_dir.path()).unwrap();
+    let (base_url, requests) = mock_providers().await;
+
+    let mut config = Config::default();
+    config.providers.openai.insert(
+        "primary".to_string(),
+        openai_config(format!("{}/primary", base_url)),
+    );
+    config.providers.default_provider = "openai.primary".to_string();
+    config.agent.max_context_length = Some(128_000);
+    config.agent.max_retry_attempts = 1;
+
+    let mut agent = Agent::new(config, NullUiWriter).await.unwrap();
+    let err = agent
+        .execute_task("Say hello", None, false)
+        .await
+        .unwrap_err();
+
+    assert!(err.to_string().contains("503"), "unexpected error: {}", err);
+    assert_eq!(paths(&requests), vec!["/primary/chat/completions"]);
+}
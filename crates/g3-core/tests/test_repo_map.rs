@@ -0,0 +1,109 @@
+use g3_core::repo_map::RepoMap;
+use g3_providers::{EstimatingTokenizer, Tokenizer};
+use std::path::Path;
+use tempfile::TempDir;
+
+fn write(root: &Path, path: &str, contents: &str) {
+    let path = root.join(path);
+    std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+    std::fs::write(path, contents).unwrap();
+}
+
+/// `store.rs` is used by two files, `cli.rs` by one and `unused.rs` by none
+fn sample_repo() -> TempDir {
+    let root = TempDir::new().unwrap();
+    write(
+        root.path(),
+        "src/store.rs",
+        "pub struct Store;\n\npub fn open_store() -> Store {\n    Store\n}\n",
+    );
+    write(
+        root.path(),
+        "src/cli.rs",
+        "pub fn run() {\n    let _s: Store = open_store();\n}\n",
+    );
+    write(
+        root.path(),
+        "src/main.rs",
+        "fn main() {\n    let _s = open_store();\n    run();\n}\n",
+    );
+    write(root.path(), "src/unused.rs", "pub fn helper() {}\n");
+    write(
+        root.path(),
+        "scripts/report.py",
+        "class Report:\n    pass\n\ndef build():\n    return Report()\n",
+    );
+    write(
+        root.path(),
+        "target/debug/gen.rs",
+        "pub fn open_store() {}\n",
+    );
+    root
+}
+
+#[test]
+fn test_files_are_ranked_by_references() {
+    let root = sample_repo();
+    let map = RepoMap::build(root.path()).unwrap();
+
+    let paths: Vec<&str> = map.files.iter().map(|f| f.path.as_str()).collect();
+    assert_eq!(paths[0], "src/store.rs");
+    assert!(paths.contains(&"scripts/report.py"));
+    assert!(!paths.iter().any(|p| p.starts_with("target/")));
+    let position = |path: &str| paths.iter().position(|p| *p == path).unwrap();
+    assert!(position("src/cli.rs") < position("src/unused.rs"));
+
+    let store = &map.files[0].definitions;
+    let names: Vec<(&str, &str, usize)> = store
+        .iter()
+        .map(|d| (d.kind.as_str(), d.name.as_str(), d.line))
+        .collect();
+    assert_eq!(
+        names,
+        vec![("struct", "Store", 1), ("function", "open_store", 3)]
+    );
+    // Both callers count; the struct's own uses inside store.rs do not
+    assert_eq!(store[1].references, 2);
+    assert_eq!(store[0].references, 1);
+
+    // main.rs defines `main`, which nothing references
+    let main = map.files.iter().find(|f| f.path == "src/main.rs").unwrap();
+    assert_eq!(main.definitions[0].references, 0);
+}
+
+#[test]
+fn test_render_fits_the_token_budget() {
+    let root = sample_repo();
+    let map = RepoMap::build(root.path()).unwrap();
+    let tokenizer = EstimatingTokenizer::default();
+
+    let full = map.render(4096, &tokenizer);
+    assert!(full.starts_with("src/store.rs:\n  1: struct Store\n  3: function open_store\n"));
+    assert!(full.contains("scripts/report.py:\n  1: class Report\n  4: function build\n"));
+    assert!(!full.contains("more files"));
+
+    let first_block = "src/store.rs:\n  1: struct Store\n  3: function open_store\n";
+    let short = map.render(tokenizer.count_tokens(first_block) + 1, &tokenizer);
+    assert!(short.starts_with("src/store.rs:\n"));
+    assert!(!short.contains("src/cli.rs"));
+    assert!(short.ends_with(&format!("... {} more files\n", map.files.len() - 1)));
+}
+
+#[test]
+fn test_root_must_be_inside_the_workspace() {
+    let root = sample_repo();
+    let workspace = root.path().join("src");
+
+    let resolved = RepoMap::resolve_root(&workspace, ".").unwrap();
+    assert_eq!(resolved, workspace.canonicalize().unwrap());
+    for path in ["..", "../scripts", "/"] {
+        let err = RepoMap::resolve_root(&workspace, path).unwrap_err();
+        assert!(
+            err.to_string().contains("outside the workspace"),
+            "{}",
+            path
+        );
+    }
+    assert!(RepoMap::resolve_root(&workspace, "main.rs").is_err());
+    assert!(RepoMap::resolve_root(&workspace, "missing").is_err());
+}
@@ -0,0 +1,142 @@
+use anyhow::Result;
+use g3_core::semantic_index::{chunk_source, Embedder, IndexUpdate, SemanticIndex};
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use tempfile::TempDir;
+
+/// Bag-of-words embedder: one dimension per hashed identifier part
+#[derive(Default)]
+struct WordEmbedder {
+    texts_embedded: AtomicUsize,
+}
+
+impl Embedder for WordEmbedder {
+    fn model_name(&self) -> &str {
+        "words"
+    }
+
+    fn embed(&self, texts: &[String]) -> Result<Vec<Vec<f32>>> {
+        self.texts_embedded.fetch_add(texts.len(), Ordering::SeqCst);
+        Ok(texts
+            .iter()
+            .map(|text| {
+                let mut vector = vec![0.0; 256];
+                for word in text
+                    .split(|c: char| !c.is_alphanumeric())
+                    .filter(|w| w.len() > 2)
+                {
+                    let mut hasher = DefaultHasher::new();
+                    word.to_lowercase().hash(&mut hasher);
+                    vector[(hasher.finish() % 256) as usize] += 1.0;
+                }
+                vector
+            })
+            .collect())
+    }
+}
+
+const RETRY_RS: &str = r#"
+/// Retry a request with exponential backoff
+pub fn retry_with_backoff(attempts: u32) -> u64 {
+    let delay = 100;
+    delay * 2u64.pow(attempts)
+}
+
+pub struct Config {
+    pub name: String,
+}
+"#;
+
+const PARSER_PY: &str = r#"
+class TokenParser:
+    def parse_tokens(self, text):
+        return text.split()
+"#;
+
+fn write(root: &Path, path: &str, contents: &str) {
+    let path = root.join(path);
+    std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+    std::fs::write(path, contents).unwrap();
+}
+
+#[test]
+fn test_chunks_follow_symbols() {
+    let chunks = chunk_source(Path::new("retry.rs"), RETRY_RS);
+    let symbols: Vec<_> = chunks
+        .iter()
+        .map(|c| (c.symbol.as_str(), c.kind.as_str(), c.start_line, c.end_line))
+        .collect();
+    assert_eq!(
+        symbols,
+        vec![
+            ("retry_with_backoff", "function_item", 3, 6),
+            ("Config", "struct_item", 8, 10),
+        ]
+    );
+
+    let chunks = chunk_source(Path::new("parser.py"), PARSER_PY);
+    assert_eq!(chunks.len(), 1);
+    assert_eq!(chunks[0].symbol, "TokenParser");
+    assert!(chunks[0].text.contains("parse_tokens"));
+
+    assert!(chunk_source(Path::new("notes.txt"), "plain text").is_empty());
+}
+
+#[test]
+fn test_search_ranks_the_matching_symbol_first() {
+    let root = TempDir::new().unwrap();
+    write(root.path(), "src/retry.rs", RETRY_RS);
+    write(root.path(), "tools/parser.py", PARSER_PY);
+    write(root.path(), "target/debug/build.rs", RETRY_RS);
+
+    let mut index = SemanticIndex::open(root.path(), Arc::new(WordEmbedder::default())).unwrap();
+    index.update().unwrap();
+    assert_eq!(index.file_count(), 2);
+
+    let matches = index.search("backoff retry delay", 5, None).unwrap();
+    assert_eq!(matches[0].file, "src/retry.rs");
+    assert_eq!(matches[0].symbol, "retry_with_backoff");
+
+    let matches = index.search("parse tokens", 5, None).unwrap();
+    assert_eq!(matches[0].symbol, "TokenParser");
+
+    let matches = index
+        .search("backoff retry delay", 5, Some("tools/"))
+        .unwrap();
+    assert!(matches.iter().all(|m| m.file == "tools/parser.py"));
+}
+
+#[test]
+fn test_update_only_embeds_changed_files() {
+    let root = TempDir::new().unwrap();
+    write(root.path(), "src/retry.rs", RETRY_RS);
+    write(root.path(), "tools/parser.py", PARSER_PY);
+
+    let embedder = Arc::new(WordEmbedder::default());
+    let mut index = SemanticIndex::open(root.path(), embedder.clone()).unwrap();
+    let update = index.update().unwrap();
+    assert_eq!(update.added, 2);
+    assert_eq!(update.chunks_embedded, 3);
+    index.save().unwrap();
+    assert!(SemanticIndex::index_path(root.path()).exists());
+
+    // A reopened index is loaded from disk and nothing is re-embedded
+    let mut index = SemanticIndex::open(root.path(), embedder.clone()).unwrap();
+    assert_eq!(index.update().unwrap(), IndexUpdate::default());
+    assert_eq!(embedder.texts_embedded.load(Ordering::SeqCst), 3);
+
+    write(
+        root.path(),
+        "src/retry.rs",
+        "pub fn retry_forever() -> bool {\n    true\n}\n",
+    );
+    std::fs::remove_file(root.path().join("tools/parser.py")).unwrap();
+    write(root.path(), "src/lib.rs", "pub mod retry;\n");
+    let update = index.update().unwrap();
+    assert_eq!((update.added, update.updated, update.removed), (1, 1, 1));
+    assert_eq!(embedder.texts_embedded.load(Ordering::SeqCst), 5);
+    assert_eq!(index.chunk_count(), 2);
+}
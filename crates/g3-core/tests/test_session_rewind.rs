@@ -0,0 +1,155 @@
+use g3_core::session::RewindTarget;
+use g3_core::ui_writer::NullUiWriter;
+use g3_core::{Agent, ToolCall};
+use g3_providers::{Message, MessageRole};
+use serde_json::json;
+use serial_test::serial;
+use std::fs;
+use tempfile::TempDir;
+
+async fn create_test_agent(temp_dir: &TempDir) -> Agent<NullUiWriter> {
+    std::env::set_current_dir(temp_dir.path()).unwrap();
+    Agent::new(g3_config::Config::default(), NullUiWriter)
+        .await
+        .unwrap()
+}
+
+/// Add a task turn that writes `content` to notes.txt
+async fn write_turn(agent: &mut Agent<NullUiWriter>, task: &str, content: &str) {
+    agent.add_message_to_context(Message::new(MessageRole::User, format!("Task: {}", task)));
+    let tool_call = ToolCall {
+        tool: "write_file".to_string(),
+        args: json!({ "file_path": "notes.txt", "content": content }),
+    };
+    let result = agent.execute_tool(&tool_call).await.unwrap();
+    assert!(result.starts_with("✅"), "unexpected result: {}", result);
+    agent.add_message_to_context(Message::new(MessageRole::Assistant, "Done".to_string()));
+}
+
+#[tokio::test]
+#[serial]
+async fn test_rewind_turns_keeps_files_by_default() {
+    let temp_dir = TempDir::new().unwrap();
+    let mut agent = create_test_agent(&temp_dir).await;
+
+    write_turn(&mut agent, "first", "one").await;
+    write_turn(&mut agent, "second", "two").await;
+    let history_len = agent.get_context_window().conversation_history.len();
+
+    let summary = agent.rewind(&RewindTarget::Turns(1), false).unwrap();
+    assert_eq!(summary.removed_messages, 2);
+    assert!(summary.reverted_files.is_empty());
+    assert_eq!(
+        agent.get_context_window().conversation_history.len(),
+        history_len - 2
+    );
+    assert_eq!(agent.user_turns().len(), 1);
+    assert_eq!(
+        fs::read_to_string(temp_dir.path().join("notes.txt")).unwrap(),
+        "two"
+    );
+}
+
+#[tokio::test]
+#[serial]
+async fn test_rewind_to_message_reverts_edits() {
+    let temp_dir = TempDir::new().unwrap();
+    let mut agent = create_test_agent(&temp_dir).await;
+
+    write_turn(&mut agent, "first", "one").await;
+    write_turn(&mut agent, "second", "two").await;
+    write_turn(&mut agent, "third", "three").await;
+
+    let second_id = agent.user_turns()[1].0.to_string();
+    let summary = agent
+        .rewind(&RewindTarget::Message(second_id), true)
+        .unwrap();
+
+    assert_eq!(summary.removed_messages, 4);
+    assert_eq!(summary.reverted_files.len(), 1);
+    assert_eq!(
+        fs::read_to_string(temp_dir.path().join("notes.txt")).unwrap(),
+        "one"
+    );
+
+    // Rewinding past the first edit removes the file it created
+    agent.rewind(&RewindTarget::Turns(1), true).unwrap();
+    assert!(!temp_dir.path().join("notes.txt").exists());
+}
+
+#[tokio::test]
+#[serial]
+async fn test_rewind_to_assistant_message_reverts_its_edit() {
+    let temp_dir = TempDir::new().unwrap();
+    let mut agent = create_test_agent(&temp_dir).await;
+
+    write_turn(&mut agent, "first", "one").await;
+    write_turn(&mut agent, "second", "two").await;
+
+    // The assistant message that issued the second write
+    let assistant_id = agent
+        .get_context_window()
+        .conversation_history
+        .last()
+        .unwrap()
+        .id
+        .clone();
+    let summary = agent
+        .rewind(&RewindTarget::Message(assistant_id), true)
+        .unwrap();
+
+    assert_eq!(summary.removed_messages, 1);
+    assert_eq!(summary.reverted_files.len(), 1);
+    assert_eq!(
+        fs::read_to_string(temp_dir.path().join("notes.txt")).unwrap(),
+        "one"
+    );
+    assert_eq!(agent.user_turns().len(), 2);
+}
+
+#[tokio::test]
+#[serial]
+async fn test_rewind_rejects_unknown_targets() {
+    let temp_dir = TempDir::new().unwrap();
+    let mut agent = create_test_agent(&temp_dir).await;
+    write_turn(&mut agent, "first", "one").await;
+
+    assert!(agent.rewind(&RewindTarget::Turns(2), false).is_err());
+    assert!(agent
+        .rewind(&RewindTarget::Message("000000-xyz".to_string()), false)
+        .is_err());
+
+    // System messages cannot be rewound away
+    let system_id = agent.get_context_window().conversation_history[0]
+        .id
+        .clone();
+    assert!(agent
+        .rewind(&RewindTarget::Message(system_id), false)
+        .is_err());
+}
+
+#[tokio::test]
+#[serial]
+async fn test_fork_session_keeps_original_log() {
+    let temp_dir = TempDir::new().unwrap();
+    let mut agent = create_test_agent(&temp_dir).await;
+    write_turn(&mut agent, "first", "one").await;
+
+    let fork_id = agent.fork_session();
+    assert!(fork_id.starts_with("session_fork_"));
+    assert_eq!(agent.get_session_id(), Some(fork_id.as_str()));
+    assert!(temp_dir
+        .path()
+        .join("logs")
+        .join(format!("g3_session_{}.json", fork_id))
+        .exists());
+}
+
+#[test]
+fn test_rewind_target_parse() {
+    assert_eq!(RewindTarget::parse("3"), RewindTarget::Turns(3));
+    assert_eq!(
+        RewindTarget::parse("101502-ghi"),
+        RewindTarget::Message("101502-ghi".to_string())
+    );
+}
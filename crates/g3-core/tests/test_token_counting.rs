@@ -1,5 +1,22 @@
 use g3_core::ContextWindow;
-use g3_providers::{Message, MessageRole, Usage};
+use g3_providers::{
+    CompletionRequest, Message, MessageRole, Tokenizer, Tool, Usage, MESSAGE_OVERHEAD_TOKENS,
+};
+use std::sync::Arc;
+
+/// One token per whitespace-separated word, to make counts predictable
+#[derive(Debug)]
+struct WordTokenizer;
+
+impl Tokenizer for WordTokenizer {
+    fn name(&self) -> &str {
+        "words"
+    }
+
+    fn count_tokens(&self, text: &str) -> u32 {
+        text.split_whitespace().count() as u32
+    }
+}
 
 /// Test that used_tokens is tracked via add_message, not update_usage_from_response.
 /// This is critical for the 80% summarization threshold to work correctly.
@@ -38,6 +55,7 @@ fn test_update_usage_only_affects_cumulative() {
         prompt_tokens: 100,
         completion_tokens: 50,
         total_tokens: 150,
+        ..Default::default()
     };
     window.update_usage_from_response(&usage);
 
@@ -52,6 +70,7 @@ fn test_update_usage_only_affects_cumulative() {
         prompt_tokens: 200,
         completion_tokens: 75,
         total_tokens: 275,
+        ..Default::default()
     };
     window.update_usage_from_response(&usage2);
 
@@ -156,6 +175,7 @@ fn test_cumulative_vs_used_independence() {
         prompt_tokens: 500,
         completion_tokens: 200,
         total_tokens: 700,
+        ..Default::default()
     };
     window.update_usage_from_response(&usage);
 
@@ -168,3 +188,39 @@ fn test_cumulative_vs_used_independence() {
     // They should now be different
     assert!(window.cumulative_tokens > window.used_tokens, "cumulative should be greater than used");
 }
+
+/// Test that messages are counted with the configured tokenizer.
+#[test]
+fn test_messages_counted_with_tokenizer() {
+    let mut window = ContextWindow::new(10000).with_tokenizer(Arc::new(WordTokenizer));
+
+    window.add_message(Message::new(MessageRole::User, "read the README please".to_string()));
+    assert_eq!(window.used_tokens, 4 + MESSAGE_OVERHEAD_TOKENS);
+    assert_eq!(window.count_tokens("one two three"), 3);
+}
+
+/// Test that the pre-flight check recounts the request, including tool definitions.
+#[test]
+fn test_preflight_counts_request_and_tools() {
+    let mut window = ContextWindow::new(10000).with_tokenizer(Arc::new(WordTokenizer));
+    window.add_message(Message::new(MessageRole::User, "hello there".to_string()));
+
+    let mut request = CompletionRequest {
+        messages: window.conversation_history.clone(),
+        max_tokens: None,
+        temperature: None,
+        stream: true,
+        tools: None,
+        disable_thinking: false,
+    };
+    assert_eq!(window.preflight(&request), window.used_tokens);
+
+    request.tools = Some(vec![Tool {
+        name: "read_file".to_string(),
+        description: "Read a file from disk".to_string(),
+        input_schema: serde_json::json!({ "type": "object" }),
+    }]);
+    let with_tools = window.preflight(&request);
+    assert!(with_tools > 2 + MESSAGE_OVERHEAD_TOKENS, "tool definitions should be counted");
+    assert_eq!(window.used_tokens, with_tools);
+}
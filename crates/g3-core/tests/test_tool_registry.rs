@@ -0,0 +1,128 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use g3_core::tools::{ToolContext, ToolHandler, ToolPermissions, ToolRegistry};
+use g3_core::ui_writer::NullUiWriter;
+use g3_core::{Agent, ToolCall};
+use g3_providers::Tool;
+use serde_json::json;
+use serial_test::serial;
+use std::sync::Arc;
+use tempfile::TempDir;
+
+struct EchoTool;
+
+#[async_trait]
+impl ToolHandler for EchoTool {
+    fn name(&self) -> &str {
+        "echo"
+    }
+
+    fn definition(&self) -> Tool {
+        Tool {
+            name: "echo".to_string(),
+            description: "Echo the given text".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": { "text": { "type": "string" } },
+                "required": ["text"]
+            }),
+        }
+    }
+
+    fn permissions(&self) -> ToolPermissions {
+        ToolPermissions::default()
+    }
+
+    async fn execute(&self, tool_call: &ToolCall, _ctx: &ToolContext<'_>) -> Result<String> {
+        Ok(format!(
+            "✅ {}",
+            tool_call.args["text"].as_str().unwrap_or_default()
+        ))
+    }
+}
+
+async fn create_test_agent(temp_dir: &TempDir) -> Agent<NullUiWriter> {
+    std::env::set_current_dir(temp_dir.path()).unwrap();
+    let mut config = g3_config::Config::default();
+    config.webdriver.enabled = false;
+    Agent::new(config, NullUiWriter).await.unwrap()
+}
+
+#[test]
+fn test_builtin_definitions_respect_config() {
+    let registry = ToolRegistry::builtin();
+    let mut config = g3_config::Config::default();
+
+    config.webdriver.enabled = false;
+    let names: Vec<String> = registry
+        .definitions(&config)
+        .into_iter()
+        .map(|t| t.name)
+        .collect();
+    assert_eq!(names.first().map(String::as_str), Some("shell"));
+    assert!(names.contains(&"code_search".to_string()));
+    assert!(!names.iter().any(|n| n.starts_with("webdriver_")));
+    assert!(!names.iter().any(|n| n.starts_with("macax_")));
+
+    config.webdriver.enabled = true;
+    let defs = registry.definitions(&config);
+    assert!(defs.iter().any(|t| t.name == "webdriver_start"));
+}
+
+#[test]
+fn test_builtin_permissions() {
+    let registry = ToolRegistry::builtin();
+    assert!(registry.get("read_file").unwrap().permissions().is_read_only());
+    assert!(registry.get("shell").unwrap().permissions().executes_commands);
+    assert!(registry.get("write_file").unwrap().permissions().writes_files);
+}
+
+#[test]
+fn test_disable_tool() {
+    let mut registry = ToolRegistry::builtin();
+    let config = g3_config::Config::default();
+
+    registry.disable("todo_write");
+    assert!(registry.get("todo_write").is_none());
+    assert!(!registry.is_enabled("todo_write"));
+    assert!(!registry
+        .definitions(&config)
+        .iter()
+        .any(|t| t.name == "todo_write"));
+
+    registry.enable("todo_write");
+    assert!(registry.is_enabled("todo_write"));
+}
+
+#[tokio::test]
+#[serial]
+async fn test_agent_executes_custom_tool() {
+    let temp_dir = TempDir::new().unwrap();
+    let mut agent = create_test_agent(&temp_dir).await;
+
+    agent.tool_registry_mut().register(Arc::new(EchoTool));
+    assert!(agent.tool_registry().names().contains(&"echo"));
+
+    let tool_call = ToolCall {
+        tool: "echo".to_string(),
+        args: json!({ "text": "hello" }),
+    };
+    let result = agent.execute_tool_call(&tool_call).await.unwrap();
+    assert_eq!(result, "✅ hello");
+}
+
+#[tokio::test]
+#[serial]
+async fn test_agent_rejects_disabled_builtin() {
+    let temp_dir = TempDir::new().unwrap();
+    let mut agent = create_test_agent(&temp_dir).await;
+
+    agent.tool_registry_mut().disable("shell");
+
+    let tool_call = ToolCall {
+        tool: "shell".to_string(),
+        args: json!({ "command": "echo hi" }),
+    };
+    let result = agent.execute_tool_call(&tool_call).await.unwrap();
+    assert!(result.starts_with("❓ Unknown tool"));
+}
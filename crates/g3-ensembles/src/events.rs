@@ -0,0 +1,51 @@
+//! Live progress of a flock run, for dashboards
+//!
+//! A [`FlockMode`](crate::FlockMode) given an event sender reports segment
+//! status changes and agent output while its segments run instead of
+//! printing them. Segments can be cancelled through a [`SegmentCanceller`].
+
+use tokio::sync::mpsc;
+
+use crate::status::SegmentStatus;
+
+/// Something that happened while the segments ran
+#[derive(Debug, Clone)]
+pub enum FlockEvent {
+    /// Segments are about to start; module names are in segment order
+    SegmentsStarted { modules: Vec<String> },
+
+    /// A segment's status changed
+    SegmentUpdated(SegmentStatus),
+
+    /// A line written by a segment's agent
+    SegmentOutput {
+        segment_id: usize,
+        line: String,
+        is_error: bool,
+    },
+
+    /// Progress message from the orchestrator
+    Message(String),
+
+    /// Every segment has completed, failed or been cancelled. The run
+    /// continues once the receiver is dropped.
+    SegmentsFinished,
+}
+
+/// Cancels segments of a running flock
+#[derive(Debug, Clone)]
+pub struct SegmentCanceller {
+    requests: mpsc::UnboundedSender<usize>,
+}
+
+impl SegmentCanceller {
+    pub(crate) fn new(requests: mpsc::UnboundedSender<usize>) -> Self {
+        Self { requests }
+    }
+
+    /// Stop a running segment, or keep a queued one from starting. Segments
+    /// that depend on it are cancelled too.
+    pub fn cancel(&self, segment_id: usize) {
+        let _ = self.requests.send(segment_id);
+    }
+}
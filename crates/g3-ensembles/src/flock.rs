@@ -3,15 +3,28 @@
 use anyhow::{Context, Result};
 use chrono::Utc;
 use g3_config::Config;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 use std::process::Stdio;
 use tokio::io::{AsyncBufReadExt, BufReader};
 use tokio::process::Command;
+use tokio::sync::{mpsc, oneshot};
+use tokio::task::JoinSet;
 use tracing::{debug, error, info, warn};
 use uuid::Uuid;
 
+use crate::events::{FlockEvent, SegmentCanceller};
+use crate::integration::{segment_changes, Integrator, SegmentWork};
+use crate::schedule::SegmentGraph;
 use crate::status::{FlockStatus, SegmentState, SegmentStatus};
 
+/// Status file written to the flock workspace
+const STATUS_FILE: &str = "flock-status.json";
+
+/// Heading of the section that seeding appends to `segment-requirements.md`
+const UPSTREAM_SECTION: &str = "\n\n## Upstream Modules\n\n";
+
 /// Configuration for flock mode
 #[derive(Debug, Clone)]
 pub struct FlockConfig {
@@ -32,6 +45,18 @@ pub struct FlockConfig {
 
     /// Path to g3 binary (defaults to current executable)
     pub g3_binary: Option<PathBuf>,
+
+    /// Shell command run on the integration branch after each merge
+    pub test_command: Option<String>,
+
+    /// Maximum number of segments running at once (unlimited if unset)
+    pub max_concurrent_segments: Option<usize>,
+
+    /// Times a failed segment is rerun in its workspace
+    pub max_retries: usize,
+
+    /// Create segments as git worktrees of the project instead of clones
+    pub segment_worktrees: bool,
 }
 
 impl FlockConfig {
@@ -79,6 +104,10 @@ impl FlockConfig {
             max_turns: 5, // Default
             g3_config,
             g3_binary: None,
+            test_command: None,
+            max_concurrent_segments: None,
+            max_retries: 0,
+            segment_worktrees: false,
         })
     }
 
@@ -124,9 +153,31 @@ impl FlockConfig {
             max_turns: 5, // Default
             g3_config,
             g3_binary: None,
+            test_command: None,
+            max_concurrent_segments: None,
+            max_retries: 0,
+            segment_worktrees: false,
         })
     }
 
+    /// Configuration for resuming the flock run saved in `flock_workspace`
+    pub fn resume(flock_workspace: PathBuf) -> Result<Self> {
+        let status = load_saved_status(&flock_workspace)?;
+        Self::new(status.project_dir, flock_workspace, status.num_segments)
+    }
+
+    /// Configuration for resuming the flock run saved in `flock_workspace`,
+    /// with a specified config path
+    pub fn resume_with_config(flock_workspace: PathBuf, config_path: Option<&str>) -> Result<Self> {
+        let status = load_saved_status(&flock_workspace)?;
+        Self::new_with_config(
+            status.project_dir,
+            flock_workspace,
+            status.num_segments,
+            config_path,
+        )
+    }
+
     /// Set maximum turns per segment
     pub fn with_max_turns(mut self, max_turns: usize) -> Self {
         self.max_turns = max_turns;
@@ -144,6 +195,61 @@ impl FlockConfig {
         self.g3_config = config;
         self
     }
+
+    /// Set how many times a failed segment is retried
+    pub fn with_max_retries(mut self, max_retries: usize) -> Self {
+        self.max_retries = max_retries;
+        self
+    }
+
+    /// Limit how many segments run at once
+    pub fn with_max_concurrent_segments(mut self, max: usize) -> Self {
+        self.max_concurrent_segments = Some(max);
+        self
+    }
+
+    /// Set the test command run after each merge during integration
+    pub fn with_test_command(mut self, command: impl Into<String>) -> Self {
+        self.test_command = Some(command.into());
+        self
+    }
+
+    /// Create segments as git worktrees on their `flock/<session>/segment-N`
+    /// branches instead of cloning the project for each. A resumed run keeps
+    /// the kind of workspace the original run created.
+    pub fn with_segment_worktrees(mut self) -> Self {
+        self.segment_worktrees = true;
+        self
+    }
+}
+
+/// One module of the partitioned requirements
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Partition {
+    /// Module name chosen by the partitioning agent
+    pub module_name: String,
+
+    /// Requirements text for this module
+    pub requirements: String,
+
+    /// Names of the modules this one depends on
+    pub dependencies: Vec<String>,
+}
+
+impl Partition {
+    /// Contents of the segment's `segment-requirements.md`
+    pub fn to_requirements_markdown(&self) -> String {
+        format!(
+            "# Module: {}\n\n## Dependencies\n{}\n\n## Requirements\n\n{}",
+            self.module_name,
+            if self.dependencies.is_empty() {
+                "None".to_string()
+            } else {
+                self.dependencies.join(", ")
+            },
+            self.requirements
+        )
+    }
 }
 
 /// Flock mode orchestrator
@@ -151,6 +257,10 @@ pub struct FlockMode {
     config: FlockConfig,
     status: FlockStatus,
     session_id: String,
+    /// Receives segment progress; it is printed when unset
+    events: Option<mpsc::UnboundedSender<FlockEvent>>,
+    cancel_tx: mpsc::UnboundedSender<usize>,
+    cancel_rx: mpsc::UnboundedReceiver<usize>,
 }
 
 impl FlockMode {
@@ -165,40 +275,115 @@ impl FlockMode {
             config.num_segments,
         );
 
+        let (cancel_tx, cancel_rx) = mpsc::unbounded_channel();
         Ok(Self {
             config,
             status,
             session_id,
+            events: None,
+            cancel_tx,
+            cancel_rx,
+        })
+    }
+
+    /// Pick up the flock run saved in the configured workspace. Completed
+    /// segments are kept; the others are rerun in their existing workspaces.
+    pub fn resume(config: FlockConfig) -> Result<Self> {
+        let status = load_saved_status(&config.flock_workspace)?;
+        if status.partitions.is_empty() {
+            anyhow::bail!(
+                "{} has no saved partitions, so the run cannot be resumed",
+                config.flock_workspace.join(STATUS_FILE).display()
+            );
+        }
+
+        let (cancel_tx, cancel_rx) = mpsc::unbounded_channel();
+        Ok(Self {
+            session_id: status.session_id.clone(),
+            config,
+            status,
+            events: None,
+            cancel_tx,
+            cancel_rx,
         })
     }
 
+    /// Send segment status changes and agent output to `events` instead of
+    /// printing them. After [`FlockEvent::SegmentsFinished`] the run waits
+    /// for the receiver to be dropped before integrating.
+    pub fn with_events(mut self, events: mpsc::UnboundedSender<FlockEvent>) -> Self {
+        self.events = Some(events);
+        self
+    }
+
+    /// Handle for cancelling segments while the flock runs
+    pub fn canceller(&self) -> SegmentCanceller {
+        SegmentCanceller::new(self.cancel_tx.clone())
+    }
+
     /// Run flock mode
     pub async fn run(&mut self) -> Result<()> {
-        info!(
-            "Starting flock mode with {} segments",
-            self.config.num_segments
-        );
+        let resuming = !self.status.partitions.is_empty();
 
         // Step 1: Partition requirements
-        println!(
-            "\n🧠 Step 1: Partitioning requirements into {} segments...",
-            self.config.num_segments
-        );
-        let partitions = self.partition_requirements().await?;
+        if resuming {
+            info!("Resuming flock session {}", self.session_id);
+            println!(
+                "\n♻️  Step 1: Resuming session {} ({} of {} segments completed)...",
+                self.session_id,
+                self.status.count_by_state(SegmentState::Completed),
+                self.status.partitions.len()
+            );
+            self.status.completed_at = None;
+        } else {
+            info!(
+                "Starting flock mode with {} segments",
+                self.config.num_segments
+            );
+            println!(
+                "\n🧠 Step 1: Partitioning requirements into {} segments...",
+                self.config.num_segments
+            );
+            self.status.partitions = self.partition_requirements().await?;
+            self.status.num_segments = self.status.partitions.len();
+            if self.config.segment_worktrees {
+                self.status.worktree_base = Some(self.project_head().await?);
+            }
+        }
+        let graph = SegmentGraph::new(&self.status.partitions)?;
+        self.save_status()?;
 
         // Step 2: Create segment workspaces
         println!("\n📁 Step 2: Creating segment workspaces...");
-        self.create_segment_workspaces(&partitions).await?;
+        let partitions = self.status.partitions.clone();
+        self.create_segment_workspaces(&partitions, resuming)
+            .await?;
 
-        // Step 3: Run segments in parallel
+        // Step 3: Run segments, dependencies first
         println!(
             "\n🚀 Step 3: Running {} segments in parallel...",
-            self.config.num_segments
+            self.status.partitions.len()
         );
-        self.run_segments_parallel().await?;
+        self.run_segments(&graph).await?;
 
-        // Step 4: Generate final report
-        println!("\n📊 Step 4: Generating final report...");
+        // Step 4: Merge segment branches
+        println!("\n🔀 Step 4: Integrating segment branches...");
+        if let Err(e) = self.integrate_segments().await {
+            error!("Integration failed: {}", e);
+            println!("   ❌ Integration failed: {}", e);
+        }
+        if self.status.worktree_base.is_some() {
+            match self.remove_segment_worktrees().await {
+                Ok(removed) => println!("   🧹 Removed {} segment worktree(s)", removed),
+                Err(e) => {
+                    warn!("Failed to remove segment worktrees: {}", e);
+                    println!("   ⚠️  Could not remove segment worktrees: {}", e);
+                }
+            }
+        }
+
+        // Step 5: Generate final report
+        println!("\n📊 Step 5: Generating final report...");
         self.status.completed_at = Some(Utc::now());
         self.save_status()?;
 
@@ -209,7 +394,7 @@ impl FlockMode {
     }
 
     /// Partition requirements using an AI agent
-    async fn partition_requirements(&mut self) -> Result<Vec<String>> {
+    async fn partition_requirements(&mut self) -> Result<Vec<Partition>> {
         let requirements_path = self.config.project_dir.join("flock-requirements.md");
         let requirements_content = std::fs::read_to_string(&requirements_path)
             .context("Failed to read flock-requirements.md")?;
@@ -300,40 +485,36 @@ impl FlockMode {
             );
         }
 
-        // Extract requirements text from each partition
-        let mut partition_texts = Vec::new();
+        // Extract module name, requirements and dependencies from each partition
+        let mut parsed = Vec::new();
         for (i, partition) in partitions.iter().enumerate() {
-            let default_name = format!("module-{}", i + 1);
-            let module_name = partition["module_name"].as_str().unwrap_or(&default_name);
+            let module_name = partition["module_name"]
+                .as_str()
+                .map(str::to_string)
+                .unwrap_or_else(|| format!("module-{}", i + 1));
             let requirements = partition["requirements"]
                 .as_str()
-                .context("Missing requirements field in partition")?;
+                .context("Missing requirements field in partition")?
+                .to_string();
             let dependencies = partition["dependencies"]
                 .as_array()
                 .map(|arr| {
                     arr.iter()
                         .filter_map(|v| v.as_str())
-                        .collect::<Vec<_>>()
-                        .join(", ")
+                        .map(str::to_string)
+                        .collect()
                 })
                 .unwrap_or_default();
 
-            let partition_text = format!(
-                "# Module: {}\n\n## Dependencies\n{}\n\n## Requirements\n\n{}",
-                module_name,
-                if dependencies.is_empty() {
-                    "None".to_string()
-                } else {
-                    dependencies
-                },
-                requirements
-            );
-
-            partition_texts.push(partition_text);
             println!("   ✓ Created partition {}: {}", i + 1, module_name);
+            parsed.push(Partition {
+                module_name,
+                requirements,
+                dependencies,
+            });
         }
 
-        Ok(partition_texts)
+        Ok(parsed)
     }
 
     /// Extract JSON from agent output (looks for JSON array in output)
@@ -414,8 +595,14 @@ impl FlockMode {
         anyhow::bail!("No valid JSON found in output")
     }
 
-    /// Create segment workspaces by copying project directory
-    async fn create_segment_workspaces(&mut self, partitions: &[String]) -> Result<()> {
+    /// Create segment workspaces by cloning the project directory, or as
+    /// worktrees of it. When resuming, existing workspaces are kept and
+    /// removed worktrees are checked out again from their branches.
+    async fn create_segment_workspaces(
+        &mut self,
+        partitions: &[Partition],
+        keep_existing: bool,
+    ) -> Result<()> {
         // Ensure flock workspace exists
         std::fs::create_dir_all(&self.config.flock_workspace)?;
 
@@ -426,19 +613,29 @@ impl FlockMode {
                 .flock_workspace
                 .join(format!("segment-{}", segment_id));
 
+            if keep_existing && segment_dir.exists() {
+                println!("   ✓ Reusing segment {} workspace", segment_id);
+                continue;
+            }
+
             println!("   Creating segment {} workspace...", segment_id);
 
-            // Copy project directory to segment directory
-            self.copy_git_repo(&self.config.project_dir, &segment_dir)
-                .await
-                .context(format!("Failed to copy project to segment {}", segment_id))?;
+            if let Some(ref base) = self.status.worktree_base {
+                self.integrator()?
+                    .add_segment_worktree(segment_id, &segment_dir, base, keep_existing)
+                    .await?;
+            } else {
+                // Copy project directory to segment directory
+                self.copy_git_repo(&self.config.project_dir, &segment_dir)
+                    .await
+                    .context(format!("Failed to copy project to segment {}", segment_id))?;
+            }
 
             // Write segment-requirements.md
             let requirements_path = segment_dir.join("segment-requirements.md");
-            std::fs::write(&requirements_path, partition).context(format!(
-                "Failed to write requirements for segment {}",
-                segment_id
-            ))?;
+            std::fs::write(&requirements_path, partition.to_requirements_markdown()).context(
+                format!("Failed to write requirements for segment {}", segment_id),
+            )?;
 
             println!(
                 "   ✓ Segment {} workspace ready at {}",
@@ -450,6 +647,23 @@ impl FlockMode {
         Ok(())
     }
 
+    /// Commit checked out in the project directory
+    async fn project_head(&self) -> Result<String> {
+        let output = Command::new("git")
+            .args(["rev-parse", "HEAD"])
+            .current_dir(&self.config.project_dir)
+            .output()
+            .await
+            .context("Failed to run git rev-parse")?;
+
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            anyhow::bail!("Cannot find the project's HEAD commit: {}", stderr);
+        }
+
+        Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+    }
+
     /// Copy a git repository to a new location
     async fn copy_git_repo(&self, source: &Path, dest: &Path) -> Result<()> {
         // Use git clone for efficient copying
@@ -469,128 +683,406 @@ impl FlockMode {
         Ok(())
     }
 
-    /// Run all segments in parallel
-    async fn run_segments_parallel(&mut self) -> Result<()> {
-        let mut handles = Vec::new();
-
-        for segment_id in 1..=self.config.num_segments {
-            let segment_dir = self
-                .config
-                .flock_workspace
-                .join(format!("segment-{}", segment_id));
-            let max_turns = self.config.max_turns;
-            let g3_binary = self.get_g3_binary()?;
-            let status_file = self.get_status_file_path();
-            let session_id = self.session_id.clone();
-
-            // Initialize segment status
-            let segment_status = SegmentStatus {
-                segment_id,
-                workspace: segment_dir.clone(),
-                state: SegmentState::Running,
-                started_at: Utc::now(),
-                completed_at: None,
-                tokens_used: 0,
-                tool_calls: 0,
-                errors: 0,
-                current_turn: 0,
-                max_turns,
-                last_message: Some("Starting...".to_string()),
-                error_message: None,
-            };
+    /// Run the segments, starting each once the segments it depends on have
+    /// completed and keeping at most `max_concurrent_segments` running
+    async fn run_segments(&mut self, graph: &SegmentGraph) -> Result<()> {
+        let total = self.status.partitions.len();
+        let limit = self.config.max_concurrent_segments.unwrap_or(total).max(1);
+        let max_turns = self.config.max_turns;
+        let g3_binary = self.get_g3_binary()?;
+        let status_file = self.get_status_file_path();
+        send_event(
+            &self.events,
+            FlockEvent::SegmentsStarted {
+                modules: self.module_names_in_order(),
+            },
+        );
 
-            self.status.update_segment(segment_id, segment_status);
-            self.save_status()?;
+        // Segments completed by an earlier run are kept. The rest wait in
+        // Pending, or in Blocked while they have dependencies.
+        let mut started = vec![false; total];
+        for (index, already_completed) in started.iter_mut().enumerate() {
+            let segment_id = index + 1;
+            if let Some(segment_status) = self
+                .status
+                .segments
+                .get(&segment_id)
+                .filter(|s| s.state == SegmentState::Completed)
+            {
+                send_event(
+                    &self.events,
+                    FlockEvent::SegmentUpdated(segment_status.clone()),
+                );
+                *already_completed = true;
+                continue;
+            }
+            let mut segment_status = self.new_segment_status(segment_id);
+            let waiting_for = self.module_names(graph.dependencies(index));
+            if waiting_for.is_empty() {
+                segment_status.last_message = Some("Queued".to_string());
+            } else {
+                segment_status.state = SegmentState::Blocked;
+                segment_status.last_message = Some(format!("Waiting for {}", waiting_for));
+            }
+            self.update_segment(segment_id, segment_status);
+        }
+        self.save_status()?;
 
-            // Spawn a task for this segment
-            let handle = tokio::spawn(async move {
-                run_segment(
-                    segment_id,
-                    segment_dir,
-                    max_turns,
-                    g3_binary,
-                    status_file,
-                    session_id,
-                )
-                .await
-            });
+        let mut retries = vec![0; total];
+        let mut running = JoinSet::new();
+        let mut running_segments: HashMap<tokio::task::Id, usize> = HashMap::new();
+        let mut cancel_senders: HashMap<usize, oneshot::Sender<()>> = HashMap::new();
+
+        loop {
+            // Start ready segments, lowest segment first, up to the limit
+            while running.len() < limit {
+                let Some(index) =
+                    (0..total).find(|&i| !started[i] && self.dependencies_completed(graph, i))
+                else {
+                    break;
+                };
+                started[index] = true;
+                let segment_id = index + 1;
+
+                if !graph.dependencies(index).is_empty() {
+                    if let Err(e) = self.seed_from_upstream(graph, index).await {
+                        warn!("Failed to seed segment {}: {}", segment_id, e);
+                        self.announce(format!(
+                            "   ⚠️  Could not seed segment {}: {}",
+                            segment_id, e
+                        ));
+                    }
+                }
 
-            handles.push((segment_id, handle));
-        }
+                let mut segment_status = self.new_segment_status(segment_id);
+                segment_status.state = SegmentState::Running;
+                segment_status.last_message = Some("Starting...".to_string());
+                self.update_segment(segment_id, segment_status);
+                *self.status.attempts.entry(segment_id).or_insert(0) += 1;
+                self.save_status()?;
+                self.announce(format!(
+                    "   ▶️  Starting segment {} ({})",
+                    segment_id, self.status.partitions[index].module_name
+                ));
+
+                let segment_dir = self.segment_dir(segment_id);
+                let g3_binary = g3_binary.clone();
+                let status_file = status_file.clone();
+                let session_id = self.session_id.clone();
+                let events = self.events.clone();
+                let (cancel_tx, cancel_rx) = oneshot::channel();
+                cancel_senders.insert(segment_id, cancel_tx);
+                let handle = running.spawn(async move {
+                    run_segment(
+                        segment_id,
+                        segment_dir,
+                        max_turns,
+                        g3_binary,
+                        status_file,
+                        session_id,
+                        events,
+                        cancel_rx,
+                    )
+                    .await
+                });
+                running_segments.insert(handle.id(), segment_id);
+            }
 
-        // Wait for all segments to complete
-        for (segment_id, handle) in handles {
-            match handle.await {
-                Ok(Ok(final_status)) => {
-                    println!("\n✅ Segment {} completed", segment_id);
-                    self.status.update_segment(segment_id, final_status);
+            // Wait for the next segment to finish or a cancel request
+            let joined = tokio::select! {
+                joined = running.join_next_with_id() => match joined {
+                    Some(joined) => joined,
+                    None => break,
+                },
+                Some(segment_id) = self.cancel_rx.recv() => {
+                    if let Some(cancel) = cancel_senders.remove(&segment_id) {
+                        let _ = cancel.send(());
+                    } else if (1..=total).contains(&segment_id) && !started[segment_id - 1] {
+                        // Not started yet, so it never will be
+                        started[segment_id - 1] = true;
+                        let mut segment_status = self.new_segment_status(segment_id);
+                        segment_status.state = SegmentState::Cancelled;
+                        segment_status.completed_at = Some(Utc::now());
+                        segment_status.last_message = Some("Cancelled by user".to_string());
+                        self.update_segment(segment_id, segment_status);
+                        self.cancel_dependents(graph, segment_id - 1, &mut started, "was cancelled");
+                        self.save_status()?;
+                    }
+                    continue;
+                }
+            };
+            let (segment_id, completed) = match joined {
+                Ok((task_id, Ok(final_status))) => {
+                    let segment_id = running_segments[&task_id];
+                    let completed = final_status.state == SegmentState::Completed;
+                    if completed {
+                        self.announce(format!("\n✅ Segment {} completed", segment_id));
+                    }
+                    self.update_segment(segment_id, final_status);
                     self.save_status()?;
+                    (segment_id, completed)
                 }
-                Ok(Err(e)) => {
+                Ok((task_id, Err(e))) => {
+                    let segment_id = running_segments[&task_id];
                     error!("Segment {} failed: {}", segment_id, e);
-                    let mut segment_status = self
-                        .status
-                        .segments
-                        .get(&segment_id)
-                        .cloned()
-                        .unwrap_or_else(|| SegmentStatus {
-                            segment_id,
-                            workspace: self
-                                .config
-                                .flock_workspace
-                                .join(format!("segment-{}", segment_id)),
-                            state: SegmentState::Failed,
-                            started_at: Utc::now(),
-                            completed_at: Some(Utc::now()),
-                            tokens_used: 0,
-                            tool_calls: 0,
-                            errors: 1,
-                            current_turn: 0,
-                            max_turns: self.config.max_turns,
-                            last_message: None,
-                            error_message: Some(e.to_string()),
-                        });
-                    segment_status.state = SegmentState::Failed;
-                    segment_status.completed_at = Some(Utc::now());
-                    segment_status.error_message = Some(e.to_string());
-                    segment_status.errors += 1;
-                    self.status.update_segment(segment_id, segment_status);
-                    self.save_status()?;
+                    self.record_segment_failure(segment_id, e.to_string())?;
+                    (segment_id, false)
                 }
                 Err(e) => {
+                    let segment_id = running_segments[&e.id()];
                     error!("Segment {} task panicked: {}", segment_id, e);
-                    let mut segment_status = self
-                        .status
-                        .segments
-                        .get(&segment_id)
-                        .cloned()
-                        .unwrap_or_else(|| SegmentStatus {
-                            segment_id,
-                            workspace: self
-                                .config
-                                .flock_workspace
-                                .join(format!("segment-{}", segment_id)),
-                            state: SegmentState::Failed,
-                            started_at: Utc::now(),
-                            completed_at: Some(Utc::now()),
-                            tokens_used: 0,
-                            tool_calls: 0,
-                            errors: 1,
-                            current_turn: 0,
-                            max_turns: self.config.max_turns,
-                            last_message: None,
-                            error_message: Some(format!("Task panicked: {}", e)),
-                        });
-                    segment_status.state = SegmentState::Failed;
-                    segment_status.completed_at = Some(Utc::now());
-                    segment_status.error_message = Some(format!("Task panicked: {}", e));
-                    segment_status.errors += 1;
-                    self.status.update_segment(segment_id, segment_status);
-                    self.save_status()?;
+                    self.record_segment_failure(segment_id, format!("Task panicked: {}", e))?;
+                    (segment_id, false)
+                }
+            };
+            cancel_senders.remove(&segment_id);
+
+            let index = segment_id - 1;
+            let cancelled = self
+                .status
+                .segments
+                .get(&segment_id)
+                .is_some_and(|s| s.state == SegmentState::Cancelled);
+            if cancelled {
+                self.announce(format!("\n⏹️  Segment {} cancelled", segment_id));
+                self.cancel_dependents(graph, index, &mut started, "was cancelled");
+            } else if !completed && retries[index] < self.config.max_retries {
+                // Rerun in the same workspace, keeping the failure for the report
+                retries[index] += 1;
+                started[index] = false;
+                self.announce(format!(
+                    "   🔁 Retrying segment {} ({} of {})",
+                    segment_id, retries[index], self.config.max_retries
+                ));
+                if let Some(mut segment_status) = self.status.segments.get(&segment_id).cloned() {
+                    segment_status.state = SegmentState::Pending;
+                    segment_status.last_message = Some("Queued for retry".to_string());
+                    self.update_segment(segment_id, segment_status);
+                }
+            } else if !completed {
+                // Nothing that builds on a failed segment can run
+                self.cancel_dependents(graph, index, &mut started, "failed");
+            } else {
+                // Unblock dependents whose dependencies are now all complete
+                for dependent in graph.transitive_dependents(index) {
+                    if !started[dependent] && self.dependencies_completed(graph, dependent) {
+                        let mut segment_status = self.new_segment_status(dependent + 1);
+                        segment_status.last_message = Some("Queued".to_string());
+                        self.update_segment(dependent + 1, segment_status);
+                    }
                 }
             }
+            self.save_status()?;
         }
 
+        send_event(&self.events, FlockEvent::SegmentsFinished);
+        // Let a dashboard restore the terminal before integration output
+        if let Some(ref events) = self.events {
+            events.closed().await;
+        }
+        Ok(())
+    }
+
+    /// Cancel the segments that build on `index` and have not started
+    fn cancel_dependents(
+        &mut self,
+        graph: &SegmentGraph,
+        index: usize,
+        started: &mut [bool],
+        reason: &str,
+    ) {
+        let module_name = self.status.partitions[index].module_name.clone();
+        for dependent in graph.transitive_dependents(index) {
+            if started[dependent] {
+                continue;
+            }
+            started[dependent] = true;
+            let mut segment_status = self.new_segment_status(dependent + 1);
+            segment_status.state = SegmentState::Cancelled;
+            segment_status.completed_at = Some(Utc::now());
+            segment_status.error_message = Some(format!("Dependency {} {}", module_name, reason));
+            self.update_segment(dependent + 1, segment_status);
+        }
+    }
+
+    /// Record a segment's status and report it to the event receiver
+    fn update_segment(&mut self, segment_id: usize, segment_status: SegmentStatus) {
+        send_event(
+            &self.events,
+            FlockEvent::SegmentUpdated(segment_status.clone()),
+        );
+        self.status.update_segment(segment_id, segment_status);
+    }
+
+    /// Print a progress line, unless an event receiver takes it
+    fn announce(&self, message: String) {
+        if !send_event(&self.events, FlockEvent::Message(message.clone())) {
+            println!("{}", message);
+        }
+    }
+
+    /// Whether every segment that `index` depends on has completed
+    fn dependencies_completed(&self, graph: &SegmentGraph, index: usize) -> bool {
+        graph.dependencies(index).iter().all(|&dependency| {
+            self.status
+                .segments
+                .get(&(dependency + 1))
+                .is_some_and(|s| s.state == SegmentState::Completed)
+        })
+    }
+
+    /// Merge the work of a segment's dependencies into its workspace and
+    /// tell its agent about them
+    async fn seed_from_upstream(&self, graph: &SegmentGraph, index: usize) -> Result<()> {
+        let integrator = self.integrator()?;
+        let segment = self.segment_work(index);
+        let upstream: Vec<SegmentWork> = graph
+            .dependencies(index)
+            .iter()
+            .map(|&dependency| self.segment_work(dependency))
+            .collect();
+
+        let mut section = String::from(UPSTREAM_SECTION);
+        section.push_str(
+            "These modules were built first by other agents and their work has been merged \
+            into this workspace. Build on them instead of reimplementing them.\n",
+        );
+        for dependency in &upstream {
+            integrator.seed_segment(&segment, dependency).await?;
+            section.push_str(&format!(
+                "\n### {} (segment {})\n\n{}\n",
+                dependency.partition.module_name,
+                dependency.segment_id,
+                segment_changes(dependency, self.segment_base()).await
+            ));
+        }
+
+        let requirements_path = segment.workspace.join("segment-requirements.md");
+        let mut requirements = std::fs::read_to_string(&requirements_path)?;
+        // A retried or resumed segment is seeded again
+        if let Some(start) = requirements.find(UPSTREAM_SECTION) {
+            requirements.truncate(start);
+        }
+        requirements.push_str(&section);
+        std::fs::write(&requirements_path, requirements)?;
+        Ok(())
+    }
+
+    /// Mark a segment as failed, keeping whatever progress was recorded
+    fn record_segment_failure(&mut self, segment_id: usize, message: String) -> Result<()> {
+        let mut segment_status = self
+            .status
+            .segments
+            .get(&segment_id)
+            .cloned()
+            .unwrap_or_else(|| self.new_segment_status(segment_id));
+        segment_status.state = SegmentState::Failed;
+        segment_status.completed_at = Some(Utc::now());
+        segment_status.error_message = Some(message);
+        segment_status.errors += 1;
+        self.update_segment(segment_id, segment_status);
+        self.save_status()
+    }
+
+    fn new_segment_status(&self, segment_id: usize) -> SegmentStatus {
+        SegmentStatus {
+            segment_id,
+            workspace: self.segment_dir(segment_id),
+            state: SegmentState::Pending,
+            started_at: Utc::now(),
+            completed_at: None,
+            tokens_used: 0,
+            tool_calls: 0,
+            errors: 0,
+            current_turn: 0,
+            max_turns: self.config.max_turns,
+            last_message: None,
+            error_message: None,
+        }
+    }
+
+    fn module_names_in_order(&self) -> Vec<String> {
+        self.status
+            .partitions
+            .iter()
+            .map(|p| p.module_name.clone())
+            .collect()
+    }
+
+    fn module_names(&self, indices: &[usize]) -> String {
+        indices
+            .iter()
+            .map(|&i| self.status.partitions[i].module_name.as_str())
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    fn segment_dir(&self, segment_id: usize) -> PathBuf {
+        self.config
+            .flock_workspace
+            .join(format!("segment-{}", segment_id))
+    }
+
+    fn segment_work(&self, index: usize) -> SegmentWork {
+        let segment_id = index + 1;
+        SegmentWork {
+            segment_id,
+            workspace: self.segment_dir(segment_id),
+            partition: self.status.partitions[index].clone(),
+            completed: self
+                .status
+                .segments
+                .get(&segment_id)
+                .is_some_and(|s| s.state == SegmentState::Completed),
+        }
+    }
+
+    fn integrator(&self) -> Result<Integrator> {
+        let mut integrator = Integrator::new(
+            self.config.project_dir.clone(),
+            self.config.flock_workspace.join("integration"),
+            &self.session_id,
+            self.get_g3_binary()?,
+        );
+        if let Some(ref command) = self.config.test_command {
+            integrator = integrator.with_test_command(command.clone());
+        }
+        if self.status.worktree_base.is_some() {
+            integrator = integrator.with_segment_worktrees();
+        }
+        Ok(integrator)
+    }
+
+    /// Commit the segments were created at
+    fn segment_base(&self) -> &str {
+        self.status
+            .worktree_base
+            .as_deref()
+            .unwrap_or("origin/HEAD")
+    }
+
+    /// Remove the worktrees of completed segments, whose work is kept on
+    /// their branches
+    async fn remove_segment_worktrees(&self) -> Result<usize> {
+        let segments: Vec<SegmentWork> = (0..self.status.partitions.len())
+            .map(|index| self.segment_work(index))
+            .collect();
+        self.integrator()?.remove_segment_worktrees(&segments).await
+    }
+
+    /// Merge the segments' work into an integration branch of the project
+    async fn integrate_segments(&mut self) -> Result<()> {
+        let segments: Vec<SegmentWork> = (0..self.status.partitions.len())
+            .map(|index| self.segment_work(index))
+            .collect();
+        let integrator = self.integrator()?;
+
+        self.status.integration_branch = Some(integrator.integration_branch());
+        self.save_status()?;
+
+        self.status.merges = integrator.run(&segments).await?;
+        self.save_status()?;
+
         Ok(())
     }
 
@@ -606,7 +1098,7 @@ impl FlockMode {
 
     /// Get the status file path
     fn get_status_file_path(&self) -> PathBuf {
-        self.config.flock_workspace.join("flock-status.json")
+        self.config.flock_workspace.join(STATUS_FILE)
     }
 
     /// Save current status to file
@@ -616,7 +1108,15 @@ impl FlockMode {
     }
 }
 
+/// Load the status file of an earlier run in `flock_workspace`
+fn load_saved_status(flock_workspace: &Path) -> Result<FlockStatus> {
+    let status_file = flock_workspace.join(STATUS_FILE);
+    FlockStatus::load_from_file(&status_file)
+        .with_context(|| format!("Failed to load {}", status_file.display()))
+}
+
 /// Run a single segment worker
+#[allow(clippy::too_many_arguments)]
 async fn run_segment(
     segment_id: usize,
     segment_dir: PathBuf,
@@ -624,6 +1124,8 @@ async fn run_segment(
     g3_binary: PathBuf,
     status_file: PathBuf,
     session_id: String,
+    events: Option<mpsc::UnboundedSender<FlockEvent>>,
+    mut cancel: oneshot::Receiver<()>,
 ) -> Result<SegmentStatus> {
     info!(
         "Starting segment {} in {}",
@@ -645,6 +1147,7 @@ async fn run_segment(
         last_message: Some("Starting autonomous mode...".to_string()),
         error_message: None,
     };
+    send_event(&events, FlockEvent::SegmentUpdated(segment_status.clone()));
 
     // Run g3 in autonomous mode with segment-requirements.md
     let mut child = Command::new(&g3_binary)
@@ -672,49 +1175,79 @@ async fn run_segment(
 
     let mut stdout_lines = stdout_reader.lines();
     let mut stderr_lines = stderr_reader.lines();
+    let mut stdout_open = true;
+    let mut stderr_open = true;
+    let mut cancelled = false;
 
-    // Read output and update status
-    loop {
+    // Read output and update status until both streams are closed
+    while stdout_open || stderr_open {
         tokio::select! {
-            line = stdout_lines.next_line() => {
+            line = stdout_lines.next_line(), if stdout_open => {
                 match line {
                     Ok(Some(line)) => {
-                        println!("[Segment {}] {}", segment_id, line);
+                        let event = FlockEvent::SegmentOutput {
+                            segment_id,
+                            line: line.clone(),
+                            is_error: false,
+                        };
+                        if !send_event(&events, event) {
+                            println!("[Segment {}] {}", segment_id, line);
+                        }
 
                         // Parse output for status updates
                         if line.contains("TURN") {
                             // Extract turn number if possible
                             if let Some(turn_str) = line.split("TURN").nth(1) {
                                 if let Ok(turn) = turn_str.trim().split('/').next().unwrap_or("0").parse::<usize>() {
+                                    if turn != segment_status.current_turn {
+                                        read_session_metrics(&segment_dir, &mut segment_status);
+                                    }
                                     segment_status.current_turn = turn;
                                 }
                             }
                         }
 
                         segment_status.last_message = Some(line);
+                        send_event(&events, FlockEvent::SegmentUpdated(segment_status.clone()));
                         update_status_file(&status_file, &session_id, segment_status.clone())?;
                     }
-                    Ok(None) => break,
+                    Ok(None) => stdout_open = false,
                     Err(e) => {
                         error!("Error reading stdout for segment {}: {}", segment_id, e);
-                        break;
+                        stdout_open = false;
                     }
                 }
             }
-            line = stderr_lines.next_line() => {
+            line = stderr_lines.next_line(), if stderr_open => {
                 match line {
                     Ok(Some(line)) => {
-                        eprintln!("[Segment {} ERROR] {}", segment_id, line);
+                        let event = FlockEvent::SegmentOutput {
+                            segment_id,
+                            line: line.clone(),
+                            is_error: true,
+                        };
+                        if !send_event(&events, event) {
+                            eprintln!("[Segment {} ERROR] {}", segment_id, line);
+                        }
                         segment_status.errors += 1;
+                        send_event(&events, FlockEvent::SegmentUpdated(segment_status.clone()));
                         update_status_file(&status_file, &session_id, segment_status.clone())?;
                     }
-                    Ok(None) => break,
+                    Ok(None) => stderr_open = false,
                     Err(e) => {
                         error!("Error reading stderr for segment {}: {}", segment_id, e);
-                        break;
+                        stderr_open = false;
                     }
                 }
             }
+            Ok(()) = &mut cancel => {
+                info!("Cancelling segment {}", segment_id);
+                if let Err(e) = child.kill().await {
+                    warn!("Failed to kill segment {}: {}", segment_id, e);
+                }
+                cancelled = true;
+                break;
+            }
         }
     }
 
@@ -726,7 +1259,10 @@ async fn run_segment(
 
     segment_status.completed_at = Some(Utc::now());
 
-    if status.success() {
+    if cancelled {
+        segment_status.state = SegmentState::Cancelled;
+        segment_status.last_message = Some("Cancelled by user".to_string());
+    } else if status.success() {
         segment_status.state = SegmentState::Completed;
         segment_status.last_message = Some("Completed successfully".to_string());
     } else {
@@ -735,7 +1271,20 @@ async fn run_segment(
         segment_status.errors += 1;
     }
 
-    // Try to extract metrics from session log if available
+    read_session_metrics(&segment_dir, &mut segment_status);
+    update_status_file(&status_file, &session_id, segment_status.clone())?;
+
+    Ok(segment_status)
+}
+
+/// Send an event if there is a receiver, returning whether it was delivered
+fn send_event(events: &Option<mpsc::UnboundedSender<FlockEvent>>, event: FlockEvent) -> bool {
+    events.as_ref().is_some_and(|tx| tx.send(event).is_ok())
+}
+
+/// Extract token usage and tool calls from the segment's session log, if
+/// there is one
+fn read_session_metrics(segment_dir: &Path, segment_status: &mut SegmentStatus) {
     let log_dir = segment_dir.join("logs");
     if log_dir.exists() {
         if let Ok(entries) = std::fs::read_dir(&log_dir) {
@@ -776,10 +1325,6 @@ async fn run_segment(
             }
         }
     }
-
-    update_status_file(&status_file, &session_id, segment_status.clone())?;
-
-    Ok(segment_status)
 }
 
 /// Update the status file with new segment status
@@ -0,0 +1,551 @@
+//! Integration phase for flock mode - merging segment work back together
+//!
+//! Each segment's changes are committed on a `flock/<session>/segment-N`
+//! branch in its clone and fetched into the project repository. Segments
+//! created as worktrees of the project already work on that branch, so
+//! nothing is fetched. The branches are merged into
+//! `flock/<session>/integration`, which is checked out in a separate
+//! worktree so the project's own checkout is left alone. Modules are merged
+//! after the modules they depend on. Conflicts are handed to a resolver
+//! agent, and the merge is aborted if it leaves conflict markers.
+
+use anyhow::{Context, Result};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::process::Output;
+use tokio::process::Command;
+use tracing::{debug, info, warn};
+
+use crate::flock::Partition;
+use crate::status::{MergeOutcome, MergeState};
+
+/// Flock and agent bookkeeping kept out of segment commits
+const SEGMENT_EXCLUDES: &[&str] = &["segment-requirements.md", "logs", ".g3"];
+
+/// Committer used when the repository has no identity configured
+const FALLBACK_IDENTITY: &[&str] = &[
+    "-c",
+    "user.name=g3 flock",
+    "-c",
+    "user.email=flock@g3.local",
+];
+
+/// A segment whose work is to be merged
+#[derive(Debug, Clone)]
+pub struct SegmentWork {
+    /// Segment number
+    pub segment_id: usize,
+
+    /// Segment clone or worktree of the project
+    pub workspace: PathBuf,
+
+    /// The module the segment implemented
+    pub partition: Partition,
+
+    /// Whether the segment's agent finished successfully
+    pub completed: bool,
+}
+
+/// Merges segment branches into an integration branch of the project
+#[derive(Debug, Clone)]
+pub struct Integrator {
+    project_dir: PathBuf,
+    worktree: PathBuf,
+    branch_prefix: String,
+    g3_binary: PathBuf,
+    test_command: Option<String>,
+    segment_worktrees: bool,
+}
+
+impl Integrator {
+    /// Create an integrator that checks the integration branch out at `worktree`
+    pub fn new(
+        project_dir: PathBuf,
+        worktree: PathBuf,
+        session_id: &str,
+        g3_binary: PathBuf,
+    ) -> Self {
+        let short_id: String = session_id.chars().take(8).collect();
+        Self {
+            project_dir,
+            worktree,
+            branch_prefix: format!("flock/{}", short_id),
+            g3_binary,
+            test_command: None,
+            segment_worktrees: false,
+        }
+    }
+
+    /// Run `command` with `sh -c` in the worktree after each merge
+    pub fn with_test_command(mut self, command: impl Into<String>) -> Self {
+        self.test_command = Some(command.into());
+        self
+    }
+
+    /// The segments are worktrees of the project, so their branches are
+    /// already in the project repository
+    pub fn with_segment_worktrees(mut self) -> Self {
+        self.segment_worktrees = true;
+        self
+    }
+
+    /// Branch the segments are merged into
+    pub fn integration_branch(&self) -> String {
+        format!("{}/integration", self.branch_prefix)
+    }
+
+    /// Branch holding a segment's work
+    pub fn segment_branch(&self, segment_id: usize) -> String {
+        format!("{}/segment-{}", self.branch_prefix, segment_id)
+    }
+
+    /// Merge a completed upstream segment's work into `segment`'s clone so
+    /// that its agent starts from the code it depends on
+    pub async fn seed_segment(&self, segment: &SegmentWork, upstream: &SegmentWork) -> Result<()> {
+        let branch = self.segment_branch(upstream.segment_id);
+        commit_segment(upstream, &branch).await?;
+
+        let source = upstream.workspace.to_string_lossy();
+        git(&segment.workspace, &["fetch", "--quiet", &source, &branch]).await?;
+
+        let message = format!("Merge {} ({})", branch, upstream.partition.module_name);
+        let mut args: Vec<&str> = identity_args(&segment.workspace).await;
+        args.extend(["merge", "--no-edit", "-m", &message, "FETCH_HEAD"]);
+        let merge = run_git(&segment.workspace, &args).await?;
+        if !merge.status.success() {
+            let _ = run_git(&segment.workspace, &["merge", "--abort"]).await;
+            anyhow::bail!(
+                "could not merge {}: {}",
+                branch,
+                String::from_utf8_lossy(&merge.stderr).trim()
+            );
+        }
+        Ok(())
+    }
+
+    /// Check out a segment's branch in a new worktree of the project at
+    /// `dir`. The branch is created at `base`, or reset to it unless
+    /// `keep_branch` is set and the branch already exists.
+    pub async fn add_segment_worktree(
+        &self,
+        segment_id: usize,
+        dir: &Path,
+        base: &str,
+        keep_branch: bool,
+    ) -> Result<()> {
+        // Forget worktrees whose directories were deleted
+        git(&self.project_dir, &["worktree", "prune"]).await?;
+
+        let branch = self.segment_branch(segment_id);
+        let dir = dir.to_string_lossy();
+        let branch_ref = format!("refs/heads/{}", branch);
+        let exists = run_git(
+            &self.project_dir,
+            &["rev-parse", "--verify", "--quiet", &branch_ref],
+        )
+        .await?
+        .status
+        .success();
+        let args: Vec<&str> = if keep_branch && exists {
+            vec!["worktree", "add", "--quiet", &dir, &branch]
+        } else {
+            vec!["worktree", "add", "--quiet", "-B", &branch, &dir, base]
+        };
+        git(&self.project_dir, &args)
+            .await
+            .with_context(|| format!("Failed to create worktree for segment {}", segment_id))?;
+        Ok(())
+    }
+
+    /// Commit the work of completed segments onto their branches and remove
+    /// their worktrees; segments that did not complete are kept so the run
+    /// can be resumed. Returns the number of worktrees removed.
+    pub async fn remove_segment_worktrees(&self, segments: &[SegmentWork]) -> Result<usize> {
+        let mut removed = 0;
+        for segment in segments {
+            if !segment.completed || !segment.workspace.exists() {
+                continue;
+            }
+            commit_segment(segment, &self.segment_branch(segment.segment_id)).await?;
+            let dir = segment.workspace.to_string_lossy();
+            git(&self.project_dir, &["worktree", "remove", "--force", &dir]).await?;
+            removed += 1;
+        }
+        git(&self.project_dir, &["worktree", "prune"]).await?;
+        Ok(removed)
+    }
+
+    /// Commit and merge every segment, dependencies first
+    pub async fn run(&self, segments: &[SegmentWork]) -> Result<Vec<MergeOutcome>> {
+        self.create_worktree().await?;
+        println!(
+            "   Integration branch {} at {}",
+            self.integration_branch(),
+            self.worktree.display()
+        );
+
+        let partitions: Vec<Partition> = segments.iter().map(|s| s.partition.clone()).collect();
+        let mut merged: Vec<String> = Vec::new();
+        let mut not_merged: HashSet<String> = HashSet::new();
+        let mut outcomes = Vec::new();
+
+        for index in merge_order(&partitions) {
+            let segment = &segments[index];
+            let outcome = self.integrate_segment(segment, &merged, &not_merged).await;
+            println!(
+                "   {} segment {} ({})",
+                outcome.state, segment.segment_id, segment.partition.module_name
+            );
+            if outcome.state.is_merged() {
+                merged.push(segment.partition.module_name.clone());
+            } else {
+                not_merged.insert(segment.partition.module_name.clone());
+            }
+            outcomes.push(outcome);
+        }
+
+        Ok(outcomes)
+    }
+
+    async fn integrate_segment(
+        &self,
+        segment: &SegmentWork,
+        merged: &[String],
+        not_merged: &HashSet<String>,
+    ) -> MergeOutcome {
+        let mut outcome = MergeOutcome {
+            segment_id: segment.segment_id,
+            module_name: segment.partition.module_name.clone(),
+            branch: self.segment_branch(segment.segment_id),
+            state: MergeState::Skipped,
+            conflicted_files: Vec::new(),
+            tests_passed: None,
+            message: None,
+        };
+
+        if !segment.completed {
+            outcome.message = Some("Segment did not complete".to_string());
+            return outcome;
+        }
+        if let Some(dependency) = segment
+            .partition
+            .dependencies
+            .iter()
+            .find(|d| not_merged.contains(*d))
+        {
+            outcome.message = Some(format!("Dependency {} was not merged", dependency));
+            return outcome;
+        }
+
+        if let Err(e) = self.merge_segment(segment, merged, &mut outcome).await {
+            warn!("Merging segment {} failed: {}", segment.segment_id, e);
+            // Leave the worktree clean for the next segment
+            let _ = run_git(&self.worktree, &["merge", "--abort"]).await;
+            outcome.state = MergeState::Failed;
+            outcome.message = Some(e.to_string());
+        }
+        outcome
+    }
+
+    async fn merge_segment(
+        &self,
+        segment: &SegmentWork,
+        merged: &[String],
+        outcome: &mut MergeOutcome,
+    ) -> Result<()> {
+        let branch = outcome.branch.clone();
+        commit_segment(segment, &branch).await?;
+
+        // Bring the segment branch into the project repository
+        if !self.segment_worktrees {
+            let source = segment.workspace.to_string_lossy();
+            let refspec = format!("+{0}:{0}", branch);
+            git(&self.project_dir, &["fetch", "--quiet", &source, &refspec]).await?;
+        }
+
+        let ahead = git(
+            &self.worktree,
+            &["rev-list", "--count", &format!("HEAD..{}", branch)],
+        )
+        .await?;
+        if ahead.trim() == "0" {
+            outcome.state = MergeState::Merged;
+            outcome.message = Some("Already up to date".to_string());
+            return Ok(());
+        }
+
+        let message = format!("Merge {} ({})", branch, segment.partition.module_name);
+        let mut args: Vec<&str> = identity_args(&self.worktree).await;
+        args.extend(["merge", "--no-ff", "--no-edit", "-m", &message, &branch]);
+        let merge = run_git(&self.worktree, &args).await?;
+
+        if merge.status.success() {
+            outcome.state = MergeState::Merged;
+        } else {
+            let conflicted = git(&self.worktree, &["diff", "--name-only", "--diff-filter=U"])
+                .await?
+                .lines()
+                .map(str::to_string)
+                .collect::<Vec<_>>();
+            if conflicted.is_empty() {
+                anyhow::bail!(
+                    "git merge failed: {}",
+                    String::from_utf8_lossy(&merge.stderr)
+                );
+            }
+            outcome.conflicted_files = conflicted;
+
+            println!(
+                "   ⚔️  {} conflicting file(s) merging segment {}; starting resolver agent...",
+                outcome.conflicted_files.len(),
+                segment.segment_id
+            );
+            if !self
+                .resolve_conflicts(segment, &outcome.conflicted_files, merged)
+                .await?
+            {
+                git(&self.worktree, &["merge", "--abort"]).await?;
+                outcome.state = MergeState::Conflicted;
+                outcome.message =
+                    Some("Resolver agent did not resolve the conflicts; merge aborted".to_string());
+                return Ok(());
+            }
+
+            git(&self.worktree, &["add", "-u"]).await?;
+            let mut args: Vec<&str> = identity_args(&self.worktree).await;
+            args.extend(["commit", "--no-edit", "--quiet"]);
+            git(&self.worktree, &args).await?;
+            outcome.state = MergeState::Resolved;
+        }
+
+        if let Some(ref command) = self.test_command {
+            let passed = self.run_tests(command).await?;
+            println!(
+                "   🧪 Tests {} after merging segment {}",
+                if passed { "passed" } else { "failed" },
+                segment.segment_id
+            );
+            outcome.tests_passed = Some(passed);
+        }
+
+        Ok(())
+    }
+
+    /// Run a g3 agent in the worktree to resolve the conflicts; returns
+    /// whether every conflicted file is free of conflict markers afterwards
+    async fn resolve_conflicts(
+        &self,
+        segment: &SegmentWork,
+        conflicted: &[String],
+        merged: &[String],
+    ) -> Result<bool> {
+        let prompt = format!(
+            "You are resolving git merge conflicts in this repository. The branch for module '{}' \
+            is being merged into an integration branch that already contains these modules: {}.\n\n\
+            FILES WITH CONFLICT MARKERS:\n{}\n\n\
+            REQUIREMENTS OF THE MODULE BEING MERGED:\n{}\n\n\
+            INSTRUCTIONS:\n\
+            1. Edit each file so that it keeps the intent of both sides\n\
+            2. Remove every conflict marker (<<<<<<<, =======, >>>>>>>)\n\
+            3. Make sure the code still builds and its tests pass\n\
+            4. Do not run git commit, git merge or git checkout; the merge is completed for you",
+            segment.partition.module_name,
+            if merged.is_empty() {
+                "none".to_string()
+            } else {
+                merged.join(", ")
+            },
+            conflicted
+                .iter()
+                .map(|f| format!("- {}", f))
+                .collect::<Vec<_>>()
+                .join("\n"),
+            segment.partition.requirements
+        );
+
+        let output = Command::new(&self.g3_binary)
+            .arg("--workspace")
+            .arg(&self.worktree)
+            .arg("--quiet")
+            .arg(&prompt)
+            .current_dir(&self.worktree)
+            .output()
+            .await
+            .context("Failed to run g3 resolver agent")?;
+        debug!(
+            "Resolver agent output: {}",
+            String::from_utf8_lossy(&output.stdout)
+        );
+        if !output.status.success() {
+            warn!(
+                "Resolver agent failed: {}",
+                String::from_utf8_lossy(&output.stderr)
+            );
+            return Ok(false);
+        }
+
+        for file in conflicted {
+            // A file the agent deleted has no markers left
+            if let Ok(content) = std::fs::read_to_string(self.worktree.join(file)) {
+                if has_conflict_markers(&content) {
+                    info!("Conflict markers remain in {}", file);
+                    return Ok(false);
+                }
+            }
+        }
+        Ok(true)
+    }
+
+    async fn run_tests(&self, command: &str) -> Result<bool> {
+        let output = Command::new("sh")
+            .arg("-c")
+            .arg(command)
+            .current_dir(&self.worktree)
+            .output()
+            .await
+            .context("Failed to run test command")?;
+        if !output.status.success() {
+            debug!(
+                "Test command failed: {}{}",
+                String::from_utf8_lossy(&output.stdout),
+                String::from_utf8_lossy(&output.stderr)
+            );
+        }
+        Ok(output.status.success())
+    }
+
+    /// Check out a fresh integration branch at the project's HEAD
+    async fn create_worktree(&self) -> Result<()> {
+        let worktree = self.worktree.to_string_lossy();
+        if self.worktree.exists() {
+            // Left over from an earlier run in the same flock workspace
+            let _ = run_git(
+                &self.project_dir,
+                &["worktree", "remove", "--force", &worktree],
+            )
+            .await;
+            if self.worktree.exists() {
+                std::fs::remove_dir_all(&self.worktree)?;
+            }
+            git(&self.project_dir, &["worktree", "prune"]).await?;
+        }
+
+        let branch = self.integration_branch();
+        git(
+            &self.project_dir,
+            &[
+                "worktree", "add", "--quiet", "-B", &branch, &worktree, "HEAD",
+            ],
+        )
+        .await
+        .context("Failed to create integration worktree")?;
+        Ok(())
+    }
+}
+
+/// Order in which to merge partitions: every module after the modules it
+/// depends on, otherwise in partition order. Dependencies on unknown modules
+/// are ignored and cycles are broken at the earliest partition.
+pub fn merge_order(partitions: &[Partition]) -> Vec<usize> {
+    let names: HashSet<&str> = partitions.iter().map(|p| p.module_name.as_str()).collect();
+    let mut placed = vec![false; partitions.len()];
+    let mut order = Vec::with_capacity(partitions.len());
+
+    while order.len() < partitions.len() {
+        let is_ready = |i: usize| {
+            partitions[i].dependencies.iter().all(|dependency| {
+                !names.contains(dependency.as_str())
+                    || partitions
+                        .iter()
+                        .zip(&placed)
+                        .all(|(p, &done)| done || p.module_name != *dependency)
+            })
+        };
+        let next = (0..partitions.len())
+            .find(|&i| !placed[i] && is_ready(i))
+            .or_else(|| (0..partitions.len()).find(|&i| !placed[i]))
+            .expect("an unplaced partition remains");
+        placed[next] = true;
+        order.push(next);
+    }
+
+    order
+}
+
+/// Commit everything the segment's agent left uncommitted onto `branch`
+async fn commit_segment(segment: &SegmentWork, branch: &str) -> Result<()> {
+    let dir = &segment.workspace;
+
+    git(dir, &["checkout", "--quiet", "-B", branch]).await?;
+    // Pathspecs rather than info/exclude, which worktrees share with the
+    // project's own checkout
+    let excludes: Vec<String> = SEGMENT_EXCLUDES
+        .iter()
+        .map(|pattern| format!(":(exclude){}", pattern))
+        .collect();
+    let mut args = vec!["add", "-A", "--", "."];
+    args.extend(excludes.iter().map(String::as_str));
+    git(dir, &args).await?;
+    let staged = run_git(dir, &["diff", "--cached", "--quiet"]).await?;
+    if !staged.status.success() {
+        let message = format!(
+            "flock: {} (segment {})",
+            segment.partition.module_name, segment.segment_id
+        );
+        let mut args: Vec<&str> = identity_args(dir).await;
+        args.extend(["commit", "--quiet", "-m", &message]);
+        git(dir, &args).await?;
+    }
+    Ok(())
+}
+
+/// `git diff --stat` of a segment's work against `base`, the commit it was
+/// created at
+pub async fn segment_changes(segment: &SegmentWork, base: &str) -> String {
+    match git(&segment.workspace, &["diff", "--stat", base, "HEAD"]).await {
+        Ok(stat) if !stat.trim().is_empty() => stat.trim_end().to_string(),
+        Ok(_) => "No file changes".to_string(),
+        Err(e) => {
+            debug!("Cannot diff segment {}: {}", segment.segment_id, e);
+            "File changes unavailable".to_string()
+        }
+    }
+}
+
+/// Whether a file still contains git conflict markers
+pub fn has_conflict_markers(content: &str) -> bool {
+    content
+        .lines()
+        .any(|line| line.starts_with("<<<<<<< ") || line.starts_with(">>>>>>> "))
+}
+
+/// `-c` options setting a committer when the repository has none
+async fn identity_args(dir: &Path) -> Vec<&'static str> {
+    match git(dir, &["config", "user.email"]).await {
+        Ok(email) if !email.trim().is_empty() => Vec::new(),
+        _ => FALLBACK_IDENTITY.to_vec(),
+    }
+}
+
+/// Run git in `dir`, returning its output whether or not it succeeded
+async fn run_git(dir: &Path, args: &[&str]) -> Result<Output> {
+    Command::new("git")
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .await
+        .with_context(|| format!("Failed to run git {}", args.join(" ")))
+}
+
+/// Run git in `dir` and return its stdout, failing if git does
+async fn git(dir: &Path, args: &[&str]) -> Result<String> {
+    let output = run_git(dir, args).await?;
+    if !output.status.success() {
+        anyhow::bail!(
+            "git {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
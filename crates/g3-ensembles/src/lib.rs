@@ -3,10 +3,16 @@
 //! This crate provides functionality for running multiple G3 agents in coordination,
 //! enabling parallel development across different architectural modules.
 
+pub mod events;
 pub mod flock;
+pub mod integration;
+pub mod schedule;
 pub mod status;
 mod tests;
 
 /// Re-export main types for convenience
-pub use flock::{FlockConfig, FlockMode};
-pub use status::{FlockStatus, SegmentStatus};
+pub use events::{FlockEvent, SegmentCanceller};
+pub use flock::{FlockConfig, FlockMode, Partition};
+pub use integration::{Integrator, SegmentWork};
+pub use schedule::SegmentGraph;
+pub use status::{FlockStatus, MergeOutcome, MergeState, SegmentStatus};
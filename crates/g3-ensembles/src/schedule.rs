@@ -0,0 +1,128 @@
+//! Dependency graph used to schedule flock segments
+//!
+//! Partitions name the modules they depend on. A segment is started once
+//! every segment it depends on has completed; segments without dependencies
+//! start immediately. Dependencies on modules that are not among the
+//! partitions are ignored.
+
+use anyhow::Result;
+use std::collections::HashMap;
+use tracing::debug;
+
+use crate::flock::Partition;
+
+/// Dependencies between partitions, by partition index
+#[derive(Debug, Clone)]
+pub struct SegmentGraph {
+    names: Vec<String>,
+    dependencies: Vec<Vec<usize>>,
+}
+
+impl SegmentGraph {
+    /// Build the graph, failing if the dependencies form a cycle
+    pub fn new(partitions: &[Partition]) -> Result<Self> {
+        let mut index_of: HashMap<&str, usize> = HashMap::new();
+        for (i, partition) in partitions.iter().enumerate() {
+            index_of.entry(partition.module_name.as_str()).or_insert(i);
+        }
+
+        let dependencies = partitions
+            .iter()
+            .map(|partition| {
+                let mut indices = Vec::new();
+                for name in &partition.dependencies {
+                    match index_of.get(name.as_str()) {
+                        Some(&i) if !indices.contains(&i) => indices.push(i),
+                        Some(_) => {}
+                        None => debug!(
+                            "Ignoring unknown dependency {} of {}",
+                            name, partition.module_name
+                        ),
+                    }
+                }
+                indices
+            })
+            .collect();
+
+        let graph = Self {
+            names: partitions.iter().map(|p| p.module_name.clone()).collect(),
+            dependencies,
+        };
+        if let Some(cycle) = graph.find_cycle() {
+            let path: Vec<&str> = cycle.iter().map(|&i| graph.names[i].as_str()).collect();
+            anyhow::bail!("Partition dependencies form a cycle: {}", path.join(" -> "));
+        }
+        Ok(graph)
+    }
+
+    /// Number of partitions
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    /// Whether there are no partitions
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+
+    /// Partitions that `index` depends on
+    pub fn dependencies(&self, index: usize) -> &[usize] {
+        &self.dependencies[index]
+    }
+
+    /// Partitions that depend on `index`, directly or through others
+    pub fn transitive_dependents(&self, index: usize) -> Vec<usize> {
+        let mut found = Vec::new();
+        let mut stack = vec![index];
+        while let Some(current) = stack.pop() {
+            for (i, deps) in self.dependencies.iter().enumerate() {
+                if deps.contains(&current) && !found.contains(&i) {
+                    found.push(i);
+                    stack.push(i);
+                }
+            }
+        }
+        found.sort_unstable();
+        found
+    }
+
+    /// A dependency cycle as a path that starts and ends at the same
+    /// partition, if there is one
+    pub fn find_cycle(&self) -> Option<Vec<usize>> {
+        // 0 = unvisited, 1 = on the current path, 2 = done
+        let mut marks = vec![0u8; self.len()];
+        let mut path = Vec::new();
+        for start in 0..self.len() {
+            if marks[start] == 0 {
+                if let Some(cycle) = self.visit(start, &mut marks, &mut path) {
+                    return Some(cycle);
+                }
+            }
+        }
+        None
+    }
+
+    fn visit(&self, node: usize, marks: &mut [u8], path: &mut Vec<usize>) -> Option<Vec<usize>> {
+        marks[node] = 1;
+        path.push(node);
+        for &dependency in &self.dependencies[node] {
+            match marks[dependency] {
+                1 => {
+                    let start = path.iter().position(|&n| n == dependency)?;
+                    let mut cycle = path[start..].to_vec();
+                    cycle.push(dependency);
+                    return Some(cycle);
+                }
+                0 => {
+                    if let Some(cycle) = self.visit(dependency, marks, path) {
+                        return Some(cycle);
+                    }
+                }
+                _ => {}
+            }
+        }
+        path.pop();
+        marks[node] = 2;
+        None
+    }
+}
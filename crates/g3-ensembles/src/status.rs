@@ -5,6 +5,8 @@ use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::path::PathBuf;
 
+use crate::flock::Partition;
+
 /// Status of an individual segment worker
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SegmentStatus {
@@ -51,6 +53,9 @@ pub enum SegmentState {
     /// Waiting to start
     Pending,
 
+    /// Waiting for the segments it depends on to complete
+    Blocked,
+
     /// Currently running
     Running,
 
@@ -60,7 +65,7 @@ pub enum SegmentState {
     /// Failed with error
     Failed,
 
-    /// Cancelled by user
+    /// Cancelled by user, or not started because a dependency failed
     Cancelled,
 }
 
@@ -68,6 +73,7 @@ impl std::fmt::Display for SegmentState {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             SegmentState::Pending => write!(f, "⏳ Pending"),
+            SegmentState::Blocked => write!(f, "🔒 Blocked"),
             SegmentState::Running => write!(f, "🔄 Running"),
             SegmentState::Completed => write!(f, "✅ Completed"),
             SegmentState::Failed => write!(f, "❌ Failed"),
@@ -76,6 +82,70 @@ impl std::fmt::Display for SegmentState {
     }
 }
 
+/// Result of merging one segment branch into the integration branch
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MergeOutcome {
+    /// Segment number
+    pub segment_id: usize,
+
+    /// Module name from the partitioning
+    pub module_name: String,
+
+    /// Segment branch that was merged
+    pub branch: String,
+
+    /// How the merge ended
+    pub state: MergeState,
+
+    /// Files that conflicted (resolved or not)
+    #[serde(default)]
+    pub conflicted_files: Vec<String>,
+
+    /// Whether the test command passed after the merge (if one was run)
+    pub tests_passed: Option<bool>,
+
+    /// Details, such as why a segment was skipped
+    pub message: Option<String>,
+}
+
+/// State of a segment merge
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub enum MergeState {
+    /// Merged without conflicts
+    Merged,
+
+    /// Conflicts were resolved by the resolver agent
+    Resolved,
+
+    /// Conflicts could not be resolved; the merge was aborted
+    Conflicted,
+
+    /// Not merged (segment failed, had no changes or a dependency was not merged)
+    Skipped,
+
+    /// Git failed for another reason
+    Failed,
+}
+
+impl MergeState {
+    /// Whether the segment's work is on the integration branch
+    pub fn is_merged(&self) -> bool {
+        matches!(self, MergeState::Merged | MergeState::Resolved)
+    }
+}
+
+impl std::fmt::Display for MergeState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MergeState::Merged => write!(f, "✅ Merged"),
+            MergeState::Resolved => write!(f, "🔧 Resolved"),
+            MergeState::Conflicted => write!(f, "⚔️  Conflicted"),
+            MergeState::Skipped => write!(f, "⏭️  Skipped"),
+            MergeState::Failed => write!(f, "❌ Failed"),
+        }
+    }
+}
+
 /// Overall flock status
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FlockStatus {
@@ -108,6 +178,27 @@ pub struct FlockStatus {
 
     /// Total errors across all segments
     pub total_errors: u64,
+
+    /// Partitioned requirements, segment N being at index N - 1
+    #[serde(default)]
+    pub partitions: Vec<Partition>,
+
+    /// Times each segment has been started, across retries and resumed runs
+    #[serde(default)]
+    pub attempts: HashMap<usize, u32>,
+
+    /// Branch the segments were merged into (once integration has started)
+    #[serde(default)]
+    pub integration_branch: Option<String>,
+
+    /// Merge outcomes, in merge order
+    #[serde(default)]
+    pub merges: Vec<MergeOutcome>,
+
+    /// Commit the segments were branched from when they are git worktrees
+    /// of the project rather than clones
+    #[serde(default)]
+    pub worktree_base: Option<String>,
 }
 
 impl FlockStatus {
@@ -129,6 +220,11 @@ impl FlockStatus {
             total_tokens: 0,
             total_tool_calls: 0,
             total_errors: 0,
+            partitions: Vec::new(),
+            attempts: HashMap::new(),
+            integration_branch: None,
+            merges: Vec::new(),
+            worktree_base: None,
         }
     }
 
@@ -190,6 +286,9 @@ impl FlockStatus {
             self.flock_workspace.display()
         ));
         report.push_str(&format!("\n🔢 Segments: {}", self.num_segments));
+        if let Some(ref base) = self.worktree_base {
+            report.push_str(&format!("\n🌳 Segment Worktrees: branched from {}", base));
+        }
 
         let duration = if let Some(completed) = self.completed_at {
             completed.signed_duration_since(self.started_at)
@@ -220,6 +319,10 @@ impl FlockStatus {
             "\n   • Pending: {}",
             self.count_by_state(SegmentState::Pending)
         ));
+        report.push_str(&format!(
+            "\n   • Blocked: {}",
+            self.count_by_state(SegmentState::Blocked)
+        ));
         report.push_str(&format!(
             "\n   • Cancelled: {}",
             self.count_by_state(SegmentState::Cancelled)
@@ -253,6 +356,9 @@ impl FlockStatus {
                 "\n      Turn: {}/{}",
                 segment.current_turn, segment.max_turns
             ));
+            if let Some(attempts) = self.attempts.get(id).filter(|&&n| n > 1) {
+                report.push_str(&format!("\n      Attempts: {}", attempts));
+            }
 
             if let Some(ref msg) = segment.last_message {
                 report.push_str(&format!("\n      Last Message: {}", msg));
@@ -263,6 +369,32 @@ impl FlockStatus {
             }
         }
 
+        // Integration
+        if let Some(ref branch) = self.integration_branch {
+            report.push_str(&format!("\n\n🔀 Integration Branch: {}", branch));
+            for merge in &self.merges {
+                report.push_str(&format!(
+                    "\n   • Segment {} ({}): {}",
+                    merge.segment_id, merge.module_name, merge.state
+                ));
+                if !merge.conflicted_files.is_empty() {
+                    report.push_str(&format!(
+                        "\n      Conflicts: {}",
+                        merge.conflicted_files.join(", ")
+                    ));
+                }
+                if let Some(passed) = merge.tests_passed {
+                    report.push_str(&format!(
+                        "\n      Tests: {}",
+                        if passed { "passed" } else { "failed" }
+                    ));
+                }
+                if let Some(ref msg) = merge.message {
+                    report.push_str(&format!("\n      {}", msg));
+                }
+            }
+        }
+
         report.push_str(&format!("\n\n{}", "=".repeat(80)));
 
         report
@@ -2,13 +2,17 @@
 
 #[cfg(test)]
 mod tests {
-    use crate::status::{FlockStatus, SegmentState, SegmentStatus};
+    use crate::flock::Partition;
+    use crate::integration::{has_conflict_markers, merge_order};
+    use crate::schedule::SegmentGraph;
+    use crate::status::{FlockStatus, MergeOutcome, MergeState, SegmentState, SegmentStatus};
     use chrono::Utc;
     use std::path::PathBuf;
 
     #[test]
     fn test_segment_state_display() {
         assert_eq!(format!("{}", SegmentState::Pending), "⏳ Pending");
+        assert_eq!(format!("{}", SegmentState::Blocked), "🔒 Blocked");
         assert_eq!(format!("{}", SegmentState::Running), "🔄 Running");
         assert_eq!(format!("{}", SegmentState::Completed), "✅ Completed");
         assert_eq!(format!("{}", SegmentState::Failed), "❌ Failed");
@@ -326,5 +330,149 @@ mod tests {
         assert!(report.contains("Total Tokens: 1000"));
         assert!(report.contains("Total Tool Calls: 50"));
         assert!(report.contains("Total Errors: 2"));
+        assert!(report.contains("Blocked: 0"));
+    }
+
+    fn partition(name: &str, dependencies: &[&str]) -> Partition {
+        Partition {
+            module_name: name.to_string(),
+            requirements: format!("Build {}", name),
+            dependencies: dependencies.iter().map(|d| d.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn test_merge_order_follows_dependencies() {
+        let partitions = vec![
+            partition("api-server", &["core-engine", "storage"]),
+            partition("core-engine", &[]),
+            partition("cli", &["api-server"]),
+            partition("storage", &["core-engine"]),
+        ];
+        assert_eq!(merge_order(&partitions), vec![1, 3, 0, 2]);
+
+        // Unknown dependencies are ignored
+        let partitions = vec![partition("a", &["serde"]), partition("b", &[])];
+        assert_eq!(merge_order(&partitions), vec![0, 1]);
+
+        // A cycle is broken at the earliest partition
+        let partitions = vec![
+            partition("a", &["b"]),
+            partition("b", &["a"]),
+            partition("c", &["b"]),
+        ];
+        assert_eq!(merge_order(&partitions), vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn test_segment_graph_dependencies() {
+        let graph = SegmentGraph::new(&[
+            partition("api-server", &["core-engine", "storage", "serde"]),
+            partition("core-engine", &[]),
+            partition("cli", &["api-server"]),
+            partition("storage", &["core-engine", "core-engine"]),
+        ])
+        .expect("acyclic dependencies");
+
+        assert_eq!(graph.len(), 4);
+        assert_eq!(graph.dependencies(0), &[1, 3]);
+        assert!(graph.dependencies(1).is_empty());
+        assert_eq!(graph.dependencies(3), &[1]);
+        assert_eq!(graph.transitive_dependents(1), vec![0, 2, 3]);
+        assert_eq!(graph.transitive_dependents(0), vec![2]);
+        assert!(graph.transitive_dependents(2).is_empty());
+    }
+
+    #[test]
+    fn test_segment_graph_rejects_cycles() {
+        let err = SegmentGraph::new(&[
+            partition("core", &[]),
+            partition("api", &["storage"]),
+            partition("storage", &["cli"]),
+            partition("cli", &["api", "core"]),
+        ])
+        .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Partition dependencies form a cycle: api -> storage -> cli -> api"
+        );
+
+        let err = SegmentGraph::new(&[partition("core", &["core"])]).unwrap_err();
+        assert!(err.to_string().ends_with("core -> core"));
+    }
+
+    #[test]
+    fn test_partition_requirements_markdown() {
+        assert_eq!(
+            partition("cli", &["core", "storage"]).to_requirements_markdown(),
+            "# Module: cli\n\n## Dependencies\ncore, storage\n\n## Requirements\n\nBuild cli"
+        );
+        assert!(partition("core", &[])
+            .to_requirements_markdown()
+            .contains("## Dependencies\nNone\n"));
+    }
+
+    #[test]
+    fn test_conflict_marker_detection() {
+        assert!(has_conflict_markers(
+            "fn a() {}\n<<<<<<< HEAD\nlet x = 1;\n=======\nlet x = 2;\n>>>>>>> flock/segment-2\n"
+        ));
+        assert!(!has_conflict_markers("Title\n=======\n\nText\n"));
+    }
+
+    #[test]
+    fn test_report_includes_merge_outcomes() {
+        let mut status = FlockStatus::new(
+            "test-session".to_string(),
+            PathBuf::from("/test/project"),
+            PathBuf::from("/test/workspace"),
+            2,
+        );
+        assert!(!status.generate_report().contains("Integration Branch"));
+        assert!(!status.generate_report().contains("Segment Worktrees"));
+
+        status.worktree_base = Some("abc123".to_string());
+        status.integration_branch = Some("flock/test-ses/integration".to_string());
+        status.merges = vec![
+            MergeOutcome {
+                segment_id: 1,
+                module_name: "core".to_string(),
+                branch: "flock/test-ses/segment-1".to_string(),
+                state: MergeState::Resolved,
+                conflicted_files: vec!["src/lib.rs".to_string()],
+                tests_passed: Some(true),
+                message: None,
+            },
+            MergeOutcome {
+                segment_id: 2,
+                module_name: "cli".to_string(),
+                branch: "flock/test-ses/segment-2".to_string(),
+                state: MergeState::Skipped,
+                conflicted_files: Vec::new(),
+                tests_passed: None,
+                message: Some("Segment did not complete".to_string()),
+            },
+        ];
+
+        let report = status.generate_report();
+        assert!(report.contains("Integration Branch: flock/test-ses/integration"));
+        assert!(report.contains("Segment 1 (core): 🔧 Resolved"));
+        assert!(report.contains("Conflicts: src/lib.rs"));
+        assert!(report.contains("Tests: passed"));
+        assert!(report.contains("Segment 2 (cli): ⏭️  Skipped"));
+        assert!(report.contains("Segment did not complete"));
+        assert!(report.contains("Segment Worktrees: branched from abc123"));
+
+        // Status files written before the integration phase existed still load
+        let mut json: serde_json::Value = serde_json::to_value(&status).unwrap();
+        json.as_object_mut().unwrap().remove("merges");
+        json.as_object_mut().unwrap().remove("integration_branch");
+        json.as_object_mut().unwrap().remove("partitions");
+        json.as_object_mut().unwrap().remove("attempts");
+        json.as_object_mut().unwrap().remove("worktree_base");
+        let old: FlockStatus = serde_json::from_value(json).expect("Failed to deserialize");
+        assert!(old.merges.is_empty());
+        assert!(old.integration_branch.is_none());
+        assert!(old.worktree_base.is_none());
     }
 }
@@ -1,6 +1,9 @@
 //! Integration tests for g3-ensembles flock mode
 
-use g3_ensembles::{FlockConfig, FlockMode};
+use g3_ensembles::status::SegmentState;
+use g3_ensembles::{
+    FlockConfig, FlockEvent, FlockMode, FlockStatus, Integrator, MergeState, Partition, SegmentWork,
+};
 use std::fs;
 use std::path::PathBuf;
 use std::process::Command;
@@ -491,3 +494,631 @@ fn test_git_repo_independence() {
     assert!(segment2.join("file2.txt").exists());
     assert!(!segment2.join("file1.txt").exists());
 }
+
+/// Clone the project into `workspace/segment-N`
+fn clone_segment(project_dir: &TempDir, workspace: &TempDir, segment_id: usize) -> PathBuf {
+    let segment = workspace.path().join(format!("segment-{}", segment_id));
+    let output = Command::new("git")
+        .arg("clone")
+        .arg(project_dir.path())
+        .arg(&segment)
+        .output()
+        .expect("Failed to clone segment");
+    assert!(output.status.success(), "git clone failed");
+    segment
+}
+
+/// Write an executable script that stands in for the g3 resolver agent
+#[cfg(unix)]
+fn fake_g3(workspace: &TempDir, script: &str) -> PathBuf {
+    use std::os::unix::fs::PermissionsExt;
+
+    let path = workspace.path().join("fake-g3");
+    fs::write(&path, format!("#!/bin/sh\n{}\n", script)).expect("Failed to write fake g3");
+    fs::set_permissions(&path, fs::Permissions::from_mode(0o755))
+        .expect("Failed to make fake g3 executable");
+    path
+}
+
+fn segment_work(segment_id: usize, workspace: PathBuf, module: &str, deps: &[&str]) -> SegmentWork {
+    SegmentWork {
+        segment_id,
+        workspace,
+        partition: Partition {
+            module_name: module.to_string(),
+            requirements: format!("Build the {} module", module),
+            dependencies: deps.iter().map(|d| d.to_string()).collect(),
+        },
+        completed: true,
+    }
+}
+
+fn git_stdout(dir: &std::path::Path, args: &[&str]) -> String {
+    let output = Command::new("git")
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .expect("Failed to run git");
+    String::from_utf8_lossy(&output.stdout).into_owned()
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_integration_merges_segments_in_dependency_order() {
+    let project_dir = create_test_project("integration-test");
+    let workspace_dir = TempDir::new().unwrap();
+
+    let segment1 = clone_segment(&project_dir, &workspace_dir, 1);
+    let segment2 = clone_segment(&project_dir, &workspace_dir, 2);
+    fs::write(segment1.join("cli.txt"), "cli").unwrap();
+    fs::write(segment2.join("core.txt"), "core").unwrap();
+    // Flock and agent bookkeeping must stay out of the segment commits
+    fs::write(segment2.join("segment-requirements.md"), "# Module: core").unwrap();
+    fs::create_dir_all(segment2.join("logs")).unwrap();
+    fs::write(segment2.join("logs").join("session.json"), "{}").unwrap();
+
+    let integrator = Integrator::new(
+        project_dir.path().to_path_buf(),
+        workspace_dir.path().join("integration"),
+        "abcdef12-3456",
+        fake_g3(&workspace_dir, "exit 1"),
+    )
+    .with_test_command("test -f core.txt");
+    let outcomes = integrator
+        .run(&[
+            segment_work(1, segment1, "cli", &["core"]),
+            segment_work(2, segment2, "core", &[]),
+        ])
+        .await
+        .expect("Integration failed");
+
+    let merged: Vec<_> = outcomes
+        .iter()
+        .map(|o| (o.segment_id, o.state.clone(), o.tests_passed))
+        .collect();
+    assert_eq!(
+        merged,
+        vec![
+            (2, MergeState::Merged, Some(true)),
+            (1, MergeState::Merged, Some(true)),
+        ]
+    );
+    assert_eq!(outcomes[0].branch, "flock/abcdef12/segment-2");
+
+    let branch = integrator.integration_branch();
+    assert_eq!(branch, "flock/abcdef12/integration");
+    let files = git_stdout(
+        project_dir.path(),
+        &["ls-tree", "-r", "--name-only", &branch],
+    );
+    assert!(files.lines().any(|f| f == "core.txt"));
+    assert!(files.lines().any(|f| f == "cli.txt"));
+    assert!(!files.contains("segment-requirements.md"));
+    assert!(!files.contains("logs/"));
+
+    let log = git_stdout(project_dir.path(), &["log", "--format=%s", &branch]);
+    let subjects: Vec<&str> = log.lines().collect();
+    assert_eq!(subjects[0], "Merge flock/abcdef12/segment-1 (cli)");
+    assert!(subjects.contains(&"Merge flock/abcdef12/segment-2 (core)"));
+
+    // The project's own checkout is untouched
+    assert!(!project_dir.path().join("core.txt").exists());
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_integration_resolves_conflicts_with_agent() {
+    let project_dir = create_test_project("resolve-test");
+    let workspace_dir = TempDir::new().unwrap();
+
+    let segment1 = clone_segment(&project_dir, &workspace_dir, 1);
+    let segment2 = clone_segment(&project_dir, &workspace_dir, 2);
+    fs::write(segment1.join("README.md"), "# From segment 1\n").unwrap();
+    fs::write(segment2.join("README.md"), "# From segment 2\n").unwrap();
+
+    let integrator = Integrator::new(
+        project_dir.path().to_path_buf(),
+        workspace_dir.path().join("integration"),
+        "resolve-session",
+        fake_g3(&workspace_dir, "printf '# Both segments\\n' > README.md"),
+    );
+    let outcomes = integrator
+        .run(&[
+            segment_work(1, segment1, "docs", &[]),
+            segment_work(2, segment2, "readme", &[]),
+        ])
+        .await
+        .expect("Integration failed");
+
+    assert_eq!(outcomes[0].state, MergeState::Merged);
+    assert_eq!(outcomes[1].state, MergeState::Resolved);
+    assert_eq!(outcomes[1].conflicted_files, vec!["README.md".to_string()]);
+
+    let worktree = workspace_dir.path().join("integration");
+    assert_eq!(
+        fs::read_to_string(worktree.join("README.md")).unwrap(),
+        "# Both segments\n"
+    );
+    let changes = git_stdout(
+        &worktree,
+        &["status", "--porcelain", "--untracked-files=no"],
+    );
+    assert!(changes.is_empty());
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_integration_aborts_unresolved_conflicts() {
+    let project_dir = create_test_project("abort-test");
+    let workspace_dir = TempDir::new().unwrap();
+
+    let segment1 = clone_segment(&project_dir, &workspace_dir, 1);
+    let segment2 = clone_segment(&project_dir, &workspace_dir, 2);
+    let segment3 = clone_segment(&project_dir, &workspace_dir, 3);
+    let segment4 = clone_segment(&project_dir, &workspace_dir, 4);
+    fs::write(segment1.join("README.md"), "# From segment 1\n").unwrap();
+    fs::write(segment2.join("README.md"), "# From segment 2\n").unwrap();
+    fs::write(segment3.join("api.txt"), "api").unwrap();
+    fs::write(segment4.join("extra.txt"), "extra").unwrap();
+
+    let mut unfinished = segment_work(4, segment4, "extra", &[]);
+    unfinished.completed = false;
+
+    // The resolver exits without touching the conflicted file
+    let integrator = Integrator::new(
+        project_dir.path().to_path_buf(),
+        workspace_dir.path().join("integration"),
+        "abort-session",
+        fake_g3(&workspace_dir, "exit 0"),
+    );
+    let outcomes = integrator
+        .run(&[
+            segment_work(1, segment1, "core", &[]),
+            segment_work(2, segment2, "storage", &[]),
+            segment_work(3, segment3, "api", &["storage"]),
+            unfinished,
+        ])
+        .await
+        .expect("Integration failed");
+
+    let states: Vec<_> = outcomes.iter().map(|o| o.state.clone()).collect();
+    assert_eq!(
+        states,
+        vec![
+            MergeState::Merged,
+            MergeState::Conflicted,
+            MergeState::Skipped,
+            MergeState::Skipped,
+        ]
+    );
+    assert_eq!(
+        outcomes[2].message.as_deref(),
+        Some("Dependency storage was not merged")
+    );
+    assert_eq!(
+        outcomes[3].message.as_deref(),
+        Some("Segment did not complete")
+    );
+
+    // The aborted merge leaves the integration branch at segment 1
+    let worktree = workspace_dir.path().join("integration");
+    assert_eq!(
+        fs::read_to_string(worktree.join("README.md")).unwrap(),
+        "# From segment 1\n"
+    );
+    let changes = git_stdout(
+        &worktree,
+        &["status", "--porcelain", "--untracked-files=no"],
+    );
+    assert!(changes.is_empty());
+}
+
+const FLOCK_PARTITIONS: &str = r#"[
+  {"module_name": "core", "requirements": "Build core", "dependencies": []},
+  {"module_name": "api", "requirements": "Build api", "dependencies": ["core"]},
+  {"module_name": "docs", "requirements": "Write docs", "dependencies": []}
+]"#;
+
+/// A g3 stand-in for a whole flock run: it prints `partitions` when asked to
+/// partition and, as a segment agent, records what it sees in `order.log`
+/// and writes `<module>.txt`. The `failing` module exits with an error every
+/// time and the `flaky` module only the first time.
+#[cfg(unix)]
+fn fake_flock_g3(workspace: &TempDir, partitions: &str, failing: &str, flaky: &str) -> PathBuf {
+    let script = r#"log="LOG"
+case "$*" in
+*--autonomous*)
+  ws="$2"
+  module=$(printf '%s\n' "$@" | sed -n 's/^# Module: //p' | head -n 1)
+  echo "start $module" >> "$log"
+  for f in "$ws"/*.txt; do
+    [ -e "$f" ] && echo "$module sees $(basename "$f")" >> "$log"
+  done
+  [ "$module" = "FAILING" ] && exit 1
+  if [ "$module" = "FLAKY" ] && [ ! -e "$log.flaked" ]; then
+    touch "$log.flaked"
+    exit 1
+  fi
+  echo "$module" > "$ws/$module.txt"
+  echo "end $module" >> "$log"
+  ;;
+*)
+  echo "partition" >> "$log"
+  printf '{{PARTITION JSON}}\n```json\n%s\n```\n' 'PARTITIONS'
+  ;;
+esac"#
+        .replace("LOG", &workspace.path().join("order.log").to_string_lossy())
+        .replace("FAILING", failing)
+        .replace("FLAKY", flaky)
+        .replace("PARTITIONS", partitions);
+    fake_g3(workspace, &script)
+}
+
+#[cfg(unix)]
+fn flock_config(project_dir: &TempDir, workspace_dir: &TempDir, g3_binary: PathBuf) -> FlockConfig {
+    let config_dir = TempDir::new().unwrap();
+    let config_path = create_test_config(&config_dir);
+    FlockConfig::new_with_config(
+        project_dir.path().to_path_buf(),
+        workspace_dir.path().to_path_buf(),
+        3,
+        Some(config_path.to_str().unwrap()),
+    )
+    .expect("Failed to create config")
+    .with_g3_binary(g3_binary)
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_flock_runs_dependents_after_dependencies() {
+    let project_dir = create_test_project("schedule-test");
+    let workspace_dir = TempDir::new().unwrap();
+    let g3_binary = fake_flock_g3(&workspace_dir, FLOCK_PARTITIONS, "none", "none");
+    let config =
+        flock_config(&project_dir, &workspace_dir, g3_binary).with_max_concurrent_segments(1);
+
+    let mut flock = FlockMode::new(config).unwrap();
+    flock.run().await.expect("Flock run failed");
+
+    // One segment at a time: core, then api (now unblocked, seeded with
+    // core's work), then docs
+    let log = fs::read_to_string(workspace_dir.path().join("order.log")).unwrap();
+    assert_eq!(
+        log.lines().collect::<Vec<_>>(),
+        vec![
+            "partition",
+            "start core",
+            "end core",
+            "start api",
+            "api sees core.txt",
+            "end api",
+            "start docs",
+            "end docs",
+        ]
+    );
+    let requirements_path = workspace_dir
+        .path()
+        .join("segment-2/segment-requirements.md");
+    let api_requirements = fs::read_to_string(requirements_path).unwrap();
+    assert!(api_requirements.contains("## Upstream Modules"));
+    assert!(api_requirements.contains("### core (segment 1)"));
+    assert!(api_requirements.contains("core.txt"));
+
+    let status =
+        FlockStatus::load_from_file(&workspace_dir.path().join("flock-status.json")).unwrap();
+    assert_eq!(status.count_by_state(SegmentState::Completed), 3);
+    assert!(status.merges.iter().all(|m| m.state == MergeState::Merged));
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_flock_cancels_dependents_of_failed_segments() {
+    let project_dir = create_test_project("cancel-test");
+    let workspace_dir = TempDir::new().unwrap();
+    let g3_binary = fake_flock_g3(&workspace_dir, FLOCK_PARTITIONS, "core", "none");
+    let config = flock_config(&project_dir, &workspace_dir, g3_binary);
+
+    let mut flock = FlockMode::new(config).unwrap();
+    flock.run().await.expect("Flock run failed");
+
+    let log = fs::read_to_string(workspace_dir.path().join("order.log")).unwrap();
+    assert!(!log.contains("start api"));
+    assert!(log.contains("end docs"));
+
+    let status =
+        FlockStatus::load_from_file(&workspace_dir.path().join("flock-status.json")).unwrap();
+    assert_eq!(status.segments[&1].state, SegmentState::Failed);
+    assert_eq!(status.segments[&2].state, SegmentState::Cancelled);
+    assert_eq!(
+        status.segments[&2].error_message.as_deref(),
+        Some("Dependency core failed")
+    );
+    assert_eq!(status.segments[&3].state, SegmentState::Completed);
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_flock_reports_dependency_cycles() {
+    let project_dir = create_test_project("cycle-test");
+    let workspace_dir = TempDir::new().unwrap();
+    let partitions = r#"[
+  {"module_name": "core", "requirements": "Build core", "dependencies": ["api"]},
+  {"module_name": "api", "requirements": "Build api", "dependencies": ["core"]}
+]"#;
+    let g3_binary = fake_flock_g3(&workspace_dir, partitions, "none", "none");
+    let config = flock_config(&project_dir, &workspace_dir, g3_binary);
+
+    let mut flock = FlockMode::new(config).unwrap();
+    let err = flock.run().await.unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Partition dependencies form a cycle: core -> api -> core"
+    );
+    assert!(!workspace_dir.path().join("segment-1").exists());
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_flock_retries_failed_segments() {
+    let project_dir = create_test_project("retry-test");
+    let workspace_dir = TempDir::new().unwrap();
+    let g3_binary = fake_flock_g3(&workspace_dir, FLOCK_PARTITIONS, "none", "core");
+    let config = flock_config(&project_dir, &workspace_dir, g3_binary)
+        .with_max_concurrent_segments(1)
+        .with_max_retries(1);
+
+    let mut flock = FlockMode::new(config).unwrap();
+    flock.run().await.expect("Flock run failed");
+
+    let log = fs::read_to_string(workspace_dir.path().join("order.log")).unwrap();
+    let lines: Vec<&str> = log.lines().collect();
+    assert_eq!(
+        &lines[..4],
+        &["partition", "start core", "start core", "end core"]
+    );
+
+    let status =
+        FlockStatus::load_from_file(&workspace_dir.path().join("flock-status.json")).unwrap();
+    assert_eq!(status.count_by_state(SegmentState::Completed), 3);
+    assert_eq!(status.attempts[&1], 2);
+    assert_eq!(status.attempts[&2], 1);
+    assert!(status.generate_report().contains("Attempts: 2"));
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_flock_resume_reruns_unfinished_segments() {
+    let project_dir = create_test_project("resume-test");
+    let workspace_dir = TempDir::new().unwrap();
+    let g3_binary = fake_flock_g3(&workspace_dir, FLOCK_PARTITIONS, "api", "none");
+    let config = flock_config(&project_dir, &workspace_dir, g3_binary);
+
+    let mut flock = FlockMode::new(config).unwrap();
+    flock.run().await.expect("Flock run failed");
+    let status_path = workspace_dir.path().join("flock-status.json");
+    let first = FlockStatus::load_from_file(&status_path).unwrap();
+    assert_eq!(first.segments[&2].state, SegmentState::Failed);
+    fs::remove_file(workspace_dir.path().join("order.log")).unwrap();
+
+    // Resume with an agent that no longer fails
+    let g3_binary = fake_flock_g3(&workspace_dir, FLOCK_PARTITIONS, "none", "none");
+    let config_dir = TempDir::new().unwrap();
+    let config_path = create_test_config(&config_dir);
+    let config = FlockConfig::resume_with_config(
+        workspace_dir.path().to_path_buf(),
+        Some(config_path.to_str().unwrap()),
+    )
+    .expect("Failed to create resume config")
+    .with_g3_binary(g3_binary);
+    assert_eq!(config.project_dir, project_dir.path());
+
+    let mut flock = FlockMode::resume(config).unwrap();
+    flock.run().await.expect("Resumed run failed");
+
+    // No partitioning and only the failed segment runs again
+    let log = fs::read_to_string(workspace_dir.path().join("order.log")).unwrap();
+    assert_eq!(
+        log.lines().collect::<Vec<_>>(),
+        vec!["start api", "api sees core.txt", "end api"]
+    );
+    let requirements_path = workspace_dir
+        .path()
+        .join("segment-2/segment-requirements.md");
+    let requirements = fs::read_to_string(requirements_path).unwrap();
+    assert_eq!(requirements.matches("## Upstream Modules").count(), 1);
+
+    let status = FlockStatus::load_from_file(&status_path).unwrap();
+    assert_eq!(status.session_id, first.session_id);
+    assert_eq!(status.count_by_state(SegmentState::Completed), 3);
+    assert_eq!(status.attempts[&1], 1);
+    assert_eq!(status.attempts[&2], 2);
+    assert!(status.merges.iter().all(|m| m.state == MergeState::Merged));
+}
+
+#[test]
+fn test_flock_resume_requires_a_status_file() {
+    let workspace_dir = TempDir::new().unwrap();
+    let err = FlockConfig::resume(workspace_dir.path().to_path_buf()).unwrap_err();
+    assert!(err.to_string().starts_with("Failed to load"));
+    assert!(err.to_string().ends_with("flock-status.json"));
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_flock_streams_events_and_cancels_segments() {
+    let project_dir = create_test_project("events-test");
+    let workspace_dir = TempDir::new().unwrap();
+    // Segment agents report a turn; core then hangs until it is killed
+    let script = r#"case "$*" in
+*--autonomous*)
+  module=$(printf '%s\n' "$@" | sed -n 's/^# Module: //p' | head -n 1)
+  echo "TURN 1/5"
+  echo "working on $module"
+  [ "$module" = "core" ] && exec sleep 30
+  echo "done" > "$2/$module.txt"
+  ;;
+*)
+  printf '{{PARTITION JSON}}\n```json\n%s\n```\n' 'PARTITIONS'
+  ;;
+esac"#
+        .replace("PARTITIONS", FLOCK_PARTITIONS);
+    let g3_binary = fake_g3(&workspace_dir, &script);
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+    let mut flock = FlockMode::new(flock_config(&project_dir, &workspace_dir, g3_binary)).unwrap();
+    flock = flock.with_events(tx);
+    let canceller = flock.canceller();
+
+    let events = tokio::spawn(async move {
+        let mut events = Vec::new();
+        while let Some(event) = rx.recv().await {
+            if let FlockEvent::SegmentOutput {
+                segment_id: 1,
+                ref line,
+                ..
+            } = event
+            {
+                if line == "working on core" {
+                    canceller.cancel(1);
+                }
+            }
+            let finished = matches!(event, FlockEvent::SegmentsFinished);
+            events.push(event);
+            if finished {
+                break;
+            }
+        }
+        events
+    });
+    tokio::time::timeout(std::time::Duration::from_secs(20), flock.run())
+        .await
+        .expect("Cancelled segment was not stopped")
+        .expect("Flock run failed");
+    let events = events.await.unwrap();
+
+    match &events[0] {
+        FlockEvent::SegmentsStarted { modules } => {
+            assert_eq!(modules, &["core", "api", "docs"]);
+        }
+        other => panic!("Unexpected first event: {:?}", other),
+    }
+    assert!(matches!(events.last(), Some(FlockEvent::SegmentsFinished)));
+    assert!(events.iter().any(|e| matches!(
+        e,
+        FlockEvent::SegmentUpdated(s) if s.segment_id == 3 && s.current_turn == 1
+    )));
+
+    let status =
+        FlockStatus::load_from_file(&workspace_dir.path().join("flock-status.json")).unwrap();
+    assert_eq!(status.segments[&1].state, SegmentState::Cancelled);
+    assert_eq!(
+        status.segments[&1].last_message.as_deref(),
+        Some("Cancelled by user")
+    );
+    assert_eq!(status.segments[&2].state, SegmentState::Cancelled);
+    assert_eq!(
+        status.segments[&2].error_message.as_deref(),
+        Some("Dependency core was cancelled")
+    );
+    assert_eq!(status.segments[&3].state, SegmentState::Completed);
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_flock_segments_as_worktrees() {
+    let project_dir = create_test_project("worktree-test");
+    let workspace_dir = TempDir::new().unwrap();
+    let g3_binary = fake_flock_g3(&workspace_dir, FLOCK_PARTITIONS, "none", "none");
+    let config = flock_config(&project_dir, &workspace_dir, g3_binary)
+        .with_max_concurrent_segments(1)
+        .with_segment_worktrees();
+
+    let mut flock = FlockMode::new(config).unwrap();
+    flock.run().await.expect("Flock run failed");
+
+    let status =
+        FlockStatus::load_from_file(&workspace_dir.path().join("flock-status.json")).unwrap();
+    let head = git_stdout(project_dir.path(), &["rev-parse", "HEAD"]);
+    assert_eq!(status.worktree_base.as_deref(), Some(head.trim()));
+
+    // Dependents are still seeded with their dependencies' work
+    let log = fs::read_to_string(workspace_dir.path().join("order.log")).unwrap();
+    assert!(log.lines().any(|l| l == "api sees core.txt"));
+
+    // The segment branches are merged as they are, without fetching
+    assert!(status.merges.iter().all(|m| m.state == MergeState::Merged));
+    let branch = status.integration_branch.unwrap();
+    let files = git_stdout(
+        project_dir.path(),
+        &["ls-tree", "-r", "--name-only", &branch],
+    );
+    for file in ["core.txt", "api.txt", "docs.txt"] {
+        assert!(files.lines().any(|f| f == file), "{} not merged", file);
+    }
+    assert!(!files.contains("segment-requirements.md"));
+
+    // Completed segments' worktrees are removed and pruned; branches remain
+    for segment_id in 1..=3 {
+        assert!(!workspace_dir
+            .path()
+            .join(format!("segment-{}", segment_id))
+            .exists());
+    }
+    let worktrees = git_stdout(project_dir.path(), &["worktree", "list", "--porcelain"]);
+    assert_eq!(worktrees.matches("worktree ").count(), 2);
+    let prefix: String = status.session_id.chars().take(8).collect();
+    let segment_branch = format!("flock/{}/segment-1", prefix);
+    assert!(!git_stdout(project_dir.path(), &["branch", "--list", &segment_branch]).is_empty());
+
+    // The project's exclude file is left alone
+    let exclude =
+        fs::read_to_string(project_dir.path().join(".git/info/exclude")).unwrap_or_default();
+    assert!(!exclude.contains("segment-requirements.md"));
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_flock_resume_restores_segment_worktrees() {
+    let project_dir = create_test_project("worktree-resume-test");
+    let workspace_dir = TempDir::new().unwrap();
+    let g3_binary = fake_flock_g3(&workspace_dir, FLOCK_PARTITIONS, "api", "none");
+    let config = flock_config(&project_dir, &workspace_dir, g3_binary).with_segment_worktrees();
+
+    let mut flock = FlockMode::new(config).unwrap();
+    flock.run().await.expect("Flock run failed");
+
+    // Only the failed segment's worktree is kept for resuming
+    assert!(!workspace_dir.path().join("segment-1").exists());
+    assert!(workspace_dir.path().join("segment-2").exists());
+    assert!(!workspace_dir.path().join("segment-3").exists());
+    fs::remove_file(workspace_dir.path().join("order.log")).unwrap();
+
+    let g3_binary = fake_flock_g3(&workspace_dir, FLOCK_PARTITIONS, "none", "none");
+    let config_dir = TempDir::new().unwrap();
+    let config_path = create_test_config(&config_dir);
+    let config = FlockConfig::resume_with_config(
+        workspace_dir.path().to_path_buf(),
+        Some(config_path.to_str().unwrap()),
+    )
+    .expect("Failed to create resume config")
+    .with_g3_binary(g3_binary);
+
+    let mut flock = FlockMode::resume(config).unwrap();
+    flock.run().await.expect("Resumed run failed");
+
+    // core's worktree is checked out again from its branch to seed api
+    let log = fs::read_to_string(workspace_dir.path().join("order.log")).unwrap();
+    assert_eq!(
+        log.lines().collect::<Vec<_>>(),
+        vec!["start api", "api sees core.txt", "end api"]
+    );
+    let status =
+        FlockStatus::load_from_file(&workspace_dir.path().join("flock-status.json")).unwrap();
+    assert_eq!(status.count_by_state(SegmentState::Completed), 3);
+    assert!(status.merges.iter().all(|m| m.state == MergeState::Merged));
+    for segment_id in 1..=3 {
+        assert!(!workspace_dir
+            .path()
+            .join(format!("segment-{}", segment_id))
+            .exists());
+    }
+}
@@ -0,0 +1,250 @@
+//! Execution backends decide where commands run: directly on the host, inside a
+//! bubblewrap sandbox confined to the workspace, or in a rootless container.
+
+use anyhow::{Context, Result};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+#[derive(Debug, Clone, Default)]
+pub enum ExecutionBackend {
+    /// Run on the host with the agent's own privileges
+    #[default]
+    Direct,
+    /// Linux namespace sandbox via bubblewrap
+    Sandbox(SandboxOptions),
+    /// Rootless container (podman or docker)
+    Container(ContainerOptions),
+}
+
+/// Bubblewrap sandbox: read-only host filesystem, writable workspace, private
+/// /tmp, and no network unless `allow_network` is set
+#[derive(Debug, Clone)]
+pub struct SandboxOptions {
+    /// bubblewrap executable
+    pub program: String,
+    pub workspace: PathBuf,
+    pub allow_network: bool,
+    /// Additional host paths mounted read-write
+    pub writable_paths: Vec<PathBuf>,
+}
+
+/// Container backend: the workspace is mounted at the same path inside the image
+#[derive(Debug, Clone)]
+pub struct ContainerOptions {
+    /// Container runtime executable ("podman" or "docker")
+    pub runtime: String,
+    pub image: String,
+    pub workspace: PathBuf,
+    pub allow_network: bool,
+    /// Extra arguments passed to `<runtime> run` before the image name
+    pub extra_args: Vec<String>,
+}
+
+impl ExecutionBackend {
+    pub fn name(&self) -> &'static str {
+        match self {
+            ExecutionBackend::Direct => "direct",
+            ExecutionBackend::Sandbox(_) => "sandbox",
+            ExecutionBackend::Container(_) => "container",
+        }
+    }
+
+    /// Verify the backend's tooling is installed, so a misconfigured sandbox
+    /// fails up front rather than silently running commands on the host
+    pub fn check_available(&self) -> Result<()> {
+        let program = match self {
+            ExecutionBackend::Direct => return Ok(()),
+            ExecutionBackend::Sandbox(opts) => &opts.program,
+            ExecutionBackend::Container(opts) => &opts.runtime,
+        };
+        let status = Command::new(program)
+            .arg("--version")
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()
+            .with_context(|| {
+                format!(
+                    "The '{}' execution backend requires '{}', which could not be run",
+                    self.name(),
+                    program
+                )
+            })?;
+        if !status.success() {
+            anyhow::bail!("'{} --version' failed; is it installed correctly?", program);
+        }
+        Ok(())
+    }
+
+    /// Build a command that runs `program args` under this backend.
+    ///
+    /// `working_dir` defaults to the current directory. `read_only_files` are
+    /// host files (e.g. temporary scripts) the command must be able to read.
+    pub fn command(
+        &self,
+        program: &str,
+        args: &[&str],
+        working_dir: Option<&Path>,
+        read_only_files: &[&Path],
+    ) -> Command {
+        let cwd = working_dir
+            .map(Path::to_path_buf)
+            .or_else(|| std::env::current_dir().ok())
+            .unwrap_or_else(|| PathBuf::from("/"));
+
+        match self {
+            ExecutionBackend::Direct => {
+                let mut cmd = Command::new(program);
+                cmd.args(args);
+                if let Some(dir) = working_dir {
+                    cmd.current_dir(dir);
+                }
+                cmd
+            }
+            ExecutionBackend::Sandbox(opts) => {
+                let mut cmd = Command::new(&opts.program);
+                cmd.args(["--ro-bind", "/", "/"])
+                    .args(["--dev", "/dev"])
+                    .args(["--proc", "/proc"])
+                    .args(["--tmpfs", "/tmp"])
+                    .arg("--unshare-all")
+                    .arg("--die-with-parent")
+                    .arg("--new-session");
+                if opts.allow_network {
+                    cmd.arg("--share-net");
+                }
+                let mut writable = vec![opts.workspace.clone()];
+                writable.extend(opts.writable_paths.iter().cloned());
+                for path in &writable {
+                    cmd.arg("--bind").arg(path).arg(path);
+                }
+                // A working directory outside the writable paths stays
+                // read-only; it is bound again so it survives the /tmp tmpfs
+                let mut read_only: Vec<&Path> = read_only_files.to_vec();
+                if !writable.iter().any(|path| cwd.starts_with(path)) {
+                    read_only.push(&cwd);
+                }
+                for file in read_only {
+                    cmd.arg("--ro-bind").arg(file).arg(file);
+                }
+                cmd.arg("--chdir")
+                    .arg(&cwd)
+                    .arg("--")
+                    .arg(program)
+                    .args(args);
+                cmd
+            }
+            ExecutionBackend::Container(opts) => {
+                let mut cmd = Command::new(&opts.runtime);
+                cmd.args(["run", "--rm"]);
+                let is_podman = Path::new(&opts.runtime)
+                    .file_name()
+                    .map(|n| n == "podman")
+                    .unwrap_or(false);
+                if is_podman {
+                    // Keep file ownership in the workspace identical to the host user
+                    cmd.arg("--userns=keep-id");
+                }
+                if !opts.allow_network {
+                    cmd.args(["--network", "none"]);
+                }
+                cmd.arg("-v").arg(format!(
+                    "{}:{}",
+                    opts.workspace.display(),
+                    opts.workspace.display()
+                ));
+                // A working directory outside the workspace is mounted read-only
+                let mut read_only: Vec<&Path> = read_only_files.to_vec();
+                if !cwd.starts_with(&opts.workspace) {
+                    read_only.push(&cwd);
+                }
+                for file in read_only {
+                    cmd.arg("-v")
+                        .arg(format!("{}:{}:ro", file.display(), file.display()));
+                }
+                cmd.arg("-w").arg(&cwd);
+                cmd.args(&opts.extra_args);
+                cmd.arg(&opts.image).arg(program).args(args);
+                cmd
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args_of(cmd: &Command) -> Vec<String> {
+        cmd.get_args()
+            .map(|a| a.to_string_lossy().into_owned())
+            .collect()
+    }
+
+    #[test]
+    fn test_direct_runs_program() {
+        let cmd =
+            ExecutionBackend::Direct.command("bash", &["-c", "ls"], Some(Path::new("/ws")), &[]);
+        assert_eq!(cmd.get_program(), "bash");
+        assert_eq!(args_of(&cmd), vec!["-c", "ls"]);
+        assert_eq!(cmd.get_current_dir(), Some(Path::new("/ws")));
+    }
+
+    #[test]
+    fn test_sandbox_confines_to_workspace_without_network() {
+        let backend = ExecutionBackend::Sandbox(SandboxOptions {
+            program: "bwrap".to_string(),
+            workspace: PathBuf::from("/ws"),
+            allow_network: false,
+            writable_paths: vec![],
+        });
+        let cmd = backend.command("bash", &["-c", "ls"], Some(Path::new("/ws/src")), &[]);
+        let args = args_of(&cmd).join(" ");
+        assert_eq!(cmd.get_program(), "bwrap");
+        assert!(args.contains("--ro-bind / /"));
+        assert!(args.contains("--bind /ws /ws"));
+        assert!(args.contains("--unshare-all"));
+        assert!(!args.contains("--share-net"));
+        assert!(args.ends_with("--chdir /ws/src -- bash -c ls"));
+    }
+
+    #[test]
+    fn test_container_mounts_workspace() {
+        let backend = ExecutionBackend::Container(ContainerOptions {
+            runtime: "podman".to_string(),
+            image: "rust:latest".to_string(),
+            workspace: PathBuf::from("/ws"),
+            allow_network: false,
+            extra_args: vec![],
+        });
+        let cmd = backend.command("bash", &["-c", "ls"], None, &[Path::new("/tmp/script.py")]);
+        let args = args_of(&cmd).join(" ");
+        assert!(args.starts_with("run --rm --userns=keep-id --network none -v /ws:/ws"));
+        assert!(args.contains("-v /tmp/script.py:/tmp/script.py:ro"));
+        assert!(args.ends_with("rust:latest bash -c ls"));
+    }
+
+    #[test]
+    fn test_working_dir_outside_workspace_is_read_only() {
+        let sandbox = ExecutionBackend::Sandbox(SandboxOptions {
+            program: "bwrap".to_string(),
+            workspace: PathBuf::from("/ws"),
+            allow_network: false,
+            writable_paths: vec![],
+        });
+        let args = args_of(&sandbox.command("ls", &[], Some(Path::new("/")), &[])).join(" ");
+        assert!(!args.contains("--bind / /"));
+        assert!(args.contains("--ro-bind / /"));
+        assert!(args.ends_with("--chdir / -- ls"));
+
+        let container = ExecutionBackend::Container(ContainerOptions {
+            runtime: "docker".to_string(),
+            image: "rust:latest".to_string(),
+            workspace: PathBuf::from("/ws"),
+            allow_network: true,
+            extra_args: vec![],
+        });
+        let args = args_of(&container.command("ls", &[], Some(Path::new("/etc")), &[])).join(" ");
+        assert!(args.contains("-v /etc:/etc:ro"));
+        assert!(!args.contains("-v /etc:/etc "));
+    }
+}
@@ -1,3 +1,6 @@
+mod backend;
+pub use backend::{ContainerOptions, ExecutionBackend, SandboxOptions};
+
 use anyhow::Result;
 use regex::Regex;
 use std::io::Write;
@@ -17,7 +20,7 @@ fn expand_tilde(path: &str) -> String {
 }
 
 pub struct CodeExecutor {
-    // Future: add configuration for execution limits, sandboxing, etc.
+    backend: ExecutionBackend,
 }
 
 #[derive(Debug, Clone)]
@@ -30,7 +33,18 @@ pub struct ExecutionResult {
 
 impl CodeExecutor {
     pub fn new() -> Self {
-        Self {}
+        Self {
+            backend: ExecutionBackend::Direct,
+        }
+    }
+
+    /// Create an executor that runs every command through `backend`
+    pub fn with_backend(backend: ExecutionBackend) -> Self {
+        Self { backend }
+    }
+
+    pub fn backend(&self) -> &ExecutionBackend {
+        &self.backend
     }
 
     /// Extract code blocks from LLM response and execute them
@@ -182,7 +196,10 @@ impl CodeExecutor {
         temp_file.write_all(code.as_bytes())?;
         let temp_path = temp_file.path();
 
-        let output = Command::new("python3").arg(temp_path).output()?;
+        let output = self
+            .backend
+            .command("python3", &[&temp_path.to_string_lossy()], None, &[temp_path])
+            .output()?;
 
         Ok(ExecutionResult {
             stdout: String::from_utf8_lossy(&output.stdout).to_string(),
@@ -203,9 +220,8 @@ impl CodeExecutor {
         if is_detached {
             // For detached commands, just spawn and return immediately
             use std::process::Stdio;
-            Command::new("bash")
-                .arg("-c")
-                .arg(code)
+            self.backend
+                .command("bash", &["-c", code], None, &[])
                 .stdin(Stdio::null())
                 .stdout(Stdio::null())
                 .stderr(Stdio::null())
@@ -219,7 +235,10 @@ impl CodeExecutor {
             });
         }
 
-        let output = Command::new("bash").arg("-c").arg(code).output()?;
+        let output = self
+            .backend
+            .command("bash", &["-c", code], None, &[])
+            .output()?;
 
         Ok(ExecutionResult {
             stdout: String::from_utf8_lossy(&output.stdout).to_string(),
@@ -235,7 +254,10 @@ impl CodeExecutor {
         temp_file.write_all(code.as_bytes())?;
         let temp_path = temp_file.path();
 
-        let output = Command::new("node").arg(temp_path).output()?;
+        let output = self
+            .backend
+            .command("node", &[&temp_path.to_string_lossy()], None, &[temp_path])
+            .output()?;
 
         Ok(ExecutionResult {
             stdout: String::from_utf8_lossy(&output.stdout).to_string(),
@@ -311,16 +333,16 @@ impl CodeExecutor {
             || code.contains(" disown")
             || (code.contains(" &") && (code.contains("nohup") || code.contains("setsid")));
 
+        let expanded_dir = working_dir.map(expand_tilde);
+
         if is_detached {
             // For detached commands, just spawn and return immediately
-            let mut cmd = TokioCommand::new("bash");
-            cmd.arg("-c").arg(code);
-
-            // Set working directory if provided
-            if let Some(dir) = working_dir {
-                let expanded_dir = expand_tilde(dir);
-                cmd.current_dir(&expanded_dir);
-            }
+            let mut cmd = TokioCommand::from(self.backend.command(
+                "bash",
+                &["-c", code],
+                expanded_dir.as_deref().map(std::path::Path::new),
+                &[],
+            ));
 
             cmd.spawn()?;
 
@@ -333,27 +355,27 @@ impl CodeExecutor {
             });
         }
 
-        let mut cmd = TokioCommand::new("bash");
-        cmd.arg("-c")
-            .arg(code)
-            .stdout(Stdio::piped())
-            .stderr(Stdio::piped());
-
         // Set working directory if provided
-        if let Some(dir) = working_dir {
-            debug!("Setting current_dir on command to: {}", dir);
-            let expanded_dir = expand_tilde(dir);
+        if let Some(expanded_dir) = &expanded_dir {
             debug!("Expanded working dir: {}", expanded_dir);
             debug!(
                 "Expanded dir exists: {}",
-                std::path::Path::new(&expanded_dir).exists()
+                std::path::Path::new(expanded_dir).exists()
             );
             debug!(
                 "Expanded dir is_dir: {}",
-                std::path::Path::new(&expanded_dir).is_dir()
+                std::path::Path::new(expanded_dir).is_dir()
             );
-            cmd.current_dir(&expanded_dir);
         }
+        debug!("Execution backend: {}", self.backend.name());
+
+        let mut cmd = TokioCommand::from(self.backend.command(
+            "bash",
+            &["-c", code],
+            expanded_dir.as_deref().map(std::path::Path::new),
+            &[],
+        ));
+        cmd.stdout(Stdio::piped()).stderr(Stdio::piped());
 
         debug!("About to spawn command...");
         let spawn_result = cmd.spawn();
@@ -22,10 +22,15 @@ pub use planner::run_planning_mode;
 
 use anyhow::Result;
 use chrono::Local;
-use g3_providers::{CompletionRequest, LLMProvider, Message, MessageRole};
+use g3_core::repo_map::RepoMap;
+use g3_providers::{tokenizer_for, CompletionRequest, LLMProvider, Message, MessageRole};
 use prompts::{DISCOVERY_REQUIREMENTS_PROMPT, DISCOVERY_SYSTEM_PROMPT};
 use std::fs::{self, OpenOptions};
 use std::io::Write;
+use std::path::Path;
+
+/// Token budget for the repo map appended to the codebase report
+const DISCOVERY_REPO_MAP_TOKENS: u32 = 2048;
 
 /// Type alias for a status callback function
 pub type StatusCallback = Box<dyn Fn(&str) + Send + Sync>;
@@ -33,7 +38,7 @@ pub type StatusCallback = Box<dyn Fn(&str) + Send + Sync>;
 /// Generates initial discovery messages for fast codebase exploration.
 ///
 /// This function:
-/// 1. Runs explore_codebase to get a codebase report
+/// 1. Runs explore_codebase and builds a repo map to get a codebase report
 /// 2. Sends the report to the LLM with DISCOVERY_SYSTEM_PROMPT
 /// 3. Extracts shell commands from the LLM response
 /// 4. Returns Assistant messages with tool calls for each command
@@ -63,8 +68,18 @@ pub async fn get_initial_discovery_messages(
 
     status("🔍 Starting code discovery...");
 
-    // Step 1: Run explore_codebase to get the codebase report
-    let codebase_report = explore_codebase(codebase_path);
+    // Step 1: Run explore_codebase to get the codebase report, followed by
+    // the definitions the rest of the code depends on most
+    let mut codebase_report = explore_codebase(codebase_path);
+    let repo_root = shellexpand::tilde(codebase_path).into_owned();
+    if let Ok(repo_map) = RepoMap::build(Path::new(&repo_root)) {
+        if !repo_map.files.is_empty() {
+            let tokenizer = tokenizer_for(provider);
+            codebase_report.push_str("\n=== REPO MAP (most referenced definitions first) ===\n");
+            let rendered = repo_map.render(DISCOVERY_REPO_MAP_TOKENS, tokenizer.as_ref());
+            codebase_report.push_str(&rendered);
+        }
+    }
 
     // Write the codebase report to logs directory
     write_code_report(&codebase_report)?;
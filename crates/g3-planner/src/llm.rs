@@ -43,7 +43,7 @@ pub async fn create_planner_provider(
                 .get_anthropic_config(&config_name)
                 .ok_or_else(|| anyhow!("Anthropic config '{}' not found", config_name))?;
             
-            let provider = g3_providers::AnthropicProvider::new_with_name(
+            let mut provider = g3_providers::AnthropicProvider::new_with_name(
                 format!("anthropic.{}", config_name),
                 anthropic_config.api_key.clone(),
                 Some(anthropic_config.model.clone()),
@@ -53,6 +53,9 @@ pub async fn create_planner_provider(
                 anthropic_config.enable_1m_context,
                 anthropic_config.thinking_budget_tokens,
             )?;
+            if let Some(base_url) = &anthropic_config.base_url {
+                provider = provider.with_base_url(base_url.clone());
+            }
             Ok(Box::new(provider))
         }
         "openai" => {
@@ -96,9 +99,26 @@ pub async fn create_planner_provider(
             };
             Ok(Box::new(provider))
         }
+        "gemini" => {
+            let gemini_config = config
+                .get_gemini_config(&config_name)
+                .ok_or_else(|| anyhow!("Gemini config '{}' not found", config_name))?;
+            
+            let mut provider = g3_providers::GeminiProvider::new_with_name(
+                format!("gemini.{}", config_name),
+                gemini_config.api_key.clone(),
+                Some(gemini_config.model.clone()),
+                gemini_config.max_tokens,
+                gemini_config.temperature,
+            )?;
+            if let Some(base_url) = &gemini_config.base_url {
+                provider = provider.with_base_url(base_url.clone());
+            }
+            Ok(Box::new(provider))
+        }
         _ => {
             Err(anyhow!(
-                "Unsupported provider type '{}' for planner. Supported: anthropic, openai, databricks",
+                "Unsupported provider type '{}' for planner. Supported: anthropic, openai, databricks, gemini",
                 provider_type
             ))
         }
@@ -343,6 +363,10 @@ pub async fn call_refinement_llm_with_tools(
         false, // not quiet
     )
     .await?;
+
+    for tool in crate::planner::get_excluded_planner_tools() {
+        agent.tool_registry_mut().disable(tool);
+    }
     
     // Execute the refinement task
     // The agent will have access to tools and execute them
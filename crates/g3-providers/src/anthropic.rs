@@ -114,7 +114,7 @@ use crate::{
     MessageRole, Tool, ToolCall, Usage,
 };
 
-const ANTHROPIC_API_URL: &str = "https://api.anthropic.com/v1/messages";
+const ANTHROPIC_API_URL: &str = "https://api.anthropic.com/v1";
 const ANTHROPIC_VERSION: &str = "2023-06-01";
 
 #[derive(Debug, Clone)]
@@ -122,6 +122,7 @@ pub struct AnthropicProvider {
     client: Client,
     name: String,
     api_key: String,
+    base_url: String,
     model: String,
     max_tokens: u32,
     temperature: f32,
@@ -153,6 +154,7 @@ impl AnthropicProvider {
             client,
             name: "anthropic".to_string(),
             api_key,
+            base_url: ANTHROPIC_API_URL.to_string(),
             model,
             max_tokens: max_tokens.unwrap_or(4096),
             temperature: temperature.unwrap_or(0.1),
@@ -186,6 +188,7 @@ impl AnthropicProvider {
             client,
             name,
             api_key,
+            base_url: ANTHROPIC_API_URL.to_string(),
             model,
             max_tokens: max_tokens.unwrap_or(4096),
             temperature: temperature.unwrap_or(0.1),
@@ -195,10 +198,16 @@ impl AnthropicProvider {
         })
     }
 
+    /// Use a different API endpoint, e.g. a proxy or a local mock server
+    pub fn with_base_url(mut self, base_url: String) -> Self {
+        self.base_url = base_url.trim_end_matches('/').to_string();
+        self
+    }
+
     fn create_request_builder(&self, streaming: bool) -> RequestBuilder {
         let mut builder = self
             .client
-            .post(ANTHROPIC_API_URL)
+            .post(format!("{}/messages", self.base_url))
             .header("x-api-key", &self.api_key)
             .header("anthropic-version", ANTHROPIC_VERSION)
             .header("content-type", "application/json");
@@ -302,6 +311,31 @@ impl AnthropicProvider {
         Ok((system_message, anthropic_messages))
     }
 
+    /// The system prompt as one text block per system message, so breakpoints
+    /// can fall between them. `None` when no system message has a breakpoint;
+    /// the concatenated string is sent instead.
+    fn system_blocks(messages: &[Message]) -> Option<Vec<AnthropicContent>> {
+        let system: Vec<&Message> = messages
+            .iter()
+            .filter(|message| matches!(message.role, MessageRole::System))
+            .collect();
+        if !system.iter().any(|message| message.cache_control.is_some()) {
+            return None;
+        }
+        Some(
+            system
+                .into_iter()
+                .map(|message| AnthropicContent::Text {
+                    text: message.content.clone(),
+                    cache_control: message
+                        .cache_control
+                        .as_ref()
+                        .map(Self::convert_cache_control),
+                })
+                .collect(),
+        )
+    }
+
     fn create_request_body(
         &self,
         messages: &[Message],
@@ -349,6 +383,11 @@ impl AnthropicProvider {
             })
         };
 
+        let system = match Self::system_blocks(messages) {
+            Some(blocks) => Some(AnthropicSystem::Blocks(blocks)),
+            None => system.map(AnthropicSystem::Text),
+        };
+
         let request = AnthropicRequest {
             model: self.model.clone(),
             max_tokens,
@@ -368,7 +407,57 @@ impl AnthropicProvider {
         Ok(request)
     }
 
-    async fn parse_streaming_response(
+    /// The Messages API request body for `request`, as JSON. Bedrock sends the
+    /// same body with a few fields swapped.
+    pub(crate) fn request_json(
+        &self,
+        request: &CompletionRequest,
+        streaming: bool,
+    ) -> Result<serde_json::Value> {
+        let request_body = self.create_request_body(
+            &request.messages,
+            request.tools.as_deref(),
+            streaming,
+            request.max_tokens.unwrap_or(self.max_tokens),
+            request.temperature.unwrap_or(self.temperature),
+            request.disable_thinking,
+        )?;
+        Ok(serde_json::to_value(request_body)?)
+    }
+
+    /// Parse a non-streaming Messages API response body
+    pub(crate) fn parse_completion(&self, body: &[u8]) -> Result<CompletionResponse> {
+        let anthropic_response: AnthropicResponse = serde_json::from_slice(body)
+            .map_err(|e| anyhow!("Failed to parse Anthropic response: {}", e))?;
+
+        // Extract text content from the response
+        let content = anthropic_response
+            .content
+            .iter()
+            .filter_map(|c| match c {
+                AnthropicContent::Text { text, .. } => Some(text.as_str()),
+                _ => None,
+            })
+            .collect::<Vec<_>>()
+            .join("");
+
+        let usage = anthropic_response.usage.to_usage();
+
+        debug!(
+            "Anthropic completion successful: {} tokens generated",
+            usage.completion_tokens
+        );
+
+        Ok(CompletionResponse {
+            content,
+            usage,
+            model: anthropic_response.model,
+            tool_calls: None,
+        })
+    }
+
+    /// Turn Messages API stream events (`data: {...}` lines) into chunks
+    pub(crate) async fn parse_streaming_response(
         &self,
         mut stream: impl futures_util::Stream<Item = reqwest::Result<Bytes>> + Unpin,
         tx: mpsc::Sender<Result<CompletionChunk>>,
@@ -459,12 +548,7 @@ impl AnthropicProvider {
                                             // Extract usage data from message_start event
                                             if let Some(message) = event.message {
                                                 if let Some(usage) = message.usage {
-                                                    accumulated_usage = Some(Usage {
-                                                        prompt_tokens: usage.input_tokens,
-                                                        completion_tokens: usage.output_tokens,
-                                                        total_tokens: usage.input_tokens
-                                                            + usage.output_tokens,
-                                                    });
+                                                    accumulated_usage = Some(usage.to_usage());
                                                     debug!(
                                                         "Captured usage from message_start: {:?}",
                                                         accumulated_usage
@@ -609,6 +693,21 @@ impl AnthropicProvider {
                                                 }
                                             }
                                         }
+                                        "message_delta" => {
+                                            // Carries the final output token count;
+                                            // message_start only had the first few
+                                            if let (Some(delta_usage), Some(usage)) =
+                                                (event.usage, accumulated_usage.as_mut())
+                                            {
+                                                usage.completion_tokens = delta_usage.output_tokens;
+                                                usage.total_tokens =
+                                                    usage.prompt_tokens + delta_usage.output_tokens;
+                                                debug!(
+                                                    "Updated usage from message_delta: {:?}",
+                                                    usage
+                                                );
+                                            }
+                                        }
                                         "message_stop" => {
                                             debug!("Received message stop event");
                                             message_stopped = true;
@@ -721,39 +820,11 @@ impl LLMProvider for AnthropicProvider {
             return Err(anyhow!("Anthropic API error {}: {}", status, error_text));
         }
 
-        let anthropic_response: AnthropicResponse = response
-            .json()
+        let body = response
+            .bytes()
             .await
-            .map_err(|e| anyhow!("Failed to parse Anthropic response: {}", e))?;
-
-        // Extract text content from the response
-        let content = anthropic_response
-            .content
-            .iter()
-            .filter_map(|c| match c {
-                AnthropicContent::Text { text, .. } => Some(text.as_str()),
-                _ => None,
-            })
-            .collect::<Vec<_>>()
-            .join("");
-
-        let usage = Usage {
-            prompt_tokens: anthropic_response.usage.input_tokens,
-            completion_tokens: anthropic_response.usage.output_tokens,
-            total_tokens: anthropic_response.usage.input_tokens
-                + anthropic_response.usage.output_tokens,
-        };
-
-        debug!(
-            "Anthropic completion successful: {} tokens generated",
-            usage.completion_tokens
-        );
-
-        Ok(CompletionResponse {
-            content,
-            usage,
-            model: anthropic_response.model,
-        })
+            .map_err(|e| anyhow!("Failed to read Anthropic response: {}", e))?;
+        self.parse_completion(&body)
     }
 
     async fn stream(&self, request: CompletionRequest) -> Result<CompletionStream> {
@@ -870,7 +941,7 @@ struct AnthropicRequest {
     temperature: f32,
     messages: Vec<AnthropicMessage>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    system: Option<String>,
+    system: Option<AnthropicSystem>,
     #[serde(skip_serializing_if = "Option::is_none")]
     tools: Option<Vec<AnthropicTool>>,
     stream: bool,
@@ -878,6 +949,13 @@ struct AnthropicRequest {
     thinking: Option<ThinkingConfig>,
 }
 
+#[derive(Debug, Serialize)]
+#[serde(untagged)]
+enum AnthropicSystem {
+    Text(String),
+    Blocks(Vec<AnthropicContent>),
+}
+
 #[derive(Debug, Serialize)]
 struct AnthropicTool {
     name: String,
@@ -934,6 +1012,25 @@ struct AnthropicResponse {
 struct AnthropicUsage {
     input_tokens: u32,
     output_tokens: u32,
+    #[serde(default)]
+    cache_read_input_tokens: u32,
+    #[serde(default)]
+    cache_creation_input_tokens: u32,
+}
+
+impl AnthropicUsage {
+    /// `input_tokens` excludes cached tokens; `Usage::prompt_tokens` includes them
+    fn to_usage(&self) -> Usage {
+        let prompt_tokens =
+            self.input_tokens + self.cache_read_input_tokens + self.cache_creation_input_tokens;
+        Usage {
+            prompt_tokens,
+            completion_tokens: self.output_tokens,
+            total_tokens: prompt_tokens + self.output_tokens,
+            cache_read_tokens: self.cache_read_input_tokens,
+            cache_creation_tokens: self.cache_creation_input_tokens,
+        }
+    }
 }
 
 // Streaming response structures
@@ -950,6 +1047,14 @@ struct AnthropicStreamEvent {
     content_block: Option<AnthropicContent>,
     #[serde(default)]
     message: Option<AnthropicStreamMessage>,
+    /// Cumulative output tokens, sent on `message_delta`
+    #[serde(default)]
+    usage: Option<AnthropicDeltaUsage>,
+}
+
+#[derive(Debug, Deserialize)]
+struct AnthropicDeltaUsage {
+    output_tokens: u32,
 }
 
 #[derive(Debug, Deserialize)]
@@ -1105,6 +1210,41 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_system_breakpoints_split_the_system_prompt() {
+        let provider =
+            AnthropicProvider::new("test-key".to_string(), None, None, None, None, None, None).unwrap();
+
+        let mut messages = vec![
+            Message::new(MessageRole::System, "You are G3".to_string()),
+            Message::new(MessageRole::System, "Project README".to_string()),
+            Message::new(MessageRole::User, "Hello".to_string()),
+        ];
+        let body = provider
+            .create_request_body(&messages, None, false, 1000, 0.1, false)
+            .unwrap();
+        assert_eq!(
+            serde_json::to_value(&body).unwrap()["system"],
+            serde_json::json!("You are G3\n\nProject README")
+        );
+
+        messages[1].cache_control = Some(crate::CacheControl::one_hour());
+        let body = provider
+            .create_request_body(&messages, None, false, 1000, 0.1, false)
+            .unwrap();
+        assert_eq!(
+            serde_json::to_value(&body).unwrap()["system"],
+            serde_json::json!([
+                {"type": "text", "text": "You are G3"},
+                {
+                    "type": "text",
+                    "text": "Project README",
+                    "cache_control": {"type": "ephemeral", "ttl": "1h"}
+                }
+            ])
+        );
+    }
+
     #[test]
     fn test_thinking_parameter_serialization() {
         // Test WITHOUT thinking parameter
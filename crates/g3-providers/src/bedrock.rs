@@ -0,0 +1,822 @@
+//! AWS Bedrock provider implementation for the g3-providers crate.
+//!
+//! This module provides an implementation of the `LLMProvider` trait for Claude models served
+//! through the Bedrock runtime API (`InvokeModel` / `InvokeModelWithResponseStream`).
+//!
+//! # Features
+//!
+//! - SigV4 request signing with credentials from the environment or `~/.aws/credentials`
+//! - Decoding of the `application/vnd.amazon.eventstream` framing used for streaming
+//! - Message conversion, cache control and native tool calling shared with `AnthropicProvider`
+//!
+//! # Usage
+//!
+//! ```rust,no_run
+//! use g3_providers::{BedrockProvider, LLMProvider, CompletionRequest, Message, MessageRole};
+//!
+//! #[tokio::main]
+//! async fn main() -> anyhow::Result<()> {
+//!     // Credentials come from AWS_ACCESS_KEY_ID / AWS_SECRET_ACCESS_KEY or the default profile
+//!     let provider = BedrockProvider::new(
+//!         "us.anthropic.claude-sonnet-4-5-20250929-v1:0".to_string(),
+//!         Some("us-west-2".to_string()), // region
+//!         None, // profile
+//!         None, // max_tokens
+//!         None, // temperature
+//!     )?;
+//!
+//!     let request = CompletionRequest {
+//!         messages: vec![Message::new(MessageRole::User, "Hello!".to_string())],
+//!         max_tokens: Some(1000),
+//!         temperature: Some(0.7),
+//!         stream: false,
+//!         tools: None,
+//!         disable_thinking: false,
+//!     };
+//!
+//!     let response = provider.complete(request).await?;
+//!     println!("Response: {}", response.content);
+//!
+//!     Ok(())
+//! }
+//! ```
+
+use anyhow::{anyhow, Context, Result};
+use async_trait::async_trait;
+use base64::Engine;
+use bytes::Bytes;
+use chrono::{DateTime, Utc};
+use futures_util::stream::{Stream, StreamExt};
+use hmac::{Hmac, Mac};
+use reqwest::Client;
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use std::path::PathBuf;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+use tracing::debug;
+use url::Url;
+
+use crate::{
+    AnthropicProvider, CompletionRequest, CompletionResponse, CompletionStream, LLMProvider,
+};
+
+const BEDROCK_ANTHROPIC_VERSION: &str = "bedrock-2023-05-31";
+const BEDROCK_SERVICE: &str = "bedrock";
+const DEFAULT_REGION: &str = "us-east-1";
+
+/// AWS access keys used to sign Bedrock requests
+#[derive(Clone)]
+pub struct AwsCredentials {
+    pub access_key_id: String,
+    pub secret_access_key: String,
+    pub session_token: Option<String>,
+}
+
+impl std::fmt::Debug for AwsCredentials {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AwsCredentials")
+            .field("access_key_id", &self.access_key_id)
+            .finish_non_exhaustive()
+    }
+}
+
+impl AwsCredentials {
+    /// Read `AWS_ACCESS_KEY_ID`, `AWS_SECRET_ACCESS_KEY` and `AWS_SESSION_TOKEN`
+    pub fn from_env() -> Option<Self> {
+        Some(Self {
+            access_key_id: std::env::var("AWS_ACCESS_KEY_ID").ok()?,
+            secret_access_key: std::env::var("AWS_SECRET_ACCESS_KEY").ok()?,
+            session_token: std::env::var("AWS_SESSION_TOKEN").ok(),
+        })
+    }
+
+    /// Read static keys for `profile` from the shared credentials file
+    pub fn from_profile(profile: &str) -> Result<Self> {
+        let path = aws_file("AWS_SHARED_CREDENTIALS_FILE", "credentials")?;
+        let content = std::fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        let value = |key: &str| ini_value(&content, profile, key);
+
+        Ok(Self {
+            access_key_id: value("aws_access_key_id").ok_or_else(|| {
+                anyhow!(
+                    "No aws_access_key_id for profile '{}' in {}",
+                    profile,
+                    path.display()
+                )
+            })?,
+            secret_access_key: value("aws_secret_access_key").ok_or_else(|| {
+                anyhow!(
+                    "No aws_secret_access_key for profile '{}' in {}",
+                    profile,
+                    path.display()
+                )
+            })?,
+            session_token: value("aws_session_token"),
+        })
+    }
+
+    /// Use `profile` if given, else the environment, else `AWS_PROFILE` or "default"
+    pub fn load(profile: Option<&str>) -> Result<Self> {
+        if let Some(profile) = profile {
+            return Self::from_profile(profile);
+        }
+        if let Some(credentials) = Self::from_env() {
+            return Ok(credentials);
+        }
+        Self::from_profile(&default_profile())
+    }
+}
+
+#[derive(Clone)]
+pub struct BedrockProvider {
+    client: Client,
+    name: String,
+    model: String,
+    region: String,
+    endpoint: String,
+    credentials: AwsCredentials,
+    /// Builds request bodies and parses responses, which Bedrock shares with the Messages API
+    messages: AnthropicProvider,
+}
+
+impl BedrockProvider {
+    pub fn new(
+        model: String,
+        region: Option<String>,
+        profile: Option<String>,
+        max_tokens: Option<u32>,
+        temperature: Option<f32>,
+    ) -> Result<Self> {
+        Self::new_with_name(
+            "bedrock".to_string(),
+            model,
+            region,
+            profile,
+            max_tokens,
+            temperature,
+            None,
+        )
+    }
+
+    pub fn new_with_name(
+        name: String,
+        model: String,
+        region: Option<String>,
+        profile: Option<String>,
+        max_tokens: Option<u32>,
+        temperature: Option<f32>,
+        cache_config: Option<String>,
+    ) -> Result<Self> {
+        let client = Client::builder()
+            .timeout(Duration::from_secs(300))
+            .build()
+            .map_err(|e| anyhow!("Failed to create HTTP client: {}", e))?;
+
+        let credentials = AwsCredentials::load(profile.as_deref())
+            .context("No AWS credentials found for Bedrock")?;
+        let region = region
+            .or_else(|| std::env::var("AWS_REGION").ok())
+            .or_else(|| std::env::var("AWS_DEFAULT_REGION").ok())
+            .or_else(|| profile_region(profile.as_deref()))
+            .unwrap_or_else(|| DEFAULT_REGION.to_string());
+
+        let messages = AnthropicProvider::new_with_name(
+            name.clone(),
+            String::new(),
+            Some(model.clone()),
+            max_tokens,
+            temperature,
+            cache_config,
+            None,
+            None,
+        )?;
+
+        debug!(
+            "Initialized Bedrock provider '{}' with model {} in {}",
+            name, model, region
+        );
+
+        Ok(Self {
+            client,
+            name,
+            model,
+            endpoint: format!("https://bedrock-runtime.{}.amazonaws.com", region),
+            region,
+            credentials,
+            messages,
+        })
+    }
+
+    /// Send requests to a different endpoint, e.g. a VPC endpoint or a local stub
+    pub fn with_endpoint_url(mut self, endpoint: String) -> Self {
+        self.endpoint = endpoint.trim_end_matches('/').to_string();
+        self
+    }
+
+    pub fn with_credentials(mut self, credentials: AwsCredentials) -> Self {
+        self.credentials = credentials;
+        self
+    }
+
+    async fn send(
+        &self,
+        request: &CompletionRequest,
+        streaming: bool,
+    ) -> Result<reqwest::Response> {
+        // Bedrock takes the model from the URL and streams based on the endpoint
+        let mut body = self.messages.request_json(request, streaming)?;
+        if let Some(body) = body.as_object_mut() {
+            body.remove("model");
+            body.remove("stream");
+            body.insert(
+                "anthropic_version".to_string(),
+                json!(BEDROCK_ANTHROPIC_VERSION),
+            );
+        }
+        let payload = serde_json::to_vec(&body)?;
+
+        let action = if streaming {
+            "invoke-with-response-stream"
+        } else {
+            "invoke"
+        };
+        let url = Url::parse(&format!(
+            "{}/model/{}/{}",
+            self.endpoint,
+            uri_encode(&self.model, true),
+            action
+        ))?;
+
+        let content_type = "application/json".to_string();
+        let signed = sign_request(
+            &self.credentials,
+            &self.region,
+            BEDROCK_SERVICE,
+            "POST",
+            &url,
+            &[("content-type", content_type.clone())],
+            &payload,
+            Utc::now(),
+        );
+
+        debug!("Sending request to Bedrock: {}", url);
+
+        let mut builder = self
+            .client
+            .post(url)
+            .header("content-type", content_type)
+            .body(payload);
+        for (name, value) in signed {
+            builder = builder.header(name, value);
+        }
+
+        let response = builder
+            .send()
+            .await
+            .map_err(|e| anyhow!("Failed to send request to Bedrock: {}", e))?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let error_text = response
+                .text()
+                .await
+                .unwrap_or_else(|_| "Unknown error".to_string());
+            return Err(anyhow!("Bedrock API error {}: {}", status, error_text));
+        }
+
+        Ok(response)
+    }
+}
+
+#[async_trait]
+impl LLMProvider for BedrockProvider {
+    async fn complete(&self, request: CompletionRequest) -> Result<CompletionResponse> {
+        debug!(
+            "Processing Bedrock completion request with {} messages",
+            request.messages.len()
+        );
+
+        let body = self.send(&request, false).await?.bytes().await?;
+        self.messages.parse_completion(&body)
+    }
+
+    async fn stream(&self, request: CompletionRequest) -> Result<CompletionStream> {
+        debug!(
+            "Processing Bedrock streaming request with {} messages",
+            request.messages.len()
+        );
+
+        let events = anthropic_events(self.send(&request, true).await?.bytes_stream());
+        let (tx, rx) = mpsc::channel(100);
+
+        // Spawn task to process the stream
+        let messages = self.messages.clone();
+        tokio::spawn(async move {
+            let usage = messages.parse_streaming_response(events, tx).await;
+            if let Some(usage) = usage {
+                debug!(
+                    "Stream completed with usage - prompt: {}, completion: {}, total: {}",
+                    usage.prompt_tokens, usage.completion_tokens, usage.total_tokens
+                );
+            }
+        });
+
+        Ok(ReceiverStream::new(rx))
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn model(&self) -> &str {
+        &self.model
+    }
+
+    fn has_native_tool_calling(&self) -> bool {
+        true
+    }
+
+    fn supports_cache_control(&self) -> bool {
+        true
+    }
+
+    fn max_tokens(&self) -> u32 {
+        self.messages.max_tokens()
+    }
+
+    fn temperature(&self) -> f32 {
+        self.messages.temperature()
+    }
+}
+
+/// Re-frame a Bedrock event stream as the `data: {...}` lines the Messages API streams
+fn anthropic_events(
+    body: impl Stream<Item = reqwest::Result<Bytes>> + Send + Unpin + 'static,
+) -> impl Stream<Item = reqwest::Result<Bytes>> + Send + Unpin {
+    let state = (body, EventStreamDecoder::default(), false);
+    Box::pin(futures_util::stream::unfold(
+        state,
+        |(mut body, mut decoder, done)| async move {
+            if done {
+                return None;
+            }
+            loop {
+                match decoder.next_message() {
+                    Ok(Some(message)) => {
+                        if let Some(line) = message.to_sse_line() {
+                            return Some((Ok(line), (body, decoder, false)));
+                        }
+                        continue;
+                    }
+                    Ok(None) => {}
+                    Err(e) => {
+                        let line = sse_error("eventStreamError", &e.to_string());
+                        return Some((Ok(line), (body, decoder, true)));
+                    }
+                }
+                match body.next().await {
+                    Some(Ok(bytes)) => decoder.push(&bytes),
+                    Some(Err(e)) => return Some((Err(e), (body, decoder, true))),
+                    None => return None,
+                }
+            }
+        },
+    ))
+}
+
+fn sse_error(error_type: &str, message: &str) -> Bytes {
+    let event = json!({
+        "type": "error",
+        "error": { "type": error_type, "message": message },
+    });
+    Bytes::from(format!("data: {}\n\n", event))
+}
+
+/// One message of the `application/vnd.amazon.eventstream` framing
+#[derive(Debug)]
+pub(crate) struct EventStreamMessage {
+    /// String-valued headers; other header types are skipped
+    pub headers: Vec<(String, String)>,
+    pub payload: Vec<u8>,
+}
+
+impl EventStreamMessage {
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// `chunk` events carry a base64-encoded Messages API event; exceptions
+    /// become Messages API `error` events
+    fn to_sse_line(&self) -> Option<Bytes> {
+        match self.header(":message-type") {
+            Some("event") if self.header(":event-type") == Some("chunk") => {
+                let payload: serde_json::Value = serde_json::from_slice(&self.payload).ok()?;
+                let event = base64::engine::general_purpose::STANDARD
+                    .decode(payload["bytes"].as_str()?)
+                    .ok()?;
+                let mut line = b"data: ".to_vec();
+                line.extend_from_slice(&event);
+                line.extend_from_slice(b"\n\n");
+                Some(Bytes::from(line))
+            }
+            Some("exception") => {
+                let payload: serde_json::Value =
+                    serde_json::from_slice(&self.payload).unwrap_or_default();
+                let message = payload["message"]
+                    .as_str()
+                    .or_else(|| payload["Message"].as_str())
+                    .unwrap_or("unknown error");
+                Some(sse_error(
+                    self.header(":exception-type").unwrap_or("exception"),
+                    message,
+                ))
+            }
+            _ => {
+                debug!("Ignoring event stream message: {:?}", self.headers);
+                None
+            }
+        }
+    }
+}
+
+/// Incremental decoder for the AWS event stream binary framing: a 12-byte
+/// prelude (total length, header length, prelude CRC), headers, payload and
+/// a CRC of the whole message
+#[derive(Debug, Default)]
+pub(crate) struct EventStreamDecoder {
+    buffer: Vec<u8>,
+}
+
+impl EventStreamDecoder {
+    pub fn push(&mut self, bytes: &[u8]) {
+        self.buffer.extend_from_slice(bytes);
+    }
+
+    /// The next complete message, or `None` if more bytes are needed
+    pub fn next_message(&mut self) -> Result<Option<EventStreamMessage>> {
+        if self.buffer.len() < 12 {
+            return Ok(None);
+        }
+        let total_len = read_u32(&self.buffer[0..4]) as usize;
+        let headers_len = read_u32(&self.buffer[4..8]) as usize;
+        if read_u32(&self.buffer[8..12]) != crc32fast::hash(&self.buffer[0..8]) {
+            return Err(anyhow!("Event stream prelude checksum mismatch"));
+        }
+        if total_len < 16 || headers_len > total_len - 16 {
+            return Err(anyhow!("Invalid event stream message length {}", total_len));
+        }
+        if self.buffer.len() < total_len {
+            return Ok(None);
+        }
+
+        let message: Vec<u8> = self.buffer.drain(..total_len).collect();
+        if read_u32(&message[total_len - 4..]) != crc32fast::hash(&message[..total_len - 4]) {
+            return Err(anyhow!("Event stream message checksum mismatch"));
+        }
+
+        let headers = parse_headers(&message[12..12 + headers_len])?;
+        let payload = message[12 + headers_len..total_len - 4].to_vec();
+        Ok(Some(EventStreamMessage { headers, payload }))
+    }
+}
+
+fn read_u32(bytes: &[u8]) -> u32 {
+    u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
+}
+
+fn parse_headers(mut bytes: &[u8]) -> Result<Vec<(String, String)>> {
+    let truncated = || anyhow!("Truncated event stream header");
+    let mut headers = Vec::new();
+
+    while !bytes.is_empty() {
+        let name_len = bytes[0] as usize;
+        let name = bytes.get(1..1 + name_len).ok_or_else(truncated)?;
+        let name = String::from_utf8_lossy(name).to_string();
+        let value_type = *bytes.get(1 + name_len).ok_or_else(truncated)?;
+        bytes = &bytes[2 + name_len..];
+
+        let value_len = match value_type {
+            0 | 1 => 0,
+            2 => 1,
+            3 => 2,
+            4 => 4,
+            5 | 8 => 8,
+            9 => 16,
+            6 | 7 => {
+                let len = bytes.get(0..2).ok_or_else(truncated)?;
+                let len = u16::from_be_bytes([len[0], len[1]]) as usize;
+                bytes = &bytes[2..];
+                len
+            }
+            other => return Err(anyhow!("Unknown event stream header type {}", other)),
+        };
+        let value = bytes.get(..value_len).ok_or_else(truncated)?;
+        if value_type == 7 {
+            headers.push((name, String::from_utf8_lossy(value).to_string()));
+        }
+        bytes = &bytes[value_len..];
+    }
+
+    Ok(headers)
+}
+
+/// Sign a request with AWS Signature Version 4. `headers` are signed along
+/// with `host`; the returned headers must be added to the request.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn sign_request(
+    credentials: &AwsCredentials,
+    region: &str,
+    service: &str,
+    method: &str,
+    url: &Url,
+    headers: &[(&str, String)],
+    payload: &[u8],
+    now: DateTime<Utc>,
+) -> Vec<(String, String)> {
+    let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+    let date = now.format("%Y%m%d").to_string();
+
+    let host = match url.port() {
+        Some(port) => format!("{}:{}", url.host_str().unwrap_or_default(), port),
+        None => url.host_str().unwrap_or_default().to_string(),
+    };
+
+    let mut signed: Vec<(String, String)> = headers
+        .iter()
+        .map(|(name, value)| (name.to_lowercase(), value.trim().to_string()))
+        .collect();
+    signed.push(("host".to_string(), host));
+    signed.push(("x-amz-date".to_string(), amz_date.clone()));
+    if let Some(token) = &credentials.session_token {
+        signed.push(("x-amz-security-token".to_string(), token.clone()));
+    }
+    signed.sort();
+
+    let canonical_headers: String = signed
+        .iter()
+        .map(|(name, value)| format!("{}:{}\n", name, value))
+        .collect();
+    let signed_headers = signed
+        .iter()
+        .map(|(name, _)| name.as_str())
+        .collect::<Vec<_>>()
+        .join(";");
+
+    let mut query: Vec<(String, String)> = url
+        .query_pairs()
+        .map(|(key, value)| (uri_encode(&key, true), uri_encode(&value, true)))
+        .collect();
+    query.sort();
+    let canonical_query = query
+        .iter()
+        .map(|(key, value)| format!("{}={}", key, value))
+        .collect::<Vec<_>>()
+        .join("&");
+
+    // Services other than S3 sign the already-encoded path, encoded once more
+    let canonical_request = format!(
+        "{}\n{}\n{}\n{}\n{}\n{}",
+        method,
+        uri_encode(url.path(), false),
+        canonical_query,
+        canonical_headers,
+        signed_headers,
+        hex::encode(Sha256::digest(payload))
+    );
+
+    let scope = format!("{}/{}/{}/aws4_request", date, region, service);
+    let string_to_sign = format!(
+        "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+        amz_date,
+        scope,
+        hex::encode(Sha256::digest(canonical_request.as_bytes()))
+    );
+
+    let key = [date.as_str(), region, service, "aws4_request"]
+        .iter()
+        .fold(
+            format!("AWS4{}", credentials.secret_access_key).into_bytes(),
+            |key, part| hmac_sha256(&key, part.as_bytes()),
+        );
+    let signature = hex::encode(hmac_sha256(&key, string_to_sign.as_bytes()));
+
+    let mut result = vec![
+        (
+            "authorization".to_string(),
+            format!(
+                "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders={}, Signature={}",
+                credentials.access_key_id, scope, signed_headers, signature
+            ),
+        ),
+        ("x-amz-date".to_string(), amz_date),
+    ];
+    if let Some(token) = &credentials.session_token {
+        result.push(("x-amz-security-token".to_string(), token.clone()));
+    }
+    result
+}
+
+fn hmac_sha256(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts any key length");
+    mac.update(data);
+    mac.finalize().into_bytes().to_vec()
+}
+
+/// Percent-encode everything but unreserved characters (and `/` unless `encode_slash`)
+fn uri_encode(input: &str, encode_slash: bool) -> String {
+    let mut encoded = String::with_capacity(input.len());
+    for byte in input.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                encoded.push(byte as char)
+            }
+            b'/' if !encode_slash => encoded.push('/'),
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
+
+fn default_profile() -> String {
+    std::env::var("AWS_PROFILE").unwrap_or_else(|_| "default".to_string())
+}
+
+fn aws_file(env_var: &str, name: &str) -> Result<PathBuf> {
+    if let Ok(path) = std::env::var(env_var) {
+        return Ok(PathBuf::from(path));
+    }
+    dirs::home_dir()
+        .map(|home| home.join(".aws").join(name))
+        .ok_or_else(|| anyhow!("Cannot locate ~/.aws/{}", name))
+}
+
+/// The `region` of a profile in `~/.aws/config`
+fn profile_region(profile: Option<&str>) -> Option<String> {
+    let profile = profile.map(str::to_string).unwrap_or_else(default_profile);
+    let content = std::fs::read_to_string(aws_file("AWS_CONFIG_FILE", "config").ok()?).ok()?;
+    let section = if profile == "default" {
+        profile
+    } else {
+        format!("profile {}", profile)
+    };
+    ini_value(&content, &section, "region")
+}
+
+/// Look up `key` in `[section]` of an AWS-style INI file
+fn ini_value(content: &str, section: &str, key: &str) -> Option<String> {
+    let mut in_section = false;
+    for line in content.lines() {
+        let line = line.trim();
+        if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            in_section = name.trim() == section;
+        } else if in_section {
+            if let Some((name, value)) = line.split_once('=') {
+                if name.trim() == key {
+                    return Some(value.trim().to_string());
+                }
+            }
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frame(headers: &[(&str, &str)], payload: &[u8]) -> Vec<u8> {
+        let mut header_bytes = Vec::new();
+        for (name, value) in headers {
+            header_bytes.push(name.len() as u8);
+            header_bytes.extend_from_slice(name.as_bytes());
+            header_bytes.push(7);
+            header_bytes.extend_from_slice(&(value.len() as u16).to_be_bytes());
+            header_bytes.extend_from_slice(value.as_bytes());
+        }
+        let total_len = (16 + header_bytes.len() + payload.len()) as u32;
+        let mut message = total_len.to_be_bytes().to_vec();
+        message.extend_from_slice(&(header_bytes.len() as u32).to_be_bytes());
+        let prelude_crc = crc32fast::hash(&message);
+        message.extend_from_slice(&prelude_crc.to_be_bytes());
+        message.extend_from_slice(&header_bytes);
+        message.extend_from_slice(payload);
+        let message_crc = crc32fast::hash(&message);
+        message.extend_from_slice(&message_crc.to_be_bytes());
+        message
+    }
+
+    #[test]
+    fn test_sigv4_matches_aws_example() {
+        // The GET ListUsers example from the AWS Signature Version 4 documentation
+        let credentials = AwsCredentials {
+            access_key_id: "AKIDEXAMPLE".to_string(),
+            secret_access_key: "wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY".to_string(),
+            session_token: None,
+        };
+        let url =
+            Url::parse("https://iam.amazonaws.com/?Action=ListUsers&Version=2010-05-08").unwrap();
+        let now = DateTime::parse_from_rfc3339("2015-08-30T12:36:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+
+        let headers = sign_request(
+            &credentials,
+            "us-east-1",
+            "iam",
+            "GET",
+            &url,
+            &[(
+                "content-type",
+                "application/x-www-form-urlencoded; charset=utf-8".to_string(),
+            )],
+            b"",
+            now,
+        );
+
+        assert_eq!(
+            headers[0].1,
+            "AWS4-HMAC-SHA256 Credential=AKIDEXAMPLE/20150830/us-east-1/iam/aws4_request, \
+             SignedHeaders=content-type;host;x-amz-date, \
+             Signature=5d672d79c15b13162d9279b0855cfba6789a8edb4c82c400e06b5924a6f2b5d7"
+        );
+        assert_eq!(headers[1].1, "20150830T123600Z");
+    }
+
+    #[test]
+    fn test_model_ids_are_encoded_in_path() {
+        assert_eq!(
+            uri_encode("anthropic.claude-3-5-sonnet-20241022-v2:0", true),
+            "anthropic.claude-3-5-sonnet-20241022-v2%3A0"
+        );
+        assert_eq!(
+            uri_encode("/model/a%3A0/invoke", false),
+            "/model/a%253A0/invoke"
+        );
+    }
+
+    #[test]
+    fn test_event_stream_decoder_handles_split_frames() {
+        let first = frame(
+            &[(":message-type", "event"), (":event-type", "chunk")],
+            br#"{"bytes":"eyJ0eXBlIjoibWVzc2FnZV9zdG9wIn0="}"#,
+        );
+        let second = frame(
+            &[
+                (":message-type", "exception"),
+                (":exception-type", "throttlingException"),
+            ],
+            br#"{"message":"Too many requests"}"#,
+        );
+        let bytes = [first, second].concat();
+
+        let mut decoder = EventStreamDecoder::default();
+        decoder.push(&bytes[..7]);
+        assert!(decoder.next_message().unwrap().is_none());
+        decoder.push(&bytes[7..]);
+
+        let message = decoder.next_message().unwrap().unwrap();
+        assert_eq!(message.header(":event-type"), Some("chunk"));
+        assert_eq!(
+            message.to_sse_line().unwrap(),
+            Bytes::from_static(b"data: {\"type\":\"message_stop\"}\n\n")
+        );
+
+        let message = decoder.next_message().unwrap().unwrap();
+        let line = String::from_utf8(message.to_sse_line().unwrap().to_vec()).unwrap();
+        assert!(line.contains("throttlingException"));
+        assert!(line.contains("Too many requests"));
+
+        assert!(decoder.next_message().unwrap().is_none());
+    }
+
+    #[test]
+    fn test_event_stream_decoder_rejects_corrupt_frames() {
+        let mut bytes = frame(&[(":message-type", "event")], b"{}");
+        let last = bytes.len() - 1;
+        bytes[last] ^= 0xff;
+
+        let mut decoder = EventStreamDecoder::default();
+        decoder.push(&bytes);
+        assert!(decoder.next_message().is_err());
+    }
+
+    #[test]
+    fn test_ini_value() {
+        let content = "[default]\nregion = us-east-1\n\n[profile work]\nregion=eu-west-1\n";
+        assert_eq!(
+            ini_value(content, "default", "region").as_deref(),
+            Some("us-east-1")
+        );
+        assert_eq!(
+            ini_value(content, "profile work", "region").as_deref(),
+            Some("eu-west-1")
+        );
+        assert_eq!(ini_value(content, "profile other", "region"), None);
+    }
+}
@@ -967,6 +967,7 @@ impl LLMProvider for DatabricksProvider {
             prompt_tokens: databricks_response.usage.prompt_tokens,
             completion_tokens: databricks_response.usage.completion_tokens,
             total_tokens: databricks_response.usage.total_tokens,
+            ..Default::default()
         };
 
         debug!(
@@ -978,6 +979,7 @@ impl LLMProvider for DatabricksProvider {
             content,
             usage,
             model: self.model.clone(),
+            tool_calls: None,
         })
     }
 
@@ -1,21 +1,119 @@
 use crate::{
-    CompletionChunk, CompletionRequest, CompletionResponse, CompletionStream, LLMProvider, Message,
-    MessageRole, Usage,
+    gbnf, CompletionChunk, CompletionRequest, CompletionResponse, CompletionStream, LLMProvider,
+    Message, MessageRole, Tokenizer, Tool, Usage,
 };
 use anyhow::Result;
 use llama_cpp::{
+    grammar::LlamaGrammar,
     standard_sampler::{SamplerStage, StandardSampler},
-    LlamaModel, LlamaParams, LlamaSession, SessionParams,
+    EmbeddingsParams, LlamaModel, LlamaParams, LlamaSession, SessionParams,
 };
+use std::fmt;
 use std::path::{Path, PathBuf};
+use std::str::FromStr;
 use std::sync::Arc;
 use tokio::sync::mpsc;
 use tokio::sync::Mutex;
 use tokio_stream::wrappers::ReceiverStream;
 use tracing::{debug, error, info};
 
+/// Counts tokens with the vocabulary stored in the GGUF file
+pub struct GgufTokenizer {
+    name: String,
+    model: LlamaModel,
+}
+
+impl fmt::Debug for GgufTokenizer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("GgufTokenizer")
+            .field("name", &self.name)
+            .finish()
+    }
+}
+
+impl Tokenizer for GgufTokenizer {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn count_tokens(&self, text: &str) -> u32 {
+        match self.model.tokenize_bytes(text, false, false) {
+            Ok(tokens) => tokens.len() as u32,
+            Err(e) => {
+                // Fall back to a rough estimate rather than under-counting
+                debug!("Tokenization failed, estimating instead: {}", e);
+                (text.len() as f32 / 4.0).ceil() as u32
+            }
+        }
+    }
+}
+
+/// A GGUF embedding model (e.g. nomic-embed-text) run in-process
+pub struct EmbeddingModel {
+    name: String,
+    model: LlamaModel,
+    threads: Option<u32>,
+}
+
+impl fmt::Debug for EmbeddingModel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EmbeddingModel")
+            .field("name", &self.name)
+            .finish()
+    }
+}
+
+impl EmbeddingModel {
+    pub fn load(model_path: &str, gpu_layers: Option<u32>, threads: Option<u32>) -> Result<Self> {
+        let expanded_path = shellexpand::tilde(model_path);
+        let model_path = Path::new(expanded_path.as_ref());
+        if !model_path.exists() {
+            anyhow::bail!("Embedding model not found: {}", model_path.display());
+        }
+
+        let mut params = LlamaParams::default();
+        if let Some(gpu_layers) = gpu_layers {
+            params.n_gpu_layers = gpu_layers;
+        }
+
+        info!("Loading embedding model from: {}", model_path.display());
+        let model = LlamaModel::load_from_file(model_path, params)
+            .map_err(|e| anyhow::anyhow!("Failed to load embedding model: {}", e))?;
+
+        let name = model_path
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .unwrap_or("embedding-model")
+            .to_string();
+        Ok(Self {
+            name,
+            model,
+            threads,
+        })
+    }
+
+    /// Model file name without extension; vectors from different models are
+    /// not comparable
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// One vector per input text
+    pub fn embed(&self, texts: &[String]) -> Result<Vec<Vec<f32>>> {
+        let mut params = EmbeddingsParams::default();
+        if let Some(threads) = self.threads {
+            params.n_threads = threads;
+            params.n_threads_batch = threads;
+        }
+        self.model
+            .embeddings(texts, params)
+            .map_err(|e| anyhow::anyhow!("Embedding failed: {}", e))
+    }
+}
+
 pub struct EmbeddedProvider {
     session: Arc<Mutex<LlamaSession>>,
+    tokenizer: Arc<GgufTokenizer>,
     model_name: String,
     max_tokens: u32,
     temperature: f32,
@@ -83,6 +181,10 @@ impl EmbeddedProvider {
 
         Ok(Self {
             session: Arc::new(Mutex::new(session)),
+            tokenizer: Arc::new(GgufTokenizer {
+                name: format!("gguf({})", model_type),
+                model,
+            }),
             model_name: format!("embedded-{}", model_type),
             max_tokens: max_tokens.unwrap_or(2048),
             temperature: temperature.unwrap_or(0.1),
@@ -348,11 +450,9 @@ impl EmbeddedProvider {
         }
     }
 
-    // Helper function to estimate token count from text
+    // Helper function to count tokens with the model's own vocabulary
     fn estimate_tokens(&self, text: &str) -> u32 {
-        // Rough estimation: average 4 characters per token
-        // This is conservative - actual tokenization might be different
-        (text.len() as f32 / 4.0).ceil() as u32
+        self.tokenizer.count_tokens(text)
     }
 
     // Helper function to get stop sequences based on model type
@@ -435,6 +535,76 @@ impl EmbeddedProvider {
         cleaned.trim().to_string()
     }
 
+    /// Add the tool descriptions and call format to the system message.
+    /// Chat templates of tool-capable models do the same with their tool list.
+    fn with_tool_instructions(messages: &[Message], tools: &[Tool]) -> Vec<Message> {
+        let instructions = gbnf::tool_instructions(tools);
+        let mut messages = messages.to_vec();
+        match messages
+            .iter_mut()
+            .find(|message| matches!(message.role, MessageRole::System))
+        {
+            Some(system) => {
+                system.content.push_str("\n\n");
+                system.content.push_str(&instructions);
+            }
+            None => messages.insert(0, Message::new(MessageRole::System, instructions)),
+        }
+        messages
+    }
+
+    /// Generate a tool call under `grammar`, starting where the model began
+    /// writing it. `token_ends` holds the end offset of each token generated
+    /// so far; the context is rewound to just before the call's opening
+    /// brace, feeding back any text that shared a token with the brace.
+    fn generate_tool_call(
+        session: &mut LlamaSession,
+        grammar: &str,
+        prompt_tokens: usize,
+        token_ends: &[usize],
+        text_before: &str,
+        temperature: f32,
+        max_tokens: usize,
+    ) -> Result<String> {
+        let kept = token_ends
+            .iter()
+            .take_while(|&&end| end <= text_before.len())
+            .count();
+        let kept_len = kept.checked_sub(1).map_or(0, |last| token_ends[last]);
+        session.truncate_context(prompt_tokens + kept);
+        let leftover = &text_before[kept_len..];
+        if !leftover.is_empty() {
+            session
+                .advance_context(leftover)
+                .map_err(|e| anyhow::anyhow!("Failed to extend context: {}", e))?;
+        }
+
+        let grammar = LlamaGrammar::from_str(grammar)
+            .map_err(|e| anyhow::anyhow!("Invalid tool call grammar: {}", e))?;
+        let stages = vec![
+            SamplerStage::Temperature(temperature),
+            SamplerStage::TopK(40),
+            SamplerStage::TopP(0.9),
+        ];
+        let sampler = StandardSampler::new_softmax_with_grammar(stages, 1, grammar);
+        let mut completion_handle = session
+            .start_completing_with(sampler, max_tokens)
+            .map_err(|e| anyhow::anyhow!("Failed to start tool call: {}", e))?;
+
+        // The grammar only allows end-of-text once the call is complete
+        let mut text = String::new();
+        let mut token_count = 0;
+        while let Some(token) = completion_handle.next_token() {
+            text.push_str(&session.model().token_to_piece(token));
+            token_count += 1;
+            if token_count >= max_tokens {
+                debug!("Reached max token limit inside a tool call");
+                break;
+            }
+        }
+        Ok(text)
+    }
+
     // Download the Qwen 2.5 7B model if it doesn't exist
     fn download_qwen_model(model_path: &Path) -> Result<()> {
         use std::fs;
@@ -520,9 +690,8 @@ impl LLMProvider for EmbeddedProvider {
             .generate_completion(&prompt, max_tokens, temperature)
             .await?;
 
-        // Estimate token usage (rough approximation)
-        let prompt_tokens = (prompt.len() / 4) as u32; // Rough estimate: 4 chars per token
-        let completion_tokens = (content.len() / 4) as u32;
+        let prompt_tokens = self.estimate_tokens(&prompt);
+        let completion_tokens = self.estimate_tokens(&content);
 
         Ok(CompletionResponse {
             content,
@@ -530,8 +699,10 @@ impl LLMProvider for EmbeddedProvider {
                 prompt_tokens,
                 completion_tokens,
                 total_tokens: prompt_tokens + completion_tokens,
+                ..Default::default()
             },
             model: self.model_name.clone(),
+            tool_calls: None,
         })
     }
 
@@ -541,7 +712,15 @@ impl LLMProvider for EmbeddedProvider {
             request.messages.len()
         );
 
-        let prompt = self.format_messages(&request.messages);
+        // With tools, calls are constrained by a grammar once the model starts one
+        let tools = request.tools.filter(|tools| !tools.is_empty());
+        let prompt = match &tools {
+            Some(tools) => {
+                self.format_messages(&Self::with_tool_instructions(&request.messages, tools))
+            }
+            None => self.format_messages(&request.messages),
+        };
+        let grammar = tools.as_deref().map(gbnf::tool_call_grammar);
         let max_tokens = request.max_tokens.unwrap_or(self.max_tokens);
         let temperature = request.temperature.unwrap_or(self.temperature);
 
@@ -593,6 +772,7 @@ impl LLMProvider for EmbeddedProvider {
                 let _ = tx.blocking_send(Err(anyhow::anyhow!("Failed to set context: {}", e)));
                 return;
             }
+            let prompt_tokens = session.context_size();
 
             // Create sampler with temperature
             let stages = vec![
@@ -617,6 +797,8 @@ impl LLMProvider for EmbeddedProvider {
             let mut accumulated_text = String::new();
             let mut token_count = 0;
             let mut unsent_tokens = String::new(); // Buffer for tokens we're holding back
+            let mut token_ends = Vec::new(); // End offset of each token in accumulated_text
+            let mut tool_call_start = None;
 
             // Get stop sequences dynamically based on model type
             let stop_sequences = if prompt.contains("<|im_start|>") {
@@ -652,8 +834,18 @@ impl LLMProvider for EmbeddedProvider {
 
                 accumulated_text.push_str(&token_string);
                 unsent_tokens.push_str(&token_string);
+                token_ends.push(accumulated_text.len());
                 token_count += 1;
 
+                // Hand the rest of a tool call over to the grammar
+                if grammar.is_some() {
+                    if let Some(start) = gbnf::find_tool_call_start(&accumulated_text) {
+                        debug!("Tool call started at offset {}", start);
+                        tool_call_start = Some(start);
+                        break;
+                    }
+                }
+
                 // Check if we've hit a complete stop sequence
                 let mut hit_stop = false;
                 for stop_seq in &stop_sequences {
@@ -754,6 +946,10 @@ impl LLMProvider for EmbeddedProvider {
                     }
                 }
 
+                if grammar.is_some() && gbnf::ends_with_partial_tool_call_start(&accumulated_text) {
+                    might_be_stop = true;
+                }
+
                 if might_be_stop {
                     // Hold back tokens, but only for a limited buffer size
                     if unsent_tokens.len() > 20 {
@@ -797,12 +993,58 @@ impl LLMProvider for EmbeddedProvider {
                 }
             }
 
+            let mut tool_calls = None;
+            if let (Some(start), Some(grammar)) = (tool_call_start, grammar.as_deref()) {
+                // Send the text before the call, which may still be held back
+                let already_sent_len = accumulated_text.len() - unsent_tokens.len();
+                if start > already_sent_len {
+                    let chunk = CompletionChunk {
+                        content: accumulated_text[already_sent_len..start].to_string(),
+                        finished: false,
+                        usage: None,
+                        tool_calls: None,
+                    };
+                    let _ = tx.blocking_send(Ok(chunk));
+                }
+                drop(completion_handle);
+
+                match Self::generate_tool_call(
+                    &mut session,
+                    grammar,
+                    prompt_tokens,
+                    &token_ends,
+                    &accumulated_text[..start],
+                    temperature,
+                    (max_tokens as usize).saturating_sub(token_count).max(1),
+                ) {
+                    Ok(text) => match gbnf::parse_tool_call(&text, "call_0".to_string()) {
+                        Some(tool_call) => tool_calls = Some(vec![tool_call]),
+                        None => {
+                            // Cut off by the token limit; let the agent's text parser try
+                            debug!("Tool call did not complete: {}", text);
+                            let chunk = CompletionChunk {
+                                content: text,
+                                finished: false,
+                                usage: None,
+                                tool_calls: None,
+                            };
+                            let _ = tx.blocking_send(Ok(chunk));
+                        }
+                    },
+                    Err(e) => {
+                        error!("Constrained tool call failed: {}", e);
+                        let _ = tx.blocking_send(Err(e));
+                        return;
+                    }
+                }
+            }
+
             // Send final chunk
             let final_chunk = CompletionChunk {
                 content: String::new(),
                 finished: true,
                 usage: None, // Embedded models calculate usage differently
-                tool_calls: None,
+                tool_calls,
             };
             let _ = tx.blocking_send(Ok(final_chunk));
         });
@@ -814,6 +1056,11 @@ impl LLMProvider for EmbeddedProvider {
         "embedded"
     }
 
+    /// Tool calls are generated under a grammar built from the tool schemas
+    fn has_native_tool_calling(&self) -> bool {
+        true
+    }
+
     fn model(&self) -> &str {
         &self.model_name
     }
@@ -825,4 +1072,8 @@ impl LLMProvider for EmbeddedProvider {
     fn temperature(&self) -> f32 {
         self.temperature
     }
+
+    fn tokenizer(&self) -> Option<Arc<dyn Tokenizer>> {
+        Some(self.tokenizer.clone())
+    }
 }
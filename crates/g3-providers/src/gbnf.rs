@@ -0,0 +1,410 @@
+//! GBNF grammars for tool calls
+//!
+//! Local models run through llama.cpp have no native tool calling. Instead
+//! they write `{"tool": "name", "args": {...}}` objects into their reply, and
+//! small models often get the JSON wrong. Once a reply starts such an object,
+//! the embedded provider switches to a llama.cpp grammar generated here from
+//! the tools' input schemas, so sampling can only produce a call to a known
+//! tool with arguments of the right shape.
+
+use crate::{Tool, ToolCall};
+use serde_json::Value;
+use std::collections::HashSet;
+
+/// Rules shared by every generated grammar. Each value rule also consumes
+/// the whitespace that follows it.
+const JSON_RULES: &str = r#"value ::= object | array | string | number | boolean | null
+object ::= "{" ws ( string ":" ws value ( "," ws string ":" ws value )* )? "}" ws
+array ::= "[" ws ( value ( "," ws value )* )? "]" ws
+string ::= "\"" ( [^"\\\x00-\x1F] | "\\" ( ["\\/bfnrt] | "u" hex hex hex hex ) )* "\"" ws
+hex ::= [0-9a-fA-F]
+int-part ::= "-"? ( [0-9] | [1-9] [0-9]* )
+integer ::= int-part ws
+number ::= int-part ( "." [0-9]+ )? ( [eE] [-+]? [0-9]+ )? ws
+boolean ::= ( "true" | "false" ) ws
+null ::= "null" ws
+ws ::= ( [ \t\n] ws )?
+"#;
+
+/// Build a grammar whose `root` matches exactly one call to one of `tools`:
+/// `{"tool": "<name>", "args": {...}}`
+pub fn tool_call_grammar(tools: &[Tool]) -> String {
+    let mut builder = GrammarBuilder::default();
+
+    let calls: Vec<String> = tools
+        .iter()
+        .map(|tool| {
+            let hint = format!("{}-args", tool.name);
+            let args = builder.schema(&hint, &tool.input_schema);
+            builder.add(
+                &format!("{}-call", tool.name),
+                format!(
+                    r#"{} ws "," ws "\"args\"" ws ":" ws {}"#,
+                    literal(&Value::String(tool.name.clone())),
+                    args
+                ),
+            )
+        })
+        .collect();
+
+    let mut grammar = format!(
+        "root ::= \"{{\" ws \"\\\"tool\\\"\" ws \":\" ws ( {} ) \"}}\"\n",
+        calls.join(" | ")
+    );
+    for (name, body) in &builder.rules {
+        grammar.push_str(&format!("{} ::= {}\n", name, body));
+    }
+    grammar.push_str(JSON_RULES);
+    grammar
+}
+
+/// Describe `tools` and the call format for the system prompt
+pub fn tool_instructions(tools: &[Tool]) -> String {
+    let mut instructions = String::from(
+        "# Tool Call Format\n\n\
+         When you need to execute a tool, write ONLY the JSON tool call on a new line:\n\n\
+         {\"tool\": \"tool_name\", \"args\": {\"param\": \"value\"}}\n\n\
+         # Available Tools\n",
+    );
+    for tool in tools {
+        instructions.push_str(&format!("\n- **{}**: {}\n", tool.name, tool.description));
+        if let Some(properties) = tool.input_schema.get("properties") {
+            instructions.push_str(&format!("  - Parameters: {}\n", properties));
+        }
+        if let Some(required) = tool.input_schema.get("required") {
+            instructions.push_str(&format!("  - Required: {}\n", required));
+        }
+    }
+    instructions
+}
+
+/// Byte offset of the first `{"tool"` in `text`, allowing whitespace after
+/// the brace
+pub fn find_tool_call_start(text: &str) -> Option<usize> {
+    text.match_indices('{')
+        .map(|(start, _)| start)
+        .find(|&start| text[start + 1..].trim_start().starts_with("\"tool\""))
+}
+
+/// True if `text` ends partway through a `{"tool"` opener, so the caller
+/// should wait for more tokens before showing it
+pub fn ends_with_partial_tool_call_start(text: &str) -> bool {
+    text.rfind('{').is_some_and(|start| {
+        let rest = text[start + 1..].trim_start();
+        rest.len() < "\"tool\"".len() && "\"tool\"".starts_with(rest)
+    })
+}
+
+/// Parse the text produced under `tool_call_grammar`
+pub fn parse_tool_call(text: &str, id: String) -> Option<ToolCall> {
+    let value: Value = serde_json::from_str(text.trim()).ok()?;
+    Some(ToolCall {
+        id,
+        tool: value.get("tool")?.as_str()?.to_string(),
+        args: value.get("args").cloned().unwrap_or(Value::Null),
+    })
+}
+
+#[derive(Default)]
+struct GrammarBuilder {
+    rules: Vec<(String, String)>,
+    names: HashSet<String>,
+}
+
+impl GrammarBuilder {
+    /// Add a rule named after `hint` and return its unique name
+    fn add(&mut self, hint: &str, body: String) -> String {
+        let base: String = hint
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+            .collect();
+        let mut name = format!("t-{}", base);
+        let mut suffix = 1;
+        while self.names.contains(&name) {
+            suffix += 1;
+            name = format!("t-{}-{}", base, suffix);
+        }
+        self.names.insert(name.clone());
+        self.rules.push((name.clone(), body));
+        name
+    }
+
+    /// Return an expression matching values of `schema`. Anything the
+    /// grammar cannot express precisely falls back to an arbitrary value.
+    fn schema(&mut self, hint: &str, schema: &Value) -> String {
+        if let Some(values) = schema.get("enum").and_then(Value::as_array) {
+            let alternatives: Vec<String> = values.iter().map(literal).collect();
+            return self.add(hint, alternatives.join(" | "));
+        }
+        if let Some(value) = schema.get("const") {
+            return literal(value);
+        }
+        for key in ["anyOf", "oneOf"] {
+            if let Some(variants) = schema.get(key).and_then(Value::as_array) {
+                let alternatives: Vec<String> = variants
+                    .iter()
+                    .map(|variant| self.schema(hint, variant))
+                    .collect();
+                return self.add(hint, alternatives.join(" | "));
+            }
+        }
+
+        match schema.get("type") {
+            Some(Value::String(kind)) => self.typed(hint, kind, schema),
+            Some(Value::Array(kinds)) => {
+                let alternatives: Vec<String> = kinds
+                    .iter()
+                    .filter_map(Value::as_str)
+                    .map(|kind| self.typed(hint, kind, schema))
+                    .collect();
+                self.add(hint, alternatives.join(" | "))
+            }
+            _ => "value".to_string(),
+        }
+    }
+
+    fn typed(&mut self, hint: &str, kind: &str, schema: &Value) -> String {
+        match kind {
+            "string" | "integer" | "number" | "boolean" | "null" => kind.to_string(),
+            "array" => match schema.get("items") {
+                Some(items) => {
+                    let item = self.schema(&format!("{}-item", hint), items);
+                    self.add(
+                        hint,
+                        format!(r#""[" ws ( {0} ( "," ws {0} )* )? "]" ws"#, item),
+                    )
+                }
+                None => "array".to_string(),
+            },
+            "object" => match schema.get("properties").and_then(Value::as_object) {
+                Some(properties) if !properties.is_empty() => {
+                    let required: Vec<&str> = schema
+                        .get("required")
+                        .and_then(Value::as_array)
+                        .map(|names| names.iter().filter_map(Value::as_str).collect())
+                        .unwrap_or_default();
+
+                    // Required members keep the schema's order; optional ones
+                    // are sorted so the grammar does not depend on map order
+                    let mut names: Vec<&String> = properties.keys().collect();
+                    names.sort_by_key(|name| {
+                        let position = required.iter().position(|r| r == name);
+                        (position.unwrap_or(usize::MAX), name.as_str())
+                    });
+
+                    let mut mandatory = Vec::new();
+                    let mut optional = Vec::new();
+                    for name in names {
+                        let value = self.schema(&format!("{}-{}", hint, name), &properties[name]);
+                        let pair = format!(
+                            r#"{} ws ":" ws {}"#,
+                            literal(&Value::String(name.clone())),
+                            value
+                        );
+                        if required.contains(&name.as_str()) {
+                            mandatory.push(pair);
+                        } else {
+                            optional.push(pair);
+                        }
+                    }
+                    let members = object_members(&mandatory, &optional);
+                    self.add(hint, format!(r#""{{" ws {} "}}" ws"#, members))
+                }
+                _ => "object".to_string(),
+            },
+            _ => "value".to_string(),
+        }
+    }
+}
+
+/// Members of an object: all of `mandatory` in order, then any subset of
+/// `optional` in order, separated by commas
+fn object_members(mandatory: &[String], optional: &[String]) -> String {
+    let optional_after = |start: usize| -> Vec<String> {
+        optional[start..]
+            .iter()
+            .map(|pair| format!(r#"( "," ws {} )?"#, pair))
+            .collect()
+    };
+
+    if !mandatory.is_empty() {
+        let mut members = vec![mandatory.join(r#" "," ws "#)];
+        members.extend(optional_after(0));
+        return members.join(" ");
+    }
+
+    // Without a required member, whichever optional member comes first
+    // must not be preceded by a comma
+    let firsts: Vec<String> = (0..optional.len())
+        .map(|i| {
+            let mut members = vec![optional[i].clone()];
+            members.extend(optional_after(i + 1));
+            members.join(" ")
+        })
+        .collect();
+    format!("( {} )?", firsts.join(" | "))
+}
+
+/// A GBNF string literal matching the JSON encoding of `value`
+fn literal(value: &Value) -> String {
+    let json = value.to_string();
+    let mut escaped = String::with_capacity(json.len() + 2);
+    escaped.push('"');
+    for c in json.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped.push('"');
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn tools() -> Vec<Tool> {
+        vec![
+            Tool {
+                name: "read_file".to_string(),
+                description: "Read a file".to_string(),
+                input_schema: json!({
+                    "type": "object",
+                    "properties": {
+                        "file_path": {"type": "string"},
+                        "start": {"type": "integer"},
+                        "end": {"type": "integer"}
+                    },
+                    "required": ["file_path"]
+                }),
+            },
+            Tool {
+                name: "todo_write".to_string(),
+                description: "Update the TODO list".to_string(),
+                input_schema: json!({
+                    "type": "object",
+                    "properties": {
+                        "items": {"type": "array", "items": {"type": "string"}},
+                        "mode": {"enum": ["append", "replace"]}
+                    }
+                }),
+            },
+        ]
+    }
+
+    /// Every rule referenced on a right-hand side must be defined
+    fn assert_rules_defined(grammar: &str) {
+        let mut defined = HashSet::new();
+        let mut bodies = Vec::new();
+        for line in grammar.lines().filter(|line| !line.trim().is_empty()) {
+            let (name, body) = line.split_once(" ::= ").expect(line);
+            defined.insert(name.to_string());
+            bodies.push(body.to_string());
+        }
+
+        for body in bodies {
+            let mut chars = body.chars().peekable();
+            let mut word = String::new();
+            while let Some(c) = chars.next() {
+                match c {
+                    '"' | '[' => {
+                        let close = if c == '"' { '"' } else { ']' };
+                        while let Some(inner) = chars.next() {
+                            if inner == '\\' {
+                                chars.next();
+                            } else if inner == close {
+                                break;
+                            }
+                        }
+                    }
+                    c if c.is_ascii_alphanumeric() || c == '-' => word.push(c),
+                    _ => {
+                        if !word.is_empty() {
+                            assert!(defined.contains(&word), "undefined rule {}", word);
+                            word.clear();
+                        }
+                    }
+                }
+            }
+            if !word.is_empty() {
+                assert!(defined.contains(&word), "undefined rule {}", word);
+            }
+        }
+    }
+
+    #[test]
+    fn test_tool_call_grammar() {
+        let grammar = tool_call_grammar(&tools());
+        assert_rules_defined(&grammar);
+
+        assert!(grammar.starts_with(
+            r#"root ::= "{" ws "\"tool\"" ws ":" ws ( t-read-file-call | t-todo-write-call ) "}""#
+        ));
+        assert!(grammar.contains(
+            r#"t-read-file-args ::= "{" ws "\"file_path\"" ws ":" ws string ( "," ws "\"end\"" ws ":" ws integer )? ( "," ws "\"start\"" ws ":" ws integer )? "}" ws"#
+        ), "{}", grammar);
+        assert!(grammar.contains(r#"t-todo-write-args-mode ::= "\"append\"" | "\"replace\"""#));
+        assert!(grammar.contains(
+            r#"t-todo-write-args ::= "{" ws ( "\"items\"" ws ":" ws t-todo-write-args-items ( "," ws "\"mode\"" ws ":" ws t-todo-write-args-mode )? | "\"mode\"" ws ":" ws t-todo-write-args-mode )? "}" ws"#
+        ), "{}", grammar);
+    }
+
+    #[test]
+    fn test_unusual_names_and_schemas() {
+        let tools = vec![
+            Tool {
+                name: "say \"hi\"".to_string(),
+                description: String::new(),
+                input_schema: json!({"type": "object", "properties": {}}),
+            },
+            Tool {
+                name: "say-hi".to_string(),
+                description: String::new(),
+                input_schema: json!({"type": ["string", "null"]}),
+            },
+        ];
+        let grammar = tool_call_grammar(&tools);
+        assert_rules_defined(&grammar);
+        assert!(grammar.contains(r#"t-say--hi--call ::= "\"say \\\"hi\\\"\"" ws"#));
+        assert!(grammar.contains("t-say-hi-args ::= string | null"));
+    }
+
+    #[test]
+    fn test_tool_call_start_detection() {
+        assert_eq!(
+            find_tool_call_start(r#"Let me look. {"tool": "shell"}"#),
+            Some(13)
+        );
+        assert_eq!(find_tool_call_start("fn main() { \"tool\" }"), Some(10));
+        assert_eq!(find_tool_call_start(r#"{"tools": 1} { "tool""#), Some(13));
+        assert_eq!(find_tool_call_start("no call here {}"), None);
+
+        assert!(ends_with_partial_tool_call_start("Sure {"));
+        assert!(ends_with_partial_tool_call_start("Sure {\n  \"to"));
+        assert!(!ends_with_partial_tool_call_start("Sure {\"tool\""));
+        assert!(!ends_with_partial_tool_call_start("fn main() { let"));
+        assert!(!ends_with_partial_tool_call_start("Sure"));
+    }
+
+    #[test]
+    fn test_parse_tool_call() {
+        let call = parse_tool_call(
+            r#"{"tool": "read_file", "args": {"file_path": "src/lib.rs"}}"#,
+            "call_0".to_string(),
+        )
+        .unwrap();
+        assert_eq!(call.tool, "read_file");
+        assert_eq!(call.args, json!({"file_path": "src/lib.rs"}));
+        assert!(parse_tool_call(r#"{"tool": "read_file", "ar"#, String::new()).is_none());
+    }
+
+    #[test]
+    fn test_tool_instructions() {
+        let instructions = tool_instructions(&tools());
+        assert!(instructions.contains("- **read_file**: Read a file"));
+        assert!(instructions.contains(r#"  - Required: ["file_path"]"#));
+    }
+}
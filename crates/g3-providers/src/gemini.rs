@@ -0,0 +1,533 @@
+//! Google Gemini provider implementation for the g3-providers crate.
+//!
+//! This module provides an implementation of the `LLMProvider` trait for the Gemini API
+//! (`generativelanguage.googleapis.com`).
+//!
+//! # Features
+//!
+//! - `generateContent` for completions and `streamGenerateContent?alt=sse` for streaming
+//! - Native function calling, mapped to `ToolCall`
+//! - Token usage from `usageMetadata`
+//! - Long-context models, e.g. as the planner provider
+//!
+//! # Usage
+//!
+//! ```rust,no_run
+//! use g3_providers::{GeminiProvider, LLMProvider, CompletionRequest, Message, MessageRole};
+//!
+//! #[tokio::main]
+//! async fn main() -> anyhow::Result<()> {
+//!     let provider = GeminiProvider::new(
+//!         "your-api-key".to_string(),
+//!         Some("gemini-2.5-pro".to_string()),
+//!         None, // max_tokens
+//!         None, // temperature
+//!     )?;
+//!
+//!     let request = CompletionRequest {
+//!         messages: vec![Message::new(MessageRole::User, "Hello!".to_string())],
+//!         max_tokens: Some(1000),
+//!         temperature: Some(0.7),
+//!         stream: false,
+//!         tools: None,
+//!         disable_thinking: false,
+//!     };
+//!
+//!     let response = provider.complete(request).await?;
+//!     println!("Response: {}", response.content);
+//!
+//!     Ok(())
+//! }
+//! ```
+
+use anyhow::Result;
+use async_trait::async_trait;
+use bytes::Bytes;
+use futures_util::stream::StreamExt;
+use reqwest::Client;
+use serde::Deserialize;
+use serde_json::json;
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+use tracing::{debug, error, warn};
+
+use crate::{
+    CompletionChunk, CompletionRequest, CompletionResponse, CompletionStream, LLMProvider, Message,
+    MessageRole, Tool, ToolCall, Usage,
+};
+
+const GEMINI_BASE_URL: &str = "https://generativelanguage.googleapis.com/v1beta";
+
+/// JSON Schema keywords the Gemini API rejects in function parameters
+const UNSUPPORTED_SCHEMA_KEYS: &[&str] = &["$schema", "additionalProperties"];
+
+#[derive(Clone)]
+pub struct GeminiProvider {
+    client: Client,
+    name: String,
+    api_key: String,
+    model: String,
+    base_url: String,
+    max_tokens: Option<u32>,
+    temperature: Option<f32>,
+}
+
+impl GeminiProvider {
+    pub fn new(
+        api_key: String,
+        model: Option<String>,
+        max_tokens: Option<u32>,
+        temperature: Option<f32>,
+    ) -> Result<Self> {
+        Self::new_with_name(
+            "gemini".to_string(),
+            api_key,
+            model,
+            max_tokens,
+            temperature,
+        )
+    }
+
+    pub fn new_with_name(
+        name: String,
+        api_key: String,
+        model: Option<String>,
+        max_tokens: Option<u32>,
+        temperature: Option<f32>,
+    ) -> Result<Self> {
+        let model = model.unwrap_or_else(|| "gemini-2.5-pro".to_string());
+
+        debug!("Initialized Gemini provider with model: {}", model);
+
+        Ok(Self {
+            client: Client::new(),
+            name,
+            api_key,
+            model,
+            base_url: GEMINI_BASE_URL.to_string(),
+            max_tokens,
+            temperature,
+        })
+    }
+
+    /// Use a different API endpoint, e.g. a proxy or a local mock server
+    pub fn with_base_url(mut self, base_url: String) -> Self {
+        self.base_url = base_url.trim_end_matches('/').to_string();
+        self
+    }
+
+    fn endpoint(&self, method: &str) -> String {
+        format!("{}/models/{}:{}", self.base_url, self.model, method)
+    }
+
+    fn create_request_body(
+        &self,
+        messages: &[Message],
+        tools: Option<&[Tool]>,
+        max_tokens: Option<u32>,
+        temperature: Option<f32>,
+    ) -> serde_json::Value {
+        let (system_instruction, contents) = convert_messages(messages);
+
+        let mut generation_config = json!({});
+        if let Some(max_tokens) = max_tokens.or(self.max_tokens) {
+            generation_config["maxOutputTokens"] = json!(max_tokens);
+        }
+        if let Some(temperature) = temperature.or(self.temperature) {
+            generation_config["temperature"] = json!(temperature);
+        }
+
+        let mut body = json!({
+            "contents": contents,
+            "generationConfig": generation_config,
+        });
+
+        if let Some(system_instruction) = system_instruction {
+            body["systemInstruction"] = system_instruction;
+        }
+
+        if let Some(tools) = tools {
+            if !tools.is_empty() {
+                body["tools"] = json!([{ "functionDeclarations": convert_tools(tools) }]);
+            }
+        }
+
+        body
+    }
+
+    async fn send(&self, method: &str, body: &serde_json::Value) -> Result<reqwest::Response> {
+        let response = self
+            .client
+            .post(self.endpoint(method))
+            .header("x-goog-api-key", &self.api_key)
+            .json(body)
+            .send()
+            .await?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let error_text = response
+                .text()
+                .await
+                .unwrap_or_else(|_| "Unknown error".to_string());
+            return Err(anyhow::anyhow!(
+                "Gemini API error {}: {}",
+                status,
+                error_text
+            ));
+        }
+
+        Ok(response)
+    }
+
+    async fn parse_streaming_response(
+        &self,
+        mut stream: impl futures_util::Stream<Item = reqwest::Result<Bytes>> + Unpin,
+        tx: mpsc::Sender<Result<CompletionChunk>>,
+    ) -> Option<Usage> {
+        let mut buffer = String::new();
+        let mut accumulated_usage: Option<Usage> = None;
+        let mut tool_calls: Vec<ToolCall> = Vec::new();
+
+        while let Some(chunk_result) = stream.next().await {
+            let chunk = match chunk_result {
+                Ok(chunk) => chunk,
+                Err(e) => {
+                    error!("Stream error: {}", e);
+                    let _ = tx.send(Err(anyhow::anyhow!("Stream error: {}", e))).await;
+                    return accumulated_usage;
+                }
+            };
+
+            match std::str::from_utf8(&chunk) {
+                Ok(s) => buffer.push_str(s),
+                Err(e) => {
+                    error!("Failed to parse chunk as UTF-8: {}", e);
+                    continue;
+                }
+            }
+
+            // Process complete lines
+            while let Some(line_end) = buffer.find('\n') {
+                let line = buffer[..line_end].trim().to_string();
+                buffer.drain(..line_end + 1);
+
+                // Parse Server-Sent Events format
+                let Some(data) = line.strip_prefix("data:") else {
+                    continue;
+                };
+
+                let response: GeminiResponse = match serde_json::from_str(data.trim()) {
+                    Ok(response) => response,
+                    Err(e) => {
+                        debug!("Failed to parse stream chunk: {} - Data: {}", e, data);
+                        continue;
+                    }
+                };
+
+                // Gemini reports cumulative usage, so the last value wins
+                if let Some(usage) = response.usage() {
+                    accumulated_usage = Some(usage);
+                }
+
+                let (text, calls) = response.content(tool_calls.len());
+                tool_calls.extend(calls);
+
+                if !text.is_empty() {
+                    let chunk = CompletionChunk {
+                        content: text,
+                        finished: false,
+                        tool_calls: None,
+                        usage: None,
+                    };
+                    if tx.send(Ok(chunk)).await.is_err() {
+                        debug!("Receiver dropped, stopping stream");
+                        return accumulated_usage;
+                    }
+                }
+            }
+        }
+
+        // Gemini has no end-of-stream marker; the connection just closes
+        let final_chunk = CompletionChunk {
+            content: String::new(),
+            finished: true,
+            tool_calls: if tool_calls.is_empty() {
+                None
+            } else {
+                Some(tool_calls)
+            },
+            usage: accumulated_usage.clone(),
+        };
+        let _ = tx.send(Ok(final_chunk)).await;
+
+        accumulated_usage
+    }
+}
+
+#[async_trait]
+impl LLMProvider for GeminiProvider {
+    async fn complete(&self, request: CompletionRequest) -> Result<CompletionResponse> {
+        debug!(
+            "Processing Gemini completion request with {} messages",
+            request.messages.len()
+        );
+
+        let body = self.create_request_body(
+            &request.messages,
+            request.tools.as_deref(),
+            request.max_tokens,
+            request.temperature,
+        );
+
+        debug!("Sending request to Gemini API: model={}", self.model);
+
+        let gemini_response: GeminiResponse =
+            self.send("generateContent", &body).await?.json().await?;
+
+        let usage = gemini_response.usage().unwrap_or_default();
+        let (content, tool_calls) = gemini_response.content(0);
+
+        debug!(
+            "Gemini completion successful: {} tokens generated",
+            usage.completion_tokens
+        );
+
+        Ok(CompletionResponse {
+            content,
+            usage,
+            model: self.model.clone(),
+            tool_calls: Some(tool_calls).filter(|calls| !calls.is_empty()),
+        })
+    }
+
+    async fn stream(&self, request: CompletionRequest) -> Result<CompletionStream> {
+        debug!(
+            "Processing Gemini streaming request with {} messages",
+            request.messages.len()
+        );
+
+        let body = self.create_request_body(
+            &request.messages,
+            request.tools.as_deref(),
+            request.max_tokens,
+            request.temperature,
+        );
+
+        debug!(
+            "Sending streaming request to Gemini API: model={}",
+            self.model
+        );
+
+        let stream = self
+            .send("streamGenerateContent?alt=sse", &body)
+            .await?
+            .bytes_stream();
+        let (tx, rx) = mpsc::channel(100);
+
+        // Spawn task to process the stream
+        let provider = self.clone();
+        tokio::spawn(async move {
+            let usage = provider.parse_streaming_response(stream, tx).await;
+            if let Some(usage) = usage {
+                debug!(
+                    "Stream completed with usage - prompt: {}, completion: {}, total: {}",
+                    usage.prompt_tokens, usage.completion_tokens, usage.total_tokens
+                );
+            }
+        });
+
+        Ok(ReceiverStream::new(rx))
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn model(&self) -> &str {
+        &self.model
+    }
+
+    fn has_native_tool_calling(&self) -> bool {
+        true
+    }
+
+    fn max_tokens(&self) -> u32 {
+        self.max_tokens.unwrap_or(8192)
+    }
+
+    fn temperature(&self) -> f32 {
+        self.temperature.unwrap_or(0.1)
+    }
+}
+
+/// Split messages into Gemini's `systemInstruction` and `contents`. Consecutive
+/// messages with the same role are merged, as Gemini expects turns to alternate.
+fn convert_messages(messages: &[Message]) -> (Option<serde_json::Value>, Vec<serde_json::Value>) {
+    let system_parts: Vec<serde_json::Value> = messages
+        .iter()
+        .filter(|msg| matches!(msg.role, MessageRole::System))
+        .map(|msg| json!({ "text": msg.content }))
+        .collect();
+
+    let mut contents: Vec<serde_json::Value> = Vec::new();
+    for msg in messages {
+        let role = match msg.role {
+            MessageRole::System => continue,
+            MessageRole::User => "user",
+            MessageRole::Assistant => "model",
+        };
+        let part = json!({ "text": msg.content });
+        match contents.last_mut() {
+            Some(last) if last["role"] == role => {
+                last["parts"].as_array_mut().unwrap().push(part);
+            }
+            _ => contents.push(json!({ "role": role, "parts": [part] })),
+        }
+    }
+
+    let system_instruction = if system_parts.is_empty() {
+        None
+    } else {
+        Some(json!({ "parts": system_parts }))
+    };
+
+    (system_instruction, contents)
+}
+
+fn convert_tools(tools: &[Tool]) -> Vec<serde_json::Value> {
+    tools
+        .iter()
+        .map(|tool| {
+            let mut parameters = tool.input_schema.clone();
+            strip_unsupported_schema_keys(&mut parameters);
+            json!({
+                "name": tool.name,
+                "description": tool.description,
+                "parameters": parameters,
+            })
+        })
+        .collect()
+}
+
+fn strip_unsupported_schema_keys(schema: &mut serde_json::Value) {
+    match schema {
+        serde_json::Value::Object(map) => {
+            for key in UNSUPPORTED_SCHEMA_KEYS {
+                map.remove(*key);
+            }
+            for value in map.values_mut() {
+                strip_unsupported_schema_keys(value);
+            }
+        }
+        serde_json::Value::Array(items) => {
+            for item in items {
+                strip_unsupported_schema_keys(item);
+            }
+        }
+        _ => {}
+    }
+}
+
+// Gemini API response structures
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GeminiResponse {
+    #[serde(default)]
+    candidates: Vec<GeminiCandidate>,
+    usage_metadata: Option<GeminiUsage>,
+}
+
+impl GeminiResponse {
+    /// The text of the first candidate, and its function calls numbered from `first_index`
+    fn content(&self, first_index: usize) -> (String, Vec<ToolCall>) {
+        let mut text = String::new();
+        let mut tool_calls = Vec::new();
+
+        let Some(candidate) = self.candidates.first() else {
+            return (text, tool_calls);
+        };
+
+        if let Some(reason) = &candidate.finish_reason {
+            if reason != "STOP" {
+                warn!("Gemini finished with reason: {}", reason);
+            }
+        }
+
+        for part in candidate.content.iter().flat_map(|content| &content.parts) {
+            // Thought summaries are not part of the answer
+            if part.thought {
+                continue;
+            }
+            if let Some(part_text) = &part.text {
+                text.push_str(part_text);
+            }
+            if let Some(call) = &part.function_call {
+                tool_calls.push(ToolCall {
+                    id: call
+                        .id
+                        .clone()
+                        .unwrap_or_else(|| format!("call_{}", first_index + tool_calls.len())),
+                    tool: call.name.clone(),
+                    args: call.args.clone(),
+                });
+            }
+        }
+
+        (text, tool_calls)
+    }
+
+    fn usage(&self) -> Option<Usage> {
+        let usage = self.usage_metadata.as_ref()?;
+        Some(Usage {
+            prompt_tokens: usage.prompt_token_count,
+            completion_tokens: usage.candidates_token_count,
+            total_tokens: usage.total_token_count,
+            cache_read_tokens: usage.cached_content_token_count,
+            cache_creation_tokens: 0,
+        })
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GeminiCandidate {
+    content: Option<GeminiContent>,
+    finish_reason: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GeminiContent {
+    #[serde(default)]
+    parts: Vec<GeminiPart>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GeminiPart {
+    text: Option<String>,
+    #[serde(default)]
+    thought: bool,
+    function_call: Option<GeminiFunctionCall>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GeminiFunctionCall {
+    id: Option<String>,
+    name: String,
+    #[serde(default)]
+    args: serde_json::Value,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GeminiUsage {
+    #[serde(default)]
+    prompt_token_count: u32,
+    #[serde(default)]
+    candidates_token_count: u32,
+    #[serde(default)]
+    total_token_count: u32,
+    #[serde(default)]
+    cached_content_token_count: u32,
+}
@@ -33,6 +33,16 @@ pub trait LLMProvider: Send + Sync {
 
     /// Get the configured temperature for this provider
     fn temperature(&self) -> f32;
+
+    /// Get the context window reported by the model server, if the provider knows it
+    fn context_length(&self) -> Option<u32> {
+        None
+    }
+
+    /// Get the model's own tokenizer, if the provider has one locally
+    fn tokenizer(&self) -> Option<std::sync::Arc<dyn Tokenizer>> {
+        None
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -106,13 +116,23 @@ pub struct CompletionResponse {
     pub content: String,
     pub usage: Usage,
     pub model: String,
+    /// Native tool calls, for providers that return them apart from `content`
+    #[serde(default)]
+    pub tool_calls: Option<Vec<ToolCall>>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct Usage {
+    /// All input tokens, including those read from or written to the prompt cache
     pub prompt_tokens: u32,
     pub completion_tokens: u32,
     pub total_tokens: u32,
+    /// Input tokens served from the prompt cache
+    #[serde(default)]
+    pub cache_read_tokens: u32,
+    /// Input tokens written to the prompt cache
+    #[serde(default)]
+    pub cache_creation_tokens: u32,
 }
 
 pub type CompletionStream = tokio_stream::wrappers::ReceiverStream<Result<CompletionChunk>>;
@@ -140,22 +160,33 @@ pub struct Tool {
 }
 
 pub mod anthropic;
+pub mod bedrock;
 pub mod databricks;
 pub mod embedded;
+pub mod gbnf;
+pub mod gemini;
 pub mod oauth;
+pub mod ollama;
 pub mod openai;
 pub mod openrouter;
+pub mod tokenizer;
 
 pub use anthropic::AnthropicProvider;
+pub use bedrock::{AwsCredentials, BedrockProvider};
 pub use databricks::DatabricksProvider;
-pub use embedded::EmbeddedProvider;
+pub use embedded::{EmbeddedProvider, EmbeddingModel, GgufTokenizer};
+pub use gemini::GeminiProvider;
+pub use ollama::{OllamaModelInfo, OllamaProvider, OllamaServer};
 pub use openai::OpenAIProvider;
 pub use openrouter::{OpenRouterProvider, ProviderPreferences};
+pub use tokenizer::{
+    tokenizer_for, BpeTokenizer, EstimatingTokenizer, Tokenizer, MESSAGE_OVERHEAD_TOKENS,
+};
 
 impl Message {
     /// Generate a unique message ID in format HHMMSS-XXX
     /// where XXX are 3 random alphanumeric characters (upper and lowercase)
-    fn generate_id() -> String {
+    pub fn generate_id() -> String {
         let now = chrono::Local::now();
         let timestamp = now.format("%H%M%S").to_string();
 
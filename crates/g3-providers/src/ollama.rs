@@ -0,0 +1,705 @@
+//! Ollama / llama-server provider implementation for the g3-providers crate.
+//!
+//! This module provides an implementation of the `LLMProvider` trait for models served by a
+//! local [Ollama](https://ollama.com) daemon or llama.cpp's `llama-server`. Unlike the
+//! `embedded` provider, llama.cpp is not compiled into g3, and several g3 instances can share
+//! one model server.
+//!
+//! # Features
+//!
+//! - Ollama's native `/api/chat` endpoint (NDJSON streaming), which lets g3 set `num_ctx`
+//! - llama-server's OpenAI-compatible `/v1/chat/completions` endpoint
+//! - Native tool calling when the model supports it
+//! - Model discovery (`/api/tags` or `/v1/models`)
+//! - Context-length and tool-support autodetection (`/api/show` or `/props`)
+//!
+//! # Usage
+//!
+//! ```rust,no_run
+//! use g3_providers::{OllamaProvider, LLMProvider, CompletionRequest, Message, MessageRole};
+//!
+//! #[tokio::main]
+//! async fn main() -> anyhow::Result<()> {
+//!     let mut provider = OllamaProvider::new(
+//!         "qwen2.5-coder:14b".to_string(),
+//!         None, // base_url, defaults to http://localhost:11434
+//!         None, // max_tokens
+//!         None, // temperature
+//!     )?;
+//!
+//!     // See what the server has, then ask it about the configured model
+//!     println!("Models: {:?}", provider.list_models().await?);
+//!     provider.autodetect().await?;
+//!
+//!     let request = CompletionRequest {
+//!         messages: vec![Message::new(MessageRole::User, "Hello!".to_string())],
+//!         max_tokens: Some(1000),
+//!         temperature: Some(0.2),
+//!         stream: false,
+//!         tools: None,
+//!         disable_thinking: false,
+//!     };
+//!
+//!     let response = provider.complete(request).await?;
+//!     println!("Response: {}", response.content);
+//!
+//!     Ok(())
+//! }
+//! ```
+
+use anyhow::Result;
+use async_trait::async_trait;
+use bytes::Bytes;
+use futures_util::stream::StreamExt;
+use reqwest::Client;
+use serde::Deserialize;
+use serde_json::json;
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+use tracing::{debug, error, warn};
+
+use crate::{
+    CompletionChunk, CompletionRequest, CompletionResponse, CompletionStream, LLMProvider, Message,
+    MessageRole, OpenAIProvider, Tool, ToolCall, Usage,
+};
+
+const DEFAULT_BASE_URL: &str = "http://localhost:11434";
+
+/// Context length used when neither the config nor the server provides one
+const DEFAULT_CONTEXT_LENGTH: u32 = 8192;
+
+/// The kind of model server behind the base URL
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OllamaServer {
+    /// Ollama's native API
+    #[default]
+    Ollama,
+    /// llama.cpp's `llama-server` (OpenAI-compatible API)
+    LlamaServer,
+}
+
+/// What the server reports about the configured model
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct OllamaModelInfo {
+    pub context_length: Option<u32>,
+    pub supports_tools: Option<bool>,
+}
+
+#[derive(Clone)]
+pub struct OllamaProvider {
+    client: Client,
+    name: String,
+    model: String,
+    base_url: String,
+    server: OllamaServer,
+    max_tokens: Option<u32>,
+    temperature: Option<f32>,
+    context_length: Option<u32>,
+    native_tool_calling: Option<bool>,
+}
+
+impl OllamaProvider {
+    pub fn new(
+        model: String,
+        base_url: Option<String>,
+        max_tokens: Option<u32>,
+        temperature: Option<f32>,
+    ) -> Result<Self> {
+        Self::new_with_name(
+            "ollama".to_string(),
+            model,
+            base_url,
+            max_tokens,
+            temperature,
+        )
+    }
+
+    pub fn new_with_name(
+        name: String,
+        model: String,
+        base_url: Option<String>,
+        max_tokens: Option<u32>,
+        temperature: Option<f32>,
+    ) -> Result<Self> {
+        let base_url = base_url
+            .unwrap_or_else(|| DEFAULT_BASE_URL.to_string())
+            .trim_end_matches('/')
+            .to_string();
+
+        Ok(Self {
+            client: Client::new(),
+            name,
+            model,
+            base_url,
+            server: OllamaServer::default(),
+            max_tokens,
+            temperature,
+            context_length: None,
+            native_tool_calling: None,
+        })
+    }
+
+    pub fn with_server(mut self, server: OllamaServer) -> Self {
+        self.server = server;
+        self
+    }
+
+    /// Fix the context length instead of asking the server
+    pub fn with_context_length(mut self, context_length: u32) -> Self {
+        self.context_length = Some(context_length);
+        self
+    }
+
+    /// Force native tool calling on or off instead of asking the server
+    pub fn with_native_tool_calling(mut self, enabled: bool) -> Self {
+        self.native_tool_calling = Some(enabled);
+        self
+    }
+
+    /// List the models the server can serve
+    pub async fn list_models(&self) -> Result<Vec<String>> {
+        match self.server {
+            OllamaServer::Ollama => {
+                let tags: OllamaTags = self.get_json("/api/tags").await?;
+                Ok(tags.models.into_iter().map(|m| m.name).collect())
+            }
+            OllamaServer::LlamaServer => {
+                let models: OpenAIModelList = self.get_json("/v1/models").await?;
+                Ok(models.data.into_iter().map(|m| m.id).collect())
+            }
+        }
+    }
+
+    /// Ask the server for the configured model's context length and tool support
+    pub async fn show_model(&self) -> Result<OllamaModelInfo> {
+        match self.server {
+            OllamaServer::Ollama => {
+                let response = self
+                    .client
+                    .post(format!("{}/api/show", self.base_url))
+                    .json(&json!({ "model": self.model }))
+                    .send()
+                    .await?;
+                let show: OllamaShow = self.check_response(response).await?.json().await?;
+                Ok(show.model_info())
+            }
+            OllamaServer::LlamaServer => {
+                let props: LlamaServerProps = self.get_json("/props").await?;
+                Ok(props.model_info())
+            }
+        }
+    }
+
+    /// Fill in the context length and tool support the config left unset
+    pub async fn autodetect(&mut self) -> Result<()> {
+        if self.context_length.is_some() && self.native_tool_calling.is_some() {
+            return Ok(());
+        }
+
+        let info = self.show_model().await?;
+        debug!("Model info for {} from {}: {:?}", self.model, self.base_url, info);
+
+        if self.context_length.is_none() {
+            self.context_length = info.context_length;
+        }
+        if self.native_tool_calling.is_none() {
+            self.native_tool_calling = info.supports_tools;
+        }
+        Ok(())
+    }
+
+    async fn get_json<T: serde::de::DeserializeOwned>(&self, path: &str) -> Result<T> {
+        let response = self
+            .client
+            .get(format!("{}{}", self.base_url, path))
+            .send()
+            .await?;
+        Ok(self.check_response(response).await?.json().await?)
+    }
+
+    async fn check_response(&self, response: reqwest::Response) -> Result<reqwest::Response> {
+        let status = response.status();
+        if status.is_success() {
+            return Ok(response);
+        }
+        let error_text = response
+            .text()
+            .await
+            .unwrap_or_else(|_| "Unknown error".to_string());
+        Err(anyhow::anyhow!(
+            "Ollama API error {} from {}: {}",
+            status,
+            self.base_url,
+            error_text
+        ))
+    }
+
+    /// llama-server speaks the OpenAI chat API, so reuse the OpenAI provider for it
+    fn llama_server_client(&self) -> Result<OpenAIProvider> {
+        OpenAIProvider::new_with_name(
+            self.name.clone(),
+            String::new(),
+            Some(self.model.clone()),
+            Some(format!("{}/v1", self.base_url)),
+            self.max_tokens,
+            self.temperature,
+        )
+    }
+
+    fn create_request_body(
+        &self,
+        messages: &[Message],
+        tools: Option<&[Tool]>,
+        stream: bool,
+        max_tokens: Option<u32>,
+        temperature: Option<f32>,
+    ) -> serde_json::Value {
+        let mut options = json!({
+            "num_ctx": self.context_length(),
+        });
+        if let Some(max_tokens) = max_tokens.or(self.max_tokens) {
+            options["num_predict"] = json!(max_tokens);
+        }
+        if let Some(temperature) = temperature.or(self.temperature) {
+            options["temperature"] = json!(temperature);
+        }
+
+        let mut body = json!({
+            "model": self.model,
+            "messages": convert_messages(messages),
+            "stream": stream,
+            "options": options,
+        });
+
+        if let Some(tools) = tools {
+            if !tools.is_empty() {
+                body["tools"] = json!(convert_tools(tools));
+            }
+        }
+
+        body
+    }
+
+    async fn parse_streaming_response(
+        &self,
+        mut stream: impl futures_util::Stream<Item = reqwest::Result<Bytes>> + Unpin,
+        tx: mpsc::Sender<Result<CompletionChunk>>,
+    ) -> Option<Usage> {
+        let mut buffer = String::new();
+        let mut tool_calls: Vec<ToolCall> = Vec::new();
+
+        while let Some(chunk_result) = stream.next().await {
+            let chunk = match chunk_result {
+                Ok(chunk) => chunk,
+                Err(e) => {
+                    error!("Stream error: {}", e);
+                    let _ = tx.send(Err(anyhow::anyhow!("Stream error: {}", e))).await;
+                    return None;
+                }
+            };
+
+            match std::str::from_utf8(&chunk) {
+                Ok(s) => buffer.push_str(s),
+                Err(e) => {
+                    error!("Failed to parse chunk as UTF-8: {}", e);
+                    continue;
+                }
+            }
+
+            // Ollama streams one JSON object per line
+            while let Some(line_end) = buffer.find('\n') {
+                let line = buffer[..line_end].trim().to_string();
+                buffer.drain(..line_end + 1);
+
+                if line.is_empty() {
+                    continue;
+                }
+
+                let chunk_data: OllamaChatResponse = match serde_json::from_str(&line) {
+                    Ok(chunk_data) => chunk_data,
+                    Err(e) => {
+                        debug!("Failed to parse stream chunk: {} - Data: {}", e, line);
+                        continue;
+                    }
+                };
+
+                if let Some(error) = chunk_data.error {
+                    let _ = tx.send(Err(anyhow::anyhow!("Ollama error: {}", error))).await;
+                    return None;
+                }
+
+                if let Some(message) = &chunk_data.message {
+                    if !message.content.is_empty() {
+                        let chunk = CompletionChunk {
+                            content: message.content.clone(),
+                            finished: false,
+                            tool_calls: None,
+                            usage: None,
+                        };
+                        if tx.send(Ok(chunk)).await.is_err() {
+                            debug!("Receiver dropped, stopping stream");
+                            return None;
+                        }
+                    }
+
+                    // Ollama sends each tool call whole rather than as deltas
+                    for tool_call in &message.tool_calls {
+                        tool_calls.push(tool_call.to_tool_call(tool_calls.len()));
+                    }
+                }
+
+                if chunk_data.done {
+                    let usage = chunk_data.usage();
+                    let final_chunk = CompletionChunk {
+                        content: String::new(),
+                        finished: true,
+                        tool_calls: if tool_calls.is_empty() {
+                            None
+                        } else {
+                            Some(std::mem::take(&mut tool_calls))
+                        },
+                        usage: Some(usage.clone()),
+                    };
+                    let _ = tx.send(Ok(final_chunk)).await;
+                    return Some(usage);
+                }
+            }
+        }
+
+        // The server closed the connection without a final `done` object
+        warn!("Ollama stream ended without a done message");
+        let final_chunk = CompletionChunk {
+            content: String::new(),
+            finished: true,
+            tool_calls: if tool_calls.is_empty() {
+                None
+            } else {
+                Some(tool_calls)
+            },
+            usage: None,
+        };
+        let _ = tx.send(Ok(final_chunk)).await;
+
+        None
+    }
+}
+
+#[async_trait]
+impl LLMProvider for OllamaProvider {
+    async fn complete(&self, request: CompletionRequest) -> Result<CompletionResponse> {
+        if self.server == OllamaServer::LlamaServer {
+            return self.llama_server_client()?.complete(request).await;
+        }
+
+        debug!(
+            "Processing Ollama completion request with {} messages",
+            request.messages.len()
+        );
+
+        let body = self.create_request_body(
+            &request.messages,
+            request.tools.as_deref(),
+            false,
+            request.max_tokens,
+            request.temperature,
+        );
+
+        debug!("Sending request to Ollama API: model={}", self.model);
+
+        let response = self
+            .client
+            .post(format!("{}/api/chat", self.base_url))
+            .json(&body)
+            .send()
+            .await?;
+
+        let ollama_response: OllamaChatResponse =
+            self.check_response(response).await?.json().await?;
+
+        if let Some(error) = ollama_response.error {
+            return Err(anyhow::anyhow!("Ollama error: {}", error));
+        }
+
+        let usage = ollama_response.usage();
+        let (content, tool_calls) = match ollama_response.message {
+            Some(message) => {
+                let tool_calls: Vec<ToolCall> = message
+                    .tool_calls
+                    .iter()
+                    .enumerate()
+                    .map(|(index, tool_call)| tool_call.to_tool_call(index))
+                    .collect();
+                (message.content, Some(tool_calls).filter(|t| !t.is_empty()))
+            }
+            None => (String::new(), None),
+        };
+
+        debug!(
+            "Ollama completion successful: {} tokens generated",
+            usage.completion_tokens
+        );
+
+        Ok(CompletionResponse {
+            content,
+            usage,
+            model: self.model.clone(),
+            tool_calls,
+        })
+    }
+
+    async fn stream(&self, request: CompletionRequest) -> Result<CompletionStream> {
+        if self.server == OllamaServer::LlamaServer {
+            return self.llama_server_client()?.stream(request).await;
+        }
+
+        debug!(
+            "Processing Ollama streaming request with {} messages",
+            request.messages.len()
+        );
+
+        let body = self.create_request_body(
+            &request.messages,
+            request.tools.as_deref(),
+            true,
+            request.max_tokens,
+            request.temperature,
+        );
+
+        debug!(
+            "Sending streaming request to Ollama API: model={}",
+            self.model
+        );
+
+        let response = self
+            .client
+            .post(format!("{}/api/chat", self.base_url))
+            .json(&body)
+            .send()
+            .await?;
+
+        let stream = self.check_response(response).await?.bytes_stream();
+        let (tx, rx) = mpsc::channel(100);
+
+        // Spawn task to process the stream
+        let provider = self.clone();
+        tokio::spawn(async move {
+            let usage = provider.parse_streaming_response(stream, tx).await;
+            if let Some(usage) = usage {
+                debug!(
+                    "Stream completed with usage - prompt: {}, completion: {}, total: {}",
+                    usage.prompt_tokens, usage.completion_tokens, usage.total_tokens
+                );
+            }
+        });
+
+        Ok(ReceiverStream::new(rx))
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn model(&self) -> &str {
+        &self.model
+    }
+
+    fn has_native_tool_calling(&self) -> bool {
+        // Only when the config or the server says the model handles tools
+        self.native_tool_calling.unwrap_or(false)
+    }
+
+    fn max_tokens(&self) -> u32 {
+        self.max_tokens.unwrap_or(4096)
+    }
+
+    fn temperature(&self) -> f32 {
+        self.temperature.unwrap_or(0.1)
+    }
+
+    fn context_length(&self) -> Option<u32> {
+        Some(self.context_length.unwrap_or(DEFAULT_CONTEXT_LENGTH))
+    }
+}
+
+fn convert_messages(messages: &[Message]) -> Vec<serde_json::Value> {
+    messages
+        .iter()
+        .map(|msg| {
+            json!({
+                "role": match msg.role {
+                    MessageRole::System => "system",
+                    MessageRole::User => "user",
+                    MessageRole::Assistant => "assistant",
+                },
+                "content": msg.content,
+            })
+        })
+        .collect()
+}
+
+fn convert_tools(tools: &[Tool]) -> Vec<serde_json::Value> {
+    tools
+        .iter()
+        .map(|tool| {
+            json!({
+                "type": "function",
+                "function": {
+                    "name": tool.name,
+                    "description": tool.description,
+                    "parameters": tool.input_schema,
+                }
+            })
+        })
+        .collect()
+}
+
+// Ollama API response structures
+#[derive(Debug, Deserialize)]
+struct OllamaChatResponse {
+    message: Option<OllamaMessage>,
+    #[serde(default)]
+    done: bool,
+    prompt_eval_count: Option<u32>,
+    eval_count: Option<u32>,
+    error: Option<String>,
+}
+
+impl OllamaChatResponse {
+    fn usage(&self) -> Usage {
+        let prompt_tokens = self.prompt_eval_count.unwrap_or(0);
+        let completion_tokens = self.eval_count.unwrap_or(0);
+        Usage {
+            prompt_tokens,
+            completion_tokens,
+            total_tokens: prompt_tokens + completion_tokens,
+            ..Default::default()
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct OllamaMessage {
+    #[serde(default)]
+    content: String,
+    #[serde(default)]
+    tool_calls: Vec<OllamaToolCall>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OllamaToolCall {
+    id: Option<String>,
+    function: OllamaFunction,
+}
+
+impl OllamaToolCall {
+    fn to_tool_call(&self, index: usize) -> ToolCall {
+        ToolCall {
+            id: self
+                .id
+                .clone()
+                .unwrap_or_else(|| format!("call_{}", index)),
+            tool: self.function.name.clone(),
+            args: self.function.arguments.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct OllamaFunction {
+    name: String,
+    /// Ollama sends arguments as a JSON object, not a string
+    #[serde(default)]
+    arguments: serde_json::Value,
+}
+
+#[derive(Debug, Deserialize)]
+struct OllamaTags {
+    #[serde(default)]
+    models: Vec<OllamaTagModel>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OllamaTagModel {
+    name: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct OllamaShow {
+    /// Modelfile parameters, one "key value" per line
+    #[serde(default)]
+    parameters: String,
+    #[serde(default)]
+    model_info: serde_json::Map<String, serde_json::Value>,
+    capabilities: Option<Vec<String>>,
+}
+
+impl OllamaShow {
+    fn model_info(&self) -> OllamaModelInfo {
+        // A num_ctx set in the Modelfile wins over the trained context length
+        let num_ctx = self.parameters.lines().find_map(|line| {
+            let mut parts = line.split_whitespace();
+            match (parts.next(), parts.next()) {
+                (Some("num_ctx"), Some(value)) => value.parse().ok(),
+                _ => None,
+            }
+        });
+        let trained = self
+            .model_info
+            .iter()
+            .find(|(key, _)| key.ends_with(".context_length"))
+            .and_then(|(_, value)| value.as_u64())
+            .map(|value| value as u32);
+
+        OllamaModelInfo {
+            context_length: num_ctx.or(trained),
+            supports_tools: self
+                .capabilities
+                .as_ref()
+                .map(|caps| caps.iter().any(|cap| cap == "tools")),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct OpenAIModelList {
+    #[serde(default)]
+    data: Vec<OpenAIModel>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OpenAIModel {
+    id: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct LlamaServerProps {
+    n_ctx: Option<u32>,
+    default_generation_settings: Option<LlamaServerGenerationSettings>,
+    chat_template_caps: Option<LlamaServerTemplateCaps>,
+}
+
+impl LlamaServerProps {
+    fn model_info(&self) -> OllamaModelInfo {
+        OllamaModelInfo {
+            context_length: self.n_ctx.or_else(|| {
+                self.default_generation_settings
+                    .as_ref()
+                    .and_then(|settings| settings.n_ctx)
+            }),
+            supports_tools: self
+                .chat_template_caps
+                .as_ref()
+                .and_then(|caps| caps.supports_tools),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct LlamaServerGenerationSettings {
+    n_ctx: Option<u32>,
+}
+
+#[derive(Debug, Deserialize)]
+struct LlamaServerTemplateCaps {
+    supports_tools: Option<bool>,
+}
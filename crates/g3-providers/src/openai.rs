@@ -218,11 +218,7 @@ impl OpenAIProvider {
 
                                     // Handle usage
                                     if let Some(usage) = chunk_data.usage {
-                                        accumulated_usage = Some(Usage {
-                                            prompt_tokens: usage.prompt_tokens,
-                                            completion_tokens: usage.completion_tokens,
-                                            total_tokens: usage.total_tokens,
-                                        });
+                                        accumulated_usage = Some(usage.to_usage());
                                     }
                                 }
                                 Err(e) => {
@@ -311,11 +307,7 @@ impl LLMProvider for OpenAIProvider {
             .and_then(|choice| choice.message.content.clone())
             .unwrap_or_default();
 
-        let usage = Usage {
-            prompt_tokens: openai_response.usage.prompt_tokens,
-            completion_tokens: openai_response.usage.completion_tokens,
-            total_tokens: openai_response.usage.total_tokens,
-        };
+        let usage = openai_response.usage.to_usage();
 
         debug!(
             "OpenAI completion successful: {} tokens generated",
@@ -326,6 +318,7 @@ impl LLMProvider for OpenAIProvider {
             content,
             usage,
             model: self.model.clone(),
+            tool_calls: None,
         })
     }
 
@@ -504,6 +497,29 @@ struct OpenAIUsage {
     prompt_tokens: u32,
     completion_tokens: u32,
     total_tokens: u32,
+    #[serde(default)]
+    prompt_tokens_details: Option<OpenAIPromptTokensDetails>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OpenAIPromptTokensDetails {
+    #[serde(default)]
+    cached_tokens: u32,
+}
+
+impl OpenAIUsage {
+    fn to_usage(&self) -> Usage {
+        Usage {
+            prompt_tokens: self.prompt_tokens,
+            completion_tokens: self.completion_tokens,
+            total_tokens: self.total_tokens,
+            cache_read_tokens: self
+                .prompt_tokens_details
+                .as_ref()
+                .map_or(0, |details| details.cached_tokens),
+            cache_creation_tokens: 0,
+        }
+    }
 }
 
 // Streaming response structures
@@ -286,11 +286,7 @@ impl OpenRouterProvider {
 
                                     // Handle usage
                                     if let Some(usage) = chunk_data.usage {
-                                        accumulated_usage = Some(Usage {
-                                            prompt_tokens: usage.prompt_tokens,
-                                            completion_tokens: usage.completion_tokens,
-                                            total_tokens: usage.total_tokens,
-                                        });
+                                        accumulated_usage = Some(usage.to_usage());
                                     }
                                 }
                                 Err(e) => {
@@ -386,11 +382,7 @@ impl LLMProvider for OpenRouterProvider {
             .and_then(|choice| choice.message.content.clone())
             .unwrap_or_default();
 
-        let usage = Usage {
-            prompt_tokens: openrouter_response.usage.prompt_tokens,
-            completion_tokens: openrouter_response.usage.completion_tokens,
-            total_tokens: openrouter_response.usage.total_tokens,
-        };
+        let usage = openrouter_response.usage.to_usage();
 
         debug!(
             "OpenRouter completion successful: {} tokens generated",
@@ -401,6 +393,7 @@ impl LLMProvider for OpenRouterProvider {
             content,
             usage,
             model: self.model.clone(),
+            tool_calls: None,
         })
     }
 
@@ -483,6 +476,12 @@ impl LLMProvider for OpenRouterProvider {
         true
     }
 
+    /// Anthropic and Gemini models only cache at explicit breakpoints; other
+    /// upstream providers cache automatically
+    fn supports_cache_control(&self) -> bool {
+        self.model.starts_with("anthropic/") || self.model.starts_with("google/gemini")
+    }
+
     fn max_tokens(&self) -> u32 {
         self.max_tokens.unwrap_or(4096)
     }
@@ -496,13 +495,22 @@ fn convert_messages(messages: &[Message]) -> Vec<serde_json::Value> {
     messages
         .iter()
         .map(|msg| {
+            // Breakpoints are only accepted on content parts
+            let content = match &msg.cache_control {
+                Some(cache_control) => json!([{
+                    "type": "text",
+                    "text": msg.content,
+                    "cache_control": cache_control,
+                }]),
+                None => json!(msg.content),
+            };
             json!({
                 "role": match msg.role {
                     MessageRole::System => "system",
                     MessageRole::User => "user",
                     MessageRole::Assistant => "assistant",
                 },
-                "content": msg.content,
+                "content": content,
             })
         })
         .collect()
@@ -586,6 +594,30 @@ struct OpenRouterUsage {
     prompt_tokens: u32,
     completion_tokens: u32,
     total_tokens: u32,
+    #[serde(default)]
+    prompt_tokens_details: Option<OpenRouterPromptTokensDetails>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OpenRouterPromptTokensDetails {
+    #[serde(default)]
+    cached_tokens: u32,
+    #[serde(default)]
+    cache_write_tokens: u32,
+}
+
+impl OpenRouterUsage {
+    /// `prompt_tokens` already includes cached tokens
+    fn to_usage(&self) -> Usage {
+        let details = self.prompt_tokens_details.as_ref();
+        Usage {
+            prompt_tokens: self.prompt_tokens,
+            completion_tokens: self.completion_tokens,
+            total_tokens: self.total_tokens,
+            cache_read_tokens: details.map_or(0, |details| details.cached_tokens),
+            cache_creation_tokens: details.map_or(0, |details| details.cache_write_tokens),
+        }
+    }
 }
 
 // Streaming response structures
@@ -618,4 +650,66 @@ struct OpenRouterDeltaToolCall {
 struct OpenRouterDeltaFunction {
     name: Option<String>,
     arguments: Option<String>,
- }
\ No newline at end of file
+ }
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::CacheControl;
+
+    #[test]
+    fn test_breakpoints_become_content_parts() {
+        let messages = vec![
+            Message::with_cache_control(
+                MessageRole::System,
+                "You are G3".to_string(),
+                CacheControl::ephemeral(),
+            ),
+            Message::new(MessageRole::User, "Hello".to_string()),
+        ];
+        let converted = convert_messages(&messages);
+
+        assert_eq!(
+            converted[0]["content"],
+            json!([{
+                "type": "text",
+                "text": "You are G3",
+                "cache_control": {"type": "ephemeral"}
+            }])
+        );
+        assert_eq!(converted[1]["content"], json!("Hello"));
+    }
+
+    #[test]
+    fn test_usage_reports_cached_tokens() {
+        let usage: OpenRouterUsage = serde_json::from_value(json!({
+            "prompt_tokens": 1200,
+            "completion_tokens": 50,
+            "total_tokens": 1250,
+            "prompt_tokens_details": {"cached_tokens": 1000, "cache_write_tokens": 150}
+        }))
+        .unwrap();
+        let usage = usage.to_usage();
+        assert_eq!(usage.prompt_tokens, 1200);
+        assert_eq!(usage.cache_read_tokens, 1000);
+        assert_eq!(usage.cache_creation_tokens, 150);
+
+        let usage: OpenRouterUsage = serde_json::from_value(json!({
+            "prompt_tokens": 10,
+            "completion_tokens": 5,
+            "total_tokens": 15
+        }))
+        .unwrap();
+        assert_eq!(usage.to_usage().cache_read_tokens, 0);
+    }
+
+    #[test]
+    fn test_cache_control_support_depends_on_model() {
+        let provider = |model: &str| {
+            OpenRouterProvider::new("key".to_string(), Some(model.to_string()), None, None)
+                .unwrap()
+        };
+        assert!(provider("anthropic/claude-sonnet-4.5").supports_cache_control());
+        assert!(provider("google/gemini-2.5-pro").supports_cache_control());
+        assert!(!provider("openai/gpt-4o").supports_cache_control());
+    }
+}
@@ -0,0 +1,425 @@
+//! Token counting for context window accounting
+//!
+//! Providers only report usage after a response, so the agent counts tokens
+//! itself to decide when to thin or summarize and to check a request fits
+//! before sending it. Three tokenizers are available:
+//!
+//! - [`BpeTokenizer`]: tiktoken-style byte-pair encoding for OpenAI models,
+//!   loaded from a `.tiktoken` rank file (`cl100k_base`, `o200k_base`)
+//! - the embedded provider's GGUF vocabulary, via [`crate::LLMProvider::tokenizer`]
+//! - [`EstimatingTokenizer`]: a heuristic scaled per model family, used when
+//!   neither of the above is available
+//!
+//! [`tokenizer_for`] picks the best one for a provider.
+
+use crate::{LLMProvider, Message};
+use anyhow::{anyhow, Context, Result};
+use base64::Engine;
+use regex::Regex;
+use std::collections::HashMap;
+use std::fmt;
+use std::path::PathBuf;
+use std::sync::Arc;
+use tracing::{debug, info};
+
+/// Tokens added around each chat message by the chat template (role markers, separators)
+pub const MESSAGE_OVERHEAD_TOKENS: u32 = 4;
+
+/// Pre-tokenization pattern shared by `cl100k_base` and the estimator.
+/// tiktoken's trailing `\s+(?!\S)` needs look-ahead, which `regex` lacks;
+/// [`split_pieces`] emulates it.
+const CL100K_PATTERN: &str = r"(?i:'s|'t|'re|'ve|'m|'ll|'d)|[^\r\n\p{L}\p{N}]?\p{L}+|\p{N}{1,3}| ?[^\s\p{L}\p{N}]+[\r\n]*|\s*[\r\n]+|\s+";
+
+const O200K_PATTERN: &str = r"[^\r\n\p{L}\p{N}]?[\p{Lu}\p{Lt}\p{Lm}\p{Lo}\p{M}]*[\p{Ll}\p{Lm}\p{Lo}\p{M}]+(?i:'s|'t|'re|'ve|'m|'ll|'d)?|[^\r\n\p{L}\p{N}]?[\p{Lu}\p{Lt}\p{Lm}\p{Lo}\p{M}]+[\p{Ll}\p{Lm}\p{Lo}\p{M}]*(?i:'s|'t|'re|'ve|'m|'ll|'d)?|\p{N}{1,3}| ?[^\s\p{L}\p{N}]+[\r\n/]*|\s*[\r\n]+|\s+";
+
+const ENCODINGS_URL: &str = "https://openaipublic.blob.core.windows.net/encodings";
+
+/// Counts tokens the way a model's tokenizer would
+pub trait Tokenizer: Send + Sync + fmt::Debug {
+    /// Short description, e.g. `o200k_base` or `estimate(claude)`
+    fn name(&self) -> &str;
+
+    /// Number of tokens in `text`
+    fn count_tokens(&self, text: &str) -> u32;
+
+    /// Number of tokens a message takes in a request, including template overhead
+    fn count_message_tokens(&self, message: &Message) -> u32 {
+        self.count_tokens(&message.content) + MESSAGE_OVERHEAD_TOKENS
+    }
+}
+
+/// Split `text` into pre-tokenization pieces, emulating tiktoken's `\s+(?!\S)`:
+/// a whitespace run followed by a word leaves its last character to that word.
+fn split_pieces<'a>(pattern: &Regex, text: &'a str) -> Vec<&'a str> {
+    let mut pieces = Vec::new();
+    let mut start = 0;
+    while let Some(m) = pattern.find_at(text, start) {
+        let mut end = m.end();
+        let piece = m.as_str();
+        let followed_by_text = text[end..]
+            .chars()
+            .next()
+            .is_some_and(|c| !c.is_whitespace());
+        if followed_by_text && piece.chars().count() > 1 && piece.chars().all(char::is_whitespace) {
+            let last = piece.chars().last().map(char::len_utf8).unwrap_or(0);
+            // A lone space is picked up by the next piece's optional leading character
+            end -= last;
+        }
+        pieces.push(&text[m.start()..end]);
+        start = end;
+    }
+    pieces
+}
+
+/// tiktoken-style byte-pair encoding
+pub struct BpeTokenizer {
+    name: String,
+    ranks: HashMap<Vec<u8>, u32>,
+    pattern: Regex,
+}
+
+impl fmt::Debug for BpeTokenizer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BpeTokenizer")
+            .field("name", &self.name)
+            .field("vocab_size", &self.ranks.len())
+            .finish()
+    }
+}
+
+impl BpeTokenizer {
+    /// Build a tokenizer from the contents of a `.tiktoken` file
+    /// (one `<base64 token> <rank>` pair per line)
+    pub fn from_tiktoken(name: &str, contents: &str) -> Result<Self> {
+        let mut ranks = HashMap::new();
+        for (line_number, line) in contents.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let (token, rank) = line
+                .split_once(' ')
+                .ok_or_else(|| anyhow!("Invalid line {} in {} ranks", line_number + 1, name))?;
+            let token = base64::engine::general_purpose::STANDARD
+                .decode(token)
+                .with_context(|| {
+                    format!(
+                        "Invalid token on line {} in {} ranks",
+                        line_number + 1,
+                        name
+                    )
+                })?;
+            let rank: u32 = rank.trim().parse().with_context(|| {
+                format!("Invalid rank on line {} in {} ranks", line_number + 1, name)
+            })?;
+            ranks.insert(token, rank);
+        }
+
+        let pattern = if name.starts_with("o200k") {
+            O200K_PATTERN
+        } else {
+            CL100K_PATTERN
+        };
+
+        Ok(Self {
+            name: name.to_string(),
+            ranks,
+            pattern: Regex::new(pattern)?,
+        })
+    }
+
+    /// Where the rank file for `encoding` is cached
+    pub fn cache_path(encoding: &str) -> PathBuf {
+        tokenizer_cache_dir().join(format!("{}.tiktoken", encoding))
+    }
+
+    /// Load `encoding` from the tokenizer cache, if it has been downloaded
+    pub fn load_cached(encoding: &str) -> Result<Self> {
+        let path = Self::cache_path(encoding);
+        let contents = std::fs::read_to_string(&path)
+            .with_context(|| format!("Tokenizer ranks not found at {}", path.display()))?;
+        Self::from_tiktoken(encoding, &contents)
+    }
+
+    /// Download the rank file for `encoding` into the tokenizer cache
+    pub async fn download(encoding: &str) -> Result<PathBuf> {
+        let path = Self::cache_path(encoding);
+        let url = format!("{}/{}.tiktoken", ENCODINGS_URL, encoding);
+
+        info!(
+            "Downloading {} tokenizer ranks to {}",
+            encoding,
+            path.display()
+        );
+        let response = reqwest::get(&url).await?;
+        if !response.status().is_success() {
+            anyhow::bail!("Failed to download {}: HTTP {}", url, response.status());
+        }
+        let contents = response.text().await?;
+        // Validate before caching so a bad download is not picked up later
+        Self::from_tiktoken(encoding, &contents)?;
+
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        std::fs::write(&path, contents)?;
+        Ok(path)
+    }
+
+    /// Number of BPE tokens in one pre-tokenized piece
+    fn piece_tokens(&self, piece: &[u8]) -> usize {
+        if piece.len() <= 1 || self.ranks.contains_key(piece) {
+            return piece.len().min(1);
+        }
+
+        // Merge the lowest-ranked adjacent pair until no pair is in the vocabulary.
+        // Each entry is (start offset, rank of the pair starting here).
+        let rank_of = |parts: &[(usize, u32)], i: usize| -> u32 {
+            if i + 3 < parts.len() {
+                *self
+                    .ranks
+                    .get(&piece[parts[i].0..parts[i + 3].0])
+                    .unwrap_or(&u32::MAX)
+            } else {
+                u32::MAX
+            }
+        };
+
+        let mut parts: Vec<(usize, u32)> = (0..piece.len() - 1)
+            .map(|i| (i, *self.ranks.get(&piece[i..i + 2]).unwrap_or(&u32::MAX)))
+            .collect();
+        parts.push((piece.len() - 1, u32::MAX));
+        parts.push((piece.len(), u32::MAX));
+
+        // Ties go to the leftmost pair, as in tiktoken
+        let lowest = |parts: &[(usize, u32)]| {
+            let mut best: Option<(usize, u32)> = None;
+            for (i, &(_, rank)) in parts[..parts.len() - 1].iter().enumerate() {
+                if rank != u32::MAX && best.is_none_or(|(_, lowest)| rank < lowest) {
+                    best = Some((i, rank));
+                }
+            }
+            best.map(|(i, _)| i)
+        };
+
+        while let Some(i) = lowest(&parts) {
+            if i > 0 {
+                parts[i - 1].1 = rank_of(&parts, i - 1);
+            }
+            parts[i].1 = rank_of(&parts, i);
+            parts.remove(i + 1);
+        }
+
+        parts.len() - 1
+    }
+}
+
+impl Tokenizer for BpeTokenizer {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn count_tokens(&self, text: &str) -> u32 {
+        split_pieces(&self.pattern, text)
+            .into_iter()
+            .map(|piece| self.piece_tokens(piece.as_bytes()))
+            .sum::<usize>() as u32
+    }
+}
+
+/// Heuristic token counts for models whose tokenizer is not available locally.
+///
+/// Text is split like `cl100k_base` would split it, and each piece is charged
+/// by length and character class. The result is scaled by how the model
+/// family's tokenizer compares with `cl100k_base`.
+#[derive(Debug)]
+pub struct EstimatingTokenizer {
+    name: String,
+    scale: f32,
+    pattern: Regex,
+}
+
+impl EstimatingTokenizer {
+    pub fn new(family: &str, scale: f32) -> Self {
+        Self {
+            name: format!("estimate({})", family),
+            scale,
+            pattern: Regex::new(CL100K_PATTERN).expect("valid pre-tokenization pattern"),
+        }
+    }
+
+    /// An estimator scaled for the model family `model` belongs to
+    pub fn for_model(model: &str) -> Self {
+        let model = model.to_lowercase();
+        if model.contains("claude") || model.contains("anthropic") {
+            // Claude's tokenizer produces ~10-15% more tokens than cl100k, more for code
+            Self::new("claude", 1.15)
+        } else if model.contains("gemini") {
+            Self::new("gemini", 0.95)
+        } else if model.contains("gpt") || model.starts_with("o1") || model.starts_with("o3") {
+            Self::new("openai", 1.0)
+        } else {
+            // Llama, Qwen, Mistral and unknown models: err on the high side
+            Self::new("generic", 1.1)
+        }
+    }
+
+    fn piece_estimate(piece: &str) -> f32 {
+        if !piece.is_ascii() {
+            // CJK characters are about one token each (3 UTF-8 bytes); accented
+            // Latin text splits into roughly one token per two bytes
+            return (piece.len() as f32 / 2.5).max(1.0);
+        }
+        let letters = piece.chars().filter(char::is_ascii_alphabetic).count();
+        if letters > 0 {
+            // Common words are one token; long identifiers split every ~5 letters
+            return (letters as f32 / 5.0).ceil().max(1.0);
+        }
+        if piece.trim().is_empty() {
+            return 1.0;
+        }
+        if piece.chars().all(|c| c.is_ascii_digit()) {
+            return 1.0;
+        }
+        // Punctuation and operators merge in pairs at best
+        let symbols = piece.chars().filter(|c| !c.is_whitespace()).count();
+        (symbols as f32 / 2.0).ceil().max(1.0)
+    }
+}
+
+impl Default for EstimatingTokenizer {
+    fn default() -> Self {
+        Self::new("generic", 1.1)
+    }
+}
+
+impl Tokenizer for EstimatingTokenizer {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn count_tokens(&self, text: &str) -> u32 {
+        let estimate: f32 = split_pieces(&self.pattern, text)
+            .into_iter()
+            .map(Self::piece_estimate)
+            .sum();
+        (estimate * self.scale).ceil() as u32
+    }
+}
+
+/// The tiktoken encoding used by an OpenAI model, if known.
+/// Accepts prefixed names such as `openai/gpt-4o`.
+pub fn bpe_encoding_for_model(model: &str) -> Option<&'static str> {
+    let model = model.rsplit('/').next().unwrap_or(model).to_lowercase();
+    let o200k = ["gpt-4o", "gpt-4.1", "gpt-4.5", "gpt-5", "o1", "o3", "o4"];
+    let cl100k = ["gpt-4", "gpt-3.5"];
+
+    if o200k.iter().any(|prefix| model.starts_with(prefix)) {
+        Some("o200k_base")
+    } else if cl100k.iter().any(|prefix| model.starts_with(prefix)) {
+        Some("cl100k_base")
+    } else {
+        None
+    }
+}
+
+/// Where downloaded `.tiktoken` rank files are kept (`G3_TOKENIZER_DIR` overrides)
+pub fn tokenizer_cache_dir() -> PathBuf {
+    if let Ok(dir) = std::env::var("G3_TOKENIZER_DIR") {
+        return PathBuf::from(dir);
+    }
+    dirs::cache_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("g3")
+        .join("tokenizers")
+}
+
+/// The most accurate tokenizer available for `provider` without network access:
+/// the provider's own, then cached BPE ranks, then an estimator
+pub fn tokenizer_for(provider: &dyn LLMProvider) -> Arc<dyn Tokenizer> {
+    if let Some(tokenizer) = provider.tokenizer() {
+        return tokenizer;
+    }
+
+    if let Some(encoding) = bpe_encoding_for_model(provider.model()) {
+        match BpeTokenizer::load_cached(encoding) {
+            Ok(tokenizer) => return Arc::new(tokenizer),
+            Err(e) => debug!(
+                "Using estimated token counts for {}: {}",
+                provider.model(),
+                e
+            ),
+        }
+    }
+
+    Arc::new(EstimatingTokenizer::for_model(provider.model()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A toy vocabulary: all single bytes plus a few merges, ranked by merge order
+    fn toy_ranks() -> String {
+        let mut tokens: Vec<Vec<u8>> = (0u8..=255).map(|b| vec![b]).collect();
+        for merge in [
+            "he", "ll", "hell", "hello", " w", "or", " wor", "ld", " world",
+        ] {
+            tokens.push(merge.as_bytes().to_vec());
+        }
+        tokens
+            .iter()
+            .enumerate()
+            .map(|(rank, token)| {
+                format!(
+                    "{} {}\n",
+                    base64::engine::general_purpose::STANDARD.encode(token),
+                    rank
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_bpe_merges_by_rank() {
+        let tokenizer = BpeTokenizer::from_tiktoken("cl100k_base", &toy_ranks()).unwrap();
+        assert_eq!(tokenizer.count_tokens("hello"), 1);
+        assert_eq!(tokenizer.count_tokens("hello world"), 2);
+        // "help" merges "he" and then stops: he + l + p
+        assert_eq!(tokenizer.count_tokens("help"), 3);
+        assert_eq!(tokenizer.count_tokens(""), 0);
+    }
+
+    #[test]
+    fn test_split_pieces_matches_tiktoken() {
+        let pattern = Regex::new(CL100K_PATTERN).unwrap();
+        assert_eq!(
+            split_pieces(&pattern, "fn main() {\n    let x = 42;\n}"),
+            vec!["fn", " main", "()", " {\n", "   ", " let", " x", " =", " ", "42", ";\n", "}"]
+        );
+        assert_eq!(
+            split_pieces(&pattern, "I'm   here"),
+            vec!["I", "'m", "  ", " here"]
+        );
+    }
+
+    #[test]
+    fn test_estimator_scales_by_family() {
+        let text = "The quick brown fox jumps over the lazy dog.";
+        let openai = EstimatingTokenizer::for_model("gpt-4o").count_tokens(text);
+        let claude = EstimatingTokenizer::for_model("claude-sonnet-4-5").count_tokens(text);
+        // cl100k_base encodes this sentence as 10 tokens
+        assert_eq!(openai, 10);
+        assert!(claude > openai);
+    }
+
+    #[test]
+    fn test_bpe_encoding_for_model() {
+        assert_eq!(bpe_encoding_for_model("gpt-4o-mini"), Some("o200k_base"));
+        assert_eq!(
+            bpe_encoding_for_model("openai/gpt-4-turbo"),
+            Some("cl100k_base")
+        );
+        assert_eq!(bpe_encoding_for_model("o3-mini"), Some("o200k_base"));
+        assert_eq!(bpe_encoding_for_model("claude-sonnet-4-5"), None);
+    }
+}
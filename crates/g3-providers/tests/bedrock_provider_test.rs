@@ -0,0 +1,241 @@
+//! Tests for the Bedrock provider
+//!
+//! Each test runs against a mock server that serves recorded Bedrock
+//! responses, including `application/vnd.amazon.eventstream` frames.
+
+mod common;
+
+use base64::Engine;
+use common::{mock_server, Route};
+use futures_util::StreamExt;
+use g3_providers::{
+    AwsCredentials, BedrockProvider, CacheControl, CompletionRequest, LLMProvider, Message,
+    MessageRole, Tool,
+};
+use serde_json::{json, Value};
+
+const MODEL: &str = "anthropic.claude-sonnet-4-5-20250929-v1:0";
+
+fn provider(endpoint: String) -> BedrockProvider {
+    // Explicit env credentials keep the test independent of ~/.aws
+    std::env::set_var("AWS_ACCESS_KEY_ID", "AKIDEXAMPLE");
+    std::env::set_var("AWS_SECRET_ACCESS_KEY", "secret");
+    BedrockProvider::new(
+        MODEL.to_string(),
+        Some("us-west-2".to_string()),
+        None,
+        Some(1024),
+        None,
+    )
+    .unwrap()
+    .with_endpoint_url(endpoint)
+    .with_credentials(AwsCredentials {
+        access_key_id: "AKIDEXAMPLE".to_string(),
+        secret_access_key: "wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY".to_string(),
+        session_token: Some("session-token".to_string()),
+    })
+}
+
+fn request(stream: bool) -> CompletionRequest {
+    CompletionRequest {
+        messages: vec![
+            Message::new(MessageRole::System, "You are G3".to_string()),
+            Message::with_cache_control(
+                MessageRole::User,
+                "Read the README".to_string(),
+                CacheControl::ephemeral(),
+            ),
+        ],
+        max_tokens: None,
+        temperature: None,
+        stream,
+        tools: Some(vec![Tool {
+            name: "read_file".to_string(),
+            description: "Read a file".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": { "file_path": { "type": "string" } },
+                "required": ["file_path"]
+            }),
+        }]),
+        disable_thinking: false,
+    }
+}
+
+/// Encode one event-stream message with string headers
+fn frame(headers: &[(&str, &str)], payload: &[u8]) -> Vec<u8> {
+    let mut header_bytes = Vec::new();
+    for (name, value) in headers {
+        header_bytes.push(name.len() as u8);
+        header_bytes.extend_from_slice(name.as_bytes());
+        header_bytes.push(7);
+        header_bytes.extend_from_slice(&(value.len() as u16).to_be_bytes());
+        header_bytes.extend_from_slice(value.as_bytes());
+    }
+    let total_len = (16 + header_bytes.len() + payload.len()) as u32;
+    let mut message = total_len.to_be_bytes().to_vec();
+    message.extend_from_slice(&(header_bytes.len() as u32).to_be_bytes());
+    let prelude_crc = crc32fast::hash(&message);
+    message.extend_from_slice(&prelude_crc.to_be_bytes());
+    message.extend_from_slice(&header_bytes);
+    message.extend_from_slice(payload);
+    let message_crc = crc32fast::hash(&message);
+    message.extend_from_slice(&message_crc.to_be_bytes());
+    message
+}
+
+/// Wrap a Messages API stream event the way Bedrock does
+fn chunk(event: Value) -> Vec<u8> {
+    let payload = json!({
+        "bytes": base64::engine::general_purpose::STANDARD.encode(event.to_string()),
+        "p": "abcdef"
+    });
+    frame(
+        &[
+            (":event-type", "chunk"),
+            (":content-type", "application/json"),
+            (":message-type", "event"),
+        ],
+        payload.to_string().as_bytes(),
+    )
+}
+
+#[tokio::test]
+async fn test_stream_decodes_event_stream_frames() {
+    let events = [
+        json!({
+            "type": "message_start",
+            "message": { "usage": { "input_tokens": 200, "output_tokens": 1 } }
+        }),
+        json!({ "type": "content_block_start", "index": 0, "content_block": { "type": "text", "text": "" } }),
+        json!({ "type": "content_block_delta", "index": 0, "delta": { "type": "text_delta", "text": "Let me " } }),
+        json!({ "type": "content_block_delta", "index": 0, "delta": { "type": "text_delta", "text": "look." } }),
+        json!({ "type": "content_block_stop", "index": 0 }),
+        json!({
+            "type": "content_block_start",
+            "index": 1,
+            "content_block": { "type": "tool_use", "id": "toolu_bdrk_01", "name": "read_file", "input": {} }
+        }),
+        json!({
+            "type": "content_block_delta",
+            "index": 1,
+            "delta": { "type": "input_json_delta", "partial_json": "{\"file_path\": \"READ" }
+        }),
+        json!({
+            "type": "content_block_delta",
+            "index": 1,
+            "delta": { "type": "input_json_delta", "partial_json": "ME.md\"}" }
+        }),
+        json!({ "type": "content_block_stop", "index": 1 }),
+        json!({ "type": "message_stop" }),
+    ];
+    let body: Vec<u8> = events.into_iter().flat_map(chunk).collect();
+    let (endpoint, requests) = mock_server(vec![Route::raw(
+        "/model/",
+        "application/vnd.amazon.eventstream",
+        body,
+    )])
+    .await;
+
+    let mut stream = provider(endpoint).stream(request(true)).await.unwrap();
+
+    let mut content = String::new();
+    let mut final_chunk = None;
+    while let Some(chunk) = stream.next().await {
+        let chunk = chunk.unwrap();
+        content.push_str(&chunk.content);
+        if chunk.finished && final_chunk.is_none() {
+            final_chunk = Some(chunk);
+        }
+    }
+
+    assert_eq!(content, "Let me look.");
+    let final_chunk = final_chunk.expect("no final chunk");
+    let tool_calls = final_chunk.tool_calls.unwrap();
+    assert_eq!(tool_calls[0].id, "toolu_bdrk_01");
+    assert_eq!(tool_calls[0].args, json!({ "file_path": "README.md" }));
+    assert_eq!(final_chunk.usage.unwrap().prompt_tokens, 200);
+
+    let requests = requests.lock().unwrap();
+    let request = &requests[0];
+    assert_eq!(
+        request.path,
+        "/model/anthropic.claude-sonnet-4-5-20250929-v1%3A0/invoke-with-response-stream"
+    );
+
+    let authorization = request.header("authorization").unwrap();
+    assert!(authorization.starts_with("AWS4-HMAC-SHA256 Credential=AKIDEXAMPLE/"));
+    assert!(authorization.contains("/us-west-2/bedrock/aws4_request"));
+    assert!(
+        authorization.contains("SignedHeaders=content-type;host;x-amz-date;x-amz-security-token")
+    );
+    assert_eq!(
+        request.header("x-amz-security-token"),
+        Some("session-token")
+    );
+
+    // The Messages API body, minus the fields Bedrock takes from the URL
+    let body = &request.body;
+    assert_eq!(body["anthropic_version"], json!("bedrock-2023-05-31"));
+    assert!(body.get("model").is_none());
+    assert!(body.get("stream").is_none());
+    assert_eq!(body["max_tokens"], json!(1024));
+    assert_eq!(body["system"], json!("You are G3"));
+    assert_eq!(body["tools"][0]["name"], json!("read_file"));
+    assert_eq!(
+        body["messages"][0]["content"][0]["cache_control"],
+        json!({ "type": "ephemeral" })
+    );
+}
+
+#[tokio::test]
+async fn test_stream_reports_exceptions() {
+    let body = frame(
+        &[
+            (":exception-type", "throttlingException"),
+            (":content-type", "application/json"),
+            (":message-type", "exception"),
+        ],
+        br#"{"message":"Too many requests, please wait before trying again."}"#,
+    );
+    let (endpoint, _) = mock_server(vec![Route::raw(
+        "/model/",
+        "application/vnd.amazon.eventstream",
+        body,
+    )])
+    .await;
+
+    let mut stream = provider(endpoint).stream(request(true)).await.unwrap();
+    let mut errors = Vec::new();
+    while let Some(chunk) = stream.next().await {
+        if let Err(e) = chunk {
+            errors.push(e.to_string());
+        }
+    }
+
+    assert_eq!(errors.len(), 1);
+    assert!(
+        errors[0].contains("throttlingException"),
+        "unexpected error: {}",
+        errors[0]
+    );
+}
+
+#[tokio::test]
+async fn test_complete() {
+    let response = json!({
+        "id": "msg_bdrk_01",
+        "type": "message",
+        "role": "assistant",
+        "model": "claude-sonnet-4-5-20250929",
+        "content": [{ "type": "text", "text": "It is a coding agent." }],
+        "stop_reason": "end_turn",
+        "usage": { "input_tokens": 40, "output_tokens": 6 }
+    });
+    let (endpoint, requests) = mock_server(vec![Route::json("/model/", response)]).await;
+
+    let response = provider(endpoint).complete(request(false)).await.unwrap();
+    assert_eq!(response.content, "It is a coding agent.");
+    assert_eq!(response.usage.total_tokens, 46);
+    assert!(requests.lock().unwrap()[0].path.ends_with("%3A0/invoke"));
+}
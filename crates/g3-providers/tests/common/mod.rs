@@ -0,0 +1,186 @@
+//! A small HTTP server on localhost for provider tests
+//!
+//! It answers each route with a canned body and records the requests it
+//! receives, so tests can check what a provider sent. Agent tests in
+//! g3-core and g3-server include this file with `#[path]`.
+
+#![allow(dead_code)]
+
+use serde_json::Value;
+use std::sync::{Arc, Mutex};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+/// A request received by the mock server
+#[derive(Debug, Clone)]
+pub struct RecordedRequest {
+    /// Path including the query string
+    pub path: String,
+    /// Header names are lowercased
+    pub headers: Vec<(String, String)>,
+    /// The body parsed as JSON, or `Value::Null`
+    pub body: Value,
+}
+
+impl RecordedRequest {
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+pub type Requests = Arc<Mutex<Vec<RecordedRequest>>>;
+
+/// A response sent by the mock server
+pub struct Response {
+    pub status: &'static str,
+    pub content_type: &'static str,
+    pub body: Vec<u8>,
+}
+
+impl Response {
+    pub fn json(body: impl ToString) -> Self {
+        Self::raw("application/json", body.to_string())
+    }
+
+    pub fn raw(content_type: &'static str, body: impl Into<Vec<u8>>) -> Self {
+        Self {
+            status: "200 OK",
+            content_type,
+            body: body.into(),
+        }
+    }
+
+    /// An OpenAI-style event stream of `events`, ended by `[DONE]`
+    pub fn sse(events: &[&str]) -> Self {
+        let body: String = events
+            .iter()
+            .chain(["[DONE]"].iter())
+            .map(|event| format!("data: {}\n\n", event))
+            .collect();
+        Self::raw("text/event-stream", body)
+    }
+
+    pub fn with_status(mut self, status: &'static str) -> Self {
+        self.status = status;
+        self
+    }
+
+    fn not_found() -> Self {
+        Self::raw("text/plain", Vec::new()).with_status("404 Not Found")
+    }
+}
+
+/// A canned response for requests whose path starts with `path`
+pub struct Route {
+    pub path: &'static str,
+    pub response: Response,
+}
+
+impl Route {
+    pub fn json(path: &'static str, body: impl ToString) -> Self {
+        Self {
+            path,
+            response: Response::json(body),
+        }
+    }
+
+    pub fn raw(path: &'static str, content_type: &'static str, body: impl Into<Vec<u8>>) -> Self {
+        Self {
+            path,
+            response: Response::raw(content_type, body),
+        }
+    }
+}
+
+/// Serve `routes` and return the base URL. Unknown paths get a 404.
+pub async fn mock_server(routes: Vec<Route>) -> (String, Requests) {
+    mock_server_with(move |request| {
+        match routes
+            .iter()
+            .find(|route| request.path.starts_with(route.path))
+        {
+            Some(route) => Response::raw(route.response.content_type, route.response.body.clone())
+                .with_status(route.response.status),
+            None => Response::not_found(),
+        }
+    })
+    .await
+}
+
+/// Answer every request with `respond` and return the base URL
+pub async fn mock_server_with<F>(respond: F) -> (String, Requests)
+where
+    F: Fn(&RecordedRequest) -> Response + Send + 'static,
+{
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let base_url = format!("http://{}", listener.local_addr().unwrap());
+    let requests: Requests = Arc::default();
+
+    let recorded = requests.clone();
+    tokio::spawn(async move {
+        loop {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let Some(request) = read_request(&mut socket).await else {
+                continue;
+            };
+
+            let response = respond(&request);
+            recorded.lock().unwrap().push(request);
+
+            let mut bytes = format!(
+                "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                response.status,
+                response.content_type,
+                response.body.len()
+            )
+            .into_bytes();
+            bytes.extend_from_slice(&response.body);
+            socket.write_all(&bytes).await.ok();
+            socket.shutdown().await.ok();
+        }
+    });
+
+    (base_url, requests)
+}
+
+/// Read the headers, then the body announced by Content-Length. Returns
+/// `None` if the client closes the connection before sending a whole request.
+async fn read_request(socket: &mut TcpStream) -> Option<RecordedRequest> {
+    let mut data = Vec::new();
+    let mut buf = [0u8; 4096];
+    loop {
+        let n = socket.read(&mut buf).await.ok()?;
+        if n == 0 {
+            return None;
+        }
+        data.extend_from_slice(&buf[..n]);
+        let Some(header_end) = data.windows(4).position(|w| w == b"\r\n\r\n") else {
+            continue;
+        };
+        let head = String::from_utf8_lossy(&data[..header_end]).to_string();
+        let headers: Vec<(String, String)> = head
+            .lines()
+            .skip(1)
+            .filter_map(|line| {
+                let (name, value) = line.split_once(':')?;
+                Some((name.trim().to_lowercase(), value.trim().to_string()))
+            })
+            .collect();
+        let content_length = headers
+            .iter()
+            .find(|(name, _)| name == "content-length")
+            .map(|(_, value)| value.parse::<usize>().unwrap())
+            .unwrap_or(0);
+        if data.len() >= header_end + 4 + content_length {
+            let body = &data[header_end + 4..header_end + 4 + content_length];
+            return Some(RecordedRequest {
+                path: head.split_whitespace().nth(1)?.to_string(),
+                headers,
+                body: serde_json::from_slice(body).unwrap_or(Value::Null),
+            });
+        }
+    }
+}
@@ -0,0 +1,218 @@
+//! Tests for the Gemini provider
+//!
+//! Each test runs against a mock server with canned Gemini API responses.
+
+mod common;
+
+use common::{mock_server, Route};
+use futures_util::StreamExt;
+use g3_providers::{CompletionRequest, GeminiProvider, LLMProvider, Message, MessageRole, Tool};
+use serde_json::json;
+
+fn provider(base_url: String) -> GeminiProvider {
+    GeminiProvider::new(
+        "test-key".to_string(),
+        Some("gemini-2.5-pro".to_string()),
+        Some(2048),
+        Some(0.5),
+    )
+    .unwrap()
+    .with_base_url(base_url)
+}
+
+fn request(stream: bool, tools: Option<Vec<Tool>>) -> CompletionRequest {
+    CompletionRequest {
+        messages: vec![
+            Message::new(MessageRole::System, "You are G3".to_string()),
+            Message::new(MessageRole::System, "README contents".to_string()),
+            Message::new(MessageRole::User, "Task: read the README".to_string()),
+            Message::new(MessageRole::Assistant, "Reading it.".to_string()),
+            Message::new(MessageRole::User, "Tool result: ...".to_string()),
+            Message::new(MessageRole::User, "Continue".to_string()),
+        ],
+        max_tokens: None,
+        temperature: None,
+        stream,
+        tools,
+        disable_thinking: false,
+    }
+}
+
+fn read_file_tool() -> Tool {
+    Tool {
+        name: "read_file".to_string(),
+        description: "Read a file".to_string(),
+        input_schema: json!({
+            "type": "object",
+            "properties": { "file_path": { "type": "string" } },
+            "required": ["file_path"],
+            "additionalProperties": false
+        }),
+    }
+}
+
+#[tokio::test]
+async fn test_complete() {
+    let response = json!({
+        "candidates": [{
+            "content": {
+                "role": "model",
+                "parts": [
+                    { "text": "Weighing the options", "thought": true },
+                    { "text": "It is a coding agent." }
+                ]
+            },
+            "finishReason": "STOP"
+        }],
+        "usageMetadata": {
+            "promptTokenCount": 52,
+            "candidatesTokenCount": 7,
+            "totalTokenCount": 59
+        }
+    });
+    let (base_url, requests) = mock_server(vec![Route::json(
+        "/models/gemini-2.5-pro:generateContent",
+        response,
+    )])
+    .await;
+
+    let response = provider(base_url).complete(request(false, None)).await.unwrap();
+    assert_eq!(response.content, "It is a coding agent.");
+    assert_eq!(response.usage.prompt_tokens, 52);
+    assert_eq!(response.usage.completion_tokens, 7);
+    assert_eq!(response.usage.total_tokens, 59);
+    assert!(response.tool_calls.is_none());
+
+    let requests = requests.lock().unwrap();
+    assert_eq!(requests[0].header("x-goog-api-key"), Some("test-key"));
+
+    let body = &requests[0].body;
+    assert_eq!(
+        body["systemInstruction"],
+        json!({ "parts": [{ "text": "You are G3" }, { "text": "README contents" }] })
+    );
+    // Consecutive user messages are merged into one turn
+    let roles: Vec<_> = body["contents"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|content| content["role"].as_str().unwrap())
+        .collect();
+    assert_eq!(roles, vec!["user", "model", "user"]);
+    assert_eq!(body["contents"][2]["parts"].as_array().unwrap().len(), 2);
+    assert_eq!(body["generationConfig"]["maxOutputTokens"], json!(2048));
+    assert_eq!(body["generationConfig"]["temperature"], json!(0.5));
+}
+
+#[tokio::test]
+async fn test_complete_with_function_call() {
+    let response = json!({
+        "candidates": [{
+            "content": {
+                "role": "model",
+                "parts": [
+                    { "text": "Let me look." },
+                    { "functionCall": { "name": "read_file", "args": { "file_path": "README.md" } } }
+                ]
+            },
+            "finishReason": "STOP"
+        }],
+        "usageMetadata": { "promptTokenCount": 80, "candidatesTokenCount": 12, "totalTokenCount": 92 }
+    });
+    let (base_url, _) = mock_server(vec![Route::json(
+        "/models/gemini-2.5-pro:generateContent",
+        response,
+    )])
+    .await;
+
+    let response = provider(base_url)
+        .complete(request(false, Some(vec![read_file_tool()])))
+        .await
+        .unwrap();
+    assert_eq!(response.content, "Let me look.");
+    let tool_calls = response.tool_calls.unwrap();
+    assert_eq!(tool_calls.len(), 1);
+    assert_eq!(tool_calls[0].id, "call_0");
+    assert_eq!(tool_calls[0].tool, "read_file");
+    assert_eq!(tool_calls[0].args, json!({ "file_path": "README.md" }));
+}
+
+#[tokio::test]
+async fn test_stream_with_function_call() {
+    let events = [
+        json!({
+            "candidates": [{ "content": { "role": "model", "parts": [{ "text": "Let me " }] } }],
+            "usageMetadata": { "promptTokenCount": 80, "totalTokenCount": 80 }
+        }),
+        json!({
+            "candidates": [{ "content": { "role": "model", "parts": [{ "text": "look." }] } }]
+        }),
+        json!({
+            "candidates": [{
+                "content": {
+                    "role": "model",
+                    "parts": [
+                        { "functionCall": { "name": "read_file", "args": { "file_path": "README.md" } } },
+                        { "functionCall": { "name": "read_file", "args": { "file_path": "Cargo.toml" } } }
+                    ]
+                },
+                "finishReason": "STOP"
+            }],
+            "usageMetadata": {
+                "promptTokenCount": 80,
+                "candidatesTokenCount": 25,
+                "totalTokenCount": 105
+            }
+        }),
+    ]
+    .iter()
+    .map(|event| format!("data: {}\r\n\r\n", event))
+    .collect::<String>();
+    let (base_url, requests) = mock_server(vec![Route::raw(
+        "/models/gemini-2.5-pro:streamGenerateContent",
+        "text/event-stream",
+        events,
+    )])
+    .await;
+
+    let mut stream = provider(base_url)
+        .stream(request(true, Some(vec![read_file_tool()])))
+        .await
+        .unwrap();
+
+    let mut content = String::new();
+    let mut final_chunk = None;
+    while let Some(chunk) = stream.next().await {
+        let chunk = chunk.unwrap();
+        content.push_str(&chunk.content);
+        if chunk.finished {
+            final_chunk = Some(chunk);
+        }
+    }
+
+    assert_eq!(content, "Let me look.");
+    let final_chunk = final_chunk.expect("no final chunk");
+    let tool_calls = final_chunk.tool_calls.unwrap();
+    assert_eq!(tool_calls.len(), 2);
+    assert_eq!(tool_calls[0].tool, "read_file");
+    assert_eq!(tool_calls[1].args, json!({ "file_path": "Cargo.toml" }));
+    assert_ne!(tool_calls[0].id, tool_calls[1].id);
+    assert_eq!(final_chunk.usage.unwrap().total_tokens, 105);
+
+    let requests = requests.lock().unwrap();
+    assert!(requests[0].path.ends_with("?alt=sse"));
+    let declaration = &requests[0].body["tools"][0]["functionDeclarations"][0];
+    assert_eq!(declaration["name"], json!("read_file"));
+    assert!(declaration["parameters"].get("additionalProperties").is_none());
+}
+
+#[tokio::test]
+async fn test_api_errors_are_reported() {
+    let (base_url, _) = mock_server(vec![]).await;
+
+    let err = provider(base_url)
+        .complete(request(false, None))
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("Gemini API error 404"), "unexpected error: {}", err);
+}
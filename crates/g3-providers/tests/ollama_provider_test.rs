@@ -0,0 +1,257 @@
+//! Tests for the Ollama / llama-server provider
+//!
+//! Each test runs against a mock server with canned Ollama or llama-server
+//! responses.
+
+mod common;
+
+use common::{mock_server, Route};
+use futures_util::StreamExt;
+use g3_providers::{
+    CompletionRequest, LLMProvider, Message, MessageRole, OllamaProvider, OllamaServer, Tool,
+};
+use serde_json::json;
+
+fn request(stream: bool, tools: Option<Vec<Tool>>) -> CompletionRequest {
+    CompletionRequest {
+        messages: vec![
+            Message::new(MessageRole::System, "You are G3".to_string()),
+            Message::new(MessageRole::User, "Read the README".to_string()),
+        ],
+        max_tokens: Some(512),
+        temperature: None,
+        stream,
+        tools,
+        disable_thinking: false,
+    }
+}
+
+fn read_file_tool() -> Tool {
+    Tool {
+        name: "read_file".to_string(),
+        description: "Read a file".to_string(),
+        input_schema: json!({
+            "type": "object",
+            "properties": { "file_path": { "type": "string" } },
+            "required": ["file_path"]
+        }),
+    }
+}
+
+#[tokio::test]
+async fn test_list_models() {
+    let tags = json!({
+        "models": [
+            { "name": "qwen2.5-coder:14b", "size": 9000000000u64 },
+            { "name": "llama3.1:8b", "size": 4900000000u64 }
+        ]
+    });
+    let (base_url, _) = mock_server(vec![Route::json("/api/tags", tags)]).await;
+
+    let provider =
+        OllamaProvider::new("qwen2.5-coder:14b".to_string(), Some(base_url), None, None).unwrap();
+    assert_eq!(
+        provider.list_models().await.unwrap(),
+        vec!["qwen2.5-coder:14b", "llama3.1:8b"]
+    );
+}
+
+#[tokio::test]
+async fn test_autodetect_from_api_show() {
+    let show = json!({
+        "parameters": "stop \"<|im_end|>\"\nnum_ctx 16384",
+        "model_info": {
+            "general.architecture": "qwen2",
+            "qwen2.context_length": 32768
+        },
+        "capabilities": ["completion", "tools"]
+    });
+    let (base_url, requests) = mock_server(vec![Route::json("/api/show", show)]).await;
+
+    let mut provider =
+        OllamaProvider::new("qwen2.5-coder:14b".to_string(), Some(base_url), None, None).unwrap();
+    assert!(!provider.has_native_tool_calling());
+
+    provider.autodetect().await.unwrap();
+    // The Modelfile's num_ctx wins over the trained context length
+    assert_eq!(provider.context_length(), Some(16384));
+    assert!(provider.has_native_tool_calling());
+    assert_eq!(
+        requests.lock().unwrap()[0].body,
+        json!({ "model": "qwen2.5-coder:14b" })
+    );
+}
+
+#[tokio::test]
+async fn test_configured_values_skip_autodetect() {
+    let (base_url, requests) = mock_server(vec![]).await;
+
+    let mut provider = OllamaProvider::new("llama3.1:8b".to_string(), Some(base_url), None, None)
+        .unwrap()
+        .with_context_length(8192)
+        .with_native_tool_calling(false);
+
+    provider.autodetect().await.unwrap();
+    assert_eq!(provider.context_length(), Some(8192));
+    assert!(!provider.has_native_tool_calling());
+    assert!(requests.lock().unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn test_stream_with_tool_call() {
+    let ndjson = [
+        json!({ "message": { "role": "assistant", "content": "Let me " }, "done": false }),
+        json!({ "message": { "role": "assistant", "content": "look." }, "done": false }),
+        json!({
+            "message": {
+                "role": "assistant",
+                "content": "",
+                "tool_calls": [
+                    { "function": { "name": "read_file", "arguments": { "file_path": "README.md" } } }
+                ]
+            },
+            "done": false
+        }),
+        json!({
+            "message": { "role": "assistant", "content": "" },
+            "done": true,
+            "prompt_eval_count": 120,
+            "eval_count": 30
+        }),
+    ]
+    .iter()
+    .map(|line| format!("{}\n", line))
+    .collect::<String>();
+    let (base_url, requests) =
+        mock_server(vec![Route::raw("/api/chat", "application/x-ndjson", ndjson)]).await;
+
+    let provider =
+        OllamaProvider::new("qwen2.5-coder:14b".to_string(), Some(base_url), None, Some(0.2))
+            .unwrap()
+            .with_context_length(16384);
+
+    let mut stream = provider
+        .stream(request(true, Some(vec![read_file_tool()])))
+        .await
+        .unwrap();
+
+    let mut content = String::new();
+    let mut final_chunk = None;
+    while let Some(chunk) = stream.next().await {
+        let chunk = chunk.unwrap();
+        content.push_str(&chunk.content);
+        if chunk.finished {
+            final_chunk = Some(chunk);
+        }
+    }
+
+    assert_eq!(content, "Let me look.");
+    let final_chunk = final_chunk.expect("no final chunk");
+    let tool_calls = final_chunk.tool_calls.unwrap();
+    assert_eq!(tool_calls.len(), 1);
+    assert_eq!(tool_calls[0].tool, "read_file");
+    assert_eq!(tool_calls[0].args, json!({ "file_path": "README.md" }));
+    assert_eq!(final_chunk.usage.unwrap().total_tokens, 150);
+
+    let requests = requests.lock().unwrap();
+    let body = &requests[0].body;
+    assert_eq!(body["stream"], json!(true));
+    assert_eq!(body["options"]["num_ctx"], json!(16384));
+    assert_eq!(body["options"]["num_predict"], json!(512));
+    assert_eq!(body["tools"][0]["function"]["name"], json!("read_file"));
+    assert_eq!(body["messages"][0]["role"], json!("system"));
+}
+
+#[tokio::test]
+async fn test_complete() {
+    let response = json!({
+        "message": { "role": "assistant", "content": "It is a coding agent." },
+        "done": true,
+        "prompt_eval_count": 40,
+        "eval_count": 6
+    });
+    let (base_url, _) = mock_server(vec![Route::json("/api/chat", response)]).await;
+
+    let provider =
+        OllamaProvider::new("llama3.1:8b".to_string(), Some(base_url), None, None).unwrap();
+    let response = provider.complete(request(false, None)).await.unwrap();
+
+    assert_eq!(response.content, "It is a coding agent.");
+    assert_eq!(response.usage.prompt_tokens, 40);
+    assert_eq!(response.usage.completion_tokens, 6);
+    assert!(response.tool_calls.is_none());
+}
+
+#[tokio::test]
+async fn test_complete_with_tool_calls() {
+    let response = json!({
+        "message": {
+            "role": "assistant",
+            "content": "",
+            "tool_calls": [
+                { "function": { "name": "read_file", "arguments": { "file_path": "README.md" } } }
+            ]
+        },
+        "done": true
+    });
+    let (base_url, _) = mock_server(vec![Route::json("/api/chat", response)]).await;
+
+    let provider =
+        OllamaProvider::new("llama3.1:8b".to_string(), Some(base_url), None, None).unwrap();
+    let response = provider
+        .complete(request(false, Some(vec![read_file_tool()])))
+        .await
+        .unwrap();
+
+    let tool_calls = response.tool_calls.unwrap();
+    assert_eq!(tool_calls.len(), 1);
+    assert_eq!(tool_calls[0].id, "call_0");
+    assert_eq!(tool_calls[0].tool, "read_file");
+    assert_eq!(tool_calls[0].args, json!({ "file_path": "README.md" }));
+}
+
+#[tokio::test]
+async fn test_llama_server() {
+    let props = json!({
+        "default_generation_settings": { "n_ctx": 65536 },
+        "chat_template_caps": { "supports_tools": true }
+    });
+    let models = json!({ "object": "list", "data": [{ "id": "qwen3-coder-30b.gguf" }] });
+    let completion = json!({
+        "choices": [{ "message": { "role": "assistant", "content": "Hello" } }],
+        "usage": { "prompt_tokens": 10, "completion_tokens": 1, "total_tokens": 11 }
+    });
+    let (base_url, requests) = mock_server(vec![
+        Route::json("/props", props),
+        Route::json("/v1/models", models),
+        Route::json("/v1/chat/completions", completion),
+    ])
+    .await;
+
+    let mut provider =
+        OllamaProvider::new("qwen3-coder-30b.gguf".to_string(), Some(base_url), None, None)
+            .unwrap()
+            .with_server(OllamaServer::LlamaServer);
+
+    assert_eq!(
+        provider.list_models().await.unwrap(),
+        vec!["qwen3-coder-30b.gguf"]
+    );
+    provider.autodetect().await.unwrap();
+    assert_eq!(provider.context_length(), Some(65536));
+    assert!(provider.has_native_tool_calling());
+
+    let response = provider.complete(request(false, None)).await.unwrap();
+    assert_eq!(response.content, "Hello");
+    assert_eq!(requests.lock().unwrap()[2].path, "/v1/chat/completions");
+}
+
+#[tokio::test]
+async fn test_server_errors_are_reported() {
+    let (base_url, _) = mock_server(vec![]).await;
+
+    let mut provider =
+        OllamaProvider::new("missing:latest".to_string(), Some(base_url), None, None).unwrap();
+    let err = provider.autodetect().await.unwrap_err();
+    assert!(err.to_string().contains("404"), "unexpected error: {}", err);
+}
@@ -0,0 +1,185 @@
+//! REST and WebSocket routes
+//!
+//! - `GET /api/sessions` and `POST /api/sessions` list and create sessions
+//! - `GET /api/sessions/:id` and `DELETE /api/sessions/:id`
+//! - `POST /api/sessions/:id/prompt` starts a prompt (`{"text": "..."}`)
+//! - `POST /api/sessions/:id/cancel` cancels it
+//! - `GET /api/sessions/:id/events` upgrades to a WebSocket that carries the
+//!   session's events, one `g3_core::machine_events::MachineEventLine` per
+//!   text message
+//!
+//! Every route requires the bearer token of the [`AccessPolicy`]. CORS is
+//! only enabled for the policy's allowed origins.
+
+use crate::auth::{require_access, AccessPolicy};
+use crate::session::{is_valid_workspace_name, SessionInfo, SessionManager};
+use axum::extract::ws::{Message, WebSocket, WebSocketUpgrade};
+use axum::extract::{Path, State};
+use axum::http::header::{AUTHORIZATION, CONTENT_TYPE};
+use axum::http::{HeaderValue, Method, StatusCode};
+use axum::middleware;
+use axum::response::Response;
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use serde::Deserialize;
+use std::sync::Arc;
+use tokio::sync::broadcast;
+use tower_http::cors::CorsLayer;
+use tracing::{error, warn};
+
+pub type ManagerState = Arc<SessionManager>;
+
+pub fn router(manager: ManagerState, policy: AccessPolicy) -> Router {
+    let origins: Vec<HeaderValue> = policy
+        .allowed_origins
+        .iter()
+        .filter_map(|origin| match HeaderValue::from_str(origin) {
+            Ok(value) => Some(value),
+            Err(_) => {
+                warn!("Ignoring invalid origin '{}'", origin);
+                None
+            }
+        })
+        .collect();
+
+    let router = Router::new()
+        .route("/api/sessions", get(list_sessions).post(create_session))
+        .route("/api/sessions/:id", get(get_session).delete(delete_session))
+        .route("/api/sessions/:id/prompt", post(prompt_session))
+        .route("/api/sessions/:id/cancel", post(cancel_session))
+        .route("/api/sessions/:id/events", get(session_events))
+        .with_state(manager)
+        .layer(middleware::from_fn_with_state(
+            Arc::new(policy),
+            require_access,
+        ));
+
+    if origins.is_empty() {
+        return router;
+    }
+    // Outermost, so preflight requests are answered without a token
+    router.layer(
+        CorsLayer::new()
+            .allow_origin(origins)
+            .allow_methods([Method::GET, Method::POST, Method::DELETE])
+            .allow_headers([AUTHORIZATION, CONTENT_TYPE]),
+    )
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct CreateSessionRequest {
+    /// Directory under the workspace root; defaults to a new one per session
+    #[serde(default)]
+    pub workspace: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PromptRequest {
+    pub text: String,
+}
+
+pub async fn list_sessions(State(manager): State<ManagerState>) -> Json<Vec<SessionInfo>> {
+    Json(
+        manager
+            .list()
+            .iter()
+            .map(|session| session.info())
+            .collect(),
+    )
+}
+
+pub async fn create_session(
+    State(manager): State<ManagerState>,
+    request: Option<Json<CreateSessionRequest>>,
+) -> Result<(StatusCode, Json<SessionInfo>), StatusCode> {
+    let request = request.map(|Json(request)| request).unwrap_or_default();
+    if let Some(workspace) = &request.workspace {
+        if !is_valid_workspace_name(workspace) {
+            return Err(StatusCode::BAD_REQUEST);
+        }
+    }
+
+    match manager.create(request.workspace.as_deref()).await {
+        Ok(session) => Ok((StatusCode::CREATED, Json(session.info()))),
+        Err(e) => {
+            error!("Failed to create session: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+pub async fn get_session(
+    State(manager): State<ManagerState>,
+    Path(id): Path<String>,
+) -> Result<Json<serde_json::Value>, StatusCode> {
+    let session = manager.get(&id).ok_or(StatusCode::NOT_FOUND)?;
+    let mut body = serde_json::to_value(session.info()).unwrap_or_default();
+    body["stats"] = serde_json::json!(session.stats());
+    Ok(Json(body))
+}
+
+pub async fn delete_session(
+    State(manager): State<ManagerState>,
+    Path(id): Path<String>,
+) -> StatusCode {
+    match manager.remove(&id) {
+        Some(_) => StatusCode::NO_CONTENT,
+        None => StatusCode::NOT_FOUND,
+    }
+}
+
+pub async fn prompt_session(
+    State(manager): State<ManagerState>,
+    Path(id): Path<String>,
+    Json(request): Json<PromptRequest>,
+) -> Result<(StatusCode, Json<serde_json::Value>), StatusCode> {
+    let session = manager.get(&id).ok_or(StatusCode::NOT_FOUND)?;
+    session
+        .prompt(request.text)
+        .map_err(|_| StatusCode::CONFLICT)?;
+    Ok((
+        StatusCode::ACCEPTED,
+        Json(serde_json::json!({"status": "started"})),
+    ))
+}
+
+pub async fn cancel_session(
+    State(manager): State<ManagerState>,
+    Path(id): Path<String>,
+) -> Result<Json<serde_json::Value>, StatusCode> {
+    let session = manager.get(&id).ok_or(StatusCode::NOT_FOUND)?;
+    Ok(Json(serde_json::json!({"cancelled": session.cancel()})))
+}
+
+pub async fn session_events(
+    State(manager): State<ManagerState>,
+    Path(id): Path<String>,
+    ws: WebSocketUpgrade,
+) -> Result<Response, StatusCode> {
+    let session = manager.get(&id).ok_or(StatusCode::NOT_FOUND)?;
+    let events = session.subscribe();
+    Ok(ws.on_upgrade(move |socket| forward_events(socket, events)))
+}
+
+/// Send events until the client disconnects or the session is gone
+async fn forward_events(mut socket: WebSocket, mut events: broadcast::Receiver<String>) {
+    loop {
+        tokio::select! {
+            event = events.recv() => match event {
+                Ok(line) => {
+                    if socket.send(Message::Text(line)).await.is_err() {
+                        break;
+                    }
+                }
+                Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                    warn!("WebSocket subscriber fell behind, skipped {} events", skipped);
+                }
+                Err(broadcast::error::RecvError::Closed) => break,
+            },
+            incoming = socket.recv() => match incoming {
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                Some(Ok(_)) => {}
+            },
+        }
+    }
+}
@@ -0,0 +1,84 @@
+//! Access control for the API
+//!
+//! Sessions run shell and file-write tools, so every request must carry the
+//! server's bearer token. Requests from a browser page are refused unless the
+//! page's origin is allowed, which also covers WebSocket upgrades: browsers
+//! always send `Origin` on those and cannot be stopped by CORS.
+
+use axum::extract::{Request, State};
+use axum::http::header::{AUTHORIZATION, ORIGIN};
+use axum::http::{HeaderMap, StatusCode};
+use axum::middleware::Next;
+use axum::response::Response;
+use std::sync::Arc;
+use tracing::warn;
+
+#[derive(Debug, Clone)]
+pub struct AccessPolicy {
+    /// Expected in `Authorization: Bearer <token>`
+    pub token: String,
+    /// Browser origins allowed to call the API, e.g. `http://localhost:3000`
+    pub allowed_origins: Vec<String>,
+}
+
+impl AccessPolicy {
+    pub fn new(token: impl Into<String>) -> Self {
+        Self {
+            token: token.into(),
+            allowed_origins: Vec::new(),
+        }
+    }
+
+    pub fn with_allowed_origins(mut self, origins: Vec<String>) -> Self {
+        self.allowed_origins = origins;
+        self
+    }
+
+    /// A request without `Origin` does not come from a web page
+    fn origin_allowed(&self, headers: &HeaderMap) -> bool {
+        match headers.get(ORIGIN) {
+            None => true,
+            Some(origin) => self
+                .allowed_origins
+                .iter()
+                .any(|allowed| origin.as_bytes() == allowed.as_bytes()),
+        }
+    }
+
+    fn token_matches(&self, headers: &HeaderMap) -> bool {
+        let Some(token) = headers
+            .get(AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "))
+        else {
+            return false;
+        };
+        // Compare every byte so the time taken does not reveal the prefix
+        token.len() == self.token.len()
+            && token
+                .bytes()
+                .zip(self.token.bytes())
+                .fold(0u8, |diff, (a, b)| diff | (a ^ b))
+                == 0
+    }
+}
+
+/// Middleware rejecting requests from disallowed origins (403) and requests
+/// without the bearer token (401)
+pub async fn require_access(
+    State(policy): State<Arc<AccessPolicy>>,
+    request: Request,
+    next: Next,
+) -> Result<Response, StatusCode> {
+    if !policy.origin_allowed(request.headers()) {
+        warn!(
+            "Rejected request from origin {:?}",
+            request.headers().get(ORIGIN)
+        );
+        return Err(StatusCode::FORBIDDEN);
+    }
+    if !policy.token_matches(request.headers()) {
+        return Err(StatusCode::UNAUTHORIZED);
+    }
+    Ok(next.run(request).await)
+}
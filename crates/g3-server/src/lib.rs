@@ -0,0 +1,13 @@
+//! HTTP/WebSocket server hosting many g3 agent sessions in one process
+//!
+//! `g3-server` exposes REST endpoints to create, prompt, cancel and delete
+//! sessions, and streams each session's machine-mode events over a
+//! WebSocket. See `api::router` for the routes. Clients authenticate with
+//! a bearer token, see `auth::AccessPolicy`.
+
+pub mod api;
+pub mod auth;
+pub mod session;
+
+pub use auth::AccessPolicy;
+pub use session::{Session, SessionBusy, SessionInfo, SessionManager};
@@ -0,0 +1,73 @@
+use clap::Parser;
+use g3_config::Config;
+use g3_server::{api, AccessPolicy, SessionManager};
+use std::path::PathBuf;
+use std::sync::Arc;
+use tracing::{info, Level};
+
+#[derive(Parser, Debug)]
+#[command(name = "g3-server")]
+#[command(about = "HTTP/WebSocket server hosting many g3 agent sessions")]
+struct Args {
+    /// Port to bind to
+    #[arg(long, default_value = "9191")]
+    port: u16,
+
+    /// Host to bind to
+    #[arg(long, default_value = "127.0.0.1")]
+    host: String,
+
+    /// Configuration file path
+    #[arg(short, long)]
+    config: Option<String>,
+
+    /// Directory holding the session workspaces
+    #[arg(long, default_value = "workspaces")]
+    workspace_root: PathBuf,
+
+    /// Bearer token clients must send; defaults to $G3_SERVER_TOKEN, or a
+    /// random token printed at startup
+    #[arg(long)]
+    token: Option<String>,
+
+    /// Browser origin allowed to call the API (repeatable). Requests from
+    /// any other web page are refused.
+    #[arg(long = "allow-origin")]
+    allowed_origins: Vec<String>,
+}
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    // Initialize tracing
+    tracing_subscriber::fmt().with_max_level(Level::INFO).init();
+
+    let args = Args::parse();
+
+    let config = Config::load(args.config.as_deref())?;
+    std::fs::create_dir_all(&args.workspace_root)?;
+    let workspace_root = args.workspace_root.canonicalize()?;
+    let manager = Arc::new(SessionManager::new(config, workspace_root));
+
+    let token = match args
+        .token
+        .or_else(|| std::env::var("G3_SERVER_TOKEN").ok())
+        .filter(|token| !token.is_empty())
+    {
+        Some(token) => token,
+        None => {
+            let token = uuid::Uuid::new_v4().simple().to_string();
+            info!("Generated API token: {}", token);
+            token
+        }
+    };
+    let policy = AccessPolicy::new(token).with_allowed_origins(args.allowed_origins);
+    let app = api::router(manager, policy);
+
+    let addr = format!("{}:{}", args.host, args.port);
+    info!("Starting g3-server on http://{}", addr);
+
+    let listener = tokio::net::TcpListener::bind(&addr).await?;
+    axum::serve(listener, app).await?;
+
+    Ok(())
+}
@@ -0,0 +1,269 @@
+//! Agent sessions hosted by the server
+//!
+//! Each session owns one `Agent` and a workspace directory under the
+//! server's workspace root. Agents share the process, so every call into a
+//! session's agent runs inside `Project::scope` to resolve tool paths and
+//! shell commands against that session's workspace.
+
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Utc};
+use g3_config::Config;
+use g3_core::machine_events::{MachineEvent, MachineEventLine, MachineEventSink, PROTOCOL_VERSION};
+use g3_core::machine_ui_writer::MachineUiWriter;
+use g3_core::project::Project;
+use g3_core::Agent;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fmt;
+use std::path::{Component, Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+use tokio::sync::broadcast;
+use tokio_util::sync::CancellationToken;
+use tracing::info;
+
+/// Events buffered per WebSocket subscriber before it starts missing some
+const EVENT_BUFFER: usize = 1024;
+
+/// Forwards a session's events, as JSON lines, to its WebSocket subscribers
+pub struct SessionEvents {
+    sender: broadcast::Sender<String>,
+    seq: AtomicU64,
+}
+
+impl SessionEvents {
+    fn new() -> Self {
+        let (sender, _) = broadcast::channel(EVENT_BUFFER);
+        Self {
+            sender,
+            seq: AtomicU64::new(0),
+        }
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<String> {
+        self.sender.subscribe()
+    }
+}
+
+impl MachineEventSink for SessionEvents {
+    fn emit(&self, event: MachineEvent) {
+        let line = MachineEventLine {
+            v: PROTOCOL_VERSION,
+            seq: self.seq.fetch_add(1, Ordering::SeqCst),
+            ts: Utc::now(),
+            event,
+        };
+        if let Ok(json) = serde_json::to_string(&line) {
+            // No subscribers is fine; events are not replayed
+            let _ = self.sender.send(json);
+        }
+    }
+}
+
+/// A prompt was sent while the session was still working on another one
+#[derive(Debug, Clone, PartialEq)]
+pub struct SessionBusy;
+
+impl fmt::Display for SessionBusy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Session is busy with another prompt")
+    }
+}
+
+impl std::error::Error for SessionBusy {}
+
+/// Summary of a session for the REST API
+#[derive(Debug, Clone, Serialize)]
+pub struct SessionInfo {
+    pub id: String,
+    pub workspace: PathBuf,
+    pub created_at: DateTime<Utc>,
+    pub busy: bool,
+    pub provider: Option<String>,
+    pub model: Option<String>,
+}
+
+pub struct Session {
+    pub id: String,
+    pub project: Project,
+    pub created_at: DateTime<Utc>,
+    agent: Arc<tokio::sync::Mutex<Agent<MachineUiWriter>>>,
+    events: Arc<SessionEvents>,
+    /// Cancels the running prompt, if any
+    cancel: Mutex<Option<CancellationToken>>,
+}
+
+impl Session {
+    /// Provider and model are omitted while a prompt holds the agent
+    pub fn info(&self) -> SessionInfo {
+        let agent = self.agent.try_lock().ok();
+        let (provider, model) = agent
+            .as_ref()
+            .and_then(|agent| agent.get_provider_info().ok())
+            .unzip();
+        SessionInfo {
+            id: self.id.clone(),
+            workspace: self.project.workspace().to_path_buf(),
+            created_at: self.created_at,
+            busy: agent.is_none(),
+            provider,
+            model,
+        }
+    }
+
+    /// The agent's `/stats` report, unless a prompt is running
+    pub fn stats(&self) -> Option<String> {
+        self.agent.try_lock().ok().map(|agent| agent.get_stats())
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<String> {
+        self.events.subscribe()
+    }
+
+    /// Start a prompt in the background. Its progress and result arrive as
+    /// events, ending with `task_end`, `cancelled` or `error`.
+    pub fn prompt(self: &Arc<Self>, text: String) -> std::result::Result<(), SessionBusy> {
+        let mut agent = self
+            .agent
+            .clone()
+            .try_lock_owned()
+            .map_err(|_| SessionBusy)?;
+        let cancellation_token = CancellationToken::new();
+        *self.cancel.lock().unwrap() = Some(cancellation_token.clone());
+
+        let session = self.clone();
+        tokio::spawn(async move {
+            session
+                .events
+                .emit(MachineEvent::TaskStart { task: text.clone() });
+            let result = session
+                .project
+                .scope(agent.execute_task_with_timing_cancellable(
+                    &text,
+                    None,
+                    false,
+                    false,
+                    false,
+                    true,
+                    cancellation_token.clone(),
+                    None,
+                ))
+                .await;
+
+            session.events.emit(match result {
+                Ok(result) => MachineEvent::TaskEnd {
+                    response: result.response,
+                },
+                Err(_) if cancellation_token.is_cancelled() => MachineEvent::Cancelled,
+                Err(e) => MachineEvent::Error {
+                    message: e.to_string(),
+                },
+            });
+            // Clear the token before releasing the agent so it cannot cancel
+            // the next prompt
+            *session.cancel.lock().unwrap() = None;
+            drop(agent);
+        });
+        Ok(())
+    }
+
+    /// Cancel the running prompt. Returns false if the session was idle.
+    pub fn cancel(&self) -> bool {
+        match self.cancel.lock().unwrap().as_ref() {
+            Some(token) => {
+                token.cancel();
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+/// True if `name` is a relative path that stays inside the workspace root
+pub fn is_valid_workspace_name(name: &str) -> bool {
+    !name.is_empty()
+        && Path::new(name)
+            .components()
+            .all(|component| matches!(component, Component::Normal(_)))
+}
+
+/// All sessions of one server
+pub struct SessionManager {
+    config: Config,
+    workspace_root: PathBuf,
+    sessions: RwLock<HashMap<String, Arc<Session>>>,
+}
+
+impl SessionManager {
+    pub fn new(config: Config, workspace_root: PathBuf) -> Self {
+        Self {
+            config,
+            workspace_root,
+            sessions: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Create a session working in `workspace`, a directory name under the
+    /// workspace root. Sessions without a name get a fresh directory named
+    /// after their id. Several sessions may share a workspace.
+    pub async fn create(&self, workspace: Option<&str>) -> Result<Arc<Session>> {
+        let id = uuid::Uuid::new_v4().simple().to_string();
+        let name = workspace.unwrap_or(&id);
+        if !is_valid_workspace_name(name) {
+            return Err(anyhow!(
+                "Invalid workspace '{}': expected a relative path inside the workspace root",
+                name
+            ));
+        }
+
+        let project = Project::new(self.workspace_root.join(name));
+        project.ensure_workspace_exists()?;
+
+        let events = Arc::new(SessionEvents::new());
+        let ui_writer = MachineUiWriter::new(events.clone());
+        let agent = project
+            .scope(Agent::new_with_readme_and_quiet(
+                self.config.clone(),
+                ui_writer,
+                None,
+                false,
+            ))
+            .await?;
+
+        let session = Arc::new(Session {
+            id: id.clone(),
+            project,
+            created_at: Utc::now(),
+            agent: Arc::new(tokio::sync::Mutex::new(agent)),
+            events,
+            cancel: Mutex::new(None),
+        });
+        info!(
+            "Created session {} in {}",
+            id,
+            session.project.workspace().display()
+        );
+        self.sessions.write().unwrap().insert(id, session.clone());
+        Ok(session)
+    }
+
+    pub fn get(&self, id: &str) -> Option<Arc<Session>> {
+        self.sessions.read().unwrap().get(id).cloned()
+    }
+
+    /// Sessions, oldest first
+    pub fn list(&self) -> Vec<Arc<Session>> {
+        let mut sessions: Vec<_> = self.sessions.read().unwrap().values().cloned().collect();
+        sessions.sort_by_key(|session| session.created_at);
+        sessions
+    }
+
+    /// Remove a session, cancelling its running prompt. The workspace
+    /// directory is left in place.
+    pub fn remove(&self, id: &str) -> Option<Arc<Session>> {
+        let session = self.sessions.write().unwrap().remove(id)?;
+        session.cancel();
+        info!("Removed session {}", id);
+        Some(session)
+    }
+}
@@ -0,0 +1,167 @@
+#[path = "../../g3-providers/tests/common/mod.rs"]
+mod common;
+
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use common::{mock_server_with, Response};
+use g3_config::{Config, OpenAIConfig};
+use g3_core::machine_events::{parse_event_line, MachineEvent};
+use g3_server::{api, AccessPolicy, SessionBusy, SessionManager};
+use serial_test::serial;
+use std::sync::Arc;
+use tempfile::TempDir;
+use tokio::sync::broadcast;
+use tower::ServiceExt;
+
+/// Serve an OpenAI stream that asks for `write_file` on `note.txt`, then
+/// finishes once the request contains the tool result
+async fn mock_openai() -> String {
+    let (base_url, _) = mock_server_with(|request| {
+        if request.body.to_string().contains("Tool result:") {
+            Response::sse(&[
+                r#"{"choices":[{"index":0,"delta":{"content":"Done"}}]}"#,
+                r#"{"choices":[{"index":0,"delta":{},"finish_reason":"stop"}]}"#,
+            ])
+        } else {
+            Response::sse(&[
+                r#"{"choices":[{"index":0,"delta":{"tool_calls":[{"index":0,"id":"call_1","type":"function","function":{"name":"write_file","arguments":"{\"file_path\":\"note.txt\",\"content\":\"hello\"}"}}]}}]}"#,
+                r#"{"choices":[{"index":0,"delta":{},"finish_reason":"tool_calls"}]}"#,
+            ])
+        }
+    })
+    .await;
+    base_url
+}
+
+async fn test_config() -> Config {
+    let mut config = Config::default();
+    config.providers.openai.insert(
+        "default".to_string(),
+        OpenAIConfig {
+            api_key: "test-key".to_string(),
+            model: "test-model".to_string(),
+            base_url: Some(mock_openai().await),
+            max_tokens: Some(1024),
+            temperature: None,
+        },
+    );
+    config.providers.default_provider = "openai.default".to_string();
+    config.agent.max_context_length = Some(128_000);
+    config
+}
+
+/// Collect event types until the prompt finishes
+async fn wait_for_prompt(events: &mut broadcast::Receiver<String>) -> Vec<MachineEvent> {
+    let mut seen = Vec::new();
+    loop {
+        let line = events.recv().await.unwrap();
+        let event = parse_event_line(&line).unwrap().event;
+        let finished = matches!(
+            event,
+            MachineEvent::TaskEnd { .. } | MachineEvent::Error { .. } | MachineEvent::Cancelled
+        );
+        seen.push(event);
+        if finished {
+            return seen;
+        }
+    }
+}
+
+#[tokio::test]
+#[serial]
+async fn test_sessions_run_in_their_own_workspaces() {
+    let root = TempDir::new().unwrap();
+    let cwd = TempDir::new().unwrap();
+    std::env::set_current_dir(cwd.path()).unwrap();
+
+    let manager = SessionManager::new(test_config().await, root.path().to_path_buf());
+    let alpha = manager.create(Some("alpha")).await.unwrap();
+    let other = manager.create(None).await.unwrap();
+    assert_eq!(manager.list().len(), 2);
+
+    let mut alpha_events = alpha.subscribe();
+    let mut other_events = other.subscribe();
+    alpha.prompt("Write a note".to_string()).unwrap();
+    other.prompt("Write a note".to_string()).unwrap();
+    assert_eq!(alpha.prompt("Again".to_string()), Err(SessionBusy));
+
+    for events in [&mut alpha_events, &mut other_events] {
+        let seen = wait_for_prompt(events).await;
+        assert!(
+            seen.iter().any(|event| matches!(
+                event,
+                MachineEvent::ToolEnd { tool, success: true, .. } if tool == "write_file"
+            )),
+            "{:?}",
+            seen
+        );
+        assert!(matches!(seen.last(), Some(MachineEvent::TaskEnd { .. })));
+    }
+
+    assert_eq!(
+        std::fs::read_to_string(root.path().join("alpha/note.txt")).unwrap(),
+        "hello"
+    );
+    assert!(other.project.workspace().join("note.txt").exists());
+    assert!(!cwd.path().join("note.txt").exists());
+    assert!(alpha.stats().is_some());
+
+    assert!(!alpha.cancel());
+    assert!(manager.remove(&alpha.id).is_some());
+    assert!(manager.get(&alpha.id).is_none());
+    assert_eq!(manager.list().len(), 1);
+}
+
+#[tokio::test]
+#[serial]
+async fn test_workspace_names_stay_inside_the_root() {
+    let root = TempDir::new().unwrap();
+    let manager = SessionManager::new(test_config().await, root.path().to_path_buf());
+
+    for name in ["../escape", "/etc", "a/../../b", ""] {
+        assert!(manager.create(Some(name)).await.is_err(), "{}", name);
+    }
+    assert!(manager.list().is_empty());
+}
+
+#[tokio::test]
+#[serial]
+async fn test_api_requires_token_and_allowed_origin() {
+    let root = TempDir::new().unwrap();
+    let manager = Arc::new(SessionManager::new(
+        test_config().await,
+        root.path().to_path_buf(),
+    ));
+    let policy =
+        AccessPolicy::new("secret").with_allowed_origins(vec!["http://localhost:3000".to_string()]);
+    let app = api::router(manager, policy);
+
+    let status = |token: Option<&str>, origin: Option<&str>| {
+        let app = app.clone();
+        let mut request = Request::get("/api/sessions");
+        if let Some(token) = token {
+            request = request.header("Authorization", format!("Bearer {}", token));
+        }
+        if let Some(origin) = origin {
+            request = request.header("Origin", origin);
+        }
+        async move {
+            app.oneshot(request.body(Body::empty()).unwrap())
+                .await
+                .unwrap()
+                .status()
+        }
+    };
+
+    assert_eq!(status(None, None).await, StatusCode::UNAUTHORIZED);
+    assert_eq!(status(Some("wrong"), None).await, StatusCode::UNAUTHORIZED);
+    assert_eq!(status(Some("secret"), None).await, StatusCode::OK);
+    assert_eq!(
+        status(Some("secret"), Some("http://localhost:3000")).await,
+        StatusCode::OK
+    );
+    assert_eq!(
+        status(Some("secret"), Some("https://evil.example")).await,
+        StatusCode::FORBIDDEN
+    );
+}